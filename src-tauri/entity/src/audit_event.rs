@@ -0,0 +1,29 @@
+// src-tauri/src/entity/audit_event.rs
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Values stored in `action`
+pub const ACTION_CREATE: &str = "Create";
+pub const ACTION_UPDATE: &str = "Update";
+pub const ACTION_DELETE: &str = "Delete";
+pub const ACTION_RESTORE: &str = "Restore";
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "audit_event")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub audit_event_id: String,
+    pub actor_id: Option<String>, // Staff or customer id, None when unknown or the system
+    pub action: String, // "Create", "Update" or "Delete"
+    pub entity_type: String, // Table name, e.g. "ride"
+    pub entity_id: String,
+    pub before: Option<Json>, // Row before the change, None for creates
+    pub after: Option<Json>, // Row after the change, None for deletes
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
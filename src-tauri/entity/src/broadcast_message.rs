@@ -2,20 +2,34 @@
 
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc, NaiveDateTime}; // Import DateTime and Utc
+use chrono::NaiveDateTime;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "broadcast_message")]
 pub struct Model {
-    #[sea_orm(primary_key)]
+    #[sea_orm(primary_key, auto_increment = false)]
     pub broadcast_message_id: String,
     pub target_audience: String,
     pub content: String,
     pub timestamp: NaiveDateTime, // Use NaiveDateTime as it matches naive_local()
-    pub status: String,
+    pub status: String, // "Pending", "Sent" or "Expired"
+    pub send_at: Option<NaiveDateTime>, // Scheduled send time; Pending without one is a draft
+    pub sent_at: Option<NaiveDateTime>, // Real send time, stamped by the dispatcher
+    pub expires_at: Option<NaiveDateTime>,
+    pub target_type: String, // "Audience", "Role", "Division", "RideQueue", "RestaurantOrdersToday" or "Venue"
+    pub target_value: Option<String>, // Role, division, ride_id, restaurant_id or ui_id; None for "Audience"
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {} // No relations defined in this simple model
+pub enum Relation {
+    #[sea_orm(has_many = "super::broadcast_recipient::Entity")]
+    BroadcastRecipient,
+}
+
+impl Related<super::broadcast_recipient::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BroadcastRecipient.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
\ No newline at end of file
@@ -0,0 +1,34 @@
+// src-tauri/src/entity/broadcast_recipient.rs
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "broadcast_recipient")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub broadcast_recipient_id: String,
+    pub broadcast_message_id: String,
+    pub recipient_type: String, // "Customer" or "Staff"
+    pub recipient_id: String,
+    pub delivered_at: Option<DateTime>, // First time the recipient's client fetched it
+    pub read_at: Option<DateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::broadcast_message::Entity",
+        from = "Column::BroadcastMessageId",
+        to = "super::broadcast_message::Column::BroadcastMessageId"
+    )]
+    BroadcastMessage,
+}
+
+impl Related<super::broadcast_message::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BroadcastMessage.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
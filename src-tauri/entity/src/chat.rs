@@ -3,15 +3,24 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
+// Values stored in `chat_type`
+pub const CHAT_TYPE_CUSTOMER_SERVICE: &str = "CustomerService";
+pub const CHAT_TYPE_DIVISION_GROUP: &str = "DivisionGroup";
+pub const CHAT_TYPE_DIRECT: &str = "Direct";
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "chat")]
 pub struct Model {
-    #[sea_orm(primary_key)]
+    #[sea_orm(primary_key, auto_increment = false)]
     pub chat_id: String,
     pub name: String,
     pub last_message_text: Option<String>,
     pub last_message_timestamp: Option<DateTime>,
     pub created_at: DateTime,
+    pub chat_type: String, // "CustomerService", "DivisionGroup" or "Direct"
+    pub division: Option<String>, // Staff division, only for division group chats
+    #[sea_orm(unique)]
+    pub direct_key: Option<String>, // Sorted participant pair, only for direct chats
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -3,14 +3,21 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
+// Values stored in `participant_type`
+pub const PARTICIPANT_CUSTOMER: &str = "Customer";
+pub const PARTICIPANT_STAFF: &str = "Staff";
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "chat_member")]
 pub struct Model {
-    #[sea_orm(primary_key)]
+    #[sea_orm(primary_key, auto_increment = false)]
     pub chat_member_id: String,
     pub chat_id: String,
     pub user_id: String, // user_id remains as String
     pub joined_at: DateTime,
+    pub participant_type: String, // "Customer" or "Staff"
+    pub customer_id: Option<String>, // Set when participant_type is "Customer"
+    pub staff_id: Option<String>, // Set when participant_type is "Staff"
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -21,6 +28,18 @@ pub enum Relation {
         to = "super::chat::Column::ChatId"
     )]
     Chat,
+    #[sea_orm(
+        belongs_to = "super::customer::Entity",
+        from = "Column::CustomerId",
+        to = "super::customer::Column::CustomerId"
+    )]
+    Customer,
+    #[sea_orm(
+        belongs_to = "super::staff::Entity",
+        from = "Column::StaffId",
+        to = "super::staff::Column::StaffId"
+    )]
+    Staff,
 }
 
 impl Related<super::chat::Entity> for Entity {
@@ -29,4 +48,16 @@ impl Related<super::chat::Entity> for Entity {
     }
 }
 
+impl Related<super::customer::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Customer.def()
+    }
+}
+
+impl Related<super::staff::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Staff.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
\ No newline at end of file
@@ -4,10 +4,13 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "customer")]
 pub struct Model {
-    #[sea_orm(primary_key)]
+    #[sea_orm(primary_key, auto_increment = false)]
     pub customer_id: String,  // Assuming customerID is a string
     pub name: String,         // Customer name
     pub virtual_balance: String, // Virtual balance as a string (you can later adjust this to a more suitable type)
+    pub deleted_at: Option<DateTime>, // Set when archived (soft deleted)
+    #[serde(skip_serializing, default)] // A short PIN hash is cheap to brute force, so it never leaves the backend
+    pub pin_hash: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,61 @@
+// src-tauri/src/entity/customer_service_ticket.rs
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "customer_service_ticket")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub ticket_id: String,
+    pub chat_id: String,
+    pub customer_id: String,
+    pub assigned_staff_id: Option<String>,
+    pub status: String, // "Open", "Pending" or "Resolved"
+    pub opened_at: DateTime,
+    pub waiting_since: Option<DateTime>, // When the customer started waiting for a staff reply
+    pub first_response_at: Option<DateTime>,
+    pub resolved_at: Option<DateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::chat::Entity",
+        from = "Column::ChatId",
+        to = "super::chat::Column::ChatId"
+    )]
+    Chat,
+    #[sea_orm(
+        belongs_to = "super::customer::Entity",
+        from = "Column::CustomerId",
+        to = "super::customer::Column::CustomerId"
+    )]
+    Customer,
+    #[sea_orm(
+        belongs_to = "super::staff::Entity",
+        from = "Column::AssignedStaffId",
+        to = "super::staff::Column::StaffId"
+    )]
+    Staff,
+}
+
+impl Related<super::chat::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Chat.def()
+    }
+}
+
+impl Related<super::customer::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Customer.def()
+    }
+}
+
+impl Related<super::staff::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Staff.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
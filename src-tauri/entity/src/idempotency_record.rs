@@ -0,0 +1,27 @@
+// src-tauri/src/entity/idempotency_record.rs
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Values stored in `status`
+pub const STATUS_PENDING: &str = "Pending"; // Claimed by a request that has not finished yet
+pub const STATUS_COMPLETED: &str = "Completed";
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "idempotency_record")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub idempotency_key: String, // Generated by the client, one per intended action
+    pub command: String, // e.g. "top_up_virtual_balance"
+    pub request: Json, // Arguments the key was first used with
+    pub status: String, // "Pending" or "Completed"
+    pub response: Option<Json>, // Data of the original successful answer
+    pub message: Option<String>, // Message of the original successful answer
+    pub created_at: DateTime,
+    pub completed_at: Option<DateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
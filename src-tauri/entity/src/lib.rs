@@ -15,6 +15,17 @@ pub mod message;
 pub mod chat_member;
 pub mod maintenance_schedule;
 pub mod broadcast_message;
+pub mod customer_service_ticket;
+pub mod message_edit;
+pub mod message_attachment;
+pub mod broadcast_recipient;
+pub mod lost_and_found_log_event;
+pub mod audit_event;
+pub mod offline_sync_conflict;
+pub mod offline_write;
+pub mod offline_snapshot;
+pub mod idempotency_record;
+pub mod user_session;
 
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "lost_and_found_items_log")]
 pub struct Model {
-    #[sea_orm(primary_key)]
+    #[sea_orm(primary_key, auto_increment = false)]
     pub log_id: String,
     pub image: Option<String>,
     pub name: String,
@@ -18,9 +18,29 @@ pub struct Model {
     pub found_location: Option<String>,
     pub timestamp: NaiveDateTime,
     pub status: String,
+    pub matched_log_id: Option<String>, // Counterpart record once a Missing/Found match is confirmed
+    pub owner_customer_id: Option<String>, // Customer account the item was returned to, if any
+    pub owner_verification_method: Option<String>, // "CustomerAccount", "IdDocument" or "ItemDescription"
+    pub owner_verification_reference: Option<String>, // e.g. last digits of the ID document shown
+    pub returned_by: Option<String>, // Staff member who handed the item back
+    pub returned_at: Option<NaiveDateTime>,
+    pub found_at: Option<NaiveDateTime>, // Start of the retention period for Found items
+    pub retention_flagged_at: Option<NaiveDateTime>, // Unclaimed past the retention period
+    pub disposed_at: Option<NaiveDateTime>, // Donated or disposed of
+    pub reporter_customer_id: Option<String>, // Customer who filed the Missing report themselves
+    pub deleted_at: Option<NaiveDateTime>, // Archived; kept with its history instead of being removed
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::lost_and_found_log_event::Entity")]
+    LostAndFoundLogEvent,
+}
+
+impl Related<super::lost_and_found_log_event::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LostAndFoundLogEvent.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
\ No newline at end of file
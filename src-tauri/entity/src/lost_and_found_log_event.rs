@@ -0,0 +1,36 @@
+// src-tauri/src/entity/lost_and_found_log_event.rs
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "lost_and_found_log_event")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub event_id: String,
+    pub log_id: String,
+    pub action: String, // "Created", "Updated", "StatusChanged", "Matched", "Returned", "RetentionFlagged", "Disposed"
+    pub from_status: Option<String>,
+    pub to_status: Option<String>,
+    pub actor_id: Option<String>, // Staff or customer id, None for the system
+    pub details: Option<String>,
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::lost_and_found_items_log::Entity",
+        from = "Column::LogId",
+        to = "super::lost_and_found_items_log::Column::LogId"
+    )]
+    LostAndFoundItemsLog,
+}
+
+impl Related<super::lost_and_found_items_log::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LostAndFoundItemsLog.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
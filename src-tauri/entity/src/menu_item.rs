@@ -4,12 +4,13 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "menu_item")]
 pub struct Model {
-    #[sea_orm(primary_key)]
+    #[sea_orm(primary_key, auto_increment = false)]
     pub menu_item_id: String,
     pub photo: Option<String>, // Option because it can be NULL in DB
     pub name: String,
     pub price: String, // Price as String
     pub restaurant_id: String, // Foreign Key
+    pub deleted_at: Option<DateTime>, // Set when archived (soft deleted)
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -6,12 +6,14 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "message")]
 pub struct Model {
-    #[sea_orm(primary_key)]
+    #[sea_orm(primary_key, auto_increment = false)]
     pub message_id: String,
     pub chat_id: String,
     pub sender_id: String,
     pub text: String,
     pub timestamp: DateTime,
+    pub edited_at: Option<DateTime>,
+    pub deleted_at: Option<DateTime>, // Soft deleted messages are hidden from the chat
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -39,6 +41,10 @@ pub enum Relation {
         on_update = "NoAction"
     )]
     Staff,
+    #[sea_orm(has_many = "super::message_edit::Entity")]
+    MessageEdit,
+    #[sea_orm(has_many = "super::message_attachment::Entity")]
+    MessageAttachment,
 }
 
 impl Related<super::chat::Entity> for Entity {
@@ -59,4 +65,16 @@ impl Related<super::staff::Entity> for Entity {
     }
 }
 
+impl Related<super::message_edit::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MessageEdit.def()
+    }
+}
+
+impl Related<super::message_attachment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MessageAttachment.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
\ No newline at end of file
@@ -0,0 +1,33 @@
+// src-tauri/src/entity/message_edit.rs
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "message_edit")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub message_edit_id: String,
+    pub message_id: String,
+    pub previous_text: String, // Text the message had before this edit
+    pub edited_by: String,
+    pub edited_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::message::Entity",
+        from = "Column::MessageId",
+        to = "super::message::Column::MessageId"
+    )]
+    Message,
+}
+
+impl Related<super::message::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Message.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
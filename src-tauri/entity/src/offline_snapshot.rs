@@ -0,0 +1,21 @@
+// src-tauri/src/entity/offline_snapshot.rs
+
+// Kiosk-local: the last successful answer to a venue read, served while the central database
+// is unreachable
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "offline_snapshot")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub snapshot_key: String, // Command and arguments, e.g. "view_souvenirs:<store_id>"
+    pub payload: Json,
+    pub fetched_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,57 @@
+// src-tauri/src/entity/offline_sync_conflict.rs
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Values stored in `status`
+pub const STATUS_OPEN: &str = "Open";
+pub const STATUS_APPLIED: &str = "Applied"; // Retried by staff and went through
+pub const STATUS_DISMISSED: &str = "Dismissed";
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "offline_sync_conflict")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub conflict_id: String,
+    pub idempotency_key: String, // Key the kiosk queued the write under
+    pub venue: Option<String>, // ui_id of the kiosk, e.g. "SR-001"
+    pub kind: String, // "OrderRestaurant", "OrderSouvenir" or "RideQueueJoin"
+    pub payload: Json, // The queued write, as replayed
+    pub customer_id: Option<String>,
+    pub reason: String,
+    pub status: String, // "Open", "Applied" or "Dismissed"
+    pub queued_at: DateTime,
+    pub reported_at: DateTime,
+    pub resolved_by: Option<String>,
+    pub resolved_at: Option<DateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::customer::Entity",
+        from = "Column::CustomerId",
+        to = "super::customer::Column::CustomerId"
+    )]
+    Customer,
+    #[sea_orm(
+        belongs_to = "super::staff::Entity",
+        from = "Column::ResolvedBy",
+        to = "super::staff::Column::StaffId"
+    )]
+    Staff,
+}
+
+impl Related<super::customer::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Customer.def()
+    }
+}
+
+impl Related<super::staff::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Staff.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
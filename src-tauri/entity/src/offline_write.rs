@@ -0,0 +1,30 @@
+// src-tauri/src/entity/offline_write.rs
+
+// Kiosk-local: lives in the offline store's SQLite file, not in the central database
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Values stored in `status`
+pub const STATUS_PENDING: &str = "Pending";
+pub const STATUS_SYNCED: &str = "Synced";
+pub const STATUS_CONFLICT: &str = "Conflict"; // Reported to staff, never replayed again
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "offline_write")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub idempotency_key: String,
+    pub kind: String, // "OrderRestaurant", "OrderSouvenir" or "RideQueueJoin"
+    pub payload: Json,
+    pub queued_at: DateTime,
+    pub status: String, // "Pending", "Synced" or "Conflict"
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub synced_at: Option<DateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
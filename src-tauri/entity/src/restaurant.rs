@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "restaurant")]
 pub struct Model {
-    #[sea_orm(primary_key)]
+    #[sea_orm(primary_key, auto_increment = false)]
     pub restaurant_id: String,
     pub name: String,
     pub photo: Option<String>, // Option because it can be NULL in DB
@@ -13,6 +13,7 @@ pub struct Model {
     pub cuisine_type: String,
     pub location: Option<String>, // Option because it can be NULL in DB
     pub status: String,
+    pub deleted_at: Option<DateTime>, // Set when archived (soft deleted)
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "ride")]
 pub struct Model {
-    #[sea_orm(primary_key)]
+    #[sea_orm(primary_key, auto_increment = false)]
     pub ride_id: String,
     pub status: String,
     pub name: String,
@@ -12,6 +12,7 @@ pub struct Model {
     pub location: String,
     pub staff_id: String,
     pub photo: Option<String>,
+    pub deleted_at: Option<DateTime>, // Set when archived (soft deleted)
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -4,12 +4,19 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "staff")]
 pub struct Model {
-    #[sea_orm(primary_key)]
+    #[sea_orm(primary_key, auto_increment = false)]
     pub staff_id: String,
     pub email: String,
+    #[serde(skip_serializing, default)] // Hashes never leave the backend
     pub password_hash: String,
     pub name: String,
     pub role: String,
+    pub deleted_at: Option<DateTime>, // Set when archived (soft deleted)
+    pub must_change_password: bool, // Forces a new password at next login
+    pub password_changed_at: Option<DateTime>,
+    #[serde(skip_serializing, default)]
+    pub reset_token_hash: Option<String>,
+    pub reset_token_expires_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "store")]
 pub struct Model {
-    #[sea_orm(primary_key)]
+    #[sea_orm(primary_key, auto_increment = false)]
     pub store_id: String,
     pub name: String,
     pub photo: Option<String>,
@@ -14,6 +14,7 @@ pub struct Model {
     pub closing_time: Time,
     pub location: Option<String>,
     pub status: String,
+    pub deleted_at: Option<DateTime>, // Set when archived (soft deleted)
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,24 @@
+// src-tauri/src/entity/user_session.rs
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Values stored in `actor_kind`
+pub const KIND_CUSTOMER: &str = "Customer";
+pub const KIND_STAFF: &str = "Staff";
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "user_session")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub token_hash: String, // SHA-256 of the token handed out at login, hex encoded
+    pub actor_id: String, // Customer or staff id
+    pub actor_kind: String, // "Customer" or "Staff"
+    pub created_at: DateTime,
+    pub expires_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
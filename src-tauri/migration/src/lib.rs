@@ -16,6 +16,24 @@ mod m20250320_151226_create_message_table;
 mod m20250320_151241_create_chat_member_table;
 mod m20250326_065633_create_maintenance_schedule_table;
 mod m20250406_094226_create_broadcast_message_table;
+mod m20261018_081512_create_customer_service_ticket_table;
+mod m20261018_093047_add_chat_type_and_participant_kind;
+mod m20261018_101204_add_chat_direct_key;
+mod m20261018_104418_add_message_edits_and_attachments;
+mod m20261018_111530_add_message_text_search_index;
+mod m20261018_114602_add_broadcast_scheduling;
+mod m20261018_121907_add_broadcast_targeting;
+mod m20261018_124530_add_lost_and_found_matching;
+mod m20261018_131204_add_lost_and_found_lifecycle;
+mod m20261018_134417_add_lost_and_found_reporter;
+mod m20261018_141052_create_audit_event_table;
+mod m20261018_143215_add_soft_delete_columns;
+mod m20261018_144508_add_staff_password_management;
+mod m20261018_150122_add_customer_pin;
+mod m20261018_153540_create_offline_sync_conflict_table;
+mod m20261018_160215_create_idempotency_record_table;
+mod m20261018_163108_archive_lost_and_found_logs;
+mod m20261018_165020_create_user_session_table;
 
 
 
@@ -44,6 +62,24 @@ impl MigratorTrait for Migrator {
             Box::new(m20250320_151241_create_chat_member_table::Migration),
             Box::new(m20250326_065633_create_maintenance_schedule_table::Migration),
             Box::new(m20250406_094226_create_broadcast_message_table::Migration),
+            Box::new(m20261018_081512_create_customer_service_ticket_table::Migration),
+            Box::new(m20261018_093047_add_chat_type_and_participant_kind::Migration),
+            Box::new(m20261018_101204_add_chat_direct_key::Migration),
+            Box::new(m20261018_104418_add_message_edits_and_attachments::Migration),
+            Box::new(m20261018_111530_add_message_text_search_index::Migration),
+            Box::new(m20261018_114602_add_broadcast_scheduling::Migration),
+            Box::new(m20261018_121907_add_broadcast_targeting::Migration),
+            Box::new(m20261018_124530_add_lost_and_found_matching::Migration),
+            Box::new(m20261018_131204_add_lost_and_found_lifecycle::Migration),
+            Box::new(m20261018_134417_add_lost_and_found_reporter::Migration),
+            Box::new(m20261018_141052_create_audit_event_table::Migration),
+            Box::new(m20261018_143215_add_soft_delete_columns::Migration),
+            Box::new(m20261018_144508_add_staff_password_management::Migration),
+            Box::new(m20261018_150122_add_customer_pin::Migration),
+            Box::new(m20261018_153540_create_offline_sync_conflict_table::Migration),
+            Box::new(m20261018_160215_create_idempotency_record_table::Migration),
+            Box::new(m20261018_163108_archive_lost_and_found_logs::Migration),
+            Box::new(m20261018_165020_create_user_session_table::Migration),
         ]
     }
 }
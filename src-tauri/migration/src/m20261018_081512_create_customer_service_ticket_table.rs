@@ -0,0 +1,89 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{
+    m20250304_152552_create_customer_table::Customer,
+    m20250306_032524_create_staff_table::Staff,
+    m20250320_151149_create_chat_table::Chat,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CustomerServiceTicket::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CustomerServiceTicket::TicketId)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(CustomerServiceTicket::ChatId).string().not_null())
+                    .col(ColumnDef::new(CustomerServiceTicket::CustomerId).string().not_null())
+                    .col(ColumnDef::new(CustomerServiceTicket::AssignedStaffId).string())
+                    .col(ColumnDef::new(CustomerServiceTicket::Status).string().not_null()) // "Open", "Pending" or "Resolved"
+                    .col(ColumnDef::new(CustomerServiceTicket::OpenedAt).timestamp().not_null())
+                    .col(ColumnDef::new(CustomerServiceTicket::WaitingSince).timestamp()) // Set while the customer waits for a staff reply
+                    .col(ColumnDef::new(CustomerServiceTicket::FirstResponseAt).timestamp())
+                    .col(ColumnDef::new(CustomerServiceTicket::ResolvedAt).timestamp())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-customer_service_ticket-chat_id")
+                            .from(CustomerServiceTicket::Table, CustomerServiceTicket::ChatId)
+                            .to(Chat::Table, Chat::ChatId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-customer_service_ticket-customer_id")
+                            .from(CustomerServiceTicket::Table, CustomerServiceTicket::CustomerId)
+                            .to(Customer::Table, Customer::CustomerId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-customer_service_ticket-assigned_staff_id")
+                            .from(CustomerServiceTicket::Table, CustomerServiceTicket::AssignedStaffId)
+                            .to(Staff::Table, Staff::StaffId)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-customer_service_ticket-chat_id")
+                    .table(CustomerServiceTicket::Table)
+                    .col(CustomerServiceTicket::ChatId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CustomerServiceTicket::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum CustomerServiceTicket {
+    Table,
+    TicketId,
+    ChatId,
+    CustomerId,
+    AssignedStaffId,
+    Status,
+    OpenedAt,
+    WaitingSince,
+    FirstResponseAt,
+    ResolvedAt,
+}
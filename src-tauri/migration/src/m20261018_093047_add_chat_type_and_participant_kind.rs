@@ -0,0 +1,188 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
+
+use crate::{
+    m20250304_152552_create_customer_table::Customer,
+    m20250306_032524_create_staff_table::Staff,
+    m20250320_151149_create_chat_table::Chat,
+    m20250320_151241_create_chat_member_table::ChatMember,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // chat.chat_type: "CustomerService", "DivisionGroup" or "Direct"
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Chat::Table)
+                    .add_column(
+                        ColumnDef::new(ChatType::ChatType)
+                            .string()
+                            .not_null()
+                            .default("DivisionGroup"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Chat::Table)
+                    .add_column(ColumnDef::new(ChatType::Division).string()) // Only set for division group chats
+                    .to_owned(),
+            )
+            .await?;
+
+        // Customer Service chats used to be recognised by name only
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Chat::Table)
+                    .value(ChatType::ChatType, "CustomerService")
+                    .and_where(Expr::col(Chat::Name).eq("Customer Service"))
+                    .to_owned(),
+            )
+            .await?;
+
+        // chat_member.participant_type: "Customer" or "Staff", with a real foreign key for each kind
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ChatMember::Table)
+                    .add_column(
+                        ColumnDef::new(ParticipantKind::ParticipantType)
+                            .string()
+                            .not_null()
+                            .default("Staff"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ChatMember::Table)
+                    .add_column(ColumnDef::new(ParticipantKind::CustomerId).string())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ChatMember::Table)
+                    .add_column(ColumnDef::new(ParticipantKind::StaffId).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Backfill the kind from whichever table the untyped user_id belongs to
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(ChatMember::Table)
+                    .value(ParticipantKind::ParticipantType, "Customer")
+                    .value(ParticipantKind::CustomerId, Expr::col(ChatMember::UserId))
+                    .and_where(
+                        Expr::col(ChatMember::UserId).in_subquery(
+                            Query::select()
+                                .column(Customer::CustomerId)
+                                .from(Customer::Table)
+                                .to_owned(),
+                        ),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(ChatMember::Table)
+                    .value(ParticipantKind::StaffId, Expr::col(ChatMember::UserId))
+                    .and_where(Expr::col(ParticipantKind::ParticipantType).eq("Staff"))
+                    .and_where(
+                        Expr::col(ChatMember::UserId).in_subquery(
+                            Query::select()
+                                .column(Staff::StaffId)
+                                .from(Staff::Table)
+                                .to_owned(),
+                        ),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // SQLite cannot add a foreign key to an existing table
+        if manager.get_database_backend() != DbBackend::Postgres {
+            return Ok(());
+        }
+        manager
+            .create_foreign_key(
+                ForeignKey::create()
+                    .name("fk-chat_member-customer_id")
+                    .from(ChatMember::Table, ParticipantKind::CustomerId)
+                    .to(Customer::Table, Customer::CustomerId)
+                    .on_delete(ForeignKeyAction::Cascade)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_foreign_key(
+                ForeignKey::create()
+                    .name("fk-chat_member-staff_id")
+                    .from(ChatMember::Table, ParticipantKind::StaffId)
+                    .to(Staff::Table, Staff::StaffId)
+                    .on_delete(ForeignKeyAction::Cascade)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DbBackend::Postgres {
+            manager
+                .drop_foreign_key(
+                    ForeignKey::drop()
+                        .name("fk-chat_member-staff_id")
+                        .table(ChatMember::Table)
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .drop_foreign_key(
+                    ForeignKey::drop()
+                        .name("fk-chat_member-customer_id")
+                        .table(ChatMember::Table)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        for column in [ParticipantKind::ParticipantType, ParticipantKind::CustomerId, ParticipantKind::StaffId] {
+            manager
+                .alter_table(Table::alter().table(ChatMember::Table).drop_column(column).to_owned())
+                .await?;
+        }
+        for column in [ChatType::ChatType, ChatType::Division] {
+            manager
+                .alter_table(Table::alter().table(Chat::Table).drop_column(column).to_owned())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+pub enum ChatType {
+    ChatType,
+    Division,
+}
+
+#[derive(Iden)]
+pub enum ParticipantKind {
+    ParticipantType,
+    CustomerId,
+    StaffId,
+}
@@ -0,0 +1,52 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20250320_151149_create_chat_table::Chat;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Sorted "staff_a:staff_b" pair, only set for direct chats
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Chat::Table)
+                    .add_column(ColumnDef::new(DirectKey::DirectKey).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        // One direct chat per participant pair (NULLs do not collide)
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-chat-direct_key")
+                    .table(Chat::Table)
+                    .col(DirectKey::DirectKey)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("idx-chat-direct_key").table(Chat::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Chat::Table)
+                    .drop_column(DirectKey::DirectKey)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum DirectKey {
+    DirectKey,
+}
@@ -0,0 +1,144 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20250320_151226_create_message_table::Message;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Message::Table)
+                    .add_column(ColumnDef::new(MessageState::EditedAt).timestamp())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Message::Table)
+                    .add_column(ColumnDef::new(MessageState::DeletedAt).timestamp()) // Soft delete marker
+                    .to_owned(),
+            )
+            .await?;
+
+        // Previous versions of a message, one row per edit
+        manager
+            .create_table(
+                Table::create()
+                    .table(MessageEdit::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MessageEdit::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MessageEdit::MessageId).string().not_null())
+                    .col(ColumnDef::new(MessageEdit::PreviousText).text().not_null())
+                    .col(ColumnDef::new(MessageEdit::EditedBy).string().not_null())
+                    .col(ColumnDef::new(MessageEdit::EditedAt).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-message_edit-message_id")
+                            .from(MessageEdit::Table, MessageEdit::MessageId)
+                            .to(Message::Table, Message::MessageId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(MessageAttachment::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MessageAttachment::AttachmentId)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MessageAttachment::MessageId).string().not_null())
+                    .col(ColumnDef::new(MessageAttachment::FileName).string().not_null())
+                    .col(ColumnDef::new(MessageAttachment::ContentType).string().not_null())
+                    .col(ColumnDef::new(MessageAttachment::Url).text()) // Already hosted image, e.g. a Cloudinary upload
+                    .col(ColumnDef::new(MessageAttachment::StorageKey).string()) // Key in the attachment storage backend
+                    .col(ColumnDef::new(MessageAttachment::SizeBytes).big_integer())
+                    .col(
+                        ColumnDef::new(MessageAttachment::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-message_attachment-message_id")
+                            .from(MessageAttachment::Table, MessageAttachment::MessageId)
+                            .to(Message::Table, Message::MessageId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-message_attachment-message_id")
+                    .table(MessageAttachment::Table)
+                    .col(MessageAttachment::MessageId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MessageAttachment::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(MessageEdit::Table).to_owned())
+            .await?;
+        for column in [MessageState::EditedAt, MessageState::DeletedAt] {
+            manager
+                .alter_table(Table::alter().table(Message::Table).drop_column(column).to_owned())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+pub enum MessageState {
+    EditedAt,
+    DeletedAt,
+}
+
+#[derive(Iden)]
+pub enum MessageEdit {
+    Table,
+    #[iden = "message_edit_id"]
+    Id,
+    MessageId,
+    PreviousText,
+    EditedBy,
+    EditedAt,
+}
+
+#[derive(Iden)]
+pub enum MessageAttachment {
+    Table,
+    AttachmentId,
+    MessageId,
+    FileName,
+    ContentType,
+    Url,
+    StorageKey,
+    SizeBytes,
+    CreatedAt,
+}
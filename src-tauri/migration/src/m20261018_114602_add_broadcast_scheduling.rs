@@ -0,0 +1,76 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20250406_094226_create_broadcast_message_table::BroadcastMessage;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            BroadcastSchedule::Send,   // When the dispatcher should send a Pending broadcast
+            BroadcastSchedule::Sent,   // When it actually went out
+            BroadcastSchedule::Expiry, // After this it is no longer shown (or sent)
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(BroadcastMessage::Table)
+                        .add_column(ColumnDef::new(column).timestamp())
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // Broadcasts sent before scheduling existed only have their creation time
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(BroadcastMessage::Table)
+                    .value(BroadcastSchedule::Sent, Expr::col(BroadcastMessage::Timestamp))
+                    .and_where(Expr::col(BroadcastMessage::Status).eq("Sent"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-broadcast_message-status-send_at")
+                    .table(BroadcastMessage::Table)
+                    .col(BroadcastMessage::Status)
+                    .col(BroadcastSchedule::Send)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-broadcast_message-status-send_at")
+                    .table(BroadcastMessage::Table)
+                    .to_owned(),
+            )
+            .await?;
+        for column in [BroadcastSchedule::Send, BroadcastSchedule::Sent, BroadcastSchedule::Expiry] {
+            manager
+                .alter_table(Table::alter().table(BroadcastMessage::Table).drop_column(column).to_owned())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+// Column names are spelled out so the variants need not all end in "At"
+#[derive(Iden)]
+pub enum BroadcastSchedule {
+    #[iden = "send_at"]
+    Send,
+    #[iden = "sent_at"]
+    Sent,
+    #[iden = "expires_at"]
+    Expiry,
+}
@@ -0,0 +1,113 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20250406_094226_create_broadcast_message_table::BroadcastMessage;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // "Audience", "Role", "Division", "RideQueue", "RestaurantOrdersToday" or "Venue"
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BroadcastMessage::Table)
+                    .add_column(
+                        ColumnDef::new(BroadcastTarget::TargetType)
+                            .string()
+                            .not_null()
+                            .default("Audience"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BroadcastMessage::Table)
+                    .add_column(ColumnDef::new(BroadcastTarget::TargetValue).string()) // Role, division, ride_id, restaurant_id or ui_id
+                    .to_owned(),
+            )
+            .await?;
+
+        // One row per person a broadcast went to, for delivery and read tracking
+        manager
+            .create_table(
+                Table::create()
+                    .table(BroadcastRecipient::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BroadcastRecipient::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(BroadcastRecipient::BroadcastMessageId).string().not_null())
+                    .col(ColumnDef::new(BroadcastRecipient::RecipientType).string().not_null()) // "Customer" or "Staff"
+                    .col(ColumnDef::new(BroadcastRecipient::RecipientId).string().not_null())
+                    .col(ColumnDef::new(BroadcastRecipient::DeliveredAt).timestamp())
+                    .col(ColumnDef::new(BroadcastRecipient::ReadAt).timestamp())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-broadcast_recipient-broadcast_message_id")
+                            .from(BroadcastRecipient::Table, BroadcastRecipient::BroadcastMessageId)
+                            .to(BroadcastMessage::Table, BroadcastMessage::BroadcastMessageId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-broadcast_recipient-message-recipient")
+                    .table(BroadcastRecipient::Table)
+                    .col(BroadcastRecipient::BroadcastMessageId)
+                    .col(BroadcastRecipient::RecipientId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-broadcast_recipient-recipient_id")
+                    .table(BroadcastRecipient::Table)
+                    .col(BroadcastRecipient::RecipientId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BroadcastRecipient::Table).to_owned())
+            .await?;
+        for column in [BroadcastTarget::TargetType, BroadcastTarget::TargetValue] {
+            manager
+                .alter_table(Table::alter().table(BroadcastMessage::Table).drop_column(column).to_owned())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+pub enum BroadcastTarget {
+    TargetType,
+    TargetValue,
+}
+
+#[derive(Iden)]
+pub enum BroadcastRecipient {
+    Table,
+    #[iden = "broadcast_recipient_id"]
+    Id,
+    BroadcastMessageId,
+    RecipientType,
+    RecipientId,
+    DeliveredAt,
+    ReadAt,
+}
@@ -0,0 +1,86 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
+
+use crate::m20250317_105552_create_lost_and_found_items_log_table::LostAndFoundItemsLog;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Links a "Missing" report to the "Found" item confirmed as the same object (set on both rows)
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(LostAndFoundItemsLog::Table)
+                    .add_column(ColumnDef::new(LostAndFoundMatch::MatchedLogId).string())
+                    .to_owned(),
+            )
+            .await?;
+        // SQLite cannot add a foreign key to an existing table
+        if manager.get_database_backend() == DbBackend::Postgres {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(LostAndFoundItemsLog::Table)
+                        .add_foreign_key(
+                            TableForeignKey::new()
+                                .name("fk-lost_and_found_items_log-matched_log_id")
+                                .from_tbl(LostAndFoundItemsLog::Table)
+                                .from_col(LostAndFoundMatch::MatchedLogId)
+                                .to_tbl(LostAndFoundItemsLog::Table)
+                                .to_col(LostAndFoundItemsLog::LogId)
+                                .on_delete(ForeignKeyAction::SetNull),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // Candidate lookups only scan open records of one status
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-lost_and_found_items_log-status")
+                    .table(LostAndFoundItemsLog::Table)
+                    .col(LostAndFoundItemsLog::Status)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-lost_and_found_items_log-status")
+                    .table(LostAndFoundItemsLog::Table)
+                    .to_owned(),
+            )
+            .await?;
+        if manager.get_database_backend() == DbBackend::Postgres {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(LostAndFoundItemsLog::Table)
+                        .drop_foreign_key(Alias::new("fk-lost_and_found_items_log-matched_log_id"))
+                        .to_owned(),
+                )
+                .await?;
+        }
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(LostAndFoundItemsLog::Table)
+                    .drop_column(LostAndFoundMatch::MatchedLogId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum LostAndFoundMatch {
+    MatchedLogId,
+}
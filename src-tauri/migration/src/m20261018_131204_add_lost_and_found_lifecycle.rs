@@ -0,0 +1,184 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
+
+use crate::m20250304_152552_create_customer_table::Customer;
+use crate::m20250306_032524_create_staff_table::Staff;
+use crate::m20250317_105552_create_lost_and_found_items_log_table::LostAndFoundItemsLog;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Who the item went back to and how staff verified it was theirs
+        for column in [
+            ColumnDef::new(LostAndFoundLifecycle::OwnerCustomerId).string().to_owned(),
+            ColumnDef::new(LostAndFoundLifecycle::OwnerVerificationMethod).string().to_owned(),
+            ColumnDef::new(LostAndFoundLifecycle::OwnerVerificationReference).string().to_owned(),
+            ColumnDef::new(LostAndFoundLifecycle::ReturnedBy).string().to_owned(),
+            ColumnDef::new(LostAndFoundLifecycle::ReturnedAt).timestamp().to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(LostAndFoundItemsLog::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        // SQLite cannot add a foreign key to an existing table
+        if manager.get_database_backend() == DbBackend::Postgres {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(LostAndFoundItemsLog::Table)
+                        .add_foreign_key(
+                            TableForeignKey::new()
+                                .name("fk-lost_and_found_items_log-owner_customer_id")
+                                .from_tbl(LostAndFoundItemsLog::Table)
+                                .from_col(LostAndFoundLifecycle::OwnerCustomerId)
+                                .to_tbl(Customer::Table)
+                                .to_col(Customer::CustomerId)
+                                .on_delete(ForeignKeyAction::SetNull),
+                        )
+                        .add_foreign_key(
+                            TableForeignKey::new()
+                                .name("fk-lost_and_found_items_log-returned_by")
+                                .from_tbl(LostAndFoundItemsLog::Table)
+                                .from_col(LostAndFoundLifecycle::ReturnedBy)
+                                .to_tbl(Staff::Table)
+                                .to_col(Staff::StaffId)
+                                .on_delete(ForeignKeyAction::SetNull),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // Retention clock: `timestamp` moves on every edit, so keep when the item was actually found
+        for column in [
+            LostAndFoundLifecycle::FoundAt,
+            LostAndFoundLifecycle::RetentionFlaggedAt, // Unclaimed past the retention period
+            LostAndFoundLifecycle::DisposedAt,         // Donated or disposed of
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(LostAndFoundItemsLog::Table)
+                        .add_column(ColumnDef::new(column).timestamp())
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // Existing Found items start their retention period from their last change
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(LostAndFoundItemsLog::Table)
+                    .value(LostAndFoundLifecycle::FoundAt, Expr::col(LostAndFoundItemsLog::Timestamp))
+                    .and_where(Expr::col(LostAndFoundItemsLog::Status).eq("Found"))
+                    .to_owned(),
+            )
+            .await?;
+
+        // Append-only history of everything that happened to an item
+        manager
+            .create_table(
+                Table::create()
+                    .table(LostAndFoundLogEvent::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LostAndFoundLogEvent::EventId)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LostAndFoundLogEvent::LogId).string().not_null())
+                    .col(ColumnDef::new(LostAndFoundLogEvent::Action).string().not_null())
+                    .col(ColumnDef::new(LostAndFoundLogEvent::FromStatus).string())
+                    .col(ColumnDef::new(LostAndFoundLogEvent::ToStatus).string())
+                    .col(ColumnDef::new(LostAndFoundLogEvent::ActorId).string()) // Staff or customer id, NULL for the system
+                    .col(ColumnDef::new(LostAndFoundLogEvent::Details).text())
+                    .col(ColumnDef::new(LostAndFoundLogEvent::CreatedAt).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-lost_and_found_log_event-log_id")
+                            .from(LostAndFoundLogEvent::Table, LostAndFoundLogEvent::LogId)
+                            .to(LostAndFoundItemsLog::Table, LostAndFoundItemsLog::LogId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-lost_and_found_log_event-log_id")
+                    .table(LostAndFoundLogEvent::Table)
+                    .col(LostAndFoundLogEvent::LogId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LostAndFoundLogEvent::Table).to_owned())
+            .await?;
+        if manager.get_database_backend() == DbBackend::Postgres {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(LostAndFoundItemsLog::Table)
+                        .drop_foreign_key(Alias::new("fk-lost_and_found_items_log-owner_customer_id"))
+                        .drop_foreign_key(Alias::new("fk-lost_and_found_items_log-returned_by"))
+                        .to_owned(),
+                )
+                .await?;
+        }
+        for column in [
+            LostAndFoundLifecycle::OwnerCustomerId,
+            LostAndFoundLifecycle::OwnerVerificationMethod,
+            LostAndFoundLifecycle::OwnerVerificationReference,
+            LostAndFoundLifecycle::ReturnedBy,
+            LostAndFoundLifecycle::ReturnedAt,
+            LostAndFoundLifecycle::FoundAt,
+            LostAndFoundLifecycle::RetentionFlaggedAt,
+            LostAndFoundLifecycle::DisposedAt,
+        ] {
+            manager
+                .alter_table(Table::alter().table(LostAndFoundItemsLog::Table).drop_column(column).to_owned())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+pub enum LostAndFoundLifecycle {
+    OwnerCustomerId,
+    OwnerVerificationMethod,
+    OwnerVerificationReference,
+    ReturnedBy,
+    ReturnedAt,
+    FoundAt,
+    RetentionFlaggedAt,
+    DisposedAt,
+}
+
+#[derive(Iden)]
+pub enum LostAndFoundLogEvent {
+    Table,
+    EventId,
+    LogId,
+    Action,
+    FromStatus,
+    ToStatus,
+    ActorId,
+    Details,
+    CreatedAt,
+}
@@ -0,0 +1,91 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditEvent::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AuditEvent::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AuditEvent::ActorId).string()) // Staff or customer id, NULL when unknown or the system
+                    .col(ColumnDef::new(AuditEvent::Action).string().not_null()) // "Create", "Update" or "Delete"
+                    .col(ColumnDef::new(AuditEvent::EntityType).string().not_null()) // Table name, e.g. "ride"
+                    .col(ColumnDef::new(AuditEvent::EntityId).string().not_null())
+                    .col(ColumnDef::new(AuditEvent::Before).json_binary())
+                    .col(ColumnDef::new(AuditEvent::After).json_binary())
+                    .col(ColumnDef::new(AuditEvent::CreatedAt).timestamp().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        for (name, columns) in [
+            ("idx-audit_event-entity", vec![AuditEvent::EntityType, AuditEvent::EntityId]),
+            ("idx-audit_event-actor_id", vec![AuditEvent::ActorId]),
+            ("idx-audit_event-created_at", vec![AuditEvent::CreatedAt]),
+        ] {
+            let mut index = Index::create();
+            index.name(name).table(AuditEvent::Table);
+            for column in columns {
+                index.col(column);
+            }
+            manager.create_index(index.to_owned()).await?;
+        }
+
+        // Append-only: reject any UPDATE or DELETE of recorded events (Postgres only)
+        if manager.get_database_backend() != DbBackend::Postgres {
+            return Ok(());
+        }
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "CREATE OR REPLACE FUNCTION audit_event_append_only() RETURNS trigger AS $$ \
+             BEGIN RAISE EXCEPTION 'audit_event is append-only'; END; \
+             $$ LANGUAGE plpgsql",
+        )
+        .await?;
+        db.execute_unprepared(
+            "CREATE TRIGGER audit_event_append_only \
+             BEFORE UPDATE OR DELETE ON audit_event \
+             FOR EACH ROW EXECUTE FUNCTION audit_event_append_only()",
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditEvent::Table).to_owned())
+            .await?;
+        if manager.get_database_backend() == DbBackend::Postgres {
+            manager
+                .get_connection()
+                .execute_unprepared("DROP FUNCTION IF EXISTS audit_event_append_only()")
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+pub enum AuditEvent {
+    Table,
+    #[iden = "audit_event_id"]
+    Id,
+    ActorId,
+    Action,
+    EntityType,
+    EntityId,
+    Before,
+    After,
+    CreatedAt,
+}
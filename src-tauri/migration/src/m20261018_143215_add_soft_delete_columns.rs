@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20250304_152552_create_customer_table::Customer;
+use crate::m20250306_032524_create_staff_table::Staff;
+use crate::m20250306_111959_create_restaurant_table::Restaurant;
+use crate::m20250306_112015_create_menu_item_table::MenuItem;
+use crate::m20250307_114130_create_ride_table::Ride;
+use crate::m20250312_143002_create_store_table::Store;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Tables whose rows are archived (deleted_at set) instead of removed
+fn archivable_tables() -> Vec<DynIden> {
+    vec![
+        Customer::Table.into_iden(),
+        Staff::Table.into_iden(),
+        Restaurant::Table.into_iden(),
+        MenuItem::Table.into_iden(),
+        Ride::Table.into_iden(),
+        Store::Table.into_iden(),
+    ]
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in archivable_tables() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .add_column(ColumnDef::new(SoftDelete::DeletedAt).timestamp())
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in archivable_tables() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .drop_column(SoftDelete::DeletedAt)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+pub enum SoftDelete {
+    DeletedAt,
+}
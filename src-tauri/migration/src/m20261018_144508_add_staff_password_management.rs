@@ -0,0 +1,51 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20250306_032524_create_staff_table::Staff;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // One column per statement, as SQLite only supports a single change per ALTER TABLE
+        for column in [
+            ColumnDef::new(StaffPassword::MustChangePassword)
+                .boolean()
+                .not_null()
+                .default(false)
+                .to_owned(),
+            ColumnDef::new(StaffPassword::PasswordChangedAt).timestamp().to_owned(),
+            // Only a bcrypt hash of the one-time reset token is kept
+            ColumnDef::new(StaffPassword::ResetTokenHash).string().to_owned(),
+            ColumnDef::new(StaffPassword::ResetTokenExpiresAt).timestamp().to_owned(),
+        ] {
+            manager
+                .alter_table(Table::alter().table(Staff::Table).add_column(column).to_owned())
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            StaffPassword::MustChangePassword,
+            StaffPassword::PasswordChangedAt,
+            StaffPassword::ResetTokenHash,
+            StaffPassword::ResetTokenExpiresAt,
+        ] {
+            manager
+                .alter_table(Table::alter().table(Staff::Table).drop_column(column).to_owned())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+pub enum StaffPassword {
+    MustChangePassword,
+    PasswordChangedAt,
+    ResetTokenHash,
+    ResetTokenExpiresAt,
+}
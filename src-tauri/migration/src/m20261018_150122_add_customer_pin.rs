@@ -0,0 +1,37 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20250304_152552_create_customer_table::Customer;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // bcrypt hash of the customer's PIN; existing customers get one set by customer service
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Customer::Table)
+                    .add_column(ColumnDef::new(CustomerPin::PinHash).string())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Customer::Table)
+                    .drop_column(CustomerPin::PinHash)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum CustomerPin {
+    PinHash,
+}
@@ -0,0 +1,96 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20250304_152552_create_customer_table::Customer;
+use crate::m20250306_032524_create_staff_table::Staff;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Writes a kiosk queued while offline that could not be applied once it reconnected
+        manager
+            .create_table(
+                Table::create()
+                    .table(OfflineSyncConflict::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OfflineSyncConflict::ConflictId)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(OfflineSyncConflict::IdempotencyKey)
+                            .string()
+                            .not_null()
+                            .unique_key(), // A write is reported once, however often the kiosk retries
+                    )
+                    .col(ColumnDef::new(OfflineSyncConflict::Venue).string()) // ui_id of the kiosk, e.g. "SR-001"
+                    .col(ColumnDef::new(OfflineSyncConflict::Kind).string().not_null())
+                    .col(ColumnDef::new(OfflineSyncConflict::Payload).json_binary().not_null())
+                    .col(ColumnDef::new(OfflineSyncConflict::CustomerId).string())
+                    .col(ColumnDef::new(OfflineSyncConflict::Reason).text().not_null())
+                    .col(
+                        ColumnDef::new(OfflineSyncConflict::Status)
+                            .string()
+                            .not_null()
+                            .default("Open"), // "Open", "Applied" or "Dismissed"
+                    )
+                    .col(ColumnDef::new(OfflineSyncConflict::QueuedAt).timestamp().not_null())
+                    .col(ColumnDef::new(OfflineSyncConflict::ReportedAt).timestamp().not_null())
+                    .col(ColumnDef::new(OfflineSyncConflict::ResolvedBy).string())
+                    .col(ColumnDef::new(OfflineSyncConflict::ResolvedAt).timestamp())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-offline_sync_conflict-customer_id")
+                            .from(OfflineSyncConflict::Table, OfflineSyncConflict::CustomerId)
+                            .to(Customer::Table, Customer::CustomerId)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-offline_sync_conflict-resolved_by")
+                            .from(OfflineSyncConflict::Table, OfflineSyncConflict::ResolvedBy)
+                            .to(Staff::Table, Staff::StaffId)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-offline_sync_conflict-status")
+                    .table(OfflineSyncConflict::Table)
+                    .col(OfflineSyncConflict::Status)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OfflineSyncConflict::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum OfflineSyncConflict {
+    Table,
+    ConflictId,
+    IdempotencyKey,
+    Venue,
+    Kind,
+    Payload,
+    CustomerId,
+    Reason,
+    Status,
+    QueuedAt,
+    ReportedAt,
+    ResolvedBy,
+    ResolvedAt,
+}
@@ -0,0 +1,67 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Outcome of payment-affecting commands per client idempotency key, so a retry or
+        // double click returns the first answer instead of charging twice
+        manager
+            .create_table(
+                Table::create()
+                    .table(IdempotencyRecord::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(IdempotencyRecord::IdempotencyKey)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(IdempotencyRecord::Command).string().not_null())
+                    .col(ColumnDef::new(IdempotencyRecord::Request).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(IdempotencyRecord::Status)
+                            .string()
+                            .not_null()
+                            .default("Pending"), // "Pending" or "Completed"
+                    )
+                    .col(ColumnDef::new(IdempotencyRecord::Response).json_binary())
+                    .col(ColumnDef::new(IdempotencyRecord::Message).string())
+                    .col(ColumnDef::new(IdempotencyRecord::CreatedAt).timestamp().not_null())
+                    .col(ColumnDef::new(IdempotencyRecord::CompletedAt).timestamp())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-idempotency_record-created_at")
+                    .table(IdempotencyRecord::Table)
+                    .col(IdempotencyRecord::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(IdempotencyRecord::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum IdempotencyRecord {
+    Table,
+    IdempotencyKey,
+    Command,
+    Request,
+    Status,
+    Response,
+    Message,
+    CreatedAt,
+    CompletedAt,
+}
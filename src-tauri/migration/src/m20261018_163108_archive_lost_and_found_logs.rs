@@ -0,0 +1,130 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
+
+use crate::m20250317_105552_create_lost_and_found_items_log_table::LostAndFoundItemsLog;
+use crate::m20261018_131204_add_lost_and_found_lifecycle::LostAndFoundLogEvent;
+use crate::m20261018_143215_add_soft_delete_columns::SoftDelete;
+
+const EVENT_LOG_FK: &str = "fk-lost_and_found_log_event-log_id";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Log entries are archived like other records instead of removed
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(LostAndFoundItemsLog::Table)
+                    .add_column(ColumnDef::new(SoftDelete::DeletedAt).timestamp())
+                    .to_owned(),
+            )
+            .await?;
+
+        // A log entry's history must outlive any attempt to remove the entry itself
+        set_event_log_fk(manager, ForeignKeyAction::Restrict).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        set_event_log_fk(manager, ForeignKeyAction::Cascade).await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(LostAndFoundItemsLog::Table)
+                    .drop_column(SoftDelete::DeletedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+fn event_log_fk(on_delete: ForeignKeyAction) -> ForeignKeyCreateStatement {
+    ForeignKey::create()
+        .name(EVENT_LOG_FK)
+        .from(LostAndFoundLogEvent::Table, LostAndFoundLogEvent::LogId)
+        .to(LostAndFoundItemsLog::Table, LostAndFoundItemsLog::LogId)
+        .on_delete(on_delete)
+        .to_owned()
+}
+
+async fn set_event_log_fk(manager: &SchemaManager<'_>, on_delete: ForeignKeyAction) -> Result<(), DbErr> {
+    if manager.get_database_backend() == DbBackend::Postgres {
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name(EVENT_LOG_FK)
+                    .table(LostAndFoundLogEvent::Table)
+                    .to_owned(),
+            )
+            .await?;
+        return manager.create_foreign_key(event_log_fk(on_delete)).await;
+    }
+
+    // SQLite cannot change a foreign key in place: copy the history into a rebuilt table
+    let rebuilt = Alias::new("lost_and_found_log_event_rebuilt");
+    let columns = || {
+        [
+            LostAndFoundLogEvent::EventId,
+            LostAndFoundLogEvent::LogId,
+            LostAndFoundLogEvent::Action,
+            LostAndFoundLogEvent::FromStatus,
+            LostAndFoundLogEvent::ToStatus,
+            LostAndFoundLogEvent::ActorId,
+            LostAndFoundLogEvent::Details,
+            LostAndFoundLogEvent::CreatedAt,
+        ]
+    };
+    manager
+        .create_table(
+            Table::create()
+                .table(rebuilt.clone())
+                .col(
+                    ColumnDef::new(LostAndFoundLogEvent::EventId)
+                        .string()
+                        .not_null()
+                        .primary_key(),
+                )
+                .col(ColumnDef::new(LostAndFoundLogEvent::LogId).string().not_null())
+                .col(ColumnDef::new(LostAndFoundLogEvent::Action).string().not_null())
+                .col(ColumnDef::new(LostAndFoundLogEvent::FromStatus).string())
+                .col(ColumnDef::new(LostAndFoundLogEvent::ToStatus).string())
+                .col(ColumnDef::new(LostAndFoundLogEvent::ActorId).string())
+                .col(ColumnDef::new(LostAndFoundLogEvent::Details).text())
+                .col(ColumnDef::new(LostAndFoundLogEvent::CreatedAt).timestamp().not_null())
+                .foreign_key(&mut event_log_fk(on_delete))
+                .to_owned(),
+        )
+        .await?;
+    manager
+        .exec_stmt(
+            Query::insert()
+                .into_table(rebuilt.clone())
+                .columns(columns())
+                .select_from(
+                    Query::select()
+                        .columns(columns())
+                        .from(LostAndFoundLogEvent::Table)
+                        .to_owned(),
+                )
+                .map_err(|err| DbErr::Migration(err.to_string()))?
+                .to_owned(),
+        )
+        .await?;
+    manager
+        .drop_table(Table::drop().table(LostAndFoundLogEvent::Table).to_owned())
+        .await?;
+    manager
+        .rename_table(Table::rename().table(rebuilt, LostAndFoundLogEvent::Table).to_owned())
+        .await?;
+    manager
+        .create_index(
+            Index::create()
+                .name("idx-lost_and_found_log_event-log_id")
+                .table(LostAndFoundLogEvent::Table)
+                .col(LostAndFoundLogEvent::LogId)
+                .to_owned(),
+        )
+        .await
+}
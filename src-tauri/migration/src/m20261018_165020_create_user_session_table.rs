@@ -0,0 +1,56 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Signed-in customers and staff. Commands name their actor through the session token,
+        // so only a hash of it is kept
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserSession::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserSession::TokenHash)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(UserSession::ActorId).string().not_null())
+                    .col(ColumnDef::new(UserSession::ActorKind).string().not_null()) // "Customer" or "Staff"
+                    .col(ColumnDef::new(UserSession::CreatedAt).timestamp().not_null())
+                    .col(ColumnDef::new(UserSession::ExpiresAt).timestamp().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-user_session-actor_id")
+                    .table(UserSession::Table)
+                    .col(UserSession::ActorId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserSession::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum UserSession {
+    Table,
+    TokenHash,
+    ActorId,
+    ActorKind,
+    CreatedAt,
+    ExpiresAt,
+}
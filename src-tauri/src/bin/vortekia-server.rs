@@ -0,0 +1,9 @@
+// Headless HTTP/JSON server; build with `cargo run --bin vortekia-server --features http-api`
+
+#[tokio::main]
+async fn main() {
+    if let Err(err) = tauri_app_lib::http_api::serve().await {
+        eprintln!("VorteKia HTTP API could not start: {}", err);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,822 @@
+// src-tauri/src/cache.rs
+
+use async_trait::async_trait;
+use deadpool_redis::{redis::cmd, Pool as RedisPool};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Entity tags. A cached view is tagged with every entity it reads, and a write to an
+// entity invalidates its tag, so every view that could have gone stale misses next time.
+pub const TAG_RIDE: &str = "ride";
+pub const TAG_STORE: &str = "store";
+pub const TAG_SOUVENIR: &str = "souvenir";
+pub const TAG_ORDER_SOUVENIR: &str = "order_souvenir";
+pub const TAG_LOST_AND_FOUND: &str = "lost_and_found_items_log";
+pub const TAG_CHAT: &str = "chat";
+pub const TAG_CHAT_MEMBER: &str = "chat_member";
+pub const TAG_CUSTOMER_SERVICE_TICKET: &str = "customer_service_ticket";
+pub const TAG_CUSTOMER: &str = "customer";
+
+const ENTITY_TAGS: [&str; 9] = [
+    TAG_RIDE,
+    TAG_STORE,
+    TAG_SOUVENIR,
+    TAG_ORDER_SOUVENIR,
+    TAG_LOST_AND_FOUND,
+    TAG_CHAT,
+    TAG_CHAT_MEMBER,
+    TAG_CUSTOMER_SERVICE_TICKET,
+    TAG_CUSTOMER,
+];
+const DEFAULT_TTL_SECS: u64 = 60;
+// While Redis is down the fallback only probes it this often, so requests do not each wait on it
+const PRIMARY_RETRY_SECS: u64 = 10;
+
+// Narrow a tag to one record, e.g. one user's chat memberships
+pub fn scoped_tag(tag: &str, id: &str) -> String {
+    format!("{}:{}", tag, id)
+}
+
+// Every cached read, with its key and the tags it depends on
+#[derive(Debug, Clone, PartialEq)]
+pub enum CachedView<'a> {
+    Rides,
+    Stores,
+    Souvenirs(Option<&'a str>), // All souvenirs, or one store's
+    OrderSouvenirs,
+    LostAndFoundLogs,
+    ChatsForUser(&'a str),
+    CustomerServiceChats,
+}
+
+impl CachedView<'_> {
+    pub fn key(&self) -> String {
+        match self {
+            CachedView::Rides => "view_rides_cache".to_string(),
+            CachedView::Stores => "view_stores_cache".to_string(),
+            CachedView::Souvenirs(Some(store_id)) => format!("view_souvenirs_cache_store_{}", store_id),
+            CachedView::Souvenirs(None) => "view_souvenirs_cache_all".to_string(),
+            CachedView::OrderSouvenirs => "view_order_souvenirs_cache".to_string(),
+            CachedView::LostAndFoundLogs => "view_logs_cache".to_string(),
+            CachedView::ChatsForUser(user_id) => format!("view_chats_user_{}", user_id),
+            CachedView::CustomerServiceChats => "view_customer_service_chats_for_staff_cache".to_string(),
+        }
+    }
+
+    pub fn tags(&self) -> Vec<String> {
+        match self {
+            CachedView::Rides => vec![TAG_RIDE.to_string()],
+            CachedView::Stores => vec![TAG_STORE.to_string()],
+            CachedView::Souvenirs(_) => vec![TAG_SOUVENIR.to_string()],
+            CachedView::OrderSouvenirs => vec![TAG_ORDER_SOUVENIR.to_string()],
+            CachedView::LostAndFoundLogs => vec![TAG_LOST_AND_FOUND.to_string()],
+            // Chat rows carry the last message preview, so any chat write affects every list
+            CachedView::ChatsForUser(user_id) => vec![TAG_CHAT.to_string(), scoped_tag(TAG_CHAT_MEMBER, user_id)],
+            CachedView::CustomerServiceChats => vec![
+                TAG_CHAT.to_string(),
+                TAG_CUSTOMER_SERVICE_TICKET.to_string(),
+                TAG_CUSTOMER.to_string(),
+            ],
+        }
+    }
+}
+
+// Raw key/value store underneath the cache. Errors are returned so the cache layer can
+// decide how to degrade; the cache itself never fails a request.
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    async fn get(&self, key: &str) -> Result<Option<String>, String>;
+    async fn get_many(&self, keys: &[String]) -> Result<Vec<Option<String>>, String>;
+    async fn set(&self, key: &str, value: &str, ttl_secs: u64) -> Result<(), String>;
+    async fn incr(&self, key: &str) -> Result<i64, String>;
+    async fn expire(&self, key: &str, ttl_secs: u64) -> Result<(), String>;
+    async fn delete(&self, key: &str) -> Result<(), String>;
+    async fn ping(&self) -> Result<(), String>;
+
+    fn name(&self) -> &'static str;
+
+    // Why the backend is not serving from its intended store, if it is not
+    fn degraded_reason(&self) -> Option<String> {
+        None
+    }
+}
+
+pub struct RedisCacheBackend {
+    pool: RedisPool,
+}
+
+impl RedisCacheBackend {
+    pub fn new(pool: RedisPool) -> Self {
+        RedisCacheBackend { pool }
+    }
+}
+
+#[async_trait]
+impl CacheBackend for RedisCacheBackend {
+    async fn get(&self, key: &str) -> Result<Option<String>, String> {
+        let mut conn = self.pool.get().await.map_err(|err| format!("get connection: {}", err))?;
+        cmd("GET").arg(&[key]).query_async(&mut conn).await.map_err(|err| format!("GET: {}", err))
+    }
+
+    async fn get_many(&self, keys: &[String]) -> Result<Vec<Option<String>>, String> {
+        let mut conn = self.pool.get().await.map_err(|err| format!("get connection: {}", err))?;
+        cmd("MGET").arg(keys).query_async(&mut conn).await.map_err(|err| format!("MGET: {}", err))
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl_secs: u64) -> Result<(), String> {
+        let mut conn = self.pool.get().await.map_err(|err| format!("get connection: {}", err))?;
+        cmd("SETEX")
+            .arg(&[key, &ttl_secs.to_string(), value])
+            .query_async::<()>(&mut conn)
+            .await
+            .map_err(|err| format!("SETEX: {}", err))
+    }
+
+    async fn incr(&self, key: &str) -> Result<i64, String> {
+        let mut conn = self.pool.get().await.map_err(|err| format!("get connection: {}", err))?;
+        cmd("INCR").arg(&[key]).query_async(&mut conn).await.map_err(|err| format!("INCR: {}", err))
+    }
+
+    async fn expire(&self, key: &str, ttl_secs: u64) -> Result<(), String> {
+        let mut conn = self.pool.get().await.map_err(|err| format!("get connection: {}", err))?;
+        cmd("EXPIRE")
+            .arg(&[key, &ttl_secs.to_string()])
+            .query_async::<()>(&mut conn)
+            .await
+            .map_err(|err| format!("EXPIRE: {}", err))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        let mut conn = self.pool.get().await.map_err(|err| format!("get connection: {}", err))?;
+        cmd("DEL").arg(&[key]).query_async::<()>(&mut conn).await.map_err(|err| format!("DEL: {}", err))
+    }
+
+    async fn ping(&self) -> Result<(), String> {
+        let mut conn = self.pool.get().await.map_err(|err| format!("get connection: {}", err))?;
+        cmd("PING").query_async::<()>(&mut conn).await.map_err(|err| format!("PING: {}", err))
+    }
+
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+}
+
+// Process-local backend with the same semantics as Redis
+#[derive(Default)]
+pub struct MemoryCacheBackend {
+    entries: Mutex<HashMap<String, (String, Option<Instant>)>>,
+}
+
+impl MemoryCacheBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+
+    fn live_value(entries: &HashMap<String, (String, Option<Instant>)>, key: &str) -> Option<String> {
+        entries
+            .get(key)
+            .filter(|(_, expires_at)| expires_at.is_none_or(|expires_at| expires_at > Instant::now()))
+            .map(|(value, _)| value.clone())
+    }
+}
+
+#[async_trait]
+impl CacheBackend for MemoryCacheBackend {
+    async fn get(&self, key: &str) -> Result<Option<String>, String> {
+        let entries = self.entries.lock().map_err(|err| err.to_string())?;
+        Ok(Self::live_value(&entries, key))
+    }
+
+    async fn get_many(&self, keys: &[String]) -> Result<Vec<Option<String>>, String> {
+        let entries = self.entries.lock().map_err(|err| err.to_string())?;
+        Ok(keys.iter().map(|key| Self::live_value(&entries, key)).collect())
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl_secs: u64) -> Result<(), String> {
+        let mut entries = self.entries.lock().map_err(|err| err.to_string())?;
+        entries.insert(key.to_string(), (value.to_string(), Some(Instant::now() + Duration::from_secs(ttl_secs))));
+        Ok(())
+    }
+
+    // Like INCR, an existing expiry is kept
+    async fn incr(&self, key: &str) -> Result<i64, String> {
+        let mut entries = self.entries.lock().map_err(|err| err.to_string())?;
+        let (current, expires_at) = match Self::live_value(&entries, key) {
+            Some(value) => (value.parse::<i64>().unwrap_or(0), entries.get(key).and_then(|(_, expires_at)| *expires_at)),
+            None => (0, None),
+        };
+        entries.insert(key.to_string(), ((current + 1).to_string(), expires_at));
+        Ok(current + 1)
+    }
+
+    async fn expire(&self, key: &str, ttl_secs: u64) -> Result<(), String> {
+        let mut entries = self.entries.lock().map_err(|err| err.to_string())?;
+        if let Some(entry) = entries.get_mut(key) {
+            entry.1 = Some(Instant::now() + Duration::from_secs(ttl_secs));
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        let mut entries = self.entries.lock().map_err(|err| err.to_string())?;
+        entries.remove(key);
+        Ok(())
+    }
+
+    async fn ping(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+}
+
+#[derive(Default)]
+struct FallbackState {
+    outage: Option<String>, // Last error from the primary while it is considered down
+    retry_at: Option<Instant>,
+}
+
+// Serves from the primary (Redis) and switches to an in-process cache while it is unreachable.
+// Each side only ever holds entries written while it was active: the in-process cache is
+// emptied when an outage starts, and every tag is invalidated on the primary when it ends,
+// since writes made during the outage only bumped tag versions in memory.
+pub struct FallbackCacheBackend {
+    primary: Arc<dyn CacheBackend>,
+    fallback: MemoryCacheBackend,
+    state: Mutex<FallbackState>,
+}
+
+impl FallbackCacheBackend {
+    pub fn new(primary: Arc<dyn CacheBackend>) -> Self {
+        FallbackCacheBackend { primary, fallback: MemoryCacheBackend::new(), state: Mutex::new(FallbackState::default()) }
+    }
+
+    fn mark_down(&self, err: String) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if state.outage.is_none() {
+            eprintln!("Cache error ({} unavailable, using in-process cache): {}", self.primary.name(), err);
+            self.fallback.clear();
+        }
+        state.outage = Some(err);
+        state.retry_at = Some(Instant::now() + Duration::from_secs(PRIMARY_RETRY_SECS));
+    }
+
+    // The primary if it is usable right now. During an outage it is re-probed once the retry
+    // interval has passed (or straight away when `probe_now`), and recovered if it answers.
+    async fn usable_primary(&self, probe_now: bool) -> Option<&dyn CacheBackend> {
+        let retry_due = match self.state.lock() {
+            Ok(state) => match (&state.outage, state.retry_at) {
+                (None, _) => return Some(self.primary.as_ref()),
+                (Some(_), retry_at) => probe_now || retry_at.is_none_or(|retry_at| Instant::now() >= retry_at),
+            },
+            Err(_) => return Some(self.primary.as_ref()),
+        };
+        if !retry_due {
+            return None;
+        }
+
+        if let Err(err) = self.primary.ping().await {
+            self.mark_down(err);
+            return None;
+        }
+        for tag in ENTITY_TAGS {
+            if let Err(err) = self.primary.incr(&Cache::version_key(tag)).await {
+                self.mark_down(err);
+                return None;
+            }
+        }
+        if let Ok(mut state) = self.state.lock() {
+            if state.outage.take().is_some() {
+                eprintln!("Cache recovered: {} is reachable again", self.primary.name());
+            }
+            state.retry_at = None;
+        }
+        Some(self.primary.as_ref())
+    }
+}
+
+#[async_trait]
+impl CacheBackend for FallbackCacheBackend {
+    async fn get(&self, key: &str) -> Result<Option<String>, String> {
+        if let Some(primary) = self.usable_primary(false).await {
+            match primary.get(key).await {
+                Ok(value) => return Ok(value),
+                Err(err) => self.mark_down(err),
+            }
+        }
+        self.fallback.get(key).await
+    }
+
+    async fn get_many(&self, keys: &[String]) -> Result<Vec<Option<String>>, String> {
+        if let Some(primary) = self.usable_primary(false).await {
+            match primary.get_many(keys).await {
+                Ok(values) => return Ok(values),
+                Err(err) => self.mark_down(err),
+            }
+        }
+        self.fallback.get_many(keys).await
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl_secs: u64) -> Result<(), String> {
+        if let Some(primary) = self.usable_primary(false).await {
+            match primary.set(key, value, ttl_secs).await {
+                Ok(()) => return Ok(()),
+                Err(err) => self.mark_down(err),
+            }
+        }
+        self.fallback.set(key, value, ttl_secs).await
+    }
+
+    async fn incr(&self, key: &str) -> Result<i64, String> {
+        if let Some(primary) = self.usable_primary(false).await {
+            match primary.incr(key).await {
+                Ok(version) => return Ok(version),
+                Err(err) => self.mark_down(err),
+            }
+        }
+        self.fallback.incr(key).await
+    }
+
+    async fn expire(&self, key: &str, ttl_secs: u64) -> Result<(), String> {
+        if let Some(primary) = self.usable_primary(false).await {
+            match primary.expire(key, ttl_secs).await {
+                Ok(()) => return Ok(()),
+                Err(err) => self.mark_down(err),
+            }
+        }
+        self.fallback.expire(key, ttl_secs).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        if let Some(primary) = self.usable_primary(false).await {
+            match primary.delete(key).await {
+                Ok(()) => return Ok(()),
+                Err(err) => self.mark_down(err),
+            }
+        }
+        self.fallback.delete(key).await
+    }
+
+    // Reports on the primary itself; a health check is also a chance to recover it
+    async fn ping(&self) -> Result<(), String> {
+        if let Some(primary) = self.usable_primary(true).await {
+            match primary.ping().await {
+                Ok(()) => return Ok(()),
+                Err(err) => self.mark_down(err),
+            }
+        }
+        Err(self.degraded_reason().unwrap_or_else(|| format!("{} unavailable", self.primary.name())))
+    }
+
+    fn name(&self) -> &'static str {
+        self.primary.name()
+    }
+
+    fn degraded_reason(&self) -> Option<String> {
+        let state = self.state.lock().ok()?;
+        state
+            .outage
+            .as_ref()
+            .map(|err| format!("{} unavailable, serving from the in-process cache: {}", self.primary.name(), err))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedData<T> {
+    data: T,
+}
+
+// Where a lookup's result may be stored. It pins the tag versions seen before the database
+// was read, so a result computed from data that was invalidated mid-flight is written under
+// an outdated key that no later lookup will ask for.
+struct CacheSlot {
+    versioned_key: Option<String>, // None when tag versions could not be read: do not store
+    ttl_secs: u64,
+}
+
+// How long cached views live, per entity. CACHE_TTL_SECS sets the default and
+// CACHE_TTL_<TAG>_SECS overrides it for one entity, e.g. CACHE_TTL_RIDE_SECS=300.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheTtls {
+    default_secs: u64,
+    per_tag: HashMap<String, u64>,
+}
+
+impl Default for CacheTtls {
+    fn default() -> Self {
+        CacheTtls { default_secs: DEFAULT_TTL_SECS, per_tag: HashMap::new() }
+    }
+}
+
+impl CacheTtls {
+    pub fn from_env() -> Self {
+        fn secs(key: &str) -> Option<u64> {
+            env::var(key).ok().and_then(|value| value.parse().ok()).filter(|secs: &u64| *secs > 0)
+        }
+        CacheTtls {
+            default_secs: secs("CACHE_TTL_SECS").unwrap_or(DEFAULT_TTL_SECS),
+            per_tag: ENTITY_TAGS
+                .iter()
+                .filter_map(|tag| secs(&format!("CACHE_TTL_{}_SECS", tag.to_uppercase())).map(|ttl| (tag.to_string(), ttl)))
+                .collect(),
+        }
+    }
+
+    pub fn with_tag(mut self, tag: &str, ttl_secs: u64) -> Self {
+        self.per_tag.insert(tag.to_string(), ttl_secs);
+        self
+    }
+
+    // A view lives only as long as the shortest-lived entity it reads
+    pub fn for_view(&self, view: &CachedView<'_>) -> u64 {
+        view.tags()
+            .iter()
+            .map(|tag| {
+                let entity = tag.split(':').next().unwrap_or(tag);
+                self.per_tag.get(entity).copied().unwrap_or(self.default_secs)
+            })
+            .min()
+            .unwrap_or(self.default_secs)
+    }
+}
+
+// Tag-versioned cache. Invalidating a tag bumps its version, which changes the key of every
+// view carrying that tag; the old entries are never read again and simply expire.
+#[derive(Clone)]
+pub struct Cache {
+    backend: Arc<dyn CacheBackend>,
+    ttls: Arc<CacheTtls>,
+}
+
+impl Cache {
+    pub fn new(backend: Arc<dyn CacheBackend>, ttls: CacheTtls) -> Self {
+        Cache { backend, ttls: Arc::new(ttls) }
+    }
+
+    // Serve the view from the cache, or run `load` and cache what it returns. Errors
+    // from `load` are passed through and never cached.
+    pub async fn read_through<T, E, F, Fut>(&self, view: &CachedView<'_>, load: F) -> Result<T, E>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let (cached, slot) = self.lookup::<T>(view).await;
+        if let Some(cached) = cached {
+            return Ok(cached);
+        }
+        let loaded = load().await?;
+        self.fill(&slot, &loaded).await;
+        Ok(loaded)
+    }
+
+    fn version_key(tag: &str) -> String {
+        format!("cache_tag_version:{}", tag)
+    }
+
+    async fn lookup<T: DeserializeOwned>(&self, view: &CachedView<'_>) -> (Option<T>, CacheSlot) {
+        let key = view.key();
+        let ttl_secs = self.ttls.for_view(view);
+        let version_keys: Vec<String> = view.tags().iter().map(|tag| Self::version_key(tag)).collect();
+        let versions = match self.backend.get_many(&version_keys).await {
+            Ok(versions) => versions,
+            Err(err) => {
+                eprintln!("Cache error (lookup {} - tag versions): {}", key, err);
+                return (None, CacheSlot { versioned_key: None, ttl_secs });
+            }
+        };
+        let versioned_key = format!(
+            "{}@{}",
+            key,
+            versions.iter().map(|version| version.as_deref().unwrap_or("0")).collect::<Vec<_>>().join(".")
+        );
+
+        let cached = match self.backend.get(&versioned_key).await {
+            Ok(Some(json)) => match serde_json::from_str::<CachedData<T>>(&json) {
+                Ok(wrapper) => Some(wrapper.data),
+                Err(err) => {
+                    eprintln!("Cache error (lookup {} - deserialization): {}", key, err);
+                    None
+                }
+            },
+            Ok(None) => None,
+            Err(err) => {
+                eprintln!("Cache error (lookup {}): {}", key, err);
+                None
+            }
+        };
+        (cached, CacheSlot { versioned_key: Some(versioned_key), ttl_secs })
+    }
+
+    async fn fill<T: Serialize>(&self, slot: &CacheSlot, value: &T) {
+        let Some(versioned_key) = &slot.versioned_key else {
+            return;
+        };
+        let json = match serde_json::to_string(&CachedData { data: value }) {
+            Ok(json) => json,
+            Err(err) => {
+                eprintln!("Cache error (fill {} - serialization): {}", versioned_key, err);
+                return;
+            }
+        };
+        if let Err(err) = self.backend.set(versioned_key, &json, slot.ttl_secs).await {
+            eprintln!("Cache error (fill {}): {}", versioned_key, err);
+        }
+    }
+
+    pub async fn ping(&self) -> Result<(), String> {
+        self.backend.ping().await
+    }
+
+    // The raw store, for callers that manage their own keys (e.g. the login guard)
+    pub fn backend(&self) -> &dyn CacheBackend {
+        self.backend.as_ref()
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    pub fn degraded_reason(&self) -> Option<String> {
+        self.backend.degraded_reason()
+    }
+
+    pub async fn invalidate(&self, tags: &[&str]) {
+        for tag in tags {
+            if let Err(err) = self.backend.incr(&Self::version_key(tag)).await {
+                eprintln!("Cache error (invalidate {}): {}", tag, err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache() -> Cache {
+        Cache::new(Arc::new(MemoryCacheBackend::new()), CacheTtls::default())
+    }
+
+    async fn fill_view(cache: &Cache, view: &CachedView<'_>, value: &str) {
+        let (_, slot) = cache.lookup::<String>(view).await;
+        cache.fill(&slot, &value.to_string()).await;
+    }
+
+    async fn read_view(cache: &Cache, view: &CachedView<'_>) -> Option<String> {
+        cache.lookup::<String>(view).await.0
+    }
+
+    fn all_views() -> Vec<CachedView<'static>> {
+        vec![
+            CachedView::Rides,
+            CachedView::Stores,
+            CachedView::Souvenirs(None),
+            CachedView::Souvenirs(Some("store-1")),
+            CachedView::OrderSouvenirs,
+            CachedView::LostAndFoundLogs,
+            CachedView::ChatsForUser("user-1"),
+            CachedView::CustomerServiceChats,
+        ]
+    }
+
+    #[tokio::test]
+    async fn filled_view_is_served_until_invalidated() {
+        let cache = cache();
+        fill_view(&cache, &CachedView::Rides, "rides").await;
+        assert_eq!(read_view(&cache, &CachedView::Rides).await.as_deref(), Some("rides"));
+
+        cache.invalidate(&[TAG_RIDE]).await;
+        assert_eq!(read_view(&cache, &CachedView::Rides).await, None);
+    }
+
+    #[tokio::test]
+    async fn invalidating_any_dependency_makes_every_view_miss() {
+        for view in all_views() {
+            for tag in view.tags() {
+                let cache = cache();
+                fill_view(&cache, &view, "stale").await;
+                cache.invalidate(&[&tag]).await;
+                assert_eq!(read_view(&cache, &view).await, None, "{:?} still served after {} changed", view, tag);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn souvenir_write_invalidates_the_full_list_and_every_store_list() {
+        let cache = cache();
+        let views = [
+            CachedView::Souvenirs(None),
+            CachedView::Souvenirs(Some("store-1")),
+            CachedView::Souvenirs(Some("store-2")),
+        ];
+        for view in &views {
+            fill_view(&cache, view, "stock 5").await;
+        }
+
+        // e.g. update_souvenir_stock, or moving a souvenir between stores
+        cache.invalidate(&[TAG_SOUVENIR]).await;
+        for view in &views {
+            assert_eq!(read_view(&cache, view).await, None, "{:?} kept a stale stock level", view);
+        }
+    }
+
+    #[tokio::test]
+    async fn new_chat_invalidates_every_users_chat_list() {
+        let cache = cache();
+        fill_view(&cache, &CachedView::ChatsForUser("user-1"), "chats").await;
+        fill_view(&cache, &CachedView::ChatsForUser("user-2"), "chats").await;
+
+        cache.invalidate(&[TAG_CHAT]).await;
+        assert_eq!(read_view(&cache, &CachedView::ChatsForUser("user-1")).await, None);
+        assert_eq!(read_view(&cache, &CachedView::ChatsForUser("user-2")).await, None);
+    }
+
+    #[tokio::test]
+    async fn membership_change_only_invalidates_that_users_chat_list() {
+        let cache = cache();
+        fill_view(&cache, &CachedView::ChatsForUser("user-1"), "chats").await;
+        fill_view(&cache, &CachedView::ChatsForUser("user-2"), "chats").await;
+
+        cache.invalidate(&[&scoped_tag(TAG_CHAT_MEMBER, "user-1")]).await;
+        assert_eq!(read_view(&cache, &CachedView::ChatsForUser("user-1")).await, None);
+        assert_eq!(read_view(&cache, &CachedView::ChatsForUser("user-2")).await.as_deref(), Some("chats"));
+    }
+
+    #[tokio::test]
+    async fn unrelated_write_keeps_the_view_cached() {
+        let cache = cache();
+        fill_view(&cache, &CachedView::Stores, "stores").await;
+        cache.invalidate(&[TAG_RIDE, TAG_SOUVENIR]).await;
+        assert_eq!(read_view(&cache, &CachedView::Stores).await.as_deref(), Some("stores"));
+    }
+
+    #[tokio::test]
+    async fn result_read_before_a_write_is_never_served_after_it() {
+        let cache = cache();
+        // A reader misses and queries the database...
+        let (cached, slot) = cache.lookup::<String>(&CachedView::Stores).await;
+        assert_eq!(cached, None);
+        // ...a write lands and invalidates before the reader stores what it read...
+        cache.invalidate(&[TAG_STORE]).await;
+        cache.fill(&slot, &"pre-write stores".to_string()).await;
+        // ...so the pre-write result must not be served
+        assert_eq!(read_view(&cache, &CachedView::Stores).await, None);
+    }
+
+    #[tokio::test]
+    async fn read_through_loads_once_and_never_caches_errors() {
+        let cache = cache();
+        let failed: Result<Vec<String>, String> =
+            cache.read_through(&CachedView::Rides, || async { Err("database down".to_string()) }).await;
+        assert!(failed.is_err());
+
+        let loaded: Result<Vec<String>, String> =
+            cache.read_through(&CachedView::Rides, || async { Ok(vec!["Coaster".to_string()]) }).await;
+        assert_eq!(loaded, Ok(vec!["Coaster".to_string()]));
+
+        let cached: Result<Vec<String>, String> =
+            cache.read_through(&CachedView::Rides, || async { panic!("served from the cache, not loaded") }).await;
+        assert_eq!(cached, Ok(vec!["Coaster".to_string()]));
+    }
+
+    #[test]
+    fn view_ttl_is_the_shortest_of_its_entities() {
+        let ttls = CacheTtls::default().with_tag(TAG_RIDE, 300).with_tag(TAG_CUSTOMER, 10).with_tag(TAG_CHAT_MEMBER, 30);
+        assert_eq!(ttls.for_view(&CachedView::Rides), 300);
+        assert_eq!(ttls.for_view(&CachedView::Stores), DEFAULT_TTL_SECS);
+        assert_eq!(ttls.for_view(&CachedView::CustomerServiceChats), 10);
+        assert_eq!(ttls.for_view(&CachedView::ChatsForUser("user-1")), 30);
+    }
+
+    // Memory backend that can be switched off to simulate a Redis outage
+    #[derive(Default)]
+    struct FlakyBackend {
+        inner: MemoryCacheBackend,
+        down: std::sync::atomic::AtomicBool,
+    }
+
+    impl FlakyBackend {
+        fn set_down(&self, down: bool) {
+            self.down.store(down, std::sync::atomic::Ordering::SeqCst);
+        }
+
+        fn check(&self) -> Result<(), String> {
+            if self.down.load(std::sync::atomic::Ordering::SeqCst) {
+                Err("connection refused".to_string())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[async_trait]
+    impl CacheBackend for FlakyBackend {
+        async fn get(&self, key: &str) -> Result<Option<String>, String> {
+            self.check()?;
+            self.inner.get(key).await
+        }
+
+        async fn get_many(&self, keys: &[String]) -> Result<Vec<Option<String>>, String> {
+            self.check()?;
+            self.inner.get_many(keys).await
+        }
+
+        async fn set(&self, key: &str, value: &str, ttl_secs: u64) -> Result<(), String> {
+            self.check()?;
+            self.inner.set(key, value, ttl_secs).await
+        }
+
+        async fn incr(&self, key: &str) -> Result<i64, String> {
+            self.check()?;
+            self.inner.incr(key).await
+        }
+
+        async fn expire(&self, key: &str, ttl_secs: u64) -> Result<(), String> {
+            self.check()?;
+            self.inner.expire(key, ttl_secs).await
+        }
+
+        async fn delete(&self, key: &str) -> Result<(), String> {
+            self.check()?;
+            self.inner.delete(key).await
+        }
+
+        async fn ping(&self) -> Result<(), String> {
+            self.check()
+        }
+
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+    }
+
+    fn fallback_cache() -> (Arc<FlakyBackend>, Cache) {
+        let primary = Arc::new(FlakyBackend::default());
+        let cache = Cache::new(Arc::new(FallbackCacheBackend::new(primary.clone())), CacheTtls::default());
+        (primary, cache)
+    }
+
+    #[tokio::test]
+    async fn cache_keeps_working_in_process_while_the_primary_is_down() {
+        let (primary, cache) = fallback_cache();
+        primary.set_down(true);
+
+        fill_view(&cache, &CachedView::Rides, "rides").await;
+        assert_eq!(read_view(&cache, &CachedView::Rides).await.as_deref(), Some("rides"));
+        assert!(cache.degraded_reason().is_some_and(|reason| reason.contains("connection refused")));
+
+        cache.invalidate(&[TAG_RIDE]).await;
+        assert_eq!(read_view(&cache, &CachedView::Rides).await, None);
+    }
+
+    #[tokio::test]
+    async fn ping_probes_the_primary_before_any_request_fails() {
+        let (primary, cache) = fallback_cache();
+        primary.set_down(true);
+        assert!(cache.ping().await.is_err());
+        assert!(cache.degraded_reason().is_some());
+    }
+
+    #[tokio::test]
+    async fn writes_during_an_outage_invalidate_the_primary_once_it_recovers() {
+        let (primary, cache) = fallback_cache();
+        fill_view(&cache, &CachedView::Rides, "before outage").await;
+
+        primary.set_down(true);
+        cache.invalidate(&[TAG_RIDE]).await; // Only reaches the in-process cache
+
+        primary.set_down(false);
+        assert!(cache.ping().await.is_ok());
+        assert_eq!(cache.degraded_reason(), None);
+        assert_eq!(read_view(&cache, &CachedView::Rides).await, None);
+    }
+
+    #[tokio::test]
+    async fn entries_from_an_earlier_outage_are_not_served_in_the_next_one() {
+        let (primary, cache) = fallback_cache();
+        primary.set_down(true);
+        fill_view(&cache, &CachedView::Stores, "first outage").await;
+
+        primary.set_down(false);
+        assert!(cache.ping().await.is_ok());
+        cache.invalidate(&[TAG_STORE]).await; // Only reaches the primary
+
+        primary.set_down(true);
+        assert_eq!(read_view(&cache, &CachedView::Stores).await, None);
+    }
+}
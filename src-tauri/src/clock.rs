@@ -0,0 +1,8 @@
+// src-tauri/src/clock.rs
+
+use chrono::{FixedOffset, NaiveDateTime, Utc};
+
+// The park runs on Jakarta time (UTC+7) and every stored timestamp is Jakarta local time
+pub fn jakarta_now() -> NaiveDateTime {
+    Utc::now().with_timezone(&FixedOffset::east_opt(7 * 3600).unwrap()).naive_local()
+}
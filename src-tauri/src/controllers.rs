@@ -12,4 +12,9 @@ pub mod lost_and_found_items_log_handler;
 pub mod chat_handler;
 pub mod maintenance_schedule_handler;
 pub mod income_report_handler;
-pub mod broadcast_message_handler;
\ No newline at end of file
+pub mod broadcast_message_handler;
+pub mod customer_service_ticket_handler;
+pub mod audit_event_handler;
+pub mod dependency_handler;
+pub mod health_handler;
+pub mod offline_sync_handler;
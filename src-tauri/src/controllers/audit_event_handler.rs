@@ -0,0 +1,109 @@
+use chrono::NaiveDateTime;
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set};
+use entity::{audit_event, staff};
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+use crate::clock;
+use crate::error::AppError;
+use crate::{ApiResponse, AppState};
+use crate::controllers::staff_handler::StaffHandler;
+
+// Fields never copied into the audit trail
+const REDACTED_FIELDS: [&str; 2] = ["password_hash", "reset_token_hash"];
+const DEFAULT_AUDIT_LIMIT: u64 = 200;
+const MAX_AUDIT_LIMIT: u64 = 1000;
+
+pub struct AuditEventHandler;
+
+impl AuditEventHandler {
+    // JSON copy of a row for the before/after columns, with secrets removed
+    pub fn snapshot<T: Serialize>(row: &T) -> Option<Value> {
+        let mut value = serde_json::to_value(row).ok()?;
+        if let Value::Object(fields) = &mut value {
+            for field in REDACTED_FIELDS {
+                if fields.contains_key(field) {
+                    fields.insert(field.to_string(), Value::String("[redacted]".to_string()));
+                }
+            }
+        }
+        Some(value)
+    }
+
+    // Append one event. Call it on the transaction of the write it describes, so the write and
+    // its record are kept or rolled back together.
+    pub async fn record<C: ConnectionTrait>(
+        conn: &C,
+        actor_id: Option<&str>,
+        action: &str,
+        entity_type: &str,
+        entity_id: &str,
+        before: Option<Value>,
+        after: Option<Value>,
+    ) -> Result<(), AppError> {
+        let event = audit_event::ActiveModel {
+            audit_event_id: Set(Uuid::new_v4().to_string()),
+            actor_id: Set(actor_id.map(str::to_string)),
+            action: Set(action.to_string()),
+            entity_type: Set(entity_type.to_string()),
+            entity_id: Set(entity_id.to_string()),
+            before: Set(before),
+            after: Set(after),
+            created_at: Set(clock::jakarta_now()),
+        };
+        audit_event::Entity::insert(event)
+            .exec(conn)
+            .await
+            .map(|_| ())
+            .map_err(|err| AppError::Database(format!("Error recording audit event ({} {} {}): {}", action, entity_type, entity_id, err)))
+    }
+
+    /// Audit trail for executives, newest first, filtered by actor, entity and date range
+    #[allow(clippy::too_many_arguments)]
+    pub async fn view_audit_events(
+        state: &AppState,
+        requester_id: String,
+        actor_id: Option<String>,
+        entity_type: Option<String>,
+        entity_id: Option<String>,
+        from: Option<NaiveDateTime>,
+        to: Option<NaiveDateTime>,
+        limit: Option<u64>,
+    ) -> Result<ApiResponse<Vec<audit_event::Model>>, AppError> {
+        let requester = match staff::Entity::find_by_id(requester_id).one(&state.db).await {
+            Ok(Some(requester)) => requester,
+            Ok(None) => return Err(AppError::NotFound("Staff not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching staff: {}", err))),
+        };
+        if StaffHandler::division_for_role(&requester.role) != Some("Executive") {
+            return Err(AppError::Forbidden("Only executives can view the audit log".to_string()));
+        }
+
+        let mut query = audit_event::Entity::find();
+        if let Some(actor_id) = actor_id {
+            query = query.filter(audit_event::Column::ActorId.eq(actor_id));
+        }
+        if let Some(entity_type) = entity_type {
+            query = query.filter(audit_event::Column::EntityType.eq(entity_type));
+        }
+        if let Some(entity_id) = entity_id {
+            query = query.filter(audit_event::Column::EntityId.eq(entity_id));
+        }
+        if let Some(from) = from {
+            query = query.filter(audit_event::Column::CreatedAt.gte(from));
+        }
+        if let Some(to) = to {
+            query = query.filter(audit_event::Column::CreatedAt.lte(to));
+        }
+
+        match query
+            .order_by_desc(audit_event::Column::CreatedAt)
+            .limit(limit.unwrap_or(DEFAULT_AUDIT_LIMIT).clamp(1, MAX_AUDIT_LIMIT))
+            .all(&state.db)
+            .await
+        {
+            Ok(events) => Ok(ApiResponse::success(events)),
+            Err(err) => Err(AppError::Database(format!("Error fetching audit events: {}", err))),
+        }
+    }
+}
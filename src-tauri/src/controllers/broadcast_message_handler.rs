@@ -1,23 +1,222 @@
-use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, Set};
-use entity::broadcast_message::{self, ActiveModel, Model};
+use sea_orm::{sea_query::{Expr, OnConflict}, ActiveModelTrait, ColumnTrait, Condition, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set, TransactionTrait};
+use entity::broadcast_message::{self, Model};
+use entity::{broadcast_recipient, customer, order_restaurant, restaurant, ride, ride_queue, staff};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 use uuid::Uuid;
+use entity::audit_event::{ACTION_CREATE, ACTION_DELETE, ACTION_UPDATE};
+use crate::clock;
+use crate::controllers::audit_event_handler::AuditEventHandler;
+use crate::error::AppError;
+use crate::validation::{self, Validator};
 use crate::{ApiResponse, AppState};
-use chrono::{Utc, FixedOffset, DateTime, NaiveDateTime}; // Import required chrono types
+use crate::controllers::staff_handler::{StaffHandler, DIVISIONS};
+use chrono::NaiveDateTime;
+
+const AUDIENCES: [&str; 2] = ["Customer", "Staff"];
+// Statuses a manager may set; "Expired" is only set by the dispatcher
+const EDITABLE_STATUSES: [&str; 2] = ["Pending", "Sent"];
+
+// Who a broadcast goes to. "Audience" is everyone in `target_audience`; the others narrow it
+// down using `target_value` (a role, a division, a ride_id, a restaurant_id or a venue ui_id).
+const TARGET_TYPES: [&str; 6] = ["Audience", "Role", "Division", "RideQueue", "RestaurantOrdersToday", "Venue"];
+
+// Broadcast as seen by one recipient
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RecipientBroadcast {
+    pub broadcast: Model,
+    pub delivered_at: Option<NaiveDateTime>,
+    pub read_at: Option<NaiveDateTime>,
+}
+
+// Delivery and read counts for one broadcast
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct BroadcastDeliveryStats {
+    pub broadcast_message_id: String,
+    pub recipient_count: u64,
+    pub delivered_count: u64,
+    pub read_count: u64,
+}
+
+// Payload pushed to open clients when a broadcast goes out. `recipient_ids` is None when the
+// broadcast is for a whole audience or a venue, so clients only filter on audience.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct SentBroadcast {
+    #[serde(flatten)]
+    pub broadcast: Model,
+    pub recipient_ids: Option<Vec<String>>,
+}
 
 pub struct BroadcastMessageHandler;
 
 impl BroadcastMessageHandler {
+    // Schedule must be coherent: nothing can expire before (or as) it goes out
+    fn validate_schedule(send_at: Option<NaiveDateTime>, expires_at: Option<NaiveDateTime>) -> Result<(), AppError> {
+        if let Some(expires_at) = expires_at {
+            let earliest_send = send_at.unwrap_or_else(clock::jakarta_now);
+            if expires_at <= earliest_send {
+                return Err(AppError::invalid_field("expires_at", "Expiry time must be after the send time"));
+            }
+        }
+        Ok(())
+    }
+
+    // Check the target against the audience and make sure what it points at exists
+    async fn validate_target(
+        state: &AppState,
+        target_audience: &str,
+        target_type: &str,
+        target_value: Option<&str>,
+    ) -> Result<(), AppError> {
+        if !TARGET_TYPES.contains(&target_type) {
+            return Err(AppError::validation(format!("Invalid target type. Must be one of: {}", TARGET_TYPES.join(", "))));
+        }
+        let target_value = match (target_type, target_value) {
+            ("Audience", None) => return Ok(()),
+            ("Audience", Some(_)) => return Err(AppError::validation("Audience broadcasts do not take a target value".to_string())),
+            (_, None) => return Err(AppError::validation(format!("A target value is required for {} broadcasts", target_type))),
+            (_, Some(value)) => value,
+        };
+
+        let required_audience = match target_type {
+            "Role" | "Division" => Some("Staff"),
+            "RideQueue" | "RestaurantOrdersToday" => Some("Customer"),
+            _ => None, // Venue broadcasts can be for either
+        };
+        if let Some(required_audience) = required_audience {
+            if target_audience != required_audience {
+                return Err(AppError::validation(format!("{} broadcasts must target the '{}' audience", target_type, required_audience)));
+            }
+        }
+
+        let target_exists = match target_type {
+            "Role" => StaffHandler::division_for_role(target_value).is_some(),
+            "Division" => DIVISIONS.contains(&target_value),
+            "RideQueue" => ride::Entity::find_by_id(target_value.to_string())
+                .one(&state.db)
+                .await
+                .map_err(|err| AppError::Database(format!("Database error fetching ride: {}", err)))?
+                .is_some(),
+            "RestaurantOrdersToday" => restaurant::Entity::find_by_id(target_value.to_string())
+                .one(&state.db)
+                .await
+                .map_err(|err| AppError::Database(format!("Database error fetching restaurant: {}", err)))?
+                .is_some(),
+            _ => !target_value.trim().is_empty(),
+        };
+        if !target_exists {
+            return Err(AppError::validation(format!("Unknown {} target: {}", target_type, target_value)));
+        }
+        Ok(())
+    }
+
+    // Work out who a broadcast goes to at the moment it is sent, as (recipient_type, recipient_id).
+    // Venue broadcasts go to devices, so their recipients are recorded when a client fetches them.
+    async fn resolve_recipients<C: sea_orm::ConnectionTrait>(
+        db: &C,
+        broadcast: &Model,
+    ) -> Result<Vec<(&'static str, String)>, AppError> {
+        let target_value = broadcast.target_value.clone().unwrap_or_default();
+
+        let staff_ids = |members: Vec<staff::Model>| members.into_iter().map(|m| ("Staff", m.staff_id)).collect();
+        let recipients: Vec<(&'static str, String)> = match broadcast.target_type.as_str() {
+            "Audience" if broadcast.target_audience == "Staff" => staff_ids(
+                staff::Entity::find().filter(staff::Column::DeletedAt.is_null()).all(db).await
+                    .map_err(|err| AppError::Database(format!("Database error fetching staff recipients: {}", err)))?,
+            ),
+            "Audience" => customer::Entity::find().filter(customer::Column::DeletedAt.is_null()).all(db).await
+                .map_err(|err| AppError::Database(format!("Database error fetching customer recipients: {}", err)))?
+                .into_iter()
+                .map(|c| ("Customer", c.customer_id))
+                .collect(),
+            "Role" => staff_ids(
+                staff::Entity::find()
+                    .filter(staff::Column::Role.eq(target_value))
+                    .filter(staff::Column::DeletedAt.is_null())
+                    .all(db)
+                    .await
+                    .map_err(|err| AppError::Database(format!("Database error fetching staff recipients: {}", err)))?,
+            ),
+            "Division" => staff_ids(
+                staff::Entity::find().filter(staff::Column::DeletedAt.is_null()).all(db).await
+                    .map_err(|err| AppError::Database(format!("Database error fetching staff recipients: {}", err)))?
+                    .into_iter()
+                    .filter(|m| StaffHandler::division_for_role(&m.role) == Some(target_value.as_str()))
+                    .collect(),
+            ),
+            "RideQueue" => ride_queue::Entity::find()
+                .filter(ride_queue::Column::RideId.eq(target_value))
+                .all(db)
+                .await
+                .map_err(|err| AppError::Database(format!("Database error fetching ride queue recipients: {}", err)))?
+                .into_iter()
+                .map(|q| ("Customer", q.customer_id))
+                .collect(),
+            "RestaurantOrdersToday" => {
+                let start_of_day = clock::jakarta_now().date().and_hms_opt(0, 0, 0).unwrap();
+                order_restaurant::Entity::find()
+                    .filter(order_restaurant::Column::RestaurantId.eq(target_value))
+                    .filter(order_restaurant::Column::Timestamp.gte(start_of_day))
+                    .all(db)
+                    .await
+                    .map_err(|err| AppError::Database(format!("Database error fetching restaurant order recipients: {}", err)))?
+                    .into_iter()
+                    .map(|o| ("Customer", o.customer_id))
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        // A customer queued twice or with several orders still gets one copy
+        let mut seen = BTreeSet::new();
+        Ok(recipients.into_iter().filter(|(_, id)| seen.insert(id.clone())).collect())
+    }
+
+    fn new_recipient(broadcast_message_id: &str, recipient_type: &str, recipient_id: &str) -> broadcast_recipient::ActiveModel {
+        broadcast_recipient::ActiveModel {
+            broadcast_recipient_id: Set(Uuid::new_v4().to_string()),
+            broadcast_message_id: Set(broadcast_message_id.to_string()),
+            recipient_type: Set(recipient_type.to_string()),
+            recipient_id: Set(recipient_id.to_string()),
+            delivered_at: Set(None),
+            read_at: Set(None),
+        }
+    }
+
+    // Insert recipient rows, skipping anyone already recorded for the broadcast
+    async fn insert_recipients<C: sea_orm::ConnectionTrait>(
+        db: &C,
+        recipients: Vec<broadcast_recipient::ActiveModel>,
+    ) -> Result<(), AppError> {
+        for chunk in recipients.chunks(500) {
+            broadcast_recipient::Entity::insert_many(chunk.to_vec())
+                .on_conflict(
+                    OnConflict::columns([
+                        broadcast_recipient::Column::BroadcastMessageId,
+                        broadcast_recipient::Column::RecipientId,
+                    ])
+                    .do_nothing()
+                    .to_owned(),
+                )
+                .do_nothing()
+                .exec(db)
+                .await
+                .map_err(|err| AppError::Database(format!("Error recording broadcast recipients: {}", err)))?;
+        }
+        Ok(())
+    }
+
     // View all broadcast messages
     pub async fn view_broadcast_messages(
         state: &AppState,
-    ) -> Result<ApiResponse<Vec<Model>>, String> {
+    ) -> Result<ApiResponse<Vec<Model>>, AppError> {
         match broadcast_message::Entity::find()
             .order_by_desc(broadcast_message::Column::Timestamp) // Order by most recent first
             .all(&state.db)
             .await
         {
             Ok(messages) => Ok(ApiResponse::success(messages)),
-            Err(err) => Err(format!("Error fetching broadcast messages: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error fetching broadcast messages: {}", err))),
         }
     }
 
@@ -25,24 +224,30 @@ impl BroadcastMessageHandler {
     pub async fn view_broadcast_messages_by_audience(
         state: &AppState,
         target_audience: String,
-    ) -> Result<ApiResponse<Vec<Model>>, String> {
+    ) -> Result<ApiResponse<Vec<Model>>, AppError> {
         // Basic validation for audience type
         if target_audience != "Customer" && target_audience != "Staff" {
-            return Err("Invalid target audience. Must be 'Customer' or 'Staff'".to_string());
+            return Err(AppError::validation("Invalid target audience. Must be 'Customer' or 'Staff'".to_string()));
         }
 
         match broadcast_message::Entity::find()
             .filter(
                 Condition::all() // Combine filters
                     .add(broadcast_message::Column::TargetAudience.eq(target_audience))
+                    .add(broadcast_message::Column::TargetType.eq("Audience")) // Targeted broadcasts are per recipient
                     .add(broadcast_message::Column::Status.eq("Sent")) // Filter for "Sent" status
+                    .add(
+                        Condition::any() // Hide broadcasts past their expiry
+                            .add(broadcast_message::Column::ExpiresAt.is_null())
+                            .add(broadcast_message::Column::ExpiresAt.gt(clock::jakarta_now()))
+                    )
             )
-            .order_by_desc(broadcast_message::Column::Timestamp)
+            .order_by_desc(broadcast_message::Column::SentAt)
             .all(&state.db)
             .await
         {
             Ok(messages) => Ok(ApiResponse::success(messages)),
-            Err(err) => Err(format!("Error fetching sent broadcast messages by audience: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error fetching sent broadcast messages by audience: {}", err))),
         }
     }
 
@@ -50,106 +255,455 @@ impl BroadcastMessageHandler {
     pub async fn get_broadcast_message_details(
         state: &AppState,
         broadcast_message_id: String,
-    ) -> Result<ApiResponse<Model>, String> {
+    ) -> Result<ApiResponse<Model>, AppError> {
         match broadcast_message::Entity::find_by_id(broadcast_message_id).one(&state.db).await {
             Ok(Some(message_details)) => Ok(ApiResponse::success(message_details)),
-            Ok(None) => Err("Broadcast message not found".to_string()),
-            Err(err) => Err(format!("Database error fetching broadcast message details: {}", err)),
+            Ok(None) => Err(AppError::NotFound("Broadcast message not found".to_string())),
+            Err(err) => Err(AppError::Database(format!("Database error fetching broadcast message details: {}", err))),
         }
     }
 
     // Save broadcast message data (create new message)
+    #[allow(clippy::too_many_arguments)]
     pub async fn save_broadcast_message_data(
         state: &AppState,
         target_audience: String,
         content: String,
         status: String, // e.g., "Pending" initially
-    ) -> Result<ApiResponse<String>, String> {
+        send_at: Option<NaiveDateTime>, // Jakarta local time to send at; None keeps a Pending broadcast as a draft
+        expires_at: Option<NaiveDateTime>,
+        target_type: Option<String>, // Defaults to the whole audience
+        target_value: Option<String>,
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new()
+            .one_of("target_audience", &target_audience, &AUDIENCES)
+            .field("content", &content, &[validation::required])
+            .one_of("status", &status, &EDITABLE_STATUSES)
+            .optional_one_of("target_type", target_type.as_deref(), &TARGET_TYPES)
+            .finish()?;
+
         let broadcast_message_id = Uuid::new_v4().to_string();
-        let jakarta_time: NaiveDateTime = Utc::now() // Use NaiveDateTime to match entity field
-            .with_timezone(&FixedOffset::east_opt(7 * 3600).unwrap())
-            .naive_local();
+        let jakarta_time: NaiveDateTime = clock::jakarta_now(); // Use NaiveDateTime to match entity field
 
-        // Basic validation
-        if target_audience != "Customer" && target_audience != "Staff" {
-            return Err("Invalid target audience. Must be 'Customer' or 'Staff'".to_string());
-        }
-        if status != "Pending" && status != "Sent" {
-             return Err("Invalid status. Must be 'Pending' or 'Sent'".to_string());
-        }
+        let target_type = target_type.unwrap_or_else(|| "Audience".to_string());
+        Self::validate_target(state, &target_audience, &target_type, target_value.as_deref()).await?;
 
+        // "Sent" means send now: the dispatcher does the actual sending so it is stamped and pushed live
+        let send_at = if status == "Sent" { Some(jakarta_time) } else { send_at };
+        Self::validate_schedule(send_at, expires_at)?;
 
         let new_broadcast_message = broadcast_message::ActiveModel {
             broadcast_message_id: Set(broadcast_message_id),
             target_audience: Set(target_audience),
             content: Set(content),
             timestamp: Set(jakarta_time), // Use jakarta_time
-            status: Set(status),
-            ..Default::default()
+            status: Set("Pending".to_string()),
+            send_at: Set(send_at),
+            sent_at: Set(None),
+            expires_at: Set(expires_at),
+            target_type: Set(target_type),
+            target_value: Set(target_value),
         };
 
-        match broadcast_message::Entity::insert(new_broadcast_message).exec(&state.db).await {
-            Ok(_) => Ok(ApiResponse::success("Broadcast message created successfully".to_string())),
-            Err(err) => Err(format!("Error creating broadcast message: {}", err)),
-        }
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let created = broadcast_message::Entity::insert(new_broadcast_message).exec_with_returning(&txn).await
+            .map_err(|err| AppError::Database(format!("Error creating broadcast message: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_CREATE, "broadcast_message", &created.broadcast_message_id, None, AuditEventHandler::snapshot(&created)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        Ok(ApiResponse::success("Broadcast message created successfully".to_string()))
     }
 
     // Update broadcast message data (e.g., change status from Pending to Sent)
+    #[allow(clippy::too_many_arguments)]
     pub async fn update_broadcast_message_data(
         state: &AppState,
         broadcast_message_id: String,
         target_audience: Option<String>,
         content: Option<String>,
         status: Option<String>,
-    ) -> Result<ApiResponse<String>, String> {
+        send_at: Option<NaiveDateTime>,
+        expires_at: Option<Option<NaiveDateTime>>, // Some(None) clears the expiry
+        target_type: Option<String>,
+        target_value: Option<String>,
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new()
+            .optional_one_of("target_audience", target_audience.as_deref(), &AUDIENCES)
+            .optional("content", content.as_deref(), &[validation::required])
+            .optional_one_of("status", status.as_deref(), &EDITABLE_STATUSES)
+            .optional_one_of("target_type", target_type.as_deref(), &TARGET_TYPES)
+            .finish()?;
+
         let message_record = match broadcast_message::Entity::find_by_id(broadcast_message_id).one(&state.db).await {
             Ok(Some(message)) => message,
-            Ok(None) => return Err("Broadcast message not found".to_string()),
-            Err(err) => return Err(format!("Error fetching broadcast message: {}", err)),
+            Ok(None) => return Err(AppError::NotFound("Broadcast message not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching broadcast message: {}", err))),
+        };
+
+        let was_pending = message_record.status == "Pending";
+        let mut new_send_at = send_at.or(message_record.send_at);
+        let new_expires_at = expires_at.unwrap_or(message_record.expires_at);
+        if !was_pending && (send_at.is_some() || status.as_deref() == Some("Pending")) {
+            return Err(AppError::Conflict(format!("Broadcast message is already {} and cannot be rescheduled", message_record.status)));
+        }
+        if !was_pending && (target_type.is_some() || target_value.is_some() || target_audience.is_some()) {
+            return Err(AppError::Conflict(format!("Broadcast message is already {} and cannot be retargeted", message_record.status)));
+        }
+
+        // Re-check the full target whenever any part of it changes
+        if target_type.is_some() || target_value.is_some() || target_audience.is_some() {
+            let new_target_type = target_type.clone().unwrap_or_else(|| message_record.target_type.clone());
+            let new_target_value = match (&target_type, target_value.clone()) {
+                (_, Some(value)) => Some(value),
+                (Some(new_type), None) if new_type == "Audience" => None,
+                _ => message_record.target_value.clone(),
+            };
+            let new_audience = target_audience.clone().unwrap_or_else(|| message_record.target_audience.clone());
+            Self::validate_target(state, &new_audience, &new_target_type, new_target_value.as_deref()).await?;
+        }
+        let new_target_value = match (&target_type, target_value) {
+            (_, Some(value)) => Some(Some(value)),
+            (Some(new_type), None) if new_type == "Audience" => Some(None),
+            _ => None,
         };
 
+        let before = AuditEventHandler::snapshot(&message_record);
         let mut active_message: broadcast_message::ActiveModel = message_record.into();
 
         if let Some(new_audience) = target_audience {
-             if new_audience != "Customer" && new_audience != "Staff" {
-                return Err("Invalid target audience. Must be 'Customer' or 'Staff'".to_string());
-            }
             active_message.target_audience = Set(new_audience);
         }
         if let Some(new_content) = content {
             active_message.content = Set(new_content);
         }
+        if let Some(new_target_type) = target_type {
+            active_message.target_type = Set(new_target_type);
+        }
+        if let Some(new_target_value) = new_target_value {
+            active_message.target_value = Set(new_target_value);
+        }
         if let Some(new_status) = status {
-            if new_status != "Pending" && new_status != "Sent" {
-                 return Err("Invalid status. Must be 'Pending' or 'Sent'".to_string());
+            // Moving a Pending broadcast to "Sent" sends it now, through the dispatcher
+            if new_status == "Sent" && was_pending {
+                new_send_at = Some(clock::jakarta_now());
+            }
+        }
+        if was_pending {
+            Self::validate_schedule(new_send_at, new_expires_at)?;
+            active_message.send_at = Set(new_send_at);
+        }
+        active_message.expires_at = Set(new_expires_at);
+
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let updated = active_message.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error updating broadcast message: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_UPDATE, "broadcast_message", &updated.broadcast_message_id, before, AuditEventHandler::snapshot(&updated)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        Ok(ApiResponse::success("Broadcast message updated successfully".to_string()))
+    }
+
+    // Send every Pending broadcast whose time has come and expire the ones that missed their window.
+    // Returns the broadcasts that were just sent so the caller can push them to open clients.
+    pub async fn dispatch_due_broadcasts(state: &AppState) -> Result<Vec<Model>, AppError> {
+        let now = clock::jakarta_now();
+
+        broadcast_message::Entity::update_many()
+            .col_expr(broadcast_message::Column::Status, Expr::value("Expired"))
+            .filter(
+                Condition::all()
+                    .add(broadcast_message::Column::Status.eq("Pending"))
+                    .add(broadcast_message::Column::ExpiresAt.lte(now))
+            )
+            .exec(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error expiring broadcast messages: {}", err)))?;
+
+        let due_messages = broadcast_message::Entity::find()
+            .filter(
+                Condition::all()
+                    .add(broadcast_message::Column::Status.eq("Pending"))
+                    .add(broadcast_message::Column::SendAt.lte(now))
+            )
+            .order_by_asc(broadcast_message::Column::SendAt)
+            .all(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching due broadcast messages: {}", err)))?;
+
+        let mut sent_messages = Vec::with_capacity(due_messages.len());
+        for due_message in due_messages {
+            let txn = state.db.begin().await
+                .map_err(|err| AppError::Database(format!("Error starting broadcast dispatch: {}", err)))?;
+
+            // Guarded on status so two dispatch runs never send the same broadcast twice
+            let result = broadcast_message::Entity::update_many()
+                .col_expr(broadcast_message::Column::Status, Expr::value("Sent"))
+                .col_expr(broadcast_message::Column::SentAt, Expr::value(now))
+                .filter(broadcast_message::Column::BroadcastMessageId.eq(due_message.broadcast_message_id.clone()))
+                .filter(broadcast_message::Column::Status.eq("Pending"))
+                .exec(&txn)
+                .await
+                .map_err(|err| AppError::Database(format!("Error sending broadcast message: {}", err)))?;
+
+            if result.rows_affected > 0 {
+                // Recipients are fixed at send time, e.g. whoever is in the ride queue right now
+                let recipients = Self::resolve_recipients(&txn, &due_message).await?
+                    .into_iter()
+                    .map(|(recipient_type, recipient_id)| {
+                        Self::new_recipient(&due_message.broadcast_message_id, recipient_type, &recipient_id)
+                    })
+                    .collect();
+                Self::insert_recipients(&txn, recipients).await?;
+            }
+            txn.commit().await
+                .map_err(|err| AppError::Database(format!("Error committing broadcast dispatch: {}", err)))?;
+
+            if result.rows_affected > 0 {
+                sent_messages.push(Model {
+                    status: "Sent".to_string(),
+                    sent_at: Some(now),
+                    ..due_message
+                });
             }
-            active_message.status = Set(new_status);
-            // Optionally update the timestamp if the status changes to "Sent"?
-            // if new_status == "Sent" {
-            //     let jakarta_time: NaiveDateTime = Utc::now()
-            //         .with_timezone(&FixedOffset::east_opt(7 * 3600).unwrap())
-            //         .naive_local();
-            //     active_message.timestamp = Set(jakarta_time);
-            // }
         }
 
-        match active_message.update(&state.db).await {
-            Ok(_) => Ok(ApiResponse::success("Broadcast message updated successfully".to_string())),
-            Err(err) => Err(format!("Error updating broadcast message: {}", err)),
+        Ok(sent_messages)
+    }
+
+    // Broadcasts sent after `since` (by any app instance), ready to push to this instance's windows.
+    // Venue broadcasts are only included for the venue this instance runs at.
+    pub async fn view_sent_broadcasts_since(
+        state: &AppState,
+        since: NaiveDateTime,
+        ui_id: Option<&str>,
+    ) -> Result<Vec<SentBroadcast>, AppError> {
+        let sent_messages = broadcast_message::Entity::find()
+            .filter(
+                Condition::all()
+                    .add(broadcast_message::Column::Status.eq("Sent"))
+                    .add(broadcast_message::Column::SentAt.gt(since))
+            )
+            .order_by_asc(broadcast_message::Column::SentAt)
+            .all(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching sent broadcast messages: {}", err)))?;
+
+        let mut sent_broadcasts = Vec::with_capacity(sent_messages.len());
+        for sent_message in sent_messages {
+            let recipient_ids = match sent_message.target_type.as_str() {
+                "Audience" => None,
+                "Venue" if sent_message.target_value.as_deref() == ui_id => None,
+                "Venue" => continue,
+                _ => Some(
+                    broadcast_recipient::Entity::find()
+                        .filter(broadcast_recipient::Column::BroadcastMessageId.eq(sent_message.broadcast_message_id.clone()))
+                        .all(&state.db)
+                        .await
+                        .map_err(|err| AppError::Database(format!("Error fetching broadcast recipients: {}", err)))?
+                        .into_iter()
+                        .map(|r| r.recipient_id)
+                        .collect(),
+                ),
+            };
+            sent_broadcasts.push(SentBroadcast {
+                broadcast: sent_message,
+                recipient_ids,
+            });
         }
+        Ok(sent_broadcasts)
+    }
+
+    // Live broadcasts for one customer or staff member, marking them delivered.
+    // `ui_id` is the venue this client runs at, which picks up Venue broadcasts for it.
+    pub async fn view_broadcast_messages_for_recipient(
+        state: &AppState,
+        recipient_id: String,
+        ui_id: Option<String>,
+    ) -> Result<ApiResponse<Vec<RecipientBroadcast>>, AppError> {
+        let now = clock::jakarta_now();
+        let is_customer = customer::Entity::find_by_id(recipient_id.clone())
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching customer: {}", err)))?
+            .is_some();
+        let is_staff = !is_customer && staff::Entity::find_by_id(recipient_id.clone())
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching staff: {}", err)))?
+            .is_some();
+        let recipient_type = match (is_customer, is_staff) {
+            (true, _) => "Customer",
+            (false, true) => "Staff",
+            _ => return Err(AppError::NotFound("Recipient not found as customer or staff".to_string())),
+        };
+
+        let live_broadcasts = Condition::all()
+            .add(broadcast_message::Column::Status.eq("Sent"))
+            .add(
+                Condition::any()
+                    .add(broadcast_message::Column::ExpiresAt.is_null())
+                    .add(broadcast_message::Column::ExpiresAt.gt(now))
+            );
+
+        // Whoever is at a venue receives its broadcasts, so record them as a recipient on first fetch
+        if let Some(ui_id) = ui_id {
+            let venue_broadcasts = broadcast_message::Entity::find()
+                .filter(live_broadcasts.clone())
+                .filter(broadcast_message::Column::TargetType.eq("Venue"))
+                .filter(broadcast_message::Column::TargetValue.eq(ui_id))
+                .filter(broadcast_message::Column::TargetAudience.eq(recipient_type))
+                .all(&state.db)
+                .await
+                .map_err(|err| AppError::Database(format!("Error fetching venue broadcast messages: {}", err)))?;
+            let venue_recipients = venue_broadcasts.iter()
+                .map(|b| Self::new_recipient(&b.broadcast_message_id, recipient_type, &recipient_id))
+                .collect();
+            Self::insert_recipients(&state.db, venue_recipients).await?;
+        }
+
+        let received = broadcast_recipient::Entity::find()
+            .filter(broadcast_recipient::Column::RecipientId.eq(recipient_id.clone()))
+            .find_also_related(broadcast_message::Entity)
+            .filter(live_broadcasts)
+            .order_by_desc(broadcast_message::Column::SentAt)
+            .all(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching broadcast messages for recipient: {}", err)))?;
+
+        broadcast_recipient::Entity::update_many()
+            .col_expr(broadcast_recipient::Column::DeliveredAt, Expr::value(now))
+            .filter(broadcast_recipient::Column::RecipientId.eq(recipient_id))
+            .filter(broadcast_recipient::Column::DeliveredAt.is_null())
+            .filter(
+                broadcast_recipient::Column::BroadcastRecipientId
+                    .is_in(received.iter().map(|(r, _)| r.broadcast_recipient_id.clone()))
+            )
+            .exec(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error marking broadcast messages delivered: {}", err)))?;
+
+        let broadcasts = received
+            .into_iter()
+            .filter_map(|(recipient, broadcast)| {
+                broadcast.map(|broadcast| RecipientBroadcast {
+                    broadcast,
+                    delivered_at: recipient.delivered_at.or(Some(now)),
+                    read_at: recipient.read_at,
+                })
+            })
+            .collect();
+        Ok(ApiResponse::success(broadcasts))
+    }
+
+    // Mark a broadcast as read by one recipient
+    pub async fn mark_broadcast_message_read(
+        state: &AppState,
+        broadcast_message_id: String,
+        recipient_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let recipient = broadcast_recipient::Entity::find()
+            .filter(broadcast_recipient::Column::BroadcastMessageId.eq(broadcast_message_id))
+            .filter(broadcast_recipient::Column::RecipientId.eq(recipient_id))
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching broadcast recipient: {}", err)))?
+            .ok_or_else(|| AppError::NotFound("Broadcast message was not sent to this recipient".to_string()))?;
+
+        if recipient.read_at.is_some() {
+            return Ok(ApiResponse::success("Broadcast message already read".to_string()));
+        }
+
+        let now = clock::jakarta_now();
+        let delivered_at = recipient.delivered_at.unwrap_or(now);
+        let mut active_recipient: broadcast_recipient::ActiveModel = recipient.into();
+        active_recipient.delivered_at = Set(Some(delivered_at));
+        active_recipient.read_at = Set(Some(now));
+        match active_recipient.update(&state.db).await {
+            Ok(_) => Ok(ApiResponse::success("Broadcast message marked as read".to_string())),
+            Err(err) => Err(AppError::Database(format!("Error marking broadcast message as read: {}", err))),
+        }
+    }
+
+    // How many recipients a broadcast reached and how many read it
+    pub async fn get_broadcast_delivery_stats(
+        state: &AppState,
+        broadcast_message_id: String,
+    ) -> Result<ApiResponse<BroadcastDeliveryStats>, AppError> {
+        let recipients = || broadcast_recipient::Entity::find()
+            .filter(broadcast_recipient::Column::BroadcastMessageId.eq(broadcast_message_id.clone()));
+
+        let recipient_count = recipients().count(&state.db).await
+            .map_err(|err| AppError::Database(format!("Error counting broadcast recipients: {}", err)))?;
+        let delivered_count = recipients()
+            .filter(broadcast_recipient::Column::DeliveredAt.is_not_null())
+            .count(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error counting delivered broadcasts: {}", err)))?;
+        let read_count = recipients()
+            .filter(broadcast_recipient::Column::ReadAt.is_not_null())
+            .count(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error counting read broadcasts: {}", err)))?;
+
+        Ok(ApiResponse::success(BroadcastDeliveryStats {
+            broadcast_message_id,
+            recipient_count,
+            delivered_count,
+            read_count,
+        }))
     }
 
     // Delete broadcast message data
-    pub async fn delete_broadcast_message_data(state: &AppState, broadcast_message_id: String) -> Result<String, String> {
-        match broadcast_message::Entity::delete_by_id(broadcast_message_id).exec(&state.db).await {
-            Ok(delete_result) => {
-                if delete_result.rows_affected > 0 {
-                    Ok("Broadcast message deleted successfully".to_string())
-                } else {
-                    Err("Broadcast message not found".to_string())
-                }
-            }
-            Err(err) => Err(format!("Error deleting broadcast message: {}", err)),
+    pub async fn delete_broadcast_message_data(state: &AppState, broadcast_message_id: String, actor_id: String) -> Result<ApiResponse<String>, AppError> {
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let existing = broadcast_message::Entity::find_by_id(broadcast_message_id.clone()).one(&txn).await.ok().flatten();
+        let delete_result = broadcast_message::Entity::delete_by_id(broadcast_message_id.clone()).exec(&txn).await
+            .map_err(|err| AppError::Database(format!("Error deleting broadcast message: {}", err)))?;
+        if delete_result.rows_affected == 0 {
+            return Err(AppError::NotFound("Broadcast message not found".to_string()));
         }
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_DELETE, "broadcast_message", &broadcast_message_id, existing.as_ref().and_then(AuditEventHandler::snapshot), None).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        Ok(ApiResponse::success("Broadcast message deleted successfully".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, data, ACTOR};
+    use chrono::Duration;
+
+    #[tokio::test]
+    async fn an_update_keeps_or_clears_the_expiry() {
+        let state = test_support::test_state().await;
+        let send_at = clock::jakarta_now() + Duration::hours(1);
+        data(BroadcastMessageHandler::save_broadcast_message_data(
+            &state,
+            "Customer".to_string(),
+            "Fireworks at eight".to_string(),
+            "Pending".to_string(),
+            Some(send_at),
+            Some(send_at + Duration::hours(2)),
+            None,
+            None,
+            ACTOR.to_string(),
+        )
+        .await);
+        let message = broadcast_message::Entity::find().one(&state.db).await.unwrap().unwrap();
+
+        let update = |expires_at| {
+            BroadcastMessageHandler::update_broadcast_message_data(
+                &state, message.broadcast_message_id.clone(), None, None, None, None, expires_at, None, None, ACTOR.to_string(),
+            )
+        };
+        data(update(None).await);
+        let kept = broadcast_message::Entity::find().one(&state.db).await.unwrap().unwrap();
+        assert_eq!(kept.expires_at, Some(send_at + Duration::hours(2)));
+
+        data(update(Some(None)).await);
+        let cleared = broadcast_message::Entity::find().one(&state.db).await.unwrap().unwrap();
+        assert_eq!(cleared.expires_at, None);
+        assert_eq!(cleared.send_at, Some(send_at));
     }
-}
\ No newline at end of file
+}
@@ -1,46 +1,142 @@
 // src-tauri/src/handler/chat_handler.rs
 
-use chrono::{FixedOffset, NaiveDateTime, Utc};
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, RelationTrait, Set, ModelTrait, QueryResult};
-use entity::{chat, chat_member, customer, message, staff};
+use chrono::NaiveDateTime;
+use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DbBackend, EntityTrait, FromQueryResult, QueryFilter, QueryOrder, QuerySelect, QueryTrait, Set, ModelTrait, Statement, TransactionTrait};
+use entity::{chat, chat_member, customer, customer_service_ticket, message, message_attachment, message_edit, staff};
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use crate::{ApiResponse, AppState, cache_get, cache_set, cache_delete};
+use entity::audit_event::{ACTION_DELETE, ACTION_UPDATE};
+use crate::clock;
+use crate::cache::{scoped_tag, CachedView, TAG_CHAT, TAG_CHAT_MEMBER};
+use crate::controllers::audit_event_handler::AuditEventHandler;
+use crate::error::AppError;
+use crate::validation::{self, Validator};
+use crate::{ApiResponse, AppState};
+use crate::controllers::customer_service_ticket_handler::{CustomerServiceTicketHandler, CUSTOMER_SERVICE_ROLES};
+use crate::controllers::staff_handler::{StaffHandler, DIVISIONS};
 use futures::{future::join_all}; 
 
+// Sender id of automated notices posted on the park's behalf (not a customer or staff row)
+pub const SYSTEM_SENDER_ID: &str = "system";
+const SYSTEM_SENDER_NAME: &str = "VorteKia";
+
 // Define a new struct to hold message data with sender name
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct MessageWithSenderName {
     pub message: message::Model,
     pub sender_name: String,
+    pub attachments: Vec<message_attachment::Model>,
+}
+
+// Image attached to a new message: either already hosted (`url`) or raw bytes for the storage backend (`data`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewAttachment {
+    pub file_name: String,
+    pub content_type: String,
+    pub url: Option<String>,
+    pub data: Option<Vec<u8>>,
+}
+
+// Piece of a search result's text, `matched` marks the search hits to highlight
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct HighlightSegment {
+    pub text: String,
+    pub matched: bool,
+}
+
+// Message matching a search, with the chat it belongs to and the messages around it
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct MessageSearchResult {
+    pub message: MessageWithSenderName,
+    pub chat: chat::Model,
+    pub highlight: Vec<HighlightSegment>,
+    pub rank: f32,
+    pub context_before: Vec<MessageWithSenderName>,
+    pub context_after: Vec<MessageWithSenderName>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct SearchHit {
+    message_id: String,
+    headline: String,
+    rank: f32,
 }
 
+// Markers ts_headline puts around matches; control characters cannot clash with message text
+const HIGHLIGHT_START: &str = "\u{1}";
+const HIGHLIGHT_STOP: &str = "\u{2}";
+const SEARCH_CONTEXT_MESSAGES: u64 = 2; // Messages shown before and after each hit
+const DEFAULT_SEARCH_LIMIT: u64 = 20;
+const MAX_SEARCH_LIMIT: u64 = 50;
+
+const MAX_ATTACHMENT_BYTES: usize = 10 * 1024 * 1024;
+const ATTACHMENT_PREVIEW_TEXT: &str = "[Image]"; // Shown as last message for image-only messages
+
 // Get Customer Service Chats for Staff with customer names
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct ChatWithCustomerName {
     chat: chat::Model,
     customer_name: String,
+    ticket: Option<customer_service_ticket::Model>, // Active ticket, if the customer is waiting or pending
 }
 
 pub struct ChatHandler;
 
 impl ChatHandler {
+    // Build a typed membership row for a customer or staff participant
+    fn new_chat_member(chat_id: &str, user_id: &str, participant_type: &str) -> chat_member::ActiveModel {
+        let is_customer = participant_type == chat_member::PARTICIPANT_CUSTOMER;
+        chat_member::ActiveModel {
+            chat_member_id: Set(Uuid::new_v4().to_string()),
+            chat_id: Set(chat_id.to_string()),
+            user_id: Set(user_id.to_string()),
+            participant_type: Set(participant_type.to_string()),
+            customer_id: Set(is_customer.then(|| user_id.to_string())),
+            staff_id: Set((!is_customer).then(|| user_id.to_string())),
+            ..Default::default()
+        }
+    }
+
+    // Work out whether a user id belongs to a customer or a staff member
+    async fn participant_type_for(state: &AppState, user_id: &str) -> Result<&'static str, AppError> {
+        let is_customer = customer::Entity::find_by_id(user_id.to_string())
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching customer: {}", err)))?
+            .is_some();
+        if is_customer {
+            return Ok(chat_member::PARTICIPANT_CUSTOMER);
+        }
+
+        let is_staff = staff::Entity::find_by_id(user_id.to_string())
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching staff: {}", err)))?
+            .is_some();
+        if is_staff {
+            return Ok(chat_member::PARTICIPANT_STAFF);
+        }
+
+        Err(AppError::NotFound("User not found as customer or staff".to_string()))
+    }
+
     // Get or create customer service chat for customer
     pub async fn get_customer_service_chat(
         state: &AppState,
         customer_id: String,
-    ) -> Result<ApiResponse<chat::Model>, String> {
+    ) -> Result<ApiResponse<chat::Model>, AppError> {
         // Define the name for the Customer Service Chat
         let customer_service_chat_name = "Customer Service";
 
         // 1. Try to find existing chat_member record for the customer in Customer Service chat
         let existing_membership = chat_member::Entity::find()
-            .filter(chat_member::Column::UserId.eq(customer_id.clone()))
+            .filter(chat_member::Column::CustomerId.eq(customer_id.clone()))
             .inner_join(chat::Entity)
-            .filter(chat::Column::Name.eq(customer_service_chat_name))
+            .filter(chat::Column::ChatType.eq(chat::CHAT_TYPE_CUSTOMER_SERVICE))
             .one(&state.db)
             .await
-            .map_err(|err| format!("Database error checking chat membership: {}", err))?;
+            .map_err(|err| AppError::Database(format!("Database error checking chat membership: {}", err)))?;
 
         match existing_membership {
             Some(membership) => {
@@ -48,8 +144,8 @@ impl ChatHandler {
                 let customer_service_chat = membership.find_related(chat::Entity)
                     .one(&state.db)
                     .await
-                    .map_err(|err| format!("Database error fetching chat from membership: {}", err))?
-                    .ok_or_else(|| "Chat not found for existing membership".to_string())?;
+                    .map_err(|err| AppError::Database(format!("Database error fetching chat from membership: {}", err)))?
+                    .ok_or_else(|| AppError::NotFound("Chat not found for existing membership".to_string()))?;
                 Ok(ApiResponse::success(customer_service_chat))
             },
             None => {
@@ -58,125 +154,188 @@ impl ChatHandler {
                 let new_chat = chat::ActiveModel {
                     chat_id: Set(new_chat_id.clone()),
                     name: Set(customer_service_chat_name.to_string()),
+                    chat_type: Set(chat::CHAT_TYPE_CUSTOMER_SERVICE.to_string()),
                     ..Default::default()
                 };
 
                 let created_chat = chat::Entity::insert(new_chat)
                     .exec_with_returning(&state.db)
                     .await
-                    .map_err(|err| format!("Error creating Customer Service chat: {}", err))?;
+                    .map_err(|err| AppError::Database(format!("Error creating Customer Service chat: {}", err)))?;
 
 
-                let new_chat_member_id = Uuid::new_v4().to_string();
-                let new_chat_member = chat_member::ActiveModel {
-                    chat_member_id: Set(new_chat_member_id),
-                    chat_id: Set(new_chat_id),
-                    user_id: Set(customer_id.clone()),
-                    ..Default::default()
-                };
+                let new_chat_member = Self::new_chat_member(&new_chat_id, &customer_id, chat_member::PARTICIPANT_CUSTOMER);
                 chat_member::Entity::insert(new_chat_member).exec(&state.db).await
-                    .map_err(|err| format!("Error creating chat membership: {}", err))?;
-
+                    .map_err(|err| AppError::Database(format!("Error creating chat membership: {}", err)))?;
+                state.cache.invalidate(&[TAG_CHAT]).await;
 
                 Ok(ApiResponse::success(created_chat)) // Return the newly created chat
             }
         }
     }
 
-    // Get Customer Service Chats for Staff (all chats named "Customer Service")
-    pub async fn get_customer_chats_for_staff( // New function
+    // Post an automated notice in the customer's Customer Service chat, creating the chat if needed
+    pub async fn notify_customer(state: &AppState, customer_id: &str, text: String) -> Result<(), AppError> {
+        let chat_id = Self::get_customer_service_chat(state, customer_id.to_string()).await?.into_data()?.chat_id;
+        Self::save_message_data(state, chat_id, SYSTEM_SENDER_ID.to_string(), text, None).await?;
+        Ok(())
+    }
+
+    // Key identifying a direct chat by its participants, independent of who started it
+    fn direct_chat_key(staff_id: &str, other_staff_id: &str) -> String {
+        if staff_id <= other_staff_id {
+            format!("{}:{}", staff_id, other_staff_id)
+        } else {
+            format!("{}:{}", other_staff_id, staff_id)
+        }
+    }
+
+    async fn find_direct_chat(state: &AppState, direct_key: &str) -> Result<Option<chat::Model>, AppError> {
+        chat::Entity::find()
+            .filter(chat::Column::ChatType.eq(chat::CHAT_TYPE_DIRECT))
+            .filter(chat::Column::DirectKey.eq(direct_key))
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching direct chat: {}", err)))
+    }
+
+    // Get or create the one-to-one chat between two staff members
+    pub async fn get_direct_chat(
         state: &AppState,
-    ) -> Result<ApiResponse<Vec<ChatWithCustomerName>>, String> {
-        let cache_key = "view_customer_service_chats_for_staff_cache"; // Separate cache key
+        staff_id: String,
+        other_staff_id: String,
+    ) -> Result<ApiResponse<chat::Model>, AppError> {
+        if staff_id == other_staff_id {
+            return Err(AppError::validation("Cannot start a direct chat with yourself".to_string()));
+        }
 
-        if let Some(cached_chats) = cache_get::<Vec<ChatWithCustomerName>>(&state.redis_pool, &cache_key).await {
-            println!("Cache hit: Returning Customer Service chats for staff from Redis");
-            return Ok(ApiResponse::success(cached_chats));
+        let direct_key = Self::direct_chat_key(&staff_id, &other_staff_id);
+        if let Some(existing_chat) = Self::find_direct_chat(state, &direct_key).await? {
+            return Ok(ApiResponse::success(existing_chat));
         }
 
-        // Fetch all "Customer Service" chats
-        let chats = chat::Entity::find()
-            .filter(chat::Column::Name.eq("Customer Service"))
-            .order_by_asc(chat::Column::CreatedAt)
-            .all(&state.db)
+        let staff_member = staff::Entity::find_by_id(staff_id.clone())
+            .one(&state.db)
             .await
-            .map_err(|err| format!("Error fetching Customer Service chats for staff: {}", err))?;
-
-        // Enrich each chat with the customer name
-        let chats_with_names_futures = chats.into_iter().map(|chat| async {
-            let customer_name = Self::get_customer_name(state, &chat).await?;
-            Ok(ChatWithCustomerName {
-                chat,
-                customer_name,
-            })
-        });
+            .map_err(|err| AppError::Database(format!("Database error fetching staff: {}", err)))?
+            .ok_or_else(|| AppError::NotFound("Staff member not found".to_string()))?;
+        let other_staff_member = staff::Entity::find_by_id(other_staff_id.clone())
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching staff: {}", err)))?
+            .ok_or_else(|| AppError::NotFound("Other staff member not found".to_string()))?;
 
-        let results: Vec<Result<ChatWithCustomerName, String>> = join_all(chats_with_names_futures).await;
-        let chats_with_names: Result<Vec<ChatWithCustomerName>, String> = results.into_iter().collect();
+        // Chat and both memberships are created together so a half-built chat is never visible
+        let txn = state.db.begin().await
+            .map_err(|err| AppError::Database(format!("Error starting direct chat transaction: {}", err)))?;
 
-        match chats_with_names {
-            Ok(chats_with_names) => {
-                cache_set(&state.redis_pool, &cache_key, &chats_with_names, 60).await;
-                Ok(ApiResponse::success(chats_with_names))
+        let new_chat_id = Uuid::new_v4().to_string();
+        let new_chat = chat::ActiveModel {
+            chat_id: Set(new_chat_id.clone()),
+            name: Set(format!("{} & {}", staff_member.name, other_staff_member.name)),
+            chat_type: Set(chat::CHAT_TYPE_DIRECT.to_string()),
+            direct_key: Set(Some(direct_key.clone())),
+            ..Default::default()
+        };
+
+        let created_chat = match chat::Entity::insert(new_chat).exec_with_returning(&txn).await {
+            Ok(created_chat) => created_chat,
+            Err(err) => {
+                let _ = txn.rollback().await;
+                // Another request created the same pair in the meantime, use that chat
+                return match Self::find_direct_chat(state, &direct_key).await? {
+                    Some(existing_chat) => Ok(ApiResponse::success(existing_chat)),
+                    None => Err(AppError::Database(format!("Error creating direct chat: {}", err))),
+                };
             }
-            Err(err) => Err(err),
+        };
+
+        for member_id in [&staff_id, &other_staff_id] {
+            let new_chat_member = Self::new_chat_member(&new_chat_id, member_id, chat_member::PARTICIPANT_STAFF);
+            chat_member::Entity::insert(new_chat_member)
+                .exec(&txn)
+                .await
+                .map_err(|err| AppError::Database(format!("Error creating direct chat membership: {}", err)))?;
         }
+
+        txn.commit().await
+            .map_err(|err| AppError::Database(format!("Error committing direct chat: {}", err)))?;
+
+        state.cache.invalidate(&[&scoped_tag(TAG_CHAT_MEMBER, &staff_id), &scoped_tag(TAG_CHAT_MEMBER, &other_staff_id)]).await;
+        Ok(ApiResponse::success(created_chat))
+    }
+
+    // Get Customer Service Chats for Staff (all chats of type CustomerService)
+    pub async fn get_customer_chats_for_staff( // New function
+        state: &AppState,
+    ) -> Result<ApiResponse<Vec<ChatWithCustomerName>>, AppError> {
+        let chats_with_names = state.cache.read_through(&CachedView::CustomerServiceChats, || async {
+            // Fetch all Customer Service chats
+            let chats = chat::Entity::find()
+                .filter(chat::Column::ChatType.eq(chat::CHAT_TYPE_CUSTOMER_SERVICE))
+                .order_by_asc(chat::Column::CreatedAt)
+                .all(&state.db)
+                .await
+                .map_err(|err| AppError::Database(format!("Error fetching Customer Service chats for staff: {}", err)))?;
+
+            // Enrich each chat with the customer name
+            let chats_with_names_futures = chats.into_iter().map(|chat| async {
+                let customer_name = Self::get_customer_name(state, &chat).await?;
+                let ticket = CustomerServiceTicketHandler::get_active_ticket(state, chat.chat_id.clone()).await?.into_data()?;
+                Ok(ChatWithCustomerName {
+                    chat,
+                    customer_name,
+                    ticket,
+                })
+            });
+
+            let results: Vec<Result<ChatWithCustomerName, AppError>> = join_all(chats_with_names_futures).await;
+            results.into_iter().collect::<Result<Vec<ChatWithCustomerName>, AppError>>()
+        }).await?;
+
+        Ok(ApiResponse::success(chats_with_names))
     }
 
     // Helper function to get customer name for a chat
-    async fn get_customer_name(state: &AppState, chat: &chat::Model) -> Result<String, String> {
-        let chat_member = chat_member::Entity::find()
+    async fn get_customer_name(state: &AppState, chat: &chat::Model) -> Result<String, AppError> {
+        let (_, customer) = chat_member::Entity::find()
             .filter(chat_member::Column::ChatId.eq(chat.chat_id.clone()))
+            .filter(chat_member::Column::ParticipantType.eq(chat_member::PARTICIPANT_CUSTOMER))
+            .find_also_related(customer::Entity)
             .one(&state.db)
             .await
-            .map_err(|err| format!("Database error fetching chat member: {}", err))?
-            .ok_or_else(|| "No chat member found for this chat".to_string())?;
+            .map_err(|err| AppError::Database(format!("Database error fetching chat member: {}", err)))?
+            .ok_or_else(|| AppError::NotFound("No customer member found for this chat".to_string()))?;
 
-        let customer = customer::Entity::find()
-            .filter(customer::Column::CustomerId.eq(chat_member.user_id))
-            .one(&state.db)
-            .await
-            .map_err(|err| format!("Database error fetching customer: {}", err))?
-            .ok_or_else(|| "Customer not found for chat member".to_string())?;
+        let customer = customer.ok_or_else(|| AppError::NotFound("Customer not found for chat member".to_string()))?;
 
         Ok(customer.name)
     }
 
     // View chats for a specific user (customer or staff)
-    pub async fn view_chats(state: &AppState, user_id: String) -> Result<ApiResponse<Vec<chat::Model>>, String> {
-        let cache_key = format!("view_chats_user_{}", user_id);
-
-        if let Some(cached_chats) = cache_get::<Vec<chat::Model>>(&state.redis_pool, &cache_key).await {
-            println!("Cache hit: Returning chats for user {} from Redis", user_id);
-            return Ok(ApiResponse::success(cached_chats));
-        }
+    pub async fn view_chats(state: &AppState, user_id: String) -> Result<ApiResponse<Vec<chat::Model>>, AppError> {
+        let chats = state.cache.read_through(&CachedView::ChatsForUser(&user_id), || async {
+            let chat_members_and_chats = chat_member::Entity::find()
+                .filter(chat_member::Column::UserId.eq(user_id.clone()))
+                .find_also_related(chat::Entity)
+                .all(&state.db)
+                .await
+                .map_err(|err| AppError::Database(format!("Error fetching chats for user {}: {}", user_id, err)))?;
+            Ok::<_, AppError>(chat_members_and_chats.into_iter().filter_map(|(_, chat_opt)| chat_opt).collect::<Vec<chat::Model>>())
+        }).await?;
 
-        match chat_member::Entity::find()
-            .filter(chat_member::Column::UserId.eq(user_id.clone()))
-            .find_also_related(chat::Entity)
-            .all(&state.db)
-            .await
-        {
-            Ok(chat_members_and_chats) => {
-                let chats: Vec<chat::Model> = chat_members_and_chats.into_iter()
-                    .filter_map(|(_, chat_opt)| chat_opt)
-                    .collect();
-                cache_set(&state.redis_pool, &cache_key, &chats, 60).await;
-                Ok(ApiResponse::success(chats))
-            }
-            Err(err) => Err(format!("Error fetching chats for user {}: {}", user_id, err)),
-        }
+        Ok(ApiResponse::success(chats))
     }
 
     // Get chat details by ID
     pub async fn get_chat_details(
         state: &AppState,
         chat_id: String,
-    ) -> Result<ApiResponse<chat::Model>, String> {
+    ) -> Result<ApiResponse<chat::Model>, AppError> {
         match chat::Entity::find_by_id(chat_id.clone()).one(&state.db).await {
             Ok(Some(chat_details)) => Ok(ApiResponse::success(chat_details)),
-            Ok(None) => Err("Chat not found".to_string()),
-            Err(err) => Err(format!("Database error fetching chat details: {}", err)),
+            Ok(None) => Err(AppError::NotFound("Chat not found".to_string())),
+            Err(err) => Err(AppError::Database(format!("Database error fetching chat details: {}", err))),
         }
     }
 
@@ -184,21 +343,29 @@ impl ChatHandler {
     pub async fn save_chat_data(
         state: &AppState,
         name: String,
-    ) -> Result<ApiResponse<String>, String> {
+        chat_type: Option<String>, // Defaults to a group chat
+    ) -> Result<ApiResponse<String>, AppError> {
+        let chat_type = chat_type.unwrap_or_else(|| chat::CHAT_TYPE_DIVISION_GROUP.to_string());
+        Validator::new()
+            .field("name", &name, &[validation::name])
+            .one_of("chat_type", &chat_type, &[chat::CHAT_TYPE_CUSTOMER_SERVICE, chat::CHAT_TYPE_DIVISION_GROUP, chat::CHAT_TYPE_DIRECT])
+            .finish()?;
+
         let chat_id = Uuid::new_v4().to_string();
 
         let new_chat = chat::ActiveModel {
             chat_id: sea_orm::ActiveValue::Set(chat_id),
             name: sea_orm::ActiveValue::Set(name),
+            chat_type: sea_orm::ActiveValue::Set(chat_type),
             ..Default::default()
         };
 
         match chat::Entity::insert(new_chat).exec(&state.db).await {
             Ok(_) => {
-                cache_delete(&state.redis_pool, "view_chats_cache_*").await; // Invalidate all view_chats caches
+                state.cache.invalidate(&[TAG_CHAT]).await;
                 Ok(ApiResponse::success("Chat created successfully".to_string()))
             }
-            Err(err) => Err(format!("Error creating chat: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error creating chat: {}", err))),
         }
     }
 
@@ -206,21 +373,18 @@ impl ChatHandler {
     pub async fn get_messages(
         state: &AppState,
         chat_id: String,
-    ) -> Result<ApiResponse<Vec<MessageWithSenderName>>, String> { // Return ApiResponse<Vec<MessageWithSenderName>>
-        let cache_key = format!("get_messages_chat_{}", chat_id);
-
-        // if let Some(cached_messages) = cache_get::<Vec<MessageWithSenderName>>(&state.redis_pool, &cache_key).await {
-        //     println!("Cache hit: Returning messages with sender names for chat {} from Redis", chat_id);
-        //     return Ok(ApiResponse::success(cached_messages));
-        // }
-
+    ) -> Result<ApiResponse<Vec<MessageWithSenderName>>, AppError> { // Return ApiResponse<Vec<MessageWithSenderName>>
+        // Not cached: messages change with every send, edit and delete
         match message::Entity::find()
             .filter(message::Column::ChatId.eq(chat_id.clone()))
+            .filter(message::Column::DeletedAt.is_null())
             .order_by_asc(message::Column::Timestamp)
             .all(&state.db)
             .await
         {
             Ok(messages) => {
+                let mut attachments_by_message = Self::get_attachments_by_message(state, &messages).await?;
+
                 // println!("Raw messages retrieved for chat {} (count: {}):", chat_id, messages.len());
                 // for (index, msg) in messages.iter().enumerate() {
                 //     println!(
@@ -229,19 +393,23 @@ impl ChatHandler {
                 //     );
                 // }
 
-                let messages_with_names_futures = messages.into_iter().map(|message| async { // Changed variable name for clarity
-                    let sender_name = Self::get_sender_name(state, &message).await?;
-                    Ok(MessageWithSenderName {
-                        message,
-                        sender_name,
-                    })
+                let messages_with_names_futures = messages.into_iter().map(|message| {
+                    let attachments = attachments_by_message.remove(&message.message_id).unwrap_or_default();
+                    async { // Changed variable name for clarity
+                        let sender_name = Self::get_sender_name(state, &message).await?;
+                        Ok(MessageWithSenderName {
+                            message,
+                            sender_name,
+                            attachments,
+                        })
+                    }
                 });
 
-                let results: Vec<Result<MessageWithSenderName, String>> = join_all(messages_with_names_futures).await;
-                let messages_with_names: Result<Vec<MessageWithSenderName>, String> = results.into_iter().collect(); // Convert Vec<Result<...>> to Result<Vec<...>>
+                let results: Vec<Result<MessageWithSenderName, AppError>> = join_all(messages_with_names_futures).await;
+                let messages_with_names: Result<Vec<MessageWithSenderName>, AppError> = results.into_iter().collect(); // Convert Vec<Result<...>> to Result<Vec<...>>
 
 
-                match messages_with_names { // Handle the Result<Vec<MessageWithSenderName>, String>
+                match messages_with_names { // Handle the Result<Vec<MessageWithSenderName>, AppError>
                     Ok(messages_with_names) => {
                         // println!("Messages with sender names for chat {} (count: {}):", chat_id, messages_with_names.len());
                         // for (index, msg_with_name) in messages_with_names.iter().enumerate() {
@@ -252,26 +420,52 @@ impl ChatHandler {
                         //     );
                         // }
 
-                        cache_set(&state.redis_pool, &cache_key, &messages_with_names, 30).await;
                         Ok(ApiResponse::success(messages_with_names))
                     },
                     Err(err) => Err(err), // Propagate error from get_sender_name calls
                 }
             } 
             Err(err) => {
-                Err(format!("Database error fetching messages: {}", err))
+                Err(AppError::Database(format!("Database error fetching messages: {}", err)))
             }
         }
     }
 
+    // Helper function to load attachments for a page of messages in one query
+    async fn get_attachments_by_message(
+        state: &AppState,
+        messages: &[message::Model],
+    ) -> Result<HashMap<String, Vec<message_attachment::Model>>, AppError> {
+        let message_ids: Vec<String> = messages.iter().map(|m| m.message_id.clone()).collect();
+        if message_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let attachments = message_attachment::Entity::find()
+            .filter(message_attachment::Column::MessageId.is_in(message_ids))
+            .order_by_asc(message_attachment::Column::CreatedAt)
+            .all(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching message attachments: {}", err)))?;
+
+        let mut attachments_by_message: HashMap<String, Vec<message_attachment::Model>> = HashMap::new();
+        for attachment in attachments {
+            attachments_by_message.entry(attachment.message_id.clone()).or_default().push(attachment);
+        }
+        Ok(attachments_by_message)
+    }
+
     // Helper function to get sender name (Customer or Staff)
-    async fn get_sender_name(state: &AppState, message: &message::Model) -> Result<String, String> {
+    async fn get_sender_name(state: &AppState, message: &message::Model) -> Result<String, AppError> {
+        if message.sender_id == SYSTEM_SENDER_ID {
+            return Ok(SYSTEM_SENDER_NAME.to_string());
+        }
         // Try to fetch as Customer first
-        if let Some(customer) = message.find_related(customer::Entity).one(&state.db).await.map_err(|e| format!("DB Error: {}", e))? {
+        if let Some(customer) = message.find_related(customer::Entity).one(&state.db).await.map_err(|e| AppError::Database(format!("DB Error: {}", e)))? {
             return Ok(customer.name);
         }
         // If not Customer, try to fetch as Staff
-        if let Some(staff) = message.find_related(staff::Entity).one(&state.db).await.map_err(|e| format!("DB Error: {}", e))? {
+        if let Some(staff) = message.find_related(staff::Entity).one(&state.db).await.map_err(|e| AppError::Database(format!("DB Error: {}", e)))? {
             return Ok(staff.name);
         }
         Ok("Unknown Sender".to_string()) // Default name if not found in either table
@@ -283,15 +477,22 @@ impl ChatHandler {
         chat_id: String,
         sender_id: String,
         text: String,
-    ) -> Result<ApiResponse<String>, String> {
+        attachments: Option<Vec<NewAttachment>>,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let attachments = attachments.unwrap_or_default();
+        if text.trim().is_empty() && attachments.is_empty() {
+            return Err(AppError::validation("Message must have text or at least one attachment".to_string()));
+        }
+        for attachment in &attachments {
+            Self::validate_attachment(attachment)?;
+        }
+
         let message_id = Uuid::new_v4().to_string();
 
-        let jakarta_time = Utc::now()
-            .with_timezone(&FixedOffset::east_opt(7 * 3600).unwrap())
-            .naive_local();
+        let jakarta_time = clock::jakarta_now();
 
         let new_message = message::ActiveModel {
-            message_id: sea_orm::ActiveValue::Set(message_id),
+            message_id: sea_orm::ActiveValue::Set(message_id.clone()),
             chat_id: sea_orm::ActiveValue::Set(chat_id.clone()),
             sender_id: sea_orm::ActiveValue::Set(sender_id.clone()),
             text: sea_orm::ActiveValue::Set(text.clone()),
@@ -299,32 +500,300 @@ impl ChatHandler {
             ..Default::default()
         };
 
-        match message::Entity::insert(new_message).exec(&state.db).await {
+        // Upload the files first so a storage failure leaves no half-sent message behind
+        let (new_attachments, stored_keys) = Self::store_attachments(state, &message_id, attachments, jakarta_time).await?;
+
+        let insert_result = async {
+            let txn = state.db.begin().await?;
+            message::Entity::insert(new_message).exec(&txn).await?;
+            if !new_attachments.is_empty() {
+                message_attachment::Entity::insert_many(new_attachments.clone()).exec(&txn).await?;
+            }
+            txn.commit().await
+        }.await;
+
+        match insert_result {
             Ok(_) => {
                 // Update last message info in chat table
-                Self::update_last_message_info(state, chat_id.clone(), text, jakarta_time).await?;
-                // Invalidate message cache
-                cache_delete(&state.redis_pool, &format!("get_messages_chat_{}", chat_id)).await;
-                // Invalidate cache for chats for sender
-                cache_delete(&state.redis_pool, &format!("view_chats_user_{}", sender_id)).await;
+                let preview = Self::message_preview(&text, !new_attachments.is_empty());
+                Self::update_last_message_info(state, chat_id.clone(), preview, jakarta_time).await?;
+                // Open, re-open or answer the Customer Service ticket for this chat (automated notices do neither)
+                if sender_id != SYSTEM_SENDER_ID {
+                    CustomerServiceTicketHandler::record_message(state, &chat_id, &sender_id, jakarta_time).await?;
+                }
                 Ok(ApiResponse::success("Message sent successfully".to_string()))
             }
-            Err(err) => Err(format!("Error sending message: {}", err)),
+            Err(err) => {
+                for storage_key in stored_keys {
+                    if let Err(cleanup_err) = state.storage.delete(&storage_key).await {
+                        eprintln!("Failed to clean up attachment {}: {}", storage_key, cleanup_err);
+                    }
+                }
+                Err(AppError::Database(format!("Error sending message: {}", err)))
+            }
+        }
+    }
+
+    // Only images are accepted, given either as a hosted url or as raw bytes
+    fn validate_attachment(attachment: &NewAttachment) -> Result<(), AppError> {
+        if !attachment.content_type.starts_with("image/") {
+            return Err(AppError::validation(format!("Attachment '{}' must be an image", attachment.file_name)));
+        }
+        match (&attachment.url, &attachment.data) {
+            (Some(url), None) if url.starts_with("https://") || url.starts_with("http://") => Ok(()),
+            (Some(_), None) => Err(AppError::validation(format!("Attachment '{}' has an invalid url", attachment.file_name))),
+            (None, Some(data)) if data.is_empty() => Err(AppError::validation(format!("Attachment '{}' is empty", attachment.file_name))),
+            (None, Some(data)) if data.len() > MAX_ATTACHMENT_BYTES => {
+                Err(AppError::validation(format!("Attachment '{}' is larger than {} MB", attachment.file_name, MAX_ATTACHMENT_BYTES / (1024 * 1024))))
+            }
+            (None, Some(_)) => Ok(()),
+            _ => Err(AppError::validation(format!("Attachment '{}' must have either a url or data", attachment.file_name))),
+        }
+    }
+
+    // Push raw attachment bytes to the storage backend and build the rows to insert,
+    // returning the storage keys written so they can be cleaned up on failure
+    async fn store_attachments(
+        state: &AppState,
+        message_id: &str,
+        attachments: Vec<NewAttachment>,
+        created_at: NaiveDateTime,
+    ) -> Result<(Vec<message_attachment::ActiveModel>, Vec<String>), AppError> {
+        let mut new_attachments = Vec::with_capacity(attachments.len());
+        let mut stored_keys: Vec<String> = Vec::new();
+
+        for attachment in attachments {
+            let (storage_key, size_bytes) = match &attachment.data {
+                Some(data) => match state.storage.store(&attachment.file_name, data).await {
+                    Ok(stored_file) => {
+                        stored_keys.push(stored_file.storage_key.clone());
+                        (Some(stored_file.storage_key), Some(stored_file.size_bytes))
+                    }
+                    Err(err) => {
+                        for storage_key in stored_keys {
+                            let _ = state.storage.delete(&storage_key).await;
+                        }
+                        return Err(AppError::Internal(err));
+                    }
+                },
+                None => (None, None),
+            };
+
+            new_attachments.push(message_attachment::ActiveModel {
+                attachment_id: Set(Uuid::new_v4().to_string()),
+                message_id: Set(message_id.to_string()),
+                file_name: Set(attachment.file_name),
+                content_type: Set(attachment.content_type),
+                url: Set(attachment.url),
+                storage_key: Set(storage_key),
+                size_bytes: Set(size_bytes),
+                created_at: Set(created_at),
+            });
+        }
+        Ok((new_attachments, stored_keys))
+    }
+
+    // Text shown in the chat list for a message
+    fn message_preview(text: &str, has_attachments: bool) -> String {
+        if text.trim().is_empty() && has_attachments {
+            ATTACHMENT_PREVIEW_TEXT.to_string()
+        } else {
+            text.to_string()
+        }
+    }
+
+    // Recompute the chat's last message from the newest message that is still visible
+    async fn refresh_last_message_info(state: &AppState, chat_id: &str) -> Result<(), AppError> {
+        let latest_message = message::Entity::find()
+            .filter(message::Column::ChatId.eq(chat_id))
+            .filter(message::Column::DeletedAt.is_null())
+            .order_by_desc(message::Column::Timestamp)
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching latest message: {}", err)))?;
+
+        let chat_record = chat::Entity::find_by_id(chat_id.to_string()).one(&state.db).await
+            .map_err(|err| AppError::Database(format!("Error fetching chat for last message update: {}", err)))?
+            .ok_or_else(|| AppError::NotFound("Chat not found for last message update".to_string()))?;
+
+        let mut active_chat: chat::ActiveModel = chat_record.into();
+        match latest_message {
+            Some(latest_message) => {
+                let has_attachments = message_attachment::Entity::find()
+                    .filter(message_attachment::Column::MessageId.eq(latest_message.message_id.clone()))
+                    .one(&state.db)
+                    .await
+                    .map_err(|err| AppError::Database(format!("Error fetching latest message attachments: {}", err)))?
+                    .is_some();
+                active_chat.last_message_text = Set(Some(Self::message_preview(&latest_message.text, has_attachments)));
+                active_chat.last_message_timestamp = Set(Some(latest_message.timestamp));
+            }
+            None => {
+                active_chat.last_message_text = Set(None);
+                active_chat.last_message_timestamp = Set(None);
+            }
+        }
+
+        active_chat.update(&state.db).await
+            .map_err(|err| AppError::Database(format!("Error updating chat last message info: {}", err)))?;
+        // The preview shows in every member's chat list
+        state.cache.invalidate(&[TAG_CHAT]).await;
+        Ok(())
+    }
+
+    // Fetch a message that the given user sent and that has not been deleted
+    async fn find_own_message(state: &AppState, message_id: &str, user_id: &str) -> Result<message::Model, AppError> {
+        let message_record = message::Entity::find_by_id(message_id.to_string())
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching message: {}", err)))?
+            .ok_or_else(|| AppError::NotFound("Message not found".to_string()))?;
+
+        if message_record.deleted_at.is_some() {
+            return Err(AppError::Conflict("Message has been deleted".to_string()));
+        }
+        if message_record.sender_id != user_id {
+            return Err(AppError::Forbidden("Only the sender can change this message".to_string()));
+        }
+        Ok(message_record)
+    }
+
+    // Edit a message's text, keeping the previous text in the edit history
+    pub async fn edit_message(
+        state: &AppState,
+        message_id: String,
+        editor_id: String,
+        text: String,
+    ) -> Result<ApiResponse<message::Model>, AppError> {
+        let message_record = Self::find_own_message(state, &message_id, &editor_id).await?;
+        if message_record.text == text {
+            return Ok(ApiResponse::success(message_record)); // Nothing changed
+        }
+
+        if text.trim().is_empty() {
+            let has_attachments = message_attachment::Entity::find()
+                .filter(message_attachment::Column::MessageId.eq(message_id.clone()))
+                .one(&state.db)
+                .await
+                .map_err(|err| AppError::Database(format!("Database error fetching message attachments: {}", err)))?
+                .is_some();
+            if !has_attachments {
+                return Err(AppError::invalid_field("text", "Message text cannot be empty"));
+            }
         }
+
+        let jakarta_time = clock::jakarta_now();
+        let chat_id = message_record.chat_id.clone();
+
+        let history_entry = message_edit::ActiveModel {
+            message_edit_id: Set(Uuid::new_v4().to_string()),
+            message_id: Set(message_id.clone()),
+            previous_text: Set(message_record.text.clone()),
+            edited_by: Set(editor_id.clone()),
+            edited_at: Set(jakarta_time),
+        };
+        let before = AuditEventHandler::snapshot(&message_record);
+        let mut active_message: message::ActiveModel = message_record.into();
+        active_message.text = Set(text);
+        active_message.edited_at = Set(Some(jakarta_time));
+
+        let txn = state.db.begin().await
+            .map_err(|err| AppError::Database(format!("Error starting message edit: {}", err)))?;
+        message_edit::Entity::insert(history_entry)
+            .exec(&txn)
+            .await
+            .map_err(|err| AppError::Database(format!("Error saving message edit history: {}", err)))?;
+        let updated_message = active_message.update(&txn)
+            .await
+            .map_err(|err| AppError::Database(format!("Error editing message: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&editor_id), ACTION_UPDATE, "message", &message_id, before, AuditEventHandler::snapshot(&updated_message)).await?;
+        txn.commit().await
+            .map_err(|err| AppError::Database(format!("Error committing message edit: {}", err)))?;
+
+        Self::refresh_last_message_info(state, &chat_id).await?;
+        Ok(ApiResponse::success(updated_message))
+    }
+
+    // Soft delete a message; it disappears from the chat but keeps its history
+    pub async fn delete_message(
+        state: &AppState,
+        message_id: String,
+        requester_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let message_record = Self::find_own_message(state, &message_id, &requester_id).await?;
+        let chat_id = message_record.chat_id.clone();
+
+        let jakarta_time = clock::jakarta_now();
+
+        let before = AuditEventHandler::snapshot(&message_record);
+        let mut active_message: message::ActiveModel = message_record.into();
+        active_message.deleted_at = Set(Some(jakarta_time));
+
+        let txn = state.db.begin().await
+            .map_err(|err| AppError::Database(format!("Error starting message delete: {}", err)))?;
+        let deleted_message = active_message.update(&txn)
+            .await
+            .map_err(|err| AppError::Database(format!("Error deleting message: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&requester_id), ACTION_DELETE, "message", &message_id, before, AuditEventHandler::snapshot(&deleted_message)).await?;
+        txn.commit().await
+            .map_err(|err| AppError::Database(format!("Error committing message delete: {}", err)))?;
+
+        Self::refresh_last_message_info(state, &chat_id).await?;
+        Ok(ApiResponse::success("Message deleted successfully".to_string()))
+    }
+
+    // Previous versions of a message, oldest first
+    pub async fn get_message_edit_history(
+        state: &AppState,
+        message_id: String,
+    ) -> Result<ApiResponse<Vec<message_edit::Model>>, AppError> {
+        match message_edit::Entity::find()
+            .filter(message_edit::Column::MessageId.eq(message_id.clone()))
+            .order_by_asc(message_edit::Column::EditedAt)
+            .all(&state.db)
+            .await
+        {
+            Ok(edits) => Ok(ApiResponse::success(edits)),
+            Err(err) => Err(AppError::Database(format!("Error fetching edit history for message {}: {}", message_id, err))),
+        }
+    }
+
+    // Raw bytes of an attachment kept in the storage backend
+    pub async fn get_attachment_data(
+        state: &AppState,
+        attachment_id: String,
+    ) -> Result<ApiResponse<Vec<u8>>, AppError> {
+        let (attachment, parent_message) = message_attachment::Entity::find_by_id(attachment_id)
+            .find_also_related(message::Entity)
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching attachment: {}", err)))?
+            .ok_or_else(|| AppError::NotFound("Attachment not found".to_string()))?;
+
+        if parent_message.is_none_or(|m| m.deleted_at.is_some()) {
+            return Err(AppError::NotFound("Attachment not found".to_string()));
+        }
+
+        let storage_key = attachment.storage_key
+            .ok_or_else(|| AppError::Conflict("Attachment is hosted externally, use its url".to_string()))?;
+        let data = state.storage.read(&storage_key).await.map_err(AppError::Internal)?;
+        Ok(ApiResponse::success(data))
     }
 
     // Helper function to update last message info in chat table
-    async fn update_last_message_info(state: &AppState, chat_id: String, last_message_text: String, jakarta_time: NaiveDateTime) -> Result<(), String> {
+    async fn update_last_message_info(state: &AppState, chat_id: String, last_message_text: String, jakarta_time: NaiveDateTime) -> Result<(), AppError> {
         let chat_record = chat::Entity::find_by_id(chat_id.clone()).one(&state.db).await
-            .map_err(|err| format!("Error fetching chat for last message update: {}", err))?
-            .ok_or_else(|| "Chat not found for last message update".to_string())?;
+            .map_err(|err| AppError::Database(format!("Error fetching chat for last message update: {}", err)))?
+            .ok_or_else(|| AppError::NotFound("Chat not found for last message update".to_string()))?;
 
         let mut active_chat: chat::ActiveModel = chat_record.into();
         active_chat.last_message_text = Set(Some(last_message_text));
         active_chat.last_message_timestamp = Set(Some(jakarta_time));
 
         active_chat.update(&state.db).await
-            .map_err(|err| format!("Error updating chat last message info: {}", err))?;
+            .map_err(|err| AppError::Database(format!("Error updating chat last message info: {}", err)))?;
+        // The preview shows in every member's chat list
+        state.cache.invalidate(&[TAG_CHAT]).await;
         Ok(())
     }
 
@@ -333,22 +802,21 @@ impl ChatHandler {
         state: &AppState,
         chat_id: String,
         user_id: String,
-    ) -> Result<ApiResponse<String>, String> {
-        let chat_member_id = Uuid::new_v4().to_string();
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new()
+            .field("chat_id", &chat_id, &[validation::required])
+            .field("user_id", &user_id, &[validation::required])
+            .finish()?;
 
-        let new_chat_member = chat_member::ActiveModel {
-            chat_member_id: sea_orm::ActiveValue::Set(chat_member_id),
-            chat_id: sea_orm::ActiveValue::Set(chat_id),
-            user_id: sea_orm::ActiveValue::Set(user_id.clone()),
-            ..Default::default()
-        };
+        let participant_type = Self::participant_type_for(state, &user_id).await?;
+        let new_chat_member = Self::new_chat_member(&chat_id, &user_id, participant_type);
 
         match chat_member::Entity::insert(new_chat_member).exec(&state.db).await {
             Ok(_) => {
-                cache_delete(&state.redis_pool, &format!("view_chats_user_{}", user_id)).await; // Invalidate user's chat list cache
+                state.cache.invalidate(&[&scoped_tag(TAG_CHAT_MEMBER, &user_id)]).await;
                 Ok(ApiResponse::success("Chat member added successfully".to_string()))
             }
-            Err(err) => Err(format!("Error adding chat member: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error adding chat member: {}", err))),
         }
     }
 
@@ -356,14 +824,518 @@ impl ChatHandler {
     pub async fn get_chat_members(
         state: &AppState,
         chat_id: String,
-    ) -> Result<ApiResponse<Vec<chat_member::Model>>, String> {
+    ) -> Result<ApiResponse<Vec<chat_member::Model>>, AppError> {
         match chat_member::Entity::find()
             .filter(chat_member::Column::ChatId.eq(chat_id.clone()))
             .all(&state.db)
             .await
         {
             Ok(chat_members) => Ok(ApiResponse::success(chat_members)),
-            Err(err) => Err(format!("Error fetching chat members for chat {}: {}", chat_id, err)),
+            Err(err) => Err(AppError::Database(format!("Error fetching chat members for chat {}: {}", chat_id, err))),
+        }
+    }
+
+    // Get the group chat for a division, creating it on first use
+    pub async fn ensure_division_group_chat(state: &AppState, division: &str) -> Result<chat::Model, AppError> {
+        let existing_chat = chat::Entity::find()
+            .filter(chat::Column::ChatType.eq(chat::CHAT_TYPE_DIVISION_GROUP))
+            .filter(chat::Column::Division.eq(division))
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching division group chat: {}", err)))?;
+
+        if let Some(existing_chat) = existing_chat {
+            return Ok(existing_chat);
+        }
+
+        let new_chat = chat::ActiveModel {
+            chat_id: Set(Uuid::new_v4().to_string()),
+            name: Set(format!("{} Division", division)),
+            chat_type: Set(chat::CHAT_TYPE_DIVISION_GROUP.to_string()),
+            division: Set(Some(division.to_string())),
+            ..Default::default()
+        };
+
+        chat::Entity::insert(new_chat)
+            .exec_with_returning(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error creating division group chat: {}", err)))
+    }
+
+    // Keep a staff member in exactly the group chat of their role's division
+    pub async fn sync_staff_division_membership(state: &AppState, staff_id: &str, role: &str) -> Result<(), AppError> {
+        let target_division = StaffHandler::division_for_role(role);
+
+        let memberships = chat_member::Entity::find()
+            .find_also_related(chat::Entity)
+            .filter(chat_member::Column::StaffId.eq(staff_id))
+            .filter(chat::Column::ChatType.eq(chat::CHAT_TYPE_DIVISION_GROUP))
+            .filter(chat::Column::Division.is_not_null())
+            .all(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching division memberships: {}", err)))?;
+
+        let mut already_member = false;
+        for (membership, member_chat) in memberships {
+            let member_division = member_chat.and_then(|c| c.division);
+            if target_division.is_some() && member_division.as_deref() == target_division {
+                already_member = true;
+                continue;
+            }
+            chat_member::Entity::delete_by_id(membership.chat_member_id)
+                .exec(&state.db)
+                .await
+                .map_err(|err| AppError::Database(format!("Error removing division membership: {}", err)))?;
+        }
+
+        if let (Some(division), false) = (target_division, already_member) {
+            let division_chat = Self::ensure_division_group_chat(state, division).await?;
+            let new_chat_member = Self::new_chat_member(&division_chat.chat_id, staff_id, chat_member::PARTICIPANT_STAFF);
+            chat_member::Entity::insert(new_chat_member)
+                .exec(&state.db)
+                .await
+                .map_err(|err| AppError::Database(format!("Error adding division membership: {}", err)))?;
+        }
+
+        state.cache.invalidate(&[&scoped_tag(TAG_CHAT_MEMBER, staff_id)]).await;
+        Ok(())
+    }
+
+    // Create every division group chat and bring all staff memberships up to date
+    pub async fn sync_division_group_chats(state: &AppState) -> Result<(), AppError> {
+        for division in DIVISIONS {
+            Self::ensure_division_group_chat(state, division).await?;
+        }
+
+        let staff_members = staff::Entity::find()
+            .filter(staff::Column::DeletedAt.is_null())
+            .all(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching staff for division sync: {}", err)))?;
+
+        for staff_member in staff_members {
+            Self::sync_staff_division_membership(state, &staff_member.staff_id, &staff_member.role).await?;
+        }
+        Ok(())
+    }
+
+    // Full-text search over messages in the chats a user belongs to. Customer Service staff
+    // also search every Customer Service chat, since they answer those without being members.
+    pub async fn search_messages(
+        state: &AppState,
+        user_id: String,
+        query: String,
+        limit: Option<u64>,
+    ) -> Result<ApiResponse<Vec<MessageSearchResult>>, AppError> {
+        let query = query.trim().to_string();
+        if query.is_empty() {
+            return Err(AppError::invalid_field("query", "Search query cannot be empty"));
+        }
+        let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, MAX_SEARCH_LIMIT);
+
+        let includes_customer_service = staff::Entity::find_by_id(user_id.clone())
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching staff: {}", err)))?
+            .is_some_and(|s| CUSTOMER_SERVICE_ROLES.contains(&s.role.as_str()));
+
+        let hits = if state.db.get_database_backend() == DbBackend::Postgres {
+            Self::full_text_hits(state, &query, &user_id, includes_customer_service, limit).await?
+        } else {
+            Self::substring_hits(state, &query, &user_id, includes_customer_service, limit).await?
+        };
+
+        // The matched messages and their chats in one query
+        let hit_ids: Vec<String> = hits.iter().map(|hit| hit.message_id.clone()).collect();
+        let mut found: HashMap<String, (message::Model, chat::Model)> = message::Entity::find()
+            .filter(message::Column::MessageId.is_in(hit_ids))
+            .find_also_related(chat::Entity)
+            .all(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching search results: {}", err)))?
+            .into_iter()
+            .filter_map(|(found_message, found_chat)| Some((found_message.message_id.clone(), (found_message, found_chat?))))
+            .collect();
+
+        let mut found_hits = Vec::with_capacity(hits.len());
+        for hit in hits {
+            let (found_message, found_chat) = found
+                .remove(&hit.message_id)
+                .ok_or_else(|| AppError::NotFound("Message not found for search result".to_string()))?;
+
+            let context_before = message::Entity::find()
+                .filter(message::Column::ChatId.eq(found_chat.chat_id.clone()))
+                .filter(message::Column::DeletedAt.is_null())
+                .filter(message::Column::Timestamp.lt(found_message.timestamp))
+                .order_by_desc(message::Column::Timestamp)
+                .limit(SEARCH_CONTEXT_MESSAGES)
+                .all(&state.db)
+                .await
+                .map_err(|err| AppError::Database(format!("Database error fetching search context: {}", err)))?
+                .into_iter()
+                .rev()
+                .collect::<Vec<_>>();
+            let context_after = message::Entity::find()
+                .filter(message::Column::ChatId.eq(found_chat.chat_id.clone()))
+                .filter(message::Column::DeletedAt.is_null())
+                .filter(message::Column::Timestamp.gt(found_message.timestamp))
+                .order_by_asc(message::Column::Timestamp)
+                .limit(SEARCH_CONTEXT_MESSAGES)
+                .all(&state.db)
+                .await
+                .map_err(|err| AppError::Database(format!("Database error fetching search context: {}", err)))?;
+            found_hits.push((hit, found_chat, context_before, found_message, context_after));
+        }
+
+        // Sender names and attachments for every hit and its context at once, handed back in order
+        let all_messages = found_hits
+            .iter()
+            .flat_map(|(_, _, before, found_message, after)| before.iter().chain([found_message]).chain(after.iter()).cloned())
+            .collect();
+        let mut named = Self::with_sender_names(state, all_messages).await?.into_iter();
+        let mut results = Vec::with_capacity(found_hits.len());
+        for (hit, found_chat, before, _, after) in found_hits {
+            let context_before = named.by_ref().take(before.len()).collect();
+            let Some(named_message) = named.next() else {
+                return Err(AppError::Internal("Search result lost its message".to_string()));
+            };
+            results.push(MessageSearchResult {
+                message: named_message,
+                chat: found_chat,
+                highlight: Self::split_highlight(&hit.headline),
+                rank: hit.rank,
+                context_before,
+                context_after: named.by_ref().take(after.len()).collect(),
+            });
+        }
+
+        Ok(ApiResponse::success(results))
+    }
+
+    async fn full_text_hits(
+        state: &AppState,
+        query: &str,
+        user_id: &str,
+        includes_customer_service: bool,
+        limit: u64,
+    ) -> Result<Vec<SearchHit>, AppError> {
+        let headline_options = format!(
+            "StartSel={}, StopSel={}, MaxWords=35, MinWords=15, MaxFragments=2, FragmentDelimiter=\" ... \"",
+            HIGHLIGHT_START, HIGHLIGHT_STOP
+        );
+
+        // to_tsvector('simple', text) matches the GIN index on message.text
+        SearchHit::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"SELECT m.message_id,
+                      ts_headline('simple', m.text, q.query, $2) AS headline,
+                      ts_rank(to_tsvector('simple', m.text), q.query) AS rank
+               FROM message m
+               CROSS JOIN websearch_to_tsquery('simple', $1) AS q(query)
+               WHERE to_tsvector('simple', m.text) @@ q.query
+                 AND m.deleted_at IS NULL
+                 AND (m.chat_id IN (SELECT cm.chat_id FROM chat_member cm WHERE cm.user_id = $3)
+                      OR ($4 AND m.chat_id IN (SELECT c.chat_id FROM chat c WHERE c.chat_type = $5)))
+               ORDER BY rank DESC, m.timestamp DESC
+               LIMIT $6"#,
+            [
+                query.into(),
+                headline_options.into(),
+                user_id.into(),
+                includes_customer_service.into(),
+                chat::CHAT_TYPE_CUSTOMER_SERVICE.into(),
+                (limit as i64).into(),
+            ],
+        ))
+        .all(&state.db)
+        .await
+        .map_err(|err| AppError::Database(format!("Error searching messages: {}", err)))
+    }
+
+    // SQLite has no full-text search: every word must appear in the message, newest first, with
+    // the whole text as the headline
+    async fn substring_hits(
+        state: &AppState,
+        query: &str,
+        user_id: &str,
+        includes_customer_service: bool,
+        limit: u64,
+    ) -> Result<Vec<SearchHit>, AppError> {
+        let words: Vec<&str> = query.split_whitespace().collect();
+
+        let mut visible_chats = Condition::any().add(
+            message::Column::ChatId.in_subquery(
+                chat_member::Entity::find()
+                    .select_only()
+                    .column(chat_member::Column::ChatId)
+                    .filter(chat_member::Column::UserId.eq(user_id))
+                    .into_query(),
+            ),
+        );
+        if includes_customer_service {
+            visible_chats = visible_chats.add(
+                message::Column::ChatId.in_subquery(
+                    chat::Entity::find()
+                        .select_only()
+                        .column(chat::Column::ChatId)
+                        .filter(chat::Column::ChatType.eq(chat::CHAT_TYPE_CUSTOMER_SERVICE))
+                        .into_query(),
+                ),
+            );
+        }
+
+        let mut select = message::Entity::find()
+            .filter(message::Column::DeletedAt.is_null())
+            .filter(visible_chats);
+        for word in &words {
+            select = select.filter(message::Column::Text.contains(*word));
+        }
+        let found_messages = select
+            .order_by_desc(message::Column::Timestamp)
+            .limit(limit)
+            .all(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error searching messages: {}", err)))?;
+
+        Ok(found_messages
+            .into_iter()
+            .map(|found| SearchHit {
+                headline: Self::mark_words(&found.text, &words),
+                rank: 1.0,
+                message_id: found.message_id,
+            })
+            .collect())
+    }
+
+    // Wrap every case-insensitive occurrence of the words in the highlight markers
+    fn mark_words(text: &str, words: &[&str]) -> String {
+        let lowered = text.to_lowercase();
+        // Lowercasing can change byte lengths outside ASCII; leave such text unmarked
+        if lowered.len() != text.len() {
+            return text.to_string();
+        }
+        let mut matched = vec![false; text.len()];
+        for word in words {
+            let word = word.to_lowercase();
+            for (start, _) in lowered.match_indices(&word) {
+                matched[start..start + word.len()].iter_mut().for_each(|byte| *byte = true);
+            }
+        }
+
+        let mut headline = String::with_capacity(text.len());
+        let mut in_match = false;
+        for (index, character) in text.char_indices() {
+            if matched[index] != in_match {
+                in_match = matched[index];
+                headline.push_str(if in_match { HIGHLIGHT_START } else { HIGHLIGHT_STOP });
+            }
+            headline.push(character);
+        }
+        if in_match {
+            headline.push_str(HIGHLIGHT_STOP);
+        }
+        headline
+    }
+
+    // Helper function to attach sender names and attachments to messages
+    async fn with_sender_names(
+        state: &AppState,
+        messages: Vec<message::Model>,
+    ) -> Result<Vec<MessageWithSenderName>, AppError> {
+        let mut attachments_by_message = Self::get_attachments_by_message(state, &messages).await?;
+        let sender_names = Self::sender_names(state, &messages).await?;
+        let messages_with_names = messages
+            .into_iter()
+            .map(|message| {
+                let sender_name = match message.sender_id.as_str() {
+                    SYSTEM_SENDER_ID => SYSTEM_SENDER_NAME.to_string(),
+                    sender_id => sender_names.get(sender_id).cloned().unwrap_or_else(|| "Unknown Sender".to_string()),
+                };
+                let attachments = attachments_by_message.remove(&message.message_id).unwrap_or_default();
+                MessageWithSenderName { message, sender_name, attachments }
+            })
+            .collect();
+        Ok(messages_with_names)
+    }
+
+    // Names of the senders of `messages`, one query per table; a customer wins over a staff member
+    // with the same id, as in get_sender_name
+    async fn sender_names(state: &AppState, messages: &[message::Model]) -> Result<HashMap<String, String>, AppError> {
+        let mut sender_ids: Vec<String> = messages.iter().map(|message| message.sender_id.clone()).collect();
+        sender_ids.sort();
+        sender_ids.dedup();
+        if sender_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let staff_members = staff::Entity::find()
+            .filter(staff::Column::StaffId.is_in(sender_ids.clone()))
+            .all(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching senders: {}", err)))?;
+        let customers = customer::Entity::find()
+            .filter(customer::Column::CustomerId.is_in(sender_ids))
+            .all(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching senders: {}", err)))?;
+        let mut names: HashMap<String, String> = staff_members.into_iter().map(|staff_member| (staff_member.staff_id, staff_member.name)).collect();
+        names.extend(customers.into_iter().map(|customer| (customer.customer_id, customer.name)));
+        Ok(names)
+    }
+
+    // Turn a marked-up ts_headline string into plain/highlighted segments
+    fn split_highlight(headline: &str) -> Vec<HighlightSegment> {
+        let mut segments = Vec::new();
+        for (index, part) in headline.split(HIGHLIGHT_START).enumerate() {
+            // Every part after the first starts with a highlighted run
+            let (matched_text, rest) = match (index, part.split_once(HIGHLIGHT_STOP)) {
+                (0, _) | (_, None) => ("", part),
+                (_, Some((matched_text, rest))) => (matched_text, rest),
+            };
+            if !matched_text.is_empty() {
+                segments.push(HighlightSegment { text: matched_text.to_string(), matched: true });
+            }
+            if !rest.is_empty() {
+                segments.push(HighlightSegment { text: rest.to_string(), matched: false });
+            }
         }
+        segments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::test_support::{data, error, seed_customer, seed_staff, test_state};
+
+    #[tokio::test]
+    async fn search_finds_words_in_the_users_chats_only() {
+        let state = test_state().await;
+        let sender = seed_staff(&state, "RideManager").await;
+        let receiver = seed_staff(&state, "RideStaff").await;
+        let outsider = seed_staff(&state, "RideStaff").await;
+        let direct_chat = data(ChatHandler::get_direct_chat(&state, sender.staff_id.clone(), receiver.staff_id.clone()).await);
+        for text in ["The Coaster brakes need checking", "Lunch at noon?"] {
+            data(ChatHandler::save_message_data(&state, direct_chat.chat_id.clone(), sender.staff_id.clone(), text.to_string(), None).await);
+        }
+
+        let results = data(ChatHandler::search_messages(&state, receiver.staff_id.clone(), "coaster brakes".to_string(), None).await);
+        assert_eq!(results.len(), 1);
+        let matched: Vec<&str> = results[0]
+            .highlight
+            .iter()
+            .filter(|segment| segment.matched)
+            .map(|segment| segment.text.as_str())
+            .collect();
+        assert_eq!(matched, ["Coaster", "brakes"]);
+        // Named along with the messages around it
+        assert_eq!(results[0].message.sender_name, sender.name);
+        let after: Vec<(&str, &str)> = results[0]
+            .context_after
+            .iter()
+            .map(|context| (context.message.text.as_str(), context.sender_name.as_str()))
+            .collect();
+        assert_eq!(after, [("Lunch at noon?", sender.name.as_str())]);
+
+        let results = data(ChatHandler::search_messages(&state, outsider.staff_id, "coaster".to_string(), None).await);
+        assert!(results.is_empty());
+    }
+
+    #[tokio::test]
+    async fn message_edits_and_deletes_are_audited_as_their_author() {
+        let state = test_state().await;
+        let sender = seed_staff(&state, "RideManager").await;
+        let receiver = seed_staff(&state, "RideStaff").await;
+        let direct_chat = data(ChatHandler::get_direct_chat(&state, sender.staff_id.clone(), receiver.staff_id.clone()).await);
+        data(ChatHandler::save_message_data(&state, direct_chat.chat_id, sender.staff_id.clone(), "Brakes chekced".to_string(), None).await);
+        let sent = message::Entity::find().one(&state.db).await.unwrap().unwrap();
+
+        data(ChatHandler::edit_message(&state, sent.message_id.clone(), sender.staff_id.clone(), "Brakes checked".to_string()).await);
+        data(ChatHandler::delete_message(&state, sent.message_id.clone(), sender.staff_id.clone()).await);
+
+        let audit = entity::audit_event::Entity::find()
+            .filter(entity::audit_event::Column::EntityId.eq(sent.message_id))
+            .all(&state.db)
+            .await
+            .unwrap();
+        let mut trail: Vec<(&str, Option<&str>)> = audit.iter().map(|event| (event.action.as_str(), event.actor_id.as_deref())).collect();
+        trail.sort_unstable();
+        assert_eq!(trail, [(ACTION_DELETE, Some(sender.staff_id.as_str())), (ACTION_UPDATE, Some(sender.staff_id.as_str()))]);
     }
-}
\ No newline at end of file
+
+    fn chat_ids(chats: &[chat::Model]) -> Vec<&str> {
+        chats.iter().map(|chat| chat.chat_id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn a_users_chat_list_follows_new_chats_members_and_messages() {
+        let state = test_state().await;
+        let staff_member = seed_staff(&state, "RideManager").await;
+        let colleague = seed_staff(&state, "RideStaff").await;
+        assert!(data(ChatHandler::view_chats(&state, staff_member.staff_id.clone()).await).is_empty());
+
+        // A new group chat shows up once the staff member is added to it
+        data(ChatHandler::save_chat_data(&state, "Coaster crew".to_string(), None).await);
+        let group_chat = chat::Entity::find().one(&state.db).await.unwrap().unwrap();
+        data(ChatHandler::save_chat_member_data(&state, group_chat.chat_id.clone(), staff_member.staff_id.clone()).await);
+        let chats = data(ChatHandler::view_chats(&state, staff_member.staff_id.clone()).await);
+        assert_eq!(chat_ids(&chats), [group_chat.chat_id.as_str()]);
+
+        let direct_chat = data(ChatHandler::get_direct_chat(&state, colleague.staff_id.clone(), staff_member.staff_id.clone()).await);
+        let chats = data(ChatHandler::view_chats(&state, staff_member.staff_id.clone()).await);
+        assert!(chat_ids(&chats).contains(&direct_chat.chat_id.as_str()));
+
+        // The list's preview of the latest message is refreshed by each send
+        data(ChatHandler::save_message_data(&state, direct_chat.chat_id.clone(), colleague.staff_id, "Brakes checked".to_string(), None).await);
+        let chats = data(ChatHandler::view_chats(&state, staff_member.staff_id).await);
+        let preview = chats.iter().find(|chat| chat.chat_id == direct_chat.chat_id).unwrap();
+        assert_eq!(preview.last_message_text.as_deref(), Some("Brakes checked"));
+    }
+
+    #[tokio::test]
+    async fn staff_see_a_customers_new_customer_service_chat() {
+        let state = test_state().await;
+        let customer = seed_customer(&state, "0").await;
+        assert!(data(ChatHandler::get_customer_chats_for_staff(&state).await).is_empty());
+
+        let created = data(ChatHandler::get_customer_service_chat(&state, customer.customer_id).await);
+
+        let chats = data(ChatHandler::get_customer_chats_for_staff(&state).await);
+        assert_eq!(chats.len(), 1);
+        assert_eq!(chats[0].chat.chat_id, created.chat_id);
+        assert_eq!(chats[0].customer_name, customer.name);
+    }
+
+    #[tokio::test]
+    async fn a_pair_of_staff_share_one_direct_chat_whoever_starts_it() {
+        let state = test_state().await;
+        let first = seed_staff(&state, "RideManager").await;
+        let second = seed_staff(&state, "RideStaff").await;
+
+        let started = data(ChatHandler::get_direct_chat(&state, first.staff_id.clone(), second.staff_id.clone()).await);
+        let (again, reversed) = tokio::join!(
+            ChatHandler::get_direct_chat(&state, first.staff_id.clone(), second.staff_id.clone()),
+            ChatHandler::get_direct_chat(&state, second.staff_id.clone(), first.staff_id.clone()),
+        );
+        assert_eq!(data(again).chat_id, started.chat_id);
+        assert_eq!(data(reversed).chat_id, started.chat_id);
+
+        assert_eq!(chat::Entity::find().all(&state.db).await.unwrap().len(), 1);
+        let members = data(ChatHandler::get_chat_members(&state, started.chat_id.clone()).await);
+        assert_eq!(members.len(), 2);
+        assert_eq!(started.chat_type, chat::CHAT_TYPE_DIRECT);
+    }
+
+    #[tokio::test]
+    async fn direct_chats_need_two_different_existing_staff() {
+        let state = test_state().await;
+        let staff_member = seed_staff(&state, "RideStaff").await;
+
+        let err = error(ChatHandler::get_direct_chat(&state, staff_member.staff_id.clone(), staff_member.staff_id.clone()).await);
+        assert_eq!(err.code(), ErrorCode::ValidationFailed);
+        let err = error(ChatHandler::get_direct_chat(&state, staff_member.staff_id, "nobody".to_string()).await);
+        assert_eq!(err.code(), ErrorCode::NotFound);
+        assert!(chat::Entity::find().all(&state.db).await.unwrap().is_empty());
+    }
+}
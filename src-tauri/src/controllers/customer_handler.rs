@@ -1,54 +1,89 @@
-use sea_orm::{ActiveModelTrait, EntityTrait};
-use entity::customer::{self, ActiveModel, Model};
+use sea_orm::{sea_query::Expr, ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, TransactionTrait};
+use entity::audit_event::{ACTION_CREATE, ACTION_DELETE, ACTION_RESTORE, ACTION_UPDATE};
+use entity::customer::{self, Model};
+use entity::user_session::KIND_CUSTOMER;
 use uuid::Uuid;
+use crate::clock;
+use crate::cache::TAG_CUSTOMER;
+use crate::error::AppError;
 use crate::{ApiResponse, AppState};
+use crate::controllers::audit_event_handler::AuditEventHandler;
+use crate::controllers::dependency_handler::{DependencyHandler, DependencyReport};
+use crate::idempotency::Idempotency;
+use crate::login_guard::LoginGuard;
+use crate::session::Session;
+use crate::validation::{self, Validator};
+use bcrypt::{hash, verify, DEFAULT_COST};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+// Same message for every failure so a login cannot be used to probe which customer ids exist
+const INVALID_LOGIN: &str = "Invalid customer ID or PIN";
+// Tries at a balance change before giving up when other writes keep changing the balance first
+const BALANCE_UPDATE_ATTEMPTS: usize = 5;
 
 pub struct CustomerHandler;
 
 impl CustomerHandler {
+    // Verified against when the customer does not exist, so both cases take as long as a real check
+    fn dummy_pin_hash() -> &'static str {
+        static DUMMY: OnceLock<String> = OnceLock::new();
+        DUMMY.get_or_init(|| hash("000000", DEFAULT_COST).unwrap_or_default())
+    }
 
-    // Customer Login
-    pub async fn customer_login(state: &AppState, customer_id: String) -> Result<ApiResponse<String>, String> {
-        match customer::Entity::find_by_id(customer_id.clone()).one(&state.db).await {
-            Ok(Some(_customer)) => { // Customer found (we can use _customer if we need to return customer details later)
-                // For now, let's generate a simple session token (UUID)
-                let session_token = Uuid::new_v4().to_string();
-                // In a real application, you might want to store this session token
-                // in a database associated with the user, and handle session expiry, etc.
-                Ok(ApiResponse::success(session_token))
-            }
-            Ok(None) => {
-                Err("Invalid Customer UID".to_string()) // Customer not found
-            }
-            Err(err) => {
-                Err(format!("Database error during login: {}", err))
-            }
+    // Customer Login: the customer id alone is not a secret, so the PIN must match too
+    pub async fn customer_login(
+        state: &AppState,
+        customer_id: String,
+        pin: String,
+        device_id: Option<String>,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let subjects = LoginGuard::subjects(&customer_id, device_id.as_deref());
+        LoginGuard::check(state.cache.backend(), "customer", &subjects).await?;
+
+        let customer = customer::Entity::find_by_id(customer_id.clone())
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error during login: {}", err)))?
+            .filter(|customer| customer.deleted_at.is_none()); // Archived customers cannot log in
+
+        // Customers without a PIN yet cannot log in until customer service sets one
+        let stored_hash = customer.as_ref().and_then(|customer| customer.pin_hash.clone());
+        let pin_matches = verify(&pin, stored_hash.as_deref().unwrap_or(Self::dummy_pin_hash())).unwrap_or(false);
+        if !pin_matches || stored_hash.is_none() {
+            LoginGuard::record_failure(state.cache.backend(), "customer", &subjects).await;
+            return Err(AppError::Unauthorized(INVALID_LOGIN.to_string()));
         }
+
+        LoginGuard::record_success(state.cache.backend(), "customer", &subjects).await;
+        let session_token = Session::open(&state.db, &customer_id, KIND_CUSTOMER).await?;
+        Ok(ApiResponse::success(session_token))
     }
 
     // Get customer details by ID
     pub async fn get_customer_details(
         state: &AppState,
         customer_id: String,
-    ) -> Result<ApiResponse<Model>, String> { // Return ApiResponse with Customer Model
+    ) -> Result<ApiResponse<Model>, AppError> { // Return ApiResponse with Customer Model
         match customer::Entity::find_by_id(customer_id.clone()).one(&state.db).await {
             Ok(Some(customer_details)) => {
                 Ok(ApiResponse::success(customer_details)) // Return the customer model on success
             }
             Ok(None) => {
-                Err("Customer not found".to_string())
+                Err(AppError::NotFound("Customer not found".to_string()))
             }
             Err(err) => {
-                Err(format!("Database error fetching customer details: {}", err))
+                Err(AppError::Database(format!("Database error fetching customer details: {}", err)))
             }
         }
     }
 
     // View customer accounts
-    pub async fn view_customer_accounts(state: &AppState) -> Result<ApiResponse<Vec<Model>>, String> {
-        match customer::Entity::find().all(&state.db).await {
+    pub async fn view_customer_accounts(state: &AppState) -> Result<ApiResponse<Vec<Model>>, AppError> {
+        match customer::Entity::find().filter(customer::Column::DeletedAt.is_null()).all(&state.db).await {
             Ok(customers) => Ok(ApiResponse::success(customers)),
-            Err(err) => Err(format!("Error fetching customers: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error fetching customers: {}", err))),
         }
     }
 
@@ -57,7 +92,16 @@ impl CustomerHandler {
         state: &AppState,
         name: String,
         virtual_balance: String,
-    ) -> Result<ApiResponse<String>, String> {
+        pin: String,
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new()
+            .field("name", &name, &[validation::name])
+            .field("virtual_balance", &virtual_balance, &[validation::required, validation::non_negative_money])
+            .field("pin", &pin, &[validation::pin])
+            .finish()?;
+        let pin_hash = hash(&pin, DEFAULT_COST).map_err(|err| AppError::Internal(format!("PIN hashing failed: {}", err)))?;
+
         // Generate a UUID for the customer_id
         let customer_id = Uuid::new_v4().to_string();  // Generate a v4 UUID
 
@@ -65,13 +109,16 @@ impl CustomerHandler {
             customer_id: sea_orm::ActiveValue::Set(customer_id),  // Set the generated customer_id
             name: sea_orm::ActiveValue::Set(name),
             virtual_balance: sea_orm::ActiveValue::Set(virtual_balance),
+            pin_hash: sea_orm::ActiveValue::Set(Some(pin_hash)),
             ..Default::default()
         };
 
-        match customer::Entity::insert(new_customer).exec(&state.db).await {
-            Ok(_) => Ok(ApiResponse::success("Customer created successfully".to_string())),
-            Err(err) => Err(format!("Error creating customer: {}", err)),
-        }
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let created = customer::Entity::insert(new_customer).exec_with_returning(&txn).await
+            .map_err(|err| AppError::Database(format!("Error creating customer: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_CREATE, "customer", &created.customer_id, None, AuditEventHandler::snapshot(&created)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        Ok(ApiResponse::success("Customer created successfully".to_string()))
     }
 
     // Update customer data
@@ -80,13 +127,24 @@ impl CustomerHandler {
         customer_id: String,
         name: Option<String>,
         virtual_balance: Option<String>,
-    ) -> Result<String, String> {
-        let customer = match customer::Entity::find_by_id(customer_id).one(&state.db).await {
+        pin: Option<String>, // Lets customer service set or reset the PIN
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new()
+            .optional("name", name.as_deref(), &[validation::name])
+            .optional("virtual_balance", virtual_balance.as_deref(), &[validation::required, validation::non_negative_money])
+            .optional("pin", pin.as_deref(), &[validation::pin])
+            .finish()?;
+        let customer = match customer::Entity::find_by_id(customer_id.clone()).one(&state.db).await {
             Ok(Some(customer)) => customer,
-            Ok(None) => return Err("Customer not found".to_string()),
-            Err(err) => return Err(format!("Error fetching customer: {}", err)),
+            Ok(None) => return Err(AppError::NotFound("Customer not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching customer: {}", err))),
         };
+        if customer.deleted_at.is_some() {
+            return Err(AppError::Conflict("Customer is archived; restore it before editing".to_string()));
+        }
 
+        let before = AuditEventHandler::snapshot(&customer);
         // Create an ActiveModel from the retrieved customer
         let mut active_customer: customer::ActiveModel = customer.into();
 
@@ -98,74 +156,291 @@ impl CustomerHandler {
             active_customer.virtual_balance = sea_orm::ActiveValue::Set(new_balance);
         }
 
-        match active_customer.update(&state.db).await {
-            Ok(_) => Ok("Customer updated successfully".to_string()),
-            Err(err) => Err(format!("Error updating customer: {}", err)),
+        if let Some(new_pin) = pin {
+            let pin_hash = hash(&new_pin, DEFAULT_COST).map_err(|err| AppError::Internal(format!("PIN hashing failed: {}", err)))?;
+            active_customer.pin_hash = sea_orm::ActiveValue::Set(Some(pin_hash));
         }
+
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let updated = active_customer.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error updating customer: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_UPDATE, "customer", &customer_id, before, AuditEventHandler::snapshot(&updated)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        state.cache.invalidate(&[TAG_CUSTOMER]).await;
+        Ok(ApiResponse::success("Customer updated successfully".to_string()))
     }
 
-    // Top up customer virtual balance
+    // Top up customer virtual balance. Retrying with the same idempotency key tops up only once.
     pub async fn top_up_virtual_balance(
         state: &AppState,
         customer_id: String,
         top_up_amount_str: String, // Receive top-up amount as string from frontend
-    ) -> Result<ApiResponse<String>, String> {
-        let customer_result = customer::Entity::find_by_id(customer_id.clone()).one(&state.db).await;
-
-        match customer_result {
-            Ok(Some(customer)) => {
-                // Get current balance as String from the database
-                let current_balance_str = customer.virtual_balance.clone();
-
-                // Parse current balance and top-up amount to i64 (integer)
-                let current_balance = match current_balance_str.parse::<i64>() {
-                    Ok(balance) => balance,
-                    Err(_) => 0, // Default to 0 if parsing fails (handle error more robustly if needed)
-                };
-                let top_up_amount = match top_up_amount_str.parse::<i64>() {
-                    Ok(amount) => amount,
-                    Err(_) => 0, // Default to 0 if parsing fails (handle error more robustly if needed)
-                };
-
-                // Ensure top_up_amount is not negative (optional, but good practice)
-                if top_up_amount <= 0 {
-                    return Err("Top-up amount must be a positive whole number".to_string());
-                }
-
-                // Calculate new balance
-                let new_balance = current_balance + top_up_amount;
-
-                // Convert new balance back to String for database storage
-                let new_balance_str = new_balance.to_string();
-
-                // Update customer data with new balance
-                match Self::update_customer_data(
-                    state,
-                    customer_id.clone(),
-                    None, // No name update
-                    Some(new_balance_str), // Update virtual balance
-                ).await {
-                    Ok(_) => Ok(ApiResponse::success("Virtual balance topped up successfully".to_string())),
-                    Err(err) => Err(format!("Error updating balance in database: {}", err)),
-                }
+        actor_id: String,
+        idempotency_key: Option<String>,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let request = serde_json::json!({ "customer_id": customer_id, "top_up_amount": top_up_amount_str });
+        let top_up = Self::apply_top_up(state, customer_id, top_up_amount_str, actor_id);
+        Idempotency::run(&state.db, idempotency_key, "top_up_virtual_balance", request, top_up).await
+    }
+
+    async fn apply_top_up(
+        state: &AppState,
+        customer_id: String,
+        top_up_amount_str: String,
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new()
+            .field("top_up_amount", &top_up_amount_str, &[validation::positive_whole_number])
+            .finish()?;
+        let top_up_amount = Decimal::from_str(top_up_amount_str.trim()).unwrap_or_default(); // Checked above
+
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        Self::adjust_balance(&txn, &customer_id, top_up_amount, Some(&actor_id)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        state.cache.invalidate(&[TAG_CUSTOMER]).await;
+        Ok(ApiResponse::success("Virtual balance topped up successfully".to_string()))
+    }
+
+    // Deduct a purchase from the balance, never below zero
+    pub async fn charge_virtual_balance(
+        state: &AppState,
+        customer_id: String,
+        amount: Decimal,
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        Self::adjust_balance(&txn, &customer_id, -amount, Some(&actor_id)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        state.cache.invalidate(&[TAG_CUSTOMER]).await;
+        Ok(ApiResponse::success("Virtual balance charged successfully".to_string()))
+    }
+
+    // Add `change` to the balance (negative to charge), refusing to go below zero. The new balance
+    // is only written if the stored one is still the balance it was computed from, so concurrent
+    // top-ups and charges cannot overwrite each other; a write that lost the race is recomputed.
+    // Callers invalidate TAG_CUSTOMER once the change is committed.
+    pub(crate) async fn adjust_balance<C: ConnectionTrait>(
+        conn: &C,
+        customer_id: &str,
+        change: Decimal,
+        actor_id: Option<&str>,
+    ) -> Result<Model, AppError> {
+        for _ in 0..BALANCE_UPDATE_ATTEMPTS {
+            let customer = customer::Entity::find_by_id(customer_id.to_string())
+                .one(conn)
+                .await
+                .map_err(|err| AppError::Database(format!("Error fetching customer: {}", err)))?
+                .ok_or_else(|| AppError::NotFound("Customer not found".to_string()))?;
+            if customer.deleted_at.is_some() {
+                return Err(AppError::Conflict("Customer is archived; restore it before editing".to_string()));
             }
-            Ok(None) => Err("Customer not found".to_string()),
-            Err(err) => Err(format!("Error fetching customer: {}", err)),
+            let balance = Decimal::from_str(customer.virtual_balance.trim())
+                .map_err(|_| AppError::Internal(format!("Customer {} has an unreadable balance", customer_id)))?;
+            let new_balance = balance + change;
+            if new_balance < Decimal::ZERO {
+                return Err(AppError::Conflict(format!(
+                    "Insufficient virtual balance: {} needed, {} available",
+                    -change, balance
+                )));
+            }
+
+            let written = customer::Entity::update_many()
+                .col_expr(customer::Column::VirtualBalance, Expr::value(new_balance.normalize().to_string()))
+                .filter(customer::Column::CustomerId.eq(customer_id))
+                .filter(customer::Column::VirtualBalance.eq(customer.virtual_balance.clone()))
+                .exec(conn)
+                .await
+                .map_err(|err| AppError::Database(format!("Error updating virtual balance: {}", err)))?;
+            if written.rows_affected == 0 {
+                continue;
+            }
+
+            let before = AuditEventHandler::snapshot(&customer);
+            let updated = Model { virtual_balance: new_balance.normalize().to_string(), ..customer };
+            AuditEventHandler::record(conn, actor_id, ACTION_UPDATE, "customer", customer_id, before, AuditEventHandler::snapshot(&updated)).await?;
+            return Ok(updated);
         }
+        Err(AppError::Conflict("The balance kept changing while it was being updated; please try again".to_string()))
     }
 
+    // Archive customer data (soft delete): the row stays so history and reports still resolve it
+    pub async fn delete_customer_data(state: &AppState, customer_id: String, cascade: bool, actor_id: String) -> Result<ApiResponse<DependencyReport>, AppError> {
+        let existing = match customer::Entity::find_by_id(customer_id.clone()).one(&state.db).await {
+            Ok(Some(record)) if record.deleted_at.is_none() => record,
+            Ok(_) => return Err(AppError::NotFound("Customer not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching customer: {}", err))),
+        };
 
-    // Delete customer data
-    pub async fn delete_customer_data(state: &AppState, customer_id: String) -> Result<String, String> {
-        match customer::Entity::delete_by_id(customer_id).exec(&state.db).await {
-            Ok(delete_result) => {
-                if delete_result.rows_affected > 0 {
-                    Ok("Customer deleted successfully".to_string())
-                } else {
-                    Err("Customer not found".to_string())
-                }
-            }
-            Err(err) => Err(format!("Error deleting customer: {}", err)),
+        // Dependents are checked and cascaded in the same transaction as the archive itself
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let report = DependencyHandler::check_customer(&txn, &customer_id).await?;
+        if let Some(blocked) = DependencyHandler::blocked(&report, cascade) {
+            return Ok(blocked);
+        }
+        let now = clock::jakarta_now();
+        if !report.is_clear() {
+            DependencyHandler::cascade_customer(&txn, &customer_id, &actor_id).await?;
+        }
+
+        let before = AuditEventHandler::snapshot(&existing);
+        let mut active_customer: customer::ActiveModel = existing.into();
+        active_customer.deleted_at = sea_orm::ActiveValue::Set(Some(now));
+        let archived = active_customer.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error deleting customer: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_DELETE, "customer", &customer_id, before, AuditEventHandler::snapshot(&archived)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+
+        state.cache.invalidate(&[TAG_CUSTOMER]).await;
+        Ok(ApiResponse::Success { data: report, message: Some("Customer deleted successfully".to_string()) })
+    }
+
+    // Bring an archived customer back into the lists
+    pub async fn restore_customer_data(state: &AppState, customer_id: String, actor_id: String) -> Result<ApiResponse<String>, AppError> {
+        let existing = match customer::Entity::find_by_id(customer_id.clone()).one(&state.db).await {
+            Ok(Some(record)) => record,
+            Ok(None) => return Err(AppError::NotFound("Customer not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching customer: {}", err))),
+        };
+        if existing.deleted_at.is_none() {
+            return Err(AppError::Conflict("Customer is not archived".to_string()));
+        }
+
+        let before = AuditEventHandler::snapshot(&existing);
+        let mut active_customer: customer::ActiveModel = existing.into();
+        active_customer.deleted_at = sea_orm::ActiveValue::Set(None);
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let restored = active_customer.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error restoring customer: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_RESTORE, "customer", &customer_id, before, AuditEventHandler::snapshot(&restored)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        state.cache.invalidate(&[TAG_CUSTOMER]).await;
+        Ok(ApiResponse::success("Customer restored successfully".to_string()))
+    }
+
+    // View archived customers, most recently archived first
+    pub async fn view_archived_customers(state: &AppState) -> Result<ApiResponse<Vec<Model>>, AppError> {
+        match customer::Entity::find()
+            .filter(customer::Column::DeletedAt.is_not_null())
+            .order_by_desc(customer::Column::DeletedAt)
+            .all(&state.db)
+            .await
+        {
+            Ok(archived) => Ok(ApiResponse::success(archived)),
+            Err(err) => Err(AppError::Database(format!("Error fetching archived customers: {}", err))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::test_support::{self, data, error, ACTOR};
+
+    #[tokio::test]
+    async fn top_up_adds_to_the_balance() {
+        let state = test_support::test_state().await;
+        let customer = test_support::seed_customer(&state, "25000").await;
+
+        data(CustomerHandler::top_up_virtual_balance(&state, customer.customer_id.clone(), "50000".to_string(), ACTOR.to_string(), None).await);
+        data(CustomerHandler::top_up_virtual_balance(&state, customer.customer_id.clone(), "5000".to_string(), ACTOR.to_string(), None).await);
+
+        let updated = data(CustomerHandler::get_customer_details(&state, customer.customer_id).await);
+        assert_eq!(updated.virtual_balance, "80000");
+    }
+
+    #[tokio::test]
+    async fn a_retried_top_up_is_only_applied_once() {
+        let state = test_support::test_state().await;
+        let customer = test_support::seed_customer(&state, "25000").await;
+        let key = Some("top-up-1".to_string());
+
+        for _ in 0..2 {
+            data(CustomerHandler::top_up_virtual_balance(&state, customer.customer_id.clone(), "50000".to_string(), ACTOR.to_string(), key.clone()).await);
+        }
+        let updated = data(CustomerHandler::get_customer_details(&state, customer.customer_id.clone()).await);
+        assert_eq!(updated.virtual_balance, "75000");
+
+        let err = error(CustomerHandler::top_up_virtual_balance(&state, customer.customer_id, "10000".to_string(), ACTOR.to_string(), key).await);
+        assert_eq!(err.code(), ErrorCode::Conflict);
+    }
+
+    #[tokio::test]
+    async fn top_up_rejects_bad_amounts_and_unknown_customers() {
+        let state = test_support::test_state().await;
+        let customer = test_support::seed_customer(&state, "25000").await;
+
+        for amount in ["0", "-100", "12.5", "abc"] {
+            let err = error(CustomerHandler::top_up_virtual_balance(&state, customer.customer_id.clone(), amount.to_string(), ACTOR.to_string(), None).await);
+            assert_eq!(err.code(), ErrorCode::ValidationFailed, "{amount}");
+        }
+        let err = error(CustomerHandler::top_up_virtual_balance(&state, "nobody".to_string(), "100".to_string(), ACTOR.to_string(), None).await);
+        assert_eq!(err.code(), ErrorCode::NotFound);
+
+        let unchanged = data(CustomerHandler::get_customer_details(&state, customer.customer_id).await);
+        assert_eq!(unchanged.virtual_balance, "25000");
+    }
+    #[tokio::test]
+    async fn charges_and_top_ups_read_the_balance_the_same_way() {
+        let state = test_support::test_state().await;
+        let customer = test_support::seed_customer(&state, "25000").await;
+
+        data(CustomerHandler::charge_virtual_balance(&state, customer.customer_id.clone(), Decimal::from_str("2500.50").unwrap(), ACTOR.to_string()).await);
+        data(CustomerHandler::top_up_virtual_balance(&state, customer.customer_id.clone(), "1000".to_string(), ACTOR.to_string(), None).await);
+        let updated = data(CustomerHandler::get_customer_details(&state, customer.customer_id.clone()).await);
+        assert_eq!(updated.virtual_balance, "23499.5");
+
+        let err = error(CustomerHandler::charge_virtual_balance(&state, customer.customer_id, Decimal::from(30000), ACTOR.to_string()).await);
+        assert_eq!(err.code(), ErrorCode::Conflict);
+    }
+
+    #[tokio::test]
+    async fn an_unreadable_balance_is_reported_instead_of_reset() {
+        let state = test_support::test_state().await;
+        let customer = test_support::seed_customer(&state, "not a number").await;
+
+        let err = error(CustomerHandler::top_up_virtual_balance(&state, customer.customer_id.clone(), "1000".to_string(), ACTOR.to_string(), None).await);
+        assert_eq!(err.code(), ErrorCode::InternalError);
+        let unchanged = data(CustomerHandler::get_customer_details(&state, customer.customer_id).await);
+        assert_eq!(unchanged.virtual_balance, "not a number");
+    }
+
+    #[tokio::test]
+    async fn concurrent_top_ups_are_all_kept() {
+        let state = test_support::test_state().await;
+        let customer = test_support::seed_customer(&state, "0").await;
+
+        let top_up = || CustomerHandler::top_up_virtual_balance(&state, customer.customer_id.clone(), "1000".to_string(), ACTOR.to_string(), None);
+        let (first, second, third) = tokio::join!(top_up(), top_up(), top_up());
+        for outcome in [first, second, third] {
+            data(outcome);
+        }
+        let updated = data(CustomerHandler::get_customer_details(&state, customer.customer_id).await);
+        assert_eq!(updated.virtual_balance, "3000");
+    }
+
+    #[tokio::test]
+    async fn login_needs_the_pin_and_locks_after_repeated_misses() {
+        let state = test_support::test_state().await;
+        let customer = test_support::seed_customer(&state, "0").await;
+
+        // No PIN set yet: even a guess of the dummy PIN is refused
+        let err = error(CustomerHandler::customer_login(&state, customer.customer_id.clone(), "000000".to_string(), None).await);
+        assert_eq!(err.code(), ErrorCode::Unauthorized);
+
+        let err = error(CustomerHandler::update_customer_data(&state, customer.customer_id.clone(), None, None, Some("12ab".to_string()), ACTOR.to_string()).await);
+        assert_eq!(err.fields()[0].field, "pin");
+        data(CustomerHandler::update_customer_data(&state, customer.customer_id.clone(), None, None, Some("4821".to_string()), ACTOR.to_string()).await);
+
+        let session_token = data(CustomerHandler::customer_login(&state, customer.customer_id.clone(), "4821".to_string(), None).await);
+        assert_eq!(Session::actor(&state.db, &session_token).await.unwrap(), customer.customer_id);
+
+        // Signing in cleared the earlier miss, so the lock takes five fresh ones
+        for _ in 0..5 {
+            let err = error(CustomerHandler::customer_login(&state, customer.customer_id.clone(), "1111".to_string(), None).await);
+            assert_eq!(err.code(), ErrorCode::Unauthorized);
         }
+        let err = error(CustomerHandler::customer_login(&state, customer.customer_id, "4821".to_string(), None).await);
+        assert_eq!(err.code(), ErrorCode::RateLimited);
     }
 }
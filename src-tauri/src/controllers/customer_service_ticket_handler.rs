@@ -0,0 +1,428 @@
+// src-tauri/src/controllers/customer_service_ticket_handler.rs
+
+use chrono::NaiveDateTime;
+use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, Set, TransactionTrait};
+use entity::{chat, customer, customer_service_ticket, staff};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use entity::audit_event::ACTION_UPDATE;
+use crate::clock;
+use crate::controllers::audit_event_handler::AuditEventHandler;
+use crate::cache::TAG_CUSTOMER_SERVICE_TICKET;
+use crate::error::AppError;
+use crate::validation::Validator;
+use crate::{ApiResponse, AppState};
+
+pub const CUSTOMER_SERVICE_ROLES: [&str; 2] = ["CustomerServiceStaff", "CustomerServiceManager"];
+const TICKET_STATUSES: [&str; 3] = ["Open", "Pending", "Resolved"];
+
+// Unassigned ticket waiting in the Customer Service queue
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct QueuedTicket {
+    pub ticket: customer_service_ticket::Model,
+    pub customer_name: String,
+    pub wait_seconds: i64,
+}
+
+// First-response and resolution metrics, averaged in seconds
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct CustomerServiceMetrics {
+    pub open_count: i64,
+    pub pending_count: i64,
+    pub resolved_count: i64,
+    pub unassigned_count: i64,
+    pub average_first_response_seconds: Option<f64>,
+    pub average_resolution_seconds: Option<f64>,
+}
+
+pub struct CustomerServiceTicketHandler;
+
+impl CustomerServiceTicketHandler {
+    // Find the ticket that is still open or pending for a chat, if any
+    async fn find_active_ticket(
+        state: &AppState,
+        chat_id: &str,
+    ) -> Result<Option<customer_service_ticket::Model>, AppError> {
+        customer_service_ticket::Entity::find()
+            .filter(
+                Condition::all()
+                    .add(customer_service_ticket::Column::ChatId.eq(chat_id))
+                    .add(customer_service_ticket::Column::Status.ne("Resolved"))
+            )
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching active ticket: {}", err)))
+    }
+
+    // Keep ticket state in sync with a message just sent to a Customer Service chat.
+    // A customer message opens (or re-opens) a ticket; a staff reply records the first
+    // response, claims the ticket if nobody owns it yet and marks it Pending.
+    pub async fn record_message(
+        state: &AppState,
+        chat_id: &str,
+        sender_id: &str,
+        sent_at: NaiveDateTime,
+    ) -> Result<(), AppError> {
+        let chat_record = chat::Entity::find_by_id(chat_id.to_string())
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching chat for ticket update: {}", err)))?
+            .ok_or_else(|| AppError::NotFound("Chat not found for ticket update".to_string()))?;
+
+        if chat_record.chat_type != chat::CHAT_TYPE_CUSTOMER_SERVICE {
+            return Ok(());
+        }
+
+        let sender_is_customer = customer::Entity::find_by_id(sender_id.to_string())
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Database error fetching message sender: {}", err)))?
+            .is_some();
+
+        let active_ticket = Self::find_active_ticket(state, chat_id).await?;
+
+        if sender_is_customer {
+            match active_ticket {
+                None => {
+                    let new_ticket = customer_service_ticket::ActiveModel {
+                        ticket_id: Set(Uuid::new_v4().to_string()),
+                        chat_id: Set(chat_id.to_string()),
+                        customer_id: Set(sender_id.to_string()),
+                        assigned_staff_id: Set(None),
+                        status: Set("Open".to_string()),
+                        opened_at: Set(sent_at),
+                        waiting_since: Set(Some(sent_at)),
+                        first_response_at: Set(None),
+                        resolved_at: Set(None),
+                    };
+                    customer_service_ticket::Entity::insert(new_ticket)
+                        .exec(&state.db)
+                        .await
+                        .map_err(|err| AppError::Database(format!("Error opening customer service ticket: {}", err)))?;
+                }
+                Some(ticket) if ticket.status == "Pending" => {
+                    let mut active_ticket: customer_service_ticket::ActiveModel = ticket.into();
+                    active_ticket.status = Set("Open".to_string());
+                    active_ticket.waiting_since = Set(Some(sent_at));
+                    active_ticket.update(&state.db)
+                        .await
+                        .map_err(|err| AppError::Database(format!("Error re-opening customer service ticket: {}", err)))?;
+                }
+                Some(_) => return Ok(()), // Already waiting on staff, keep the original wait time
+            }
+        } else {
+            let Some(ticket) = active_ticket else {
+                return Ok(()); // Staff-initiated message with no customer request pending
+            };
+
+            let mut active_ticket: customer_service_ticket::ActiveModel = ticket.clone().into();
+            if ticket.first_response_at.is_none() {
+                active_ticket.first_response_at = Set(Some(sent_at));
+            }
+            if ticket.assigned_staff_id.is_none() {
+                active_ticket.assigned_staff_id = Set(Some(sender_id.to_string()));
+            }
+            active_ticket.status = Set("Pending".to_string());
+            active_ticket.waiting_since = Set(None);
+            active_ticket.update(&state.db)
+                .await
+                .map_err(|err| AppError::Database(format!("Error recording staff response on ticket: {}", err)))?;
+        }
+
+        state.cache.invalidate(&[TAG_CUSTOMER_SERVICE_TICKET]).await;
+        Ok(())
+    }
+
+    // View unassigned tickets, longest waiting first
+    pub async fn view_queue(state: &AppState) -> Result<ApiResponse<Vec<QueuedTicket>>, AppError> {
+        let tickets = customer_service_ticket::Entity::find()
+            .find_also_related(customer::Entity)
+            .filter(
+                Condition::all()
+                    .add(customer_service_ticket::Column::AssignedStaffId.is_null())
+                    .add(customer_service_ticket::Column::Status.ne("Resolved"))
+            )
+            .order_by_asc(customer_service_ticket::Column::WaitingSince)
+            .order_by_asc(customer_service_ticket::Column::OpenedAt)
+            .all(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching customer service queue: {}", err)))?;
+
+        let now = clock::jakarta_now();
+        let queue = tickets
+            .into_iter()
+            .map(|(ticket, customer)| {
+                let waiting_from = ticket.waiting_since.unwrap_or(ticket.opened_at);
+                QueuedTicket {
+                    wait_seconds: (now - waiting_from).num_seconds().max(0),
+                    customer_name: customer.map(|c| c.name).unwrap_or_else(|| "Unknown Customer".to_string()),
+                    ticket,
+                }
+            })
+            .collect();
+
+        Ok(ApiResponse::success(queue))
+    }
+
+    // View unresolved tickets assigned to a staff member
+    pub async fn view_assigned_tickets(
+        state: &AppState,
+        staff_id: String,
+    ) -> Result<ApiResponse<Vec<customer_service_ticket::Model>>, AppError> {
+        match customer_service_ticket::Entity::find()
+            .filter(
+                Condition::all()
+                    .add(customer_service_ticket::Column::AssignedStaffId.eq(staff_id))
+                    .add(customer_service_ticket::Column::Status.ne("Resolved"))
+            )
+            .order_by_asc(customer_service_ticket::Column::OpenedAt)
+            .all(&state.db)
+            .await
+        {
+            Ok(tickets) => Ok(ApiResponse::success(tickets)),
+            Err(err) => Err(AppError::Database(format!("Error fetching assigned tickets: {}", err))),
+        }
+    }
+
+    // Get the open or pending ticket for a chat (None when the conversation is idle)
+    pub async fn get_active_ticket(
+        state: &AppState,
+        chat_id: String,
+    ) -> Result<ApiResponse<Option<customer_service_ticket::Model>>, AppError> {
+        Self::find_active_ticket(state, &chat_id)
+            .await
+            .map(ApiResponse::success)
+    }
+
+    // Assign (or re-assign) a ticket to a customer service staff member
+    pub async fn assign_ticket(
+        state: &AppState,
+        ticket_id: String,
+        staff_id: String,
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let staff_member = match staff::Entity::find_by_id(staff_id.clone()).one(&state.db).await {
+            Ok(Some(staff_member)) => staff_member,
+            Ok(None) => return Err(AppError::NotFound("Staff not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Database error fetching staff details: {}", err))),
+        };
+        if !CUSTOMER_SERVICE_ROLES.contains(&staff_member.role.as_str()) {
+            return Err(AppError::validation("Tickets can only be assigned to Customer Service staff".to_string()));
+        }
+
+        let ticket_record = match customer_service_ticket::Entity::find_by_id(ticket_id).one(&state.db).await {
+            Ok(Some(ticket)) => ticket,
+            Ok(None) => return Err(AppError::NotFound("Ticket not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching ticket: {}", err))),
+        };
+        if ticket_record.status == "Resolved" {
+            return Err(AppError::Conflict("Resolved tickets cannot be assigned".to_string()));
+        }
+
+        let before = AuditEventHandler::snapshot(&ticket_record);
+        let mut active_ticket: customer_service_ticket::ActiveModel = ticket_record.into();
+        active_ticket.assigned_staff_id = Set(Some(staff_id));
+
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let updated = active_ticket.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error assigning ticket: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_UPDATE, "customer_service_ticket", &updated.ticket_id, before, AuditEventHandler::snapshot(&updated)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        state.cache.invalidate(&[TAG_CUSTOMER_SERVICE_TICKET]).await;
+        Ok(ApiResponse::success("Ticket assigned successfully".to_string()))
+    }
+
+    // Update ticket status ("Open", "Pending" or "Resolved")
+    pub async fn update_ticket_status(
+        state: &AppState,
+        ticket_id: String,
+        status: String,
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new().one_of("status", &status, &TICKET_STATUSES).finish()?;
+
+        let ticket_record = match customer_service_ticket::Entity::find_by_id(ticket_id).one(&state.db).await {
+            Ok(Some(ticket)) => ticket,
+            Ok(None) => return Err(AppError::NotFound("Ticket not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching ticket: {}", err))),
+        };
+
+        // Re-opening a resolved ticket must not create a second active ticket for the chat
+        if ticket_record.status == "Resolved" && status != "Resolved" {
+            if let Some(other) = Self::find_active_ticket(state, &ticket_record.chat_id).await? {
+                if other.ticket_id != ticket_record.ticket_id {
+                    return Err(AppError::Conflict("This conversation already has an active ticket".to_string()));
+                }
+            }
+        }
+
+        let now = clock::jakarta_now();
+        let mut active_ticket: customer_service_ticket::ActiveModel = ticket_record.clone().into();
+        match status.as_str() {
+            "Resolved" => {
+                active_ticket.resolved_at = Set(Some(now));
+                active_ticket.waiting_since = Set(None);
+            }
+            "Open" => {
+                active_ticket.resolved_at = Set(None);
+                if ticket_record.waiting_since.is_none() {
+                    active_ticket.waiting_since = Set(Some(now));
+                }
+            }
+            _ => {
+                active_ticket.resolved_at = Set(None);
+                active_ticket.waiting_since = Set(None);
+            }
+        }
+        active_ticket.status = Set(status);
+
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let updated = active_ticket.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error updating ticket status: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_UPDATE, "customer_service_ticket", &updated.ticket_id, AuditEventHandler::snapshot(&ticket_record), AuditEventHandler::snapshot(&updated)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        state.cache.invalidate(&[TAG_CUSTOMER_SERVICE_TICKET]).await;
+        Ok(ApiResponse::success("Ticket status updated successfully".to_string()))
+    }
+
+    // First-response and resolution metrics, optionally for a single staff member
+    pub async fn get_metrics(
+        state: &AppState,
+        staff_id: Option<String>,
+    ) -> Result<ApiResponse<CustomerServiceMetrics>, AppError> {
+        let mut query = customer_service_ticket::Entity::find();
+        if let Some(staff_id) = staff_id {
+            query = query.filter(customer_service_ticket::Column::AssignedStaffId.eq(staff_id));
+        }
+
+        let tickets = query
+            .all(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching tickets for metrics: {}", err)))?;
+
+        let count_status = |status: &str| tickets.iter().filter(|t| t.status == status).count() as i64;
+        let average = |durations: Vec<i64>| {
+            if durations.is_empty() {
+                None
+            } else {
+                Some(durations.iter().sum::<i64>() as f64 / durations.len() as f64)
+            }
+        };
+
+        let first_response_durations = tickets
+            .iter()
+            .filter_map(|t| t.first_response_at.map(|at| (at - t.opened_at).num_seconds()))
+            .collect();
+        let resolution_durations = tickets
+            .iter()
+            .filter(|t| t.status == "Resolved")
+            .filter_map(|t| t.resolved_at.map(|at| (at - t.opened_at).num_seconds()))
+            .collect();
+
+        Ok(ApiResponse::success(CustomerServiceMetrics {
+            open_count: count_status("Open"),
+            pending_count: count_status("Pending"),
+            resolved_count: count_status("Resolved"),
+            unassigned_count: tickets
+                .iter()
+                .filter(|t| t.assigned_staff_id.is_none() && t.status != "Resolved")
+                .count() as i64,
+            average_first_response_seconds: average(first_response_durations),
+            average_resolution_seconds: average(resolution_durations),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controllers::chat_handler::ChatHandler;
+    use crate::error::ErrorCode;
+    use crate::test_support::{self, data, error, ACTOR};
+
+    // A customer's Customer Service chat and a staff member who answers it
+    async fn conversation(state: &AppState) -> (String, String, String) {
+        let customer = test_support::seed_customer(state, "0").await;
+        let agent = test_support::seed_staff(state, "CustomerServiceStaff").await;
+        let chat = data(ChatHandler::get_customer_service_chat(state, customer.customer_id.clone()).await);
+        (chat.chat_id, customer.customer_id, agent.staff_id)
+    }
+
+    async fn say(state: &AppState, chat_id: &str, sender_id: &str) {
+        data(ChatHandler::save_message_data(state, chat_id.to_string(), sender_id.to_string(), "Hello".to_string(), None).await);
+    }
+
+    async fn active(state: &AppState, chat_id: &str) -> Option<customer_service_ticket::Model> {
+        data(CustomerServiceTicketHandler::get_active_ticket(state, chat_id.to_string()).await)
+    }
+
+    #[tokio::test]
+    async fn messages_move_a_ticket_between_open_and_pending() {
+        let state = test_support::test_state().await;
+        let (chat_id, customer_id, agent_id) = conversation(&state).await;
+
+        // Staff writing first does not open anything
+        say(&state, &chat_id, &agent_id).await;
+        assert!(active(&state, &chat_id).await.is_none());
+
+        say(&state, &chat_id, &customer_id).await;
+        let ticket = active(&state, &chat_id).await.unwrap();
+        assert_eq!((ticket.status.as_str(), ticket.assigned_staff_id.as_deref()), ("Open", None));
+        assert!(ticket.waiting_since.is_some());
+        assert_eq!(data(CustomerServiceTicketHandler::view_queue(&state).await).len(), 1);
+
+        // The first reply claims the ticket and stops the wait clock
+        say(&state, &chat_id, &agent_id).await;
+        let answered = active(&state, &chat_id).await.unwrap();
+        assert_eq!((answered.status.as_str(), answered.assigned_staff_id.as_deref()), ("Pending", Some(agent_id.as_str())));
+        assert!(answered.first_response_at.is_some() && answered.waiting_since.is_none());
+        assert!(data(CustomerServiceTicketHandler::view_queue(&state).await).is_empty());
+
+        // The customer answering back re-opens the same ticket
+        say(&state, &chat_id, &customer_id).await;
+        let reopened = active(&state, &chat_id).await.unwrap();
+        assert_eq!((reopened.ticket_id.as_str(), reopened.status.as_str()), (ticket.ticket_id.as_str(), "Open"));
+        assert_eq!(reopened.first_response_at, answered.first_response_at);
+    }
+
+    #[tokio::test]
+    async fn resolving_ends_the_ticket_and_the_next_message_opens_a_new_one() {
+        let state = test_support::test_state().await;
+        let (chat_id, customer_id, agent_id) = conversation(&state).await;
+        say(&state, &chat_id, &customer_id).await;
+        say(&state, &chat_id, &agent_id).await;
+        let ticket = active(&state, &chat_id).await.unwrap();
+
+        data(CustomerServiceTicketHandler::update_ticket_status(&state, ticket.ticket_id.clone(), "Resolved".to_string(), ACTOR.to_string()).await);
+        assert!(active(&state, &chat_id).await.is_none());
+        let resolved = customer_service_ticket::Entity::find_by_id(ticket.ticket_id.clone()).one(&state.db).await.unwrap().unwrap();
+        assert!(resolved.resolved_at.is_some());
+
+        say(&state, &chat_id, &customer_id).await;
+        let next = active(&state, &chat_id).await.unwrap();
+        assert_ne!(next.ticket_id, ticket.ticket_id);
+
+        // The old ticket cannot come back while the new one is active
+        let err = error(CustomerServiceTicketHandler::update_ticket_status(&state, ticket.ticket_id, "Open".to_string(), ACTOR.to_string()).await);
+        assert_eq!(err.code(), ErrorCode::Conflict);
+    }
+
+    #[tokio::test]
+    async fn status_and_assignment_changes_are_checked() {
+        let state = test_support::test_state().await;
+        let (chat_id, customer_id, _) = conversation(&state).await;
+        let ride_staff = test_support::seed_staff(&state, "RideStaff").await;
+        say(&state, &chat_id, &customer_id).await;
+        let ticket = active(&state, &chat_id).await.unwrap();
+
+        let err = error(CustomerServiceTicketHandler::update_ticket_status(&state, ticket.ticket_id.clone(), "Closed".to_string(), ACTOR.to_string()).await);
+        assert_eq!(err.code(), ErrorCode::ValidationFailed);
+        let err = error(CustomerServiceTicketHandler::assign_ticket(&state, ticket.ticket_id.clone(), ride_staff.staff_id, ACTOR.to_string()).await);
+        assert_eq!(err.code(), ErrorCode::ValidationFailed);
+
+        let manager = test_support::seed_staff(&state, "CustomerServiceManager").await;
+        data(CustomerServiceTicketHandler::update_ticket_status(&state, ticket.ticket_id.clone(), "Resolved".to_string(), ACTOR.to_string()).await);
+        let err = error(CustomerServiceTicketHandler::assign_ticket(&state, ticket.ticket_id, manager.staff_id, ACTOR.to_string()).await);
+        assert_eq!(err.code(), ErrorCode::Conflict);
+    }
+}
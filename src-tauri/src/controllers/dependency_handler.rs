@@ -0,0 +1,469 @@
+// src-tauri/src/controllers/dependency_handler.rs
+
+use rust_decimal::Decimal;
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait, QueryFilter, Set};
+use entity::audit_event::{ACTION_DELETE, ACTION_UPDATE};
+use entity::{customer_service_ticket, maintenance_schedule, menu_item, order_restaurant, ride, ride_queue, souvenir};
+use serde::{Deserialize, Serialize};
+use crate::controllers::audit_event_handler::AuditEventHandler;
+use crate::controllers::customer_handler::CustomerHandler;
+use crate::error::AppError;
+use crate::validation;
+use crate::{ApiResponse, AppState};
+
+pub const ORDER_STATUS_COMPLETE: &str = "Complete";
+pub const ORDER_STATUS_CANCELLED: &str = "Cancelled";
+const MAINTENANCE_OPEN_STATUSES: [&str; 2] = ["Pending", "Ongoing"];
+const MAINTENANCE_STATUS_CANCELLED: &str = "Cancelled";
+
+// Rows that still point at a record about to be archived
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Dependent {
+    pub kind: String,
+    pub count: u64,
+    pub cascade_action: Option<String>, // What a cascading delete does to them; None means they must be dealt with by hand
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct DependencyReport {
+    pub entity_type: String,
+    pub entity_id: String,
+    pub dependents: Vec<Dependent>,
+}
+
+impl DependencyReport {
+    fn new(entity_type: &str, entity_id: &str) -> Self {
+        DependencyReport { entity_type: entity_type.to_string(), entity_id: entity_id.to_string(), dependents: Vec::new() }
+    }
+
+    fn add(&mut self, kind: &str, count: u64, cascade_action: Option<&str>) {
+        if count > 0 {
+            self.dependents.push(Dependent { kind: kind.to_string(), count, cascade_action: cascade_action.map(str::to_string) });
+        }
+    }
+
+    pub fn is_clear(&self) -> bool {
+        self.dependents.is_empty()
+    }
+
+    pub fn can_cascade(&self) -> bool {
+        self.dependents.iter().all(|dependent| dependent.cascade_action.is_some())
+    }
+
+    // e.g. "3 open orders, 12 menu items"
+    pub fn summary(&self) -> String {
+        self.dependents
+            .iter()
+            .map(|dependent| format!("{} {}", dependent.count, dependent.kind))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+pub struct DependencyHandler;
+
+impl DependencyHandler {
+    fn open_orders() -> sea_orm::Condition {
+        sea_orm::Condition::all()
+            .add(order_restaurant::Column::Status.ne(ORDER_STATUS_COMPLETE))
+            .add(order_restaurant::Column::Status.ne(ORDER_STATUS_CANCELLED))
+    }
+
+    fn open_maintenance() -> sea_orm::Condition {
+        sea_orm::Condition::all().add(maintenance_schedule::Column::Status.is_in(MAINTENANCE_OPEN_STATUSES))
+    }
+
+    async fn count<E, C>(conn: &C, query: sea_orm::Select<E>) -> Result<u64, AppError>
+    where
+        E: EntityTrait,
+        E::Model: Sync,
+        C: ConnectionTrait,
+    {
+        query.count(conn).await.map_err(|err| AppError::Database(format!("Error checking dependencies: {}", err)))
+    }
+
+    pub async fn check_ride<C: ConnectionTrait>(conn: &C, ride_id: &str) -> Result<DependencyReport, AppError> {
+        let mut report = DependencyReport::new("ride", ride_id);
+        let queued = Self::count(conn, ride_queue::Entity::find().filter(ride_queue::Column::RideId.eq(ride_id))).await?;
+        report.add("queue entries", queued, Some("removed from the queue"));
+        let tasks = Self::count(
+            conn,
+            maintenance_schedule::Entity::find()
+                .filter(maintenance_schedule::Column::RideId.eq(ride_id))
+                .filter(Self::open_maintenance()),
+        ).await?;
+        report.add("open maintenance tasks", tasks, Some("cancelled"));
+        Ok(report)
+    }
+
+    pub async fn cascade_ride<C: ConnectionTrait>(conn: &C, ride_id: &str, actor_id: &str) -> Result<(), AppError> {
+        Self::clear_queue(conn, ride_queue::Column::RideId.eq(ride_id), actor_id).await?;
+        Self::cancel_maintenance(conn, maintenance_schedule::Column::RideId.eq(ride_id), actor_id).await
+    }
+
+    pub async fn check_restaurant<C: ConnectionTrait>(conn: &C, restaurant_id: &str) -> Result<DependencyReport, AppError> {
+        let mut report = DependencyReport::new("restaurant", restaurant_id);
+        let orders = Self::count(
+            conn,
+            order_restaurant::Entity::find()
+                .filter(order_restaurant::Column::RestaurantId.eq(restaurant_id))
+                .filter(Self::open_orders()),
+        ).await?;
+        report.add("open orders", orders, Some("cancelled and refunded"));
+        let items = Self::count(
+            conn,
+            menu_item::Entity::find()
+                .filter(menu_item::Column::RestaurantId.eq(restaurant_id))
+                .filter(menu_item::Column::DeletedAt.is_null()),
+        ).await?;
+        report.add("menu items", items, Some("archived"));
+        Ok(report)
+    }
+
+    pub async fn cascade_restaurant<C: ConnectionTrait>(conn: &C, restaurant_id: &str, now: chrono::NaiveDateTime, actor_id: &str) -> Result<(), AppError> {
+        Self::cancel_orders(conn, order_restaurant::Column::RestaurantId.eq(restaurant_id), actor_id).await?;
+        let items = menu_item::Entity::find()
+            .filter(menu_item::Column::RestaurantId.eq(restaurant_id))
+            .filter(menu_item::Column::DeletedAt.is_null())
+            .all(conn)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching menu items: {}", err)))?;
+        for item in items {
+            let before = AuditEventHandler::snapshot(&item);
+            let mut active_item: menu_item::ActiveModel = item.into();
+            active_item.deleted_at = Set(Some(now));
+            let archived = active_item.update(conn).await
+                .map_err(|err| AppError::Database(format!("Error archiving menu items: {}", err)))?;
+            AuditEventHandler::record(conn, Some(actor_id), ACTION_UPDATE, "menu_item", &archived.menu_item_id, before, AuditEventHandler::snapshot(&archived)).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn check_menu_item<C: ConnectionTrait>(conn: &C, menu_item_id: &str) -> Result<DependencyReport, AppError> {
+        let mut report = DependencyReport::new("menu_item", menu_item_id);
+        let orders = Self::count(
+            conn,
+            order_restaurant::Entity::find()
+                .filter(order_restaurant::Column::MenuItemId.eq(menu_item_id))
+                .filter(Self::open_orders()),
+        ).await?;
+        report.add("open orders", orders, Some("cancelled and refunded"));
+        Ok(report)
+    }
+
+    pub async fn cascade_menu_item<C: ConnectionTrait>(conn: &C, menu_item_id: &str, actor_id: &str) -> Result<(), AppError> {
+        Self::cancel_orders(conn, order_restaurant::Column::MenuItemId.eq(menu_item_id), actor_id).await
+    }
+
+    pub async fn check_store<C: ConnectionTrait>(conn: &C, store_id: &str) -> Result<DependencyReport, AppError> {
+        let mut report = DependencyReport::new("store", store_id);
+        let on_sale = Self::count(
+            conn,
+            souvenir::Entity::find()
+                .filter(souvenir::Column::StoreId.eq(store_id))
+                .filter(souvenir::Column::Stock.gt(0)),
+        ).await?;
+        report.add("souvenirs in stock", on_sale, Some("taken off sale (stock set to 0)"));
+        Ok(report)
+    }
+
+    pub async fn cascade_store<C: ConnectionTrait>(conn: &C, store_id: &str, actor_id: &str) -> Result<(), AppError> {
+        let on_sale = souvenir::Entity::find()
+            .filter(souvenir::Column::StoreId.eq(store_id))
+            .filter(souvenir::Column::Stock.gt(0))
+            .all(conn)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching souvenirs: {}", err)))?;
+        for item in on_sale {
+            let before = AuditEventHandler::snapshot(&item);
+            let mut active_item: souvenir::ActiveModel = item.into();
+            active_item.stock = Set(0);
+            let taken_off = active_item.update(conn).await
+                .map_err(|err| AppError::Database(format!("Error taking souvenirs off sale: {}", err)))?;
+            AuditEventHandler::record(conn, Some(actor_id), ACTION_UPDATE, "souvenir", &taken_off.souvenir_id, before, AuditEventHandler::snapshot(&taken_off)).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn check_customer<C: ConnectionTrait>(conn: &C, customer_id: &str) -> Result<DependencyReport, AppError> {
+        let mut report = DependencyReport::new("customer", customer_id);
+        let queued = Self::count(conn, ride_queue::Entity::find().filter(ride_queue::Column::CustomerId.eq(customer_id))).await?;
+        report.add("ride queue entries", queued, Some("removed from the queue"));
+        let orders = Self::count(
+            conn,
+            order_restaurant::Entity::find()
+                .filter(order_restaurant::Column::CustomerId.eq(customer_id))
+                .filter(Self::open_orders()),
+        ).await?;
+        report.add("open orders", orders, Some("cancelled and refunded"));
+        Ok(report)
+    }
+
+    pub async fn cascade_customer<C: ConnectionTrait>(conn: &C, customer_id: &str, actor_id: &str) -> Result<(), AppError> {
+        Self::clear_queue(conn, ride_queue::Column::CustomerId.eq(customer_id), actor_id).await?;
+        Self::cancel_orders(conn, order_restaurant::Column::CustomerId.eq(customer_id), actor_id).await
+    }
+
+    pub async fn check_staff<C: ConnectionTrait>(conn: &C, staff_id: &str) -> Result<DependencyReport, AppError> {
+        let mut report = DependencyReport::new("staff", staff_id);
+        // A ride always needs an operator, so these have to be reassigned first
+        let rides = Self::count(
+            conn,
+            ride::Entity::find()
+                .filter(ride::Column::StaffId.eq(staff_id))
+                .filter(ride::Column::DeletedAt.is_null()),
+        ).await?;
+        report.add("rides operated", rides, None);
+        let tasks = Self::count(
+            conn,
+            maintenance_schedule::Entity::find()
+                .filter(maintenance_schedule::Column::StaffId.eq(staff_id))
+                .filter(Self::open_maintenance()),
+        ).await?;
+        report.add("open maintenance tasks", tasks, Some("cancelled"));
+        let tickets = Self::count(
+            conn,
+            customer_service_ticket::Entity::find()
+                .filter(customer_service_ticket::Column::AssignedStaffId.eq(staff_id))
+                .filter(customer_service_ticket::Column::Status.ne("Resolved")),
+        ).await?;
+        report.add("assigned customer service tickets", tickets, Some("returned to the queue"));
+        Ok(report)
+    }
+
+    pub async fn cascade_staff<C: ConnectionTrait>(conn: &C, staff_id: &str, actor_id: &str) -> Result<(), AppError> {
+        Self::cancel_maintenance(conn, maintenance_schedule::Column::StaffId.eq(staff_id), actor_id).await?;
+        let tickets = customer_service_ticket::Entity::find()
+            .filter(customer_service_ticket::Column::AssignedStaffId.eq(staff_id))
+            .filter(customer_service_ticket::Column::Status.ne("Resolved"))
+            .all(conn)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching assigned tickets: {}", err)))?;
+        for ticket in tickets {
+            let before = AuditEventHandler::snapshot(&ticket);
+            let mut active_ticket: customer_service_ticket::ActiveModel = ticket.into();
+            active_ticket.assigned_staff_id = Set(None);
+            let unassigned = active_ticket.update(conn).await
+                .map_err(|err| AppError::Database(format!("Error unassigning tickets: {}", err)))?;
+            AuditEventHandler::record(conn, Some(actor_id), ACTION_UPDATE, "customer_service_ticket", &unassigned.ticket_id, before, AuditEventHandler::snapshot(&unassigned)).await?;
+        }
+        Ok(())
+    }
+
+    async fn cancel_maintenance<C: ConnectionTrait>(conn: &C, owner: sea_orm::sea_query::SimpleExpr, actor_id: &str) -> Result<(), AppError> {
+        let tasks = maintenance_schedule::Entity::find()
+            .filter(owner)
+            .filter(Self::open_maintenance())
+            .all(conn)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching maintenance tasks: {}", err)))?;
+        for task in tasks {
+            let before = AuditEventHandler::snapshot(&task);
+            let mut active_task: maintenance_schedule::ActiveModel = task.into();
+            active_task.status = Set(MAINTENANCE_STATUS_CANCELLED.to_string());
+            let cancelled = active_task.update(conn).await
+                .map_err(|err| AppError::Database(format!("Error cancelling maintenance tasks: {}", err)))?;
+            AuditEventHandler::record(conn, Some(actor_id), ACTION_UPDATE, "maintenance_schedule", &cancelled.maintenance_task_id, before, AuditEventHandler::snapshot(&cancelled)).await?;
+        }
+        Ok(())
+    }
+
+    // Take customers out of ride queues, one audited delete per entry
+    async fn clear_queue<C: ConnectionTrait>(conn: &C, owner: sea_orm::sea_query::SimpleExpr, actor_id: &str) -> Result<(), AppError> {
+        let entries = ride_queue::Entity::find()
+            .filter(owner)
+            .all(conn)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching ride queue entries: {}", err)))?;
+        for entry in entries {
+            ride_queue::Entity::delete_by_id(entry.ride_queue_id.clone())
+                .exec(conn)
+                .await
+                .map_err(|err| AppError::Database(format!("Error clearing ride queue entries: {}", err)))?;
+            AuditEventHandler::record(conn, Some(actor_id), ACTION_DELETE, "ride_queue", &entry.ride_queue_id, AuditEventHandler::snapshot(&entry), None).await?;
+        }
+        Ok(())
+    }
+
+    // Cancel open restaurant orders and give customers their money back. Callers invalidate
+    // TAG_CUSTOMER once the refunds are committed.
+    async fn cancel_orders<C: ConnectionTrait>(conn: &C, owner: sea_orm::sea_query::SimpleExpr, actor_id: &str) -> Result<(), AppError> {
+        let orders = order_restaurant::Entity::find()
+            .filter(owner)
+            .filter(Self::open_orders())
+            .find_also_related(menu_item::Entity)
+            .all(conn)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching open orders: {}", err)))?;
+
+        for (order, item) in orders {
+            let item = item.ok_or_else(|| AppError::Internal(format!("Order {} has no menu item to refund", order.order_restaurant_id)))?;
+            let refund = validation::parse_price(&item.price)? * Decimal::from(order.quantity);
+            if refund > Decimal::ZERO {
+                CustomerHandler::adjust_balance(conn, &order.customer_id, refund, Some(actor_id)).await?;
+            }
+
+            let before = AuditEventHandler::snapshot(&order);
+            let mut active_order: order_restaurant::ActiveModel = order.into();
+            active_order.status = Set(ORDER_STATUS_CANCELLED.to_string());
+            let cancelled = active_order.update(conn).await
+                .map_err(|err| AppError::Database(format!("Error cancelling order: {}", err)))?;
+            AuditEventHandler::record(conn, Some(actor_id), ACTION_UPDATE, "order_restaurant", &cancelled.order_restaurant_id, before, AuditEventHandler::snapshot(&cancelled)).await?;
+        }
+        Ok(())
+    }
+
+    // The response a delete gives back when dependents stop it, or None if it may go ahead
+    pub fn blocked(report: &DependencyReport, cascade: bool) -> Option<ApiResponse<DependencyReport>> {
+        if report.is_clear() || (cascade && report.can_cascade()) {
+            return None;
+        }
+        let message = if cascade {
+            format!("Cannot delete {}: {} must be reassigned first", report.entity_type.replace('_', " "), report.summary())
+        } else {
+            format!("Cannot delete {}: {}", report.entity_type.replace('_', " "), report.summary())
+        };
+        Some(ApiResponse::Error { data: Some(report.clone()), error: AppError::Conflict(message) })
+    }
+
+    /// Dependency check for any archivable record, so the UI can warn before deleting
+    pub async fn check_dependencies(
+        state: &AppState,
+        entity_type: String,
+        entity_id: String,
+    ) -> Result<ApiResponse<DependencyReport>, AppError> {
+        let report = match entity_type.as_str() {
+            "ride" => Self::check_ride(&state.db, &entity_id).await?,
+            "restaurant" => Self::check_restaurant(&state.db, &entity_id).await?,
+            "menu_item" => Self::check_menu_item(&state.db, &entity_id).await?,
+            "store" => Self::check_store(&state.db, &entity_id).await?,
+            "customer" => Self::check_customer(&state.db, &entity_id).await?,
+            "staff" => Self::check_staff(&state.db, &entity_id).await?,
+            _ => return Err(AppError::validation(format!("Unknown entity type '{}'", entity_type))),
+        };
+        Ok(ApiResponse::success(report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controllers::restaurant_handler::RestaurantHandler;
+    use crate::controllers::ride_handler::RideHandler;
+    use crate::controllers::staff_handler::StaffHandler;
+    use crate::error::ErrorCode;
+    use crate::test_support::{self, data, error, ACTOR};
+    use entity::{audit_event, customer, restaurant};
+    use sea_orm::sea_query::Expr;
+
+    // Seeded orders are Complete; move them back into the kitchen
+    async fn reopen_orders(state: &AppState) {
+        order_restaurant::Entity::update_many()
+            .col_expr(order_restaurant::Column::Status, Expr::value("Pending"))
+            .exec(&state.db)
+            .await
+            .unwrap();
+    }
+
+    fn blocked_report(response: ApiResponse<DependencyReport>) -> (DependencyReport, AppError) {
+        match response {
+            ApiResponse::Error { data: Some(report), error } => (report, error),
+            _ => panic!("expected the delete to be blocked"),
+        }
+    }
+
+    #[tokio::test]
+    async fn the_report_counts_only_live_dependents() {
+        let state = test_support::test_state().await;
+        let customer = test_support::seed_customer(&state, "0").await;
+        let restaurant = test_support::seed_restaurant(&state).await;
+        let ramen = test_support::seed_menu_item(&state, &restaurant.restaurant_id, "30000").await;
+        test_support::seed_menu_item(&state, &restaurant.restaurant_id, "15000").await;
+        test_support::seed_order_restaurant(&state, &ramen, &customer.customer_id, 1).await;
+
+        // Completed orders are history, not dependents
+        let report = data(DependencyHandler::check_dependencies(&state, "restaurant".to_string(), restaurant.restaurant_id.clone()).await);
+        assert_eq!(report.summary(), "2 menu items");
+        assert!(report.can_cascade());
+
+        reopen_orders(&state).await;
+        test_support::seed_order_restaurant(&state, &ramen, &customer.customer_id, 1).await;
+        let report = data(DependencyHandler::check_dependencies(&state, "restaurant".to_string(), restaurant.restaurant_id).await);
+        assert_eq!(report.summary(), "1 open orders, 2 menu items");
+
+        let err = error(DependencyHandler::check_dependencies(&state, "parking_lot".to_string(), "lot".to_string()).await);
+        assert_eq!(err.code(), ErrorCode::ValidationFailed);
+    }
+
+    #[tokio::test]
+    async fn a_cascading_delete_refunds_open_orders_and_archives_the_menu() {
+        let state = test_support::test_state().await;
+        let customer = test_support::seed_customer(&state, "10000").await;
+        let restaurant = test_support::seed_restaurant(&state).await;
+        let ramen = test_support::seed_menu_item(&state, &restaurant.restaurant_id, "30000").await;
+        test_support::seed_order_restaurant(&state, &ramen, &customer.customer_id, 2).await;
+        reopen_orders(&state).await;
+
+        // Without cascade nothing changes and the caller sees what is in the way
+        let response = RestaurantHandler::delete_restaurant_data(&state, restaurant.restaurant_id.clone(), false, ACTOR.to_string()).await.unwrap();
+        let (report, err) = blocked_report(response);
+        assert_eq!(err.code(), ErrorCode::Conflict);
+        assert_eq!(report.summary(), "1 open orders, 1 menu items");
+        let untouched = restaurant::Entity::find_by_id(restaurant.restaurant_id.clone()).one(&state.db).await.unwrap().unwrap();
+        assert!(untouched.deleted_at.is_none());
+
+        data(RestaurantHandler::delete_restaurant_data(&state, restaurant.restaurant_id.clone(), true, ACTOR.to_string()).await);
+        let refunded = customer::Entity::find_by_id(customer.customer_id).one(&state.db).await.unwrap().unwrap();
+        assert_eq!(refunded.virtual_balance, "70000");
+        let order = order_restaurant::Entity::find().one(&state.db).await.unwrap().unwrap();
+        assert_eq!(order.status, ORDER_STATUS_CANCELLED);
+        let item = menu_item::Entity::find_by_id(ramen.menu_item_id).one(&state.db).await.unwrap().unwrap();
+        assert!(item.deleted_at.is_some());
+        let archived = restaurant::Entity::find_by_id(restaurant.restaurant_id).one(&state.db).await.unwrap().unwrap();
+        assert!(archived.deleted_at.is_some());
+
+        // The refund, the cancelled order and the archived menu item each leave a trail
+        let audit = audit_event::Entity::find()
+            .filter(audit_event::Column::EntityType.is_in(["customer", "order_restaurant", "menu_item"]))
+            .all(&state.db)
+            .await
+            .unwrap();
+        let mut trail: Vec<_> = audit.iter().map(|event| event.entity_type.as_str()).collect();
+        trail.sort_unstable();
+        assert_eq!(trail, ["customer", "menu_item", "order_restaurant"]);
+        assert!(audit.iter().all(|event| event.actor_id.as_deref() == Some(ACTOR)));
+    }
+
+    #[tokio::test]
+    async fn clearing_a_ride_queue_audits_every_removed_entry() {
+        let state = test_support::test_state().await;
+        let operator = test_support::seed_staff(&state, "RideStaff").await;
+        let ride = test_support::seed_ride(&state, &operator.staff_id, "50000").await;
+        let first = test_support::seed_customer(&state, "0").await;
+        let second = test_support::seed_customer(&state, "0").await;
+        test_support::seed_ride_queue(&state, &ride.ride_id, &first.customer_id).await;
+        test_support::seed_ride_queue(&state, &ride.ride_id, &second.customer_id).await;
+
+        data(RideHandler::delete_ride_data(&state, ride.ride_id.clone(), true, ACTOR.to_string()).await);
+        assert_eq!(ride_queue::Entity::find().count(&state.db).await.unwrap(), 0);
+        let audit = audit_event::Entity::find()
+            .filter(audit_event::Column::EntityType.eq("ride_queue"))
+            .all(&state.db)
+            .await
+            .unwrap();
+        assert_eq!(audit.len(), 2);
+        assert!(audit.iter().all(|event| event.action == ACTION_DELETE && event.actor_id.as_deref() == Some(ACTOR)));
+    }
+
+    #[tokio::test]
+    async fn a_ride_operator_must_be_reassigned_even_with_cascade() {
+        let state = test_support::test_state().await;
+        let operator = test_support::seed_staff(&state, "RideStaff").await;
+        test_support::seed_ride(&state, &operator.staff_id, "50000").await;
+
+        let response = StaffHandler::delete_staff_data(&state, operator.staff_id.clone(), true, ACTOR.to_string()).await.unwrap();
+        let (report, err) = blocked_report(response);
+        assert!(!report.can_cascade());
+        assert_eq!(err.message(), "Cannot delete staff: 1 rides operated must be reassigned first");
+    }
+}
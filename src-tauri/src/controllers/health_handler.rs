@@ -0,0 +1,104 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+use crate::clock;
+use crate::error::AppError;
+use crate::{ApiResponse, AppState};
+
+pub const HEALTH_UP: &str = "up";
+pub const HEALTH_DEGRADED: &str = "degraded";
+pub const HEALTH_DOWN: &str = "down";
+
+// A probe that has not answered by now counts as down
+const PROBE_TIMEOUT_SECS: u64 = 5;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ComponentHealth {
+    pub status: String,
+    pub backend: String,
+    pub latency_ms: Option<u64>,
+    pub detail: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct HealthReport {
+    pub status: String, // Worst of the components: the app is unusable without the database
+    pub database: ComponentHealth,
+    pub cache: ComponentHealth,
+    pub startup_error: Option<String>,
+    pub checked_at: NaiveDateTime,
+}
+
+pub struct HealthHandler;
+
+impl HealthHandler {
+    // Run a probe, timing it and turning a timeout into an error
+    async fn probe<F: std::future::Future<Output = Result<(), String>>>(probe: F) -> (Result<(), String>, u64) {
+        let started = Instant::now();
+        let result = match tokio::time::timeout(Duration::from_secs(PROBE_TIMEOUT_SECS), probe).await {
+            Ok(result) => result,
+            Err(_) => Err(format!("no response within {} seconds", PROBE_TIMEOUT_SECS)),
+        };
+        (result, started.elapsed().as_millis() as u64)
+    }
+
+    pub async fn health_check(state: &AppState) -> Result<ApiResponse<HealthReport>, AppError> {
+        let (db_result, db_latency) = Self::probe(async { state.db.ping().await.map_err(|err| err.to_string()) }).await;
+        let database = ComponentHealth {
+            status: if db_result.is_ok() { HEALTH_UP } else { HEALTH_DOWN }.to_string(),
+            backend: "postgres".to_string(),
+            latency_ms: db_result.is_ok().then_some(db_latency),
+            detail: db_result.err(),
+        };
+
+        // Reads and writes keep working from the in-process cache while Redis is down
+        let (cache_result, cache_latency) = Self::probe(state.cache.ping()).await;
+        let cache = match (cache_result, state.cache.degraded_reason()) {
+            (Ok(()), None) => ComponentHealth {
+                status: HEALTH_UP.to_string(),
+                backend: state.cache.backend_name().to_string(),
+                latency_ms: Some(cache_latency),
+                detail: None,
+            },
+            (result, reason) => ComponentHealth {
+                status: HEALTH_DEGRADED.to_string(),
+                backend: state.cache.backend_name().to_string(),
+                latency_ms: None,
+                detail: reason.or(result.err()),
+            },
+        };
+
+        let status = if database.status == HEALTH_DOWN {
+            HEALTH_DOWN
+        } else if cache.status != HEALTH_UP {
+            HEALTH_DEGRADED
+        } else {
+            HEALTH_UP
+        };
+        Ok(ApiResponse::success(HealthReport {
+            status: status.to_string(),
+            database,
+            cache,
+            startup_error: None,
+            checked_at: clock::jakarta_now(),
+        }))
+    }
+
+    // Report for when the app state could not be built at all, e.g. a missing DATABASE_URL
+    pub fn startup_failed(error: &str) -> HealthReport {
+        let not_started = |backend: &str| ComponentHealth {
+            status: HEALTH_DOWN.to_string(),
+            backend: backend.to_string(),
+            latency_ms: None,
+            detail: Some("Not started".to_string()),
+        };
+        HealthReport {
+            status: HEALTH_DOWN.to_string(),
+            database: not_started("postgres"),
+            cache: not_started("redis"),
+            startup_error: Some(error.to_string()),
+            checked_at: clock::jakarta_now(),
+        }
+    }
+}
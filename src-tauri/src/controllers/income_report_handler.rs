@@ -1,24 +1,19 @@
-use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use chrono::{Datelike, Duration, TimeZone, Utc};
 use std::collections::HashMap;
 // use std::str::FromStr;
 use rust_decimal::Decimal; // For Decimal handling if needed
+use sea_orm::EntityTrait;
 use rust_decimal::prelude::{FromPrimitive, FromStr, ToPrimitive};
 
+use crate::error::AppError;
 use crate::{ApiResponse, AppState};
 use crate::controllers::{
     order_restaurant_handler::OrderRestaurantHandler,
     order_souvenir_handler::OrderSouvenirHandler,
     ride_queue_handler::RideQueueHandler,
-    restaurant_handler::RestaurantHandler, // For restaurant names
-    menu_item_handler::MenuItemHandler, // For menu item prices
-    store_handler::StoreHandler, // For store names
     souvenir_handler::SouvenirHandler, // For souvenir prices
-    ride_handler::RideHandler, // For ride names and prices
-};
-use entity::{
-    order_restaurant, order_souvenir, ride_queue,
-    restaurant, menu_item, store, souvenir, ride
 };
+use entity::{restaurant, menu_item, store, ride};
 use serde::Serialize;
 
 // Define the Report Structs (matching frontend)
@@ -82,7 +77,7 @@ impl IncomeReportHandler {
     pub async fn generate_income_report(
         state: &AppState,
         period: String, // "day", "week", "month"
-    ) -> Result<ApiResponse<IncomeReport>, String> {
+    ) -> Result<ApiResponse<IncomeReport>, AppError> {
         let now = Utc::now();
         let (start_time, end_time, period_str) = match period.as_str() {
             "day" => {
@@ -117,58 +112,40 @@ impl IncomeReportHandler {
                     start.format("%Y-%m").to_string()
                 )
             },
-            _ => return Err("Invalid time period specified. Use 'day', 'week', or 'month'".to_string()),
+            _ => return Err(AppError::validation("Invalid time period specified. Use 'day', 'week', or 'month'".to_string())),
         };
 
         // --- Fetch Data Sequentially ---
         let restaurant_orders_res = OrderRestaurantHandler::get_restaurant_orders_in_range(state, start_time, end_time).await;
         let souvenir_orders_res = OrderSouvenirHandler::get_souvenir_orders_in_range(state, start_time, end_time).await;
         let ride_queues_res = RideQueueHandler::get_ride_queues_in_range(state, start_time, end_time).await;
-        let all_restaurants_res = RestaurantHandler::view_restaurants(state).await;
-        let all_menu_items_res = MenuItemHandler::view_menu_items(state, None).await;
-        let all_stores_res = StoreHandler::view_stores(state).await;
+        // Archived restaurants, menu items, stores and rides still earned income in the period,
+        // so names and prices are looked up across every row, not just the active lists
+        let all_restaurants_res = restaurant::Entity::find().all(&state.db).await;
+        let all_menu_items_res = menu_item::Entity::find().all(&state.db).await;
+        let all_stores_res = store::Entity::find().all(&state.db).await;
         let all_souvenirs_res = SouvenirHandler::view_souvenirs(state, None).await;
-        let all_rides_res = RideHandler::view_rides(state).await;
+        let all_rides_res = ride::Entity::find().all(&state.db).await;
 
         // --- Correctly Extract Data (same as before) ---
-        let restaurant_orders = match restaurant_orders_res? {
-            ApiResponse::Success { data, .. } => data,
-            ApiResponse::Error { message, .. } => return Err(message),
-        };
+        let restaurant_orders = restaurant_orders_res?.into_data()?;
         // ... (extract other data similarly) ...
-         let souvenir_orders = match souvenir_orders_res? {
-            ApiResponse::Success { data, .. } => data,
-            ApiResponse::Error { message, .. } => return Err(message),
-        };
-        let ride_queues = match ride_queues_res? {
-            ApiResponse::Success { data, .. } => data,
-            ApiResponse::Error { message, .. } => return Err(message),
-        };
-         let all_restaurants = match all_restaurants_res? {
-             ApiResponse::Success { data, .. } => data
-                 .into_iter().map(|r| (r.restaurant_id.clone(), r.name.clone())).collect::<HashMap<_,_>>(),
-             ApiResponse::Error { message, .. } => return Err(message),
-         };
-        let all_menu_items = match all_menu_items_res? {
-             ApiResponse::Success { data, .. } => data
-                 .into_iter().map(|m| (m.menu_item_id.clone(), Decimal::from_str(&m.price).unwrap_or_default())).collect::<HashMap<_,_>>(),
-             ApiResponse::Error { message, .. } => return Err(message),
-         };
-        let all_stores = match all_stores_res? {
-             ApiResponse::Success { data, .. } => data
-                 .into_iter().map(|s| (s.store_id.clone(), s.name.clone())).collect::<HashMap<_,_>>(),
-             ApiResponse::Error { message, .. } => return Err(message),
-         };
-        let all_souvenirs = match all_souvenirs_res? {
-             ApiResponse::Success { data, .. } => data
-                 .into_iter().map(|s| (s.souvenir_id.clone(), s.price)).collect::<HashMap<_,_>>(),
-             ApiResponse::Error { message, .. } => return Err(message),
-         };
-        let all_rides = match all_rides_res? {
-             ApiResponse::Success { data, .. } => data
-                 .into_iter().map(|r| (r.ride_id.clone(), (r.name.clone(), Decimal::from_str(&r.price).unwrap_or_default()))).collect::<HashMap<_,_>>(),
-             ApiResponse::Error { message, .. } => return Err(message),
-         };
+         let souvenir_orders = souvenir_orders_res?.into_data()?;
+        let ride_queues = ride_queues_res?.into_data()?;
+         let all_restaurants = all_restaurants_res
+             .map_err(|err| AppError::Database(format!("Error fetching restaurants: {}", err)))?
+             .into_iter().map(|r| (r.restaurant_id.clone(), r.name.clone())).collect::<HashMap<_,_>>();
+        let all_menu_items = all_menu_items_res
+             .map_err(|err| AppError::Database(format!("Error fetching menu items: {}", err)))?
+             .into_iter().map(|m| (m.menu_item_id.clone(), Decimal::from_str(&m.price).unwrap_or_default())).collect::<HashMap<_,_>>();
+        let all_stores = all_stores_res
+             .map_err(|err| AppError::Database(format!("Error fetching stores: {}", err)))?
+             .into_iter().map(|s| (s.store_id.clone(), s.name.clone())).collect::<HashMap<_,_>>();
+        let all_souvenirs = all_souvenirs_res?.into_data()?
+             .into_iter().map(|s| (s.souvenir_id.clone(), s.price)).collect::<HashMap<_,_>>();
+        let all_rides = all_rides_res
+             .map_err(|err| AppError::Database(format!("Error fetching rides: {}", err)))?
+             .into_iter().map(|r| (r.ride_id.clone(), (r.name.clone(), Decimal::from_str(&r.price).unwrap_or_default()))).collect::<HashMap<_,_>>();
 
         // --- Process Consumption Data ---
         let mut restaurant_income_map: HashMap<String, RestaurantIncome> = HashMap::new();
@@ -259,4 +236,45 @@ impl IncomeReportHandler {
 
         Ok(ApiResponse::success(final_report))
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::test_support::{self, data, error};
+
+    #[tokio::test]
+    async fn report_totals_income_per_division() {
+        let state = test_support::test_state().await;
+        let customer = test_support::seed_customer(&state, "0").await;
+        let operator = test_support::seed_staff(&state, "Ride Staff").await;
+        let restaurant = test_support::seed_restaurant(&state).await;
+        let ramen = test_support::seed_menu_item(&state, &restaurant.restaurant_id, "25000").await;
+        let store = test_support::seed_store(&state).await;
+        let plush = test_support::seed_souvenir(&state, &store.store_id, Decimal::new(1500050, 2), 20).await;
+        let coaster = test_support::seed_ride(&state, &operator.staff_id, "50000").await;
+
+        test_support::seed_order_restaurant(&state, &ramen, &customer.customer_id, 2).await;
+        test_support::seed_order_souvenir(&state, &plush, &customer.customer_id, 1).await;
+        test_support::seed_order_souvenir(&state, &plush, &customer.customer_id, 2).await;
+        test_support::seed_ride_queue(&state, &coaster.ride_id, &customer.customer_id).await;
+        test_support::seed_ride_queue(&state, &coaster.ride_id, &customer.customer_id).await;
+
+        let report = data(IncomeReportHandler::generate_income_report(&state, "month".to_string()).await);
+        assert_eq!(report.consumption.total, 50000.0);
+        assert_eq!((report.consumption.restaurants[0].order_count, report.consumption.restaurants[0].items_sold), (1, 2));
+        assert_eq!(report.marketing.total, 45001.5);
+        assert_eq!((report.marketing.stores[0].order_count, report.marketing.stores[0].items_sold), (2, 3));
+        assert_eq!(report.operations.total, 100000.0);
+        assert_eq!(report.operations.rides[0].ticket_count, 2);
+        assert_eq!(report.grand_total, 195001.5);
+    }
+
+    #[tokio::test]
+    async fn report_rejects_unknown_periods() {
+        let state = test_support::test_state().await;
+        assert!(data(IncomeReportHandler::generate_income_report(&state, "day".to_string()).await).grand_total == 0.0);
+        let err = error(IncomeReportHandler::generate_income_report(&state, "year".to_string()).await);
+        assert_eq!(err.code(), ErrorCode::ValidationFailed);
+    }
+}
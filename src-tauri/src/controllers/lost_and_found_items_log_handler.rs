@@ -1,39 +1,482 @@
-use chrono::Utc;
-use sea_orm::{ActiveModelTrait, EntityTrait, QueryOrder, ColumnTrait};
-use entity::lost_and_found_items_log::{self, ActiveModel, Model};
+use chrono::{Duration, NaiveDateTime};
+use sea_orm::{sea_query::Expr, ActiveModelTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, ColumnTrait, TransactionTrait, TryIntoModel};
+use entity::{customer, lost_and_found_log_event, staff};
+use entity::audit_event::{ACTION_CREATE, ACTION_DELETE, ACTION_RESTORE, ACTION_UPDATE};
+use entity::lost_and_found_items_log::{self, Model};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::env;
 use uuid::Uuid;
-use crate::{cache_delete, cache_get, cache_set, ApiResponse, AppState};
+use crate::clock;
+use crate::cache::CachedView;
+use crate::repository::CachedRepository;
+use crate::error::{AppError, FieldError};
+use crate::validation::{self, Validator};
+use crate::{ApiResponse, AppState};
+use crate::controllers::audit_event_handler::AuditEventHandler;
+use crate::controllers::chat_handler::ChatHandler;
+
+pub const STATUS_MISSING: &str = "Missing";
+pub const STATUS_FOUND: &str = "Found";
+pub const STATUS_RETURNED: &str = "Returned to Owner";
+pub const STATUS_DONATED: &str = "Donated";
+pub const STATUS_DISPOSED: &str = "Disposed";
+// Ways staff can confirm the person collecting an item is its owner
+const VERIFICATION_METHODS: [&str; 3] = ["CustomerAccount", "IdDocument", "ItemDescription"];
+// Found items unclaimed for this many days are flagged for donation or disposal
+const DEFAULT_RETENTION_DAYS: i64 = 30;
+
+// Weights of each signal in a match score (sum to 1.0)
+const TYPE_WEIGHT: f64 = 0.30;
+const NAME_WEIGHT: f64 = 0.25;
+const COLOR_WEIGHT: f64 = 0.20;
+const LOCATION_WEIGHT: f64 = 0.15;
+const TIME_WEIGHT: f64 = 0.10;
+// Pairs scoring below this are not suggested
+const MIN_MATCH_SCORE: f64 = 0.5;
+// Customers are only messaged about stronger matches
+const MIN_NOTIFY_SCORE: f64 = 0.65;
+// Time proximity decays to zero over this many hours (7 days)
+const MATCH_TIME_WINDOW_HOURS: f64 = 7.0 * 24.0;
+const DEFAULT_MATCH_LIMIT: u64 = 20;
+const MAX_MATCH_LIMIT: u64 = 100;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MatchScore {
+    pub total: f64,
+    pub type_score: f64,
+    pub name_score: f64,
+    pub color_score: f64,
+    pub location_score: f64,
+    pub time_score: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LostAndFoundMatch {
+    pub missing: Model,
+    pub found: Model,
+    pub score: MatchScore,
+}
+
+// Identity check recorded when an item is handed back
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OwnerVerification {
+    pub owner: String, // Name of the person collecting the item
+    pub customer_id: Option<String>,
+    pub method: String, // One of VERIFICATION_METHODS
+    pub reference: Option<String>, // Required for "IdDocument", e.g. last digits of the ID shown
+}
 
 pub struct LostAndFoundItemsLogHandler;
 
 impl LostAndFoundItemsLogHandler {
-    /// View all lost and found logs, ordered by timestamp (most recent first)
-    pub async fn view_logs(state: &AppState) -> Result<ApiResponse<Vec<Model>>, String> {
-        let cache_key = "view_logs_cache";
+    fn tokens(value: &str) -> HashSet<String> {
+        value
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|token| !token.is_empty())
+            .map(|token| token.to_lowercase())
+            .collect()
+    }
 
-        // Check Redis cache first
-        if let Some(cached_logs) = cache_get::<Vec<Model>>(&state.redis_pool, cache_key).await {
-            println!("Cache hit: Returning logs from Redis");
-            return Ok(ApiResponse::success(cached_logs));
+    // Jaccard overlap of the word sets ("Black iPhone" vs "iphone" = 0.5)
+    fn token_similarity(a: &str, b: &str) -> f64 {
+        let (a, b) = (Self::tokens(a), Self::tokens(b));
+        if a.is_empty() || b.is_empty() {
+            return 0.0;
         }
+        a.intersection(&b).count() as f64 / a.union(&b).count() as f64
+    }
 
-        // Cache miss: Query the database
-        println!("Cache miss: Querying database");
-        match lost_and_found_items_log::Entity::find()
-            .order_by_desc(lost_and_found_items_log::Column::Timestamp)
+    // Dice coefficient over character bigrams, tolerant of typos ("wallet" vs "walet")
+    fn bigram_similarity(a: &str, b: &str) -> f64 {
+        let bigrams = |value: &str| -> Vec<(char, char)> {
+            let chars: Vec<char> = value
+                .chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(|c| c.to_lowercase())
+                .collect();
+            chars.windows(2).map(|pair| (pair[0], pair[1])).collect()
+        };
+        let (a, mut b) = (bigrams(a), bigrams(b));
+        if a.is_empty() || b.is_empty() {
+            return 0.0;
+        }
+        let total = (a.len() + b.len()) as f64;
+        let mut shared = 0;
+        for bigram in &a {
+            if let Some(position) = b.iter().position(|other| other == bigram) {
+                b.swap_remove(position);
+                shared += 1;
+            }
+        }
+        2.0 * shared as f64 / total
+    }
+
+    fn text_similarity(a: &str, b: &str) -> f64 {
+        if a.trim().eq_ignore_ascii_case(b.trim()) && !a.trim().is_empty() {
+            return 1.0;
+        }
+        Self::token_similarity(a, b).max(Self::bigram_similarity(a, b))
+    }
+
+    // Where the owner last saw it vs where it was picked up; unknown on either side is neutral
+    fn location_similarity(last_seen: Option<&str>, found_at: Option<&str>) -> f64 {
+        match (last_seen, found_at) {
+            (Some(a), Some(b)) if !a.trim().is_empty() && !b.trim().is_empty() => Self::text_similarity(a, b),
+            _ => 0.5,
+        }
+    }
+
+    fn time_similarity(a: NaiveDateTime, b: NaiveDateTime) -> f64 {
+        let hours = (a - b).num_minutes().abs() as f64 / 60.0;
+        (1.0 - hours / MATCH_TIME_WINDOW_HOURS).max(0.0)
+    }
+
+    fn score_pair(missing: &Model, found: &Model) -> MatchScore {
+        let type_score = Self::text_similarity(&missing.r#type, &found.r#type);
+        let name_score = Self::text_similarity(&missing.name, &found.name);
+        let color_score = Self::text_similarity(&missing.color, &found.color);
+        let location_score = Self::location_similarity(
+            missing.last_seen_location.as_deref(),
+            found.found_location.as_deref(),
+        );
+        let time_score = Self::time_similarity(missing.timestamp, found.timestamp);
+        let total = type_score * TYPE_WEIGHT
+            + name_score * NAME_WEIGHT
+            + color_score * COLOR_WEIGHT
+            + location_score * LOCATION_WEIGHT
+            + time_score * TIME_WEIGHT;
+
+        MatchScore { total, type_score, name_score, color_score, location_score, time_score }
+    }
+
+    async fn open_logs_with_status(state: &AppState, status: &str) -> Result<Vec<Model>, AppError> {
+        lost_and_found_items_log::Entity::find()
+            .filter(lost_and_found_items_log::Column::Status.eq(status))
+            .filter(lost_and_found_items_log::Column::DeletedAt.is_null())
+            .filter(lost_and_found_items_log::Column::MatchedLogId.is_null())
             .all(&state.db)
             .await
-        {
-            Ok(logs) => {
-                // Cache the result for 60 seconds
-                cache_set(&state.redis_pool, cache_key, &logs, 60).await;
-                Ok(ApiResponse::success(logs))
+            .map_err(|err| AppError::Database(format!("Error fetching {} logs: {}", status, err)))
+    }
+
+    /// Suggest likely Missing/Found pairs, best first; restricted to pairs involving `log_id` when given
+    pub async fn suggest_matches(
+        state: &AppState,
+        log_id: Option<String>,
+        limit: Option<u64>,
+    ) -> Result<ApiResponse<Vec<LostAndFoundMatch>>, AppError> {
+        let limit = limit.unwrap_or(DEFAULT_MATCH_LIMIT).clamp(1, MAX_MATCH_LIMIT) as usize;
+
+        let (missing_logs, found_logs) = match log_id {
+            Some(log_id) => {
+                let log = match lost_and_found_items_log::Entity::find_by_id(log_id).one(&state.db).await {
+                    Ok(Some(log)) => log,
+                    Ok(None) => return Err(AppError::NotFound("Log entry not found".to_string())),
+                    Err(err) => return Err(AppError::Database(format!("Error fetching log entry: {}", err))),
+                };
+                if log.matched_log_id.is_some() {
+                    return Err(AppError::Conflict("Log entry has already been matched".to_string()));
+                }
+                match log.status.as_str() {
+                    STATUS_MISSING => (vec![log], Self::open_logs_with_status(state, STATUS_FOUND).await?),
+                    STATUS_FOUND => (Self::open_logs_with_status(state, STATUS_MISSING).await?, vec![log]),
+                    _ => return Err(AppError::validation("Only 'Missing' or 'Found' log entries can be matched".to_string())),
+                }
+            }
+            None => (
+                Self::open_logs_with_status(state, STATUS_MISSING).await?,
+                Self::open_logs_with_status(state, STATUS_FOUND).await?,
+            ),
+        };
+
+        let mut matches: Vec<LostAndFoundMatch> = missing_logs
+            .iter()
+            .flat_map(|missing| {
+                found_logs.iter().map(move |found| LostAndFoundMatch {
+                    missing: missing.clone(),
+                    found: found.clone(),
+                    score: Self::score_pair(missing, found),
+                })
+            })
+            .filter(|candidate| candidate.score.total >= MIN_MATCH_SCORE)
+            .collect();
+        matches.sort_by(|a, b| b.score.total.total_cmp(&a.score.total));
+        matches.truncate(limit);
+
+        Ok(ApiResponse::success(matches))
+    }
+
+    // Statuses an item may move to; Returned/Donated/Disposed are final
+    fn can_transition(from: &str, to: &str) -> bool {
+        matches!(
+            (from, to),
+            (STATUS_MISSING, STATUS_FOUND)
+                | (STATUS_MISSING, STATUS_RETURNED)
+                | (STATUS_FOUND, STATUS_RETURNED)
+                | (STATUS_FOUND, STATUS_DONATED)
+                | (STATUS_FOUND, STATUS_DISPOSED)
+        )
+    }
+
+    fn is_closed(status: &str) -> bool {
+        [STATUS_RETURNED, STATUS_DONATED, STATUS_DISPOSED].contains(&status)
+    }
+
+    // Fields a record must carry while in its status
+    fn check_required_fields(log: &Model) -> Result<(), AppError> {
+        let blank = |value: &Option<String>| value.as_deref().is_none_or(|v| v.trim().is_empty());
+        let mut missing_fields = Vec::new();
+        if log.name.trim().is_empty() {
+            missing_fields.push("name");
+        }
+        if log.r#type.trim().is_empty() {
+            missing_fields.push("type");
+        }
+        if log.color.trim().is_empty() {
+            missing_fields.push("color");
+        }
+        match log.status.as_str() {
+            STATUS_MISSING => {
+                if blank(&log.owner) {
+                    missing_fields.push("owner");
+                }
+                if blank(&log.last_seen_location) {
+                    missing_fields.push("last_seen_location");
+                }
+            }
+            STATUS_FOUND => {
+                if blank(&log.image) {
+                    missing_fields.push("image");
+                }
+                if blank(&log.finder) {
+                    missing_fields.push("finder");
+                }
+                if blank(&log.found_location) {
+                    missing_fields.push("found_location");
+                }
             }
-            Err(err) => Ok(ApiResponse::error(format!("Error fetching logs: {}", err))),
+            STATUS_RETURNED => {
+                if blank(&log.owner) {
+                    missing_fields.push("owner");
+                }
+                if blank(&log.owner_verification_method) {
+                    missing_fields.push("owner_verification_method");
+                }
+            }
+            _ => {}
+        }
+        if missing_fields.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::Validation {
+                message: format!("Missing required fields for '{}' status: {}", log.status, missing_fields.join(", ")),
+                fields: missing_fields
+                    .iter()
+                    .map(|field| FieldError::new(field, format!("Required for '{}' status", log.status)))
+                    .collect(),
+            })
         }
     }
 
-    /// Save a new lost and found log entry with auto-generated timestamp
+    async fn validate_verification<C: ConnectionTrait>(
+        conn: &C,
+        verification: &OwnerVerification,
+        staff_id: &str,
+    ) -> Result<(), AppError> {
+        if verification.owner.trim().is_empty() {
+            return Err(AppError::invalid_field("owner", "Owner name is required to return an item"));
+        }
+        if !VERIFICATION_METHODS.contains(&verification.method.as_str()) {
+            return Err(AppError::invalid_field("method", format!("Invalid verification method. Must be one of: {}", VERIFICATION_METHODS.join(", "))));
+        }
+        let has_reference = verification.reference.as_deref().is_some_and(|r| !r.trim().is_empty());
+        if verification.method == "IdDocument" && !has_reference {
+            return Err(AppError::invalid_field("reference", "An ID document reference is required for 'IdDocument' verification"));
+        }
+        match verification.customer_id.as_deref() {
+            Some(customer_id) => match customer::Entity::find_by_id(customer_id.to_string()).one(conn).await {
+                Ok(Some(_)) => {}
+                Ok(None) => return Err(AppError::NotFound("Customer not found".to_string())),
+                Err(err) => return Err(AppError::Database(format!("Error fetching customer: {}", err))),
+            },
+            None if verification.method == "CustomerAccount" => {
+                return Err(AppError::invalid_field("customer_id", "A customer id is required for 'CustomerAccount' verification"));
+            }
+            None => {}
+        }
+        match staff::Entity::find_by_id(staff_id.to_string()).one(conn).await {
+            Ok(Some(_)) => Ok(()),
+            Ok(None) => Err(AppError::NotFound("Staff not found".to_string())),
+            Err(err) => Err(AppError::Database(format!("Error fetching staff: {}", err))),
+        }
+    }
+
+    async fn record_event<C: ConnectionTrait>(
+        conn: &C,
+        log_id: &str,
+        action: &str,
+        from_status: Option<&str>,
+        to_status: Option<&str>,
+        actor_id: Option<String>,
+        details: Option<String>,
+    ) -> Result<(), AppError> {
+        let event = lost_and_found_log_event::ActiveModel {
+            event_id: sea_orm::ActiveValue::Set(Uuid::new_v4().to_string()),
+            log_id: sea_orm::ActiveValue::Set(log_id.to_string()),
+            action: sea_orm::ActiveValue::Set(action.to_string()),
+            from_status: sea_orm::ActiveValue::Set(from_status.map(str::to_string)),
+            to_status: sea_orm::ActiveValue::Set(to_status.map(str::to_string)),
+            actor_id: sea_orm::ActiveValue::Set(actor_id),
+            details: sea_orm::ActiveValue::Set(details),
+            created_at: sea_orm::ActiveValue::Set(clock::jakarta_now()),
+        };
+        lost_and_found_log_event::Entity::insert(event)
+            .exec(conn)
+            .await
+            .map(|_| ())
+            .map_err(|err| AppError::Database(format!("Error recording log history: {}", err)))
+    }
+
+    // Hand one open record back to its owner, guarded on its current status
+    async fn mark_returned<C: ConnectionTrait>(
+        conn: &C,
+        log: &Model,
+        verification: &OwnerVerification,
+        staff_id: &str,
+        matched_log_id: Option<&str>,
+        finder: Option<String>,
+        found_location: Option<String>,
+    ) -> Result<(), AppError> {
+        let now = clock::jakarta_now();
+        let mut update = lost_and_found_items_log::Entity::update_many()
+            .col_expr(lost_and_found_items_log::Column::Status, Expr::value(STATUS_RETURNED))
+            .col_expr(lost_and_found_items_log::Column::Owner, Expr::value(verification.owner.clone()))
+            .col_expr(lost_and_found_items_log::Column::OwnerCustomerId, Expr::value(verification.customer_id.clone()))
+            .col_expr(lost_and_found_items_log::Column::OwnerVerificationMethod, Expr::value(verification.method.clone()))
+            .col_expr(lost_and_found_items_log::Column::OwnerVerificationReference, Expr::value(verification.reference.clone()))
+            .col_expr(lost_and_found_items_log::Column::ReturnedBy, Expr::value(staff_id))
+            .col_expr(lost_and_found_items_log::Column::ReturnedAt, Expr::value(now))
+            .col_expr(lost_and_found_items_log::Column::Finder, Expr::value(finder))
+            .col_expr(lost_and_found_items_log::Column::FoundLocation, Expr::value(found_location))
+            .col_expr(lost_and_found_items_log::Column::Timestamp, Expr::value(now));
+        if let Some(matched_log_id) = matched_log_id {
+            update = update.col_expr(lost_and_found_items_log::Column::MatchedLogId, Expr::value(matched_log_id));
+        }
+        // Status guard so two staff handing the same item back cannot both win
+        let result = update
+            .filter(lost_and_found_items_log::Column::LogId.eq(log.log_id.clone()))
+            .filter(lost_and_found_items_log::Column::Status.eq(log.status.clone()))
+            .filter(lost_and_found_items_log::Column::MatchedLogId.is_null())
+            .exec(conn)
+            .await
+            .map_err(|err| AppError::Database(format!("Error returning log entry: {}", err)))?;
+        if result.rows_affected == 0 {
+            return Err(AppError::Conflict("Log entry was changed by someone else in the meantime".to_string()));
+        }
+
+        let details = format!(
+            "Returned to {} (verified by {}{})",
+            verification.owner,
+            verification.method,
+            verification.customer_id.as_deref().map(|id| format!(", customer {}", id)).unwrap_or_default()
+        );
+        let action = if matched_log_id.is_some() { "Matched" } else { "Returned" };
+        Self::record_event(conn, &log.log_id, action, Some(&log.status), Some(STATUS_RETURNED), Some(staff_id.to_string()), Some(details)).await
+    }
+
+    // Audit a status change inside its transaction by re-reading the row it produced
+    async fn audit_status_change<C: ConnectionTrait>(conn: &C, before: &Model, actor_id: &str) -> Result<(), AppError> {
+        let after = lost_and_found_items_log::Entity::find_by_id(before.log_id.clone()).one(conn).await.ok().flatten();
+        AuditEventHandler::record(conn, Some(actor_id), ACTION_UPDATE, "lost_and_found_items_log", &before.log_id, AuditEventHandler::snapshot(before), after.as_ref().and_then(AuditEventHandler::snapshot)).await
+    }
+
+    /// Confirm that a Missing report and a Found item are the same object: link both and return it to its verified owner
+    pub async fn confirm_match(
+        state: &AppState,
+        missing_log_id: String,
+        found_log_id: String,
+        verification: OwnerVerification,
+        staff_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let txn = state.db.begin().await
+            .map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+
+        let missing = match lost_and_found_items_log::Entity::find_by_id(missing_log_id.clone()).one(&txn).await {
+            Ok(Some(log)) if log.deleted_at.is_none() => log,
+            Ok(_) => return Err(AppError::NotFound("Missing report not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching missing report: {}", err))),
+        };
+        let found = match lost_and_found_items_log::Entity::find_by_id(found_log_id.clone()).one(&txn).await {
+            Ok(Some(log)) if log.deleted_at.is_none() => log,
+            Ok(_) => return Err(AppError::NotFound("Found item not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching found item: {}", err))),
+        };
+        if missing.status != STATUS_MISSING || missing.matched_log_id.is_some() {
+            return Err(AppError::Conflict("The first log entry must be an unmatched 'Missing' report".to_string()));
+        }
+        if found.status != STATUS_FOUND || found.matched_log_id.is_some() {
+            return Err(AppError::Conflict("The second log entry must be an unmatched 'Found' item".to_string()));
+        }
+        Self::validate_verification(&txn, &verification, &staff_id).await?;
+
+        // Each record fills in what only the other one knew (who found it and where)
+        let finder = found.finder.clone().or(missing.finder.clone());
+        let found_location = found.found_location.clone().or(missing.found_location.clone());
+        Self::mark_returned(&txn, &missing, &verification, &staff_id, Some(&found_log_id), finder.clone(), found_location.clone()).await?;
+        Self::mark_returned(&txn, &found, &verification, &staff_id, Some(&missing_log_id), finder, found_location).await?;
+
+        Self::audit_status_change(&txn, &missing, &staff_id).await?;
+        Self::audit_status_change(&txn, &found, &staff_id).await?;
+        txn.commit().await
+            .map_err(|err| AppError::Database(format!("Error committing match: {}", err)))?;
+
+        CachedRepository::<lost_and_found_items_log::Entity>::new(state).invalidate().await;
+        Ok(ApiResponse::success("Match confirmed and items marked as returned to owner".to_string()))
+    }
+
+    /// Hand a Found item (or a Missing one located directly) back to its verified owner
+    pub async fn return_log_to_owner(
+        state: &AppState,
+        log_id: String,
+        verification: OwnerVerification,
+        staff_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let txn = state.db.begin().await
+            .map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+
+        let log = match lost_and_found_items_log::Entity::find_by_id(log_id).one(&txn).await {
+            Ok(Some(log)) if log.deleted_at.is_none() => log,
+            Ok(_) => return Err(AppError::NotFound("Log entry not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching log entry: {}", err))),
+        };
+        if !Self::can_transition(&log.status, STATUS_RETURNED) {
+            return Err(AppError::Conflict(format!("A '{}' log entry cannot be returned to its owner", log.status)));
+        }
+        Self::validate_verification(&txn, &verification, &staff_id).await?;
+        let (finder, found_location) = (log.finder.clone(), log.found_location.clone());
+        Self::mark_returned(&txn, &log, &verification, &staff_id, None, finder, found_location).await?;
+
+        Self::audit_status_change(&txn, &log, &staff_id).await?;
+        txn.commit().await
+            .map_err(|err| AppError::Database(format!("Error committing return: {}", err)))?;
+
+        CachedRepository::<lost_and_found_items_log::Entity>::new(state).invalidate().await;
+        Ok(ApiResponse::success("Item returned to owner".to_string()))
+    }
+
+    /// View all lost and found logs, ordered by timestamp (most recent first)
+    pub async fn view_logs(state: &AppState) -> Result<ApiResponse<Vec<Model>>, AppError> {
+        let query = lost_and_found_items_log::Entity::find()
+            .filter(lost_and_found_items_log::Column::DeletedAt.is_null())
+            .order_by_desc(lost_and_found_items_log::Column::Timestamp);
+        match CachedRepository::<lost_and_found_items_log::Entity>::new(state).find_all(CachedView::LostAndFoundLogs, query).await {
+            Ok(logs) => Ok(ApiResponse::success(logs)),
+            Err(err) => Err(AppError::Database(format!("Error fetching logs: {}", err))),
+        }
+    }
+
+    /// Save a new Missing report or Found item with auto-generated timestamp
     pub async fn save_log_data(
         state: &AppState,
         image: Option<String>,
@@ -45,44 +488,222 @@ impl LostAndFoundItemsLogHandler {
         owner: Option<String>,
         found_location: Option<String>,
         status: String,
-    ) -> Result<ApiResponse<String>, String> {
-        // Validate status
-        if !["Returned to Owner", "Found", "Missing"].contains(&status.as_str()) {
-            return Err("Invalid status provided. Must be 'Returned to Owner', 'Found', or 'Missing'.".to_string());
-        }
+        staff_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        // New records start open; returning and disposal have their own commands.
+        // Fields required by the status are checked on insert.
+        Validator::new()
+            .field("name", &name, &[validation::name])
+            .one_of("status", &status, &[STATUS_FOUND, STATUS_MISSING])
+            .finish()?;
 
         let log_id = Uuid::new_v4().to_string();
+        let jakarta_time = clock::jakarta_now();
+
+        let new_log = Model {
+            log_id: log_id.clone(),
+            image,
+            name,
+            r#type,
+            color,
+            last_seen_location,
+            finder,
+            owner,
+            found_location,
+            timestamp: jakarta_time,
+            found_at: (status == STATUS_FOUND).then_some(jakarta_time),
+            status,
+            matched_log_id: None,
+            owner_customer_id: None,
+            owner_verification_method: None,
+            owner_verification_reference: None,
+            returned_by: None,
+            returned_at: None,
+            retention_flagged_at: None,
+            disposed_at: None,
+            reporter_customer_id: None,
+            deleted_at: None,
+        };
+        Self::insert_log(state, &new_log, &staff_id).await?;
+
+        let notified = if new_log.status == STATUS_FOUND {
+            Self::notify_reporters_of_found_item(state, &new_log).await
+        } else {
+            Ok(())
+        };
+        Ok(Self::saved_with_notice("Log entry created successfully", notified))
+    }
+
+    async fn insert_log(state: &AppState, new_log: &Model, actor_id: &str) -> Result<(), AppError> {
+        Self::check_required_fields(new_log)?;
+
+        let txn = state.db.begin().await
+            .map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let active_log: lost_and_found_items_log::ActiveModel = new_log.clone().into();
+        if let Err(err) = lost_and_found_items_log::Entity::insert(active_log.reset_all()).exec(&txn).await {
+            return Err(AppError::Database(format!("Error creating log entry: {}", err)));
+        }
+        Self::record_event(&txn, &new_log.log_id, "Created", None, Some(&new_log.status), Some(actor_id.to_string()), None).await?;
+        AuditEventHandler::record(&txn, Some(actor_id), ACTION_CREATE, "lost_and_found_items_log", &new_log.log_id, None, AuditEventHandler::snapshot(new_log)).await?;
+        txn.commit().await
+            .map_err(|err| AppError::Database(format!("Error creating log entry: {}", err)))?;
+
+        // Invalidate the cache after creating a new entry
+        CachedRepository::<lost_and_found_items_log::Entity>::new(state).invalidate().await;
+        Ok(())
+    }
+
+    fn found_item_notice(report: &Model, found: &Model) -> String {
+        format!(
+            "Good news! An item matching your lost item report \"{}\" was handed in to Lost and Found{}. \
+             Please visit the Lost and Found counter with your ID to collect it.",
+            report.name,
+            found.found_location.as_deref().map(|location| format!(" (found at {})", location)).unwrap_or_default()
+        )
+    }
+
+    async fn notify_of_found_item(state: &AppState, customer_id: &str, report: &Model, found: &Model) -> Result<(), AppError> {
+        ChatHandler::notify_customer(state, customer_id, Self::found_item_notice(report, found))
+            .await
+            .map_err(|err| AppError::Internal(format!("Could not notify customer {} of a found item: {}", customer_id, err)))
+    }
+
+    // Notices go out after the write has committed, so a failed one is logged and reported
+    // alongside the success instead of failing a command whose retry would log the item twice
+    fn saved_with_notice(saved: &str, notified: Result<(), AppError>) -> ApiResponse<String> {
+        match notified {
+            Ok(()) => ApiResponse::success(saved.to_string()),
+            Err(err) => {
+                eprintln!("Lost and found notice failed ({:?}): {}", err.code(), err);
+                ApiResponse::Success { data: saved.to_string(), message: Some(err.to_string()) }
+            }
+        }
+    }
+
+    // Tell customers whose own open reports look like a newly logged Found item. Every
+    // matching reporter is tried; the first failure is returned.
+    async fn notify_reporters_of_found_item(state: &AppState, found: &Model) -> Result<(), AppError> {
+        let reports = lost_and_found_items_log::Entity::find()
+            .filter(lost_and_found_items_log::Column::Status.eq(STATUS_MISSING))
+            .filter(lost_and_found_items_log::Column::DeletedAt.is_null())
+            .filter(lost_and_found_items_log::Column::MatchedLogId.is_null())
+            .filter(lost_and_found_items_log::Column::ReporterCustomerId.is_not_null())
+            .all(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching reports to notify: {}", err)))?;
+
+        let mut notified = Ok(());
+        for report in reports {
+            if Self::score_pair(&report, found).total < MIN_NOTIFY_SCORE {
+                continue;
+            }
+            if let Some(customer_id) = &report.reporter_customer_id {
+                let outcome = Self::notify_of_found_item(state, customer_id, &report, found).await;
+                notified = notified.and(outcome);
+            }
+        }
+        notified
+    }
+
+    /// Customer-facing: file a Missing report for the customer's own lost item
+    pub async fn file_missing_report(
+        state: &AppState,
+        customer_id: String,
+        image: Option<String>,
+        name: String,
+        r#type: String,
+        color: String,
+        last_seen_location: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new()
+            .field("name", &name, &[validation::name])
+            .field("type", &r#type, &[validation::required])
+            .field("color", &color, &[validation::required])
+            .field("last_seen_location", &last_seen_location, &[validation::required])
+            .finish()?;
 
-        let jakarta_time = Utc::now()
-            .with_timezone(&chrono::FixedOffset::east_opt(7 * 3600).unwrap())
-            .naive_local();
+        let customer = match customer::Entity::find_by_id(customer_id.clone()).one(&state.db).await {
+            Ok(Some(customer)) => customer,
+            Ok(None) => return Err(AppError::NotFound("Customer not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching customer: {}", err))),
+        };
 
-        let new_log = lost_and_found_items_log::ActiveModel {
-            log_id: sea_orm::ActiveValue::Set(log_id),
-            image: sea_orm::ActiveValue::Set(image),
-            name: sea_orm::ActiveValue::Set(name),
-            r#type: sea_orm::ActiveValue::Set(r#type),
-            color: sea_orm::ActiveValue::Set(color),
-            last_seen_location: sea_orm::ActiveValue::Set(last_seen_location),
-            finder: sea_orm::ActiveValue::Set(finder),
-            owner: sea_orm::ActiveValue::Set(owner),
-            found_location: sea_orm::ActiveValue::Set(found_location),
-            timestamp: sea_orm::ActiveValue::Set(jakarta_time),
-            status: sea_orm::ActiveValue::Set(status),
-            ..Default::default()
+        let jakarta_time = clock::jakarta_now();
+        let report = Model {
+            log_id: Uuid::new_v4().to_string(),
+            image,
+            name,
+            r#type,
+            color,
+            last_seen_location: Some(last_seen_location),
+            finder: None,
+            owner: Some(customer.name),
+            found_location: None,
+            timestamp: jakarta_time,
+            status: STATUS_MISSING.to_string(),
+            matched_log_id: None,
+            owner_customer_id: None,
+            owner_verification_method: None,
+            owner_verification_reference: None,
+            returned_by: None,
+            returned_at: None,
+            found_at: None,
+            retention_flagged_at: None,
+            disposed_at: None,
+            reporter_customer_id: Some(customer_id.clone()),
+            deleted_at: None,
         };
+        Self::insert_log(state, &report, &customer_id).await?;
 
-        match lost_and_found_items_log::Entity::insert(new_log).exec(&state.db).await {
-            Ok(_) => {
-                // Invalidate the cache after creating a new entry
-                cache_delete(&state.redis_pool, "view_logs_cache").await;
-                Ok(ApiResponse::success("Log entry created successfully".to_string()))
+        // The item may already be waiting at Lost and Found
+        let notified = match Self::open_logs_with_status(state, STATUS_FOUND).await {
+            Ok(found_items) => match found_items
+                .iter()
+                .filter(|found| Self::score_pair(&report, found).total >= MIN_NOTIFY_SCORE)
+                .max_by(|a, b| Self::score_pair(&report, a).total.total_cmp(&Self::score_pair(&report, b).total))
+            {
+                Some(found) => Self::notify_of_found_item(state, &customer_id, &report, found).await,
+                None => Ok(()),
+            },
+            Err(err) => Err(err),
+        };
+        Ok(Self::saved_with_notice("Lost item report filed successfully", notified))
+    }
+
+    /// Customer-facing: the customer's own reports and items returned to them, most recent first
+    pub async fn view_reports_by_customer(
+        state: &AppState,
+        customer_id: String,
+    ) -> Result<ApiResponse<Vec<Model>>, AppError> {
+        match lost_and_found_items_log::Entity::find()
+            .filter(lost_and_found_items_log::Column::DeletedAt.is_null())
+            .filter(
+                Condition::any()
+                    .add(lost_and_found_items_log::Column::ReporterCustomerId.eq(customer_id.clone()))
+                    .add(lost_and_found_items_log::Column::OwnerCustomerId.eq(customer_id)),
+            )
+            .order_by_desc(lost_and_found_items_log::Column::Timestamp)
+            .all(&state.db)
+            .await
+        {
+            Ok(logs) => {
+                // A confirmed match returns both records to the customer; only show their report
+                let report_ids: HashSet<String> = logs
+                    .iter()
+                    .filter(|log| log.reporter_customer_id.is_some())
+                    .map(|log| log.log_id.clone())
+                    .collect();
+                let reports = logs
+                    .into_iter()
+                    .filter(|log| !log.matched_log_id.as_ref().is_some_and(|id| report_ids.contains(id)))
+                    .collect();
+                Ok(ApiResponse::success(reports))
             }
-            Err(err) => Err(format!("Error creating log entry: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error fetching lost item reports: {}", err))),
         }
     }
 
-    /// Update an existing lost and found log entry with auto-updated timestamp
+    /// Update an open lost and found log entry with auto-updated timestamp
     pub async fn update_log_data(
         state: &AppState,
         log_id: String,
@@ -95,75 +716,567 @@ impl LostAndFoundItemsLogHandler {
         owner: Option<Option<String>>,
         found_location: Option<Option<String>>,
         status: Option<String>,
-    ) -> Result<ApiResponse<String>, String> {
-        let log_record = match lost_and_found_items_log::Entity::find_by_id(log_id).one(&state.db).await {
-            Ok(Some(log)) => log,
-            Ok(None) => return Err("Log entry not found".to_string()),
-            Err(err) => return Err(format!("Error fetching log entry: {}", err)),
+        staff_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new()
+            .optional("name", name.as_deref(), &[validation::name])
+            .finish()?;
+
+        let log_record = match lost_and_found_items_log::Entity::find_by_id(log_id.clone()).one(&state.db).await {
+            Ok(Some(log)) if log.deleted_at.is_none() => log,
+            Ok(_) => return Err(AppError::NotFound("Log entry not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching log entry: {}", err))),
         };
+        if Self::is_closed(&log_record.status) {
+            return Err(AppError::Conflict(format!("A '{}' log entry is closed and can no longer be edited", log_record.status)));
+        }
 
-        let mut active_log: lost_and_found_items_log::ActiveModel = log_record.into();
+        let mut active_log: lost_and_found_items_log::ActiveModel = log_record.clone().into();
+        let mut changed_fields = Vec::new();
 
         if let Some(new_image) = image {
+            if new_image != log_record.image {
+                changed_fields.push("image");
+            }
             active_log.image = sea_orm::ActiveValue::Set(new_image);
         }
         if let Some(new_name) = name {
+            if new_name != log_record.name {
+                changed_fields.push("name");
+            }
             active_log.name = sea_orm::ActiveValue::Set(new_name);
         }
         if let Some(new_type) = r#type {
+            if new_type != log_record.r#type {
+                changed_fields.push("type");
+            }
             active_log.r#type = sea_orm::ActiveValue::Set(new_type);
         }
         if let Some(new_color) = color {
+            if new_color != log_record.color {
+                changed_fields.push("color");
+            }
             active_log.color = sea_orm::ActiveValue::Set(new_color);
         }
         if let Some(new_last_seen_location) = last_seen_location {
+            if new_last_seen_location != log_record.last_seen_location {
+                changed_fields.push("last_seen_location");
+            }
             active_log.last_seen_location = sea_orm::ActiveValue::Set(new_last_seen_location);
         }
         if let Some(new_finder) = finder {
+            if new_finder != log_record.finder {
+                changed_fields.push("finder");
+            }
             active_log.finder = sea_orm::ActiveValue::Set(new_finder);
         }
         if let Some(new_owner) = owner {
+            if new_owner != log_record.owner {
+                changed_fields.push("owner");
+            }
             active_log.owner = sea_orm::ActiveValue::Set(new_owner);
         }
         if let Some(new_found_location) = found_location {
+            if new_found_location != log_record.found_location {
+                changed_fields.push("found_location");
+            }
             active_log.found_location = sea_orm::ActiveValue::Set(new_found_location);
         }
         // Auto-update timestamp to current time
-        let jakarta_time = Utc::now()
-            .with_timezone(&chrono::FixedOffset::east_opt(7 * 3600).unwrap())
-            .naive_local();
+        let jakarta_time = clock::jakarta_now();
         active_log.timestamp = sea_orm::ActiveValue::Set(jakarta_time);
-        
-        if let Some(new_status) = status {
-            if !["Returned to Owner", "Found", "Missing"].contains(&new_status.as_str()) {
-                return Err("Invalid status provided. Must be 'Returned to Owner', 'Found', or 'Missing'.".to_string());
+
+        let new_status = status.filter(|new_status| *new_status != log_record.status);
+        if let Some(new_status) = &new_status {
+            if [STATUS_RETURNED, STATUS_DONATED, STATUS_DISPOSED].contains(&new_status.as_str()) {
+                return Err(AppError::validation(format!("Use the dedicated command to mark an item as '{}'", new_status)));
             }
-            active_log.status = sea_orm::ActiveValue::Set(new_status);
+            if !Self::can_transition(&log_record.status, new_status) {
+                return Err(AppError::validation(format!("Invalid status change from '{}' to '{}'", log_record.status, new_status)));
+            }
+            if new_status == STATUS_FOUND {
+                active_log.found_at = sea_orm::ActiveValue::Set(Some(jakarta_time));
+            }
+            active_log.status = sea_orm::ActiveValue::Set(new_status.clone());
+        }
+
+        let updated_log = active_log.clone().try_into_model()
+            .map_err(|err| AppError::Database(format!("Error updating log entry: {}", err)))?;
+        Self::check_required_fields(&updated_log)?;
+
+        let txn = state.db.begin().await
+            .map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        if let Err(err) = active_log.update(&txn).await {
+            return Err(AppError::Database(format!("Error updating log entry: {}", err)));
+        }
+        let details = (!changed_fields.is_empty()).then(|| format!("Changed {}", changed_fields.join(", ")));
+        match &new_status {
+            Some(new_status) => {
+                Self::record_event(&txn, &log_id, "StatusChanged", Some(&log_record.status), Some(new_status), Some(staff_id.clone()), details).await?
+            }
+            None => Self::record_event(&txn, &log_id, "Updated", None, None, Some(staff_id.clone()), details).await?,
         }
+        AuditEventHandler::record(&txn, Some(&staff_id), ACTION_UPDATE, "lost_and_found_items_log", &log_id, AuditEventHandler::snapshot(&log_record), AuditEventHandler::snapshot(&updated_log)).await?;
+        txn.commit().await
+            .map_err(|err| AppError::Database(format!("Error updating log entry: {}", err)))?;
+
+        // Invalidate the cache after updating an entry
+        CachedRepository::<lost_and_found_items_log::Entity>::new(state).invalidate().await;
 
-        match active_log.update(&state.db).await {
-            Ok(_) => {
-                // Invalidate the cache after updating an entry
-                cache_delete(&state.redis_pool, "view_logs_cache").await;
-                Ok(ApiResponse::success("Log entry updated successfully".to_string()))
+        // A customer's own report was located: tell them directly
+        let notified = match (&new_status, &updated_log.reporter_customer_id) {
+            (Some(new_status), Some(customer_id)) if new_status == STATUS_FOUND => {
+                Self::notify_of_found_item(state, customer_id, &updated_log, &updated_log).await
             }
-            Err(err) => Err(format!("Error updating log entry: {}", err)),
+            _ => Ok(()),
+        };
+        Ok(Self::saved_with_notice("Log entry updated successfully", notified))
+    }
+
+    fn retention_days() -> i64 {
+        env::var("LOST_AND_FOUND_RETENTION_DAYS")
+            .ok()
+            .and_then(|days| days.parse().ok())
+            .filter(|days: &i64| *days > 0)
+            .unwrap_or(DEFAULT_RETENTION_DAYS)
+    }
+
+    /// Flag Found items unclaimed past the retention period; returns how many were flagged
+    pub async fn flag_unclaimed_items(state: &AppState) -> Result<u64, AppError> {
+        let now = clock::jakarta_now();
+        let cutoff = now - Duration::days(Self::retention_days());
+
+        let expired = lost_and_found_items_log::Entity::find()
+            .filter(lost_and_found_items_log::Column::Status.eq(STATUS_FOUND))
+            .filter(lost_and_found_items_log::Column::DeletedAt.is_null())
+            .filter(lost_and_found_items_log::Column::RetentionFlaggedAt.is_null())
+            .filter(lost_and_found_items_log::Column::FoundAt.lt(cutoff))
+            .all(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching unclaimed items: {}", err)))?;
+
+        let mut flagged = 0;
+        for log in expired {
+            let txn = state.db.begin().await
+                .map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+            // Guarded so concurrent instances flag (and record) each item once
+            let result = lost_and_found_items_log::Entity::update_many()
+                .col_expr(lost_and_found_items_log::Column::RetentionFlaggedAt, Expr::value(now))
+                .filter(lost_and_found_items_log::Column::LogId.eq(log.log_id.clone()))
+                .filter(lost_and_found_items_log::Column::Status.eq(STATUS_FOUND))
+                .filter(lost_and_found_items_log::Column::RetentionFlaggedAt.is_null())
+                .exec(&txn)
+                .await
+                .map_err(|err| AppError::Database(format!("Error flagging unclaimed item: {}", err)))?;
+            if result.rows_affected == 0 {
+                continue;
+            }
+            let details = format!("Unclaimed for more than {} days", Self::retention_days());
+            Self::record_event(&txn, &log.log_id, "RetentionFlagged", None, None, None, Some(details)).await?;
+            txn.commit().await
+                .map_err(|err| AppError::Database(format!("Error flagging unclaimed item: {}", err)))?;
+            flagged += 1;
+        }
+
+        if flagged > 0 {
+            CachedRepository::<lost_and_found_items_log::Entity>::new(state).invalidate().await;
         }
+        Ok(flagged)
     }
 
-    /// Delete a lost and found log entry by log_id
-    pub async fn delete_log_data(state: &AppState, log_id: String) -> Result<String, String> {
-        match lost_and_found_items_log::Entity::delete_by_id(log_id).exec(&state.db).await {
-            Ok(delete_result) => {
-                if delete_result.rows_affected > 0 {
-                    // Invalidate the cache after deleting an entry
-                    cache_delete(&state.redis_pool, "view_logs_cache").await;
-                    Ok("Log entry deleted successfully".to_string())
-                } else {
-                    Err("Log entry not found".to_string())
-                }
+    /// Found items past the retention period, awaiting donation or disposal
+    pub async fn view_retention_flagged_logs(state: &AppState) -> Result<ApiResponse<Vec<Model>>, AppError> {
+        match lost_and_found_items_log::Entity::find()
+            .filter(lost_and_found_items_log::Column::Status.eq(STATUS_FOUND))
+            .filter(lost_and_found_items_log::Column::DeletedAt.is_null())
+            .filter(lost_and_found_items_log::Column::RetentionFlaggedAt.is_not_null())
+            .order_by_asc(lost_and_found_items_log::Column::FoundAt)
+            .all(&state.db)
+            .await
+        {
+            Ok(logs) => Ok(ApiResponse::success(logs)),
+            Err(err) => Err(AppError::Database(format!("Error fetching flagged logs: {}", err))),
+        }
+    }
+
+    /// Donate or dispose of a Found item once it has been flagged by the retention policy
+    pub async fn dispose_log(
+        state: &AppState,
+        log_id: String,
+        disposition: String,
+        staff_id: String,
+        note: Option<String>,
+    ) -> Result<ApiResponse<String>, AppError> {
+        if ![STATUS_DONATED, STATUS_DISPOSED].contains(&disposition.as_str()) {
+            return Err(AppError::validation("Invalid disposition. Must be 'Donated' or 'Disposed'.".to_string()));
+        }
+        let log = match lost_and_found_items_log::Entity::find_by_id(log_id.clone()).one(&state.db).await {
+            Ok(Some(log)) if log.deleted_at.is_none() => log,
+            Ok(_) => return Err(AppError::NotFound("Log entry not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching log entry: {}", err))),
+        };
+        if !Self::can_transition(&log.status, &disposition) {
+            return Err(AppError::Conflict(format!("A '{}' log entry cannot be {}", log.status, disposition.to_lowercase())));
+        }
+        if log.retention_flagged_at.is_none() {
+            return Err(AppError::Conflict("Only items past the retention period can be donated or disposed of".to_string()));
+        }
+
+        let now = clock::jakarta_now();
+        let txn = state.db.begin().await
+            .map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let result = lost_and_found_items_log::Entity::update_many()
+            .col_expr(lost_and_found_items_log::Column::Status, Expr::value(disposition.clone()))
+            .col_expr(lost_and_found_items_log::Column::DisposedAt, Expr::value(now))
+            .col_expr(lost_and_found_items_log::Column::Timestamp, Expr::value(now))
+            .filter(lost_and_found_items_log::Column::LogId.eq(log_id.clone()))
+            .filter(lost_and_found_items_log::Column::Status.eq(STATUS_FOUND))
+            .exec(&txn)
+            .await
+            .map_err(|err| AppError::Database(format!("Error updating log entry: {}", err)))?;
+        if result.rows_affected == 0 {
+            return Err(AppError::Conflict("Log entry was changed by someone else in the meantime".to_string()));
+        }
+        Self::record_event(&txn, &log_id, "Disposed", Some(STATUS_FOUND), Some(&disposition), Some(staff_id.clone()), note).await?;
+        Self::audit_status_change(&txn, &log, &staff_id).await?;
+        txn.commit().await
+            .map_err(|err| AppError::Database(format!("Error updating log entry: {}", err)))?;
+
+        CachedRepository::<lost_and_found_items_log::Entity>::new(state).invalidate().await;
+        Ok(ApiResponse::success(format!("Item marked as {}", disposition)))
+    }
+
+    /// Full history of a log entry, oldest first
+    pub async fn get_log_history(
+        state: &AppState,
+        log_id: String,
+    ) -> Result<ApiResponse<Vec<lost_and_found_log_event::Model>>, AppError> {
+        match lost_and_found_log_event::Entity::find()
+            .filter(lost_and_found_log_event::Column::LogId.eq(log_id))
+            .order_by_asc(lost_and_found_log_event::Column::CreatedAt)
+            .all(&state.db)
+            .await
+        {
+            Ok(events) => Ok(ApiResponse::success(events)),
+            Err(err) => Err(AppError::Database(format!("Error fetching log history: {}", err))),
+        }
+    }
+
+    /// Archive a lost and found log entry (soft delete): the entry and its history stay on record
+    pub async fn delete_log_data(state: &AppState, log_id: String, actor_id: String) -> Result<ApiResponse<String>, AppError> {
+        let now = clock::jakarta_now();
+        let txn = state.db.begin().await
+            .map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let existing = match lost_and_found_items_log::Entity::find_by_id(log_id.clone()).one(&txn).await {
+            Ok(Some(log)) if log.deleted_at.is_none() => log,
+            Ok(_) => return Err(AppError::NotFound("Log entry not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching log entry: {}", err))),
+        };
+
+        let before = AuditEventHandler::snapshot(&existing);
+        let mut active_log: lost_and_found_items_log::ActiveModel = existing.into();
+        active_log.deleted_at = sea_orm::ActiveValue::Set(Some(now));
+        let archived = active_log.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error deleting log entry: {}", err)))?;
+        Self::record_event(&txn, &log_id, "Archived", None, None, Some(actor_id.clone()), None).await?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_DELETE, "lost_and_found_items_log", &log_id, before, AuditEventHandler::snapshot(&archived)).await?;
+        txn.commit().await
+            .map_err(|err| AppError::Database(format!("Error deleting log entry: {}", err)))?;
+
+        CachedRepository::<lost_and_found_items_log::Entity>::new(state).invalidate().await;
+        Ok(ApiResponse::success("Log entry deleted successfully".to_string()))
+    }
+
+    /// Bring an archived log entry back into the lists
+    pub async fn restore_log_data(state: &AppState, log_id: String, actor_id: String) -> Result<ApiResponse<String>, AppError> {
+        let txn = state.db.begin().await
+            .map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let existing = match lost_and_found_items_log::Entity::find_by_id(log_id.clone()).one(&txn).await {
+            Ok(Some(log)) => log,
+            Ok(None) => return Err(AppError::NotFound("Log entry not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching log entry: {}", err))),
+        };
+        if existing.deleted_at.is_none() {
+            return Err(AppError::Conflict("Log entry is not archived".to_string()));
+        }
+
+        let before = AuditEventHandler::snapshot(&existing);
+        let mut active_log: lost_and_found_items_log::ActiveModel = existing.into();
+        active_log.deleted_at = sea_orm::ActiveValue::Set(None);
+        let restored = active_log.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error restoring log entry: {}", err)))?;
+        Self::record_event(&txn, &log_id, "Restored", None, None, Some(actor_id.clone()), None).await?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_RESTORE, "lost_and_found_items_log", &log_id, before, AuditEventHandler::snapshot(&restored)).await?;
+        txn.commit().await
+            .map_err(|err| AppError::Database(format!("Error restoring log entry: {}", err)))?;
+
+        CachedRepository::<lost_and_found_items_log::Entity>::new(state).invalidate().await;
+        Ok(ApiResponse::success("Log entry restored successfully".to_string()))
+    }
+
+    /// View archived log entries, most recently archived first
+    pub async fn view_archived_logs(state: &AppState) -> Result<ApiResponse<Vec<Model>>, AppError> {
+        match lost_and_found_items_log::Entity::find()
+            .filter(lost_and_found_items_log::Column::DeletedAt.is_not_null())
+            .order_by_desc(lost_and_found_items_log::Column::DeletedAt)
+            .all(&state.db)
+            .await
+        {
+            Ok(archived) => Ok(ApiResponse::success(archived)),
+            Err(err) => Err(AppError::Database(format!("Error fetching archived logs: {}", err))),
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::test_support::{self, data, error, ACTOR};
+
+    async fn seed_missing_report(state: &AppState) -> Model {
+        data(LostAndFoundItemsLogHandler::save_log_data(
+            state,
+            None,
+            "Blue backpack".to_string(),
+            "Bag".to_string(),
+            "Blue".to_string(),
+            Some("Food court".to_string()),
+            None,
+            Some("Rina".to_string()),
+            None,
+            STATUS_MISSING.to_string(),
+            ACTOR.to_string(),
+        )
+        .await);
+        data(LostAndFoundItemsLogHandler::view_logs(state).await).remove(0)
+    }
+
+    #[tokio::test]
+    async fn deleting_a_log_archives_it_and_keeps_its_history() {
+        let state = test_support::test_state().await;
+        let staff_member = test_support::seed_staff(&state, "LostAndFoundStaff").await;
+        let log = seed_missing_report(&state).await;
+
+        data(LostAndFoundItemsLogHandler::delete_log_data(&state, log.log_id.clone(), staff_member.staff_id.clone()).await);
+        assert!(data(LostAndFoundItemsLogHandler::view_logs(&state).await).is_empty());
+        assert_eq!(data(LostAndFoundItemsLogHandler::view_archived_logs(&state).await).len(), 1);
+        let history = data(LostAndFoundItemsLogHandler::get_log_history(&state, log.log_id.clone()).await);
+        let actions: Vec<&str> = history.iter().map(|event| event.action.as_str()).collect();
+        assert_eq!(actions, ["Created", "Archived"]);
+
+        // Archived entries cannot be edited or archived again until restored
+        let err = error(LostAndFoundItemsLogHandler::delete_log_data(&state, log.log_id.clone(), ACTOR.to_string()).await);
+        assert_eq!(err.code(), ErrorCode::NotFound);
+        data(LostAndFoundItemsLogHandler::restore_log_data(&state, log.log_id.clone(), staff_member.staff_id).await);
+        assert_eq!(data(LostAndFoundItemsLogHandler::view_logs(&state).await).len(), 1);
+        let err = error(LostAndFoundItemsLogHandler::restore_log_data(&state, log.log_id, ACTOR.to_string()).await);
+        assert_eq!(err.code(), ErrorCode::Conflict);
+    }
+
+    #[tokio::test]
+    async fn a_log_with_history_cannot_be_removed_outright() {
+        let state = test_support::test_state().await;
+        let log = seed_missing_report(&state).await;
+
+        let removed = lost_and_found_items_log::Entity::delete_by_id(log.log_id.clone()).exec(&state.db).await;
+        assert!(removed.is_err(), "the history foreign key must restrict deletes");
+        let history = data(LostAndFoundItemsLogHandler::get_log_history(&state, log.log_id).await);
+        assert_eq!(history.len(), 1);
+    }
+
+    // A log entry built in memory, for the scoring and lifecycle rules that need no database
+    fn log_entry(status: &str, name: &str, r#type: &str, color: &str) -> Model {
+        Model {
+            log_id: "log".to_string(),
+            image: None,
+            name: name.to_string(),
+            r#type: r#type.to_string(),
+            color: color.to_string(),
+            last_seen_location: None,
+            finder: None,
+            owner: None,
+            found_location: None,
+            timestamp: NaiveDateTime::parse_from_str("2026-10-18 10:00", "%Y-%m-%d %H:%M").unwrap(),
+            status: status.to_string(),
+            matched_log_id: None,
+            owner_customer_id: None,
+            owner_verification_method: None,
+            owner_verification_reference: None,
+            returned_by: None,
+            returned_at: None,
+            found_at: None,
+            retention_flagged_at: None,
+            disposed_at: None,
+            reporter_customer_id: None,
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn text_similarity_tolerates_case_extra_words_and_typos() {
+        assert_eq!(LostAndFoundItemsLogHandler::text_similarity("Black", " black "), 1.0);
+        assert_eq!(LostAndFoundItemsLogHandler::token_similarity("Black iPhone", "iphone"), 0.5);
+        assert!(LostAndFoundItemsLogHandler::text_similarity("wallet", "walet") > 0.8);
+        assert_eq!(LostAndFoundItemsLogHandler::text_similarity("Umbrella", "Hat"), 0.0);
+        assert_eq!(LostAndFoundItemsLogHandler::text_similarity("", ""), 0.0);
+    }
+
+    #[test]
+    fn unknown_locations_are_neutral_and_time_fades_over_a_week() {
+        assert_eq!(LostAndFoundItemsLogHandler::location_similarity(None, Some("Food court")), 0.5);
+        assert_eq!(LostAndFoundItemsLogHandler::location_similarity(Some(" "), Some("Food court")), 0.5);
+        assert_eq!(LostAndFoundItemsLogHandler::location_similarity(Some("Food court"), Some("food court")), 1.0);
+
+        let reported = log_entry(STATUS_MISSING, "", "", "").timestamp;
+        let time = |hours: i64| LostAndFoundItemsLogHandler::time_similarity(reported, reported + chrono::Duration::hours(hours));
+        assert_eq!(time(0), 1.0);
+        assert_eq!(time(84), 0.5);
+        assert_eq!(time(-84), 0.5);
+        assert_eq!(time(24 * 30), 0.0);
+    }
+
+    #[test]
+    fn matching_descriptions_score_above_the_thresholds_and_others_below() {
+        let mut missing = log_entry(STATUS_MISSING, "Blue backpack", "Bag", "Blue");
+        missing.last_seen_location = Some("Food court".to_string());
+        let mut found = log_entry(STATUS_FOUND, "blue back pack", "bag", "Navy blue");
+        found.found_location = Some("Food Court".to_string());
+        found.timestamp = missing.timestamp + chrono::Duration::hours(2);
+
+        let score = LostAndFoundItemsLogHandler::score_pair(&missing, &found);
+        assert_eq!((score.type_score, score.location_score), (1.0, 1.0));
+        assert!(score.total >= MIN_NOTIFY_SCORE, "{score:?}");
+        let weights = TYPE_WEIGHT + NAME_WEIGHT + COLOR_WEIGHT + LOCATION_WEIGHT + TIME_WEIGHT;
+        assert!((weights - 1.0).abs() < 1e-9 && score.total <= 1.0);
+
+        let umbrella = log_entry(STATUS_FOUND, "Umbrella", "Accessory", "Red");
+        assert!(LostAndFoundItemsLogHandler::score_pair(&missing, &umbrella).total < MIN_MATCH_SCORE);
+    }
+
+    #[tokio::test]
+    async fn suggestions_are_ranked_and_leave_out_weak_pairs() {
+        let state = test_support::test_state().await;
+        let missing = seed_missing_report(&state).await;
+        for (name, color) in [("Blue backpack", "Blue"), ("Backpack", "Black"), ("Umbrella", "Red")] {
+            data(LostAndFoundItemsLogHandler::save_log_data(
+                &state,
+                Some("https://example.com/item.jpg".to_string()),
+                name.to_string(),
+                if name == "Umbrella" { "Accessory" } else { "Bag" }.to_string(),
+                color.to_string(),
+                None,
+                Some("Cleaner".to_string()),
+                None,
+                Some("Food court".to_string()),
+                STATUS_FOUND.to_string(),
+                ACTOR.to_string(),
+            )
+            .await);
+        }
+
+        let matches = data(LostAndFoundItemsLogHandler::suggest_matches(&state, Some(missing.log_id.clone()), None).await);
+        let names: Vec<&str> = matches.iter().map(|candidate| candidate.found.name.as_str()).collect();
+        assert_eq!(names, ["Blue backpack", "Backpack"]);
+        assert!(matches[0].score.total > matches[1].score.total);
+
+        let limited = data(LostAndFoundItemsLogHandler::suggest_matches(&state, None, Some(1)).await);
+        assert_eq!(limited.len(), 1);
+    }
+
+    #[test]
+    fn items_only_move_forward_and_closed_statuses_are_final() {
+        let statuses = [STATUS_MISSING, STATUS_FOUND, STATUS_RETURNED, STATUS_DONATED, STATUS_DISPOSED];
+        let allowed: Vec<(&str, &str)> = statuses
+            .iter()
+            .flat_map(|from| statuses.iter().map(move |to| (*from, *to)))
+            .filter(|(from, to)| LostAndFoundItemsLogHandler::can_transition(from, to))
+            .collect();
+        assert_eq!(
+            allowed,
+            [
+                (STATUS_MISSING, STATUS_FOUND),
+                (STATUS_MISSING, STATUS_RETURNED),
+                (STATUS_FOUND, STATUS_RETURNED),
+                (STATUS_FOUND, STATUS_DONATED),
+                (STATUS_FOUND, STATUS_DISPOSED),
+            ]
+        );
+
+        let closed: Vec<&str> = statuses.into_iter().filter(|status| LostAndFoundItemsLogHandler::is_closed(status)).collect();
+        assert_eq!(closed, [STATUS_RETURNED, STATUS_DONATED, STATUS_DISPOSED]);
+    }
+
+    #[test]
+    fn each_status_requires_its_own_fields() {
+        let missing_fields = |log: &Model| -> Vec<String> {
+            match LostAndFoundItemsLogHandler::check_required_fields(log) {
+                Ok(()) => Vec::new(),
+                Err(err) => err.fields().iter().map(|field| field.field.clone()).collect(),
+            }
+        };
+
+        let mut missing = log_entry(STATUS_MISSING, "Wallet", "Accessory", " ");
+        assert_eq!(missing_fields(&missing), ["color", "owner", "last_seen_location"]);
+        missing.color = "Brown".to_string();
+        missing.owner = Some("Rina".to_string());
+        missing.last_seen_location = Some("Gate".to_string());
+        assert!(missing_fields(&missing).is_empty());
+
+        let mut found = log_entry(STATUS_FOUND, "Wallet", "Accessory", "Brown");
+        found.finder = Some("  ".to_string());
+        assert_eq!(missing_fields(&found), ["image", "finder", "found_location"]);
+
+        let mut returned = log_entry(STATUS_RETURNED, "Wallet", "Accessory", "Brown");
+        returned.owner = Some("Rina".to_string());
+        assert_eq!(missing_fields(&returned), ["owner_verification_method"]);
+
+        // Donated and disposed items keep only the description
+        assert!(missing_fields(&log_entry(STATUS_DISPOSED, "Wallet", "Accessory", "Brown")).is_empty());
+    }
+
+    #[tokio::test]
+    async fn updates_cannot_reopen_a_closed_item() {
+        let state = test_support::test_state().await;
+        let log = seed_missing_report(&state).await;
+        let verification = OwnerVerification {
+            owner: "Rina".to_string(),
+            customer_id: None,
+            method: "ItemDescription".to_string(),
+            reference: None,
+        };
+        let staff_member = test_support::seed_staff(&state, "LostAndFoundStaff").await;
+        data(LostAndFoundItemsLogHandler::return_log_to_owner(&state, log.log_id.clone(), verification, staff_member.staff_id).await);
+
+        let err = error(LostAndFoundItemsLogHandler::update_log_data(
+            &state,
+            log.log_id,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(STATUS_MISSING.to_string()),
+            ACTOR.to_string(),
+        )
+        .await);
+        assert_eq!(err.code(), ErrorCode::Conflict);
+    }
+
+    #[test]
+    fn a_failed_notice_is_reported_without_failing_the_save() {
+        let saved = LostAndFoundItemsLogHandler::saved_with_notice("Log entry created successfully", Ok(()));
+        assert!(matches!(saved, ApiResponse::Success { message: None, .. }));
+
+        let notice_failed = Err(AppError::Internal("Could not notify customer c-1 of a found item: chat offline".to_string()));
+        match LostAndFoundItemsLogHandler::saved_with_notice("Log entry created successfully", notice_failed) {
+            ApiResponse::Success { data, message } => {
+                assert_eq!(data, "Log entry created successfully");
+                assert_eq!(message.as_deref(), Some("Could not notify customer c-1 of a found item: chat offline"));
             }
-            Err(err) => Err(format!("Error deleting log entry: {}", err)),
+            _ => panic!("expected the save to succeed"),
         }
     }
-}
\ No newline at end of file
+}
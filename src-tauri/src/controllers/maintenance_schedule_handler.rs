@@ -1,25 +1,30 @@
 // src-tauri/src/handler/maintenance_schedule_handler.rs
 
-use chrono::NaiveDateTime;
-use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, Set};
-use entity::maintenance_schedule::{self, ActiveModel, Model};
+use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, Set, TransactionTrait};
+use entity::maintenance_schedule::{self, Model};
 use uuid::Uuid;
+use entity::audit_event::{ACTION_CREATE, ACTION_DELETE, ACTION_UPDATE};
+use crate::controllers::audit_event_handler::AuditEventHandler;
+use crate::error::AppError;
+use crate::validation::{self, Validator};
 use crate::{ApiResponse, AppState};
 
+pub const MAINTENANCE_STATUSES: [&str; 4] = ["Pending", "Ongoing", "Completed", "Cancelled"];
+
 pub struct MaintenanceScheduleHandler;
 
 impl MaintenanceScheduleHandler {
     // View all maintenance schedules
     pub async fn view_maintenance_schedules(
         state: &AppState,
-    ) -> Result<ApiResponse<Vec<Model>>, String> {
+    ) -> Result<ApiResponse<Vec<Model>>, AppError> {
         match maintenance_schedule::Entity::find()
             .order_by_asc(maintenance_schedule::Column::StartDate)
             .all(&state.db)
             .await
         {
             Ok(maintenance_schedules) => Ok(ApiResponse::success(maintenance_schedules)),
-            Err(err) => Err(format!("Error fetching maintenance schedules: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error fetching maintenance schedules: {}", err))),
         }
     }
 
@@ -27,7 +32,7 @@ impl MaintenanceScheduleHandler {
     pub async fn view_maintenance_schedule_by_staff(
         state: &AppState,
         staff_id: String,
-    ) -> Result<ApiResponse<Vec<Model>>, String> {
+    ) -> Result<ApiResponse<Vec<Model>>, AppError> {
         match maintenance_schedule::Entity::find()
             .filter(maintenance_schedule::Column::StaffId.eq(staff_id))
             .order_by_asc(maintenance_schedule::Column::StartDate)
@@ -35,12 +40,13 @@ impl MaintenanceScheduleHandler {
             .await
         {
             Ok(maintenance_schedules) => Ok(ApiResponse::success(maintenance_schedules)),
-            Err(err) => Err(format!("Error fetching maintenance schedules for staff: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error fetching maintenance schedules for staff: {}", err))),
         }
     }
 
 
     // Save maintenance schedule data (create new schedule)
+    #[allow(clippy::too_many_arguments)]
     pub async fn save_maintenance_schedule_data(
         state: &AppState,
         ride_id: String,
@@ -49,7 +55,15 @@ impl MaintenanceScheduleHandler {
         start_date: String,
         end_date: String,
         status: String,
-    ) -> Result<ApiResponse<String>, String> {
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new()
+            .field("ride_id", &ride_id, &[validation::required])
+            .field("staff_id", &staff_id, &[validation::required])
+            .date_range(Some(&start_date), Some(&end_date))
+            .one_of("status", &status, &MAINTENANCE_STATUSES)
+            .finish()?;
+
         // **Check if the assigned staff already has an active schedule**
         let active_statuses = vec!["Pending".to_string(), "Ongoing".to_string()];
 
@@ -61,19 +75,17 @@ impl MaintenanceScheduleHandler {
             )
             .one(&state.db)
             .await
-            .map_err(|e| format!("Database error checking existing schedule: {}", e))?;
+            .map_err(|e| AppError::Database(format!("Database error checking existing schedule: {}", e)))?;
 
         if existing_active_schedule.is_some() {
-            return Err("This staff member already has an active (Pending or Ongoing) maintenance task.".to_string());
+            return Err(AppError::Conflict("This staff member already has an active (Pending or Ongoing) maintenance task.".to_string()));
         }
 
         // If no active schedule, proceed with creation
         let maintenance_task_id = Uuid::new_v4().to_string();
-        // Parse the custom "YYYY-MM-DDTHH:MM" format
-        let parsed_start_date = NaiveDateTime::parse_from_str(&start_date, "%Y-%m-%dT%H:%M")
-            .map_err(|e| format!("Invalid start_date format: {}", e))?;
-        let parsed_end_date = NaiveDateTime::parse_from_str(&end_date, "%Y-%m-%dT%H:%M")
-            .map_err(|e| format!("Invalid end_date format: {}", e))?;
+        // Parse the custom "YYYY-MM-DDTHH:MM" format (checked above)
+        let parsed_start_date = validation::parse_local_date_time(&start_date).unwrap_or_default();
+        let parsed_end_date = validation::parse_local_date_time(&end_date).unwrap_or_default();
 
 
         let new_maintenance_schedule = maintenance_schedule::ActiveModel {
@@ -84,13 +96,14 @@ impl MaintenanceScheduleHandler {
             start_date: Set(parsed_start_date),
             end_date: Set(parsed_end_date),
             status: Set(status),
-            ..Default::default()
         };
 
-        match maintenance_schedule::Entity::insert(new_maintenance_schedule).exec(&state.db).await {
-            Ok(_) => Ok(ApiResponse::success("Maintenance schedule created successfully".to_string())),
-            Err(err) => Err(format!("Error creating maintenance schedule: {}", err)),
-        }
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let created = maintenance_schedule::Entity::insert(new_maintenance_schedule).exec_with_returning(&txn).await
+            .map_err(|err| AppError::Database(format!("Error creating maintenance schedule: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_CREATE, "maintenance_schedule", &created.maintenance_task_id, None, AuditEventHandler::snapshot(&created)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        Ok(ApiResponse::success("Maintenance schedule created successfully".to_string()))
     }
 
     // Update maintenance schedule data
@@ -103,13 +116,22 @@ impl MaintenanceScheduleHandler {
         start_date: Option<String>,
         end_date: Option<String>,
         status: Option<String>,
-    ) -> Result<ApiResponse<String>, String> {
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new()
+            .optional("ride_id", ride_id.as_deref(), &[validation::required])
+            .optional("staff_id", staff_id.as_deref(), &[validation::required])
+            .date_range(start_date.as_deref(), end_date.as_deref())
+            .optional_one_of("status", status.as_deref(), &MAINTENANCE_STATUSES)
+            .finish()?;
+
         let maintenance_schedule_record = match maintenance_schedule::Entity::find_by_id(maintenance_task_id).one(&state.db).await {
             Ok(Some(schedule)) => schedule,
-            Ok(None) => return Err("Maintenance schedule not found".to_string()),
-            Err(err) => return Err(format!("Error fetching maintenance schedule: {}", err)),
+            Ok(None) => return Err(AppError::NotFound("Maintenance schedule not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching maintenance schedule: {}", err))),
         };
 
+        let before = AuditEventHandler::snapshot(&maintenance_schedule_record);
         let mut active_maintenance_schedule: maintenance_schedule::ActiveModel = maintenance_schedule_record.into();
 
         if let Some(new_ride_id) = ride_id {
@@ -122,13 +144,11 @@ impl MaintenanceScheduleHandler {
             active_maintenance_schedule.description = Set(new_description);
         }
         if let Some(start_date_str) = start_date {
-            let parsed_start_date = NaiveDateTime::parse_from_str(&start_date_str, "%Y-%m-%dT%H:%M")
-            .map_err(|e| format!("Invalid start_date format: {}", e))?;
+            let parsed_start_date = validation::parse_local_date_time(&start_date_str).unwrap_or_default();
             active_maintenance_schedule.start_date = Set(parsed_start_date);
         }
         if let Some(end_date_str) = end_date {
-            let parsed_end_date = NaiveDateTime::parse_from_str(&end_date_str, "%Y-%m-%dT%H:%M")
-            .map_err(|e| format!("Invalid end_date format: {}", e))?;
+            let parsed_end_date = validation::parse_local_date_time(&end_date_str).unwrap_or_default();
             active_maintenance_schedule.end_date = Set(parsed_end_date);
         }
         if let Some(new_status) = status {
@@ -136,23 +156,95 @@ impl MaintenanceScheduleHandler {
         }
 
 
-        match active_maintenance_schedule.update(&state.db).await {
-            Ok(_) => Ok(ApiResponse::success("Maintenance schedule updated successfully".to_string())),
-            Err(err) => Err(format!("Error updating maintenance schedule: {}", err)),
-        }
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let updated = active_maintenance_schedule.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error updating maintenance schedule: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_UPDATE, "maintenance_schedule", &updated.maintenance_task_id, before, AuditEventHandler::snapshot(&updated)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        Ok(ApiResponse::success("Maintenance schedule updated successfully".to_string()))
     }
 
     // Delete maintenance schedule data
-    pub async fn delete_maintenance_schedule_data(state: &AppState, maintenance_task_id: String) -> Result<String, String> {
-        match maintenance_schedule::Entity::delete_by_id(maintenance_task_id).exec(&state.db).await {
-            Ok(delete_result) => {
-                if delete_result.rows_affected > 0 {
-                    Ok("Maintenance schedule deleted successfully".to_string())
-                } else {
-                    Err("Maintenance schedule not found".to_string())
-                }
-            }
-            Err(err) => Err(format!("Error deleting maintenance schedule: {}", err)),
+    pub async fn delete_maintenance_schedule_data(state: &AppState, maintenance_task_id: String, actor_id: String) -> Result<ApiResponse<String>, AppError> {
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let existing = maintenance_schedule::Entity::find_by_id(maintenance_task_id.clone()).one(&txn).await.ok().flatten();
+        let delete_result = maintenance_schedule::Entity::delete_by_id(maintenance_task_id.clone()).exec(&txn).await
+            .map_err(|err| AppError::Database(format!("Error deleting maintenance schedule: {}", err)))?;
+        if delete_result.rows_affected == 0 {
+            return Err(AppError::NotFound("Maintenance schedule not found".to_string()));
         }
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_DELETE, "maintenance_schedule", &maintenance_task_id, existing.as_ref().and_then(AuditEventHandler::snapshot), None).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        Ok(ApiResponse::success("Maintenance schedule deleted successfully".to_string()))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::test_support::{self, data, error, ACTOR};
+
+    async fn schedule(state: &AppState, ride_id: &str, staff_id: &str, status: &str) -> Result<ApiResponse<String>, AppError> {
+        MaintenanceScheduleHandler::save_maintenance_schedule_data(
+            state,
+            ride_id.to_string(),
+            staff_id.to_string(),
+            None,
+            "2026-10-20T08:00".to_string(),
+            "2026-10-20T12:00".to_string(),
+            status.to_string(),
+            ACTOR.to_string(),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn staff_with_an_active_task_cannot_be_scheduled_again() {
+        let state = test_support::test_state().await;
+        let technician = test_support::seed_staff(&state, "Maintenance Staff").await;
+        let colleague = test_support::seed_staff(&state, "Maintenance Staff").await;
+        let ride = test_support::seed_ride(&state, &technician.staff_id, "50000").await;
+
+        data(schedule(&state, &ride.ride_id, &technician.staff_id, "Pending").await);
+        let err = error(schedule(&state, &ride.ride_id, &technician.staff_id, "Pending").await);
+        assert_eq!(err.code(), ErrorCode::Conflict);
+        // Someone else is free to take the ride
+        data(schedule(&state, &ride.ride_id, &colleague.staff_id, "Ongoing").await);
+
+        // Once the first task is done the technician can be booked again
+        let tasks = data(MaintenanceScheduleHandler::view_maintenance_schedule_by_staff(&state, technician.staff_id.clone()).await);
+        data(MaintenanceScheduleHandler::update_maintenance_schedule_data(
+            &state,
+            tasks[0].maintenance_task_id.clone(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some("Completed".to_string()),
+            ACTOR.to_string(),
+        )
+        .await);
+        data(schedule(&state, &ride.ride_id, &technician.staff_id, "Pending").await);
+    }
+
+    #[tokio::test]
+    async fn schedule_must_end_after_it_starts() {
+        let state = test_support::test_state().await;
+        let technician = test_support::seed_staff(&state, "Maintenance Staff").await;
+
+        let err = error(MaintenanceScheduleHandler::save_maintenance_schedule_data(
+            &state,
+            "ride".to_string(),
+            technician.staff_id,
+            None,
+            "2026-10-20T12:00".to_string(),
+            "2026-10-20T08:00".to_string(),
+            "Pending".to_string(),
+            ACTOR.to_string(),
+        )
+        .await);
+        assert_eq!(err.fields().iter().map(|field| field.field.as_str()).collect::<Vec<_>>(), ["end_date"]);
+        assert!(data(MaintenanceScheduleHandler::view_maintenance_schedules(&state).await).is_empty());
     }
-}
\ No newline at end of file
+}
@@ -1,14 +1,23 @@
-use sea_orm::{ActiveModelTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder, QuerySelect, RelationTrait, ColumnTrait};
-use entity::menu_item::{self, ActiveModel, Model};
+use sea_orm::{ActiveModelTrait, EntityTrait, QueryFilter, QueryOrder, ColumnTrait, TransactionTrait};
+use entity::menu_item::{self, Model};
 use uuid::Uuid;
+use entity::audit_event::{ACTION_CREATE, ACTION_DELETE, ACTION_RESTORE, ACTION_UPDATE};
+use crate::clock;
+use crate::controllers::audit_event_handler::AuditEventHandler;
+use crate::controllers::dependency_handler::{DependencyHandler, DependencyReport};
+use crate::cache::TAG_CUSTOMER;
+use crate::error::AppError;
+use crate::validation::{self, Validator};
 use crate::{ApiResponse, AppState};
 
 pub struct MenuItemHandler;
 
 impl MenuItemHandler {
     // View menu items for a specific restaurant (or all if restaurant_id is None)
-    pub async fn view_menu_items(state: &AppState, restaurant_id: Option<String>) -> Result<ApiResponse<Vec<Model>>, String> {
-        let mut query = menu_item::Entity::find().order_by_asc(menu_item::Column::Name);
+    pub async fn view_menu_items(state: &AppState, restaurant_id: Option<String>) -> Result<ApiResponse<Vec<Model>>, AppError> {
+        let mut query = menu_item::Entity::find()
+            .filter(menu_item::Column::DeletedAt.is_null())
+            .order_by_asc(menu_item::Column::Name);
 
         if let Some(rest_id) = restaurant_id {
             query = query.filter(menu_item::Column::RestaurantId.eq(rest_id));
@@ -16,7 +25,7 @@ impl MenuItemHandler {
 
         match query.all(&state.db).await {
             Ok(menu_items) => Ok(ApiResponse::success(menu_items)),
-            Err(err) => Err(format!("Error fetching menu items: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error fetching menu items: {}", err))),
         }
     }
 
@@ -25,16 +34,16 @@ impl MenuItemHandler {
     pub async fn get_menu_item_details(
         state: &AppState,
         menu_item_id: String,
-    ) -> Result<ApiResponse<Model>, String> {
+    ) -> Result<ApiResponse<Model>, AppError> {
         match menu_item::Entity::find_by_id(menu_item_id.clone()).one(&state.db).await {
             Ok(Some(menu_item_details)) => {
                 Ok(ApiResponse::success(menu_item_details))
             }
             Ok(None) => {
-                Err("Menu item not found".to_string())
+                Err(AppError::NotFound("Menu item not found".to_string()))
             }
             Err(err) => {
-                Err(format!("Database error fetching menu item details: {}", err))
+                Err(AppError::Database(format!("Database error fetching menu item details: {}", err)))
             }
         }
     }
@@ -46,7 +55,14 @@ impl MenuItemHandler {
         name: String,
         price: String,
         restaurant_id: String, // Required: menu item must belong to a restaurant
-    ) -> Result<ApiResponse<String>, String> {
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new()
+            .field("name", &name, &[validation::name])
+            .field("price", &price, &[validation::positive_money])
+            .field("restaurant_id", &restaurant_id, &[validation::required])
+            .finish()?;
+
         // Generate a UUID for the menu_item_id
         let menu_item_id = Uuid::new_v4().to_string();
 
@@ -59,10 +75,12 @@ impl MenuItemHandler {
             ..Default::default()
         };
 
-        match menu_item::Entity::insert(new_menu_item).exec(&state.db).await {
-            Ok(_) => Ok(ApiResponse::success("Menu item created successfully".to_string())),
-            Err(err) => Err(format!("Error creating menu item: {}", err)),
-        }
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let created = menu_item::Entity::insert(new_menu_item).exec_with_returning(&txn).await
+            .map_err(|err| AppError::Database(format!("Error creating menu item: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_CREATE, "menu_item", &created.menu_item_id, None, AuditEventHandler::snapshot(&created)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        Ok(ApiResponse::success("Menu item created successfully".to_string()))
     }
 
     // Update menu item data
@@ -73,13 +91,24 @@ impl MenuItemHandler {
         name: Option<String>,
         price: Option<String>,
         restaurant_id: Option<String>, // Allow changing restaurant_id
-    ) -> Result<ApiResponse<String>, String> {
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new()
+            .optional("name", name.as_deref(), &[validation::name])
+            .optional("price", price.as_deref(), &[validation::positive_money])
+            .optional("restaurant_id", restaurant_id.as_deref(), &[validation::required])
+            .finish()?;
+
         let menu_item_record = match menu_item::Entity::find_by_id(menu_item_id).one(&state.db).await {
             Ok(Some(menu_item)) => menu_item,
-            Ok(None) => return Err("Menu item not found".to_string()),
-            Err(err) => return Err(format!("Error fetching menu item: {}", err)),
+            Ok(None) => return Err(AppError::NotFound("Menu item not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching menu item: {}", err))),
         };
+        if menu_item_record.deleted_at.is_some() {
+            return Err(AppError::Conflict("Menu item is archived; restore it before editing".to_string()));
+        }
 
+        let before = AuditEventHandler::snapshot(&menu_item_record);
         let mut active_menu_item: menu_item::ActiveModel = menu_item_record.into();
 
         if let Some(new_photo) = photo {
@@ -96,23 +125,79 @@ impl MenuItemHandler {
         }
 
 
-        match active_menu_item.update(&state.db).await {
-            Ok(_) => Ok(ApiResponse::success("Menu item updated successfully".to_string())),
-            Err(err) => Err(format!("Error updating menu item: {}", err)),
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let updated = active_menu_item.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error updating menu item: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_UPDATE, "menu_item", &updated.menu_item_id, before, AuditEventHandler::snapshot(&updated)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        Ok(ApiResponse::success("Menu item updated successfully".to_string()))
+    }
+
+    // Archive menu item data (soft delete): the row stays so history and reports still resolve it
+    pub async fn delete_menu_item_data(state: &AppState, menu_item_id: String, cascade: bool, actor_id: String) -> Result<ApiResponse<DependencyReport>, AppError> {
+        let existing = match menu_item::Entity::find_by_id(menu_item_id.clone()).one(&state.db).await {
+            Ok(Some(record)) if record.deleted_at.is_none() => record,
+            Ok(_) => return Err(AppError::NotFound("Menu item not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching menu item: {}", err))),
+        };
+
+        // Dependents are checked and cascaded in the same transaction as the archive itself
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let report = DependencyHandler::check_menu_item(&txn, &menu_item_id).await?;
+        if let Some(blocked) = DependencyHandler::blocked(&report, cascade) {
+            return Ok(blocked);
+        }
+        let now = clock::jakarta_now();
+        if !report.is_clear() {
+            DependencyHandler::cascade_menu_item(&txn, &menu_item_id, &actor_id).await?;
+        }
+
+        let before = AuditEventHandler::snapshot(&existing);
+        let mut active_menu_item: menu_item::ActiveModel = existing.into();
+        active_menu_item.deleted_at = sea_orm::ActiveValue::Set(Some(now));
+        let archived = active_menu_item.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error deleting menu item: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_DELETE, "menu_item", &menu_item_id, before, AuditEventHandler::snapshot(&archived)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+
+        if !report.is_clear() {
+            state.cache.invalidate(&[TAG_CUSTOMER]).await;
         }
+        Ok(ApiResponse::Success { data: report, message: Some("Menu item deleted successfully".to_string()) })
     }
 
-    // Delete menu item data
-    pub async fn delete_menu_item_data(state: &AppState, menu_item_id: String) -> Result<String, String> {
-        match menu_item::Entity::delete_by_id(menu_item_id).exec(&state.db).await {
-            Ok(delete_result) => {
-                if delete_result.rows_affected > 0 {
-                    Ok("Menu item deleted successfully".to_string())
-                } else {
-                    Err("Menu item not found".to_string())
-                }
-            }
-            Err(err) => Err(format!("Error deleting menu item: {}", err)),
+    // Bring an archived menu item back into the lists
+    pub async fn restore_menu_item_data(state: &AppState, menu_item_id: String, actor_id: String) -> Result<ApiResponse<String>, AppError> {
+        let existing = match menu_item::Entity::find_by_id(menu_item_id.clone()).one(&state.db).await {
+            Ok(Some(record)) => record,
+            Ok(None) => return Err(AppError::NotFound("Menu item not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching menu item: {}", err))),
+        };
+        if existing.deleted_at.is_none() {
+            return Err(AppError::Conflict("Menu item is not archived".to_string()));
+        }
+
+        let before = AuditEventHandler::snapshot(&existing);
+        let mut active_menu_item: menu_item::ActiveModel = existing.into();
+        active_menu_item.deleted_at = sea_orm::ActiveValue::Set(None);
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let restored = active_menu_item.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error restoring menu item: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_RESTORE, "menu_item", &menu_item_id, before, AuditEventHandler::snapshot(&restored)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        Ok(ApiResponse::success("Menu item restored successfully".to_string()))
+    }
+
+    // View archived menu items, most recently archived first
+    pub async fn view_archived_menu_items(state: &AppState) -> Result<ApiResponse<Vec<Model>>, AppError> {
+        match menu_item::Entity::find()
+            .filter(menu_item::Column::DeletedAt.is_not_null())
+            .order_by_desc(menu_item::Column::DeletedAt)
+            .all(&state.db)
+            .await
+        {
+            Ok(archived) => Ok(ApiResponse::success(archived)),
+            Err(err) => Err(AppError::Database(format!("Error fetching archived menu items: {}", err))),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,397 @@
+use entity::audit_event::ACTION_UPDATE;
+use entity::{offline_sync_conflict, offline_write, staff};
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseTransaction, EntityTrait, QueryFilter, QueryOrder, Set, TransactionTrait};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::clock;
+use crate::cache::{TAG_CUSTOMER, TAG_ORDER_SOUVENIR, TAG_SOUVENIR};
+use crate::controllers::audit_event_handler::AuditEventHandler;
+use crate::controllers::chat_handler::ChatHandler;
+use crate::controllers::order_restaurant_handler::{OrderRestaurantHandler, ORDER_CREATED, SAVE_ORDER_RESTAURANT};
+use crate::controllers::order_souvenir_handler::{OrderSouvenirHandler, ORDER_SOUVENIR_CREATED, SAVE_ORDER_SOUVENIR};
+use crate::controllers::ride_queue_handler::{RideQueueHandler, RIDE_QUEUE_CREATED, SAVE_RIDE_QUEUE};
+use crate::controllers::staff_handler::StaffHandler;
+use crate::error::AppError;
+use crate::idempotency::Idempotency;
+use crate::offline::{OfflineStore, OfflineWrite};
+use crate::{ApiResponse, AppState};
+
+// Venue managers and Customer Service handle what offline kiosks could not apply; executives
+// can as well
+pub const SYNC_CONFLICT_ROLES: [&str; 5] = ["CustomerServiceStaff", "CustomerServiceManager", "FBSupervisor", "RetailManager", "RideManager"];
+
+// A write that keeps failing with a database error while the database answers is not a
+// connection problem; after this many tries it is reported like any other conflict
+const MAX_REPLAY_ATTEMPTS: i32 = 5;
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct SyncReport {
+    pub synced: usize,
+    pub conflicts: usize,
+    pub remaining: usize, // Still queued, e.g. because the connection dropped again
+}
+
+pub struct OfflineSyncHandler;
+
+impl OfflineSyncHandler {
+    // Apply a queued write through the same path as the kiosk online. The order, the stock and the
+    // charge are written in one transaction together with the write's idempotency key, so a
+    // write that fails part way (out of stock, short of balance, connection lost) leaves nothing
+    // behind and a replay of a write that already went through does nothing.
+    pub async fn apply(state: &AppState, write: &OfflineWrite, idempotency_key: &str) -> Result<(), AppError> {
+        let txn = state.db.begin().await
+            .map_err(|err| AppError::Database(format!("Error starting replay: {}", err)))?;
+        if !Idempotency::claim_in(&txn, idempotency_key).await? {
+            return Ok(());
+        }
+
+        let (command, request, message) = Self::apply_in(&txn, write).await?;
+        Idempotency::complete_in(&txn, idempotency_key, command, request, &message).await?;
+        txn.commit().await
+            .map_err(|err| AppError::Database(format!("Error committing replay: {}", err)))?;
+
+        state.cache.invalidate(&[TAG_CUSTOMER, TAG_SOUVENIR, TAG_ORDER_SOUVENIR]).await;
+        Ok(())
+    }
+
+    // The writes of one replay, with the command, request and answer its idempotency key is
+    // recorded under
+    async fn apply_in(txn: &DatabaseTransaction, write: &OfflineWrite) -> Result<(&'static str, serde_json::Value, &'static str), AppError> {
+        match write {
+            OfflineWrite::OrderRestaurant { customer_id, restaurant_id, menu_item_id, quantity, actor_id } => {
+                OrderRestaurantHandler::place_order(txn, customer_id.clone(), restaurant_id.clone(), menu_item_id.clone(), *quantity, actor_id.as_deref()).await?;
+                let request = OrderRestaurantHandler::idempotency_request(customer_id, restaurant_id, menu_item_id, *quantity);
+                Ok((SAVE_ORDER_RESTAURANT, request, ORDER_CREATED))
+            }
+            OfflineWrite::OrderSouvenir { customer_id, store_id, souvenir_id, quantity, actor_id } => {
+                OrderSouvenirHandler::place_order(txn, customer_id.clone(), store_id.clone(), souvenir_id.clone(), *quantity, actor_id.as_deref()).await?;
+                let request = OrderSouvenirHandler::idempotency_request(customer_id, store_id, souvenir_id, *quantity);
+                Ok((SAVE_ORDER_SOUVENIR, request, ORDER_SOUVENIR_CREATED))
+            }
+            OfflineWrite::RideQueueJoin { ride_id, customer_id, actor_id } => {
+                // The position the kiosk showed offline is stale: join at the back of the queue now
+                RideQueueHandler::join_queue(txn, ride_id.clone(), customer_id.clone(), None, actor_id.as_deref()).await?;
+                Ok((SAVE_RIDE_QUEUE, RideQueueHandler::idempotency_request(ride_id, customer_id), RIDE_QUEUE_CREATED))
+            }
+        }
+    }
+
+    // Replay everything the kiosk queued, oldest first. Stops at the first database error, since
+    // that usually means the connection is gone again.
+    pub async fn sync_pending(state: &AppState, store: &OfflineStore, venue: Option<&str>) -> Result<SyncReport, AppError> {
+        let pending = store.pending_writes().await?;
+        let mut report = SyncReport::default();
+
+        for (index, queued) in pending.iter().enumerate() {
+            let outcome = match OfflineWrite::from_payload(&queued.payload) {
+                Ok(write) => Self::apply(state, &write, &queued.idempotency_key).await,
+                Err(err) => Err(err),
+            };
+            match outcome {
+                Ok(()) => {
+                    store.mark_synced(queued).await?;
+                    report.synced += 1;
+                }
+                Err(AppError::Database(message)) if queued.attempts + 1 < MAX_REPLAY_ATTEMPTS => {
+                    store.record_failure(queued, &message).await?;
+                    report.remaining = pending.len() - index;
+                    break;
+                }
+                Err(err) => {
+                    Self::report_conflict(state, queued, venue, err.message()).await?;
+                    store.mark_conflict(queued, err.message()).await?;
+                    report.conflicts += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    // Record the conflict centrally for staff and tell the customer in their Customer Service chat
+    async fn report_conflict(state: &AppState, queued: &offline_write::Model, venue: Option<&str>, reason: &str) -> Result<(), AppError> {
+        let already_reported = offline_sync_conflict::Entity::find()
+            .filter(offline_sync_conflict::Column::IdempotencyKey.eq(queued.idempotency_key.clone()))
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching sync conflict: {}", err)))?;
+        if already_reported.is_some() {
+            return Ok(());
+        }
+
+        let write = OfflineWrite::from_payload(&queued.payload).ok();
+        let conflict = offline_sync_conflict::ActiveModel {
+            conflict_id: Set(Uuid::new_v4().to_string()),
+            idempotency_key: Set(queued.idempotency_key.clone()),
+            venue: Set(venue.map(str::to_string)),
+            kind: Set(queued.kind.clone()),
+            payload: Set(queued.payload.clone()),
+            customer_id: Set(write.as_ref().map(|write| write.customer_id().to_string())),
+            reason: Set(reason.to_string()),
+            status: Set(offline_sync_conflict::STATUS_OPEN.to_string()),
+            queued_at: Set(queued.queued_at),
+            reported_at: Set(clock::jakarta_now()),
+            resolved_by: Set(None),
+            resolved_at: Set(None),
+        };
+        offline_sync_conflict::Entity::insert(conflict)
+            .exec_without_returning(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error recording sync conflict: {}", err)))?;
+
+        if let Some(write) = write {
+            let notice = format!(
+                "Your {} from {} was made while the kiosk was offline and could not be completed: {}. Our staff will follow up with you.",
+                write.description(),
+                queued.queued_at.format("%Y-%m-%d %H:%M"),
+                reason
+            );
+            if let Err(err) = ChatHandler::notify_customer(state, write.customer_id(), notice).await {
+                eprintln!("Failed to notify customer about sync conflict: {}", err);
+            }
+        }
+        Ok(())
+    }
+
+    async fn ensure_can_resolve(state: &AppState, staff_id: &str) -> Result<(), AppError> {
+        let requester = staff::Entity::find_by_id(staff_id.to_string())
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching staff: {}", err)))?
+            .filter(|requester| requester.deleted_at.is_none())
+            .ok_or_else(|| AppError::NotFound("Staff not found".to_string()))?;
+        let is_executive = StaffHandler::division_for_role(&requester.role) == Some("Executive");
+        if !is_executive && !SYNC_CONFLICT_ROLES.contains(&requester.role.as_str()) {
+            return Err(AppError::Forbidden("Only venue managers and Customer Service can handle sync conflicts".to_string()));
+        }
+        Ok(())
+    }
+
+    // Newest first; resolved conflicts only when asked for
+    pub async fn view_sync_conflicts(
+        state: &AppState,
+        staff_id: String,
+        include_resolved: bool,
+    ) -> Result<ApiResponse<Vec<offline_sync_conflict::Model>>, AppError> {
+        Self::ensure_can_resolve(state, &staff_id).await?;
+        let mut query = offline_sync_conflict::Entity::find().order_by_desc(offline_sync_conflict::Column::ReportedAt);
+        if !include_resolved {
+            query = query.filter(offline_sync_conflict::Column::Status.eq(offline_sync_conflict::STATUS_OPEN));
+        }
+        match query.all(&state.db).await {
+            Ok(conflicts) => Ok(ApiResponse::success(conflicts)),
+            Err(err) => Err(AppError::Database(format!("Error fetching sync conflicts: {}", err))),
+        }
+    }
+
+    async fn open_conflict(state: &AppState, conflict_id: &str) -> Result<offline_sync_conflict::Model, AppError> {
+        let conflict = offline_sync_conflict::Entity::find_by_id(conflict_id.to_string())
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching sync conflict: {}", err)))?
+            .ok_or_else(|| AppError::NotFound("Sync conflict not found".to_string()))?;
+        if conflict.status != offline_sync_conflict::STATUS_OPEN {
+            return Err(AppError::Conflict(format!("Sync conflict is already {}", conflict.status.to_lowercase())));
+        }
+        Ok(conflict)
+    }
+
+    async fn resolve(
+        state: &AppState,
+        conflict: offline_sync_conflict::Model,
+        staff_id: String,
+        status: &str,
+        reason: Option<String>,
+    ) -> Result<(), AppError> {
+        let before = AuditEventHandler::snapshot(&conflict);
+        let mut active: offline_sync_conflict::ActiveModel = conflict.into();
+        active.status = Set(status.to_string());
+        if let Some(reason) = reason {
+            active.reason = Set(reason);
+        }
+        if status != offline_sync_conflict::STATUS_OPEN {
+            active.resolved_by = Set(Some(staff_id.clone()));
+            active.resolved_at = Set(Some(clock::jakarta_now()));
+        }
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let updated = active
+            .update(&txn)
+            .await
+            .map_err(|err| AppError::Database(format!("Error updating sync conflict: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&staff_id), ACTION_UPDATE, "offline_sync_conflict", &updated.conflict_id, before, AuditEventHandler::snapshot(&updated)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))
+    }
+
+    // Try the write again, e.g. after the customer topped up or stock was corrected
+    pub async fn retry_sync_conflict(state: &AppState, conflict_id: String, staff_id: String) -> Result<ApiResponse<String>, AppError> {
+        Self::ensure_can_resolve(state, &staff_id).await?;
+        let conflict = Self::open_conflict(state, &conflict_id).await?;
+        let write = OfflineWrite::from_payload(&conflict.payload)?;
+
+        match Self::apply(state, &write, &conflict.idempotency_key).await {
+            Ok(()) => {
+                Self::resolve(state, conflict, staff_id, offline_sync_conflict::STATUS_APPLIED, None).await?;
+                Ok(ApiResponse::success("Queued write applied successfully".to_string()))
+            }
+            Err(err @ AppError::Database(_)) => Err(err),
+            Err(err) => {
+                // Still open, with the reason it failed this time
+                Self::resolve(state, conflict, staff_id, offline_sync_conflict::STATUS_OPEN, Some(err.message().to_string())).await?;
+                Err(err)
+            }
+        }
+    }
+
+    // Give up on the write, e.g. once the customer has been refunded or served another way
+    pub async fn dismiss_sync_conflict(state: &AppState, conflict_id: String, staff_id: String) -> Result<ApiResponse<String>, AppError> {
+        Self::ensure_can_resolve(state, &staff_id).await?;
+        let conflict = Self::open_conflict(state, &conflict_id).await?;
+        Self::resolve(state, conflict, staff_id, offline_sync_conflict::STATUS_DISMISSED, None).await?;
+        Ok(ApiResponse::success("Sync conflict dismissed successfully".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controllers::customer_handler::CustomerHandler;
+    use crate::controllers::souvenir_handler::SouvenirHandler;
+    use crate::error::ErrorCode;
+    use crate::test_support::{self, data, error, ACTOR};
+    use entity::souvenir;
+    use rust_decimal::Decimal;
+
+    async fn queue(store: &OfflineStore, state: &AppState, write: OfflineWrite) {
+        let offline = async { Err(AppError::Database("connection refused".to_string())) };
+        store.set_online(false);
+        store.write_through(&state.db, write, None, offline).await.unwrap();
+        store.set_online(true);
+    }
+
+    fn souvenir_order(customer_id: &str, item: &souvenir::Model, quantity: i32) -> OfflineWrite {
+        OfflineWrite::OrderSouvenir {
+            customer_id: customer_id.to_string(),
+            store_id: item.store_id.clone(),
+            souvenir_id: item.souvenir_id.clone(),
+            quantity,
+            actor_id: Some(customer_id.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn replay_places_the_order_charges_the_customer_and_takes_the_stock() {
+        let state = test_support::test_state().await;
+        let store = OfflineStore::open("sqlite::memory:").await.unwrap();
+        let customer = test_support::seed_customer(&state, "100000").await;
+        let shop = test_support::seed_store(&state).await;
+        let plush = test_support::seed_souvenir(&state, &shop.store_id, Decimal::from(25000), 5).await;
+        let staff_member = test_support::seed_staff(&state, "RideManager").await;
+        let coaster = test_support::seed_ride(&state, &staff_member.staff_id, "30000").await;
+
+        queue(&store, &state, souvenir_order(&customer.customer_id, &plush, 2)).await;
+        let join = OfflineWrite::RideQueueJoin { ride_id: coaster.ride_id.clone(), customer_id: customer.customer_id.clone(), actor_id: None };
+        queue(&store, &state, join).await;
+
+        let report = OfflineSyncHandler::sync_pending(&state, &store, Some("SR-001")).await.unwrap();
+        assert_eq!(report, SyncReport { synced: 2, conflicts: 0, remaining: 0 });
+
+        let charged = data(CustomerHandler::get_customer_details(&state, customer.customer_id.clone()).await);
+        assert_eq!(charged.virtual_balance, "20000");
+        let stocked = data(SouvenirHandler::get_souvenir_details(&state, plush.souvenir_id.clone()).await);
+        assert_eq!(stocked.stock, 3);
+        let orders = data(OrderSouvenirHandler::view_order_souvenirs_by_customer(&state, customer.customer_id.clone(), shop.store_id).await);
+        assert_eq!(orders.len(), 1);
+        let riders = data(RideQueueHandler::view_ride_queues(&state, Some(coaster.ride_id)).await);
+        assert_eq!(riders.len(), 1);
+
+        let again = OfflineSyncHandler::sync_pending(&state, &store, Some("SR-001")).await.unwrap();
+        assert_eq!(again, SyncReport::default(), "synced writes are never replayed");
+    }
+
+    #[tokio::test]
+    async fn a_replay_that_fails_part_way_leaves_nothing_behind() {
+        let state = test_support::test_state().await;
+        let customer = test_support::seed_customer(&state, "10000").await;
+        let shop = test_support::seed_store(&state).await;
+        let plush = test_support::seed_souvenir(&state, &shop.store_id, Decimal::from(25000), 5).await;
+
+        // The order and the stock go through before the charge finds the balance short
+        let err = OfflineSyncHandler::apply(&state, &souvenir_order(&customer.customer_id, &plush, 1), "replay-1").await.unwrap_err();
+        assert_eq!(err.code(), ErrorCode::Conflict);
+
+        let orders = data(OrderSouvenirHandler::view_order_souvenirs_by_customer(&state, customer.customer_id.clone(), shop.store_id).await);
+        assert!(orders.is_empty());
+        let stocked = data(SouvenirHandler::get_souvenir_details(&state, plush.souvenir_id).await);
+        assert_eq!(stocked.stock, 5);
+        let unchanged = data(CustomerHandler::get_customer_details(&state, customer.customer_id).await);
+        assert_eq!(unchanged.virtual_balance, "10000");
+    }
+
+    #[tokio::test]
+    async fn a_write_is_applied_once_however_often_it_is_replayed() {
+        let state = test_support::test_state().await;
+        let customer = test_support::seed_customer(&state, "100000").await;
+        let shop = test_support::seed_store(&state).await;
+        let plush = test_support::seed_souvenir(&state, &shop.store_id, Decimal::from(25000), 5).await;
+        let write = souvenir_order(&customer.customer_id, &plush, 1);
+
+        for _ in 0..2 {
+            OfflineSyncHandler::apply(&state, &write, "replay-1").await.unwrap();
+        }
+        let charged = data(CustomerHandler::get_customer_details(&state, customer.customer_id.clone()).await);
+        assert_eq!(charged.virtual_balance, "75000");
+        let orders = data(OrderSouvenirHandler::view_order_souvenirs_by_customer(&state, customer.customer_id.clone(), shop.store_id.clone()).await);
+        assert_eq!(orders.len(), 1);
+
+        // The kiosk retrying online with the same key gets the replay's answer
+        let retried = OrderSouvenirHandler::save_order_souvenir_data(
+            &state,
+            customer.customer_id.clone(),
+            shop.store_id.clone(),
+            plush.souvenir_id.clone(),
+            1,
+            ACTOR.to_string(),
+            Some("replay-1".to_string()),
+        )
+        .await;
+        assert_eq!(data(retried), ORDER_SOUVENIR_CREATED);
+        let orders = data(OrderSouvenirHandler::view_order_souvenirs_by_customer(&state, customer.customer_id, shop.store_id).await);
+        assert_eq!(orders.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn conflicts_are_reported_to_staff_and_can_be_retried() {
+        let state = test_support::test_state().await;
+        let store = OfflineStore::open("sqlite::memory:").await.unwrap();
+        let customer = test_support::seed_customer(&state, "10000").await;
+        let shop = test_support::seed_store(&state).await;
+        let plush = test_support::seed_souvenir(&state, &shop.store_id, Decimal::from(25000), 1).await;
+        let manager = test_support::seed_staff(&state, "RetailManager").await;
+        let waiter = test_support::seed_staff(&state, "Waiter").await;
+
+        queue(&store, &state, souvenir_order(&customer.customer_id, &plush, 1)).await;
+        let report = OfflineSyncHandler::sync_pending(&state, &store, Some("SR-001")).await.unwrap();
+        assert_eq!(report.conflicts, 1);
+        assert_eq!(store.status().await.unwrap().conflicts, 1);
+
+        let err = error(OfflineSyncHandler::view_sync_conflicts(&state, waiter.staff_id, false).await);
+        assert_eq!(err.code(), ErrorCode::Forbidden);
+        let conflicts = data(OfflineSyncHandler::view_sync_conflicts(&state, manager.staff_id.clone(), false).await);
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].reason.starts_with("Insufficient virtual balance"), "{}", conflicts[0].reason);
+        assert_eq!(conflicts[0].venue.as_deref(), Some("SR-001"));
+
+        // Still short: stays open
+        let err = error(OfflineSyncHandler::retry_sync_conflict(&state, conflicts[0].conflict_id.clone(), manager.staff_id.clone()).await);
+        assert_eq!(err.code(), ErrorCode::Conflict);
+
+        data(CustomerHandler::top_up_virtual_balance(&state, customer.customer_id.clone(), "20000".to_string(), ACTOR.to_string(), None).await);
+        data(OfflineSyncHandler::retry_sync_conflict(&state, conflicts[0].conflict_id.clone(), manager.staff_id.clone()).await);
+        let charged = data(CustomerHandler::get_customer_details(&state, customer.customer_id).await);
+        assert_eq!(charged.virtual_balance, "5000");
+        assert!(data(OfflineSyncHandler::view_sync_conflicts(&state, manager.staff_id.clone(), false).await).is_empty());
+
+        let err = error(OfflineSyncHandler::dismiss_sync_conflict(&state, conflicts[0].conflict_id.clone(), manager.staff_id).await);
+        assert_eq!(err.code(), ErrorCode::Conflict);
+    }
+}
@@ -1,9 +1,23 @@
 use chrono::{DateTime, Utc};
-use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, Set};
-use entity::order_restaurant::{self, ActiveModel, Model};
+use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, Set, TransactionTrait};
+use entity::menu_item;
+use entity::order_restaurant::{self, Model};
+use rust_decimal::Decimal;
 use uuid::Uuid;
+use entity::audit_event::{ACTION_CREATE, ACTION_DELETE, ACTION_UPDATE};
+use crate::clock;
+use crate::cache::TAG_CUSTOMER;
+use crate::controllers::audit_event_handler::AuditEventHandler;
+use crate::controllers::customer_handler::CustomerHandler;
+use crate::error::AppError;
+use crate::idempotency::Idempotency;
+use crate::validation::{self, Validator};
 use crate::{ApiResponse, AppState};
 
+pub const ORDER_RESTAURANT_STATUSES: [&str; 4] = ["Pending", "Cooking", "Ready to Serve", "Complete"];
+pub const ORDER_CREATED: &str = "Restaurant order created successfully";
+pub const SAVE_ORDER_RESTAURANT: &str = "save_order_restaurant_data";
+
 pub struct OrderRestaurantHandler;
 
 impl OrderRestaurantHandler {
@@ -11,7 +25,7 @@ impl OrderRestaurantHandler {
     pub async fn view_order_restaurants( // Renamed function
         state: &AppState,
         restaurant_id: Option<String>,
-    ) -> Result<ApiResponse<Vec<Model>>, String> {
+    ) -> Result<ApiResponse<Vec<Model>>, AppError> {
         let mut query = order_restaurant::Entity::find().order_by_asc(order_restaurant::Column::Timestamp);
 
         if let Some(restaurant_id) = restaurant_id {
@@ -20,7 +34,7 @@ impl OrderRestaurantHandler {
 
         match query.all(&state.db).await {
             Ok(orders) => Ok(ApiResponse::success(orders)),
-            Err(err) => Err(format!("Error fetching restaurant orders: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error fetching restaurant orders: {}", err))),
         }
     }
 
@@ -28,7 +42,7 @@ impl OrderRestaurantHandler {
         state: &AppState,
         customer_id: String,
         restaurant_id: String, // Added restaurant_id parameter
-    ) -> Result<ApiResponse<Vec<Model>>, String> {
+    ) -> Result<ApiResponse<Vec<Model>>, AppError> {
         match order_restaurant::Entity::find()
             .filter(order_restaurant::Column::CustomerId.eq(customer_id))
             .filter(order_restaurant::Column::RestaurantId.eq(restaurant_id)) // Added filter
@@ -37,7 +51,7 @@ impl OrderRestaurantHandler {
             .await
         {
             Ok(orders) => Ok(ApiResponse::success(orders)),
-            Err(err) => Err(format!("Error fetching orders for customer: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error fetching orders for customer: {}", err))),
         }
     }
 
@@ -46,7 +60,7 @@ impl OrderRestaurantHandler {
         state: &AppState,
         start_time: DateTime<Utc>,
         end_time: DateTime<Utc>,
-    ) -> Result<ApiResponse<Vec<Model>>, String> {
+    ) -> Result<ApiResponse<Vec<Model>>, AppError> {
         match order_restaurant::Entity::find()
             .filter(
                 Condition::all()
@@ -57,23 +71,93 @@ impl OrderRestaurantHandler {
             .await
         {
             Ok(orders) => Ok(ApiResponse::success(orders)),
-            Err(err) => Err(format!("Error fetching restaurant orders in range: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error fetching restaurant orders in range: {}", err))),
         }
     }
 
-    // Create a new restaurant order
+    // Create a new restaurant order and charge the customer for it. Retrying with the same
+    // idempotency key orders and charges only once.
     pub async fn save_order_restaurant_data( // Renamed function
         state: &AppState,
         customer_id: String,
         restaurant_id: String,
         menu_item_id: String,
         quantity: i32,
-    ) -> Result<ApiResponse<String>, String> {
+        actor_id: String,
+        idempotency_key: Option<String>,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let request = Self::idempotency_request(&customer_id, &restaurant_id, &menu_item_id, quantity);
+        let save = Self::create_order(state, customer_id, restaurant_id, menu_item_id, quantity, actor_id);
+        Idempotency::run(&state.db, idempotency_key, SAVE_ORDER_RESTAURANT, request, save).await
+    }
+
+    // What a repeated idempotency key must match, shared with offline replays of the order
+    pub(crate) fn idempotency_request(customer_id: &str, restaurant_id: &str, menu_item_id: &str, quantity: i32) -> serde_json::Value {
+        serde_json::json!({
+            "customer_id": customer_id,
+            "restaurant_id": restaurant_id,
+            "menu_item_id": menu_item_id,
+            "quantity": quantity,
+        })
+    }
+
+    async fn create_order(
+        state: &AppState,
+        customer_id: String,
+        restaurant_id: String,
+        menu_item_id: String,
+        quantity: i32,
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        Self::place_order(&txn, customer_id, restaurant_id, menu_item_id, quantity, Some(&actor_id)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        state.cache.invalidate(&[TAG_CUSTOMER]).await;
+        Ok(ApiResponse::success(ORDER_CREATED.to_string()))
+    }
+
+    // Record the order and charge the customer for it, on one connection so both go through or
+    // neither does. Callers invalidate TAG_CUSTOMER once it is committed.
+    pub(crate) async fn place_order<C: ConnectionTrait>(
+        conn: &C,
+        customer_id: String,
+        restaurant_id: String,
+        menu_item_id: String,
+        quantity: i32,
+        actor_id: Option<&str>,
+    ) -> Result<Model, AppError> {
+        let item = menu_item::Entity::find_by_id(menu_item_id.clone())
+            .one(conn)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching menu item: {}", err)))?
+            .filter(|item| item.deleted_at.is_none())
+            .ok_or_else(|| AppError::NotFound("Menu item is no longer on the menu".to_string()))?;
+        let amount = validation::parse_price(&item.price)? * Decimal::from(quantity);
+
+        let order = Self::insert_order(conn, customer_id, restaurant_id, menu_item_id, quantity, actor_id).await?;
+        CustomerHandler::adjust_balance(conn, &order.customer_id, -amount, actor_id).await?;
+        Ok(order)
+    }
+
+    // Insert the order row on any connection; place_order charges for it
+    async fn insert_order<C: ConnectionTrait>(
+        conn: &C,
+        customer_id: String,
+        restaurant_id: String,
+        menu_item_id: String,
+        quantity: i32,
+        actor_id: Option<&str>,
+    ) -> Result<Model, AppError> {
+        Validator::new()
+            .field("customer_id", &customer_id, &[validation::required])
+            .field("restaurant_id", &restaurant_id, &[validation::required])
+            .field("menu_item_id", &menu_item_id, &[validation::required])
+            .at_least("quantity", quantity, 1)
+            .finish()?;
+
         let order_restaurant_id = Uuid::new_v4().to_string();
 
-        let jakarta_time = Utc::now()
-            .with_timezone(&chrono::FixedOffset::east_opt(7 * 3600).unwrap())
-            .naive_local();
+        let jakarta_time = clock::jakarta_now();
 
         let new_order = order_restaurant::ActiveModel {
             order_restaurant_id: Set(order_restaurant_id),
@@ -83,12 +167,14 @@ impl OrderRestaurantHandler {
             quantity: Set(quantity),
             timestamp: Set(jakarta_time), // Auto-set to current time
             status: Set("Pending".to_string()), // Initial status
-            ..Default::default()
         };
 
-        match order_restaurant::Entity::insert(new_order).exec(&state.db).await {
-            Ok(_) => Ok(ApiResponse::success("Restaurant order created successfully".to_string())),
-            Err(err) => Err(format!("Error creating restaurant order: {}", err)),
+        match order_restaurant::Entity::insert(new_order).exec_with_returning(conn).await {
+            Ok(created) => {
+                AuditEventHandler::record(conn, actor_id, ACTION_CREATE, "order_restaurant", &created.order_restaurant_id, None, AuditEventHandler::snapshot(&created)).await?;
+                Ok(created)
+            }
+            Err(err) => Err(AppError::Database(format!("Error creating restaurant order: {}", err))),
         }
     }
 
@@ -97,46 +183,46 @@ impl OrderRestaurantHandler {
         state: &AppState,
         order_restaurant_id: String,
         status: String,
-    ) -> Result<ApiResponse<String>, String> {
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new().one_of("status", &status, &ORDER_RESTAURANT_STATUSES).finish()?;
 
-        if !["Pending", "Cooking", "Ready to Serve", "Complete"].contains(&status.as_str()) {
-          return Err("Invalid status provided.".to_string());
-        }
         let order_record = match order_restaurant::Entity::find_by_id(order_restaurant_id)
             .one(&state.db)
             .await
         {
             Ok(Some(order)) => order,
-            Ok(None) => return Err("Restaurant order not found".to_string()),
-            Err(err) => return Err(format!("Error fetching restaurant order: {}", err)),
+            Ok(None) => return Err(AppError::NotFound("Restaurant order not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching restaurant order: {}", err))),
         };
 
+        let before = AuditEventHandler::snapshot(&order_record);
         let mut active_order: order_restaurant::ActiveModel = order_record.into();
         active_order.status = Set(status);
 
-        match active_order.update(&state.db).await {
-            Ok(_) => Ok(ApiResponse::success("Order status updated successfully".to_string())),
-            Err(err) => Err(format!("Error updating order status: {}", err)),
-        }
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let updated = active_order.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error updating order status: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_UPDATE, "order_restaurant", &updated.order_restaurant_id, before, AuditEventHandler::snapshot(&updated)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        Ok(ApiResponse::success("Order status updated successfully".to_string()))
     }
 
     // Delete a restaurant order
     pub async fn delete_order_restaurant_data( // Renamed Function
         state: &AppState,
         order_restaurant_id: String,
-    ) -> Result<ApiResponse<String>, String> {
-        match order_restaurant::Entity::delete_by_id(order_restaurant_id)
-            .exec(&state.db)
-            .await
-        {
-            Ok(delete_result) => {
-                if delete_result.rows_affected > 0 {
-                    Ok(ApiResponse::success("Restaurant order deleted successfully".to_string()))
-                } else {
-                    Err("Restaurant order not found".to_string())
-                }
-            }
-            Err(err) => Err(format!("Error deleting restaurant order: {}", err)),
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let existing = order_restaurant::Entity::find_by_id(order_restaurant_id.clone()).one(&txn).await.ok().flatten();
+        let delete_result = order_restaurant::Entity::delete_by_id(order_restaurant_id.clone()).exec(&txn).await
+            .map_err(|err| AppError::Database(format!("Error deleting restaurant order: {}", err)))?;
+        if delete_result.rows_affected == 0 {
+            return Err(AppError::NotFound("Restaurant order not found".to_string()));
         }
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_DELETE, "order_restaurant", &order_restaurant_id, existing.as_ref().and_then(AuditEventHandler::snapshot), None).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        Ok(ApiResponse::success("Restaurant order deleted successfully".to_string()))
     }
 }
\ No newline at end of file
@@ -1,35 +1,32 @@
 use chrono::{DateTime, Utc};
-use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder};
-use entity::order_souvenir::{self, ActiveModel, Model};
+use sea_orm::{ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, TransactionTrait};
+use entity::order_souvenir::{self, Model};
 use uuid::Uuid;
-use crate::{cache_delete, cache_get, cache_set, ApiResponse, AppState};
+use entity::audit_event::{ACTION_CREATE, ACTION_DELETE};
+use rust_decimal::Decimal;
+use crate::clock;
+use crate::controllers::audit_event_handler::AuditEventHandler;
+use crate::controllers::customer_handler::CustomerHandler;
+use crate::controllers::souvenir_handler::SouvenirHandler;
+use crate::cache::{CachedView, TAG_CUSTOMER, TAG_ORDER_SOUVENIR, TAG_SOUVENIR};
+use crate::repository::CachedRepository;
+use crate::error::AppError;
+use crate::idempotency::Idempotency;
+use crate::validation::{self, Validator};
+use crate::{ApiResponse, AppState};
+
+pub const SAVE_ORDER_SOUVENIR: &str = "save_order_souvenir_data";
+pub const ORDER_SOUVENIR_CREATED: &str = "OrderSouvenir created successfully";
 
 pub struct OrderSouvenirHandler;
 
 impl OrderSouvenirHandler {
     // View all order_souvenirs
-    pub async fn view_order_souvenirs(state: &AppState) -> Result<ApiResponse<Vec<Model>>, String> {
-        let cache_key = "view_order_souvenirs_cache";
-
-        // Check Redis cache first
-        if let Some(cached_order_souvenirs) = cache_get::<Vec<Model>>(&state.redis_pool, cache_key).await {
-            println!("Cache hit: Returning order_souvenirs from Redis");
-            return Ok(ApiResponse::success(cached_order_souvenirs));
-        }
-
-        // Cache miss: Query the database
-        println!("Cache miss: Querying database");
-        match order_souvenir::Entity::find()
-            .order_by_asc(order_souvenir::Column::Timestamp)
-            .all(&state.db)
-            .await
-        {
-            Ok(order_souvenirs) => {
-                // Cache the result for 60 seconds
-                cache_set(&state.redis_pool, cache_key, &order_souvenirs, 60).await;
-                Ok(ApiResponse::success(order_souvenirs))
-            }
-            Err(err) => Ok(ApiResponse::error(format!("Error fetching order_souvenirs: {}", err))),
+    pub async fn view_order_souvenirs(state: &AppState) -> Result<ApiResponse<Vec<Model>>, AppError> {
+        let query = order_souvenir::Entity::find().order_by_asc(order_souvenir::Column::Timestamp);
+        match CachedRepository::<order_souvenir::Entity>::new(state).find_all(CachedView::OrderSouvenirs, query).await {
+            Ok(order_souvenirs) => Ok(ApiResponse::success(order_souvenirs)),
+            Err(err) => Err(AppError::Database(format!("Error fetching order_souvenirs: {}", err))),
         }
     }
 
@@ -37,7 +34,7 @@ impl OrderSouvenirHandler {
         state: &AppState,
         customer_id: String,
         store_id: String,
-    ) -> Result<ApiResponse<Vec<Model>>, String> {
+    ) -> Result<ApiResponse<Vec<Model>>, AppError> {
         match order_souvenir::Entity::find()
             .filter(order_souvenir::Column::CustomerId.eq(customer_id))
             .filter(order_souvenir::Column::StoreId.eq(store_id))
@@ -46,7 +43,7 @@ impl OrderSouvenirHandler {
             .await
         {
             Ok(orders) => Ok(ApiResponse::success(orders)),
-            Err(err) => Err(format!("Error fetching souvenir orders for customer: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error fetching souvenir orders for customer: {}", err))),
         }
     }
 
@@ -55,7 +52,7 @@ impl OrderSouvenirHandler {
         state: &AppState,
         start_time: DateTime<Utc>,
         end_time: DateTime<Utc>,
-    ) -> Result<ApiResponse<Vec<Model>>, String> {
+    ) -> Result<ApiResponse<Vec<Model>>, AppError> {
         match order_souvenir::Entity::find()
             .filter(
                 Condition::all()
@@ -66,7 +63,7 @@ impl OrderSouvenirHandler {
             .await
         {
             Ok(orders) => Ok(ApiResponse::success(orders)),
-            Err(err) => Err(format!("Error fetching souvenir orders in range: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error fetching souvenir orders in range: {}", err))),
         }
     }
 
@@ -74,27 +71,91 @@ impl OrderSouvenirHandler {
     pub async fn get_order_souvenir_details(
         state: &AppState,
         order_souvenir_id: String,
-    ) -> Result<ApiResponse<Model>, String> {
+    ) -> Result<ApiResponse<Model>, AppError> {
         match order_souvenir::Entity::find_by_id(order_souvenir_id.clone()).one(&state.db).await {
             Ok(Some(order_souvenir_details)) => Ok(ApiResponse::success(order_souvenir_details)),
-            Ok(None) => Err("OrderSouvenir not found".to_string()),
-            Err(err) => Err(format!("Database error fetching order_souvenir details: {}", err)),
+            Ok(None) => Err(AppError::NotFound("OrderSouvenir not found".to_string())),
+            Err(err) => Err(AppError::Database(format!("Database error fetching order_souvenir details: {}", err))),
         }
     }
 
-    // Save order_souvenir data (create new order_souvenir)
+    // Save order_souvenir data (create new order_souvenir), taking the stock and charging the
+    // customer. Retrying with the same idempotency key records and charges the order only once.
     pub async fn save_order_souvenir_data(
         state: &AppState,
         customer_id: String,
         store_id: String,
         souvenir_id: String,
         quantity: i32,
-    ) -> Result<ApiResponse<String>, String> {
+        actor_id: String,
+        idempotency_key: Option<String>,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let request = Self::idempotency_request(&customer_id, &store_id, &souvenir_id, quantity);
+        let save = Self::create_order_souvenir(state, customer_id, store_id, souvenir_id, quantity, actor_id);
+        Idempotency::run(&state.db, idempotency_key, SAVE_ORDER_SOUVENIR, request, save).await
+    }
+
+    // What a repeated idempotency key must match, shared with offline replays of the order
+    pub(crate) fn idempotency_request(customer_id: &str, store_id: &str, souvenir_id: &str, quantity: i32) -> serde_json::Value {
+        serde_json::json!({
+            "customer_id": customer_id,
+            "store_id": store_id,
+            "souvenir_id": souvenir_id,
+            "quantity": quantity,
+        })
+    }
+
+    async fn create_order_souvenir(
+        state: &AppState,
+        customer_id: String,
+        store_id: String,
+        souvenir_id: String,
+        quantity: i32,
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        Self::place_order(&txn, customer_id, store_id, souvenir_id, quantity, Some(&actor_id)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        state.cache.invalidate(&[TAG_CUSTOMER, TAG_SOUVENIR, TAG_ORDER_SOUVENIR]).await;
+        Ok(ApiResponse::success(ORDER_SOUVENIR_CREATED.to_string()))
+    }
+
+    // Record the order, take it from stock and charge the customer for it, all on one connection
+    // so the three go through or fail together. Short stock and a short balance are conflicts.
+    // Callers invalidate the customer, souvenir and order views once it is committed.
+    pub(crate) async fn place_order<C: ConnectionTrait>(
+        conn: &C,
+        customer_id: String,
+        store_id: String,
+        souvenir_id: String,
+        quantity: i32,
+        actor_id: Option<&str>,
+    ) -> Result<Model, AppError> {
+        let order = Self::insert_order(conn, customer_id, store_id, souvenir_id, quantity, actor_id).await?;
+        let item = SouvenirHandler::take_stock(conn, &order.souvenir_id, quantity, actor_id).await?;
+        CustomerHandler::adjust_balance(conn, &order.customer_id, -(item.price * Decimal::from(quantity)), actor_id).await?;
+        Ok(order)
+    }
+
+    // Insert the order row on any connection; place_order takes the stock and charges for it
+    async fn insert_order<C: ConnectionTrait>(
+        conn: &C,
+        customer_id: String,
+        store_id: String,
+        souvenir_id: String,
+        quantity: i32,
+        actor_id: Option<&str>,
+    ) -> Result<Model, AppError> {
+        Validator::new()
+            .field("customer_id", &customer_id, &[validation::required])
+            .field("store_id", &store_id, &[validation::required])
+            .field("souvenir_id", &souvenir_id, &[validation::required])
+            .at_least("quantity", quantity, 1)
+            .finish()?;
+
         let order_souvenir_id = Uuid::new_v4().to_string();
 
-        let jakarta_time = Utc::now()
-            .with_timezone(&chrono::FixedOffset::east_opt(7 * 3600).unwrap())
-            .naive_local();
+        let jakarta_time = clock::jakarta_now();
 
         let new_order_souvenir = order_souvenir::ActiveModel {
             order_souvenir_id: sea_orm::ActiveValue::Set(order_souvenir_id),
@@ -103,30 +164,168 @@ impl OrderSouvenirHandler {
             souvenir_id: sea_orm::ActiveValue::Set(souvenir_id),
             quantity: sea_orm::ActiveValue::Set(quantity),
             timestamp: sea_orm::ActiveValue::Set(jakarta_time),
-            ..Default::default()
         };
 
-        match order_souvenir::Entity::insert(new_order_souvenir).exec(&state.db).await {
-            Ok(_) => {
-                cache_delete(&state.redis_pool, "view_order_souvenirs_cache").await;
-                Ok(ApiResponse::success("OrderSouvenir created successfully".to_string()))
+        match order_souvenir::Entity::insert(new_order_souvenir).exec_with_returning(conn).await {
+            Ok(created) => {
+                AuditEventHandler::record(conn, actor_id, ACTION_CREATE, "order_souvenir", &created.order_souvenir_id, None, AuditEventHandler::snapshot(&created)).await?;
+                Ok(created)
             }
-            Err(err) => Err(format!("Error creating order_souvenir: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error creating order_souvenir: {}", err))),
         }
     }
 
     // Delete order_souvenir data
-    pub async fn delete_order_souvenir_data(state: &AppState, order_souvenir_id: String) -> Result<String, String> {
-        match order_souvenir::Entity::delete_by_id(order_souvenir_id).exec(&state.db).await {
-            Ok(delete_result) => {
-                if delete_result.rows_affected > 0 {
-                    cache_delete(&state.redis_pool, "view_order_souvenirs_cache").await;
-                    Ok("OrderSouvenir deleted successfully".to_string())
-                } else {
-                    Err("OrderSouvenir not found".to_string())
-                }
-            }
-            Err(err) => Err(format!("Error deleting order_souvenir: {}", err)),
+    pub async fn delete_order_souvenir_data(state: &AppState, order_souvenir_id: String, actor_id: String) -> Result<ApiResponse<String>, AppError> {
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let existing = order_souvenir::Entity::find_by_id(order_souvenir_id.clone()).one(&txn).await.ok().flatten();
+        let delete_result = order_souvenir::Entity::delete_by_id(order_souvenir_id.clone()).exec(&txn).await
+            .map_err(|err| AppError::Database(format!("Error deleting order_souvenir: {}", err)))?;
+        if delete_result.rows_affected == 0 {
+            return Err(AppError::NotFound("OrderSouvenir not found".to_string()));
+        }
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_DELETE, "order_souvenir", &order_souvenir_id, existing.as_ref().and_then(AuditEventHandler::snapshot), None).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        CachedRepository::<order_souvenir::Entity>::new(state).invalidate().await;
+        Ok(ApiResponse::success("OrderSouvenir deleted successfully".to_string()))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::test_support::{self, data, error, ACTOR};
+    use entity::audit_event;
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn checkout_records_the_order_for_the_customer_and_audits_it() {
+        let state = test_support::test_state().await;
+        let store = test_support::seed_store(&state).await;
+        let souvenir = test_support::seed_souvenir(&state, &store.store_id, Decimal::new(1500050, 2), 10).await;
+        let customer = test_support::seed_customer(&state, "100000").await;
+        let cashier = test_support::seed_staff(&state, "Sales Associate").await;
+        assert!(data(OrderSouvenirHandler::view_order_souvenirs(&state).await).is_empty());
+
+        data(OrderSouvenirHandler::save_order_souvenir_data(
+            &state,
+            customer.customer_id.clone(),
+            store.store_id.clone(),
+            souvenir.souvenir_id.clone(),
+            2,
+            cashier.staff_id.clone(),
+            None,
+        )
+        .await);
+
+        let orders = data(OrderSouvenirHandler::view_order_souvenirs_by_customer(&state, customer.customer_id.clone(), store.store_id.clone()).await);
+        assert_eq!(orders.len(), 1);
+        assert_eq!((orders[0].souvenir_id.as_str(), orders[0].quantity), (souvenir.souvenir_id.as_str(), 2));
+        // The cached list saw the insert too
+        assert_eq!(data(OrderSouvenirHandler::view_order_souvenirs(&state).await).len(), 1);
+
+        // Charged for and taken from stock in the same go
+        let charged = data(CustomerHandler::get_customer_details(&state, customer.customer_id.clone()).await);
+        assert_eq!(charged.virtual_balance.parse::<Decimal>().unwrap(), Decimal::new(6999900, 2));
+        assert_eq!(data(SouvenirHandler::get_souvenir_details(&state, souvenir.souvenir_id.clone()).await).stock, 8);
+
+        let audit = audit_event::Entity::find()
+            .filter(audit_event::Column::EntityType.eq("order_souvenir"))
+            .all(&state.db)
+            .await
+            .unwrap();
+        assert_eq!(audit.len(), 1);
+        assert_eq!((audit[0].action.as_str(), audit[0].actor_id.as_deref()), (ACTION_CREATE, Some(cashier.staff_id.as_str())));
+    }
+
+    #[tokio::test]
+    async fn a_retried_checkout_is_charged_once() {
+        let state = test_support::test_state().await;
+        let store = test_support::seed_store(&state).await;
+        let souvenir = test_support::seed_souvenir(&state, &store.store_id, Decimal::from(25000), 10).await;
+        let customer = test_support::seed_customer(&state, "100000").await;
+
+        for _ in 0..2 {
+            data(OrderSouvenirHandler::save_order_souvenir_data(
+                &state,
+                customer.customer_id.clone(),
+                store.store_id.clone(),
+                souvenir.souvenir_id.clone(),
+                1,
+                ACTOR.to_string(),
+                Some("checkout-1".to_string()),
+            )
+            .await);
         }
+
+        let charged = data(CustomerHandler::get_customer_details(&state, customer.customer_id.clone()).await);
+        assert_eq!(charged.virtual_balance, "75000");
+        assert_eq!(data(SouvenirHandler::get_souvenir_details(&state, souvenir.souvenir_id).await).stock, 9);
+        assert_eq!(data(OrderSouvenirHandler::view_order_souvenirs(&state).await).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn checkout_short_of_stock_or_balance_writes_nothing() {
+        let state = test_support::test_state().await;
+        let store = test_support::seed_store(&state).await;
+        let souvenir = test_support::seed_souvenir(&state, &store.store_id, Decimal::from(25000), 3).await;
+        let customer = test_support::seed_customer(&state, "60000").await;
+        let order = |quantity| {
+            OrderSouvenirHandler::save_order_souvenir_data(
+                &state,
+                customer.customer_id.clone(),
+                store.store_id.clone(),
+                souvenir.souvenir_id.clone(),
+                quantity,
+                ACTOR.to_string(),
+                None,
+            )
+        };
+
+        assert_eq!(error(order(4).await).code(), ErrorCode::Conflict);
+        // The last one is in stock, but the balance no longer covers it
+        data(order(2).await);
+        let charged = data(CustomerHandler::get_customer_details(&state, customer.customer_id.clone()).await);
+        assert_eq!(charged.virtual_balance, "10000");
+        assert_eq!(error(order(1).await).code(), ErrorCode::Conflict);
+
+        assert_eq!(data(OrderSouvenirHandler::view_order_souvenirs(&state).await).len(), 1);
+        assert_eq!(data(SouvenirHandler::get_souvenir_details(&state, souvenir.souvenir_id.clone()).await).stock, 1);
+    }
+
+    #[tokio::test]
+    async fn a_deleted_order_leaves_the_cached_list() {
+        let state = test_support::test_state().await;
+        let store = test_support::seed_store(&state).await;
+        let souvenir = test_support::seed_souvenir(&state, &store.store_id, Decimal::from(15000), 10).await;
+        let customer = test_support::seed_customer(&state, "100000").await;
+        data(OrderSouvenirHandler::save_order_souvenir_data(
+            &state,
+            customer.customer_id,
+            store.store_id,
+            souvenir.souvenir_id,
+            1,
+            ACTOR.to_string(),
+            None,
+        )
+        .await);
+        let orders = data(OrderSouvenirHandler::view_order_souvenirs(&state).await);
+        assert_eq!(orders.len(), 1);
+
+        data(OrderSouvenirHandler::delete_order_souvenir_data(&state, orders[0].order_souvenir_id.clone(), ACTOR.to_string()).await);
+
+        assert!(data(OrderSouvenirHandler::view_order_souvenirs(&state).await).is_empty());
+    }
+
+    #[tokio::test]
+    async fn checkout_rejects_incomplete_orders_without_writing() {
+        let state = test_support::test_state().await;
+        let store = test_support::seed_store(&state).await;
+        let customer = test_support::seed_customer(&state, "100000").await;
+
+        let err = error(OrderSouvenirHandler::save_order_souvenir_data(&state, customer.customer_id, store.store_id, " ".to_string(), 0, ACTOR.to_string(), None).await);
+        let fields: Vec<&str> = err.fields().iter().map(|field| field.field.as_str()).collect();
+        assert_eq!(fields, ["souvenir_id", "quantity"]);
+        assert!(order_souvenir::Entity::find().all(&state.db).await.unwrap().is_empty());
     }
-}
\ No newline at end of file
+}
@@ -1,7 +1,13 @@
-use chrono::{FixedOffset, Utc};
-use sea_orm::{prelude::Time, ActiveModelTrait, EntityTrait, QueryOrder, QuerySelect};
-use entity::restaurant::{self, ActiveModel, Model};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, TransactionTrait};
+use entity::restaurant::{self, Model};
 use uuid::Uuid;
+use entity::audit_event::{ACTION_CREATE, ACTION_DELETE, ACTION_RESTORE, ACTION_UPDATE};
+use crate::clock;
+use crate::controllers::audit_event_handler::AuditEventHandler;
+use crate::controllers::dependency_handler::{DependencyHandler, DependencyReport};
+use crate::cache::TAG_CUSTOMER;
+use crate::error::AppError;
+use crate::validation::{self, Validator};
 use crate::{ApiResponse, AppState};
 
 pub struct RestaurantHandler;
@@ -9,8 +15,7 @@ pub struct RestaurantHandler;
 impl RestaurantHandler {
 
     fn get_restaurant_current_status(restaurant: &Model) -> String {
-        let jakarta_offset = FixedOffset::east_opt(7 * 3600).unwrap(); // Define Jakarta offset
-        let current_time_jakarta = Utc::now().with_timezone(&jakarta_offset).naive_local().time(); // Use FixedOffset
+        let current_time_jakarta = clock::jakarta_now().time();
         let opening_time = restaurant.opening_time;
         let closing_time = restaurant.closing_time;
 
@@ -26,8 +31,8 @@ impl RestaurantHandler {
     }
 
     // View restaurants
-    pub async fn view_restaurants(state: &AppState) -> Result<ApiResponse<Vec<Model>>, String> {
-        match restaurant::Entity::find().order_by_asc(restaurant::Column::Name).all(&state.db).await {
+    pub async fn view_restaurants(state: &AppState) -> Result<ApiResponse<Vec<Model>>, AppError> {
+        match restaurant::Entity::find().filter(restaurant::Column::DeletedAt.is_null()).order_by_asc(restaurant::Column::Name).all(&state.db).await {
             Ok(restaurants) => {
                 // Update status based on current time for each restaurant
                 let updated_restaurants: Vec<Model> = restaurants.into_iter().map(|mut restaurant| {
@@ -36,7 +41,7 @@ impl RestaurantHandler {
                 }).collect();
                 Ok(ApiResponse::success(updated_restaurants))
             }
-            Err(err) => Err(format!("Error fetching restaurants: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error fetching restaurants: {}", err))),
         }
     }
 
@@ -44,7 +49,7 @@ impl RestaurantHandler {
     pub async fn get_restaurant_details(
         state: &AppState,
         restaurant_id: String,
-    ) -> Result<ApiResponse<Model>, String> {
+    ) -> Result<ApiResponse<Model>, AppError> {
         match restaurant::Entity::find_by_id(restaurant_id.clone()).one(&state.db).await {
              Ok(Some(mut restaurant_details)) => {
                 // Update status based on current time
@@ -52,10 +57,10 @@ impl RestaurantHandler {
                 Ok(ApiResponse::success(restaurant_details))
             }
             Ok(None) => {
-                Err("Restaurant not found".to_string())
+                Err(AppError::NotFound("Restaurant not found".to_string()))
             }
             Err(err) => {
-                Err(format!("Database error fetching restaurant details: {}", err))
+                Err(AppError::Database(format!("Database error fetching restaurant details: {}", err)))
             }
         }
     }
@@ -70,13 +75,20 @@ impl RestaurantHandler {
         cuisine_type: String,
         location: Option<String>,
         status: String,
-    ) -> Result<ApiResponse<String>, String> {
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new()
+            .field("name", &name, &[validation::name])
+            .opening_hours(Some(&opening_time), Some(&closing_time))
+            .field("cuisine_type", &cuisine_type, &[validation::required])
+            .finish()?;
+
         // Generate a UUID for the restaurant_id
         let restaurant_id = Uuid::new_v4().to_string();
 
-        // Parse opening and closing times from String to Time
-        let parsed_opening_time = Time::parse_from_str(&opening_time, "%H:%M:%S").map_err(|e| format!("Invalid opening time format: {}", e))?;
-        let parsed_closing_time = Time::parse_from_str(&closing_time, "%H:%M:%S").map_err(|e| format!("Invalid closing time format: {}", e))?;
+        // Parse opening and closing times from String to Time (checked above)
+        let parsed_opening_time = validation::parse_time(&opening_time).unwrap_or_default();
+        let parsed_closing_time = validation::parse_time(&closing_time).unwrap_or_default();
 
 
         let new_restaurant = restaurant::ActiveModel {
@@ -91,10 +103,12 @@ impl RestaurantHandler {
             ..Default::default()
         };
 
-        match restaurant::Entity::insert(new_restaurant).exec(&state.db).await {
-            Ok(_) => Ok(ApiResponse::success("Restaurant created successfully".to_string())),
-            Err(err) => Err(format!("Error creating restaurant: {}", err)),
-        }
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let created = restaurant::Entity::insert(new_restaurant).exec_with_returning(&txn).await
+            .map_err(|err| AppError::Database(format!("Error creating restaurant: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_CREATE, "restaurant", &created.restaurant_id, None, AuditEventHandler::snapshot(&created)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        Ok(ApiResponse::success("Restaurant created successfully".to_string()))
     }
 
     // Update restaurant data
@@ -108,13 +122,24 @@ impl RestaurantHandler {
         cuisine_type: Option<String>,
         location: Option<Option<String>>, // Option<Option<String>> for location as well
         status: Option<String>,
-    ) -> Result<ApiResponse<String>, String> {
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new()
+            .optional("name", name.as_deref(), &[validation::name])
+            .opening_hours(opening_time.as_deref(), closing_time.as_deref())
+            .optional("cuisine_type", cuisine_type.as_deref(), &[validation::required])
+            .finish()?;
+
         let restaurant_record = match restaurant::Entity::find_by_id(restaurant_id).one(&state.db).await {
             Ok(Some(restaurant)) => restaurant,
-            Ok(None) => return Err("Restaurant not found".to_string()),
-            Err(err) => return Err(format!("Error fetching restaurant: {}", err)),
+            Ok(None) => return Err(AppError::NotFound("Restaurant not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching restaurant: {}", err))),
         };
+        if restaurant_record.deleted_at.is_some() {
+            return Err(AppError::Conflict("Restaurant is archived; restore it before editing".to_string()));
+        }
 
+        let before = AuditEventHandler::snapshot(&restaurant_record);
         let mut active_restaurant: restaurant::ActiveModel = restaurant_record.into();
 
         if let Some(new_name) = name {
@@ -124,11 +149,11 @@ impl RestaurantHandler {
             active_restaurant.photo = sea_orm::ActiveValue::Set(new_photo);
         }
         if let Some(new_opening_time_str) = opening_time {
-            let parsed_opening_time = Time::parse_from_str(&new_opening_time_str, "%H:%M:%S").map_err(|e| format!("Invalid opening time format: {}", e))?;
+            let parsed_opening_time = validation::parse_time(&new_opening_time_str).unwrap_or_default();
             active_restaurant.opening_time = sea_orm::ActiveValue::Set(parsed_opening_time);
         }
         if let Some(new_closing_time_str) = closing_time {
-            let parsed_closing_time = Time::parse_from_str(&new_closing_time_str, "%H:%M:%S").map_err(|e| format!("Invalid closing time format: {}", e))?;
+            let parsed_closing_time = validation::parse_time(&new_closing_time_str).unwrap_or_default();
             active_restaurant.closing_time = sea_orm::ActiveValue::Set(parsed_closing_time);
         }
         if let Some(new_cuisine_type) = cuisine_type {
@@ -142,23 +167,79 @@ impl RestaurantHandler {
         }
 
 
-        match active_restaurant.update(&state.db).await {
-            Ok(_) => Ok(ApiResponse::success("Restaurant updated successfully".to_string())),
-            Err(err) => Err(format!("Error updating restaurant: {}", err)),
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let updated = active_restaurant.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error updating restaurant: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_UPDATE, "restaurant", &updated.restaurant_id, before, AuditEventHandler::snapshot(&updated)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        Ok(ApiResponse::success("Restaurant updated successfully".to_string()))
+    }
+
+    // Archive restaurant data (soft delete): the row stays so history and reports still resolve it
+    pub async fn delete_restaurant_data(state: &AppState, restaurant_id: String, cascade: bool, actor_id: String) -> Result<ApiResponse<DependencyReport>, AppError> {
+        let existing = match restaurant::Entity::find_by_id(restaurant_id.clone()).one(&state.db).await {
+            Ok(Some(record)) if record.deleted_at.is_none() => record,
+            Ok(_) => return Err(AppError::NotFound("Restaurant not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching restaurant: {}", err))),
+        };
+
+        // Dependents are checked and cascaded in the same transaction as the archive itself
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let report = DependencyHandler::check_restaurant(&txn, &restaurant_id).await?;
+        if let Some(blocked) = DependencyHandler::blocked(&report, cascade) {
+            return Ok(blocked);
+        }
+        let now = clock::jakarta_now();
+        if !report.is_clear() {
+            DependencyHandler::cascade_restaurant(&txn, &restaurant_id, now, &actor_id).await?;
+        }
+
+        let before = AuditEventHandler::snapshot(&existing);
+        let mut active_restaurant: restaurant::ActiveModel = existing.into();
+        active_restaurant.deleted_at = sea_orm::ActiveValue::Set(Some(now));
+        let archived = active_restaurant.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error deleting restaurant: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_DELETE, "restaurant", &restaurant_id, before, AuditEventHandler::snapshot(&archived)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+
+        if !report.is_clear() {
+            state.cache.invalidate(&[TAG_CUSTOMER]).await;
         }
+        Ok(ApiResponse::Success { data: report, message: Some("Restaurant deleted successfully".to_string()) })
     }
 
-    // Delete restaurant data
-    pub async fn delete_restaurant_data(state: &AppState, restaurant_id: String) -> Result<String, String> {
-        match restaurant::Entity::delete_by_id(restaurant_id).exec(&state.db).await {
-            Ok(delete_result) => {
-                if delete_result.rows_affected > 0 {
-                    Ok("Restaurant deleted successfully".to_string())
-                } else {
-                    Err("Restaurant not found".to_string())
-                }
-            }
-            Err(err) => Err(format!("Error deleting restaurant: {}", err)),
+    // Bring an archived restaurant back into the lists
+    pub async fn restore_restaurant_data(state: &AppState, restaurant_id: String, actor_id: String) -> Result<ApiResponse<String>, AppError> {
+        let existing = match restaurant::Entity::find_by_id(restaurant_id.clone()).one(&state.db).await {
+            Ok(Some(record)) => record,
+            Ok(None) => return Err(AppError::NotFound("Restaurant not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching restaurant: {}", err))),
+        };
+        if existing.deleted_at.is_none() {
+            return Err(AppError::Conflict("Restaurant is not archived".to_string()));
+        }
+
+        let before = AuditEventHandler::snapshot(&existing);
+        let mut active_restaurant: restaurant::ActiveModel = existing.into();
+        active_restaurant.deleted_at = sea_orm::ActiveValue::Set(None);
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let restored = active_restaurant.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error restoring restaurant: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_RESTORE, "restaurant", &restaurant_id, before, AuditEventHandler::snapshot(&restored)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        Ok(ApiResponse::success("Restaurant restored successfully".to_string()))
+    }
+
+    // View archived restaurants, most recently archived first
+    pub async fn view_archived_restaurants(state: &AppState) -> Result<ApiResponse<Vec<Model>>, AppError> {
+        match restaurant::Entity::find()
+            .filter(restaurant::Column::DeletedAt.is_not_null())
+            .order_by_desc(restaurant::Column::DeletedAt)
+            .all(&state.db)
+            .await
+        {
+            Ok(archived) => Ok(ApiResponse::success(archived)),
+            Err(err) => Err(AppError::Database(format!("Error fetching archived restaurants: {}", err))),
         }
     }
 }
\ No newline at end of file
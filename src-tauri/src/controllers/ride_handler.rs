@@ -1,34 +1,29 @@
-use sea_orm::{ActiveModelTrait, EntityTrait, QueryOrder};
-use entity::ride::{self, ActiveModel, Model};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, TransactionTrait};
+use entity::ride::{self, Model};
 use uuid::Uuid;
-use crate::{cache_delete, cache_get, cache_set, ApiResponse, AppState};
+use entity::audit_event::{ACTION_CREATE, ACTION_DELETE, ACTION_RESTORE, ACTION_UPDATE};
+use crate::clock;
+use crate::controllers::audit_event_handler::AuditEventHandler;
+use crate::controllers::dependency_handler::{DependencyHandler, DependencyReport};
+use crate::cache::CachedView;
+use crate::repository::CachedRepository;
+use crate::error::AppError;
+use crate::validation::{self, Validator};
+use crate::{ApiResponse, AppState};
+
+pub const RIDE_STATUSES: [&str; 3] = ["Operational", "Pending", "Closed"];
 
 pub struct RideHandler;
 
 impl RideHandler {
     // View all rides
-    pub async fn view_rides(state: &AppState) -> Result<ApiResponse<Vec<Model>>, String> {
-        let cache_key = "view_rides_cache";
-
-        // Check Redis cache first
-        if let Some(cached_rides) = cache_get::<Vec<Model>>(&state.redis_pool, cache_key).await {
-            println!("Cache hit: Returning rides from Redis");
-            return Ok(ApiResponse::success(cached_rides));
-        }
-
-        // Cache miss: Query the database
-        println!("Cache miss: Querying database");
-        match ride::Entity::find()
-            .order_by_asc(ride::Column::Name)
-            .all(&state.db)
-            .await
-        {
-            Ok(rides) => {
-                // Cache the result for 60 seconds
-                cache_set(&state.redis_pool, cache_key, &rides, 60).await;
-                Ok(ApiResponse::success(rides))
-            }
-            Err(err) => Ok(ApiResponse::error(format!("Error fetching rides: {}", err))),
+    pub async fn view_rides(state: &AppState) -> Result<ApiResponse<Vec<Model>>, AppError> {
+        let query = ride::Entity::find()
+            .filter(ride::Column::DeletedAt.is_null())
+            .order_by_asc(ride::Column::Name);
+        match CachedRepository::<ride::Entity>::new(state).find_all(CachedView::Rides, query).await {
+            Ok(rides) => Ok(ApiResponse::success(rides)),
+            Err(err) => Err(AppError::Database(format!("Error fetching rides: {}", err))),
         }
     }
 
@@ -36,15 +31,16 @@ impl RideHandler {
     pub async fn get_ride_details(
         state: &AppState,
         ride_id: String,
-    ) -> Result<ApiResponse<Model>, String> {
+    ) -> Result<ApiResponse<Model>, AppError> {
         match ride::Entity::find_by_id(ride_id.clone()).one(&state.db).await {
             Ok(Some(ride_details)) => Ok(ApiResponse::success(ride_details)),
-            Ok(None) => Err("Ride not found".to_string()),
-            Err(err) => Err(format!("Database error fetching ride details: {}", err)),
+            Ok(None) => Err(AppError::NotFound("Ride not found".to_string())),
+            Err(err) => Err(AppError::Database(format!("Database error fetching ride details: {}", err))),
         }
     }
 
     // Save ride data (create new ride)
+    #[allow(clippy::too_many_arguments)]
     pub async fn save_ride_data(
         state: &AppState,
         status: String,
@@ -53,7 +49,16 @@ impl RideHandler {
         location: String,
         staff_id: String,
         photo: Option<String>,
-    ) -> Result<ApiResponse<String>, String> {
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new()
+            .one_of("status", &status, &RIDE_STATUSES)
+            .field("name", &name, &[validation::name])
+            .field("price", &price, &[validation::positive_money])
+            .field("location", &location, &[validation::required])
+            .field("staff_id", &staff_id, &[validation::required])
+            .finish()?;
+
         let ride_id = Uuid::new_v4().to_string();
 
         let new_ride = ride::ActiveModel {
@@ -67,13 +72,13 @@ impl RideHandler {
             ..Default::default()
         };
 
-        match ride::Entity::insert(new_ride).exec(&state.db).await {
-            Ok(_) => {
-                cache_delete(&state.redis_pool, "view_rides_cache").await;
-                Ok(ApiResponse::success("Ride created successfully".to_string()))
-            }
-            Err(err) => Err(format!("Error creating ride: {}", err)),
-        }
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let created = ride::Entity::insert(new_ride).exec_with_returning(&txn).await
+            .map_err(|err| AppError::Database(format!("Error creating ride: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_CREATE, "ride", &created.ride_id, None, AuditEventHandler::snapshot(&created)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        CachedRepository::<ride::Entity>::new(state).invalidate().await;
+        Ok(ApiResponse::success("Ride created successfully".to_string()))
     }
 
     // Update ride data
@@ -86,13 +91,26 @@ impl RideHandler {
         location: Option<String>,
         staff_id: Option<String>,
         photo: Option<Option<String>>, // Option<Option<String>> to allow setting to NULL
-    ) -> Result<ApiResponse<String>, String> {
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new()
+            .optional_one_of("status", status.as_deref(), &RIDE_STATUSES)
+            .optional("name", name.as_deref(), &[validation::name])
+            .optional("price", price.as_deref(), &[validation::positive_money])
+            .optional("location", location.as_deref(), &[validation::required])
+            .optional("staff_id", staff_id.as_deref(), &[validation::required])
+            .finish()?;
+
         let ride_record = match ride::Entity::find_by_id(ride_id).one(&state.db).await {
             Ok(Some(ride)) => ride,
-            Ok(None) => return Err("Ride not found".to_string()),
-            Err(err) => return Err(format!("Error fetching ride: {}", err)),
+            Ok(None) => return Err(AppError::NotFound("Ride not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching ride: {}", err))),
         };
+        if ride_record.deleted_at.is_some() {
+            return Err(AppError::Conflict("Ride is archived; restore it before editing".to_string()));
+        }
 
+        let before = AuditEventHandler::snapshot(&ride_record);
         let mut active_ride: ride::ActiveModel = ride_record.into();
 
         if let Some(new_status) = status {
@@ -114,27 +132,79 @@ impl RideHandler {
             active_ride.photo = sea_orm::ActiveValue::Set(new_photo);
         }
 
-        match active_ride.update(&state.db).await {
-            Ok(_) => { 
-                cache_delete(&state.redis_pool, "view_rides_cache").await;
-                Ok(ApiResponse::success("Ride updated successfully".to_string()))
-            }
-            Err(err) => Err(format!("Error updating ride: {}", err)),
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let updated = active_ride.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error updating ride: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_UPDATE, "ride", &updated.ride_id, before, AuditEventHandler::snapshot(&updated)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        CachedRepository::<ride::Entity>::new(state).invalidate().await;
+        Ok(ApiResponse::success("Ride updated successfully".to_string()))
+    }
+
+    // Archive ride data (soft delete): the row stays so history and reports still resolve it
+    pub async fn delete_ride_data(state: &AppState, ride_id: String, cascade: bool, actor_id: String) -> Result<ApiResponse<DependencyReport>, AppError> {
+        let existing = match ride::Entity::find_by_id(ride_id.clone()).one(&state.db).await {
+            Ok(Some(record)) if record.deleted_at.is_none() => record,
+            Ok(_) => return Err(AppError::NotFound("Ride not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching ride: {}", err))),
+        };
+
+        // Dependents are checked and cascaded in the same transaction as the archive itself
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let report = DependencyHandler::check_ride(&txn, &ride_id).await?;
+        if let Some(blocked) = DependencyHandler::blocked(&report, cascade) {
+            return Ok(blocked);
+        }
+        let now = clock::jakarta_now();
+        if !report.is_clear() {
+            DependencyHandler::cascade_ride(&txn, &ride_id, &actor_id).await?;
+        }
+
+        let before = AuditEventHandler::snapshot(&existing);
+        let mut active_ride: ride::ActiveModel = existing.into();
+        active_ride.deleted_at = sea_orm::ActiveValue::Set(Some(now));
+        let archived = active_ride.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error deleting ride: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_DELETE, "ride", &ride_id, before, AuditEventHandler::snapshot(&archived)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+
+        CachedRepository::<ride::Entity>::new(state).invalidate().await;
+        Ok(ApiResponse::Success { data: report, message: Some("Ride deleted successfully".to_string()) })
+    }
+
+    // Bring an archived ride back into the lists
+    pub async fn restore_ride_data(state: &AppState, ride_id: String, actor_id: String) -> Result<ApiResponse<String>, AppError> {
+        let existing = match ride::Entity::find_by_id(ride_id.clone()).one(&state.db).await {
+            Ok(Some(record)) => record,
+            Ok(None) => return Err(AppError::NotFound("Ride not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching ride: {}", err))),
+        };
+        if existing.deleted_at.is_none() {
+            return Err(AppError::Conflict("Ride is not archived".to_string()));
         }
+
+        let before = AuditEventHandler::snapshot(&existing);
+        let mut active_ride: ride::ActiveModel = existing.into();
+        active_ride.deleted_at = sea_orm::ActiveValue::Set(None);
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let restored = active_ride.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error restoring ride: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_RESTORE, "ride", &ride_id, before, AuditEventHandler::snapshot(&restored)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        CachedRepository::<ride::Entity>::new(state).invalidate().await;
+        Ok(ApiResponse::success("Ride restored successfully".to_string()))
     }
 
-    // Delete ride data
-    pub async fn delete_ride_data(state: &AppState, ride_id: String) -> Result<String, String> {
-        match ride::Entity::delete_by_id(ride_id).exec(&state.db).await {
-            Ok(delete_result) => {
-                if delete_result.rows_affected > 0 {
-                    cache_delete(&state.redis_pool, "view_rides_cache").await;
-                    Ok("Ride deleted successfully".to_string())
-                } else {
-                    Err("Ride not found".to_string())
-                }
-            }
-            Err(err) => Err(format!("Error deleting ride: {}", err)),
+    // View archived rides, most recently archived first
+    pub async fn view_archived_rides(state: &AppState) -> Result<ApiResponse<Vec<Model>>, AppError> {
+        match ride::Entity::find()
+            .filter(ride::Column::DeletedAt.is_not_null())
+            .order_by_desc(ride::Column::DeletedAt)
+            .all(&state.db)
+            .await
+        {
+            Ok(archived) => Ok(ApiResponse::success(archived)),
+            Err(err) => Err(AppError::Database(format!("Error fetching archived rides: {}", err))),
         }
     }
 }
\ No newline at end of file
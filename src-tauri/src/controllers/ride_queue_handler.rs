@@ -1,10 +1,22 @@
 use chrono::{DateTime, Utc};
-use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder};
-use entity::ride_queue::{self, ActiveModel, Model};
+use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, TransactionTrait};
+use entity::ride;
+use entity::ride_queue::{self, Model};
 use rust_decimal::Decimal;
 use uuid::Uuid;
+use entity::audit_event::{ACTION_CREATE, ACTION_DELETE, ACTION_UPDATE};
+use crate::clock;
+use crate::cache::TAG_CUSTOMER;
+use crate::controllers::audit_event_handler::AuditEventHandler;
+use crate::controllers::customer_handler::CustomerHandler;
+use crate::error::AppError;
+use crate::idempotency::Idempotency;
+use crate::validation::{self, Validator};
 use crate::{ApiResponse, AppState};
 
+pub const SAVE_RIDE_QUEUE: &str = "save_ride_queue_data";
+pub const RIDE_QUEUE_CREATED: &str = "Ride queue entry created successfully";
+
 pub struct RideQueueHandler;
 
 impl RideQueueHandler {
@@ -12,7 +24,7 @@ impl RideQueueHandler {
     pub async fn view_ride_queues(
         state: &AppState,
         ride_id: Option<String>,
-    ) -> Result<ApiResponse<Vec<Model>>, String> {
+    ) -> Result<ApiResponse<Vec<Model>>, AppError> {
         let mut query = ride_queue::Entity::find().order_by_asc(ride_queue::Column::QueuePosition);
 
         if let Some(ride_id) = ride_id {
@@ -21,7 +33,7 @@ impl RideQueueHandler {
 
         match query.all(&state.db).await {
             Ok(ride_queues) => Ok(ApiResponse::success(ride_queues)),
-            Err(err) => Err(format!("Error fetching ride queues: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error fetching ride queues: {}", err))),
         }
     }
 
@@ -30,7 +42,7 @@ impl RideQueueHandler {
         state: &AppState,
         start_time: DateTime<Utc>,
         end_time: DateTime<Utc>,
-    ) -> Result<ApiResponse<Vec<Model>>, String> {
+    ) -> Result<ApiResponse<Vec<Model>>, AppError> {
         match ride_queue::Entity::find()
             .filter(
                 Condition::all()
@@ -41,22 +53,100 @@ impl RideQueueHandler {
             .await
         {
             Ok(queues) => Ok(ApiResponse::success(queues)),
-            Err(err) => Err(format!("Error fetching ride queues in range: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error fetching ride queues in range: {}", err))),
         }
     }
 
-    // Save ride queue data (create new queue entry)
+    // Save ride queue data (create new queue entry) and charge the customer for the ride. Retrying
+    // with the same idempotency key joins and charges only once.
     pub async fn save_ride_queue_data(
         state: &AppState,
         ride_id: String,
         customer_id: String,
         queue_position: Decimal,
-    ) -> Result<ApiResponse<String>, String> {
+        actor_id: String,
+        idempotency_key: Option<String>,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let request = Self::idempotency_request(&ride_id, &customer_id);
+        let save = Self::create_ride_queue(state, ride_id, customer_id, queue_position, actor_id);
+        Idempotency::run(&state.db, idempotency_key, SAVE_RIDE_QUEUE, request, save).await
+    }
+
+    // What a repeated idempotency key must match, shared with offline replays of the join. The
+    // position is left out: a retry recomputes it from the queue the first try already joined.
+    pub(crate) fn idempotency_request(ride_id: &str, customer_id: &str) -> serde_json::Value {
+        serde_json::json!({ "ride_id": ride_id, "customer_id": customer_id })
+    }
+
+    async fn create_ride_queue(
+        state: &AppState,
+        ride_id: String,
+        customer_id: String,
+        queue_position: Decimal,
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        Self::join_queue(&txn, ride_id, customer_id, Some(queue_position), Some(&actor_id)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        state.cache.invalidate(&[TAG_CUSTOMER]).await;
+        Ok(ApiResponse::success(RIDE_QUEUE_CREATED.to_string()))
+    }
+
+    // Join an operational ride's queue and charge the customer its price, on one connection so
+    // both go through or neither does. Without a position the customer joins at the back.
+    // Callers invalidate TAG_CUSTOMER once it is committed.
+    pub(crate) async fn join_queue<C: ConnectionTrait>(
+        conn: &C,
+        ride_id: String,
+        customer_id: String,
+        queue_position: Option<Decimal>,
+        actor_id: Option<&str>,
+    ) -> Result<Model, AppError> {
+        let queued_ride = ride::Entity::find_by_id(ride_id.clone())
+            .one(conn)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching ride: {}", err)))?
+            .filter(|queued_ride| queued_ride.deleted_at.is_none())
+            .ok_or_else(|| AppError::NotFound("Ride no longer exists".to_string()))?;
+        if queued_ride.status != "Operational" {
+            return Err(AppError::Conflict(format!("{} is {} and not taking riders", queued_ride.name, queued_ride.status)));
+        }
+        let amount = validation::parse_price(&queued_ride.price)?;
+
+        let queue_position = match queue_position {
+            Some(position) => position,
+            None => {
+                let last = ride_queue::Entity::find()
+                    .filter(ride_queue::Column::RideId.eq(ride_id.clone()))
+                    .order_by_desc(ride_queue::Column::QueuePosition)
+                    .one(conn)
+                    .await
+                    .map_err(|err| AppError::Database(format!("Error fetching ride queue: {}", err)))?;
+                last.map_or(Decimal::ONE, |entry| entry.queue_position.floor() + Decimal::ONE)
+            }
+        };
+
+        let joined = Self::insert_ride_queue(conn, ride_id, customer_id, queue_position, actor_id).await?;
+        CustomerHandler::adjust_balance(conn, &joined.customer_id, -amount, actor_id).await?;
+        Ok(joined)
+    }
+
+    // Insert the queue entry on any connection; join_queue checks the ride and charges for it
+    async fn insert_ride_queue<C: ConnectionTrait>(
+        conn: &C,
+        ride_id: String,
+        customer_id: String,
+        queue_position: Decimal,
+        actor_id: Option<&str>,
+    ) -> Result<Model, AppError> {
+        Validator::new()
+            .field("ride_id", &ride_id, &[validation::required])
+            .field("customer_id", &customer_id, &[validation::required])
+            .finish()?;
+
         let ride_queue_id = Uuid::new_v4().to_string();
 
-        let jakarta_time = Utc::now()
-            .with_timezone(&chrono::FixedOffset::east_opt(7 * 3600).unwrap())
-            .naive_local();
+        let jakarta_time = clock::jakarta_now();
 
         let new_ride_queue = ride_queue::ActiveModel {
             ride_queue_id: sea_orm::ActiveValue::Set(ride_queue_id),
@@ -64,12 +154,14 @@ impl RideQueueHandler {
             joined_at: sea_orm::ActiveValue::Set(jakarta_time), // Auto-set to current time
             customer_id: sea_orm::ActiveValue::Set(customer_id),
             queue_position: sea_orm::ActiveValue::Set(queue_position),
-            ..Default::default()
         };
 
-        match ride_queue::Entity::insert(new_ride_queue).exec(&state.db).await {
-            Ok(_) => Ok(ApiResponse::success("Ride queue entry created successfully".to_string())),
-            Err(err) => Err(format!("Error creating ride queue entry: {}", err)),
+        match ride_queue::Entity::insert(new_ride_queue).exec_with_returning(conn).await {
+            Ok(created) => {
+                AuditEventHandler::record(conn, actor_id, ACTION_CREATE, "ride_queue", &created.ride_queue_id, None, AuditEventHandler::snapshot(&created)).await?;
+                Ok(created)
+            }
+            Err(err) => Err(AppError::Database(format!("Error creating ride queue entry: {}", err))),
         }
     }
 
@@ -78,33 +170,119 @@ impl RideQueueHandler {
         state: &AppState,
         ride_queue_id: String,
         queue_position: Decimal,
-    ) -> Result<ApiResponse<String>, String> {
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
         let ride_queue_record = match ride_queue::Entity::find_by_id(ride_queue_id).one(&state.db).await {
             Ok(Some(ride_queue)) => ride_queue,
-            Ok(None) => return Err("Ride queue entry not found".to_string()),
-            Err(err) => return Err(format!("Error fetching ride queue entry: {}", err)),
+            Ok(None) => return Err(AppError::NotFound("Ride queue entry not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching ride queue entry: {}", err))),
         };
 
+        let before = AuditEventHandler::snapshot(&ride_queue_record);
         let mut active_ride_queue: ride_queue::ActiveModel = ride_queue_record.into();
         active_ride_queue.queue_position = sea_orm::ActiveValue::Set(queue_position);
 
-        match active_ride_queue.update(&state.db).await {
-            Ok(_) => Ok(ApiResponse::success("Queue position updated successfully".to_string())),
-            Err(err) => Err(format!("Error updating queue position: {}", err)),
-        }
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let updated = active_ride_queue.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error updating queue position: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_UPDATE, "ride_queue", &updated.ride_queue_id, before, AuditEventHandler::snapshot(&updated)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        Ok(ApiResponse::success("Queue position updated successfully".to_string()))
     }
 
     // Delete ride queue data
-    pub async fn delete_ride_queue_data(state: &AppState, ride_queue_id: String) -> Result<String, String> {
-        match ride_queue::Entity::delete_by_id(ride_queue_id).exec(&state.db).await {
-            Ok(delete_result) => {
-                if delete_result.rows_affected > 0 {
-                    Ok("Ride queue entry deleted successfully".to_string())
-                } else {
-                    Err("Ride queue entry not found".to_string())
-                }
-            }
-            Err(err) => Err(format!("Error deleting ride queue entry: {}", err)),
+    pub async fn delete_ride_queue_data(state: &AppState, ride_queue_id: String, actor_id: String) -> Result<ApiResponse<String>, AppError> {
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let existing = ride_queue::Entity::find_by_id(ride_queue_id.clone()).one(&txn).await.ok().flatten();
+        let delete_result = ride_queue::Entity::delete_by_id(ride_queue_id.clone()).exec(&txn).await
+            .map_err(|err| AppError::Database(format!("Error deleting ride queue entry: {}", err)))?;
+        if delete_result.rows_affected == 0 {
+            return Err(AppError::NotFound("Ride queue entry not found".to_string()));
+        }
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_DELETE, "ride_queue", &ride_queue_id, existing.as_ref().and_then(AuditEventHandler::snapshot), None).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        Ok(ApiResponse::success("Ride queue entry deleted successfully".to_string()))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::test_support::{self, data, error, ACTOR};
+
+    async fn join(state: &AppState, ride_id: &str, customer_id: &str, position: i64) {
+        data(RideQueueHandler::save_ride_queue_data(state, ride_id.to_string(), customer_id.to_string(), Decimal::from(position), ACTOR.to_string(), None).await);
+    }
+
+    async fn customers(state: &AppState, count: usize) -> Vec<String> {
+        let mut ids = Vec::new();
+        for _ in 0..count {
+            ids.push(test_support::seed_customer(state, "100000").await.customer_id);
         }
+        ids
+    }
+
+    fn customers_in_order(queue: &[Model]) -> Vec<&str> {
+        queue.iter().map(|entry| entry.customer_id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn queue_is_served_in_position_order_per_ride() {
+        let state = test_support::test_state().await;
+        let operator = test_support::seed_staff(&state, "Ride Staff").await;
+        let coaster = test_support::seed_ride(&state, &operator.staff_id, "50000").await;
+        let carousel = test_support::seed_ride(&state, &operator.staff_id, "20000").await;
+        let [a, b, c] = <[String; 3]>::try_from(customers(&state, 3).await).unwrap();
+
+        join(&state, &coaster.ride_id, &c, 3).await;
+        join(&state, &coaster.ride_id, &a, 1).await;
+        join(&state, &carousel.ride_id, &c, 1).await;
+        join(&state, &coaster.ride_id, &b, 2).await;
+
+        let queue = data(RideQueueHandler::view_ride_queues(&state, Some(coaster.ride_id.clone())).await);
+        assert_eq!(customers_in_order(&queue), [&a, &b, &c]);
+        assert_eq!(data(RideQueueHandler::view_ride_queues(&state, None).await).len(), 4);
+    }
+
+    #[tokio::test]
+    async fn moving_and_leaving_the_queue() {
+        let state = test_support::test_state().await;
+        let operator = test_support::seed_staff(&state, "Ride Staff").await;
+        let ride = test_support::seed_ride(&state, &operator.staff_id, "50000").await;
+        let [a, b, c] = <[String; 3]>::try_from(customers(&state, 3).await).unwrap();
+        join(&state, &ride.ride_id, &a, 1).await;
+        join(&state, &ride.ride_id, &b, 2).await;
+        join(&state, &ride.ride_id, &c, 3).await;
+
+        // Staff let the third customer in between the first two, then the first one boards
+        let queue = data(RideQueueHandler::view_ride_queues(&state, Some(ride.ride_id.clone())).await);
+        data(RideQueueHandler::update_queue_position(&state, queue[2].ride_queue_id.clone(), Decimal::new(15, 1), ACTOR.to_string()).await);
+        data(RideQueueHandler::delete_ride_queue_data(&state, queue[0].ride_queue_id.clone(), ACTOR.to_string()).await);
+
+        let queue = data(RideQueueHandler::view_ride_queues(&state, Some(ride.ride_id.clone())).await);
+        assert_eq!(customers_in_order(&queue), [&c, &b]);
+
+        let err = error(RideQueueHandler::update_queue_position(&state, "gone".to_string(), Decimal::ONE, ACTOR.to_string()).await);
+        assert_eq!(err.code(), ErrorCode::NotFound);
+    }
+
+    #[tokio::test]
+    async fn joining_charges_the_ride_and_a_short_balance_joins_nothing() {
+        let state = test_support::test_state().await;
+        let operator = test_support::seed_staff(&state, "Ride Staff").await;
+        let ride = test_support::seed_ride(&state, &operator.staff_id, "30000").await;
+        let customer = test_support::seed_customer(&state, "50000").await;
+        let save = |key: &str| {
+            RideQueueHandler::save_ride_queue_data(&state, ride.ride_id.clone(), customer.customer_id.clone(), Decimal::ONE, ACTOR.to_string(), Some(key.to_string()))
+        };
+
+        // A retry with the same key joins and charges once
+        data(save("join-1").await);
+        data(save("join-1").await);
+        let charged = data(CustomerHandler::get_customer_details(&state, customer.customer_id.clone()).await);
+        assert_eq!(charged.virtual_balance, "20000");
+
+        assert_eq!(error(save("join-2").await).code(), ErrorCode::Conflict);
+        assert_eq!(data(RideQueueHandler::view_ride_queues(&state, Some(ride.ride_id.clone())).await).len(), 1);
     }
-}
\ No newline at end of file
+}
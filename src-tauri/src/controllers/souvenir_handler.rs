@@ -1,41 +1,27 @@
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
-use entity::souvenir::{self, ActiveModel, Model};
+use sea_orm::{sea_query::Expr, ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, Set, TransactionTrait};
+use entity::souvenir::{self, Model};
 use uuid::Uuid;
 use rust_decimal::Decimal;
-use crate::{cache_delete, cache_get, cache_set, ApiResponse, AppState};
+use entity::audit_event::{ACTION_CREATE, ACTION_DELETE, ACTION_UPDATE};
+use crate::controllers::audit_event_handler::AuditEventHandler;
+use crate::cache::CachedView;
+use crate::repository::CachedRepository;
+use crate::error::AppError;
+use crate::validation::{self, Validator};
+use crate::{ApiResponse, AppState};
 
 pub struct SouvenirHandler;
 
 impl SouvenirHandler {
     // View all souvenirs, optionally filtered by store_id
-    pub async fn view_souvenirs(state: &AppState, store_id: Option<String>) -> Result<ApiResponse<Vec<Model>>, String> {
-        let cache_key = match &store_id {
-            Some(id) => format!("view_souvenirs_cache_store_{}", id),
-            None => "view_souvenirs_cache_all".to_string(),
-        };
-
-        // Check Redis cache first
-        if let Some(cached_souvenirs) = cache_get::<Vec<Model>>(&state.redis_pool, &cache_key).await {
-            println!("Cache hit: Returning souvenirs from Redis (key: {})", cache_key);
-            return Ok(ApiResponse::success(cached_souvenirs));
-        }
-
-        // Cache miss: Query the database
-        println!("Cache miss: Querying database (key: {})", cache_key);
+    pub async fn view_souvenirs(state: &AppState, store_id: Option<String>) -> Result<ApiResponse<Vec<Model>>, AppError> {
         let mut query = souvenir::Entity::find().order_by_asc(souvenir::Column::Name);
-        if let Some(store_id) = store_id {
-            query = query.filter(souvenir::Column::StoreId.eq(store_id));
+        if let Some(store_id) = &store_id {
+            query = query.filter(souvenir::Column::StoreId.eq(store_id.as_str()));
         }
-
-
-        match query.all(&state.db).await
-        {
-            Ok(souvenirs) => {
-                // Cache the result for 60 seconds
-                cache_set(&state.redis_pool, &cache_key, &souvenirs, 60).await;
-                Ok(ApiResponse::success(souvenirs))
-            }
-            Err(err) => Ok(ApiResponse::error(format!("Error fetching souvenirs: {}", err))),
+        match CachedRepository::<souvenir::Entity>::new(state).find_all(CachedView::Souvenirs(store_id.as_deref()), query).await {
+            Ok(souvenirs) => Ok(ApiResponse::success(souvenirs)),
+            Err(err) => Err(AppError::Database(format!("Error fetching souvenirs: {}", err))),
         }
     }
 
@@ -43,11 +29,11 @@ impl SouvenirHandler {
     pub async fn get_souvenir_details(
         state: &AppState,
         souvenir_id: String,
-    ) -> Result<ApiResponse<Model>, String> {
+    ) -> Result<ApiResponse<Model>, AppError> {
         match souvenir::Entity::find_by_id(souvenir_id.clone()).one(&state.db).await {
             Ok(Some(souvenir_details)) => Ok(ApiResponse::success(souvenir_details)),
-            Ok(None) => Err("Souvenir not found".to_string()),
-            Err(err) => Err(format!("Database error fetching souvenir details: {}", err)),
+            Ok(None) => Err(AppError::NotFound("Souvenir not found".to_string())),
+            Err(err) => Err(AppError::Database(format!("Database error fetching souvenir details: {}", err))),
         }
     }
 
@@ -59,9 +45,17 @@ impl SouvenirHandler {
         price: String,
         stock: i32,
         store_id: String,
-    ) -> Result<ApiResponse<String>, String> {
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new()
+            .field("name", &name, &[validation::name])
+            .field("price", &price, &[validation::positive_money])
+            .at_least("stock", stock, 0)
+            .field("store_id", &store_id, &[validation::required])
+            .finish()?;
+
         let souvenir_id = Uuid::new_v4().to_string();
-        let price_decimal = Decimal::from_str_exact(&price).map_err(|e| format!("Invalid price format: {}", e))?;
+        let price_decimal = Decimal::from_str_exact(price.trim()).unwrap_or_default(); // Checked above
 
 
         let new_souvenir = souvenir::ActiveModel {
@@ -70,21 +64,20 @@ impl SouvenirHandler {
             photo: sea_orm::ActiveValue::Set(photo),
             price: sea_orm::ActiveValue::Set(price_decimal),
             stock: sea_orm::ActiveValue::Set(stock),
-            store_id: sea_orm::ActiveValue::Set(store_id.clone()),
-            ..Default::default()
+            store_id: sea_orm::ActiveValue::Set(store_id),
         };
 
-        match souvenir::Entity::insert(new_souvenir).exec(&state.db).await {
-            Ok(_) => {
-                cache_delete(&state.redis_pool, "view_souvenirs_cache_all").await;
-                cache_delete(&state.redis_pool, &format!("view_souvenirs_cache_store_{}", store_id)).await;
-                Ok(ApiResponse::success("Souvenir created successfully".to_string()))
-            }
-            Err(err) => Err(format!("Error creating souvenir: {}", err)),
-        }
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let created = souvenir::Entity::insert(new_souvenir).exec_with_returning(&txn).await
+            .map_err(|err| AppError::Database(format!("Error creating souvenir: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_CREATE, "souvenir", &created.souvenir_id, None, AuditEventHandler::snapshot(&created)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        CachedRepository::<souvenir::Entity>::new(state).invalidate().await;
+        Ok(ApiResponse::success("Souvenir created successfully".to_string()))
     }
 
     // Update souvenir data
+    #[allow(clippy::too_many_arguments)]
     pub async fn update_souvenir_data(
         state: &AppState,
         souvenir_id: String,
@@ -93,13 +86,25 @@ impl SouvenirHandler {
         price: Option<String>,
         stock: Option<i32>,
         store_id: Option<String>,
-    ) -> Result<ApiResponse<String>, String> {
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let mut input = Validator::new();
+        input
+            .optional("name", name.as_deref(), &[validation::name])
+            .optional("price", price.as_deref(), &[validation::positive_money])
+            .optional("store_id", store_id.as_deref(), &[validation::required]);
+        if let Some(stock) = stock {
+            input.at_least("stock", stock, 0);
+        }
+        input.finish()?;
+
         let souvenir_record = match souvenir::Entity::find_by_id(souvenir_id).one(&state.db).await {
             Ok(Some(souvenir)) => souvenir,
-            Ok(None) => return Err("Souvenir not found".to_string()),
-            Err(err) => return Err(format!("Error fetching souvenir: {}", err)),
+            Ok(None) => return Err(AppError::NotFound("Souvenir not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching souvenir: {}", err))),
         };
 
+        let before = AuditEventHandler::snapshot(&souvenir_record);
         let mut active_souvenir: souvenir::ActiveModel = souvenir_record.into();
 
         if let Some(new_name) = name {
@@ -109,7 +114,7 @@ impl SouvenirHandler {
             active_souvenir.photo = sea_orm::ActiveValue::Set(new_photo);
         }
         if let Some(new_price_str) = price {
-            let new_price = Decimal::from_str_exact(&new_price_str).map_err(|e| format!("Invalid price format: {}", e))?;
+            let new_price = Decimal::from_str_exact(new_price_str.trim()).unwrap_or_default();
             active_souvenir.price = sea_orm::ActiveValue::Set(new_price);
         }
         if let Some(new_stock) = stock {
@@ -120,17 +125,14 @@ impl SouvenirHandler {
         }
 
 
-        match active_souvenir.update(&state.db).await {
-            Ok(_) => {
-                cache_delete(&state.redis_pool, "view_souvenirs_cache_all").await;
-                // Invalidate cache for specific store as well, if store_id is updated in future versions
-                // if let Some(store_id) = store_id {
-                //     cache_delete(&state.redis_pool, &format!("view_souvenirs_cache_store_{}", store_id)).await;
-                // }
-                Ok(ApiResponse::success("Souvenir updated successfully".to_string()))
-            }
-            Err(err) => Err(format!("Error updating souvenir: {}", err)),
-        }
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let updated = active_souvenir.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error updating souvenir: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_UPDATE, "souvenir", &updated.souvenir_id, before, AuditEventHandler::snapshot(&updated)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        // Covers both the old and the new store's list when the souvenir moves
+        CachedRepository::<souvenir::Entity>::new(state).invalidate().await;
+        Ok(ApiResponse::success("Souvenir updated successfully".to_string()))
     }
 
     // Update souvenir stock
@@ -138,43 +140,156 @@ impl SouvenirHandler {
         state: &AppState,
         souvenir_id: String,
         stock: i32,
-    ) -> Result<ApiResponse<String>, String> {
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new().at_least("stock", stock, 0).finish()?;
+
         let souvenir_record = match souvenir::Entity::find_by_id(souvenir_id).one(&state.db).await {
             Ok(Some(souvenir)) => souvenir,
-            Ok(None) => return Err("Souvenir not found".to_string()),
-            Err(err) => return Err(format!("Error fetching souvenir: {}", err)),
+            Ok(None) => return Err(AppError::NotFound("Souvenir not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching souvenir: {}", err))),
         };
 
+        let before = AuditEventHandler::snapshot(&souvenir_record);
         let mut active_souvenir: souvenir::ActiveModel = souvenir_record.into();
         active_souvenir.stock = Set(stock);
 
 
-        match active_souvenir.update(&state.db).await {
-            Ok(_) => {
-                // No need to delete cache for view_souvenirs here, as stock change might not affect the general view significantly,
-                // or you can choose to invalidate cache if stock level is critical for the view.
-                // cache_delete(&state.redis_pool, "view_souvenirs_cache").await;
-                Ok(ApiResponse::success("Souvenir stock updated successfully".to_string()))
-            }
-            Err(err) => Err(format!("Error updating souvenir stock: {}", err)),
-        }
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let updated = active_souvenir.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error updating souvenir stock: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_UPDATE, "souvenir", &updated.souvenir_id, before, AuditEventHandler::snapshot(&updated)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        CachedRepository::<souvenir::Entity>::new(state).invalidate().await;
+        Ok(ApiResponse::success("Souvenir stock updated successfully".to_string()))
     }
 
 
+    // Take sold items out of stock on any connection, never below zero. The decrement is done by
+    // the database, so two sales at once cannot both take the last item. Callers invalidate the
+    // souvenir views once it is committed.
+    pub(crate) async fn take_stock<C: ConnectionTrait>(
+        conn: &C,
+        souvenir_id: &str,
+        quantity: i32,
+        actor_id: Option<&str>,
+    ) -> Result<Model, AppError> {
+        let find = || souvenir::Entity::find_by_id(souvenir_id.to_string()).one(conn);
+        let before = match find().await {
+            Ok(Some(souvenir)) => souvenir,
+            Ok(None) => return Err(AppError::NotFound("Souvenir not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching souvenir: {}", err))),
+        };
+
+        let taken = souvenir::Entity::update_many()
+            .col_expr(souvenir::Column::Stock, Expr::col(souvenir::Column::Stock).sub(quantity))
+            .filter(souvenir::Column::SouvenirId.eq(souvenir_id))
+            .filter(souvenir::Column::Stock.gte(quantity))
+            .exec(conn)
+            .await
+            .map_err(|err| AppError::Database(format!("Error updating souvenir stock: {}", err)))?;
+        let updated = match find().await {
+            Ok(Some(souvenir)) => souvenir,
+            Ok(None) => return Err(AppError::NotFound("Souvenir not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching souvenir: {}", err))),
+        };
+        if taken.rows_affected == 0 {
+            return Err(AppError::Conflict(format!("Only {} left in stock, {} ordered", updated.stock, quantity)));
+        }
+
+        AuditEventHandler::record(conn, actor_id, ACTION_UPDATE, "souvenir", souvenir_id, AuditEventHandler::snapshot(&before), AuditEventHandler::snapshot(&updated)).await?;
+        Ok(updated)
+    }
+
     // Delete souvenir data
-    pub async fn delete_souvenir_data(state: &AppState, souvenir_id: String) -> Result<String, String> {
-        match souvenir::Entity::delete_by_id(souvenir_id).exec(&state.db).await {
-            Ok(delete_result) => {
-                if delete_result.rows_affected > 0 {
-                    cache_delete(&state.redis_pool, "view_souvenirs_cache_all").await;
-                    // Invalidate cache for specific store as well, if store-specific cache invalidation is implemented
-                    // cache_delete(&state.redis_pool, &format!("view_souvenirs_cache_store_{}", store_id)).await;
-                    Ok("Souvenir deleted successfully".to_string())
-                } else {
-                    Err("Souvenir not found".to_string())
-                }
-            }
-            Err(err) => Err(format!("Error deleting souvenir: {}", err)),
+    pub async fn delete_souvenir_data(state: &AppState, souvenir_id: String, actor_id: String) -> Result<ApiResponse<String>, AppError> {
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let existing = souvenir::Entity::find_by_id(souvenir_id.clone()).one(&txn).await.ok().flatten();
+        let delete_result = souvenir::Entity::delete_by_id(souvenir_id.clone()).exec(&txn).await
+            .map_err(|err| AppError::Database(format!("Error deleting souvenir: {}", err)))?;
+        if delete_result.rows_affected == 0 {
+            return Err(AppError::NotFound("Souvenir not found".to_string()));
+        }
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_DELETE, "souvenir", &souvenir_id, existing.as_ref().and_then(AuditEventHandler::snapshot), None).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        CachedRepository::<souvenir::Entity>::new(state).invalidate().await;
+        Ok(ApiResponse::success("Souvenir deleted successfully".to_string()))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, data, ACTOR};
+    use rust_decimal::Decimal;
+
+    fn stock_of(souvenirs: &[Model], souvenir_id: &str) -> Option<i32> {
+        souvenirs.iter().find(|souvenir| souvenir.souvenir_id == souvenir_id).map(|souvenir| souvenir.stock)
+    }
+
+    // Reads every list a souvenir shows up in, so each one is cached before the write under test
+    async fn read_lists(state: &AppState, store_ids: &[&str]) -> Vec<Vec<Model>> {
+        let mut lists = vec![data(SouvenirHandler::view_souvenirs(state, None).await)];
+        for store_id in store_ids {
+            lists.push(data(SouvenirHandler::view_souvenirs(state, Some(store_id.to_string())).await));
+        }
+        lists
+    }
+
+    #[tokio::test]
+    async fn a_stock_change_is_seen_in_every_cached_list() {
+        let state = test_support::test_state().await;
+        let store = test_support::seed_store(&state).await;
+        let plush = test_support::seed_souvenir(&state, &store.store_id, Decimal::from(15000), 10).await;
+        for list in read_lists(&state, &[&store.store_id]).await {
+            assert_eq!(stock_of(&list, &plush.souvenir_id), Some(10));
+        }
+
+        data(SouvenirHandler::update_souvenir_stock(&state, plush.souvenir_id.clone(), 4, ACTOR.to_string()).await);
+
+        for list in read_lists(&state, &[&store.store_id]).await {
+            assert_eq!(stock_of(&list, &plush.souvenir_id), Some(4));
+        }
+    }
+
+    #[tokio::test]
+    async fn moving_a_souvenir_updates_both_stores_lists() {
+        let state = test_support::test_state().await;
+        let old_store = test_support::seed_store(&state).await;
+        let new_store = test_support::seed_store(&state).await;
+        let plush = test_support::seed_souvenir(&state, &old_store.store_id, Decimal::from(15000), 10).await;
+        read_lists(&state, &[&old_store.store_id, &new_store.store_id]).await;
+
+        data(SouvenirHandler::update_souvenir_data(
+            &state,
+            plush.souvenir_id.clone(),
+            Some("Giant plush".to_string()),
+            None,
+            None,
+            None,
+            Some(new_store.store_id.clone()),
+            ACTOR.to_string(),
+        )
+        .await);
+
+        let lists = read_lists(&state, &[&old_store.store_id, &new_store.store_id]).await;
+        assert_eq!(lists[0][0].name, "Giant plush");
+        assert_eq!(stock_of(&lists[1], &plush.souvenir_id), None);
+        assert_eq!(stock_of(&lists[2], &plush.souvenir_id), Some(10));
+    }
+
+    #[tokio::test]
+    async fn a_deleted_souvenir_leaves_every_cached_list() {
+        let state = test_support::test_state().await;
+        let store = test_support::seed_store(&state).await;
+        let plush = test_support::seed_souvenir(&state, &store.store_id, Decimal::from(15000), 10).await;
+        for list in read_lists(&state, &[&store.store_id]).await {
+            assert_eq!(stock_of(&list, &plush.souvenir_id), Some(10));
+        }
+
+        data(SouvenirHandler::delete_souvenir_data(&state, plush.souvenir_id.clone(), ACTOR.to_string()).await);
+
+        for list in read_lists(&state, &[&store.store_id]).await {
+            assert_eq!(stock_of(&list, &plush.souvenir_id), None);
         }
     }
-}
\ No newline at end of file
+}
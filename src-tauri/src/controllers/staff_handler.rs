@@ -1,52 +1,313 @@
-use sea_orm::{ActiveModelTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect, ColumnTrait};
-use entity::staff::{self, ActiveModel, Model};
+use sea_orm::{ActiveModelTrait, EntityTrait, QueryFilter, QueryOrder, ColumnTrait, TransactionTrait};
+use entity::audit_event::{ACTION_CREATE, ACTION_DELETE, ACTION_RESTORE, ACTION_UPDATE};
+use entity::staff::{self, Model};
+use entity::user_session::KIND_STAFF;
 use uuid::Uuid;
+use crate::clock;
+use crate::cache::TAG_CUSTOMER_SERVICE_TICKET;
+use crate::error::AppError;
 use crate::{ApiResponse, AppState};
-use bcrypt::{hash, verify, BcryptResult, DEFAULT_COST}; // Import bcrypt -> Salting is automatic and built into bcrypt
+use crate::controllers::audit_event_handler::AuditEventHandler;
+use crate::controllers::dependency_handler::{DependencyHandler, DependencyReport};
+use crate::controllers::chat_handler::ChatHandler;
+use bcrypt::{hash, verify, DEFAULT_COST}; // Import bcrypt -> Salting is automatic and built into bcrypt
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::sync::OnceLock;
+use crate::login_guard::LoginGuard;
+use crate::session::Session;
+use crate::validation::{self, Validator};
+
+// Staff divisions, each with its own group chat
+pub const DIVISIONS: [&str; 6] = ["Customer Service", "Operational", "Consumption", "Maintenance", "Marketing", "Executive"];
+
+const RESET_TOKEN_TTL_MINUTES: i64 = 60;
+const INVALID_LOGIN: &str = "Invalid email or password";
+const INVALID_CURRENT_PASSWORD: &str = "Current password is incorrect";
+
+// Strength rules for staff passwords, configurable through STAFF_PASSWORD_* environment variables
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+}
+
+impl PasswordPolicy {
+    pub fn from_env() -> Self {
+        fn flag(key: &str, default: bool) -> bool {
+            env::var(key).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+        }
+        PasswordPolicy {
+            min_length: env::var("STAFF_PASSWORD_MIN_LENGTH")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .filter(|length: &usize| *length > 0)
+                .unwrap_or(8),
+            require_uppercase: flag("STAFF_PASSWORD_REQUIRE_UPPERCASE", true),
+            require_lowercase: flag("STAFF_PASSWORD_REQUIRE_LOWERCASE", true),
+            require_digit: flag("STAFF_PASSWORD_REQUIRE_DIGIT", true),
+            require_symbol: flag("STAFF_PASSWORD_REQUIRE_SYMBOL", false),
+        }
+    }
+
+    // Every rule the password breaks, so the user can fix them all at once
+    pub fn violations(&self, password: &str) -> Vec<String> {
+        let mut violations = Vec::new();
+        if password.chars().count() < self.min_length {
+            violations.push(format!("at least {} characters", self.min_length));
+        }
+        if self.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+            violations.push("an uppercase letter".to_string());
+        }
+        if self.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+            violations.push("a lowercase letter".to_string());
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            violations.push("a digit".to_string());
+        }
+        if self.require_symbol && password.chars().all(|c| c.is_alphanumeric()) {
+            violations.push("a symbol".to_string());
+        }
+        violations
+    }
+
+    pub fn validate(&self, input: &mut Validator, field: &str, password: &str) {
+        let violations = self.violations(password);
+        input.check(field, violations.is_empty(), format!("Password must contain {}", violations.join(", ")));
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct StaffLoginResult {
+    pub session_token: String,
+    pub staff_id: String,
+    pub must_change_password: bool, // The client must collect a new password before continuing
+}
 
 pub struct StaffHandler;
 
 impl StaffHandler {
-    // Staff Login
-    pub async fn staff_login(state: &AppState, email: String, password: String) -> Result<ApiResponse<String>, String> {
-        match staff::Entity::find()
+    // Map a staff role to the division it belongs to
+    pub fn division_for_role(role: &str) -> Option<&'static str> {
+        match role {
+            "CustomerServiceStaff" | "CustomerServiceManager" | "LostAndFoundStaff" => Some("Customer Service"),
+            "RideStaff" | "RideManager" => Some("Operational"),
+            "FBSupervisor" | "Chef" | "Waiter" => Some("Consumption"),
+            "MaintenanceStaff" | "MaintenanceManager" => Some("Maintenance"),
+            "SalesAssociate" | "RetailManager" => Some("Marketing"),
+            "CEO" | "CFO" | "COO" => Some("Executive"),
+            _ => None,
+        }
+    }
+
+    // Emails are unique across all accounts, archived ones included, since archived staff can be restored
+    async fn email_taken(state: &AppState, email: &str, except_staff_id: Option<&str>) -> Result<bool, AppError> {
+        let mut query = staff::Entity::find().filter(staff::Column::Email.eq(email));
+        if let Some(staff_id) = except_staff_id {
+            query = query.filter(staff::Column::StaffId.ne(staff_id));
+        }
+        match query.one(&state.db).await {
+            Ok(existing) => Ok(existing.is_some()),
+            Err(err) => Err(AppError::Database(format!("Error checking staff email: {}", err))),
+        }
+    }
+
+    fn validate_role(input: &mut Validator, role: &str) {
+        input.check("role", Self::division_for_role(role).is_some(), format!("Role '{}' is not a staff role", role));
+    }
+
+    // Verified against when no account matches, so unknown emails take as long as wrong passwords
+    fn dummy_password_hash() -> &'static str {
+        static DUMMY: OnceLock<String> = OnceLock::new();
+        DUMMY.get_or_init(|| hash("not-a-real-password", DEFAULT_COST).unwrap_or_default())
+    }
+
+    // Staff Login. Every failure gives the same message so emails cannot be enumerated.
+    pub async fn staff_login(
+        state: &AppState,
+        email: String,
+        password: String,
+        device_id: Option<String>,
+    ) -> Result<ApiResponse<StaffLoginResult>, AppError> {
+        let subjects = LoginGuard::subjects(&email, device_id.as_deref());
+        LoginGuard::check(state.cache.backend(), "staff", &subjects).await?;
+
+        let staff_member = staff::Entity::find()
             .filter(staff::Column::Email.eq(email.clone()))
+            .filter(staff::Column::DeletedAt.is_null()) // Archived staff can no longer sign in
             .one(&state.db)
             .await
-        {
-            Ok(Some(staff_member)) => {
-                // Verify password
-                // bcrypt::verify extracts the salt from the stored hash to perform verification
-                match verify(password, &staff_member.password_hash) {
-                    Ok(true) => {
-                        // Passwords match - generate session token
-                        let session_token = Uuid::new_v4().to_string();
-                        Ok(ApiResponse::success(session_token))
-                    }
-                    Ok(false) => {
-                        Err("Invalid password".to_string()) // Passwords don't match
-                    }
-                    Err(err) => {
-                        Err(format!("Password verification error: {}", err)) // Bcrypt error
-                    }
-                }
+            .map_err(|err| AppError::Database(format!("Database error during login: {}", err)))?;
+
+        // bcrypt::verify extracts the salt from the stored hash to perform verification
+        let stored_hash = staff_member.as_ref().map_or(Self::dummy_password_hash(), |staff_member| staff_member.password_hash.as_str());
+        let password_matches = verify(password, stored_hash).unwrap_or(false);
+        match staff_member {
+            Some(staff_member) if password_matches => {
+                LoginGuard::record_success(state.cache.backend(), "staff", &subjects).await;
+                // Passwords match - start a session
+                let session_token = Session::open(&state.db, &staff_member.staff_id, KIND_STAFF).await?;
+                Ok(ApiResponse::success(StaffLoginResult {
+                    session_token,
+                    staff_id: staff_member.staff_id,
+                    must_change_password: staff_member.must_change_password,
+                }))
             }
-            Ok(None) => {
-                Err("Invalid email or password".to_string()) // Staff not found or incorrect email
+            _ => {
+                LoginGuard::record_failure(state.cache.backend(), "staff", &subjects).await;
+                Err(AppError::Unauthorized(INVALID_LOGIN.to_string()))
             }
-            Err(err) => {
-                Err(format!("Database error during login: {}", err)) // Database error
+        }
+    }
+
+    pub fn view_password_policy() -> ApiResponse<PasswordPolicy> {
+        ApiResponse::success(PasswordPolicy::from_env())
+    }
+
+    // Change a password, proving knowledge of the current one
+    pub async fn change_staff_password(
+        state: &AppState,
+        staff_id: String,
+        current_password: String,
+        new_password: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let staff_member = staff::Entity::find_by_id(staff_id.clone())
+            .filter(staff::Column::DeletedAt.is_null())
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching staff account: {}", err)))?;
+
+        // Guessing the current password counts against the same lock as guessing it at login, and
+        // an unknown account fails the same way as a wrong password
+        let account = staff_member.as_ref().map_or(staff_id.as_str(), |staff_member| staff_member.email.as_str());
+        let subjects = LoginGuard::subjects(account, None);
+        LoginGuard::check(state.cache.backend(), "staff", &subjects).await?;
+        let stored_hash = staff_member.as_ref().map_or(Self::dummy_password_hash(), |staff_member| staff_member.password_hash.as_str());
+        let password_matches = verify(&current_password, stored_hash).unwrap_or(false);
+        let staff_member = match staff_member {
+            Some(staff_member) if password_matches => {
+                LoginGuard::record_success(state.cache.backend(), "staff", &subjects).await;
+                staff_member
             }
+            _ => {
+                LoginGuard::record_failure(state.cache.backend(), "staff", &subjects).await;
+                return Err(AppError::Unauthorized(INVALID_CURRENT_PASSWORD.to_string()));
+            }
+        };
+        if current_password == new_password {
+            return Err(AppError::invalid_field("new_password", "New password must be different from the current one"));
         }
+
+        Self::set_password(state, staff_member, &new_password).await?;
+        Ok(ApiResponse::success("Password changed successfully".to_string()))
+    }
+
+    // Issue a one-time reset token for a staff member who forgot their password (executives only).
+    // The token is returned once for the executive to hand over; only its hash is stored.
+    pub async fn reset_staff_password(
+        state: &AppState,
+        requester_id: String,
+        staff_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let requester = match staff::Entity::find_by_id(requester_id.clone()).one(&state.db).await {
+            Ok(Some(requester)) if requester.deleted_at.is_none() => requester,
+            Ok(_) => return Err(AppError::NotFound("Staff not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching staff: {}", err))),
+        };
+        if Self::division_for_role(&requester.role) != Some("Executive") {
+            return Err(AppError::Forbidden("Only executives can reset staff passwords".to_string()));
+        }
+
+        let staff_member = match staff::Entity::find_by_id(staff_id.clone()).one(&state.db).await {
+            Ok(Some(staff_member)) if staff_member.deleted_at.is_none() => staff_member,
+            Ok(_) => return Err(AppError::NotFound("Staff account not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching staff account: {}", err))),
+        };
+
+        let token = Uuid::new_v4().simple().to_string();
+        let token_hash = hash(&token, DEFAULT_COST).map_err(|err| AppError::Internal(format!("Token hashing failed: {}", err)))?;
+
+        let before = AuditEventHandler::snapshot(&staff_member);
+        let mut active_staff_member: staff::ActiveModel = staff_member.into();
+        active_staff_member.reset_token_hash = sea_orm::ActiveValue::Set(Some(token_hash));
+        active_staff_member.reset_token_expires_at = sea_orm::ActiveValue::Set(Some(clock::jakarta_now() + Duration::minutes(RESET_TOKEN_TTL_MINUTES)));
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let updated = active_staff_member.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error issuing reset token: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&requester_id), ACTION_UPDATE, "staff", &staff_id, before, AuditEventHandler::snapshot(&updated)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        Ok(ApiResponse::Success {
+            data: token,
+            message: Some(format!("Reset token issued; it expires in {} minutes", RESET_TOKEN_TTL_MINUTES)),
+        })
+    }
+
+    // Redeem a reset token and choose a new password
+    pub async fn complete_staff_password_reset(
+        state: &AppState,
+        email: String,
+        token: String,
+        new_password: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let staff_member = match staff::Entity::find()
+            .filter(staff::Column::Email.eq(email))
+            .filter(staff::Column::DeletedAt.is_null())
+            .one(&state.db)
+            .await
+        {
+            Ok(Some(staff_member)) => staff_member,
+            Ok(None) => return Err(AppError::Unauthorized("Invalid or expired reset token".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching staff account: {}", err))),
+        };
+
+        let token_valid = match (&staff_member.reset_token_hash, staff_member.reset_token_expires_at) {
+            (Some(token_hash), Some(expires_at)) if expires_at > clock::jakarta_now() => verify(&token, token_hash).unwrap_or(false),
+            _ => false,
+        };
+        if !token_valid {
+            return Err(AppError::Unauthorized("Invalid or expired reset token".to_string()));
+        }
+
+        Self::set_password(state, staff_member, &new_password).await?;
+        Ok(ApiResponse::success("Password reset successfully".to_string()))
+    }
+
+    // Hash and store a new password, clearing any pending reset or forced rotation. Only the
+    // staff member themselves gets here, so they are recorded as the actor.
+    async fn set_password(state: &AppState, staff_member: Model, new_password: &str) -> Result<(), AppError> {
+        let mut input = Validator::new();
+        PasswordPolicy::from_env().validate(&mut input, "new_password", new_password);
+        input.finish()?;
+        let password_hash = hash(new_password, DEFAULT_COST).map_err(|err| AppError::Internal(format!("Password hashing failed: {}", err)))?;
+
+        let staff_id = staff_member.staff_id.clone();
+        let before = AuditEventHandler::snapshot(&staff_member);
+        let mut active_staff_member: staff::ActiveModel = staff_member.into();
+        active_staff_member.password_hash = sea_orm::ActiveValue::Set(password_hash);
+        active_staff_member.must_change_password = sea_orm::ActiveValue::Set(false);
+        active_staff_member.password_changed_at = sea_orm::ActiveValue::Set(Some(clock::jakarta_now()));
+        active_staff_member.reset_token_hash = sea_orm::ActiveValue::Set(None);
+        active_staff_member.reset_token_expires_at = sea_orm::ActiveValue::Set(None);
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let updated = active_staff_member.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error updating password: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&staff_id), ACTION_UPDATE, "staff", &staff_id, before, AuditEventHandler::snapshot(&updated)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        Ok(())
     }
 
     // Get staff details by Email
     pub async fn get_staff_details_by_email(
         state: &AppState,
         email: String,
-    ) -> Result<ApiResponse<Model>, String> {
+    ) -> Result<ApiResponse<Model>, AppError> {
         match staff::Entity::find()
             .filter(staff::Column::Email.eq(email.clone()))
+            .filter(staff::Column::DeletedAt.is_null())
             .one(&state.db)
             .await
         {
@@ -54,10 +315,10 @@ impl StaffHandler {
                 Ok(ApiResponse::success(staff_details))
             }
             Ok(None) => {
-                Err("Staff not found with this email".to_string())
+                Err(AppError::NotFound("Staff not found with this email".to_string()))
             }
             Err(err) => {
-                Err(format!("Database error fetching staff details: {}", err))
+                Err(AppError::Database(format!("Database error fetching staff details: {}", err)))
             }
         }
     }
@@ -66,49 +327,51 @@ impl StaffHandler {
     pub async fn get_staff_details(
         state: &AppState,
         staff_id: String,
-    ) -> Result<ApiResponse<Model>, String> {
+    ) -> Result<ApiResponse<Model>, AppError> {
         match staff::Entity::find_by_id(staff_id.clone()).one(&state.db).await {
             Ok(Some(staff_details)) => {
                 Ok(ApiResponse::success(staff_details))
             }
             Ok(None) => {
-                Err("Staff not found".to_string())
+                Err(AppError::NotFound("Staff not found".to_string()))
             }
             Err(err) => {
-                Err(format!("Database error fetching staff details: {}", err))
+                Err(AppError::Database(format!("Database error fetching staff details: {}", err)))
             }
         }
     }
 
     // View staff accounts
-    pub async fn view_staff_accounts(state: &AppState) -> Result<ApiResponse<Vec<Model>>, String> {
-        match staff::Entity::find().order_by_asc(staff::Column::Name).all(&state.db).await { // Order by staff name for better readability
+    pub async fn view_staff_accounts(state: &AppState) -> Result<ApiResponse<Vec<Model>>, AppError> {
+        match staff::Entity::find().filter(staff::Column::DeletedAt.is_null()).order_by_asc(staff::Column::Name).all(&state.db).await { // Order by staff name for better readability
             Ok(staff_list) => Ok(ApiResponse::success(staff_list)),
-            Err(err) => Err(format!("Error fetching staff accounts: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error fetching staff accounts: {}", err))),
         }
     }
 
-    pub async fn view_ride_staffs(state: &AppState) -> Result<ApiResponse<Vec<Model>>, String> {
+    pub async fn view_ride_staffs(state: &AppState) -> Result<ApiResponse<Vec<Model>>, AppError> {
         match staff::Entity::find()
             .filter(staff::Column::Role.eq("RideStaff")) // Filter by role "RideStaff"
+            .filter(staff::Column::DeletedAt.is_null())
             .order_by_asc(staff::Column::Name)
             .all(&state.db)
             .await
         {
             Ok(staff_list) => Ok(ApiResponse::success(staff_list)),
-            Err(err) => Err(format!("Error fetching RideStaff accounts: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error fetching RideStaff accounts: {}", err))),
         }
     }
 
-    pub async fn view_maintenance_staffs(state: &AppState) -> Result<ApiResponse<Vec<Model>>, String> {
+    pub async fn view_maintenance_staffs(state: &AppState) -> Result<ApiResponse<Vec<Model>>, AppError> {
         match staff::Entity::find()
             .filter(staff::Column::Role.eq("MaintenanceStaff")) // Filter by role "MaintenanceStaff"
+            .filter(staff::Column::DeletedAt.is_null())
             .order_by_asc(staff::Column::Name)
             .all(&state.db)
             .await
         {
             Ok(staff_list) => Ok(ApiResponse::success(staff_list)),
-            Err(err) => Err(format!("Error fetching MaintenanceStaff accounts: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error fetching MaintenanceStaff accounts: {}", err))),
         }
     }
 
@@ -119,7 +382,27 @@ impl StaffHandler {
         password: String,
         name: String,
         role: String,
-    ) -> Result<ApiResponse<String>, String> {
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let mut input = Validator::new();
+        input
+            .field("email", &email, &[validation::email])
+            .field("name", &name, &[validation::name]);
+        Self::validate_role(&mut input, &role);
+        PasswordPolicy::from_env().validate(&mut input, "password", &password);
+        if input.is_valid("email") {
+            let taken = Self::email_taken(state, &email, None).await?;
+            input.check("email", !taken, "Email is already used by another staff account");
+        }
+        input.finish()?;
+
+        // Accounts set up by an executive get a password only the executive knows, so it must be rotated
+        let created_by_executive = staff::Entity::find_by_id(actor_id.clone())
+            .one(&state.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching staff: {}", err)))?
+            .is_some_and(|actor| Self::division_for_role(&actor.role) == Some("Executive"));
+
         // Generate a UUID for the staff_id
         let staff_id = Uuid::new_v4().to_string();
 
@@ -128,20 +411,28 @@ impl StaffHandler {
         match hash(password, DEFAULT_COST) {
             Ok(password_hash) => {
                 let new_staff = staff::ActiveModel {
-                    staff_id: sea_orm::ActiveValue::Set(staff_id),
+                    staff_id: sea_orm::ActiveValue::Set(staff_id.clone()),
                     email: sea_orm::ActiveValue::Set(email),
                     password_hash: sea_orm::ActiveValue::Set(password_hash), // Store the hashed password
                     name: sea_orm::ActiveValue::Set(name),
-                    role: sea_orm::ActiveValue::Set(role),
+                    role: sea_orm::ActiveValue::Set(role.clone()),
+                    must_change_password: sea_orm::ActiveValue::Set(created_by_executive),
                     ..Default::default()
                 };
 
-                match staff::Entity::insert(new_staff).exec(&state.db).await {
-                    Ok(_) => Ok(ApiResponse::success("Staff account created successfully".to_string())),
-                    Err(err) => Err(format!("Error creating staff account: {}", err)),
+                let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+                match staff::Entity::insert(new_staff).exec_with_returning(&txn).await {
+                    Ok(created) => {
+                        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_CREATE, "staff", &staff_id, None, AuditEventHandler::snapshot(&created)).await?;
+                        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+                        // Join the group chat of the new staff member's division
+                        ChatHandler::sync_staff_division_membership(state, &staff_id, &role).await?;
+                        Ok(ApiResponse::success("Staff account created successfully".to_string()))
+                    }
+                    Err(err) => Err(AppError::Database(format!("Error creating staff account: {}", err))),
                 }
             }
-            Err(err) => Err(format!("Password hashing failed: {}", err)), // Hashing error
+            Err(err) => Err(AppError::Internal(format!("Password hashing failed: {}", err))), // Hashing error
         }
     }
 
@@ -152,13 +443,32 @@ impl StaffHandler {
         email: Option<String>,
         name: Option<String>,
         role: Option<String>,
-    ) -> Result<ApiResponse<String>, String> {
-        let staff_member = match staff::Entity::find_by_id(staff_id).one(&state.db).await {
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        let mut input = Validator::new();
+        input
+            .optional("email", email.as_deref(), &[validation::email])
+            .optional("name", name.as_deref(), &[validation::name]);
+        if let Some(role) = &role {
+            Self::validate_role(&mut input, role);
+        }
+        if let (Some(email), true) = (&email, input.is_valid("email")) {
+            let taken = Self::email_taken(state, email, Some(&staff_id)).await?;
+            input.check("email", !taken, "Email is already used by another staff account");
+        }
+        input.finish()?;
+
+        let staff_member = match staff::Entity::find_by_id(staff_id.clone()).one(&state.db).await {
             Ok(Some(staff_member)) => staff_member,
-            Ok(None) => return Err("Staff account not found".to_string()),
-            Err(err) => return Err(format!("Error fetching staff account: {}", err)),
+            Ok(None) => return Err(AppError::NotFound("Staff account not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching staff account: {}", err))),
         };
+        if staff_member.deleted_at.is_some() {
+            return Err(AppError::Conflict("Staff account is archived; restore it before editing".to_string()));
+        }
 
+        let role_changed = role.as_ref().is_some_and(|new_role| *new_role != staff_member.role);
+        let before = AuditEventHandler::snapshot(&staff_member);
         let mut active_staff_member: staff::ActiveModel = staff_member.into();
 
         if let Some(new_email) = email {
@@ -170,23 +480,188 @@ impl StaffHandler {
         if let Some(new_role) = role {
             active_staff_member.role = sea_orm::ActiveValue::Set(new_role);
         }
-        match active_staff_member.update(&state.db).await {
-            Ok(_) => Ok(ApiResponse::success("Staff account updated successfully".to_string())),
-            Err(err) => Err(format!("Error updating staff account: {}", err)),
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        match active_staff_member.update(&txn).await {
+            Ok(updated_staff_member) => {
+                AuditEventHandler::record(&txn, Some(&actor_id), ACTION_UPDATE, "staff", &staff_id, before, AuditEventHandler::snapshot(&updated_staff_member)).await?;
+                txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+                // Move the staff member to their new division's group chat
+                if role_changed {
+                    ChatHandler::sync_staff_division_membership(state, &staff_id, &updated_staff_member.role).await?;
+                }
+                Ok(ApiResponse::success("Staff account updated successfully".to_string()))
+            }
+            Err(err) => Err(AppError::Database(format!("Error updating staff account: {}", err))),
         }
     }
 
-    // Delete staff data
-    pub async fn delete_staff_data(state: &AppState, staff_id: String) -> Result<String, String> {
-        match staff::Entity::delete_by_id(staff_id).exec(&state.db).await {
-            Ok(delete_result) => {
-                if delete_result.rows_affected > 0 {
-                    Ok("Staff account deleted successfully".to_string())
-                } else {
-                    Err("Staff account not found".to_string())
-                }
+    // Archive staff account data (soft delete): the row stays so history and reports still resolve it
+    pub async fn delete_staff_data(state: &AppState, staff_id: String, cascade: bool, actor_id: String) -> Result<ApiResponse<DependencyReport>, AppError> {
+        let existing = match staff::Entity::find_by_id(staff_id.clone()).one(&state.db).await {
+            Ok(Some(record)) if record.deleted_at.is_none() => record,
+            Ok(_) => return Err(AppError::NotFound("Staff account not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching staff account: {}", err))),
+        };
+
+        // Dependents are checked and cascaded in the same transaction as the archive itself
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let report = DependencyHandler::check_staff(&txn, &staff_id).await?;
+        if let Some(blocked) = DependencyHandler::blocked(&report, cascade) {
+            return Ok(blocked);
+        }
+        let now = clock::jakarta_now();
+        if !report.is_clear() {
+            DependencyHandler::cascade_staff(&txn, &staff_id, &actor_id).await?;
+        }
+
+        let before = AuditEventHandler::snapshot(&existing);
+        let mut active_staff: staff::ActiveModel = existing.into();
+        active_staff.deleted_at = sea_orm::ActiveValue::Set(Some(now));
+        let archived = active_staff.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error deleting staff account: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_DELETE, "staff", &staff_id, before, AuditEventHandler::snapshot(&archived)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+
+        // No role maps to no division, so this takes them out of every division group chat
+        ChatHandler::sync_staff_division_membership(state, &staff_id, "").await?;
+        if !report.is_clear() {
+            state.cache.invalidate(&[TAG_CUSTOMER_SERVICE_TICKET]).await;
+        }
+        Ok(ApiResponse::Success { data: report, message: Some("Staff account deleted successfully".to_string()) })
+    }
+
+    // Bring an archived staff account back into the lists
+    pub async fn restore_staff_data(state: &AppState, staff_id: String, actor_id: String) -> Result<ApiResponse<String>, AppError> {
+        let existing = match staff::Entity::find_by_id(staff_id.clone()).one(&state.db).await {
+            Ok(Some(record)) => record,
+            Ok(None) => return Err(AppError::NotFound("Staff account not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching staff account: {}", err))),
+        };
+        if existing.deleted_at.is_none() {
+            return Err(AppError::Conflict("Staff account is not archived".to_string()));
+        }
+
+        let before = AuditEventHandler::snapshot(&existing);
+        let mut active_staff: staff::ActiveModel = existing.into();
+        active_staff.deleted_at = sea_orm::ActiveValue::Set(None);
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        match active_staff.update(&txn).await {
+            Ok(restored) => {
+                AuditEventHandler::record(&txn, Some(&actor_id), ACTION_RESTORE, "staff", &staff_id, before, AuditEventHandler::snapshot(&restored)).await?;
+                txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+                ChatHandler::sync_staff_division_membership(state, &staff_id, &restored.role).await?;
+                Ok(ApiResponse::success("Staff account restored successfully".to_string()))
             }
-            Err(err) => Err(format!("Error deleting staff account: {}", err)),
+            Err(err) => Err(AppError::Database(format!("Error restoring staff account: {}", err))),
+        }
+    }
+
+    // View archived staff, most recently archived first
+    pub async fn view_archived_staff(state: &AppState) -> Result<ApiResponse<Vec<Model>>, AppError> {
+        match staff::Entity::find()
+            .filter(staff::Column::DeletedAt.is_not_null())
+            .order_by_desc(staff::Column::DeletedAt)
+            .all(&state.db)
+            .await
+        {
+            Ok(archived) => Ok(ApiResponse::success(archived)),
+            Err(err) => Err(AppError::Database(format!("Error fetching archived staff: {}", err))),
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::test_support::{self, data, error};
+
+    #[test]
+    fn the_policy_lists_every_rule_a_password_breaks() {
+        let policy = PasswordPolicy { min_length: 10, require_uppercase: true, require_lowercase: true, require_digit: true, require_symbol: true };
+        assert_eq!(
+            policy.violations("short"),
+            ["at least 10 characters", "an uppercase letter", "a digit", "a symbol"]
+        );
+        assert!(policy.violations("Long-enough-1").is_empty());
+
+        let mut input = Validator::new();
+        policy.validate(&mut input, "new_password", "PASSWORD-WITHOUT-LOWERCASE-1");
+        let err = input.finish().unwrap_err();
+        assert_eq!(err.fields()[0].field, "new_password");
+        assert_eq!(err.fields()[0].message, "Password must contain a lowercase letter");
+    }
+
+    #[tokio::test]
+    async fn only_executives_can_issue_reset_tokens() {
+        let state = test_support::test_state().await;
+        let manager = test_support::seed_staff(&state, "RideManager").await;
+        let staff_member = test_support::seed_staff(&state, "RideStaff").await;
+
+        let err = error(StaffHandler::reset_staff_password(&state, manager.staff_id, staff_member.staff_id.clone()).await);
+        assert_eq!(err.code(), ErrorCode::Forbidden);
+        let err = error(StaffHandler::reset_staff_password(&state, "nobody".to_string(), staff_member.staff_id).await);
+        assert_eq!(err.code(), ErrorCode::NotFound);
+    }
+
+    #[tokio::test]
+    async fn a_reset_token_works_once_and_only_with_a_strong_password() {
+        let state = test_support::test_state().await;
+        let executive = test_support::seed_staff(&state, "CEO").await;
+        let staff_member = test_support::seed_staff(&state, "RideStaff").await;
+        let token = data(StaffHandler::reset_staff_password(&state, executive.staff_id, staff_member.staff_id.clone()).await);
+
+        let err = error(StaffHandler::complete_staff_password_reset(&state, staff_member.email.clone(), "guessed".to_string(), "Str0ngPassword".to_string()).await);
+        assert_eq!(err.code(), ErrorCode::Unauthorized);
+        // A weak password is refused without spending the token
+        let err = error(StaffHandler::complete_staff_password_reset(&state, staff_member.email.clone(), token.clone(), "weak".to_string()).await);
+        assert_eq!(err.code(), ErrorCode::ValidationFailed);
+
+        data(StaffHandler::complete_staff_password_reset(&state, staff_member.email.clone(), token.clone(), "Str0ngPassword".to_string()).await);
+        let err = error(StaffHandler::complete_staff_password_reset(&state, staff_member.email.clone(), token, "An0therPassword".to_string()).await);
+        assert_eq!(err.code(), ErrorCode::Unauthorized);
+
+        let login = data(StaffHandler::staff_login(&state, staff_member.email, "Str0ngPassword".to_string(), None).await);
+        assert_eq!(login.staff_id, staff_member.staff_id);
+        assert!(!login.must_change_password);
+    }
+
+    #[tokio::test]
+    async fn an_expired_reset_token_is_refused() {
+        let state = test_support::test_state().await;
+        let executive = test_support::seed_staff(&state, "CFO").await;
+        let staff_member = test_support::seed_staff(&state, "RideStaff").await;
+        let token = data(StaffHandler::reset_staff_password(&state, executive.staff_id, staff_member.staff_id.clone()).await);
+
+        let issued = staff::Entity::find_by_id(staff_member.staff_id).one(&state.db).await.unwrap().unwrap();
+        let mut expired: staff::ActiveModel = issued.into();
+        expired.reset_token_expires_at = sea_orm::ActiveValue::Set(Some(clock::jakarta_now() - Duration::minutes(1)));
+        expired.update(&state.db).await.unwrap();
+
+        let err = error(StaffHandler::complete_staff_password_reset(&state, staff_member.email, token, "Str0ngPassword".to_string()).await);
+        assert_eq!(err.code(), ErrorCode::Unauthorized);
+    }
+
+    #[tokio::test]
+    async fn changing_a_password_fails_alike_for_unknown_accounts_and_locks_like_login() {
+        let state = test_support::test_state().await;
+        let staff_member = test_support::seed_staff(&state, "RideStaff").await;
+        let mut with_password: staff::ActiveModel = staff_member.clone().into();
+        with_password.password_hash = sea_orm::ActiveValue::Set(hash("Str0ngPassword", 4).unwrap());
+        with_password.update(&state.db).await.unwrap();
+        let change = |staff_id: &str, current: &str| {
+            StaffHandler::change_staff_password(&state, staff_id.to_string(), current.to_string(), "An0therPassword".to_string())
+        };
+
+        let unknown = error(change("nobody", "Str0ngPassword").await);
+        let wrong = error(change(&staff_member.staff_id, "guessed").await);
+        assert_eq!((unknown.code(), unknown.message()), (wrong.code(), wrong.message()));
+
+        for _ in 0..4 {
+            error(change(&staff_member.staff_id, "guessed").await);
         }
+        // Locked now, and the lock is the one login uses
+        assert_eq!(error(change(&staff_member.staff_id, "Str0ngPassword").await).code(), ErrorCode::RateLimited);
+        let err = error(StaffHandler::staff_login(&state, staff_member.email, "Str0ngPassword".to_string(), None).await);
+        assert_eq!(err.code(), ErrorCode::RateLimited);
     }
-}
\ No newline at end of file
+}
@@ -1,34 +1,28 @@
-use sea_orm::{ActiveModelTrait, EntityTrait, QueryOrder, ColumnTrait};
-use entity::store::{self, ActiveModel, Model};
+use sea_orm::{ActiveModelTrait, EntityTrait, QueryFilter, QueryOrder, ColumnTrait, TransactionTrait};
+use entity::souvenir;
+use entity::store::{self, Model};
 use uuid::Uuid;
-use crate::{cache_delete, cache_get, cache_set, ApiResponse, AppState};
+use entity::audit_event::{ACTION_CREATE, ACTION_DELETE, ACTION_RESTORE, ACTION_UPDATE};
+use crate::clock;
+use crate::controllers::audit_event_handler::AuditEventHandler;
+use crate::controllers::dependency_handler::{DependencyHandler, DependencyReport};
+use crate::cache::CachedView;
+use crate::repository::CachedRepository;
+use crate::error::AppError;
+use crate::validation::{self, Validator};
+use crate::{ApiResponse, AppState};
 
 pub struct StoreHandler;
 
 impl StoreHandler {
     // View all stores
-    pub async fn view_stores(state: &AppState) -> Result<ApiResponse<Vec<Model>>, String> {
-        let cache_key = "view_stores_cache";
-
-        // Check Redis cache first
-        if let Some(cached_stores) = cache_get::<Vec<Model>>(&state.redis_pool, cache_key).await {
-            println!("Cache hit: Returning stores from Redis");
-            return Ok(ApiResponse::success(cached_stores));
-        }
-
-        // Cache miss: Query the database
-        println!("Cache miss: Querying database");
-        match store::Entity::find()
-            .order_by_asc(store::Column::Name)
-            .all(&state.db)
-            .await
-        {
-            Ok(stores) => {
-                // Cache the result for 60 seconds
-                cache_set(&state.redis_pool, cache_key, &stores, 60).await;
-                Ok(ApiResponse::success(stores))
-            }
-            Err(err) => Ok(ApiResponse::error(format!("Error fetching stores: {}", err))),
+    pub async fn view_stores(state: &AppState) -> Result<ApiResponse<Vec<Model>>, AppError> {
+        let query = store::Entity::find()
+            .filter(store::Column::DeletedAt.is_null())
+            .order_by_asc(store::Column::Name);
+        match CachedRepository::<store::Entity>::new(state).find_all(CachedView::Stores, query).await {
+            Ok(stores) => Ok(ApiResponse::success(stores)),
+            Err(err) => Err(AppError::Database(format!("Error fetching stores: {}", err))),
         }
     }
 
@@ -36,15 +30,16 @@ impl StoreHandler {
     pub async fn get_store_details(
         state: &AppState,
         store_id: String,
-    ) -> Result<ApiResponse<Model>, String> {
+    ) -> Result<ApiResponse<Model>, AppError> {
         match store::Entity::find_by_id(store_id.clone()).one(&state.db).await {
             Ok(Some(store_details)) => Ok(ApiResponse::success(store_details)),
-            Ok(None) => Err("Store not found".to_string()),
-            Err(err) => Err(format!("Database error fetching store details: {}", err)),
+            Ok(None) => Err(AppError::NotFound("Store not found".to_string())),
+            Err(err) => Err(AppError::Database(format!("Database error fetching store details: {}", err))),
         }
     }
 
     // Save store data (create new store)
+    #[allow(clippy::too_many_arguments)]
     pub async fn save_store_data(
         state: &AppState,
         name: String,
@@ -53,10 +48,16 @@ impl StoreHandler {
         closing_time: String,
         location: Option<String>,
         status: String,
-    ) -> Result<ApiResponse<String>, String> {
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new()
+            .field("name", &name, &[validation::name])
+            .opening_hours(Some(&opening_time), Some(&closing_time))
+            .finish()?;
+
         let store_id = Uuid::new_v4().to_string();
-        let opening_time_parsed = chrono::NaiveTime::parse_from_str(&opening_time, "%H:%M:%S").map_err(|e| format!("Invalid opening_time format: {}", e))?;
-        let closing_time_parsed = chrono::NaiveTime::parse_from_str(&closing_time, "%H:%M:%S").map_err(|e| format!("Invalid closing_time format: {}", e))?;
+        let opening_time_parsed = validation::parse_time(&opening_time).unwrap_or_default(); // Checked above
+        let closing_time_parsed = validation::parse_time(&closing_time).unwrap_or_default();
 
 
         let new_store = store::ActiveModel {
@@ -70,13 +71,13 @@ impl StoreHandler {
             ..Default::default()
         };
 
-        match store::Entity::insert(new_store).exec(&state.db).await {
-            Ok(_) => {
-                cache_delete(&state.redis_pool, "view_stores_cache").await;
-                Ok(ApiResponse::success("Store created successfully".to_string()))
-            }
-            Err(err) => Err(format!("Error creating store: {}", err)),
-        }
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let created = store::Entity::insert(new_store).exec_with_returning(&txn).await
+            .map_err(|err| AppError::Database(format!("Error creating store: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_CREATE, "store", &created.store_id, None, AuditEventHandler::snapshot(&created)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        CachedRepository::<store::Entity>::new(state).invalidate().await;
+        Ok(ApiResponse::success("Store created successfully".to_string()))
     }
 
     // Update store data
@@ -89,13 +90,23 @@ impl StoreHandler {
         closing_time: Option<String>,
         location: Option<Option<String>>,
         status: Option<String>,
-    ) -> Result<ApiResponse<String>, String> {
+        actor_id: String,
+    ) -> Result<ApiResponse<String>, AppError> {
+        Validator::new()
+            .optional("name", name.as_deref(), &[validation::name])
+            .opening_hours(opening_time.as_deref(), closing_time.as_deref())
+            .finish()?;
+
         let store_record = match store::Entity::find_by_id(store_id).one(&state.db).await {
             Ok(Some(store)) => store,
-            Ok(None) => return Err("Store not found".to_string()),
-            Err(err) => return Err(format!("Error fetching store: {}", err)),
+            Ok(None) => return Err(AppError::NotFound("Store not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching store: {}", err))),
         };
+        if store_record.deleted_at.is_some() {
+            return Err(AppError::Conflict("Store is archived; restore it before editing".to_string()));
+        }
 
+        let before = AuditEventHandler::snapshot(&store_record);
         let mut active_store: store::ActiveModel = store_record.into();
 
         if let Some(new_name) = name {
@@ -105,11 +116,11 @@ impl StoreHandler {
             active_store.photo = sea_orm::ActiveValue::Set(new_photo);
         }
         if let Some(opening_time) = opening_time {
-            let opening_time_parsed = chrono::NaiveTime::parse_from_str(&opening_time, "%H:%M:%S").map_err(|e| format!("Invalid opening_time format: {}", e))?;
+            let opening_time_parsed = validation::parse_time(&opening_time).unwrap_or_default();
             active_store.opening_time = sea_orm::ActiveValue::Set(opening_time_parsed);
         }
         if let Some(closing_time) = closing_time {
-            let closing_time_parsed = chrono::NaiveTime::parse_from_str(&closing_time, "%H:%M:%S").map_err(|e| format!("Invalid closing_time format: {}", e))?;
+            let closing_time_parsed = validation::parse_time(&closing_time).unwrap_or_default();
             active_store.closing_time = sea_orm::ActiveValue::Set(closing_time_parsed);
         }
         if let Some(new_location) = location {
@@ -120,27 +131,82 @@ impl StoreHandler {
         }
 
 
-        match active_store.update(&state.db).await {
-            Ok(_) => {
-                cache_delete(&state.redis_pool, "view_stores_cache").await;
-                Ok(ApiResponse::success("Store updated successfully".to_string()))
-            }
-            Err(err) => Err(format!("Error updating store: {}", err)),
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let updated = active_store.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error updating store: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_UPDATE, "store", &updated.store_id, before, AuditEventHandler::snapshot(&updated)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        CachedRepository::<store::Entity>::new(state).invalidate().await;
+        Ok(ApiResponse::success("Store updated successfully".to_string()))
+    }
+
+    // Archive store data (soft delete): the row stays so history and reports still resolve it
+    pub async fn delete_store_data(state: &AppState, store_id: String, cascade: bool, actor_id: String) -> Result<ApiResponse<DependencyReport>, AppError> {
+        let existing = match store::Entity::find_by_id(store_id.clone()).one(&state.db).await {
+            Ok(Some(record)) if record.deleted_at.is_none() => record,
+            Ok(_) => return Err(AppError::NotFound("Store not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching store: {}", err))),
+        };
+
+        // Dependents are checked and cascaded in the same transaction as the archive itself
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let report = DependencyHandler::check_store(&txn, &store_id).await?;
+        if let Some(blocked) = DependencyHandler::blocked(&report, cascade) {
+            return Ok(blocked);
+        }
+        let now = clock::jakarta_now();
+        if !report.is_clear() {
+            DependencyHandler::cascade_store(&txn, &store_id, &actor_id).await?;
+        }
+
+        let before = AuditEventHandler::snapshot(&existing);
+        let mut active_store: store::ActiveModel = existing.into();
+        active_store.deleted_at = sea_orm::ActiveValue::Set(Some(now));
+        let archived = active_store.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error deleting store: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_DELETE, "store", &store_id, before, AuditEventHandler::snapshot(&archived)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+
+        CachedRepository::<store::Entity>::new(state).invalidate().await;
+        if !report.is_clear() {
+            CachedRepository::<souvenir::Entity>::new(state).invalidate().await;
+        }
+        Ok(ApiResponse::Success { data: report, message: Some("Store deleted successfully".to_string()) })
+    }
+
+    // Bring an archived store back into the lists
+    pub async fn restore_store_data(state: &AppState, store_id: String, actor_id: String) -> Result<ApiResponse<String>, AppError> {
+        let existing = match store::Entity::find_by_id(store_id.clone()).one(&state.db).await {
+            Ok(Some(record)) => record,
+            Ok(None) => return Err(AppError::NotFound("Store not found".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching store: {}", err))),
+        };
+        if existing.deleted_at.is_none() {
+            return Err(AppError::Conflict("Store is not archived".to_string()));
         }
+
+        let before = AuditEventHandler::snapshot(&existing);
+        let mut active_store: store::ActiveModel = existing.into();
+        active_store.deleted_at = sea_orm::ActiveValue::Set(None);
+        let txn = state.db.begin().await.map_err(|err| AppError::Database(format!("Error starting transaction: {}", err)))?;
+        let restored = active_store.update(&txn).await
+            .map_err(|err| AppError::Database(format!("Error restoring store: {}", err)))?;
+        AuditEventHandler::record(&txn, Some(&actor_id), ACTION_RESTORE, "store", &store_id, before, AuditEventHandler::snapshot(&restored)).await?;
+        txn.commit().await.map_err(|err| AppError::Database(format!("Error committing transaction: {}", err)))?;
+        CachedRepository::<store::Entity>::new(state).invalidate().await;
+        Ok(ApiResponse::success("Store restored successfully".to_string()))
     }
 
-    // Delete store data
-    pub async fn delete_store_data(state: &AppState, store_id: String) -> Result<String, String> {
-        match store::Entity::delete_by_id(store_id).exec(&state.db).await {
-            Ok(delete_result) => {
-                if delete_result.rows_affected > 0 {
-                    cache_delete(&state.redis_pool, "view_stores_cache").await;
-                    Ok("Store deleted successfully".to_string())
-                } else {
-                    Err("Store not found".to_string())
-                }
-            }
-            Err(err) => Err(format!("Error deleting store: {}", err)),
+    // View archived stores, most recently archived first
+    pub async fn view_archived_stores(state: &AppState) -> Result<ApiResponse<Vec<Model>>, AppError> {
+        match store::Entity::find()
+            .filter(store::Column::DeletedAt.is_not_null())
+            .order_by_desc(store::Column::DeletedAt)
+            .all(&state.db)
+            .await
+        {
+            Ok(archived) => Ok(ApiResponse::success(archived)),
+            Err(err) => Err(AppError::Database(format!("Error fetching archived stores: {}", err))),
         }
     }
 }
\ No newline at end of file
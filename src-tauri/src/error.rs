@@ -0,0 +1,167 @@
+use sea_orm::DbErr;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+// Stable identifiers the frontend branches on; never rename a variant, only add new ones
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    NotFound,
+    ValidationFailed,
+    Conflict,
+    Unauthorized,
+    Forbidden,
+    RateLimited,
+    DatabaseError,
+    InternalError,
+}
+
+impl ErrorCode {
+    // Status the HTTP API answers with; the envelope body is the same as over Tauri
+    pub fn http_status(self) -> u16 {
+        match self {
+            ErrorCode::NotFound => 404,
+            ErrorCode::ValidationFailed => 422,
+            ErrorCode::Conflict => 409,
+            ErrorCode::Unauthorized => 401,
+            ErrorCode::Forbidden => 403,
+            ErrorCode::RateLimited => 429,
+            ErrorCode::DatabaseError | ErrorCode::InternalError => 500,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: impl Into<String>) -> Self {
+        FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppError {
+    NotFound(String),
+    Validation { message: String, fields: Vec<FieldError> },
+    Conflict(String),      // The record is in a state that does not allow the change
+    Unauthorized(String),  // Bad or missing credentials
+    Forbidden(String),     // Known caller without the required role
+    RateLimited(String),
+    Database(String),
+    Internal(String),
+}
+
+impl AppError {
+    pub fn validation(message: impl Into<String>) -> Self {
+        AppError::Validation {
+            message: message.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    // A validation error pinned to a single input field
+    pub fn invalid_field(field: &str, message: impl Into<String>) -> Self {
+        let message = message.into();
+        AppError::Validation {
+            fields: vec![FieldError::new(field, message.clone())],
+            message,
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::NotFound(_) => ErrorCode::NotFound,
+            AppError::Validation { .. } => ErrorCode::ValidationFailed,
+            AppError::Conflict(_) => ErrorCode::Conflict,
+            AppError::Unauthorized(_) => ErrorCode::Unauthorized,
+            AppError::Forbidden(_) => ErrorCode::Forbidden,
+            AppError::RateLimited(_) => ErrorCode::RateLimited,
+            AppError::Database(_) => ErrorCode::DatabaseError,
+            AppError::Internal(_) => ErrorCode::InternalError,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::NotFound(message)
+            | AppError::Validation { message, .. }
+            | AppError::Conflict(message)
+            | AppError::Unauthorized(message)
+            | AppError::Forbidden(message)
+            | AppError::RateLimited(message)
+            | AppError::Database(message)
+            | AppError::Internal(message) => message,
+        }
+    }
+
+    pub fn fields(&self) -> &[FieldError] {
+        match self {
+            AppError::Validation { fields, .. } => fields,
+            _ => &[],
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<DbErr> for AppError {
+    fn from(err: DbErr) -> Self {
+        match err {
+            DbErr::RecordNotFound(message) => AppError::NotFound(message),
+            err => AppError::Database(err.to_string()),
+        }
+    }
+}
+
+// Serialized as { code, message, fields } and flattened into ApiResponse::Error
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("AppError", 3)?;
+        error.serialize_field("code", &self.code())?;
+        error.serialize_field("message", self.message())?;
+        error.serialize_field("fields", self.fields())?;
+        error.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ApiResponse;
+
+    #[test]
+    fn error_envelope_carries_code_message_and_fields() {
+        let response: ApiResponse<String> = AppError::invalid_field("pin", "PIN must be 4 to 6 digits").into();
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            serde_json::json!({
+                "status": "error",
+                "data": null,
+                "code": "VALIDATION_FAILED",
+                "message": "PIN must be 4 to 6 digits",
+                "fields": [{ "field": "pin", "message": "PIN must be 4 to 6 digits" }],
+            })
+        );
+    }
+
+    #[test]
+    fn missing_records_map_to_not_found() {
+        let error = AppError::from(DbErr::RecordNotFound("Ride not found".to_string()));
+        assert_eq!(error.code(), ErrorCode::NotFound);
+        assert_eq!(AppError::from(DbErr::Custom("boom".to_string())).code(), ErrorCode::DatabaseError);
+    }
+}
@@ -0,0 +1,1721 @@
+// Headless HTTP/JSON API (the `http-api` feature). It exposes the same handlers as the Tauri
+// commands in lib.rs as REST endpoints under /api, for clients that run without the desktop
+// shell: kiosks, the ticket gate, reporting scripts and integration tests.
+//
+// Auth works like the desktop app: a client proves it is trusted (here with the HTTP_API_TOKEN
+// bearer token), and the person acting through it signs in with /customers/login or /staff/login.
+// The session token those return goes in the X-Session-Token header; the actor passed on where
+// the commands take actor_id, requester_id or staff_id is whoever that session belongs to.
+// Handlers do their own role checks, so those apply unchanged.
+//
+// Top-ups, souvenir orders and ride queue joins take a client-generated Idempotency-Key header;
+// a retry with the same key gets the first answer instead of charging again.
+//
+// Responses carry the same { status, data, message, code, fields } envelope as the commands;
+// errors also set the HTTP status for their code (see ErrorCode::http_status).
+
+use axum::extract::{FromRequest, FromRequestParts, Path, Request, State};
+use axum::http::request::Parts;
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, patch, post, put};
+use axum::{Json, Router};
+use chrono::NaiveDateTime;
+use dotenv::dotenv;
+use entity::{
+    audit_event, broadcast_message, chat, chat_member, customer, customer_service_ticket, lost_and_found_items_log,
+    lost_and_found_log_event, maintenance_schedule, menu_item, message, message_edit, offline_sync_conflict, order_restaurant,
+    order_souvenir, restaurant, ride, ride_queue, souvenir, staff, store,
+};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::convert::Infallible;
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::controllers::audit_event_handler::AuditEventHandler;
+use crate::controllers::broadcast_message_handler::{BroadcastDeliveryStats, BroadcastMessageHandler, RecipientBroadcast};
+use crate::controllers::chat_handler::{ChatHandler, ChatWithCustomerName, MessageSearchResult, MessageWithSenderName, NewAttachment};
+use crate::controllers::customer_handler::CustomerHandler;
+use crate::controllers::customer_service_ticket_handler::{CustomerServiceMetrics, CustomerServiceTicketHandler, QueuedTicket};
+use crate::controllers::dependency_handler::{DependencyHandler, DependencyReport};
+use crate::controllers::health_handler::{HealthHandler, HealthReport};
+use crate::controllers::income_report_handler::{IncomeReport, IncomeReportHandler};
+use crate::controllers::lost_and_found_items_log_handler::{LostAndFoundItemsLogHandler, LostAndFoundMatch, OwnerVerification};
+use crate::controllers::maintenance_schedule_handler::MaintenanceScheduleHandler;
+use crate::controllers::menu_item_handler::MenuItemHandler;
+use crate::controllers::offline_sync_handler::OfflineSyncHandler;
+use crate::controllers::order_restaurant_handler::OrderRestaurantHandler;
+use crate::controllers::order_souvenir_handler::OrderSouvenirHandler;
+use crate::controllers::restaurant_handler::RestaurantHandler;
+use crate::controllers::ride_handler::RideHandler;
+use crate::controllers::ride_queue_handler::RideQueueHandler;
+use crate::controllers::souvenir_handler::SouvenirHandler;
+use crate::controllers::staff_handler::{PasswordPolicy, StaffHandler, StaffLoginResult};
+use crate::controllers::store_handler::StoreHandler;
+use crate::error::AppError;
+use crate::session::Session;
+use crate::{
+    build_state, send_due_broadcasts, ApiResponse, AppState, BROADCAST_DISPATCH_INTERVAL_SECS, DIVISION_SYNC_RETRY_SECS,
+    LOST_AND_FOUND_RETENTION_INTERVAL_SECS,
+};
+
+pub const SESSION_HEADER: &str = "x-session-token";
+pub const IDEMPOTENCY_HEADER: &str = "idempotency-key";
+const DEFAULT_HTTP_API_ADDR: &str = "127.0.0.1:8080";
+
+type Shared = State<Arc<AppState>>;
+type Reply<T> = Result<ApiResponse<T>, AppError>;
+
+impl<T: Serialize> IntoResponse for ApiResponse<T> {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            ApiResponse::Success { .. } => StatusCode::OK,
+            ApiResponse::Queued { .. } => StatusCode::ACCEPTED,
+            ApiResponse::Error { error, .. } => {
+                StatusCode::from_u16(error.code().http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        ApiResponse::<()>::error(self).into_response()
+    }
+}
+
+// Malformed bodies and query strings get the usual envelope instead of axum's plain-text errors
+pub struct Rejection(AppError);
+
+impl From<axum::extract::rejection::JsonRejection> for Rejection {
+    fn from(rejection: axum::extract::rejection::JsonRejection) -> Self {
+        Rejection(AppError::validation(rejection.body_text()))
+    }
+}
+
+impl From<axum::extract::rejection::QueryRejection> for Rejection {
+    fn from(rejection: axum::extract::rejection::QueryRejection) -> Self {
+        Rejection(AppError::validation(rejection.body_text()))
+    }
+}
+
+impl IntoResponse for Rejection {
+    fn into_response(self) -> Response {
+        self.0.into_response()
+    }
+}
+
+#[derive(FromRequest)]
+#[from_request(via(axum::Json), rejection(Rejection))]
+struct Body<T>(T);
+
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(Rejection))]
+struct Params<T>(T);
+
+// Who is acting through the client: the owner of the session in the X-Session-Token header.
+// Requests without a valid session are refused before the handler runs.
+struct Actor(String);
+
+impl FromRequestParts<Arc<AppState>> for Actor {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let session_token = parts
+            .headers
+            .get(SESSION_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .unwrap_or_default();
+        Session::actor(&state.db, session_token).await.map(Actor)
+    }
+}
+
+// The session token itself, for signing out
+struct SessionToken(String);
+
+impl<S: Send + Sync> FromRequestParts<S> for SessionToken {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .headers
+            .get(SESSION_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| SessionToken(value.trim().to_string()))
+            .filter(|SessionToken(value)| !value.is_empty())
+            .ok_or_else(|| AppError::Unauthorized("Sign in to continue".to_string()))
+    }
+}
+
+// The client's key for a payment-affecting request, from the Idempotency-Key header
+struct IdempotencyKey(Option<String>);
+
+impl<S: Send + Sync> FromRequestParts<S> for IdempotencyKey {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let idempotency_key = parts
+            .headers
+            .get(IDEMPOTENCY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+        Ok(IdempotencyKey(idempotency_key))
+    }
+}
+
+// Tells a field left out of a PATCH body (keep the value) from an explicit null (clear it)
+fn nullable<'de, D: Deserializer<'de>, T: Deserialize<'de>>(deserializer: D) -> Result<Option<Option<T>>, D::Error> {
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+// Compared in constant time so the token cannot be guessed byte by byte from response times
+fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len() && given.bytes().zip(expected.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+async fn require_api_token(State(api_token): State<Arc<str>>, request: Request, next: Next) -> Response {
+    let given = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match given {
+        Some(given) if token_matches(given, &api_token) => next.run(request).await,
+        _ => AppError::Unauthorized("A valid API token is required".to_string()).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct CascadeParams {
+    cascade: Option<bool>,
+}
+
+// Health (no token, so load balancers and monitors can probe it)
+
+async fn health_check(State(state): Shared) -> Reply<HealthReport> {
+    HealthHandler::health_check(&state).await
+}
+
+// Customers
+
+#[derive(Deserialize)]
+struct CustomerLogin {
+    customer_id: String,
+    pin: String,
+    device_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NewCustomer {
+    name: String,
+    virtual_balance: String,
+    pin: String,
+}
+
+#[derive(Deserialize)]
+struct CustomerChanges {
+    name: Option<String>,
+    virtual_balance: Option<String>,
+    pin: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TopUp {
+    amount: String,
+}
+
+#[derive(Deserialize)]
+struct RestaurantFilter {
+    restaurant_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StoreFilter {
+    store_id: Option<String>,
+}
+
+async fn customer_login(State(state): Shared, Body(login): Body<CustomerLogin>) -> Reply<String> {
+    CustomerHandler::customer_login(&state, login.customer_id, login.pin, login.device_id).await
+}
+
+async fn view_customer_accounts(State(state): Shared) -> Reply<Vec<customer::Model>> {
+    CustomerHandler::view_customer_accounts(&state).await
+}
+
+async fn view_archived_customers(State(state): Shared) -> Reply<Vec<customer::Model>> {
+    CustomerHandler::view_archived_customers(&state).await
+}
+
+async fn get_customer_details(State(state): Shared, Path(customer_id): Path<String>) -> Reply<customer::Model> {
+    CustomerHandler::get_customer_details(&state, customer_id).await
+}
+
+async fn save_customer_data(State(state): Shared, Actor(actor_id): Actor, Body(new): Body<NewCustomer>) -> Reply<String> {
+    CustomerHandler::save_customer_data(&state, new.name, new.virtual_balance, new.pin, actor_id).await
+}
+
+async fn update_customer_data(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(customer_id): Path<String>,
+    Body(changes): Body<CustomerChanges>,
+) -> Reply<String> {
+    CustomerHandler::update_customer_data(&state, customer_id, changes.name, changes.virtual_balance, changes.pin, actor_id).await
+}
+
+async fn top_up_virtual_balance(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    IdempotencyKey(idempotency_key): IdempotencyKey,
+    Path(customer_id): Path<String>,
+    Body(top_up): Body<TopUp>,
+) -> Reply<String> {
+    CustomerHandler::top_up_virtual_balance(&state, customer_id, top_up.amount, actor_id, idempotency_key).await
+}
+
+async fn delete_customer_data(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(customer_id): Path<String>,
+    Params(params): Params<CascadeParams>,
+) -> Reply<DependencyReport> {
+    CustomerHandler::delete_customer_data(&state, customer_id, params.cascade.unwrap_or(false), actor_id).await
+}
+
+async fn restore_customer_data(State(state): Shared, Actor(actor_id): Actor, Path(customer_id): Path<String>) -> Reply<String> {
+    CustomerHandler::restore_customer_data(&state, customer_id, actor_id).await
+}
+
+async fn view_order_restaurants_by_customer(
+    State(state): Shared,
+    Path(customer_id): Path<String>,
+    Params(filter): Params<RestaurantFilter>,
+) -> Reply<Vec<order_restaurant::Model>> {
+    let restaurant_id = filter.restaurant_id.ok_or_else(|| AppError::invalid_field("restaurant_id", "Restaurant id is required"))?;
+    OrderRestaurantHandler::view_order_restaurants_by_customer(&state, customer_id, restaurant_id).await
+}
+
+async fn view_order_souvenirs_by_customer(
+    State(state): Shared,
+    Path(customer_id): Path<String>,
+    Params(filter): Params<StoreFilter>,
+) -> Reply<Vec<order_souvenir::Model>> {
+    let store_id = filter.store_id.ok_or_else(|| AppError::invalid_field("store_id", "Store id is required"))?;
+    OrderSouvenirHandler::view_order_souvenirs_by_customer(&state, customer_id, store_id).await
+}
+
+async fn get_customer_service_chat(State(state): Shared, Path(customer_id): Path<String>) -> Reply<chat::Model> {
+    ChatHandler::get_customer_service_chat(&state, customer_id).await
+}
+
+// Staff
+
+#[derive(Deserialize)]
+struct StaffLogin {
+    email: String,
+    password: String,
+    device_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PasswordChange {
+    current_password: String,
+    new_password: String,
+}
+
+#[derive(Deserialize)]
+struct PasswordReset {
+    email: String,
+    token: String,
+    new_password: String,
+}
+
+#[derive(Deserialize)]
+struct EmailParams {
+    email: String,
+}
+
+#[derive(Deserialize)]
+struct NewStaff {
+    email: String,
+    password: String,
+    name: String,
+    role: String,
+}
+
+#[derive(Deserialize)]
+struct StaffChanges {
+    email: Option<String>,
+    name: Option<String>,
+    role: Option<String>,
+}
+
+async fn staff_login(State(state): Shared, Body(login): Body<StaffLogin>) -> Reply<StaffLoginResult> {
+    StaffHandler::staff_login(&state, login.email, login.password, login.device_id).await
+}
+
+async fn logout(State(state): Shared, SessionToken(session_token): SessionToken) -> Reply<String> {
+    Session::close(&state.db, &session_token).await?;
+    Ok(ApiResponse::success("Signed out".to_string()))
+}
+
+async fn view_password_policy() -> ApiResponse<PasswordPolicy> {
+    StaffHandler::view_password_policy()
+}
+
+async fn complete_staff_password_reset(State(state): Shared, Body(reset): Body<PasswordReset>) -> Reply<String> {
+    StaffHandler::complete_staff_password_reset(&state, reset.email, reset.token, reset.new_password).await
+}
+
+async fn change_staff_password(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(staff_id): Path<String>,
+    Body(change): Body<PasswordChange>,
+) -> Reply<String> {
+    // Staff change their own password; executives reset other people's
+    if actor_id != staff_id {
+        return Err(AppError::Forbidden("You can only change your own password".to_string()));
+    }
+    StaffHandler::change_staff_password(&state, staff_id, change.current_password, change.new_password).await
+}
+
+async fn reset_staff_password(State(state): Shared, Actor(actor_id): Actor, Path(staff_id): Path<String>) -> Reply<String> {
+    StaffHandler::reset_staff_password(&state, actor_id, staff_id).await
+}
+
+async fn view_staff_accounts(State(state): Shared, Actor(_): Actor) -> Reply<Vec<staff::Model>> {
+    StaffHandler::view_staff_accounts(&state).await
+}
+
+async fn view_ride_staffs(State(state): Shared) -> Reply<Vec<staff::Model>> {
+    StaffHandler::view_ride_staffs(&state).await
+}
+
+async fn view_maintenance_staffs(State(state): Shared) -> Reply<Vec<staff::Model>> {
+    StaffHandler::view_maintenance_staffs(&state).await
+}
+
+async fn view_archived_staff(State(state): Shared) -> Reply<Vec<staff::Model>> {
+    StaffHandler::view_archived_staff(&state).await
+}
+
+async fn get_staff_details_by_email(State(state): Shared, Actor(_): Actor, Params(params): Params<EmailParams>) -> Reply<staff::Model> {
+    StaffHandler::get_staff_details_by_email(&state, params.email).await
+}
+
+async fn get_staff_details(State(state): Shared, Actor(_): Actor, Path(staff_id): Path<String>) -> Reply<staff::Model> {
+    StaffHandler::get_staff_details(&state, staff_id).await
+}
+
+async fn save_staff_data(State(state): Shared, Actor(actor_id): Actor, Body(new): Body<NewStaff>) -> Reply<String> {
+    StaffHandler::save_staff_data(&state, new.email, new.password, new.name, new.role, actor_id).await
+}
+
+async fn update_staff_data(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(staff_id): Path<String>,
+    Body(changes): Body<StaffChanges>,
+) -> Reply<String> {
+    StaffHandler::update_staff_data(&state, staff_id, changes.email, changes.name, changes.role, actor_id).await
+}
+
+async fn delete_staff_data(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(staff_id): Path<String>,
+    Params(params): Params<CascadeParams>,
+) -> Reply<DependencyReport> {
+    StaffHandler::delete_staff_data(&state, staff_id, params.cascade.unwrap_or(false), actor_id).await
+}
+
+async fn restore_staff_data(State(state): Shared, Actor(actor_id): Actor, Path(staff_id): Path<String>) -> Reply<String> {
+    StaffHandler::restore_staff_data(&state, staff_id, actor_id).await
+}
+
+async fn view_maintenance_schedule_by_staff(State(state): Shared, Path(staff_id): Path<String>) -> Reply<Vec<maintenance_schedule::Model>> {
+    MaintenanceScheduleHandler::view_maintenance_schedule_by_staff(&state, staff_id).await
+}
+
+async fn view_assigned_customer_service_tickets(
+    State(state): Shared,
+    Path(staff_id): Path<String>,
+) -> Reply<Vec<customer_service_ticket::Model>> {
+    CustomerServiceTicketHandler::view_assigned_tickets(&state, staff_id).await
+}
+
+async fn get_direct_chat(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path((staff_id, other_staff_id)): Path<(String, String)>,
+) -> Reply<chat::Model> {
+    if actor_id != staff_id {
+        return Err(AppError::Forbidden("You can only open your own direct chats".to_string()));
+    }
+    ChatHandler::get_direct_chat(&state, staff_id, other_staff_id).await
+}
+
+// Dependencies and audit trail
+
+#[derive(Deserialize)]
+struct AuditFilter {
+    actor_id: Option<String>,
+    entity_type: Option<String>,
+    entity_id: Option<String>,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+    limit: Option<u64>,
+}
+
+async fn check_delete_dependencies(
+    State(state): Shared,
+    Path((entity_type, entity_id)): Path<(String, String)>,
+) -> Reply<DependencyReport> {
+    DependencyHandler::check_dependencies(&state, entity_type, entity_id).await
+}
+
+async fn view_audit_events(State(state): Shared, Actor(actor_id): Actor, Params(filter): Params<AuditFilter>) -> Reply<Vec<audit_event::Model>> {
+    AuditEventHandler::view_audit_events(
+        &state,
+        actor_id,
+        filter.actor_id,
+        filter.entity_type,
+        filter.entity_id,
+        filter.from,
+        filter.to,
+        filter.limit,
+    )
+    .await
+}
+
+// Restaurants and menu items
+
+#[derive(Deserialize)]
+struct NewRestaurant {
+    name: String,
+    photo: Option<String>,
+    opening_time: String,
+    closing_time: String,
+    cuisine_type: String,
+    location: Option<String>,
+    status: String,
+}
+
+#[derive(Deserialize)]
+struct RestaurantChanges {
+    name: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    photo: Option<Option<String>>,
+    opening_time: Option<String>,
+    closing_time: Option<String>,
+    cuisine_type: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    location: Option<Option<String>>,
+    status: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NewMenuItem {
+    photo: Option<String>,
+    name: String,
+    price: String,
+    restaurant_id: String,
+}
+
+#[derive(Deserialize)]
+struct MenuItemChanges {
+    #[serde(default, deserialize_with = "nullable")]
+    photo: Option<Option<String>>,
+    name: Option<String>,
+    price: Option<String>,
+    restaurant_id: Option<String>,
+}
+
+async fn view_restaurants(State(state): Shared) -> Reply<Vec<restaurant::Model>> {
+    RestaurantHandler::view_restaurants(&state).await
+}
+
+async fn view_archived_restaurants(State(state): Shared) -> Reply<Vec<restaurant::Model>> {
+    RestaurantHandler::view_archived_restaurants(&state).await
+}
+
+async fn get_restaurant_details(State(state): Shared, Path(restaurant_id): Path<String>) -> Reply<restaurant::Model> {
+    RestaurantHandler::get_restaurant_details(&state, restaurant_id).await
+}
+
+async fn save_restaurant_data(State(state): Shared, Actor(actor_id): Actor, Body(new): Body<NewRestaurant>) -> Reply<String> {
+    RestaurantHandler::save_restaurant_data(
+        &state, new.name, new.photo, new.opening_time, new.closing_time, new.cuisine_type, new.location, new.status, actor_id,
+    )
+    .await
+}
+
+async fn update_restaurant_data(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(restaurant_id): Path<String>,
+    Body(changes): Body<RestaurantChanges>,
+) -> Reply<String> {
+    RestaurantHandler::update_restaurant_data(
+        &state,
+        restaurant_id,
+        changes.name,
+        changes.photo,
+        changes.opening_time,
+        changes.closing_time,
+        changes.cuisine_type,
+        changes.location,
+        changes.status,
+        actor_id,
+    )
+    .await
+}
+
+async fn delete_restaurant_data(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(restaurant_id): Path<String>,
+    Params(params): Params<CascadeParams>,
+) -> Reply<DependencyReport> {
+    RestaurantHandler::delete_restaurant_data(&state, restaurant_id, params.cascade.unwrap_or(false), actor_id).await
+}
+
+async fn restore_restaurant_data(State(state): Shared, Actor(actor_id): Actor, Path(restaurant_id): Path<String>) -> Reply<String> {
+    RestaurantHandler::restore_restaurant_data(&state, restaurant_id, actor_id).await
+}
+
+async fn view_menu_items(State(state): Shared, Params(filter): Params<RestaurantFilter>) -> Reply<Vec<menu_item::Model>> {
+    MenuItemHandler::view_menu_items(&state, filter.restaurant_id).await
+}
+
+async fn view_archived_menu_items(State(state): Shared) -> Reply<Vec<menu_item::Model>> {
+    MenuItemHandler::view_archived_menu_items(&state).await
+}
+
+async fn get_menu_item_details(State(state): Shared, Path(menu_item_id): Path<String>) -> Reply<menu_item::Model> {
+    MenuItemHandler::get_menu_item_details(&state, menu_item_id).await
+}
+
+async fn save_menu_item_data(State(state): Shared, Actor(actor_id): Actor, Body(new): Body<NewMenuItem>) -> Reply<String> {
+    MenuItemHandler::save_menu_item_data(&state, new.photo, new.name, new.price, new.restaurant_id, actor_id).await
+}
+
+async fn update_menu_item_data(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(menu_item_id): Path<String>,
+    Body(changes): Body<MenuItemChanges>,
+) -> Reply<String> {
+    MenuItemHandler::update_menu_item_data(&state, menu_item_id, changes.photo, changes.name, changes.price, changes.restaurant_id, actor_id)
+        .await
+}
+
+async fn delete_menu_item_data(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(menu_item_id): Path<String>,
+    Params(params): Params<CascadeParams>,
+) -> Reply<DependencyReport> {
+    MenuItemHandler::delete_menu_item_data(&state, menu_item_id, params.cascade.unwrap_or(false), actor_id).await
+}
+
+async fn restore_menu_item_data(State(state): Shared, Actor(actor_id): Actor, Path(menu_item_id): Path<String>) -> Reply<String> {
+    MenuItemHandler::restore_menu_item_data(&state, menu_item_id, actor_id).await
+}
+
+// Restaurant orders
+
+#[derive(Deserialize)]
+struct NewOrderRestaurant {
+    customer_id: String,
+    restaurant_id: String,
+    menu_item_id: String,
+    quantity: i32,
+}
+
+#[derive(Deserialize)]
+struct StatusChange {
+    status: String,
+}
+
+async fn view_order_restaurants(State(state): Shared, Params(filter): Params<RestaurantFilter>) -> Reply<Vec<order_restaurant::Model>> {
+    OrderRestaurantHandler::view_order_restaurants(&state, filter.restaurant_id).await
+}
+
+async fn save_order_restaurant_data(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    IdempotencyKey(idempotency_key): IdempotencyKey,
+    Body(new): Body<NewOrderRestaurant>,
+) -> Reply<String> {
+    OrderRestaurantHandler::save_order_restaurant_data(&state, new.customer_id, new.restaurant_id, new.menu_item_id, new.quantity, actor_id, idempotency_key)
+        .await
+}
+
+async fn update_order_restaurant_status(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(order_restaurant_id): Path<String>,
+    Body(change): Body<StatusChange>,
+) -> Reply<String> {
+    OrderRestaurantHandler::update_order_restaurant_status(&state, order_restaurant_id, change.status, actor_id).await
+}
+
+async fn delete_order_restaurant_data(State(state): Shared, Actor(actor_id): Actor, Path(order_restaurant_id): Path<String>) -> Reply<String> {
+    OrderRestaurantHandler::delete_order_restaurant_data(&state, order_restaurant_id, actor_id).await
+}
+
+// Rides and ride queues
+
+#[derive(Deserialize)]
+struct NewRide {
+    status: String,
+    name: String,
+    price: String,
+    location: String,
+    staff_id: String,
+    photo: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RideChanges {
+    status: Option<String>,
+    name: Option<String>,
+    price: Option<String>,
+    location: Option<String>,
+    staff_id: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    photo: Option<Option<String>>,
+}
+
+#[derive(Deserialize)]
+struct RideFilter {
+    ride_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NewRideQueue {
+    ride_id: String,
+    customer_id: String,
+    queue_position: Decimal,
+}
+
+#[derive(Deserialize)]
+struct QueuePositionChange {
+    queue_position: Decimal,
+}
+
+async fn view_rides(State(state): Shared) -> Reply<Vec<ride::Model>> {
+    RideHandler::view_rides(&state).await
+}
+
+async fn view_archived_rides(State(state): Shared) -> Reply<Vec<ride::Model>> {
+    RideHandler::view_archived_rides(&state).await
+}
+
+async fn get_ride_details(State(state): Shared, Path(ride_id): Path<String>) -> Reply<ride::Model> {
+    RideHandler::get_ride_details(&state, ride_id).await
+}
+
+async fn save_ride_data(State(state): Shared, Actor(actor_id): Actor, Body(new): Body<NewRide>) -> Reply<String> {
+    RideHandler::save_ride_data(&state, new.status, new.name, new.price, new.location, new.staff_id, new.photo, actor_id).await
+}
+
+async fn update_ride_data(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(ride_id): Path<String>,
+    Body(changes): Body<RideChanges>,
+) -> Reply<String> {
+    RideHandler::update_ride_data(
+        &state, ride_id, changes.status, changes.name, changes.price, changes.location, changes.staff_id, changes.photo, actor_id,
+    )
+    .await
+}
+
+async fn delete_ride_data(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(ride_id): Path<String>,
+    Params(params): Params<CascadeParams>,
+) -> Reply<DependencyReport> {
+    RideHandler::delete_ride_data(&state, ride_id, params.cascade.unwrap_or(false), actor_id).await
+}
+
+async fn restore_ride_data(State(state): Shared, Actor(actor_id): Actor, Path(ride_id): Path<String>) -> Reply<String> {
+    RideHandler::restore_ride_data(&state, ride_id, actor_id).await
+}
+
+async fn view_ride_queues(State(state): Shared, Params(filter): Params<RideFilter>) -> Reply<Vec<ride_queue::Model>> {
+    RideQueueHandler::view_ride_queues(&state, filter.ride_id).await
+}
+
+async fn save_ride_queue_data(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    IdempotencyKey(idempotency_key): IdempotencyKey,
+    Body(new): Body<NewRideQueue>,
+) -> Reply<String> {
+    RideQueueHandler::save_ride_queue_data(&state, new.ride_id, new.customer_id, new.queue_position, actor_id, idempotency_key).await
+}
+
+async fn update_queue_position(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(ride_queue_id): Path<String>,
+    Body(change): Body<QueuePositionChange>,
+) -> Reply<String> {
+    RideQueueHandler::update_queue_position(&state, ride_queue_id, change.queue_position, actor_id).await
+}
+
+async fn delete_ride_queue_data(State(state): Shared, Actor(actor_id): Actor, Path(ride_queue_id): Path<String>) -> Reply<String> {
+    RideQueueHandler::delete_ride_queue_data(&state, ride_queue_id, actor_id).await
+}
+
+// Stores, souvenirs and souvenir orders
+
+#[derive(Deserialize)]
+struct NewStore {
+    name: String,
+    photo: Option<String>,
+    opening_time: String,
+    closing_time: String,
+    location: Option<String>,
+    status: String,
+}
+
+#[derive(Deserialize)]
+struct StoreChanges {
+    name: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    photo: Option<Option<String>>,
+    opening_time: Option<String>,
+    closing_time: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    location: Option<Option<String>>,
+    status: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NewSouvenir {
+    name: String,
+    photo: Option<String>,
+    price: String,
+    stock: i32,
+    store_id: String,
+}
+
+#[derive(Deserialize)]
+struct SouvenirChanges {
+    name: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    photo: Option<Option<String>>,
+    price: Option<String>,
+    stock: Option<i32>,
+    store_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StockChange {
+    stock: i32,
+}
+
+#[derive(Deserialize)]
+struct NewOrderSouvenir {
+    customer_id: String,
+    store_id: String,
+    souvenir_id: String,
+    quantity: i32,
+}
+
+async fn view_stores(State(state): Shared) -> Reply<Vec<store::Model>> {
+    StoreHandler::view_stores(&state).await
+}
+
+async fn view_archived_stores(State(state): Shared) -> Reply<Vec<store::Model>> {
+    StoreHandler::view_archived_stores(&state).await
+}
+
+async fn get_store_details(State(state): Shared, Path(store_id): Path<String>) -> Reply<store::Model> {
+    StoreHandler::get_store_details(&state, store_id).await
+}
+
+async fn save_store_data(State(state): Shared, Actor(actor_id): Actor, Body(new): Body<NewStore>) -> Reply<String> {
+    StoreHandler::save_store_data(&state, new.name, new.photo, new.opening_time, new.closing_time, new.location, new.status, actor_id).await
+}
+
+async fn update_store_data(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(store_id): Path<String>,
+    Body(changes): Body<StoreChanges>,
+) -> Reply<String> {
+    StoreHandler::update_store_data(
+        &state,
+        store_id,
+        changes.name,
+        changes.photo,
+        changes.opening_time,
+        changes.closing_time,
+        changes.location,
+        changes.status,
+        actor_id,
+    )
+    .await
+}
+
+async fn delete_store_data(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(store_id): Path<String>,
+    Params(params): Params<CascadeParams>,
+) -> Reply<DependencyReport> {
+    StoreHandler::delete_store_data(&state, store_id, params.cascade.unwrap_or(false), actor_id).await
+}
+
+async fn restore_store_data(State(state): Shared, Actor(actor_id): Actor, Path(store_id): Path<String>) -> Reply<String> {
+    StoreHandler::restore_store_data(&state, store_id, actor_id).await
+}
+
+async fn view_souvenirs(State(state): Shared, Params(filter): Params<StoreFilter>) -> Reply<Vec<souvenir::Model>> {
+    SouvenirHandler::view_souvenirs(&state, filter.store_id).await
+}
+
+async fn get_souvenir_details(State(state): Shared, Path(souvenir_id): Path<String>) -> Reply<souvenir::Model> {
+    SouvenirHandler::get_souvenir_details(&state, souvenir_id).await
+}
+
+async fn save_souvenir_data(State(state): Shared, Actor(actor_id): Actor, Body(new): Body<NewSouvenir>) -> Reply<String> {
+    SouvenirHandler::save_souvenir_data(&state, new.name, new.photo, new.price, new.stock, new.store_id, actor_id).await
+}
+
+async fn update_souvenir_data(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(souvenir_id): Path<String>,
+    Body(changes): Body<SouvenirChanges>,
+) -> Reply<String> {
+    SouvenirHandler::update_souvenir_data(
+        &state, souvenir_id, changes.name, changes.photo, changes.price, changes.stock, changes.store_id, actor_id,
+    )
+    .await
+}
+
+async fn update_souvenir_stock(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(souvenir_id): Path<String>,
+    Body(change): Body<StockChange>,
+) -> Reply<String> {
+    SouvenirHandler::update_souvenir_stock(&state, souvenir_id, change.stock, actor_id).await
+}
+
+async fn delete_souvenir_data(State(state): Shared, Actor(actor_id): Actor, Path(souvenir_id): Path<String>) -> Reply<String> {
+    SouvenirHandler::delete_souvenir_data(&state, souvenir_id, actor_id).await
+}
+
+async fn view_order_souvenirs(State(state): Shared) -> Reply<Vec<order_souvenir::Model>> {
+    OrderSouvenirHandler::view_order_souvenirs(&state).await
+}
+
+async fn get_order_souvenir_details(State(state): Shared, Path(order_souvenir_id): Path<String>) -> Reply<order_souvenir::Model> {
+    OrderSouvenirHandler::get_order_souvenir_details(&state, order_souvenir_id).await
+}
+
+async fn save_order_souvenir_data(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    IdempotencyKey(idempotency_key): IdempotencyKey,
+    Body(new): Body<NewOrderSouvenir>,
+) -> Reply<String> {
+    OrderSouvenirHandler::save_order_souvenir_data(&state, new.customer_id, new.store_id, new.souvenir_id, new.quantity, actor_id, idempotency_key)
+        .await
+}
+
+async fn delete_order_souvenir_data(State(state): Shared, Actor(actor_id): Actor, Path(order_souvenir_id): Path<String>) -> Reply<String> {
+    OrderSouvenirHandler::delete_order_souvenir_data(&state, order_souvenir_id, actor_id).await
+}
+
+// Lost and found. The staff member handling the item is the actor.
+
+#[derive(Deserialize)]
+struct NewLog {
+    image: Option<String>,
+    name: String,
+    r#type: String,
+    color: String,
+    last_seen_location: Option<String>,
+    finder: Option<String>,
+    owner: Option<String>,
+    found_location: Option<String>,
+    status: String,
+}
+
+#[derive(Deserialize)]
+struct LogChanges {
+    #[serde(default, deserialize_with = "nullable")]
+    image: Option<Option<String>>,
+    name: Option<String>,
+    r#type: Option<String>,
+    color: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    last_seen_location: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    finder: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    owner: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    found_location: Option<Option<String>>,
+    status: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct MatchParams {
+    log_id: Option<String>,
+    limit: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct MatchConfirmation {
+    missing_log_id: String,
+    found_log_id: String,
+    verification: OwnerVerification,
+}
+
+#[derive(Deserialize)]
+struct Disposal {
+    disposition: String,
+    note: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct MissingReport {
+    image: Option<String>,
+    name: String,
+    r#type: String,
+    color: String,
+    last_seen_location: String,
+}
+
+async fn view_logs(State(state): Shared) -> Reply<Vec<lost_and_found_items_log::Model>> {
+    LostAndFoundItemsLogHandler::view_logs(&state).await
+}
+
+async fn save_log_data(State(state): Shared, Actor(staff_id): Actor, Body(new): Body<NewLog>) -> Reply<String> {
+    LostAndFoundItemsLogHandler::save_log_data(
+        &state,
+        new.image,
+        new.name,
+        new.r#type,
+        new.color,
+        new.last_seen_location,
+        new.finder,
+        new.owner,
+        new.found_location,
+        new.status,
+        staff_id,
+    )
+    .await
+}
+
+async fn update_log_data(
+    State(state): Shared,
+    Actor(staff_id): Actor,
+    Path(log_id): Path<String>,
+    Body(changes): Body<LogChanges>,
+) -> Reply<String> {
+    LostAndFoundItemsLogHandler::update_log_data(
+        &state,
+        log_id,
+        changes.image,
+        changes.name,
+        changes.r#type,
+        changes.color,
+        changes.last_seen_location,
+        changes.finder,
+        changes.owner,
+        changes.found_location,
+        changes.status,
+        staff_id,
+    )
+    .await
+}
+
+async fn delete_log_data(State(state): Shared, Actor(actor_id): Actor, Path(log_id): Path<String>) -> Reply<String> {
+    LostAndFoundItemsLogHandler::delete_log_data(&state, log_id, actor_id).await
+}
+
+async fn suggest_lost_and_found_matches(State(state): Shared, Params(params): Params<MatchParams>) -> Reply<Vec<LostAndFoundMatch>> {
+    LostAndFoundItemsLogHandler::suggest_matches(&state, params.log_id, params.limit).await
+}
+
+async fn confirm_lost_and_found_match(State(state): Shared, Actor(actor_id): Actor, Body(confirmation): Body<MatchConfirmation>) -> Reply<String> {
+    LostAndFoundItemsLogHandler::confirm_match(
+        &state,
+        confirmation.missing_log_id,
+        confirmation.found_log_id,
+        confirmation.verification,
+        actor_id,
+    )
+    .await
+}
+
+async fn return_log_to_owner(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(log_id): Path<String>,
+    Body(verification): Body<OwnerVerification>,
+) -> Reply<String> {
+    LostAndFoundItemsLogHandler::return_log_to_owner(&state, log_id, verification, actor_id).await
+}
+
+async fn view_retention_flagged_logs(State(state): Shared) -> Reply<Vec<lost_and_found_items_log::Model>> {
+    LostAndFoundItemsLogHandler::view_retention_flagged_logs(&state).await
+}
+
+async fn dispose_log(State(state): Shared, Actor(actor_id): Actor, Path(log_id): Path<String>, Body(disposal): Body<Disposal>) -> Reply<String> {
+    LostAndFoundItemsLogHandler::dispose_log(&state, log_id, disposal.disposition, actor_id, disposal.note).await
+}
+
+async fn get_log_history(State(state): Shared, Path(log_id): Path<String>) -> Reply<Vec<lost_and_found_log_event::Model>> {
+    LostAndFoundItemsLogHandler::get_log_history(&state, log_id).await
+}
+
+async fn file_missing_report(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(customer_id): Path<String>,
+    Body(report): Body<MissingReport>,
+) -> Reply<String> {
+    // Customers report their own missing items
+    if actor_id != customer_id {
+        return Err(AppError::Forbidden("You can only report items you have lost yourself".to_string()));
+    }
+    LostAndFoundItemsLogHandler::file_missing_report(&state, customer_id, report.image, report.name, report.r#type, report.color, report.last_seen_location)
+        .await
+}
+
+async fn view_lost_item_reports_by_customer(
+    State(state): Shared,
+    Path(customer_id): Path<String>,
+) -> Reply<Vec<lost_and_found_items_log::Model>> {
+    LostAndFoundItemsLogHandler::view_reports_by_customer(&state, customer_id).await
+}
+
+// Chats and messages. The sender, editor or deleter of a message is the actor, and chats are
+// listed and searched for the actor.
+
+#[derive(Deserialize)]
+struct NewChat {
+    name: String,
+    chat_type: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NewMessage {
+    text: String,
+    attachments: Option<Vec<NewAttachment>>,
+}
+
+#[derive(Deserialize)]
+struct MessageEdit {
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    query: String,
+    limit: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct NewChatMember {
+    user_id: String,
+}
+
+async fn view_chats(State(state): Shared, Actor(actor_id): Actor) -> Reply<Vec<chat::Model>> {
+    ChatHandler::view_chats(&state, actor_id).await
+}
+
+async fn get_chat_details(State(state): Shared, Path(chat_id): Path<String>) -> Reply<chat::Model> {
+    ChatHandler::get_chat_details(&state, chat_id).await
+}
+
+async fn save_chat_data(State(state): Shared, Body(new): Body<NewChat>) -> Reply<String> {
+    ChatHandler::save_chat_data(&state, new.name, new.chat_type).await
+}
+
+async fn get_messages(State(state): Shared, Path(chat_id): Path<String>) -> Reply<Vec<MessageWithSenderName>> {
+    ChatHandler::get_messages(&state, chat_id).await
+}
+
+async fn save_message_data(State(state): Shared, Actor(actor_id): Actor, Path(chat_id): Path<String>, Body(new): Body<NewMessage>) -> Reply<String> {
+    ChatHandler::save_message_data(&state, chat_id, actor_id, new.text, new.attachments).await
+}
+
+async fn edit_message(State(state): Shared, Actor(actor_id): Actor, Path(message_id): Path<String>, Body(edit): Body<MessageEdit>) -> Reply<message::Model> {
+    ChatHandler::edit_message(&state, message_id, actor_id, edit.text).await
+}
+
+async fn delete_message(State(state): Shared, Actor(actor_id): Actor, Path(message_id): Path<String>) -> Reply<String> {
+    ChatHandler::delete_message(&state, message_id, actor_id).await
+}
+
+async fn get_message_edit_history(State(state): Shared, Path(message_id): Path<String>) -> Reply<Vec<message_edit::Model>> {
+    ChatHandler::get_message_edit_history(&state, message_id).await
+}
+
+async fn search_messages(State(state): Shared, Actor(actor_id): Actor, Params(params): Params<SearchParams>) -> Reply<Vec<MessageSearchResult>> {
+    ChatHandler::search_messages(&state, actor_id, params.query, params.limit).await
+}
+
+// Raw bytes rather than a JSON array of numbers; errors still use the envelope
+async fn get_attachment_data(State(state): Shared, Path(attachment_id): Path<String>) -> Response {
+    match ChatHandler::get_attachment_data(&state, attachment_id).await {
+        Ok(ApiResponse::Success { data, .. }) => ([(header::CONTENT_TYPE, "application/octet-stream")], data).into_response(),
+        Ok(response) => response.into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
+async fn save_chat_member_data(State(state): Shared, Path(chat_id): Path<String>, Body(new): Body<NewChatMember>) -> Reply<String> {
+    ChatHandler::save_chat_member_data(&state, chat_id, new.user_id).await
+}
+
+async fn get_chat_members(State(state): Shared, Path(chat_id): Path<String>) -> Reply<Vec<chat_member::Model>> {
+    ChatHandler::get_chat_members(&state, chat_id).await
+}
+
+async fn view_customer_chats_for_staff(State(state): Shared) -> Reply<Vec<ChatWithCustomerName>> {
+    ChatHandler::get_customer_chats_for_staff(&state).await
+}
+
+async fn get_active_customer_service_ticket(State(state): Shared, Path(chat_id): Path<String>) -> Reply<Option<customer_service_ticket::Model>> {
+    CustomerServiceTicketHandler::get_active_ticket(&state, chat_id).await
+}
+
+// Customer service tickets
+
+#[derive(Deserialize)]
+struct TicketAssignment {
+    staff_id: String,
+}
+
+#[derive(Deserialize)]
+struct MetricsParams {
+    staff_id: Option<String>,
+}
+
+async fn view_customer_service_queue(State(state): Shared) -> Reply<Vec<QueuedTicket>> {
+    CustomerServiceTicketHandler::view_queue(&state).await
+}
+
+async fn assign_customer_service_ticket(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(ticket_id): Path<String>,
+    Body(assignment): Body<TicketAssignment>,
+) -> Reply<String> {
+    CustomerServiceTicketHandler::assign_ticket(&state, ticket_id, assignment.staff_id, actor_id).await
+}
+
+async fn update_customer_service_ticket_status(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(ticket_id): Path<String>,
+    Body(change): Body<StatusChange>,
+) -> Reply<String> {
+    CustomerServiceTicketHandler::update_ticket_status(&state, ticket_id, change.status, actor_id).await
+}
+
+async fn get_customer_service_metrics(State(state): Shared, Params(params): Params<MetricsParams>) -> Reply<CustomerServiceMetrics> {
+    CustomerServiceTicketHandler::get_metrics(&state, params.staff_id).await
+}
+
+// Maintenance schedules
+
+#[derive(Deserialize)]
+struct NewMaintenanceSchedule {
+    ride_id: String,
+    staff_id: String,
+    description: Option<String>,
+    start_date: String,
+    end_date: String,
+    status: String,
+}
+
+#[derive(Deserialize)]
+struct MaintenanceScheduleChanges {
+    ride_id: Option<String>,
+    staff_id: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    description: Option<Option<String>>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    status: Option<String>,
+}
+
+async fn view_maintenance_schedules(State(state): Shared) -> Reply<Vec<maintenance_schedule::Model>> {
+    MaintenanceScheduleHandler::view_maintenance_schedules(&state).await
+}
+
+async fn save_maintenance_schedule_data(State(state): Shared, Actor(actor_id): Actor, Body(new): Body<NewMaintenanceSchedule>) -> Reply<String> {
+    MaintenanceScheduleHandler::save_maintenance_schedule_data(
+        &state, new.ride_id, new.staff_id, new.description, new.start_date, new.end_date, new.status, actor_id,
+    )
+    .await
+}
+
+async fn update_maintenance_schedule_data(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(maintenance_task_id): Path<String>,
+    Body(changes): Body<MaintenanceScheduleChanges>,
+) -> Reply<String> {
+    MaintenanceScheduleHandler::update_maintenance_schedule_data(
+        &state,
+        maintenance_task_id,
+        changes.ride_id,
+        changes.staff_id,
+        changes.description,
+        changes.start_date,
+        changes.end_date,
+        changes.status,
+        actor_id,
+    )
+    .await
+}
+
+async fn delete_maintenance_schedule_data(State(state): Shared, Actor(actor_id): Actor, Path(maintenance_task_id): Path<String>) -> Reply<String> {
+    MaintenanceScheduleHandler::delete_maintenance_schedule_data(&state, maintenance_task_id, actor_id).await
+}
+
+// Reports
+
+#[derive(Deserialize)]
+struct ReportParams {
+    period: String,
+}
+
+async fn generate_income_report(State(state): Shared, Params(params): Params<ReportParams>) -> Reply<IncomeReport> {
+    IncomeReportHandler::generate_income_report(&state, params.period).await
+}
+
+// Broadcasts
+
+#[derive(Deserialize)]
+struct AudienceFilter {
+    target_audience: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NewBroadcast {
+    target_audience: String,
+    content: String,
+    status: String,
+    send_at: Option<NaiveDateTime>,
+    expires_at: Option<NaiveDateTime>,
+    target_type: Option<String>,
+    target_value: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BroadcastChanges {
+    target_audience: Option<String>,
+    content: Option<String>,
+    status: Option<String>,
+    send_at: Option<NaiveDateTime>,
+    #[serde(default, deserialize_with = "nullable")]
+    expires_at: Option<Option<NaiveDateTime>>,
+    target_type: Option<String>,
+    target_value: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct VenueParams {
+    ui_id: Option<String>, // The venue the client runs at, for venue broadcasts
+}
+
+async fn view_broadcast_messages(State(state): Shared, Params(filter): Params<AudienceFilter>) -> Reply<Vec<broadcast_message::Model>> {
+    match filter.target_audience {
+        Some(target_audience) => BroadcastMessageHandler::view_broadcast_messages_by_audience(&state, target_audience).await,
+        None => BroadcastMessageHandler::view_broadcast_messages(&state).await,
+    }
+}
+
+async fn get_broadcast_message_details(State(state): Shared, Path(broadcast_message_id): Path<String>) -> Reply<broadcast_message::Model> {
+    BroadcastMessageHandler::get_broadcast_message_details(&state, broadcast_message_id).await
+}
+
+async fn save_broadcast_message_data(State(state): Shared, Actor(actor_id): Actor, Body(new): Body<NewBroadcast>) -> Reply<String> {
+    let result = BroadcastMessageHandler::save_broadcast_message_data(
+        &state, new.target_audience, new.content, new.status, new.send_at, new.expires_at, new.target_type, new.target_value, actor_id,
+    )
+    .await;
+    if result.is_ok() {
+        send_due_broadcasts(&state).await;
+    }
+    result
+}
+
+async fn update_broadcast_message_data(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(broadcast_message_id): Path<String>,
+    Body(changes): Body<BroadcastChanges>,
+) -> Reply<String> {
+    let result = BroadcastMessageHandler::update_broadcast_message_data(
+        &state,
+        broadcast_message_id,
+        changes.target_audience,
+        changes.content,
+        changes.status,
+        changes.send_at,
+        changes.expires_at,
+        changes.target_type,
+        changes.target_value,
+        actor_id,
+    )
+    .await;
+    if result.is_ok() {
+        send_due_broadcasts(&state).await;
+    }
+    result
+}
+
+async fn delete_broadcast_message_data(State(state): Shared, Actor(actor_id): Actor, Path(broadcast_message_id): Path<String>) -> Reply<String> {
+    BroadcastMessageHandler::delete_broadcast_message_data(&state, broadcast_message_id, actor_id).await
+}
+
+async fn get_broadcast_delivery_stats(State(state): Shared, Path(broadcast_message_id): Path<String>) -> Reply<BroadcastDeliveryStats> {
+    BroadcastMessageHandler::get_broadcast_delivery_stats(&state, broadcast_message_id).await
+}
+
+async fn mark_broadcast_message_read(State(state): Shared, Actor(actor_id): Actor, Path(broadcast_message_id): Path<String>) -> Reply<String> {
+    BroadcastMessageHandler::mark_broadcast_message_read(&state, broadcast_message_id, actor_id).await
+}
+
+async fn view_broadcast_messages_for_recipient(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Path(recipient_id): Path<String>,
+    Params(params): Params<VenueParams>,
+) -> Reply<Vec<RecipientBroadcast>> {
+    if actor_id != recipient_id {
+        return Err(AppError::Forbidden("You can only read your own broadcasts".to_string()));
+    }
+    BroadcastMessageHandler::view_broadcast_messages_for_recipient(&state, recipient_id, params.ui_id).await
+}
+
+// Offline sync conflicts
+
+#[derive(Deserialize)]
+struct ConflictFilter {
+    include_resolved: Option<bool>,
+}
+
+async fn view_sync_conflicts(
+    State(state): Shared,
+    Actor(actor_id): Actor,
+    Params(filter): Params<ConflictFilter>,
+) -> Reply<Vec<offline_sync_conflict::Model>> {
+    OfflineSyncHandler::view_sync_conflicts(&state, actor_id, filter.include_resolved.unwrap_or(false)).await
+}
+
+async fn retry_sync_conflict(State(state): Shared, Actor(actor_id): Actor, Path(conflict_id): Path<String>) -> Reply<String> {
+    OfflineSyncHandler::retry_sync_conflict(&state, conflict_id, actor_id).await
+}
+
+async fn dismiss_sync_conflict(State(state): Shared, Actor(actor_id): Actor, Path(conflict_id): Path<String>) -> Reply<String> {
+    OfflineSyncHandler::dismiss_sync_conflict(&state, conflict_id, actor_id).await
+}
+
+// Every endpoint except /api/health requires `Authorization: Bearer <api_token>`
+pub fn router(state: Arc<AppState>, api_token: &str) -> Router {
+    let protected = Router::new()
+        .route("/customers/login", post(customer_login))
+        .route("/customers", get(view_customer_accounts).post(save_customer_data))
+        .route("/customers/archived", get(view_archived_customers))
+        .route("/customers/{customer_id}", get(get_customer_details).patch(update_customer_data).delete(delete_customer_data))
+        .route("/customers/{customer_id}/restore", post(restore_customer_data))
+        .route("/customers/{customer_id}/top-up", post(top_up_virtual_balance))
+        .route("/customers/{customer_id}/restaurant-orders", get(view_order_restaurants_by_customer))
+        .route("/customers/{customer_id}/souvenir-orders", get(view_order_souvenirs_by_customer))
+        .route("/customers/{customer_id}/lost-item-reports", get(view_lost_item_reports_by_customer).post(file_missing_report))
+        .route("/customers/{customer_id}/customer-service-chat", get(get_customer_service_chat))
+        .route("/staff/login", post(staff_login))
+        .route("/logout", post(logout))
+        .route("/staff/password-policy", get(view_password_policy))
+        .route("/staff/password-reset", post(complete_staff_password_reset))
+        .route("/staff", get(view_staff_accounts).post(save_staff_data))
+        .route("/staff/archived", get(view_archived_staff))
+        .route("/staff/ride-staff", get(view_ride_staffs))
+        .route("/staff/maintenance-staff", get(view_maintenance_staffs))
+        .route("/staff/by-email", get(get_staff_details_by_email))
+        .route("/staff/{staff_id}", get(get_staff_details).patch(update_staff_data).delete(delete_staff_data))
+        .route("/staff/{staff_id}/restore", post(restore_staff_data))
+        .route("/staff/{staff_id}/password", put(change_staff_password))
+        .route("/staff/{staff_id}/reset-token", post(reset_staff_password))
+        .route("/staff/{staff_id}/maintenance-schedules", get(view_maintenance_schedule_by_staff))
+        .route("/staff/{staff_id}/customer-service-tickets", get(view_assigned_customer_service_tickets))
+        .route("/staff/{staff_id}/direct-chats/{other_staff_id}", get(get_direct_chat))
+        .route("/dependencies/{entity_type}/{entity_id}", get(check_delete_dependencies))
+        .route("/audit-events", get(view_audit_events))
+        .route("/restaurants", get(view_restaurants).post(save_restaurant_data))
+        .route("/restaurants/archived", get(view_archived_restaurants))
+        .route("/restaurants/{restaurant_id}", get(get_restaurant_details).patch(update_restaurant_data).delete(delete_restaurant_data))
+        .route("/restaurants/{restaurant_id}/restore", post(restore_restaurant_data))
+        .route("/menu-items", get(view_menu_items).post(save_menu_item_data))
+        .route("/menu-items/archived", get(view_archived_menu_items))
+        .route("/menu-items/{menu_item_id}", get(get_menu_item_details).patch(update_menu_item_data).delete(delete_menu_item_data))
+        .route("/menu-items/{menu_item_id}/restore", post(restore_menu_item_data))
+        .route("/restaurant-orders", get(view_order_restaurants).post(save_order_restaurant_data))
+        .route("/restaurant-orders/{order_restaurant_id}", axum::routing::delete(delete_order_restaurant_data))
+        .route("/restaurant-orders/{order_restaurant_id}/status", put(update_order_restaurant_status))
+        .route("/rides", get(view_rides).post(save_ride_data))
+        .route("/rides/archived", get(view_archived_rides))
+        .route("/rides/{ride_id}", get(get_ride_details).patch(update_ride_data).delete(delete_ride_data))
+        .route("/rides/{ride_id}/restore", post(restore_ride_data))
+        .route("/ride-queues", get(view_ride_queues).post(save_ride_queue_data))
+        .route("/ride-queues/{ride_queue_id}", patch(update_queue_position).delete(delete_ride_queue_data))
+        .route("/stores", get(view_stores).post(save_store_data))
+        .route("/stores/archived", get(view_archived_stores))
+        .route("/stores/{store_id}", get(get_store_details).patch(update_store_data).delete(delete_store_data))
+        .route("/stores/{store_id}/restore", post(restore_store_data))
+        .route("/souvenirs", get(view_souvenirs).post(save_souvenir_data))
+        .route("/souvenirs/{souvenir_id}", get(get_souvenir_details).patch(update_souvenir_data).delete(delete_souvenir_data))
+        .route("/souvenirs/{souvenir_id}/stock", put(update_souvenir_stock))
+        .route("/souvenir-orders", get(view_order_souvenirs).post(save_order_souvenir_data))
+        .route("/souvenir-orders/{order_souvenir_id}", get(get_order_souvenir_details).delete(delete_order_souvenir_data))
+        .route("/lost-and-found", get(view_logs).post(save_log_data))
+        .route("/lost-and-found/matches", get(suggest_lost_and_found_matches).post(confirm_lost_and_found_match))
+        .route("/lost-and-found/retention-flagged", get(view_retention_flagged_logs))
+        .route("/lost-and-found/{log_id}", patch(update_log_data).delete(delete_log_data))
+        .route("/lost-and-found/{log_id}/return", post(return_log_to_owner))
+        .route("/lost-and-found/{log_id}/dispose", post(dispose_log))
+        .route("/lost-and-found/{log_id}/history", get(get_log_history))
+        .route("/chats", get(view_chats).post(save_chat_data))
+        .route("/chats/customer-service", get(view_customer_chats_for_staff))
+        .route("/chats/{chat_id}", get(get_chat_details))
+        .route("/chats/{chat_id}/messages", get(get_messages).post(save_message_data))
+        .route("/chats/{chat_id}/members", get(get_chat_members).post(save_chat_member_data))
+        .route("/chats/{chat_id}/active-ticket", get(get_active_customer_service_ticket))
+        .route("/messages/search", get(search_messages))
+        .route("/messages/{message_id}", patch(edit_message).delete(delete_message))
+        .route("/messages/{message_id}/edits", get(get_message_edit_history))
+        .route("/attachments/{attachment_id}", get(get_attachment_data))
+        .route("/customer-service/queue", get(view_customer_service_queue))
+        .route("/customer-service/metrics", get(get_customer_service_metrics))
+        .route("/customer-service/tickets/{ticket_id}/assignee", put(assign_customer_service_ticket))
+        .route("/customer-service/tickets/{ticket_id}/status", put(update_customer_service_ticket_status))
+        .route("/maintenance-schedules", get(view_maintenance_schedules).post(save_maintenance_schedule_data))
+        .route(
+            "/maintenance-schedules/{maintenance_task_id}",
+            patch(update_maintenance_schedule_data).delete(delete_maintenance_schedule_data),
+        )
+        .route("/reports/income", get(generate_income_report))
+        .route("/broadcasts", get(view_broadcast_messages).post(save_broadcast_message_data))
+        .route(
+            "/broadcasts/{broadcast_message_id}",
+            get(get_broadcast_message_details).patch(update_broadcast_message_data).delete(delete_broadcast_message_data),
+        )
+        .route("/broadcasts/{broadcast_message_id}/stats", get(get_broadcast_delivery_stats))
+        .route("/broadcasts/{broadcast_message_id}/read", post(mark_broadcast_message_read))
+        .route("/recipients/{recipient_id}/broadcasts", get(view_broadcast_messages_for_recipient))
+        .route("/sync-conflicts", get(view_sync_conflicts))
+        .route("/sync-conflicts/{conflict_id}/retry", post(retry_sync_conflict))
+        .route("/sync-conflicts/{conflict_id}/dismiss", post(dismiss_sync_conflict))
+        .route_layer(middleware::from_fn_with_state(Arc::<str>::from(api_token), require_api_token));
+
+    let api = Router::new().route("/health", get(health_check)).merge(protected);
+    Router::new()
+        .nest("/api", api)
+        .fallback(|| async { AppError::NotFound("No such endpoint".to_string()) })
+        .with_state(state)
+}
+
+// The background work the desktop app does, for deployments that only run this server
+fn spawn_background_tasks(state: &Arc<AppState>) {
+    let sync_state = state.clone();
+    tokio::spawn(async move {
+        loop {
+            match ChatHandler::sync_division_group_chats(&sync_state).await {
+                Ok(()) => break,
+                Err(err) => eprintln!("Failed to sync division group chats: {}", err),
+            }
+            tokio::time::sleep(Duration::from_secs(DIVISION_SYNC_RETRY_SECS)).await;
+        }
+    });
+
+    // Clients poll /api/recipients/{id}/broadcasts; there is no live push over HTTP
+    let dispatch_state = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(BROADCAST_DISPATCH_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            send_due_broadcasts(&dispatch_state).await;
+        }
+    });
+
+    let retention_state = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(LOST_AND_FOUND_RETENTION_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            if let Err(err) = LostAndFoundItemsLogHandler::flag_unclaimed_items(&retention_state).await {
+                eprintln!("Lost and found retention sweep failed: {}", err);
+            }
+        }
+    });
+}
+
+// Entry point of the vortekia-server binary. Listens on HTTP_API_ADDR (default 127.0.0.1:8080)
+// and refuses to start without HTTP_API_TOKEN.
+pub async fn serve() -> Result<(), String> {
+    dotenv().ok();
+    let api_token = env::var("HTTP_API_TOKEN")
+        .ok()
+        .filter(|token| !token.trim().is_empty())
+        .ok_or_else(|| "HTTP_API_TOKEN is not set".to_string())?;
+    let addr = env::var("HTTP_API_ADDR").unwrap_or_else(|_| DEFAULT_HTTP_API_ADDR.to_string());
+
+    let state = Arc::new(build_state().await?);
+    spawn_background_tasks(&state);
+
+    let listener = tokio::net::TcpListener::bind(&addr)
+        .await
+        .map_err(|err| format!("Cannot listen on {}: {}", addr, err))?;
+    println!("VorteKia HTTP API listening on http://{}", addr);
+    axum::serve(listener, router(state, &api_token))
+        .await
+        .map_err(|err| format!("HTTP server error: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body as RequestBody;
+    use axum::http::Request as HttpRequest;
+    use entity::user_session::KIND_STAFF;
+    use http_body_util::BodyExt;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    use crate::test_support;
+
+    const API_TOKEN: &str = "test-api-token";
+
+    fn request(method: &str, uri: &str, session_token: Option<&str>, body: Option<Value>) -> HttpRequest<RequestBody> {
+        let mut builder = HttpRequest::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {}", API_TOKEN));
+        if let Some(session_token) = session_token {
+            builder = builder.header(SESSION_HEADER, session_token);
+        }
+        match body {
+            Some(body) => builder
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(RequestBody::from(body.to_string()))
+                .unwrap(),
+            None => builder.body(RequestBody::empty()).unwrap(),
+        }
+    }
+
+    async fn send(app: &Router, request: HttpRequest<RequestBody>) -> (StatusCode, Value) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn requests_need_the_api_token_and_writes_a_session() {
+        let state = Arc::new(test_support::test_state().await);
+        let customer = test_support::seed_customer(&state, "25000").await;
+        let app = router(state.clone(), API_TOKEN);
+        let uri = format!("/api/customers/{}", customer.customer_id);
+
+        let mut unauthenticated = request("GET", &uri, None, None);
+        unauthenticated.headers_mut().remove(header::AUTHORIZATION);
+        let (status, body) = send(&app, unauthenticated).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!((body["status"].as_str(), body["code"].as_str()), (Some("error"), Some("UNAUTHORIZED")));
+
+        // A trusted client still cannot write without someone signed in, nor with a made-up session
+        let top_up = json!({ "amount": "1000" });
+        for session_token in [None, Some(customer.customer_id.as_str())] {
+            let (status, body) = send(&app, request("POST", &format!("{}/top-up", uri), session_token, Some(top_up.clone()))).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+            assert_eq!(body["code"], "UNAUTHORIZED");
+        }
+        let (_, body) = send(&app, request("GET", &uri, None, None)).await;
+        assert_eq!(body["data"]["virtual_balance"], "25000");
+    }
+
+    #[tokio::test]
+    async fn a_signed_in_write_returns_the_success_envelope() {
+        let state = Arc::new(test_support::test_state().await);
+        let customer = test_support::seed_customer(&state, "25000").await;
+        let cashier = test_support::seed_staff(&state, "CFO").await;
+        let session_token = Session::open(&state.db, &cashier.staff_id, KIND_STAFF).await.unwrap();
+        let app = router(state.clone(), API_TOKEN);
+        let uri = format!("/api/customers/{}", customer.customer_id);
+
+        let (status, body) = send(&app, request("POST", &format!("{}/top-up", uri), Some(&session_token), Some(json!({ "amount": "1000" })))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "success");
+
+        let (status, body) = send(&app, request("GET", &uri, None, None)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["virtual_balance"], "26000");
+    }
+
+    #[tokio::test]
+    async fn staff_accounts_need_a_session_and_never_carry_hashes() {
+        let state = Arc::new(test_support::test_state().await);
+        let manager = test_support::seed_staff(&state, "CFO").await;
+        let colleague = test_support::seed_staff(&state, "Ride Staff").await;
+        let session_token = Session::open(&state.db, &manager.staff_id, KIND_STAFF).await.unwrap();
+        let app = router(state, API_TOKEN);
+        let uri = format!("/api/staff/{}", colleague.staff_id);
+
+        let (status, _) = send(&app, request("GET", &uri, None, None)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, body) = send(&app, request("GET", &uri, Some(&session_token), None)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["staff_id"], colleague.staff_id.as_str());
+        assert!(body["data"].get("password_hash").is_none() && body["data"].get("reset_token_hash").is_none());
+
+        // Only the signed-in staff member can change their own password
+        let change = json!({ "current_password": "old", "new_password": "Another-Passw0rd!" });
+        let (status, body) = send(&app, request("PUT", &format!("{}/password", uri), Some(&session_token), Some(change))).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["code"], "FORBIDDEN");
+    }
+
+    #[tokio::test]
+    async fn handler_errors_keep_their_code_and_http_status() {
+        let state = Arc::new(test_support::test_state().await);
+        let customer = test_support::seed_customer(&state, "25000").await;
+        let cashier = test_support::seed_staff(&state, "CFO").await;
+        let session_token = Session::open(&state.db, &cashier.staff_id, KIND_STAFF).await.unwrap();
+        let app = router(state, API_TOKEN);
+
+        let (status, body) = send(&app, request("GET", "/api/customers/nobody", None, None)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "NOT_FOUND");
+
+        let top_up = format!("/api/customers/{}/top-up", customer.customer_id);
+        let (status, body) = send(&app, request("POST", &top_up, Some(&session_token), Some(json!({ "amount": "abc" })))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "VALIDATION_FAILED");
+    }
+}
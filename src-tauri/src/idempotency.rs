@@ -0,0 +1,289 @@
+// src-tauri/src/idempotency.rs
+
+// Client-generated keys for commands that charge a customer or take a place in a queue, so a
+// double click or a retry after a lost response returns the first answer instead of running the
+// command again. Keys are kept in the central database rather than the cache: they must survive
+// Redis outages and restarts, and the primary key settles two requests racing on one key.
+
+use chrono::Duration;
+use entity::idempotency_record;
+use sea_orm::{sea_query::Expr, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::future::Future;
+
+use crate::clock;
+use crate::error::AppError;
+use crate::validation::{self, Validator};
+use crate::ApiResponse;
+
+// A key still pending after this long was abandoned (e.g. the app closed mid-request) and may
+// be claimed again
+const PENDING_TIMEOUT_SECS: i64 = 120;
+
+pub struct Idempotency;
+
+impl Idempotency {
+    // Run `action` once per key. A repeat with the same key and arguments gets the stored answer;
+    // the same key with different arguments is refused. Only successes are kept, so a request
+    // that failed (e.g. insufficient stock) can be retried with the same key. Without a key the
+    // action simply runs.
+    pub async fn run<T, F>(
+        db: &DatabaseConnection,
+        idempotency_key: Option<String>,
+        command: &str,
+        request: serde_json::Value,
+        action: F,
+    ) -> Result<ApiResponse<T>, AppError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = Result<ApiResponse<T>, AppError>>,
+    {
+        let Some(key) = idempotency_key else {
+            return action.await;
+        };
+        Validator::new()
+            .field("idempotency_key", &key, &[validation::idempotency_key])
+            .finish()?;
+
+        if let Some(original) = Self::claim(db, &key, command, &request).await? {
+            return Ok(original);
+        }
+
+        match action.await {
+            Ok(ApiResponse::Success { data, message }) => {
+                Self::complete(db, &key, &data, message.as_deref()).await;
+                Ok(ApiResponse::Success { data, message })
+            }
+            outcome => {
+                Self::release(db, &key).await;
+                outcome
+            }
+        }
+    }
+
+    // For work done inside the caller's transaction, such as an offline replay: whether the key
+    // is free to use. False means it already completed, online or in an earlier replay, and the
+    // work must not run again. A key still pending elsewhere is reported as a database error so
+    // the work is retried later; an abandoned one is cleared.
+    pub(crate) async fn claim_in<C: ConnectionTrait>(conn: &C, key: &str) -> Result<bool, AppError> {
+        let existing = idempotency_record::Entity::find_by_id(key.to_string())
+            .one(conn)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching idempotency key: {}", err)))?;
+        let Some(existing) = existing else {
+            return Ok(true);
+        };
+        if existing.status == idempotency_record::STATUS_COMPLETED {
+            return Ok(false);
+        }
+        if clock::jakarta_now() - existing.created_at < Duration::seconds(PENDING_TIMEOUT_SECS) {
+            return Err(AppError::Database("A request with this idempotency key is still being processed".to_string()));
+        }
+        idempotency_record::Entity::delete_by_id(key.to_string())
+            .exec(conn)
+            .await
+            .map_err(|err| AppError::Database(format!("Error claiming idempotency key: {}", err)))?;
+        Ok(true)
+    }
+
+    // Record the key as completed in the caller's transaction, so it commits together with the
+    // work it guards. `command` and `request` must match what the online command records, so a
+    // client retrying online gets this answer back.
+    pub(crate) async fn complete_in<C: ConnectionTrait, T: Serialize>(
+        conn: &C,
+        key: &str,
+        command: &str,
+        request: serde_json::Value,
+        data: &T,
+    ) -> Result<(), AppError> {
+        let now = clock::jakarta_now();
+        let response = serde_json::to_value(data)
+            .map_err(|err| AppError::Internal(format!("Error encoding response for idempotency key: {}", err)))?;
+        let record = idempotency_record::ActiveModel {
+            idempotency_key: Set(key.to_string()),
+            command: Set(command.to_string()),
+            request: Set(request),
+            status: Set(idempotency_record::STATUS_COMPLETED.to_string()),
+            response: Set(Some(response)),
+            message: Set(None),
+            created_at: Set(now),
+            completed_at: Set(Some(now)),
+        };
+        idempotency_record::Entity::insert(record)
+            .exec_without_returning(conn)
+            .await
+            .map_err(|err| AppError::Database(format!("Error recording idempotency key: {}", err)))?;
+        Ok(())
+    }
+
+    // Take the key for this request, or return the answer it already produced
+    async fn claim<T: Serialize + DeserializeOwned>(
+        db: &DatabaseConnection,
+        key: &str,
+        command: &str,
+        request: &serde_json::Value,
+    ) -> Result<Option<ApiResponse<T>>, AppError> {
+        let now = clock::jakarta_now();
+        let record = idempotency_record::ActiveModel {
+            idempotency_key: Set(key.to_string()),
+            command: Set(command.to_string()),
+            request: Set(request.clone()),
+            status: Set(idempotency_record::STATUS_PENDING.to_string()),
+            response: Set(None),
+            message: Set(None),
+            created_at: Set(now),
+            completed_at: Set(None),
+        };
+        if idempotency_record::Entity::insert(record).exec_without_returning(db).await.is_ok() {
+            return Ok(None);
+        }
+
+        // The insert failed, normally because the key is taken; if the database is down this
+        // lookup fails too and reports it
+        let existing = idempotency_record::Entity::find_by_id(key.to_string())
+            .one(db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching idempotency key: {}", err)))?
+            .ok_or_else(|| AppError::Database("Error recording idempotency key".to_string()))?;
+
+        if existing.command != command || existing.request != *request {
+            return Err(AppError::Conflict(
+                "This idempotency key was already used for a different request".to_string(),
+            ));
+        }
+        if existing.status == idempotency_record::STATUS_COMPLETED {
+            let data = serde_json::from_value(existing.response.unwrap_or_default())
+                .map_err(|err| AppError::Internal(format!("Unreadable stored response: {}", err)))?;
+            return Ok(Some(ApiResponse::Success {
+                data,
+                message: existing.message,
+            }));
+        }
+
+        // Pending: another request with this key is running, unless it was abandoned. Guarded on
+        // created_at so only one retry takes over an abandoned key.
+        let still_processing = AppError::Conflict("A request with this idempotency key is still being processed".to_string());
+        if now - existing.created_at < Duration::seconds(PENDING_TIMEOUT_SECS) {
+            return Err(still_processing);
+        }
+        let taken_over = idempotency_record::Entity::update_many()
+            .col_expr(idempotency_record::Column::CreatedAt, Expr::value(now))
+            .filter(idempotency_record::Column::IdempotencyKey.eq(key))
+            .filter(idempotency_record::Column::Status.eq(idempotency_record::STATUS_PENDING))
+            .filter(idempotency_record::Column::CreatedAt.eq(existing.created_at))
+            .exec(db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error claiming idempotency key: {}", err)))?;
+        if taken_over.rows_affected == 0 {
+            return Err(still_processing);
+        }
+        Ok(None)
+    }
+
+    // The command already ran, so a failure here is only logged. The key then stays pending
+    // until it times out.
+    async fn complete<T: Serialize>(db: &DatabaseConnection, key: &str, data: &T, message: Option<&str>) {
+        let response = match serde_json::to_value(data) {
+            Ok(response) => response,
+            Err(err) => {
+                eprintln!("Failed to encode response for idempotency key {}: {}", key, err);
+                return;
+            }
+        };
+        let completed = idempotency_record::Entity::update_many()
+            .col_expr(idempotency_record::Column::Status, Expr::value(idempotency_record::STATUS_COMPLETED))
+            .col_expr(idempotency_record::Column::Response, Expr::value(response))
+            .col_expr(idempotency_record::Column::Message, Expr::value(message.map(str::to_string)))
+            .col_expr(idempotency_record::Column::CompletedAt, Expr::value(clock::jakarta_now()))
+            .filter(idempotency_record::Column::IdempotencyKey.eq(key))
+            .exec(db)
+            .await;
+        if let Err(err) = completed {
+            eprintln!("Failed to store response for idempotency key {}: {}", key, err);
+        }
+    }
+
+    // Free the key after a failure so the same request can be retried
+    async fn release(db: &DatabaseConnection, key: &str) {
+        let released = idempotency_record::Entity::delete_many()
+            .filter(idempotency_record::Column::IdempotencyKey.eq(key))
+            .filter(idempotency_record::Column::Status.eq(idempotency_record::STATUS_PENDING))
+            .exec(db)
+            .await;
+        if let Err(err) = released {
+            eprintln!("Failed to release idempotency key {}: {}", key, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::test_support::{self, data, error};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn key() -> Option<String> {
+        Some("3f2c9a1e-7b4d-4e8a-9c1f-2d6b8e0a5c73".to_string())
+    }
+
+    async fn count_run(runs: &AtomicUsize) -> Result<ApiResponse<String>, AppError> {
+        let run = runs.fetch_add(1, Ordering::SeqCst) + 1;
+        Ok(ApiResponse::success(format!("run {}", run)))
+    }
+
+    #[tokio::test]
+    async fn a_repeated_key_returns_the_first_answer_without_running_again() {
+        let state = test_support::test_state().await;
+        let runs = AtomicUsize::new(0);
+
+        let first = Idempotency::run(&state.db, key(), "top_up", json!({ "amount": "100" }), count_run(&runs)).await;
+        let second = Idempotency::run(&state.db, key(), "top_up", json!({ "amount": "100" }), count_run(&runs)).await;
+        assert_eq!(data(first), "run 1");
+        assert_eq!(data(second), "run 1");
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        let reused = Idempotency::run(&state.db, key(), "top_up", json!({ "amount": "999" }), count_run(&runs)).await;
+        assert_eq!(error(reused).code(), ErrorCode::Conflict);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn failures_release_the_key_for_a_retry() {
+        let state = test_support::test_state().await;
+        let failed = async { Err::<ApiResponse<String>, _>(AppError::Conflict("Only 1 left in stock".to_string())) };
+        assert!(Idempotency::run(&state.db, key(), "order", json!({}), failed).await.is_err());
+
+        let retried = Idempotency::run(&state.db, key(), "order", json!({}), async { Ok(ApiResponse::success(1)) }).await;
+        assert_eq!(data(retried), 1);
+    }
+
+    #[tokio::test]
+    async fn a_pending_key_is_refused_until_it_times_out() {
+        let state = test_support::test_state().await;
+        let claimed = Idempotency::claim::<String>(&state.db, "pending-key", "order", &json!({})).await.unwrap();
+        assert!(claimed.is_none());
+
+        let busy = Idempotency::run(&state.db, Some("pending-key".to_string()), "order", json!({}), async {
+            Ok(ApiResponse::success("ran".to_string()))
+        })
+        .await;
+        assert_eq!(error(busy).code(), ErrorCode::Conflict);
+
+        idempotency_record::Entity::update_many()
+            .col_expr(
+                idempotency_record::Column::CreatedAt,
+                Expr::value(clock::jakarta_now() - Duration::seconds(PENDING_TIMEOUT_SECS + 1)),
+            )
+            .exec(&state.db)
+            .await
+            .unwrap();
+        let taken_over = Idempotency::run(&state.db, Some("pending-key".to_string()), "order", json!({}), async {
+            Ok(ApiResponse::success("ran".to_string()))
+        })
+        .await;
+        assert_eq!(data(taken_over), "ran");
+    }
+}
@@ -1,29 +1,57 @@
 use anyhow::Result;
-use controllers::broadcast_message_handler::BroadcastMessageHandler;
-use controllers::chat_handler::{ChatHandler, ChatWithCustomerName, MessageWithSenderName};
+use controllers::audit_event_handler::AuditEventHandler;
+use controllers::broadcast_message_handler::{BroadcastDeliveryStats, BroadcastMessageHandler, RecipientBroadcast};
+use controllers::chat_handler::{ChatHandler, ChatWithCustomerName, MessageSearchResult, MessageWithSenderName, NewAttachment};
+use controllers::customer_service_ticket_handler::{CustomerServiceMetrics, CustomerServiceTicketHandler, QueuedTicket};
+use controllers::dependency_handler::{DependencyHandler, DependencyReport};
+use controllers::health_handler::{HealthHandler, HealthReport};
 use controllers::income_report_handler::{IncomeReport, IncomeReportHandler};
-use controllers::lost_and_found_items_log_handler::LostAndFoundItemsLogHandler;
+use controllers::lost_and_found_items_log_handler::{LostAndFoundItemsLogHandler, LostAndFoundMatch, OwnerVerification};
 use controllers::maintenance_schedule_handler::MaintenanceScheduleHandler;
-use deadpool_redis::{redis::cmd, Config as RedisConfig, Pool as RedisPool, Runtime};
+use controllers::offline_sync_handler::OfflineSyncHandler;
+use deadpool_redis::{Config as RedisConfig, PoolConfig as RedisPoolConfig, Runtime, Timeouts as RedisTimeouts};
 use dotenv::dotenv;
 use entity::broadcast_message;
-use sea_orm::{Database, DatabaseConnection};
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{ConnectOptions, ConnectionTrait, Database, DatabaseConnection, DbBackend};
 use serde::{Deserialize, Serialize};
 use std::env;
+use std::future::Future;
+use std::sync::Arc;
 use std::{fs::File, io::Read};
-use tauri::{Emitter, Manager, State};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use session::Session;
 
 use controllers::souvenir_handler::SouvenirHandler;
 use controllers::order_restaurant_handler::OrderRestaurantHandler;
 use controllers::order_souvenir_handler::OrderSouvenirHandler;
 use controllers::store_handler::StoreHandler;
 use controllers::customer_handler::CustomerHandler;
-use controllers::staff_handler::StaffHandler;
+use controllers::staff_handler::{PasswordPolicy, StaffHandler, StaffLoginResult};
 use controllers::menu_item_handler::MenuItemHandler;
 use controllers::restaurant_handler::RestaurantHandler;
 use controllers::ride_handler::RideHandler;
 use controllers::ride_queue_handler::RideQueueHandler;
+pub mod cache;
+pub mod clock;
 pub mod controllers;
+pub mod error;
+#[cfg(feature = "http-api")]
+pub mod http_api;
+pub mod idempotency;
+pub mod login_guard;
+pub mod offline;
+pub mod repository;
+pub mod session;
+pub mod storage;
+#[cfg(test)]
+mod test_support;
+pub mod validation;
+use cache::{Cache, CacheTtls, FallbackCacheBackend, RedisCacheBackend};
+use error::AppError;
+use offline::{OfflineStatus, OfflineStore, OfflineWrite, DEFAULT_OFFLINE_DATABASE_URL};
+use storage::{AttachmentStorage, LocalFileStorage};
 
 
 #[derive(Serialize, Deserialize)]
@@ -36,16 +64,34 @@ struct UiConfig {
     ui_id: String,
 }
 
+// Why the app state could not be built; managed instead of AppState so the UI can say so
+struct StartupError(String);
+
+// What every handler works against. Each part is swappable: the database may be any SeaORM
+// backend, and the cache and attachment store are traits with in-memory implementations.
 pub struct AppState {
     db: DatabaseConnection,
-    redis_pool: RedisPool,
+    cache: Cache,
+    storage: Arc<dyn AttachmentStorage>,
+}
+
+impl AppState {
+    pub fn new(db: DatabaseConnection, cache: Cache, storage: Arc<dyn AttachmentStorage>) -> Self {
+        AppState { db, cache, storage }
+    }
 }
 
 #[derive(Serialize)]
 #[serde(tag = "status", rename_all = "lowercase")]
 pub enum ApiResponse<T: Serialize> {
     Success { data: T, message: Option<String> },
-    Error { data: Option<T>, message: String },
+    // Saved on this kiosk while the central database is unreachable; applied once it is back
+    Queued { data: T, idempotency_key: String },
+    Error {
+        data: Option<T>,
+        #[serde(flatten)]
+        error: AppError,
+    },
 }
 
 impl<T: Serialize> ApiResponse<T> {
@@ -56,89 +102,54 @@ impl<T: Serialize> ApiResponse<T> {
         }
     }
 
-    pub fn error(message: String) -> Self {
-        ApiResponse::Error {
-            data: None,
-            message,
-        }
+    pub fn error(error: AppError) -> Self {
+        ApiResponse::Error { data: None, error }
     }
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct CachedData<T> {
-    data: T,
-}
 
-pub async fn cache_set<T: Serialize>(pool: &RedisPool, key: &str, value: &T, ttl: usize) {
-    let mut conn = match pool.get().await {
-        Ok(conn) => conn,
-        Err(err) => {
-            eprintln!("Redis error (cache_set - get connection): {}", err);
-            return; // ✅ Silently fail, don't stop execution
+    // For handlers that build on another handler's answer
+    pub fn into_data(self) -> Result<T, AppError> {
+        match self {
+            ApiResponse::Success { data, .. } | ApiResponse::Queued { data, .. } => Ok(data),
+            ApiResponse::Error { error, .. } => Err(error),
         }
-    };
-
-    let json_value = match serde_json::to_string(&CachedData { data: value }) {
-        Ok(json) => json,
-        Err(err) => {
-            eprintln!("Redis error (cache_set - serialization): {}", err);
-            return;
-        }
-    };
-
-    if let Err(err) = cmd("SETEX")
-        .arg(&[key, &ttl.to_string(), &json_value])
-        .query_async::<()>(&mut conn)
-        .await
-    {
-        eprintln!("Redis error (cache_set - SETEX): {}", err);
     }
 }
 
-pub async fn cache_delete(pool: &RedisPool, key: &str) {
-    let mut conn = match pool.get().await {
-        Ok(conn) => conn,
-        Err(err) => {
-            eprintln!("Redis error (cache_delete - get connection): {}", err);
-            return;
-        }
-    };
-
-    if let Err(err) = cmd("DEL").arg(&[key]).query_async::<()>(&mut conn).await {
-        eprintln!("Redis error (cache_delete - DEL): {}", err);
+impl<T: Serialize> From<AppError> for ApiResponse<T> {
+    fn from(error: AppError) -> Self {
+        ApiResponse::error(error)
     }
 }
 
-pub async fn cache_get<T: for<'de> Deserialize<'de>>(pool: &RedisPool, key: &str) -> Option<T> {
-    let mut conn = match pool.get().await {
-        Ok(conn) => conn,
-        Err(err) => {
-            eprintln!("Redis error (cache_get - get connection): {}", err);
-            return None; // ✅ Silently fail
-        }
-    };
+// Tauri needs a Result from async commands that borrow state, but errors are folded into the
+// envelope so every command resolves with the same shape and never rejects
+type CommandResult<T> = Result<ApiResponse<T>, AppError>;
 
-    let cached_data: Option<String> = match cmd("GET").arg(&[key]).query_async(&mut conn).await {
-        Ok(value) => value,
-        Err(err) => {
-            eprintln!("Redis error (cache_get - GET): {}", err);
-            return None;
-        }
-    };
+fn respond<T: Serialize>(result: Result<ApiResponse<T>, AppError>) -> CommandResult<T> {
+    Ok(result.unwrap_or_else(ApiResponse::from))
+}
 
-    if let Some(json_str) = cached_data {
-        match serde_json::from_str::<CachedData<T>>(&json_str) {
-            Ok(wrapper) => Some(wrapper.data),
-            Err(err) => {
-                eprintln!("Redis error (cache_get - deserialization): {}", err);
-                None
-            }
-        }
-    } else {
-        None
+// Run a command as whoever signed in with `session_token`, the token customer_login or
+// staff_login returned. Without a valid session the command is refused and never runs.
+async fn as_actor<T, F, Fut>(state: &AppState, session_token: &str, command: F) -> CommandResult<T>
+where
+    T: Serialize,
+    F: FnOnce(String) -> Fut,
+    Fut: Future<Output = Result<ApiResponse<T>, AppError>>,
+{
+    match Session::actor(&state.db, session_token).await {
+        Ok(actor_id) => respond(command(actor_id).await),
+        Err(err) => Ok(err.into()),
     }
 }
 
+// The actor for a write a kiosk may have to queue. While the central database is unreachable a
+// session this kiosk already verified still counts, so customers can keep ordering offline.
+async fn kiosk_actor(state: &AppState, offline: &OfflineStore, session_token: &str) -> Result<String, AppError> {
+    let verify = async { Session::actor(&state.db, session_token).await.map(ApiResponse::success) };
+    offline.read_through(&state.db, &Session::offline_key(session_token), verify).await?.into_data()
+}
+
 fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
     println!("Current working directory: {:?}", env::current_dir()?);
     let mut file = File::open("config/config.json")?;
@@ -176,16 +187,70 @@ fn map_ui_id_to_name(ui_id: &str) -> &str {
 
 // Get UI ID from config.json
 #[tauri::command]
-fn get_ui_name_from_config() -> String {
-    let config = load_config().expect("Failed to load config");
+fn get_ui_name_from_config() -> ApiResponse<String> {
+    match load_config() {
+        Ok(config) => ApiResponse::success(map_ui_id_to_name(&config.chosen_ui.ui_id).to_string()),
+        Err(err) => ApiResponse::error(AppError::Internal(format!("Failed to load config: {}", err))),
+    }
+}
+
+// Database and cache status for the startup screen and the staff UI. Works even when
+// startup failed, in which case it reports why.
+#[tauri::command]
+async fn health_check(app: AppHandle) -> CommandResult<HealthReport> {
+    if let Some(state) = app.try_state::<AppState>() {
+        return respond(HealthHandler::health_check(&state).await);
+    }
+    let error = app
+        .try_state::<StartupError>()
+        .map(|startup_error| startup_error.0.clone())
+        .unwrap_or_else(|| "VorteKia is still starting".to_string());
+    Ok(ApiResponse::success(HealthHandler::startup_failed(&error)))
+}
 
-    map_ui_id_to_name(&config.chosen_ui.ui_id).to_string()
+// Whether this kiosk is working from its offline store and how much it still has to send
+#[tauri::command]
+async fn get_offline_status(offline: State<'_, OfflineStore>) -> CommandResult<OfflineStatus> {
+    respond(offline.status().await.map(ApiResponse::success))
+}
+
+#[tauri::command]
+async fn view_sync_conflicts(
+    state: State<'_, AppState>,
+    session_token: String,
+    include_resolved: Option<bool>,
+) -> CommandResult<Vec<entity::offline_sync_conflict::Model>> {
+    as_actor(&state, &session_token, |staff_id| {
+        OfflineSyncHandler::view_sync_conflicts(&state, staff_id, include_resolved.unwrap_or(false))
+    })
+    .await
+}
+
+#[tauri::command]
+async fn retry_sync_conflict(state: State<'_, AppState>, conflict_id: String, session_token: String) -> CommandResult<String> {
+    as_actor(&state, &session_token, |staff_id| OfflineSyncHandler::retry_sync_conflict(&state, conflict_id, staff_id)).await
+}
+
+#[tauri::command]
+async fn dismiss_sync_conflict(state: State<'_, AppState>, conflict_id: String, session_token: String) -> CommandResult<String> {
+    as_actor(&state, &session_token, |staff_id| OfflineSyncHandler::dismiss_sync_conflict(&state, conflict_id, staff_id)).await
 }
 
 // Customer login
 #[tauri::command]
-async fn customer_login(state: State<'_, AppState>, customer_id: String) -> Result<ApiResponse<String>, String> {
-    CustomerHandler::customer_login(&state, customer_id).await
+async fn customer_login(
+    state: State<'_, AppState>,
+    customer_id: String,
+    pin: String,
+    device_id: Option<String>,
+) -> CommandResult<String> {
+    respond(CustomerHandler::customer_login(&state, customer_id, pin, device_id).await)
+}
+
+// End the session customer_login or staff_login opened
+#[tauri::command]
+async fn logout(state: State<'_, AppState>, session_token: String) -> CommandResult<String> {
+    respond(Session::close(&state.db, &session_token).await.map(|_| ApiResponse::success("Signed out".to_string())))
 }
 
 // Get customer details by ID
@@ -193,15 +258,15 @@ async fn customer_login(state: State<'_, AppState>, customer_id: String) -> Resu
 async fn get_customer_details(
     state: State<'_, AppState>,
     customer_id: String,
-) -> Result<ApiResponse<entity::customer::Model>, String> {
-    CustomerHandler::get_customer_details(&state, customer_id).await
+) -> CommandResult<entity::customer::Model> {
+    respond(CustomerHandler::get_customer_details(&state, customer_id).await)
 }
 
 
 // View all customer accounts
 #[tauri::command]
-async fn view_customer_accounts(state: State<'_, AppState>) -> Result<ApiResponse<Vec<entity::customer::Model>>, String> {
-    CustomerHandler::view_customer_accounts(&state).await
+async fn view_customer_accounts(state: State<'_, AppState>) -> CommandResult<Vec<entity::customer::Model>> {
+    respond(CustomerHandler::view_customer_accounts(&state).await)
 }
 
 // Save (create) a new customer
@@ -210,8 +275,10 @@ async fn save_customer_data(
     state: State<'_, AppState>,
     name: String,
     virtual_balance: String,
-) -> Result<ApiResponse<String>, String> {
-    CustomerHandler::save_customer_data(&state, name, virtual_balance).await
+    pin: String,
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| CustomerHandler::save_customer_data(&state, name, virtual_balance, pin, actor_id)).await
 }
 
 // Update an existing customer
@@ -221,8 +288,10 @@ async fn update_customer_data(
     customer_id: String,
     name: Option<String>,
     virtual_balance: Option<String>,
-) -> Result<String, String> {
-    CustomerHandler::update_customer_data(&state, customer_id, name, virtual_balance).await
+    pin: Option<String>,
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| CustomerHandler::update_customer_data(&state, customer_id, name, virtual_balance, pin, actor_id)).await
 }
 
 // Top up virtual balance
@@ -231,8 +300,13 @@ async fn top_up_virtual_balance(
     state: State<'_, AppState>,
     customer_id: String,
     top_up_amount_str: String,
-) -> Result<ApiResponse<String>, String> {
-    CustomerHandler::top_up_virtual_balance(&state, customer_id, top_up_amount_str).await
+    session_token: String,
+    idempotency_key: Option<String>, // Client-generated per top-up, so a retry is not charged twice
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| {
+        CustomerHandler::top_up_virtual_balance(&state, customer_id, top_up_amount_str, actor_id, idempotency_key)
+    })
+    .await
 }
 
 // Delete a customer
@@ -240,8 +314,26 @@ async fn top_up_virtual_balance(
 async fn delete_customer_data(
     state: State<'_, AppState>,
     customer_id: String,
-) -> Result<String, String> {
-    CustomerHandler::delete_customer_data(&state, customer_id).await
+    cascade: Option<bool>,
+    session_token: String,
+) -> CommandResult<DependencyReport> {
+    as_actor(&state, &session_token, |actor_id| CustomerHandler::delete_customer_data(&state, customer_id, cascade.unwrap_or(false), actor_id)).await
+}
+
+#[tauri::command]
+async fn restore_customer_data(
+    state: State<'_, AppState>,
+    customer_id: String,
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| CustomerHandler::restore_customer_data(&state, customer_id, actor_id)).await
+}
+
+#[tauri::command]
+async fn view_archived_customers(
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<entity::customer::Model>> {
+    respond(CustomerHandler::view_archived_customers(&state).await)
 }
 
 // Staff related commands
@@ -250,43 +342,79 @@ async fn staff_login(
     state: State<'_, AppState>,
     email: String,
     password: String,
-) -> Result<ApiResponse<String>, String> {
-    StaffHandler::staff_login(&state, email, password).await
+    device_id: Option<String>,
+) -> CommandResult<StaffLoginResult> {
+    respond(StaffHandler::staff_login(&state, email, password, device_id).await)
+}
+
+#[tauri::command]
+fn view_password_policy() -> ApiResponse<PasswordPolicy> {
+    StaffHandler::view_password_policy()
+}
+
+#[tauri::command]
+async fn change_staff_password(
+    state: State<'_, AppState>,
+    staff_id: String,
+    current_password: String,
+    new_password: String,
+) -> CommandResult<String> {
+    respond(StaffHandler::change_staff_password(&state, staff_id, current_password, new_password).await)
+}
+
+// Executives issue a one-time reset token for staff who forgot their password
+#[tauri::command]
+async fn reset_staff_password(
+    state: State<'_, AppState>,
+    session_token: String,
+    staff_id: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |requester_id| StaffHandler::reset_staff_password(&state, requester_id, staff_id)).await
+}
+
+#[tauri::command]
+async fn complete_staff_password_reset(
+    state: State<'_, AppState>,
+    email: String,
+    token: String,
+    new_password: String,
+) -> CommandResult<String> {
+    respond(StaffHandler::complete_staff_password_reset(&state, email, token, new_password).await)
 }
 
 #[tauri::command]
 async fn get_staff_details(
     state: State<'_, AppState>,
     staff_id: String,
-) -> Result<ApiResponse<entity::staff::Model>, String> {
-    StaffHandler::get_staff_details(&state, staff_id).await
+) -> CommandResult<entity::staff::Model> {
+    respond(StaffHandler::get_staff_details(&state, staff_id).await)
 }
 
 #[tauri::command]
 async fn get_staff_details_by_email(
     state: State<'_, AppState>,
     email: String, 
-)-> Result<ApiResponse<entity::staff::Model>, String> {
-  StaffHandler::get_staff_details_by_email(&state, email).await
+)-> CommandResult<entity::staff::Model> {
+  respond(StaffHandler::get_staff_details_by_email(&state, email).await)
 }
 
 #[tauri::command]
 async fn view_staff_accounts(
     state: State<'_, AppState>,
-) -> Result<ApiResponse<Vec<entity::staff::Model>>, String> {
-    StaffHandler::view_staff_accounts(&state).await
+) -> CommandResult<Vec<entity::staff::Model>> {
+    respond(StaffHandler::view_staff_accounts(&state).await)
 }
 
 #[tauri::command]
 async fn view_ride_staffs( // New command
     state: State<'_, AppState>,
-) -> Result<ApiResponse<Vec<entity::staff::Model>>, String> {
-    StaffHandler::view_ride_staffs(&state).await // Call the new handler function
+) -> CommandResult<Vec<entity::staff::Model>> {
+    respond(StaffHandler::view_ride_staffs(&state).await) // Call the new handler function
 }
 
 #[tauri::command]
-async fn view_maintenance_staffs(state: State<'_, AppState>,) -> Result<ApiResponse<Vec<entity::staff::Model>>, String> {
-  StaffHandler::view_maintenance_staffs(&state).await
+async fn view_maintenance_staffs(state: State<'_, AppState>,) -> CommandResult<Vec<entity::staff::Model>> {
+    respond(StaffHandler::view_maintenance_staffs(&state).await)
 }
 
 #[tauri::command]
@@ -296,8 +424,9 @@ async fn save_staff_data(
     password: String,
     name: String,
     role: String,
-) -> Result<ApiResponse<String>, String> {
-    StaffHandler::save_staff_data(&state, email, password, name, role).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| StaffHandler::save_staff_data(&state, email, password, name, role, actor_id)).await
 }
 
 #[tauri::command]
@@ -307,32 +436,82 @@ async fn update_staff_data(
     email: Option<String>,
     name: Option<String>,
     role: Option<String>,
-) -> Result<ApiResponse<String>, String> {
-    StaffHandler::update_staff_data(&state, staff_id, email, name, role).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| StaffHandler::update_staff_data(&state, staff_id, email, name, role, actor_id)).await
 }
 
 #[tauri::command]
 async fn delete_staff_data(
     state: State<'_, AppState>,
     staff_id: String,
-) -> Result<String, String> {
-    StaffHandler::delete_staff_data(&state, staff_id).await
+    cascade: Option<bool>,
+    session_token: String,
+) -> CommandResult<DependencyReport> {
+    as_actor(&state, &session_token, |actor_id| StaffHandler::delete_staff_data(&state, staff_id, cascade.unwrap_or(false), actor_id)).await
+}
+
+#[tauri::command]
+async fn restore_staff_data(
+    state: State<'_, AppState>,
+    staff_id: String,
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| StaffHandler::restore_staff_data(&state, staff_id, actor_id)).await
+}
+
+#[tauri::command]
+async fn view_archived_staff(
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<entity::staff::Model>> {
+    respond(StaffHandler::view_archived_staff(&state).await)
+}
+
+// What would block (or be cascaded by) deleting a record, so the UI can warn up front
+#[tauri::command]
+async fn check_delete_dependencies(
+    state: State<'_, AppState>,
+    entity_type: String,
+    entity_id: String,
+) -> CommandResult<DependencyReport> {
+    respond(DependencyHandler::check_dependencies(&state, entity_type, entity_id).await)
+}
+
+// Audit trail of create/update/delete operations (executives only)
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn view_audit_events(
+    state: State<'_, AppState>,
+    session_token: String,
+    actor_id: Option<String>, // Filter: only this actor's events
+    entity_type: Option<String>,
+    entity_id: Option<String>,
+    from: Option<chrono::NaiveDateTime>,
+    to: Option<chrono::NaiveDateTime>,
+    limit: Option<u64>,
+) -> CommandResult<Vec<entity::audit_event::Model>> {
+    as_actor(&state, &session_token, |requester_id| {
+        AuditEventHandler::view_audit_events(&state, requester_id, actor_id, entity_type, entity_id, from, to, limit)
+    })
+    .await
 }
 
 // Restaurant related commands
 #[tauri::command]
 async fn view_restaurants(
     state: State<'_, AppState>,
-) -> Result<ApiResponse<Vec<entity::restaurant::Model>>, String> {
-    RestaurantHandler::view_restaurants(&state).await
+) -> CommandResult<Vec<entity::restaurant::Model>> {
+    respond(RestaurantHandler::view_restaurants(&state).await)
 }
 
 #[tauri::command]
 async fn get_restaurant_details(
     state: State<'_, AppState>,
+    offline: State<'_, OfflineStore>,
     restaurant_id: String,
-) -> Result<ApiResponse<entity::restaurant::Model>, String> {
-    RestaurantHandler::get_restaurant_details(&state, restaurant_id).await
+) -> CommandResult<entity::restaurant::Model> {
+    let key = format!("get_restaurant_details:{}", restaurant_id);
+    respond(offline.read_through(&state.db, &key, RestaurantHandler::get_restaurant_details(&state, restaurant_id)).await)
 }
 
 #[tauri::command]
@@ -345,8 +524,9 @@ async fn save_restaurant_data(
     cuisine_type: String,
     location: Option<String>,
     status: String,
-) -> Result<ApiResponse<String>, String> {
-    RestaurantHandler::save_restaurant_data(&state, name, photo, opening_time, closing_time, cuisine_type, location, status).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| RestaurantHandler::save_restaurant_data(&state, name, photo, opening_time, closing_time, cuisine_type, location, status, actor_id)).await
 }
 
 #[tauri::command]
@@ -360,8 +540,9 @@ async fn update_restaurant_data(
     cuisine_type: Option<String>,
     location: Option<Option<String>>,
     status: Option<String>,
-) -> Result<ApiResponse<String>, String> {
-    RestaurantHandler::update_restaurant_data(&state, restaurant_id, name, photo, opening_time, closing_time, cuisine_type, location, status).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| RestaurantHandler::update_restaurant_data(&state, restaurant_id, name, photo, opening_time, closing_time, cuisine_type, location, status, actor_id)).await
 }
 
 
@@ -369,8 +550,26 @@ async fn update_restaurant_data(
 async fn delete_restaurant_data(
     state: State<'_, AppState>,
     restaurant_id: String,
-) -> Result<String, String> {
-    RestaurantHandler::delete_restaurant_data(&state, restaurant_id).await
+    cascade: Option<bool>,
+    session_token: String,
+) -> CommandResult<DependencyReport> {
+    as_actor(&state, &session_token, |actor_id| RestaurantHandler::delete_restaurant_data(&state, restaurant_id, cascade.unwrap_or(false), actor_id)).await
+}
+
+#[tauri::command]
+async fn restore_restaurant_data(
+    state: State<'_, AppState>,
+    restaurant_id: String,
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| RestaurantHandler::restore_restaurant_data(&state, restaurant_id, actor_id)).await
+}
+
+#[tauri::command]
+async fn view_archived_restaurants(
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<entity::restaurant::Model>> {
+    respond(RestaurantHandler::view_archived_restaurants(&state).await)
 }
 
 
@@ -378,17 +577,19 @@ async fn delete_restaurant_data(
 #[tauri::command]
 async fn view_menu_items(
     state: State<'_, AppState>,
+    offline: State<'_, OfflineStore>,
     restaurant_id: Option<String>,
-) -> Result<ApiResponse<Vec<entity::menu_item::Model>>, String> {
-    MenuItemHandler::view_menu_items(&state, restaurant_id).await
+) -> CommandResult<Vec<entity::menu_item::Model>> {
+    let key = format!("view_menu_items:{}", restaurant_id.as_deref().unwrap_or("*"));
+    respond(offline.read_through(&state.db, &key, MenuItemHandler::view_menu_items(&state, restaurant_id)).await)
 }
 
 #[tauri::command]
 async fn get_menu_item_details(
     state: State<'_, AppState>,
     menu_item_id: String,
-) -> Result<ApiResponse<entity::menu_item::Model>, String> {
-    MenuItemHandler::get_menu_item_details(&state, menu_item_id).await
+) -> CommandResult<entity::menu_item::Model> {
+    respond(MenuItemHandler::get_menu_item_details(&state, menu_item_id).await)
 }
 
 #[tauri::command]
@@ -398,8 +599,9 @@ async fn save_menu_item_data(
     name: String,
     price: String,
     restaurant_id: String,
-) -> Result<ApiResponse<String>, String> {
-    MenuItemHandler::save_menu_item_data(&state, photo, name, price, restaurant_id).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| MenuItemHandler::save_menu_item_data(&state, photo, name, price, restaurant_id, actor_id)).await
 }
 
 #[tauri::command]
@@ -410,24 +612,43 @@ async fn update_menu_item_data(
     name: Option<String>,
     price: Option<String>,
     restaurant_id: Option<String>,
-) -> Result<ApiResponse<String>, String> {
-    MenuItemHandler::update_menu_item_data(&state, menu_item_id, photo, name, price, restaurant_id).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| MenuItemHandler::update_menu_item_data(&state, menu_item_id, photo, name, price, restaurant_id, actor_id)).await
 }
 
 #[tauri::command]
 async fn delete_menu_item_data(
     state: State<'_, AppState>,
     menu_item_id: String,
-) -> Result<String, String> {
-    MenuItemHandler::delete_menu_item_data(&state, menu_item_id).await
+    cascade: Option<bool>,
+    session_token: String,
+) -> CommandResult<DependencyReport> {
+    as_actor(&state, &session_token, |actor_id| MenuItemHandler::delete_menu_item_data(&state, menu_item_id, cascade.unwrap_or(false), actor_id)).await
+}
+
+#[tauri::command]
+async fn restore_menu_item_data(
+    state: State<'_, AppState>,
+    menu_item_id: String,
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| MenuItemHandler::restore_menu_item_data(&state, menu_item_id, actor_id)).await
+}
+
+#[tauri::command]
+async fn view_archived_menu_items(
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<entity::menu_item::Model>> {
+    respond(MenuItemHandler::view_archived_menu_items(&state).await)
 }
 
 #[tauri::command]
 async fn view_order_restaurants(
     state: tauri::State<'_, AppState>,
     restaurant_id: Option<String>,
-) -> Result<ApiResponse<Vec<entity::order_restaurant::Model>>, String> {
-    OrderRestaurantHandler::view_order_restaurants(&state, restaurant_id).await
+) -> CommandResult<Vec<entity::order_restaurant::Model>> {
+    respond(OrderRestaurantHandler::view_order_restaurants(&state, restaurant_id).await)
 }
 
 #[tauri::command]
@@ -435,19 +656,35 @@ async fn view_order_restaurants_by_customer(
     state: tauri::State<'_, AppState>,
     customer_id: String,
     restaurant_id: String,
-) -> Result<ApiResponse<Vec<entity::order_restaurant::Model>>, String> {
-    OrderRestaurantHandler::view_order_restaurants_by_customer(&state, customer_id, restaurant_id).await
+) -> CommandResult<Vec<entity::order_restaurant::Model>> {
+    respond(OrderRestaurantHandler::view_order_restaurants_by_customer(&state, customer_id, restaurant_id).await)
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn save_order_restaurant_data(
     state: tauri::State<'_, AppState>,
+    offline: State<'_, OfflineStore>,
     customer_id: String,
     restaurant_id: String,
     menu_item_id: String,
     quantity: i32,
-) -> Result<ApiResponse<String>, String> {
-    OrderRestaurantHandler::save_order_restaurant_data(&state, customer_id, restaurant_id, menu_item_id, quantity).await
+    session_token: String,
+    idempotency_key: Option<String>,
+) -> CommandResult<String> {
+    let actor_id = match kiosk_actor(&state, &offline, &session_token).await {
+        Ok(actor_id) => actor_id,
+        Err(err) => return Ok(err.into()),
+    };
+    let write = OfflineWrite::OrderRestaurant {
+        customer_id: customer_id.clone(),
+        restaurant_id: restaurant_id.clone(),
+        menu_item_id: menu_item_id.clone(),
+        quantity,
+        actor_id: Some(actor_id.clone()),
+    };
+    let apply = OrderRestaurantHandler::save_order_restaurant_data(&state, customer_id, restaurant_id, menu_item_id, quantity, actor_id, idempotency_key.clone());
+    respond(offline.write_through(&state.db, write, idempotency_key, apply).await)
 }
 
 #[tauri::command]
@@ -455,34 +692,40 @@ async fn update_order_restaurant_status(
     state: tauri::State<'_, AppState>,
     order_restaurant_id: String,
     status: String,
-) -> Result<ApiResponse<String>, String> {
-    OrderRestaurantHandler::update_order_restaurant_status(&state, order_restaurant_id, status).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| OrderRestaurantHandler::update_order_restaurant_status(&state, order_restaurant_id, status, actor_id)).await
 }
 
 #[tauri::command]
 async fn delete_order_restaurant_data(
     state: tauri::State<'_, AppState>,
     order_restaurant_id: String,
-) -> Result<ApiResponse<String>, String> {
-    OrderRestaurantHandler::delete_order_restaurant_data(&state, order_restaurant_id).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| OrderRestaurantHandler::delete_order_restaurant_data(&state, order_restaurant_id, actor_id)).await
 }
 
 #[tauri::command]
 async fn view_rides(
     state: State<'_, AppState>,
-) -> Result<ApiResponse<Vec<entity::ride::Model>>, String> {
-    RideHandler::view_rides(&state).await
+    offline: State<'_, OfflineStore>,
+) -> CommandResult<Vec<entity::ride::Model>> {
+    respond(offline.read_through(&state.db, "view_rides", RideHandler::view_rides(&state)).await)
 }
 
 #[tauri::command]
 async fn get_ride_details(
     state: State<'_, AppState>,
+    offline: State<'_, OfflineStore>,
     ride_id: String,
-) -> Result<ApiResponse<entity::ride::Model>, String> {
-    RideHandler::get_ride_details(&state, ride_id).await
+) -> CommandResult<entity::ride::Model> {
+    let key = format!("get_ride_details:{}", ride_id);
+    respond(offline.read_through(&state.db, &key, RideHandler::get_ride_details(&state, ride_id)).await)
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn save_ride_data(
     state: State<'_, AppState>,
     status: String,
@@ -491,8 +734,9 @@ async fn save_ride_data(
     location: String,
     staff_id: String,
     photo: Option<String>,
-) -> Result<ApiResponse<String>, String> {
-    RideHandler::save_ride_data(&state, status, name, price, location, staff_id, photo).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| RideHandler::save_ride_data(&state, status, name, price, location, staff_id, photo, actor_id)).await
 }
 
 #[tauri::command]
@@ -505,36 +749,78 @@ async fn update_ride_data(
     location: Option<String>,
     staff_id: Option<String>,
     photo: Option<Option<String>>,
-) -> Result<ApiResponse<String>, String> {
-    RideHandler::update_ride_data(&state, ride_id, status, name, price, location, staff_id, photo).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| RideHandler::update_ride_data(&state, ride_id, status, name, price, location, staff_id, photo, actor_id)).await
 }
 
 #[tauri::command]
 async fn delete_ride_data(
     state: State<'_, AppState>,
     ride_id: String,
-) -> Result<String, String> {
-    RideHandler::delete_ride_data(&state, ride_id).await
+    cascade: Option<bool>,
+    session_token: String,
+) -> CommandResult<DependencyReport> {
+    as_actor(&state, &session_token, |actor_id| RideHandler::delete_ride_data(&state, ride_id, cascade.unwrap_or(false), actor_id)).await
+}
+
+#[tauri::command]
+async fn restore_ride_data(
+    state: State<'_, AppState>,
+    ride_id: String,
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| RideHandler::restore_ride_data(&state, ride_id, actor_id)).await
+}
+
+#[tauri::command]
+async fn view_archived_rides(
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<entity::ride::Model>> {
+    respond(RideHandler::view_archived_rides(&state).await)
 }
 
 #[tauri::command]
 async fn view_ride_queues(
     state: State<'_, AppState>,
+    offline: State<'_, OfflineStore>,
     ride_id: Option<String>,
-) -> Result<ApiResponse<Vec<entity::ride_queue::Model>>, String> {
-    RideQueueHandler::view_ride_queues(&state, ride_id).await
+) -> CommandResult<Vec<entity::ride_queue::Model>> {
+    let key = format!("view_ride_queues:{}", ride_id.as_deref().unwrap_or("*"));
+    respond(offline.read_through(&state.db, &key, RideQueueHandler::view_ride_queues(&state, ride_id)).await)
+}
+
+fn parse_queue_position(queue_position: &str) -> Result<rust_decimal::Decimal, AppError> {
+    queue_position
+        .parse::<rust_decimal::Decimal>()
+        .map_err(|e| AppError::invalid_field("queue_position", format!("Invalid queue position: {}", e)))
 }
 
 #[tauri::command]
 async fn save_ride_queue_data(
     state: State<'_, AppState>,
+    offline: State<'_, OfflineStore>,
     ride_id: String,
     customer_id: String,
     queue_position: String,
-) -> Result<ApiResponse<String>, String> {
-    let position = queue_position.parse::<rust_decimal::Decimal>()
-        .map_err(|e| format!("Invalid queue position: {}", e))?;
-    RideQueueHandler::save_ride_queue_data(&state, ride_id, customer_id, position).await
+    session_token: String,
+    idempotency_key: Option<String>,
+) -> CommandResult<String> {
+    let actor_id = match kiosk_actor(&state, &offline, &session_token).await {
+        Ok(actor_id) => actor_id,
+        Err(err) => return Ok(err.into()),
+    };
+    let position = match parse_queue_position(&queue_position) {
+        Ok(position) => position,
+        Err(err) => return Ok(err.into()),
+    };
+    let write = OfflineWrite::RideQueueJoin {
+        ride_id: ride_id.clone(),
+        customer_id: customer_id.clone(),
+        actor_id: Some(actor_id.clone()),
+    };
+    let apply = RideQueueHandler::save_ride_queue_data(&state, ride_id, customer_id, position, actor_id, idempotency_key.clone());
+    respond(offline.write_through(&state.db, write, idempotency_key, apply).await)
 }
 
 #[tauri::command]
@@ -542,36 +828,43 @@ async fn update_queue_position(
     state: State<'_, AppState>,
     ride_queue_id: String,
     queue_position: String,
-) -> Result<ApiResponse<String>, String> {
-    let position = queue_position.parse::<rust_decimal::Decimal>()
-        .map_err(|e| format!("Invalid queue position: {}", e))?;
-    RideQueueHandler::update_queue_position(&state, ride_queue_id, position).await
+    session_token: String,
+) -> CommandResult<String> {
+    let position = match parse_queue_position(&queue_position) {
+        Ok(position) => position,
+        Err(err) => return Ok(err.into()),
+    };
+    as_actor(&state, &session_token, |actor_id| RideQueueHandler::update_queue_position(&state, ride_queue_id, position, actor_id)).await
 }
 
 #[tauri::command]
 async fn delete_ride_queue_data(
     state: State<'_, AppState>,
     ride_queue_id: String,
-) -> Result<String, String> {
-    RideQueueHandler::delete_ride_queue_data(&state, ride_queue_id).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| RideQueueHandler::delete_ride_queue_data(&state, ride_queue_id, actor_id)).await
 }
 
 #[tauri::command]
 async fn view_stores(
     state: State<'_, AppState>,
-) -> Result<ApiResponse<Vec<entity::store::Model>>, String> {
-    StoreHandler::view_stores(&state).await
+) -> CommandResult<Vec<entity::store::Model>> {
+    respond(StoreHandler::view_stores(&state).await)
 }
 
 #[tauri::command]
 async fn get_store_details(
     state: State<'_, AppState>,
+    offline: State<'_, OfflineStore>,
     store_id: String,
-) -> Result<ApiResponse<entity::store::Model>, String> {
-    StoreHandler::get_store_details(&state, store_id).await
+) -> CommandResult<entity::store::Model> {
+    let key = format!("get_store_details:{}", store_id);
+    respond(offline.read_through(&state.db, &key, StoreHandler::get_store_details(&state, store_id)).await)
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn save_store_data(
     state: State<'_, AppState>,
     name: String,
@@ -580,8 +873,9 @@ async fn save_store_data(
     closing_time: String,
     location: Option<String>,
     status: String,
-) -> Result<ApiResponse<String>, String> {
-    StoreHandler::save_store_data(&state, name, photo, opening_time, closing_time, location, status).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| StoreHandler::save_store_data(&state, name, photo, opening_time, closing_time, location, status, actor_id)).await
 }
 
 #[tauri::command]
@@ -594,32 +888,53 @@ async fn update_store_data(
     closing_time: Option<String>,
     location: Option<Option<String>>,
     status: Option<String>,
-) -> Result<ApiResponse<String>, String> {
-    StoreHandler::update_store_data(&state, store_id, name, photo, opening_time, closing_time, location, status).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| StoreHandler::update_store_data(&state, store_id, name, photo, opening_time, closing_time, location, status, actor_id)).await
 }
 
 #[tauri::command]
 async fn delete_store_data(
     state: State<'_, AppState>,
     store_id: String,
-) -> Result<String, String> {
-    StoreHandler::delete_store_data(&state, store_id).await
+    cascade: Option<bool>,
+    session_token: String,
+) -> CommandResult<DependencyReport> {
+    as_actor(&state, &session_token, |actor_id| StoreHandler::delete_store_data(&state, store_id, cascade.unwrap_or(false), actor_id)).await
+}
+
+#[tauri::command]
+async fn restore_store_data(
+    state: State<'_, AppState>,
+    store_id: String,
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| StoreHandler::restore_store_data(&state, store_id, actor_id)).await
+}
+
+#[tauri::command]
+async fn view_archived_stores(
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<entity::store::Model>> {
+    respond(StoreHandler::view_archived_stores(&state).await)
 }
 
 #[tauri::command]
 async fn view_souvenirs(
     state: State<'_, AppState>,
+    offline: State<'_, OfflineStore>,
     store_id: Option<String>,
-) -> Result<ApiResponse<Vec<entity::souvenir::Model>>, String> {
-    SouvenirHandler::view_souvenirs(&state, store_id).await
+) -> CommandResult<Vec<entity::souvenir::Model>> {
+    let key = format!("view_souvenirs:{}", store_id.as_deref().unwrap_or("*"));
+    respond(offline.read_through(&state.db, &key, SouvenirHandler::view_souvenirs(&state, store_id)).await)
 }
 
 #[tauri::command]
 async fn get_souvenir_details(
     state: State<'_, AppState>,
     souvenir_id: String,
-) -> Result<ApiResponse<entity::souvenir::Model>, String> {
-    SouvenirHandler::get_souvenir_details(&state, souvenir_id).await
+) -> CommandResult<entity::souvenir::Model> {
+    respond(SouvenirHandler::get_souvenir_details(&state, souvenir_id).await)
 }
 
 #[tauri::command]
@@ -630,11 +945,13 @@ async fn save_souvenir_data(
     price: String,
     stock: i32,
     store_id: String,
-) -> Result<ApiResponse<String>, String> {
-    SouvenirHandler::save_souvenir_data(&state, name, photo, price, stock, store_id).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| SouvenirHandler::save_souvenir_data(&state, name, photo, price, stock, store_id, actor_id)).await
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn update_souvenir_data(
     state: State<'_, AppState>,
     souvenir_id: String,
@@ -643,8 +960,9 @@ async fn update_souvenir_data(
     price: Option<String>,
     stock: Option<i32>,
     store_id: Option<String>,
-) -> Result<ApiResponse<String>, String> {
-    SouvenirHandler::update_souvenir_data(&state, souvenir_id, name, photo, price, stock, store_id).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| SouvenirHandler::update_souvenir_data(&state, souvenir_id, name, photo, price, stock, store_id, actor_id)).await
 }
 
 #[tauri::command]
@@ -652,23 +970,25 @@ async fn update_souvenir_stock(
     state: State<'_, AppState>,
     souvenir_id: String,
     stock: i32,
-) -> Result<ApiResponse<String>, String> {
-    SouvenirHandler::update_souvenir_stock(&state, souvenir_id, stock).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| SouvenirHandler::update_souvenir_stock(&state, souvenir_id, stock, actor_id)).await
 }
 
 #[tauri::command]
 async fn delete_souvenir_data(
     state: State<'_, AppState>,
     souvenir_id: String,
-) -> Result<String, String> {
-    SouvenirHandler::delete_souvenir_data(&state, souvenir_id).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| SouvenirHandler::delete_souvenir_data(&state, souvenir_id, actor_id)).await
 }
 
 #[tauri::command]
 async fn view_order_souvenirs(
     state: State<'_, AppState>,
-) -> Result<ApiResponse<Vec<entity::order_souvenir::Model>>, String> {
-    OrderSouvenirHandler::view_order_souvenirs(&state).await
+) -> CommandResult<Vec<entity::order_souvenir::Model>> {
+    respond(OrderSouvenirHandler::view_order_souvenirs(&state).await)
 }
 
 #[tauri::command]
@@ -676,41 +996,58 @@ async fn view_order_souvenirs_by_customer( // New command
     state: tauri::State<'_, AppState>,
     customer_id: String,
     store_id: String,
-) -> Result<ApiResponse<Vec<entity::order_souvenir::Model>>, String> {
-    OrderSouvenirHandler::view_order_souvenirs_by_customer(&state, customer_id, store_id).await
+) -> CommandResult<Vec<entity::order_souvenir::Model>> {
+    respond(OrderSouvenirHandler::view_order_souvenirs_by_customer(&state, customer_id, store_id).await)
 }
 
 #[tauri::command]
 async fn get_order_souvenir_details(
     state: State<'_, AppState>,
     order_souvenir_id: String,
-) -> Result<ApiResponse<entity::order_souvenir::Model>, String> {
-    OrderSouvenirHandler::get_order_souvenir_details(&state, order_souvenir_id).await
+) -> CommandResult<entity::order_souvenir::Model> {
+    respond(OrderSouvenirHandler::get_order_souvenir_details(&state, order_souvenir_id).await)
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn save_order_souvenir_data(
     state: State<'_, AppState>,
+    offline: State<'_, OfflineStore>,
     customer_id: String,
     store_id: String,
     souvenir_id: String,
     quantity: i32,
-) -> Result<ApiResponse<String>, String> {
-    OrderSouvenirHandler::save_order_souvenir_data(&state, customer_id, store_id, souvenir_id, quantity).await
+    session_token: String,
+    idempotency_key: Option<String>,
+) -> CommandResult<String> {
+    let actor_id = match kiosk_actor(&state, &offline, &session_token).await {
+        Ok(actor_id) => actor_id,
+        Err(err) => return Ok(err.into()),
+    };
+    let write = OfflineWrite::OrderSouvenir {
+        customer_id: customer_id.clone(),
+        store_id: store_id.clone(),
+        souvenir_id: souvenir_id.clone(),
+        quantity,
+        actor_id: Some(actor_id.clone()),
+    };
+    let apply = OrderSouvenirHandler::save_order_souvenir_data(&state, customer_id, store_id, souvenir_id, quantity, actor_id, idempotency_key.clone());
+    respond(offline.write_through(&state.db, write, idempotency_key, apply).await)
 }
 
 #[tauri::command]
 async fn delete_order_souvenir_data(
     state: State<'_, AppState>,
     order_souvenir_id: String,
-) -> Result<String, String> {
-    OrderSouvenirHandler::delete_order_souvenir_data(&state, order_souvenir_id).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| OrderSouvenirHandler::delete_order_souvenir_data(&state, order_souvenir_id, actor_id)).await
 }
 
 // Tauri commands for LostAndFoundItemsLogHandler
 #[tauri::command]
-async fn view_logs(state: State<'_, AppState>) -> Result<ApiResponse<Vec<entity::lost_and_found_items_log::Model>>, String> {
-    LostAndFoundItemsLogHandler::view_logs(&state).await
+async fn view_logs(state: State<'_, AppState>) -> CommandResult<Vec<entity::lost_and_found_items_log::Model>> {
+    respond(LostAndFoundItemsLogHandler::view_logs(&state).await)
 }
 
 #[tauri::command]
@@ -725,8 +1062,9 @@ async fn save_log_data(
     owner: Option<String>,
     found_location: Option<String>,
     status: String,
-) -> Result<ApiResponse<String>, String> {
-    LostAndFoundItemsLogHandler::save_log_data(
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |staff_id| LostAndFoundItemsLogHandler::save_log_data(
         &state,
         image,
         name,
@@ -737,7 +1075,9 @@ async fn save_log_data(
         owner,
         found_location,
         status,
-    ).await
+        staff_id,
+    ))
+    .await
 }
 
 #[tauri::command]
@@ -753,8 +1093,9 @@ async fn update_log_data(
     owner: Option<Option<String>>,
     found_location: Option<Option<String>>,
     status: Option<String>,
-) -> Result<ApiResponse<String>, String> {
-    LostAndFoundItemsLogHandler::update_log_data(
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |staff_id| LostAndFoundItemsLogHandler::update_log_data(
         &state,
         log_id,
         image,
@@ -766,55 +1107,197 @@ async fn update_log_data(
         owner,
         found_location,
         status,
-    ).await
+        staff_id,
+    ))
+    .await
+}
+
+#[tauri::command]
+async fn delete_log_data(state: State<'_, AppState>, log_id: String, session_token: String) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| LostAndFoundItemsLogHandler::delete_log_data(&state, log_id, actor_id)).await
+}
+
+#[tauri::command]
+async fn restore_log_data(state: State<'_, AppState>, log_id: String, session_token: String) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| LostAndFoundItemsLogHandler::restore_log_data(&state, log_id, actor_id)).await
+}
+
+#[tauri::command]
+async fn view_archived_logs(state: State<'_, AppState>) -> CommandResult<Vec<entity::lost_and_found_items_log::Model>> {
+    respond(LostAndFoundItemsLogHandler::view_archived_logs(&state).await)
 }
 
 #[tauri::command]
-async fn delete_log_data(state: State<'_, AppState>, log_id: String) -> Result<String, String> {
-    LostAndFoundItemsLogHandler::delete_log_data(&state, log_id).await
+async fn suggest_lost_and_found_matches(
+    state: State<'_, AppState>,
+    log_id: Option<String>,
+    limit: Option<u64>,
+) -> CommandResult<Vec<LostAndFoundMatch>> {
+    respond(LostAndFoundItemsLogHandler::suggest_matches(&state, log_id, limit).await)
+}
+
+#[tauri::command]
+async fn confirm_lost_and_found_match(
+    state: State<'_, AppState>,
+    missing_log_id: String,
+    found_log_id: String,
+    verification: OwnerVerification,
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |staff_id| {
+        LostAndFoundItemsLogHandler::confirm_match(&state, missing_log_id, found_log_id, verification, staff_id)
+    })
+    .await
+}
+
+#[tauri::command]
+async fn file_missing_report(
+    state: State<'_, AppState>,
+    session_token: String,
+    image: Option<String>,
+    name: String,
+    r#type: String,
+    color: String,
+    last_seen_location: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |customer_id| {
+        LostAndFoundItemsLogHandler::file_missing_report(&state, customer_id, image, name, r#type, color, last_seen_location)
+    })
+    .await
+}
+
+#[tauri::command]
+async fn view_lost_item_reports_by_customer(
+    state: State<'_, AppState>,
+    customer_id: String,
+) -> CommandResult<Vec<entity::lost_and_found_items_log::Model>> {
+    respond(LostAndFoundItemsLogHandler::view_reports_by_customer(&state, customer_id).await)
+}
+
+#[tauri::command]
+async fn return_log_to_owner(
+    state: State<'_, AppState>,
+    log_id: String,
+    verification: OwnerVerification,
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |staff_id| LostAndFoundItemsLogHandler::return_log_to_owner(&state, log_id, verification, staff_id)).await
+}
+
+#[tauri::command]
+async fn view_retention_flagged_logs(
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<entity::lost_and_found_items_log::Model>> {
+    respond(LostAndFoundItemsLogHandler::view_retention_flagged_logs(&state).await)
+}
+
+#[tauri::command]
+async fn dispose_log(
+    state: State<'_, AppState>,
+    log_id: String,
+    disposition: String,
+    session_token: String,
+    note: Option<String>,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |staff_id| LostAndFoundItemsLogHandler::dispose_log(&state, log_id, disposition, staff_id, note)).await
+}
+
+#[tauri::command]
+async fn get_log_history(
+    state: State<'_, AppState>,
+    log_id: String,
+) -> CommandResult<Vec<entity::lost_and_found_log_event::Model>> {
+    respond(LostAndFoundItemsLogHandler::get_log_history(&state, log_id).await)
 }
 
 // Chat related commands
 #[tauri::command]
 async fn view_chats(
     state: State<'_, AppState>,
-    user_id: String,
-) -> Result<ApiResponse<Vec<entity::chat::Model>>, String> {
-    ChatHandler::view_chats(&state, user_id).await
+    session_token: String,
+) -> CommandResult<Vec<entity::chat::Model>> {
+    as_actor(&state, &session_token, |user_id| ChatHandler::view_chats(&state, user_id)).await
 }
 
 #[tauri::command]
 async fn get_chat_details(
     state: State<'_, AppState>,
     chat_id: String,
-) -> Result<ApiResponse<entity::chat::Model>, String> {
-    ChatHandler::get_chat_details(&state, chat_id).await
+) -> CommandResult<entity::chat::Model> {
+    respond(ChatHandler::get_chat_details(&state, chat_id).await)
 }
 
 #[tauri::command]
 async fn save_chat_data(
     state: State<'_, AppState>,
     name: String,
-) -> Result<ApiResponse<String>, String> {
-    ChatHandler::save_chat_data(&state, name).await
+    chat_type: Option<String>,
+) -> CommandResult<String> {
+    respond(ChatHandler::save_chat_data(&state, name, chat_type).await)
 }
 
 #[tauri::command]
 async fn get_messages(
     state: State<'_, AppState>,
     chat_id: String,
-) -> Result<ApiResponse<Vec<MessageWithSenderName>>, String> {
-    ChatHandler::get_messages(&state, chat_id).await
+) -> CommandResult<Vec<MessageWithSenderName>> {
+    respond(ChatHandler::get_messages(&state, chat_id).await)
 }
 
 #[tauri::command]
 async fn save_message_data(
     state: State<'_, AppState>,
     chat_id: String,
-    sender_id: String,
     text: String,
-) -> Result<ApiResponse<String>, String> {
-    ChatHandler::save_message_data(&state, chat_id, sender_id, text).await
+    attachments: Option<Vec<NewAttachment>>,
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |sender_id| ChatHandler::save_message_data(&state, chat_id, sender_id, text, attachments)).await
+}
+
+#[tauri::command]
+async fn edit_message(
+    state: State<'_, AppState>,
+    message_id: String,
+    text: String,
+    session_token: String,
+) -> CommandResult<entity::message::Model> {
+    as_actor(&state, &session_token, |editor_id| ChatHandler::edit_message(&state, message_id, editor_id, text)).await
+}
+
+#[tauri::command]
+async fn delete_message(
+    state: State<'_, AppState>,
+    message_id: String,
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |requester_id| ChatHandler::delete_message(&state, message_id, requester_id)).await
+}
+
+#[tauri::command]
+async fn get_message_edit_history(
+    state: State<'_, AppState>,
+    message_id: String,
+) -> CommandResult<Vec<entity::message_edit::Model>> {
+    respond(ChatHandler::get_message_edit_history(&state, message_id).await)
+}
+
+#[tauri::command]
+async fn search_messages(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<u64>,
+    session_token: String,
+) -> CommandResult<Vec<MessageSearchResult>> {
+    as_actor(&state, &session_token, |user_id| ChatHandler::search_messages(&state, user_id, query, limit)).await
+}
+
+#[tauri::command]
+async fn get_attachment_data(
+    state: State<'_, AppState>,
+    attachment_id: String,
+) -> CommandResult<Vec<u8>> {
+    respond(ChatHandler::get_attachment_data(&state, attachment_id).await)
 }
 
 #[tauri::command]
@@ -822,51 +1305,113 @@ async fn save_chat_member_data(
     state: State<'_, AppState>,
     chat_id: String,
     user_id: String,
-) -> Result<ApiResponse<String>, String> {
-    ChatHandler::save_chat_member_data(&state, chat_id, user_id).await
+) -> CommandResult<String> {
+    respond(ChatHandler::save_chat_member_data(&state, chat_id, user_id).await)
 }
 
 #[tauri::command]
 async fn get_chat_members( // Example additional command
     state: State<'_, AppState>,
     chat_id: String,
-) -> Result<ApiResponse<Vec<entity::chat_member::Model>>, String> {
-    ChatHandler::get_chat_members(&state, chat_id).await
+) -> CommandResult<Vec<entity::chat_member::Model>> {
+    respond(ChatHandler::get_chat_members(&state, chat_id).await)
 }
 
 #[tauri::command]
 async fn get_customer_service_chat(
     state: State<'_, AppState>,
     customer_id: String,
-) -> Result<ApiResponse<entity::chat::Model>, String> {
-    ChatHandler::get_customer_service_chat(&state, customer_id).await
+) -> CommandResult<entity::chat::Model> {
+    respond(ChatHandler::get_customer_service_chat(&state, customer_id).await)
+}
+
+#[tauri::command]
+async fn get_direct_chat(
+    state: State<'_, AppState>,
+    other_staff_id: String,
+    session_token: String,
+) -> CommandResult<entity::chat::Model> {
+    as_actor(&state, &session_token, |staff_id| ChatHandler::get_direct_chat(&state, staff_id, other_staff_id)).await
 }
 
 // NEW COMMAND: get_customer_chats_for_staff
 #[tauri::command]
 async fn view_customer_chats_for_staff( // New command
     state: State<'_, AppState>,
-) -> Result<ApiResponse<Vec<ChatWithCustomerName>>, String> {
-    ChatHandler::get_customer_chats_for_staff(&state).await // Call new handler function
+) -> CommandResult<Vec<ChatWithCustomerName>> {
+    respond(ChatHandler::get_customer_chats_for_staff(&state).await) // Call new handler function
+}
+
+// Customer Service ticket related commands
+#[tauri::command]
+async fn view_customer_service_queue(
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<QueuedTicket>> {
+    respond(CustomerServiceTicketHandler::view_queue(&state).await)
+}
+
+#[tauri::command]
+async fn view_assigned_customer_service_tickets(
+    state: State<'_, AppState>,
+    staff_id: String,
+) -> CommandResult<Vec<entity::customer_service_ticket::Model>> {
+    respond(CustomerServiceTicketHandler::view_assigned_tickets(&state, staff_id).await)
+}
+
+#[tauri::command]
+async fn get_active_customer_service_ticket(
+    state: State<'_, AppState>,
+    chat_id: String,
+) -> CommandResult<Option<entity::customer_service_ticket::Model>> {
+    respond(CustomerServiceTicketHandler::get_active_ticket(&state, chat_id).await)
+}
+
+#[tauri::command]
+async fn assign_customer_service_ticket(
+    state: State<'_, AppState>,
+    ticket_id: String,
+    staff_id: String,
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| CustomerServiceTicketHandler::assign_ticket(&state, ticket_id, staff_id, actor_id)).await
+}
+
+#[tauri::command]
+async fn update_customer_service_ticket_status(
+    state: State<'_, AppState>,
+    ticket_id: String,
+    status: String,
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| CustomerServiceTicketHandler::update_ticket_status(&state, ticket_id, status, actor_id)).await
+}
+
+#[tauri::command]
+async fn get_customer_service_metrics(
+    state: State<'_, AppState>,
+    staff_id: Option<String>,
+) -> CommandResult<CustomerServiceMetrics> {
+    respond(CustomerServiceTicketHandler::get_metrics(&state, staff_id).await)
 }
 
 // Maintenance Schedule related commands
 #[tauri::command]
 async fn view_maintenance_schedules(
     state: State<'_, AppState>,
-) -> Result<ApiResponse<Vec<entity::maintenance_schedule::Model>>, String> {
-    MaintenanceScheduleHandler::view_maintenance_schedules(&state).await
+) -> CommandResult<Vec<entity::maintenance_schedule::Model>> {
+    respond(MaintenanceScheduleHandler::view_maintenance_schedules(&state).await)
 }
 
 #[tauri::command]
 async fn view_maintenance_schedule_by_staff(
     state: State<'_, AppState>,
     staff_id: String,
-) -> Result<ApiResponse<Vec<entity::maintenance_schedule::Model>>, String> {
-    MaintenanceScheduleHandler::view_maintenance_schedule_by_staff(&state, staff_id).await
+) -> CommandResult<Vec<entity::maintenance_schedule::Model>> {
+    respond(MaintenanceScheduleHandler::view_maintenance_schedule_by_staff(&state, staff_id).await)
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn save_maintenance_schedule_data(
     state: State<'_, AppState>,
     ride_id: String,
@@ -875,8 +1420,9 @@ async fn save_maintenance_schedule_data(
     start_date: String,
     end_date: String,
     status: String,
-) -> Result<ApiResponse<String>, String> {
-    MaintenanceScheduleHandler::save_maintenance_schedule_data(&state, ride_id, staff_id, description, start_date, end_date, status).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| MaintenanceScheduleHandler::save_maintenance_schedule_data(&state, ride_id, staff_id, description, start_date, end_date, status, actor_id)).await
 }
 
 #[tauri::command]
@@ -889,16 +1435,18 @@ async fn update_maintenance_schedule_data(
     start_date: Option<String>,
     end_date: Option<String>,
     status: Option<String>,
-) -> Result<ApiResponse<String>, String> {
-    MaintenanceScheduleHandler::update_maintenance_schedule_data(&state, maintenance_task_id, ride_id, staff_id, description, start_date, end_date, status).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| MaintenanceScheduleHandler::update_maintenance_schedule_data(&state, maintenance_task_id, ride_id, staff_id, description, start_date, end_date, status, actor_id)).await
 }
 
 #[tauri::command]
 async fn delete_maintenance_schedule_data(
     state: State<'_, AppState>,
     maintenance_task_id: String,
-) -> Result<String, String> {
-    MaintenanceScheduleHandler::delete_maintenance_schedule_data(&state, maintenance_task_id).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| MaintenanceScheduleHandler::delete_maintenance_schedule_data(&state, maintenance_task_id, actor_id)).await
 }
 
 // Income Report Command
@@ -906,63 +1454,209 @@ async fn delete_maintenance_schedule_data(
 async fn generate_income_report(
     state: State<'_, AppState>,
     period: String,
-) -> Result<ApiResponse<IncomeReport>, String> { // Use IncomeReport struct
-    IncomeReportHandler::generate_income_report(&state, period).await
+) -> CommandResult<IncomeReport> { // Use IncomeReport struct
+    respond(IncomeReportHandler::generate_income_report(&state, period).await)
 }
 
 // Broadcast Message related commands
 #[tauri::command]
 async fn view_broadcast_messages(
     state: State<'_, AppState>,
-) -> Result<ApiResponse<Vec<broadcast_message::Model>>, String> {
-    BroadcastMessageHandler::view_broadcast_messages(&state).await
+) -> CommandResult<Vec<broadcast_message::Model>> {
+    respond(BroadcastMessageHandler::view_broadcast_messages(&state).await)
 }
 
 #[tauri::command]
 async fn view_broadcast_messages_by_audience(
     state: State<'_, AppState>,
     target_audience: String,
-) -> Result<ApiResponse<Vec<broadcast_message::Model>>, String> {
-    BroadcastMessageHandler::view_broadcast_messages_by_audience(&state, target_audience).await
+) -> CommandResult<Vec<broadcast_message::Model>> {
+    respond(BroadcastMessageHandler::view_broadcast_messages_by_audience(&state, target_audience).await)
 }
 
 #[tauri::command]
 async fn get_broadcast_message_details(
     state: State<'_, AppState>,
     broadcast_message_id: String,
-) -> Result<ApiResponse<broadcast_message::Model>, String> {
-    BroadcastMessageHandler::get_broadcast_message_details(&state, broadcast_message_id).await
+) -> CommandResult<broadcast_message::Model> {
+    respond(BroadcastMessageHandler::get_broadcast_message_details(&state, broadcast_message_id).await)
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn save_broadcast_message_data(
     state: State<'_, AppState>,
     target_audience: String,
     content: String,
     status: String,
-) -> Result<ApiResponse<String>, String> {
-    BroadcastMessageHandler::save_broadcast_message_data(&state, target_audience, content, status).await
+    send_at: Option<chrono::NaiveDateTime>,
+    expires_at: Option<chrono::NaiveDateTime>,
+    target_type: Option<String>,
+    target_value: Option<String>,
+    session_token: String,
+) -> CommandResult<String> {
+    let actor_id = match Session::actor(&state.db, &session_token).await {
+        Ok(actor_id) => actor_id,
+        Err(err) => return Ok(err.into()),
+    };
+    let result = BroadcastMessageHandler::save_broadcast_message_data(&state, target_audience, content, status, send_at, expires_at, target_type, target_value, actor_id).await;
+    if result.is_ok() {
+        send_due_broadcasts(&state).await; // Anything due now goes out without waiting for the next tick
+    }
+    respond(result)
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn update_broadcast_message_data(
     state: State<'_, AppState>,
     broadcast_message_id: String,
     target_audience: Option<String>,
     content: Option<String>,
     status: Option<String>,
-) -> Result<ApiResponse<String>, String> {
-    BroadcastMessageHandler::update_broadcast_message_data(&state, broadcast_message_id, target_audience, content, status).await
+    send_at: Option<chrono::NaiveDateTime>,
+    expires_at: Option<Option<chrono::NaiveDateTime>>,
+    target_type: Option<String>,
+    target_value: Option<String>,
+    session_token: String,
+) -> CommandResult<String> {
+    let actor_id = match Session::actor(&state.db, &session_token).await {
+        Ok(actor_id) => actor_id,
+        Err(err) => return Ok(err.into()),
+    };
+    let result = BroadcastMessageHandler::update_broadcast_message_data(&state, broadcast_message_id, target_audience, content, status, send_at, expires_at, target_type, target_value, actor_id).await;
+    if result.is_ok() {
+        send_due_broadcasts(&state).await;
+    }
+    respond(result)
+}
+
+#[tauri::command]
+async fn view_broadcast_messages_for_recipient(
+    state: State<'_, AppState>,
+    session_token: String,
+) -> CommandResult<Vec<RecipientBroadcast>> {
+    let ui_id = load_config().ok().map(|config| config.chosen_ui.ui_id); // Venue this client runs at
+    as_actor(&state, &session_token, |recipient_id| BroadcastMessageHandler::view_broadcast_messages_for_recipient(&state, recipient_id, ui_id)).await
+}
+
+#[tauri::command]
+async fn mark_broadcast_message_read(
+    state: State<'_, AppState>,
+    broadcast_message_id: String,
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |recipient_id| BroadcastMessageHandler::mark_broadcast_message_read(&state, broadcast_message_id, recipient_id)).await
+}
+
+#[tauri::command]
+async fn get_broadcast_delivery_stats(
+    state: State<'_, AppState>,
+    broadcast_message_id: String,
+) -> CommandResult<BroadcastDeliveryStats> {
+    respond(BroadcastMessageHandler::get_broadcast_delivery_stats(&state, broadcast_message_id).await)
 }
 
 #[tauri::command]
 async fn delete_broadcast_message_data(
     state: State<'_, AppState>,
     broadcast_message_id: String,
-) -> Result<String, String> {
-    BroadcastMessageHandler::delete_broadcast_message_data(&state, broadcast_message_id).await
+    session_token: String,
+) -> CommandResult<String> {
+    as_actor(&state, &session_token, |actor_id| BroadcastMessageHandler::delete_broadcast_message_data(&state, broadcast_message_id, actor_id)).await
+}
+
+
+const BROADCAST_DISPATCH_INTERVAL_SECS: u64 = 5;
+const BROADCAST_SENT_EVENT: &str = "broadcast_message_sent";
+const LOST_AND_FOUND_RETENTION_INTERVAL_SECS: u64 = 60 * 60;
+// Requests fail fast while a backing service is down instead of hanging; the pools keep
+// trying to connect in the background and pick the service up again once it is back
+const CONNECT_TIMEOUT_SECS: u64 = 5;
+const DIVISION_SYNC_RETRY_SECS: u64 = 30;
+// How often a kiosk checks the central database and replays what it queued while offline
+const OFFLINE_SYNC_INTERVAL_SECS: u64 = 15;
+
+// Everything run() needs from the environment. Only missing or malformed configuration fails
+// here; an unreachable database or Redis is left to the pools and reported by health_check.
+async fn build_state() -> Result<AppState, String> {
+    let database_url = env::var("DATABASE_URL").map_err(|_| "DATABASE_URL is not set".to_string())?;
+    let redis_url = env::var("REDIS_URL").map_err(|_| "REDIS_URL is not set".to_string())?;
+
+    let mut db_options = ConnectOptions::new(database_url);
+    db_options
+        .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS))
+        .acquire_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS))
+        .connect_lazy(true);
+    let db = Database::connect(db_options)
+        .await
+        .map_err(|err| format!("Invalid DATABASE_URL: {}", err))?;
+    // A kiosk's local SQLite file is created and kept up to date here, as nobody runs the CLI on it
+    if db.get_database_backend() == DbBackend::Sqlite {
+        Migrator::up(&db, None)
+            .await
+            .map_err(|err| format!("Error migrating the SQLite database: {}", err))?;
+    }
+
+    let mut redis_cfg = RedisConfig::from_url(redis_url);
+    let timeout = Some(Duration::from_secs(CONNECT_TIMEOUT_SECS));
+    redis_cfg.pool = Some(RedisPoolConfig {
+        timeouts: RedisTimeouts { wait: timeout, create: timeout, recycle: timeout },
+        ..RedisPoolConfig::default()
+    });
+    let redis_pool = redis_cfg
+        .create_pool(Some(Runtime::Tokio1))
+        .map_err(|err| format!("Invalid REDIS_URL: {}", err))?;
+
+    let attachment_dir = env::var("ATTACHMENT_DIR").unwrap_or_else(|_| "attachments".to_string());
+    let storage: Arc<dyn AttachmentStorage> = Arc::new(LocalFileStorage::new(attachment_dir));
+
+    let cache = Cache::new(
+        Arc::new(FallbackCacheBackend::new(Arc::new(RedisCacheBackend::new(redis_pool)))),
+        CacheTtls::from_env(),
+    );
+    Ok(AppState::new(db, cache, storage))
+}
+
+async fn sync_offline_writes(state: &AppState, offline: &OfflineStore, venue: Option<&str>) {
+    let online = state.db.ping().await.is_ok();
+    if offline.set_online(online) {
+        eprintln!("Central database {}", if online { "reachable again" } else { "unreachable, working offline" });
+    }
+    if !online {
+        return;
+    }
+    match OfflineSyncHandler::sync_pending(state, offline, venue).await {
+        Ok(report) if report.conflicts > 0 => eprintln!("{} offline writes could not be applied and were reported to staff", report.conflicts),
+        Ok(_) => {}
+        Err(err) => eprintln!("Offline sync failed: {}", err),
+    }
 }
 
+// Mark due broadcasts as sent and record their recipients
+async fn send_due_broadcasts(state: &AppState) {
+    if let Err(err) = BroadcastMessageHandler::dispatch_due_broadcasts(state).await {
+        eprintln!("Broadcast dispatch failed: {}", err);
+    }
+}
+
+// Push broadcasts sent since `last_sent_at` to this instance's windows. Every kiosk runs this,
+// so broadcasts sent by another instance still reach the clients open here.
+async fn emit_sent_broadcasts(app: &AppHandle, state: &AppState, ui_id: Option<&str>, last_sent_at: &mut chrono::NaiveDateTime) {
+    match BroadcastMessageHandler::view_sent_broadcasts_since(state, *last_sent_at, ui_id).await {
+        Ok(sent_broadcasts) => {
+            for sent_broadcast in sent_broadcasts {
+                if let Some(sent_at) = sent_broadcast.broadcast.sent_at {
+                    *last_sent_at = (*last_sent_at).max(sent_at);
+                }
+                if let Err(err) = app.emit(BROADCAST_SENT_EVENT, sent_broadcast) {
+                    eprintln!("Failed to emit broadcast message: {}", err);
+                }
+            }
+        }
+        Err(err) => eprintln!("Failed to fetch sent broadcasts: {}", err),
+    }
+}
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -970,44 +1664,107 @@ pub fn run() {
         .setup(|app| {
             dotenv().ok();
 
-            let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-            let redis_url = env::var("REDIS_URL").expect("REDIS_URL must be set");
-
-            // Create the runtime
-            let rt = tokio::runtime::Runtime::new().unwrap();
-
-            // Execute the future, blocking the current thread until completion
-            let db = rt
-                .block_on(Database::connect(&database_url))
-                .expect("Failed to connect to database");
-
-            let redis_cfg = RedisConfig::from_url(redis_url);
-            let redis_pool = redis_cfg
-                .create_pool(Some(Runtime::Tokio1))
-                .expect("Failed to create Redis pool");
-
-            app.manage(AppState { db, redis_pool });
+            let state = match tauri::async_runtime::block_on(build_state()) {
+                Ok(state) => state,
+                Err(err) => {
+                    // The window still opens and the startup screen shows the error
+                    eprintln!("VorteKia could not start: {}", err);
+                    app.manage(StartupError(err));
+                    return Ok(());
+                }
+            };
+            app.manage(state);
+
+            // The kiosk's own store; without a writable file it still works, just not across restarts
+            let offline_url = env::var("OFFLINE_DATABASE_URL").unwrap_or_else(|_| DEFAULT_OFFLINE_DATABASE_URL.to_string());
+            let offline = tauri::async_runtime::block_on(async {
+                match OfflineStore::open(&offline_url).await {
+                    Ok(offline) => Ok(offline),
+                    Err(err) => {
+                        eprintln!("Offline store {} unavailable, keeping it in memory: {}", offline_url, err);
+                        OfflineStore::open("sqlite::memory:").await
+                    }
+                }
+            })?;
+            app.manage(offline);
+
+            // Make sure every division has its group chat and staff sit in the right one,
+            // retrying until the database is reachable
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    let state = app_handle.state::<AppState>();
+                    match ChatHandler::sync_division_group_chats(&state).await {
+                        Ok(()) => break,
+                        Err(err) => eprintln!("Failed to sync division group chats: {}", err),
+                    }
+                    tokio::time::sleep(Duration::from_secs(DIVISION_SYNC_RETRY_SECS)).await;
+                }
+            });
+
+            // Background dispatcher for scheduled broadcasts
+            let app_handle = app.handle().clone();
+            let ui_id = load_config().ok().map(|config| config.chosen_ui.ui_id);
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(BROADCAST_DISPATCH_INTERVAL_SECS));
+                let mut last_sent_at = clock::jakarta_now();
+                loop {
+                    interval.tick().await;
+                    let state = app_handle.state::<AppState>();
+                    send_due_broadcasts(&state).await;
+                    emit_sent_broadcasts(&app_handle, &state, ui_id.as_deref(), &mut last_sent_at).await;
+                }
+            });
+
+            // Notice when the central database goes away or comes back, and replay queued writes
+            let app_handle = app.handle().clone();
+            let venue = load_config().ok().map(|config| config.chosen_ui.ui_id);
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(OFFLINE_SYNC_INTERVAL_SECS));
+                loop {
+                    interval.tick().await;
+                    let state = app_handle.state::<AppState>();
+                    let offline = app_handle.state::<OfflineStore>();
+                    sync_offline_writes(&state, &offline, venue.as_deref()).await;
+                }
+            });
+
+            // Hourly sweep flagging unclaimed Found items for donation or disposal
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(LOST_AND_FOUND_RETENTION_INTERVAL_SECS));
+                loop {
+                    interval.tick().await;
+                    let state = app_handle.state::<AppState>();
+                    if let Err(err) = LostAndFoundItemsLogHandler::flag_unclaimed_items(&state).await {
+                        eprintln!("Lost and found retention sweep failed: {}", err);
+                    }
+                }
+            });
 
             Ok(())
         })
         .plugin(tauri_plugin_opener::init())
         .invoke_handler(tauri::generate_handler![
-            get_ui_name_from_config,
-            customer_login, get_customer_details, view_customer_accounts, save_customer_data, update_customer_data, top_up_virtual_balance, delete_customer_data,
-            staff_login, get_staff_details, get_staff_details_by_email, view_staff_accounts, view_ride_staffs, view_maintenance_staffs, save_staff_data, update_staff_data, delete_staff_data,
-            view_restaurants, get_restaurant_details, save_restaurant_data, update_restaurant_data, delete_restaurant_data,
-            view_menu_items, get_menu_item_details, save_menu_item_data, update_menu_item_data, delete_menu_item_data,
+            get_ui_name_from_config, health_check, get_offline_status, view_sync_conflicts, retry_sync_conflict, dismiss_sync_conflict,
+            customer_login, logout, get_customer_details, view_customer_accounts, save_customer_data, update_customer_data, top_up_virtual_balance, delete_customer_data, restore_customer_data, view_archived_customers,
+            staff_login, view_password_policy, change_staff_password, reset_staff_password, complete_staff_password_reset, get_staff_details, get_staff_details_by_email, view_staff_accounts, view_ride_staffs, view_maintenance_staffs, save_staff_data, update_staff_data, delete_staff_data, restore_staff_data, view_archived_staff, view_audit_events, check_delete_dependencies,
+            view_restaurants, get_restaurant_details, save_restaurant_data, update_restaurant_data, delete_restaurant_data, restore_restaurant_data, view_archived_restaurants,
+            view_menu_items, get_menu_item_details, save_menu_item_data, update_menu_item_data, delete_menu_item_data, restore_menu_item_data, view_archived_menu_items,
             view_order_restaurants, view_order_restaurants_by_customer, save_order_restaurant_data, update_order_restaurant_status, delete_order_restaurant_data,
-            view_rides, get_ride_details, save_ride_data, update_ride_data, delete_ride_data,
+            view_rides, get_ride_details, save_ride_data, update_ride_data, delete_ride_data, restore_ride_data, view_archived_rides,
             view_ride_queues, save_ride_queue_data, update_queue_position, delete_ride_queue_data,
-            view_stores, get_store_details, save_store_data, update_store_data, delete_store_data,
+            view_stores, get_store_details, save_store_data, update_store_data, delete_store_data, restore_store_data, view_archived_stores,
             view_souvenirs, get_souvenir_details, save_souvenir_data, update_souvenir_data, update_souvenir_stock, delete_souvenir_data,
             view_order_souvenirs, view_order_souvenirs_by_customer, get_order_souvenir_details, save_order_souvenir_data, delete_order_souvenir_data,
-            view_logs, save_log_data, update_log_data, delete_log_data,
-            view_chats, get_chat_details, save_chat_data, get_messages, save_message_data, save_chat_member_data, get_chat_members, get_customer_service_chat, view_customer_chats_for_staff,
+            view_logs, save_log_data, update_log_data, delete_log_data, restore_log_data, view_archived_logs, suggest_lost_and_found_matches, confirm_lost_and_found_match,
+            return_log_to_owner, view_retention_flagged_logs, dispose_log, get_log_history, file_missing_report, view_lost_item_reports_by_customer,
+            view_chats, get_chat_details, save_chat_data, get_messages, save_message_data, edit_message, delete_message, get_message_edit_history, get_attachment_data, search_messages, save_chat_member_data, get_chat_members, get_customer_service_chat, get_direct_chat, view_customer_chats_for_staff,
+            view_customer_service_queue, view_assigned_customer_service_tickets, get_active_customer_service_ticket, assign_customer_service_ticket, update_customer_service_ticket_status, get_customer_service_metrics,
             view_maintenance_schedules, view_maintenance_schedule_by_staff, save_maintenance_schedule_data, update_maintenance_schedule_data, delete_maintenance_schedule_data,
             generate_income_report,
             view_broadcast_messages, view_broadcast_messages_by_audience, get_broadcast_message_details, save_broadcast_message_data, update_broadcast_message_data, delete_broadcast_message_data,
+            view_broadcast_messages_for_recipient, mark_broadcast_message_read, get_broadcast_delivery_stats,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,182 @@
+// src-tauri/src/login_guard.rs
+
+use chrono::Utc;
+
+use crate::cache::CacheBackend;
+use crate::error::AppError;
+
+// Failed attempts are forgotten after an hour without another failure
+const FAILURE_WINDOW_SECS: u64 = 3600;
+const BASE_LOCKOUT_SECS: u64 = 30;
+const MAX_LOCKOUT_SECS: u64 = 3600;
+// A device (e.g. a shared kiosk) serves many people, so it gets more slack than one account
+const ACCOUNT_THRESHOLD: u64 = 5;
+const DEVICE_THRESHOLD: u64 = 20;
+
+// Who a login attempt is counted against
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoginSubject {
+    Account(String),
+    Device(String),
+}
+
+impl LoginSubject {
+    fn key(&self) -> String {
+        match self {
+            LoginSubject::Account(account) => format!("account:{}", account.to_lowercase()),
+            LoginSubject::Device(device_id) => format!("device:{}", device_id),
+        }
+    }
+
+    fn threshold(&self) -> u64 {
+        match self {
+            LoginSubject::Account(_) => ACCOUNT_THRESHOLD,
+            LoginSubject::Device(_) => DEVICE_THRESHOLD,
+        }
+    }
+}
+
+// Lockout doubles every time the threshold is reached again: 30s, 60s, 120s ... capped at an hour
+pub fn lockout_secs(failures: u64, threshold: u64) -> Option<u64> {
+    if failures == 0 || !failures.is_multiple_of(threshold) {
+        return None;
+    }
+    let round = (failures / threshold - 1).min(16) as u32;
+    Some((BASE_LOCKOUT_SECS << round).min(MAX_LOCKOUT_SECS))
+}
+
+// Attempt counters with progressive lockout, kept in the cache backend (Redis, or the
+// in-process fallback while Redis is down). Like the cache it fails open: if the backend
+// errors, logins still work, they are just not rate limited.
+pub struct LoginGuard;
+
+impl LoginGuard {
+    pub fn subjects(account: &str, device_id: Option<&str>) -> Vec<LoginSubject> {
+        let mut subjects = vec![LoginSubject::Account(account.to_string())];
+        if let Some(device_id) = device_id.filter(|device_id| !device_id.trim().is_empty()) {
+            subjects.push(LoginSubject::Device(device_id.to_string()));
+        }
+        subjects
+    }
+
+    // Refuse the attempt outright while any of the subjects is locked out. A lock holds the
+    // Unix time it ends at, so the remaining wait can be reported.
+    pub async fn check(store: &dyn CacheBackend, scope: &str, subjects: &[LoginSubject]) -> Result<(), AppError> {
+        for subject in subjects {
+            let lock_key = format!("login_lock:{}:{}", scope, subject.key());
+            match store.get(&lock_key).await {
+                Ok(Some(locked_until)) => {
+                    let remaining = locked_until.parse::<i64>().unwrap_or(0) - Utc::now().timestamp();
+                    if remaining > 0 {
+                        return Err(AppError::RateLimited(format!("Too many failed login attempts. Try again in {} seconds", remaining)));
+                    }
+                }
+                Ok(None) => {}
+                Err(err) => eprintln!("Cache error (login_guard check): {}", err),
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn record_failure(store: &dyn CacheBackend, scope: &str, subjects: &[LoginSubject]) {
+        for subject in subjects {
+            let failures_key = format!("login_failures:{}:{}", scope, subject.key());
+            let failures = match store.incr(&failures_key).await {
+                Ok(failures) => failures.max(0) as u64,
+                Err(err) => {
+                    eprintln!("Cache error (login_guard record_failure - incr): {}", err);
+                    continue;
+                }
+            };
+            if let Err(err) = store.expire(&failures_key, FAILURE_WINDOW_SECS).await {
+                eprintln!("Cache error (login_guard record_failure - expire): {}", err);
+            }
+
+            if let Some(lockout) = lockout_secs(failures, subject.threshold()) {
+                let lock_key = format!("login_lock:{}:{}", scope, subject.key());
+                let locked_until = Utc::now().timestamp() + lockout as i64;
+                if let Err(err) = store.set(&lock_key, &locked_until.to_string(), lockout).await {
+                    eprintln!("Cache error (login_guard record_failure - lock): {}", err);
+                }
+            }
+        }
+    }
+
+    // Only the account is cleared: a device's count must not be reset by someone
+    // logging into their own account between guesses at others
+    pub async fn record_success(store: &dyn CacheBackend, scope: &str, subjects: &[LoginSubject]) {
+        for subject in subjects.iter().filter(|subject| matches!(subject, LoginSubject::Account(_))) {
+            let failures_key = format!("login_failures:{}:{}", scope, subject.key());
+            if let Err(err) = store.delete(&failures_key).await {
+                eprintln!("Cache error (login_guard record_success): {}", err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::MemoryCacheBackend;
+
+    #[test]
+    fn lockout_doubles_each_time_the_threshold_is_reached() {
+        assert_eq!(lockout_secs(4, 5), None);
+        assert_eq!(lockout_secs(5, 5), Some(30));
+        assert_eq!(lockout_secs(10, 5), Some(60));
+        assert_eq!(lockout_secs(500, 5), Some(MAX_LOCKOUT_SECS));
+    }
+
+    #[tokio::test]
+    async fn account_is_locked_after_repeated_failures_until_reset() {
+        let store = MemoryCacheBackend::new();
+        let subjects = LoginGuard::subjects("staff@vortekia.com", Some("kiosk-1"));
+        for _ in 0..ACCOUNT_THRESHOLD {
+            assert!(LoginGuard::check(&store, "staff", &subjects).await.is_ok());
+            LoginGuard::record_failure(&store, "staff", &subjects).await;
+        }
+
+        let locked = LoginGuard::check(&store, "staff", &subjects).await.unwrap_err();
+        assert_eq!(locked.code(), crate::error::ErrorCode::RateLimited);
+        // Scopes are separate: a staff lockout does not block the customer login
+        assert!(LoginGuard::check(&store, "customer", &subjects).await.is_ok());
+
+        LoginGuard::record_success(&store, "staff", &subjects).await;
+        assert_eq!(store.get("login_failures:staff:account:staff@vortekia.com").await.unwrap(), None);
+        assert_eq!(store.get("login_failures:staff:device:kiosk-1").await.unwrap().as_deref(), Some("5"));
+    }
+
+    #[tokio::test]
+    async fn a_shared_device_is_locked_later_and_stays_counted_across_accounts() {
+        let store = MemoryCacheBackend::new();
+        // Different people on one kiosk, each staying under the account threshold
+        for attempt in 0..DEVICE_THRESHOLD {
+            let subjects = LoginGuard::subjects(&format!("guest-{}", attempt / 2), Some("kiosk-1"));
+            assert!(LoginGuard::check(&store, "customer", &subjects).await.is_ok(), "attempt {attempt}");
+            LoginGuard::record_failure(&store, "customer", &subjects).await;
+            if attempt % 2 == 0 {
+                LoginGuard::record_success(&store, "customer", &LoginGuard::subjects("regular", Some("kiosk-1"))).await;
+            }
+        }
+
+        let locked = LoginGuard::check(&store, "customer", &LoginGuard::subjects("regular", Some("kiosk-1"))).await.unwrap_err();
+        assert_eq!(locked.code(), crate::error::ErrorCode::RateLimited);
+        assert!(LoginGuard::check(&store, "customer", &LoginGuard::subjects("regular", Some("kiosk-2"))).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn accounts_are_counted_case_insensitively_and_locks_lapse() {
+        let store = MemoryCacheBackend::new();
+        for email in ["Staff@Vortekia.com", "staff@vortekia.com", "STAFF@VORTEKIA.COM", "staff@vortekia.com", "Staff@vortekia.com"] {
+            LoginGuard::record_failure(&store, "staff", &LoginGuard::subjects(email, None)).await;
+        }
+        let subjects = LoginGuard::subjects("staff@VORTEKIA.com", Some("  "));
+        assert_eq!(subjects.len(), 1, "a blank device id is not a subject");
+        assert!(LoginGuard::check(&store, "staff", &subjects).await.is_err());
+
+        // A lock whose end time has passed no longer holds, even if the key lingers
+        let lapsed = (Utc::now().timestamp() - 1).to_string();
+        store.set("login_lock:staff:account:staff@vortekia.com", &lapsed, 60).await.unwrap();
+        assert!(LoginGuard::check(&store, "staff", &subjects).await.is_ok());
+    }
+}
@@ -0,0 +1,424 @@
+// src-tauri/src/offline.rs
+
+// A kiosk's own SQLite store for when the central database is unreachable. It keeps the last
+// answer to each venue read (menus, souvenirs, rides) and queues orders and queue joins, which
+// the sync task replays once the central database answers again.
+
+use chrono::NaiveDateTime;
+use entity::{offline_snapshot, offline_write};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, Database, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, Schema, Set,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use uuid::Uuid;
+
+use crate::clock;
+use crate::error::AppError;
+use crate::ApiResponse;
+
+pub const DEFAULT_OFFLINE_DATABASE_URL: &str = "sqlite://offline.db?mode=rwc";
+
+const QUEUED_MESSAGE: &str = "Saved on this kiosk; it will be sent once the connection is back";
+
+// A write accepted while offline. It is replayed the way the kiosk would have made it online:
+// the order or queue entry, the charge to the customer's balance and, for souvenirs, the stock.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind")]
+pub enum OfflineWrite {
+    OrderRestaurant {
+        customer_id: String,
+        restaurant_id: String,
+        menu_item_id: String,
+        quantity: i32,
+        actor_id: Option<String>,
+    },
+    OrderSouvenir {
+        customer_id: String,
+        store_id: String,
+        souvenir_id: String,
+        quantity: i32,
+        actor_id: Option<String>,
+    },
+    RideQueueJoin {
+        ride_id: String,
+        customer_id: String,
+        actor_id: Option<String>,
+    },
+}
+
+impl OfflineWrite {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            OfflineWrite::OrderRestaurant { .. } => "OrderRestaurant",
+            OfflineWrite::OrderSouvenir { .. } => "OrderSouvenir",
+            OfflineWrite::RideQueueJoin { .. } => "RideQueueJoin",
+        }
+    }
+
+    pub fn customer_id(&self) -> &str {
+        match self {
+            OfflineWrite::OrderRestaurant { customer_id, .. }
+            | OfflineWrite::OrderSouvenir { customer_id, .. }
+            | OfflineWrite::RideQueueJoin { customer_id, .. } => customer_id,
+        }
+    }
+
+    // How the write is named to the customer, e.g. in "Your souvenir order could not be completed"
+    pub fn description(&self) -> &'static str {
+        match self {
+            OfflineWrite::OrderRestaurant { .. } => "restaurant order",
+            OfflineWrite::OrderSouvenir { .. } => "souvenir order",
+            OfflineWrite::RideQueueJoin { .. } => "ride queue booking",
+        }
+    }
+
+    pub fn to_payload(&self) -> Result<serde_json::Value, AppError> {
+        serde_json::to_value(self).map_err(|err| AppError::Internal(format!("Error encoding offline write: {}", err)))
+    }
+
+    pub fn from_payload(payload: &serde_json::Value) -> Result<Self, AppError> {
+        serde_json::from_value(payload.clone())
+            .map_err(|err| AppError::Internal(format!("Unreadable offline write: {}", err)))
+    }
+}
+
+// What the kiosk banner shows
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct OfflineStatus {
+    pub online: bool,
+    pub pending: u64,   // Queued writes not sent yet
+    pub conflicts: u64, // Queued writes that could not be applied and were reported to staff
+    pub last_synced_at: Option<NaiveDateTime>,
+}
+
+pub struct OfflineStore {
+    db: DatabaseConnection,
+    online: AtomicBool, // Whether the central database answered last time it was tried
+}
+
+impl OfflineStore {
+    // Open (or create) the store. Its two tables are not part of the central schema, so they are
+    // created from their entities rather than by the migrator.
+    pub async fn open(url: &str) -> Result<Self, DbErr> {
+        let db = Database::connect(url).await?;
+        let backend = db.get_database_backend();
+        let schema = Schema::new(backend);
+        for mut statement in [
+            schema.create_table_from_entity(offline_snapshot::Entity),
+            schema.create_table_from_entity(offline_write::Entity),
+        ] {
+            db.execute(backend.build(statement.if_not_exists())).await?;
+        }
+        Ok(OfflineStore {
+            db,
+            online: AtomicBool::new(true),
+        })
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.online.load(Ordering::SeqCst)
+    }
+
+    // Returns whether this changed the state
+    pub fn set_online(&self, online: bool) -> bool {
+        self.online.swap(online, Ordering::SeqCst) != online
+    }
+
+    // A failed call only sends the kiosk offline when the central database is really gone, not
+    // when the call itself was wrong
+    async fn lost_connection(&self, central: &DatabaseConnection, error: &AppError) -> bool {
+        if !matches!(error, AppError::Database(_)) || central.ping().await.is_ok() {
+            return false;
+        }
+        self.set_online(false);
+        true
+    }
+
+    // Run a venue read against the central database and keep its answer, or serve the kept
+    // answer while offline
+    pub async fn read_through<T, F>(&self, central: &DatabaseConnection, key: &str, load: F) -> Result<ApiResponse<T>, AppError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = Result<ApiResponse<T>, AppError>>,
+    {
+        if self.is_online() {
+            let result = load.await;
+            let offline = match &result {
+                Ok(ApiResponse::Success { data, .. }) => {
+                    self.save_snapshot(key, data).await;
+                    false
+                }
+                Ok(_) => false,
+                Err(err) => self.lost_connection(central, err).await,
+            };
+            if !offline {
+                return result;
+            }
+        }
+
+        match self.snapshot(key).await {
+            Some((data, fetched_at)) => Ok(ApiResponse::Success {
+                data,
+                message: Some(format!("Offline: showing data saved at {}", fetched_at.format("%Y-%m-%d %H:%M"))),
+            }),
+            None => Err(AppError::Database(
+                "The central database is unreachable and this kiosk has no saved copy".to_string(),
+            )),
+        }
+    }
+
+    // Apply a write to the central database, or queue it while offline. The client's idempotency
+    // key, if it sent one, becomes the queued write's key, so a repeat is queued only once.
+    pub async fn write_through<F>(
+        &self,
+        central: &DatabaseConnection,
+        write: OfflineWrite,
+        idempotency_key: Option<String>,
+        apply: F,
+    ) -> Result<ApiResponse<String>, AppError>
+    where
+        F: Future<Output = Result<ApiResponse<String>, AppError>>,
+    {
+        if self.is_online() {
+            let result = apply.await;
+            let offline = match &result {
+                Err(err) => self.lost_connection(central, err).await,
+                Ok(_) => false,
+            };
+            if !offline {
+                return result;
+            }
+        }
+
+        let idempotency_key = self.queue(&write, idempotency_key).await?;
+        Ok(ApiResponse::Queued {
+            data: QUEUED_MESSAGE.to_string(),
+            idempotency_key,
+        })
+    }
+
+    async fn snapshot<T: DeserializeOwned>(&self, key: &str) -> Option<(T, NaiveDateTime)> {
+        let saved = offline_snapshot::Entity::find_by_id(key.to_string()).one(&self.db).await.ok()??;
+        let data = serde_json::from_value(saved.payload).ok()?;
+        Some((data, saved.fetched_at))
+    }
+
+    // Best effort: a kiosk that cannot write its own file still works online
+    async fn save_snapshot<T: Serialize>(&self, key: &str, data: &T) {
+        let Ok(payload) = serde_json::to_value(data) else {
+            return;
+        };
+        let snapshot = offline_snapshot::ActiveModel {
+            snapshot_key: Set(key.to_string()),
+            payload: Set(payload),
+            fetched_at: Set(clock::jakarta_now()),
+        };
+        let saved = match offline_snapshot::Entity::find_by_id(key.to_string()).one(&self.db).await {
+            Ok(Some(_)) => snapshot.update(&self.db).await.map(|_| ()),
+            Ok(None) => offline_snapshot::Entity::insert(snapshot).exec_without_returning(&self.db).await.map(|_| ()),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = saved {
+            eprintln!("Failed to save offline copy of {}: {}", key, err);
+        }
+    }
+
+    async fn queue(&self, write: &OfflineWrite, idempotency_key: Option<String>) -> Result<String, AppError> {
+        let idempotency_key = idempotency_key.unwrap_or_else(|| Uuid::new_v4().to_string());
+        let already_queued = offline_write::Entity::find_by_id(idempotency_key.clone())
+            .one(&self.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching queued write: {}", err)))?;
+        if let Some(queued) = already_queued {
+            if queued.payload != write.to_payload()? {
+                return Err(AppError::Conflict(
+                    "This idempotency key was already used for a different request".to_string(),
+                ));
+            }
+            return Ok(idempotency_key);
+        }
+
+        let queued = offline_write::ActiveModel {
+            idempotency_key: Set(idempotency_key.clone()),
+            kind: Set(write.kind().to_string()),
+            payload: Set(write.to_payload()?),
+            queued_at: Set(clock::jakarta_now()),
+            status: Set(offline_write::STATUS_PENDING.to_string()),
+            attempts: Set(0),
+            last_error: Set(None),
+            synced_at: Set(None),
+        };
+        offline_write::Entity::insert(queued)
+            .exec_without_returning(&self.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error saving write on this kiosk: {}", err)))?;
+        Ok(idempotency_key)
+    }
+
+    // Oldest first, so writes are replayed in the order they were made
+    pub async fn pending_writes(&self) -> Result<Vec<offline_write::Model>, AppError> {
+        offline_write::Entity::find()
+            .filter(offline_write::Column::Status.eq(offline_write::STATUS_PENDING))
+            .order_by_asc(offline_write::Column::QueuedAt)
+            .all(&self.db)
+            .await
+            .map_err(|err| AppError::Database(format!("Error fetching queued writes: {}", err)))
+    }
+
+    async fn update_write(&self, queued: &offline_write::Model, status: &str, last_error: Option<String>) -> Result<(), AppError> {
+        let mut active: offline_write::ActiveModel = queued.clone().into();
+        active.status = Set(status.to_string());
+        active.attempts = Set(queued.attempts + 1);
+        active.last_error = Set(last_error);
+        if status == offline_write::STATUS_SYNCED {
+            active.synced_at = Set(Some(clock::jakarta_now()));
+        }
+        active
+            .update(&self.db)
+            .await
+            .map(|_| ())
+            .map_err(|err| AppError::Database(format!("Error updating queued write: {}", err)))
+    }
+
+    pub async fn mark_synced(&self, queued: &offline_write::Model) -> Result<(), AppError> {
+        self.update_write(queued, offline_write::STATUS_SYNCED, None).await
+    }
+
+    pub async fn mark_conflict(&self, queued: &offline_write::Model, reason: &str) -> Result<(), AppError> {
+        self.update_write(queued, offline_write::STATUS_CONFLICT, Some(reason.to_string())).await
+    }
+
+    // Stays pending and is tried again on the next sync
+    pub async fn record_failure(&self, queued: &offline_write::Model, error: &str) -> Result<(), AppError> {
+        self.update_write(queued, offline_write::STATUS_PENDING, Some(error.to_string())).await
+    }
+
+    pub async fn status(&self) -> Result<OfflineStatus, AppError> {
+        let count = |status: &'static str| {
+            offline_write::Entity::find()
+                .filter(offline_write::Column::Status.eq(status))
+                .count(&self.db)
+        };
+        let pending = count(offline_write::STATUS_PENDING).await;
+        let conflicts = count(offline_write::STATUS_CONFLICT).await;
+        let last_synced = offline_write::Entity::find()
+            .filter(offline_write::Column::SyncedAt.is_not_null())
+            .order_by_desc(offline_write::Column::SyncedAt)
+            .one(&self.db)
+            .await;
+        match (pending, conflicts, last_synced) {
+            (Ok(pending), Ok(conflicts), Ok(last_synced)) => Ok(OfflineStatus {
+                online: self.is_online(),
+                pending,
+                conflicts,
+                last_synced_at: last_synced.and_then(|queued| queued.synced_at),
+            }),
+            (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => {
+                Err(AppError::Database(format!("Error reading the offline queue: {}", err)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::data;
+
+    async fn store() -> OfflineStore {
+        OfflineStore::open("sqlite::memory:").await.expect("open offline store")
+    }
+
+    // A central database that can never be reached
+    async fn central_down() -> DatabaseConnection {
+        let mut options = sea_orm::ConnectOptions::new("sqlite:///nonexistent/vortekia/central.db?mode=ro");
+        options.connect_lazy(true);
+        Database::connect(options).await.expect("lazy connection")
+    }
+
+    fn order() -> OfflineWrite {
+        OfflineWrite::OrderSouvenir {
+            customer_id: "customer".to_string(),
+            store_id: "store".to_string(),
+            souvenir_id: "souvenir".to_string(),
+            quantity: 2,
+            actor_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_fall_back_to_the_last_answer_once_the_database_is_gone() {
+        let store = store().await;
+        let central = central_down().await;
+
+        let fresh = store
+            .read_through(&central, "view_rides", async { Ok(ApiResponse::success(vec!["Coaster".to_string()])) })
+            .await;
+        assert_eq!(data(fresh), ["Coaster"]);
+
+        let failing = async { Err::<ApiResponse<Vec<String>>, _>(AppError::Database("connection refused".to_string())) };
+        let served = store.read_through(&central, "view_rides", failing).await;
+        assert!(!store.is_online());
+        assert_eq!(data(served), ["Coaster"]);
+
+        let never_read = store.read_through(&central, "view_stores", async { Ok(ApiResponse::success(Vec::<String>::new())) }).await;
+        assert!(never_read.is_err(), "offline reads without a saved copy fail instead of calling the database");
+    }
+
+    #[tokio::test]
+    async fn writes_are_queued_in_order_while_offline() {
+        let store = store().await;
+        let central = central_down().await;
+
+        let failing = async { Err(AppError::Database("connection refused".to_string())) };
+        let queued = store.write_through(&central, order(), None, failing).await;
+        let Ok(ApiResponse::Queued { idempotency_key, .. }) = queued else {
+            panic!("expected the write to be queued");
+        };
+        store.write_through(&central, order(), None, async { unreachable!("not tried while offline") }).await.unwrap();
+
+        let pending = store.pending_writes().await.unwrap();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].idempotency_key, idempotency_key);
+        assert_eq!(OfflineWrite::from_payload(&pending[0].payload).unwrap(), order());
+
+        store.mark_synced(&pending[0]).await.unwrap();
+        store.mark_conflict(&pending[1], "Only 1 left in stock").await.unwrap();
+        let status = store.status().await.unwrap();
+        assert_eq!((status.pending, status.conflicts), (0, 1));
+        assert!(status.last_synced_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn validation_errors_are_returned_rather_than_queued() {
+        let store = store().await;
+        let central = central_down().await;
+
+        let invalid = async { Err(AppError::invalid_field("quantity", "Quantity must be at least 1")) };
+        assert!(store.write_through(&central, order(), None, invalid).await.is_err());
+        assert!(store.is_online());
+        assert!(store.pending_writes().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_repeated_idempotency_key_is_queued_once() {
+        let store = store().await;
+        let central = central_down().await;
+        store.set_online(false);
+
+        let key = Some("order-1".to_string());
+        for _ in 0..2 {
+            let queued = store.write_through(&central, order(), key.clone(), async { unreachable!("offline") }).await;
+            assert!(matches!(queued, Ok(ApiResponse::Queued { ref idempotency_key, .. }) if idempotency_key == "order-1"));
+        }
+        assert_eq!(store.pending_writes().await.unwrap().len(), 1);
+
+        let other = OfflineWrite::RideQueueJoin { ride_id: "ride".to_string(), customer_id: "customer".to_string(), actor_id: None };
+        let reused = store.write_through(&central, other, key, async { unreachable!("offline") }).await;
+        assert!(matches!(reused, Err(AppError::Conflict(_))));
+    }
+}
@@ -0,0 +1,94 @@
+// src-tauri/src/repository.rs
+
+use crate::cache::{Cache, CachedView, TAG_LOST_AND_FOUND, TAG_ORDER_SOUVENIR, TAG_RIDE, TAG_SOUVENIR, TAG_STORE};
+use crate::AppState;
+use entity::{lost_and_found_items_log, order_souvenir, ride, souvenir, store};
+use sea_orm::{
+    ActiveModelBehavior, ActiveModelTrait, DatabaseConnection, DbErr, DeleteResult, EntityTrait, IntoActiveModel, PrimaryKeyTrait, Select,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::marker::PhantomData;
+
+// An entity whose list views are cached; writes to it invalidate its tag
+pub trait CachedEntity: EntityTrait {
+    const TAG: &'static str;
+}
+
+impl CachedEntity for ride::Entity {
+    const TAG: &'static str = TAG_RIDE;
+}
+
+impl CachedEntity for store::Entity {
+    const TAG: &'static str = TAG_STORE;
+}
+
+impl CachedEntity for souvenir::Entity {
+    const TAG: &'static str = TAG_SOUVENIR;
+}
+
+impl CachedEntity for order_souvenir::Entity {
+    const TAG: &'static str = TAG_ORDER_SOUVENIR;
+}
+
+impl CachedEntity for lost_and_found_items_log::Entity {
+    const TAG: &'static str = TAG_LOST_AND_FOUND;
+}
+
+// Read-through access to one entity. Reads are served from the cache with the entity's
+// configured TTL; writes made through it invalidate every view of the entity.
+pub struct CachedRepository<'a, E> {
+    db: &'a DatabaseConnection,
+    cache: &'a Cache,
+    entity: PhantomData<E>,
+}
+
+impl<'a, E> CachedRepository<'a, E>
+where
+    E: CachedEntity,
+    E::Model: Serialize + DeserializeOwned,
+{
+    pub fn new(state: &'a AppState) -> Self {
+        CachedRepository { db: &state.db, cache: &state.cache, entity: PhantomData }
+    }
+
+    pub async fn find_all(&self, view: CachedView<'_>, query: Select<E>) -> Result<Vec<E::Model>, DbErr> {
+        debug_assert!(view.tags().iter().any(|tag| tag == E::TAG), "{:?} is not tagged {}", view, E::TAG);
+        self.cache.read_through(&view, || query.all(self.db)).await
+    }
+
+    pub async fn insert<A>(&self, model: A) -> Result<E::Model, DbErr>
+    where
+        A: ActiveModelTrait<Entity = E> + Send,
+        E::Model: IntoActiveModel<A>,
+    {
+        let created = E::insert(model).exec_with_returning(self.db).await?;
+        self.invalidate().await;
+        Ok(created)
+    }
+
+    pub async fn update<A>(&self, model: A) -> Result<E::Model, DbErr>
+    where
+        A: ActiveModelTrait<Entity = E> + ActiveModelBehavior + Send,
+        E::Model: IntoActiveModel<A>,
+    {
+        let updated = model.update(self.db).await?;
+        self.invalidate().await;
+        Ok(updated)
+    }
+
+    pub async fn delete_by_id<K>(&self, id: K) -> Result<DeleteResult, DbErr>
+    where
+        K: Into<<E::PrimaryKey as PrimaryKeyTrait>::ValueType>,
+    {
+        let result = E::delete_by_id(id).exec(self.db).await?;
+        if result.rows_affected > 0 {
+            self.invalidate().await;
+        }
+        Ok(result)
+    }
+
+    // For writes made elsewhere, e.g. inside a transaction, once they are committed
+    pub async fn invalidate(&self) {
+        self.cache.invalidate(&[E::TAG]).await;
+    }
+}
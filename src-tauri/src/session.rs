@@ -0,0 +1,156 @@
+// src-tauri/src/session.rs
+
+// Sessions opened by customer_login and staff_login. Commands that change data take the session
+// token instead of an actor id, so the actor written to the audit trail is whoever signed in and
+// cannot be named freely by the caller. Only a hash of each token is stored, so a leaked copy of
+// the table does not let anyone act as the people in it.
+
+use chrono::Duration;
+use entity::user_session::{self, KIND_CUSTOMER, KIND_STAFF};
+use entity::{customer, staff};
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, Set};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::clock;
+use crate::error::AppError;
+
+// A shift's worth; after that the app asks for the login again
+const SESSION_TTL_HOURS: i64 = 12;
+
+pub struct Session;
+
+impl Session {
+    fn token_hash(session_token: &str) -> String {
+        format!("{:x}", Sha256::digest(session_token.as_bytes()))
+    }
+
+    // Key a kiosk's offline snapshot of a verified session under, hashed like the stored token
+    pub fn offline_key(session_token: &str) -> String {
+        format!("session:{}", Self::token_hash(session_token))
+    }
+
+    // Start a session for an account that just proved who it is; the token is returned only here
+    pub async fn open<C: ConnectionTrait>(conn: &C, actor_id: &str, actor_kind: &str) -> Result<String, AppError> {
+        let session_token = Uuid::new_v4().simple().to_string();
+        let now = clock::jakarta_now();
+        let session = user_session::ActiveModel {
+            token_hash: Set(Self::token_hash(&session_token)),
+            actor_id: Set(actor_id.to_string()),
+            actor_kind: Set(actor_kind.to_string()),
+            created_at: Set(now),
+            expires_at: Set(now + Duration::hours(SESSION_TTL_HOURS)),
+        };
+        user_session::Entity::insert(session)
+            .exec(conn)
+            .await
+            .map_err(|err| AppError::Database(format!("Error opening session: {}", err)))?;
+
+        // Expired sessions are only cleared here; Session::actor already refuses them
+        if let Err(err) = user_session::Entity::delete_many()
+            .filter(user_session::Column::ExpiresAt.lt(now))
+            .exec(conn)
+            .await
+        {
+            eprintln!("Failed to clear expired sessions: {}", err);
+        }
+        Ok(session_token)
+    }
+
+    // The customer or staff id a session token was issued to. Missing, expired and unknown
+    // tokens are all Unauthorized, as is a token whose account has since been archived. Staff
+    // who still have to replace a temporary password cannot act until they have.
+    pub async fn actor<C: ConnectionTrait>(conn: &C, session_token: &str) -> Result<String, AppError> {
+        if session_token.trim().is_empty() {
+            return Err(AppError::Unauthorized("Sign in to continue".to_string()));
+        }
+        let session = match user_session::Entity::find_by_id(Self::token_hash(session_token)).one(conn).await {
+            Ok(Some(session)) if session.expires_at > clock::jakarta_now() => session,
+            Ok(_) => return Err(AppError::Unauthorized("Your session has expired; sign in again".to_string())),
+            Err(err) => return Err(AppError::Database(format!("Error fetching session: {}", err))),
+        };
+
+        match session.actor_kind.as_str() {
+            KIND_CUSTOMER => match customer::Entity::find_by_id(session.actor_id.clone()).one(conn).await {
+                Ok(Some(customer)) if customer.deleted_at.is_none() => Ok(session.actor_id),
+                Ok(_) => Err(AppError::Unauthorized("This account is no longer active".to_string())),
+                Err(err) => Err(AppError::Database(format!("Error fetching customer: {}", err))),
+            },
+            KIND_STAFF => match staff::Entity::find_by_id(session.actor_id.clone()).one(conn).await {
+                Ok(Some(staff_member)) if staff_member.deleted_at.is_some() => {
+                    Err(AppError::Unauthorized("This account is no longer active".to_string()))
+                }
+                Ok(Some(staff_member)) if staff_member.must_change_password => {
+                    Err(AppError::Forbidden("Change your temporary password before continuing".to_string()))
+                }
+                Ok(Some(_)) => Ok(session.actor_id),
+                Ok(None) => Err(AppError::Unauthorized("This account is no longer active".to_string())),
+                Err(err) => Err(AppError::Database(format!("Error fetching staff: {}", err))),
+            },
+            other => Err(AppError::Internal(format!("Session has an unknown account kind '{}'", other))),
+        }
+    }
+
+    // Sign out; closing a session that is already gone is not an error
+    pub async fn close<C: ConnectionTrait>(conn: &C, session_token: &str) -> Result<(), AppError> {
+        user_session::Entity::delete_by_id(Self::token_hash(session_token))
+            .exec(conn)
+            .await
+            .map(|_| ())
+            .map_err(|err| AppError::Database(format!("Error closing session: {}", err)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::test_support;
+    use sea_orm::{ActiveModelTrait, IntoActiveModel};
+
+    #[tokio::test]
+    async fn a_session_names_the_account_that_opened_it_until_closed() {
+        let state = test_support::test_state().await;
+        let customer = test_support::seed_customer(&state, "0").await;
+
+        let session_token = Session::open(&state.db, &customer.customer_id, KIND_CUSTOMER).await.unwrap();
+        assert_eq!(Session::actor(&state.db, &session_token).await.unwrap(), customer.customer_id);
+
+        Session::close(&state.db, &session_token).await.unwrap();
+        let err = Session::actor(&state.db, &session_token).await.unwrap_err();
+        assert_eq!(err.code(), ErrorCode::Unauthorized);
+    }
+
+    #[tokio::test]
+    async fn missing_and_made_up_tokens_are_refused() {
+        let state = test_support::test_state().await;
+        let staff_member = test_support::seed_staff(&state, "CEO").await;
+        Session::open(&state.db, &staff_member.staff_id, KIND_STAFF).await.unwrap();
+
+        // Knowing someone's id is not enough to act as them
+        for session_token in ["", "  ", staff_member.staff_id.as_str()] {
+            let err = Session::actor(&state.db, session_token).await.unwrap_err();
+            assert_eq!(err.code(), ErrorCode::Unauthorized, "{session_token:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn archived_staff_and_pending_rotations_cannot_act() {
+        let state = test_support::test_state().await;
+        let staff_member = test_support::seed_staff(&state, "CEO").await;
+        let session_token = Session::open(&state.db, &staff_member.staff_id, KIND_STAFF).await.unwrap();
+
+        let mut rotating = staff_member.clone().into_active_model();
+        rotating.must_change_password = Set(true);
+        rotating.update(&state.db).await.unwrap();
+        let err = Session::actor(&state.db, &session_token).await.unwrap_err();
+        assert_eq!(err.code(), ErrorCode::Forbidden);
+
+        let mut archived = staff_member.into_active_model();
+        archived.must_change_password = Set(false);
+        archived.deleted_at = Set(Some(clock::jakarta_now()));
+        archived.update(&state.db).await.unwrap();
+        let err = Session::actor(&state.db, &session_token).await.unwrap_err();
+        assert_eq!(err.code(), ErrorCode::Unauthorized);
+    }
+}
@@ -0,0 +1,125 @@
+// src-tauri/src/storage.rs
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+// Where a stored file ended up
+#[derive(Debug, Clone)]
+pub struct StoredFile {
+    pub storage_key: String,
+    pub size_bytes: i64,
+}
+
+// Backend for message attachment bytes. Handlers only see this trait, so a cloud
+// bucket can replace the local filesystem without touching them.
+#[async_trait]
+pub trait AttachmentStorage: Send + Sync {
+    async fn store(&self, file_name: &str, bytes: &[u8]) -> Result<StoredFile, String>;
+    async fn read(&self, storage_key: &str) -> Result<Vec<u8>, String>;
+    async fn delete(&self, storage_key: &str) -> Result<(), String>;
+}
+
+// Keeps attachments as plain files under one directory
+pub struct LocalFileStorage {
+    root: PathBuf,
+}
+
+impl LocalFileStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        LocalFileStorage { root: root.into() }
+    }
+
+    // Keys are generated by `store`, anything that could escape the root is rejected
+    fn path_for(&self, storage_key: &str) -> Result<PathBuf, String> {
+        if storage_key.is_empty()
+            || storage_key.contains(['/', '\\'])
+            || storage_key.contains("..")
+        {
+            return Err(format!("Invalid storage key: {}", storage_key));
+        }
+        Ok(self.root.join(storage_key))
+    }
+}
+
+#[async_trait]
+impl AttachmentStorage for LocalFileStorage {
+    async fn store(&self, file_name: &str, bytes: &[u8]) -> Result<StoredFile, String> {
+        tokio::fs::create_dir_all(&self.root)
+            .await
+            .map_err(|err| format!("Error creating attachment directory: {}", err))?;
+
+        // Keep the extension so the file is still recognisable on disk
+        let extension = std::path::Path::new(file_name)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .filter(|ext| ext.chars().all(|c| c.is_ascii_alphanumeric()))
+            .map(|ext| format!(".{}", ext.to_lowercase()))
+            .unwrap_or_default();
+        let storage_key = format!("{}{}", Uuid::new_v4(), extension);
+
+        tokio::fs::write(self.path_for(&storage_key)?, bytes)
+            .await
+            .map_err(|err| format!("Error writing attachment: {}", err))?;
+
+        Ok(StoredFile {
+            storage_key,
+            size_bytes: bytes.len() as i64,
+        })
+    }
+
+    async fn read(&self, storage_key: &str) -> Result<Vec<u8>, String> {
+        tokio::fs::read(self.path_for(storage_key)?)
+            .await
+            .map_err(|err| format!("Error reading attachment: {}", err))
+    }
+
+    async fn delete(&self, storage_key: &str) -> Result<(), String> {
+        match tokio::fs::remove_file(self.path_for(storage_key)?).await {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(format!("Error deleting attachment: {}", err)),
+        }
+    }
+}
+
+// Keeps attachments in process memory; for tests and throwaway instances
+#[derive(Default)]
+pub struct MemoryStorage {
+    files: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl AttachmentStorage for MemoryStorage {
+    async fn store(&self, _file_name: &str, bytes: &[u8]) -> Result<StoredFile, String> {
+        let storage_key = Uuid::new_v4().to_string();
+        let mut files = self.files.lock().map_err(|err| err.to_string())?;
+        files.insert(storage_key.clone(), bytes.to_vec());
+        Ok(StoredFile {
+            storage_key,
+            size_bytes: bytes.len() as i64,
+        })
+    }
+
+    async fn read(&self, storage_key: &str) -> Result<Vec<u8>, String> {
+        let files = self.files.lock().map_err(|err| err.to_string())?;
+        files
+            .get(storage_key)
+            .cloned()
+            .ok_or_else(|| format!("Error reading attachment: {} not found", storage_key))
+    }
+
+    async fn delete(&self, storage_key: &str) -> Result<(), String> {
+        let mut files = self.files.lock().map_err(|err| err.to_string())?;
+        files.remove(storage_key);
+        Ok(())
+    }
+}
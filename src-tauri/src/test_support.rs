@@ -0,0 +1,197 @@
+// src-tauri/src/test_support.rs
+
+// Shared setup for handler tests: an AppState backed by in-memory SQLite, the in-process cache
+// and in-memory attachment storage, so the suite needs no Postgres or Redis.
+
+use chrono::{NaiveTime, Utc};
+use entity::*;
+use rust_decimal::Decimal;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{Database, EntityTrait, IntoActiveModel};
+use serde::Serialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::cache::{Cache, CacheTtls, MemoryCacheBackend};
+use crate::error::AppError;
+use crate::storage::MemoryStorage;
+use crate::{ApiResponse, AppState};
+
+// A fresh, empty database per call, migrated like a kiosk's local SQLite store
+pub async fn test_state() -> AppState {
+    let db = Database::connect("sqlite::memory:").await.expect("open in-memory SQLite");
+    Migrator::up(&db, None).await.expect("migrate test database");
+
+    let cache = Cache::new(Arc::new(MemoryCacheBackend::new()), CacheTtls::default());
+    AppState::new(db, cache, Arc::new(MemoryStorage::new()))
+}
+
+// The data of a successful handler call; panics with the error otherwise
+pub fn data<T: Serialize>(result: Result<ApiResponse<T>, AppError>) -> T {
+    match result {
+        Ok(ApiResponse::Success { data, .. }) => data,
+        Ok(ApiResponse::Queued { idempotency_key, .. }) => panic!("expected success, got queued as {}", idempotency_key),
+        Ok(ApiResponse::Error { error, .. }) | Err(error) => panic!("expected success, got {:?}", error),
+    }
+}
+
+// The error of a failed handler call; panics if it succeeded
+pub fn error<T: Serialize>(result: Result<ApiResponse<T>, AppError>) -> AppError {
+    match result {
+        Ok(ApiResponse::Success { .. } | ApiResponse::Queued { .. }) => panic!("expected an error, got success"),
+        Ok(ApiResponse::Error { error, .. }) | Err(error) => error,
+    }
+}
+
+// Who the writes in handler tests are made by; audit events do not require the id to exist
+pub const ACTOR: &str = "staff-under-test";
+
+fn new_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+async fn insert<A: sea_orm::ActiveModelTrait + Send>(state: &AppState, model: A)
+where
+    <A::Entity as EntityTrait>::Model: IntoActiveModel<A>,
+{
+    A::Entity::insert(model).exec_without_returning(&state.db).await.expect("seed test row");
+}
+
+pub async fn seed_staff(state: &AppState, role: &str) -> staff::Model {
+    let id = new_id();
+    let model = staff::Model {
+        email: format!("{}@vortekia.com", id),
+        staff_id: id,
+        password_hash: String::new(),
+        name: format!("{} staff", role),
+        role: role.to_string(),
+        deleted_at: None,
+        must_change_password: false,
+        password_changed_at: None,
+        reset_token_hash: None,
+        reset_token_expires_at: None,
+    };
+    insert(state, model.clone().into_active_model()).await;
+    model
+}
+
+pub async fn seed_customer(state: &AppState, virtual_balance: &str) -> customer::Model {
+    let model = customer::Model {
+        customer_id: new_id(),
+        name: "Test Customer".to_string(),
+        virtual_balance: virtual_balance.to_string(),
+        deleted_at: None,
+        pin_hash: None,
+    };
+    insert(state, model.clone().into_active_model()).await;
+    model
+}
+
+pub async fn seed_ride(state: &AppState, staff_id: &str, price: &str) -> ride::Model {
+    let model = ride::Model {
+        ride_id: new_id(),
+        status: "Operational".to_string(),
+        name: "Coaster".to_string(),
+        price: price.to_string(),
+        location: "North".to_string(),
+        staff_id: staff_id.to_string(),
+        photo: None,
+        deleted_at: None,
+    };
+    insert(state, model.clone().into_active_model()).await;
+    model
+}
+
+pub async fn seed_restaurant(state: &AppState) -> restaurant::Model {
+    let model = restaurant::Model {
+        restaurant_id: new_id(),
+        name: "Noodle Bar".to_string(),
+        photo: None,
+        opening_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap_or_default(),
+        closing_time: NaiveTime::from_hms_opt(21, 0, 0).unwrap_or_default(),
+        cuisine_type: "Asian".to_string(),
+        location: None,
+        status: "Open".to_string(),
+        deleted_at: None,
+    };
+    insert(state, model.clone().into_active_model()).await;
+    model
+}
+
+pub async fn seed_menu_item(state: &AppState, restaurant_id: &str, price: &str) -> menu_item::Model {
+    let model = menu_item::Model {
+        menu_item_id: new_id(),
+        photo: None,
+        name: "Ramen".to_string(),
+        price: price.to_string(),
+        restaurant_id: restaurant_id.to_string(),
+        deleted_at: None,
+    };
+    insert(state, model.clone().into_active_model()).await;
+    model
+}
+
+pub async fn seed_store(state: &AppState) -> store::Model {
+    let model = store::Model {
+        store_id: new_id(),
+        name: "Gift Shop".to_string(),
+        photo: None,
+        opening_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap_or_default(),
+        closing_time: NaiveTime::from_hms_opt(21, 0, 0).unwrap_or_default(),
+        location: None,
+        status: "Open".to_string(),
+        deleted_at: None,
+    };
+    insert(state, model.clone().into_active_model()).await;
+    model
+}
+
+pub async fn seed_souvenir(state: &AppState, store_id: &str, price: Decimal, stock: i32) -> souvenir::Model {
+    let model = souvenir::Model {
+        souvenir_id: new_id(),
+        name: "Plush".to_string(),
+        photo: None,
+        price,
+        stock,
+        store_id: store_id.to_string(),
+    };
+    insert(state, model.clone().into_active_model()).await;
+    model
+}
+
+// Orders and queue entries timestamped now, in UTC, for the reports' date ranges
+pub async fn seed_order_souvenir(state: &AppState, souvenir: &souvenir::Model, customer_id: &str, quantity: i32) {
+    let model = order_souvenir::Model {
+        order_souvenir_id: new_id(),
+        customer_id: customer_id.to_string(),
+        store_id: souvenir.store_id.clone(),
+        souvenir_id: souvenir.souvenir_id.clone(),
+        quantity,
+        timestamp: Utc::now().naive_utc(),
+    };
+    insert(state, model.into_active_model()).await;
+}
+
+pub async fn seed_order_restaurant(state: &AppState, menu_item: &menu_item::Model, customer_id: &str, quantity: i32) {
+    let model = order_restaurant::Model {
+        order_restaurant_id: new_id(),
+        customer_id: customer_id.to_string(),
+        restaurant_id: menu_item.restaurant_id.clone(),
+        menu_item_id: menu_item.menu_item_id.clone(),
+        quantity,
+        timestamp: Utc::now().naive_utc(),
+        status: "Complete".to_string(),
+    };
+    insert(state, model.into_active_model()).await;
+}
+
+pub async fn seed_ride_queue(state: &AppState, ride_id: &str, customer_id: &str) {
+    let model = ride_queue::Model {
+        ride_queue_id: new_id(),
+        ride_id: ride_id.to_string(),
+        joined_at: Utc::now().naive_utc(),
+        customer_id: customer_id.to_string(),
+        queue_position: Decimal::ONE,
+    };
+    insert(state, model.into_active_model()).await;
+}
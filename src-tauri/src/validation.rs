@@ -0,0 +1,271 @@
+// src-tauri/src/validation.rs
+
+use chrono::{NaiveDateTime, NaiveTime};
+use rust_decimal::Decimal;
+
+use crate::error::{AppError, FieldError};
+
+pub const MAX_NAME_LENGTH: usize = 100;
+pub const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 128;
+const MAX_MONEY_SCALE: u32 = 2;
+
+// A check on one raw input. The error describes what is wrong and reads after the field's label,
+// e.g. "must be a positive amount".
+pub type Rule = fn(&str) -> Result<(), String>;
+
+pub fn required(value: &str) -> Result<(), String> {
+    if value.trim().is_empty() {
+        Err("is required".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+pub fn name(value: &str) -> Result<(), String> {
+    required(value)?;
+    if value.trim().chars().count() > MAX_NAME_LENGTH {
+        return Err(format!("must be at most {} characters", MAX_NAME_LENGTH));
+    }
+    Ok(())
+}
+
+pub fn email(value: &str) -> Result<(), String> {
+    let invalid = || Err("must be a valid email address".to_string());
+    let Some((local, domain)) = value.trim().split_once('@') else {
+        return invalid();
+    };
+    let domain_ok = !domain.contains('@')
+        && domain.contains('.')
+        && domain.split('.').all(|label| !label.is_empty());
+    if local.is_empty() || !domain_ok || value.trim().chars().any(char::is_whitespace) {
+        return invalid();
+    }
+    Ok(())
+}
+
+fn money(value: &str) -> Result<Decimal, String> {
+    let amount = Decimal::from_str_exact(value.trim()).map_err(|_| "must be a number".to_string())?;
+    if amount.scale() > MAX_MONEY_SCALE {
+        return Err(format!("must have at most {} decimal places", MAX_MONEY_SCALE));
+    }
+    Ok(amount)
+}
+
+// Prices: anything sold must cost something
+pub fn positive_money(value: &str) -> Result<(), String> {
+    if money(value)? <= Decimal::ZERO {
+        return Err("must be greater than zero".to_string());
+    }
+    Ok(())
+}
+
+// Prices as stored, to charge them. Prices were validated when they were saved, so one that does
+// not read back is a fault in the data rather than in the order.
+pub fn parse_price(price: &str) -> Result<Decimal, AppError> {
+    price.trim().parse::<Decimal>().map_err(|_| AppError::Internal(format!("Unreadable price {:?}", price)))
+}
+
+// Balances: may run down to zero but never below. Older balances were recomputed by the frontend,
+// so the number of decimal places is not limited here.
+pub fn non_negative_money(value: &str) -> Result<(), String> {
+    let amount = Decimal::from_str_exact(value.trim()).map_err(|_| "must be a number".to_string())?;
+    if amount < Decimal::ZERO {
+        return Err("cannot be negative".to_string());
+    }
+    Ok(())
+}
+
+pub fn positive_whole_number(value: &str) -> Result<(), String> {
+    match value.trim().parse::<i64>() {
+        Ok(number) if number > 0 => Ok(()),
+        _ => Err("must be a positive whole number".to_string()),
+    }
+}
+
+// Opening and closing times, "HH:MM:SS"
+pub fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value, "%H:%M:%S").ok()
+}
+
+pub fn time_of_day(value: &str) -> Result<(), String> {
+    parse_time(value)
+        .map(|_| ())
+        .ok_or_else(|| "must be a time formatted as HH:MM:SS".to_string())
+}
+
+// Dates as sent by datetime-local inputs, "YYYY-MM-DDTHH:MM"
+pub fn parse_local_date_time(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M").ok()
+}
+
+pub fn local_date_time(value: &str) -> Result<(), String> {
+    parse_local_date_time(value)
+        .map(|_| ())
+        .ok_or_else(|| "must be a date and time formatted as YYYY-MM-DDTHH:MM".to_string())
+}
+
+pub fn pin(value: &str) -> Result<(), String> {
+    if (4..=6).contains(&value.len()) && value.chars().all(|c| c.is_ascii_digit()) {
+        Ok(())
+    } else {
+        Err("must be 4 to 6 digits".to_string())
+    }
+}
+
+// Client-generated, e.g. a UUID; printable ASCII so it reads back the same from every backend
+pub fn idempotency_key(value: &str) -> Result<(), String> {
+    required(value)?;
+    if value.len() > MAX_IDEMPOTENCY_KEY_LENGTH {
+        return Err(format!("must be at most {} characters", MAX_IDEMPOTENCY_KEY_LENGTH));
+    }
+    if !value.chars().all(|c| c.is_ascii_graphic()) {
+        return Err("must only contain letters, digits and punctuation".to_string());
+    }
+    Ok(())
+}
+
+// "virtual_balance" -> "Virtual balance"
+fn label(field: &str) -> String {
+    let words = field.replace('_', " ");
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => words,
+    }
+}
+
+// Collects every invalid field of a command's input, so the UI can flag them all in one go
+#[derive(Debug, Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Run the rules in order, keeping only the first failure so each field gets one message
+    pub fn field(&mut self, field: &str, value: &str, rules: &[Rule]) -> &mut Self {
+        if let Some(problem) = rules.iter().find_map(|rule| rule(value).err()) {
+            self.errors.push(FieldError::new(field, format!("{} {}", label(field), problem)));
+        }
+        self
+    }
+
+    // For update commands, where a missing value means "leave unchanged"
+    pub fn optional(&mut self, field: &str, value: Option<&str>, rules: &[Rule]) -> &mut Self {
+        match value {
+            Some(value) => self.field(field, value, rules),
+            None => self,
+        }
+    }
+
+    pub fn one_of(&mut self, field: &str, value: &str, allowed: &[&str]) -> &mut Self {
+        let message = format!("{} must be one of: {}", label(field), allowed.join(", "));
+        self.check(field, allowed.contains(&value), message)
+    }
+
+    pub fn optional_one_of(&mut self, field: &str, value: Option<&str>, allowed: &[&str]) -> &mut Self {
+        match value {
+            Some(value) => self.one_of(field, value, allowed),
+            None => self,
+        }
+    }
+
+    // Restaurants and stores are open between the two times on the same day
+    pub fn opening_hours(&mut self, opening_time: Option<&str>, closing_time: Option<&str>) -> &mut Self {
+        self.optional("opening_time", opening_time, &[time_of_day])
+            .optional("closing_time", closing_time, &[time_of_day]);
+        if let (Some(opening), Some(closing)) = (opening_time.and_then(parse_time), closing_time.and_then(parse_time)) {
+            self.check("closing_time", closing > opening, "Closing time must be after opening time");
+        }
+        self
+    }
+
+    // Scheduled work: both dates well-formed and the end after the start
+    pub fn date_range(&mut self, start_date: Option<&str>, end_date: Option<&str>) -> &mut Self {
+        self.optional("start_date", start_date, &[local_date_time])
+            .optional("end_date", end_date, &[local_date_time]);
+        if let (Some(start), Some(end)) = (start_date.and_then(parse_local_date_time), end_date.and_then(parse_local_date_time)) {
+            self.check("end_date", end > start, "End date must be after start date");
+        }
+        self
+    }
+
+    pub fn at_least(&mut self, field: &str, value: i32, min: i32) -> &mut Self {
+        let message = format!("{} must be at least {}", label(field), min);
+        self.check(field, value >= min, message)
+    }
+
+    // Anything the rules above cannot express, e.g. cross-field or database checks; the message
+    // is used as is
+    pub fn check(&mut self, field: &str, valid: bool, message: impl Into<String>) -> &mut Self {
+        if !valid && !self.errors.iter().any(|error| error.field == field) {
+            self.errors.push(FieldError::new(field, message));
+        }
+        self
+    }
+
+    pub fn is_valid(&self, field: &str) -> bool {
+        !self.errors.iter().any(|error| error.field == field)
+    }
+
+    pub fn finish(&mut self) -> Result<(), AppError> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+        let fields = std::mem::take(&mut self.errors);
+        let message = fields.iter().map(|error| error.message.as_str()).collect::<Vec<_>>().join("; ");
+        Err(AppError::Validation { message, fields })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_every_invalid_field() {
+        let error = Validator::new()
+            .field("name", " ", &[name])
+            .field("price", "-5", &[required, positive_money])
+            .field("email", "staff@vortekia.com", &[email])
+            .at_least("quantity", 0, 1)
+            .finish()
+            .unwrap_err();
+
+        let fields: Vec<&str> = error.fields().iter().map(|field| field.field.as_str()).collect();
+        assert_eq!(fields, ["name", "price", "quantity"]);
+        assert_eq!(
+            error.message(),
+            "Name is required; Price must be greater than zero; Quantity must be at least 1"
+        );
+    }
+
+    #[test]
+    fn optional_fields_are_only_checked_when_given() {
+        assert!(Validator::new().optional("price", None, &[positive_money]).finish().is_ok());
+        assert!(Validator::new().optional("price", Some("abc"), &[positive_money]).finish().is_err());
+    }
+
+    #[test]
+    fn shared_rules() {
+        assert!(email("a@b.co").is_ok());
+        for bad in ["", "ab.co", "a@b", "a@@b.co", "a b@c.co", "a@b..co"] {
+            assert!(email(bad).is_err(), "{bad}");
+        }
+        assert!(positive_money("12500.50").is_ok());
+        assert!(positive_money("0").is_err());
+        assert!(positive_money("1.005").is_err());
+        assert!(non_negative_money("0").is_ok());
+        assert!(non_negative_money("-1").is_err());
+        assert!(pin("1234").is_ok());
+        assert!(pin("12a4").is_err());
+        assert!(time_of_day("09:30:00").is_ok());
+        assert!(time_of_day("9.30").is_err());
+        assert!(idempotency_key("3f2c9a1e-7b4d-4e8a-9c1f-2d6b8e0a5c73").is_ok());
+        assert!(idempotency_key("two words").is_err());
+        assert!(idempotency_key(&"k".repeat(MAX_IDEMPOTENCY_KEY_LENGTH + 1)).is_err());
+    }
+}
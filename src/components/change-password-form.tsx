@@ -0,0 +1,122 @@
+"use client";
+
+import type React from "react";
+import { useEffect, useState } from "react";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import { invoke } from "@tauri-apps/api/core";
+import { ApiResponse, PasswordPolicy } from "@/types";
+
+interface ChangePasswordFormProps {
+  staffId: string;
+  currentPassword?: string; // Already known when rotation is forced right after login
+  onDone: () => void;
+}
+
+// Describe the backend password policy as a single hint line
+function describePolicy(policy: PasswordPolicy) {
+  const rules = [`at least ${policy.min_length} characters`];
+  if (policy.require_uppercase) rules.push("an uppercase letter");
+  if (policy.require_lowercase) rules.push("a lowercase letter");
+  if (policy.require_digit) rules.push("a digit");
+  if (policy.require_symbol) rules.push("a symbol");
+  return `Use ${rules.join(", ")}.`;
+}
+
+export function ChangePasswordForm({
+  staffId,
+  currentPassword,
+  onDone,
+}: ChangePasswordFormProps) {
+  const [oldPassword, setOldPassword] = useState(currentPassword ?? "");
+  const [newPassword, setNewPassword] = useState("");
+  const [confirmPassword, setConfirmPassword] = useState("");
+  const [policyHint, setPolicyHint] = useState("");
+  const [error, setError] = useState("");
+  const [isLoading, setIsLoading] = useState(false);
+
+  useEffect(() => {
+    invoke<ApiResponse<PasswordPolicy>>("view_password_policy")
+      .then((response) => {
+        if (response.status === "success" && response.data) {
+          setPolicyHint(describePolicy(response.data));
+        }
+      })
+      .catch((error) => console.error("Error fetching password policy:", error));
+  }, []);
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setError("");
+    if (newPassword !== confirmPassword) {
+      setError("Passwords do not match");
+      return;
+    }
+    setIsLoading(true);
+    try {
+      const response = await invoke<ApiResponse<string>>(
+        "change_staff_password",
+        {
+          staffId,
+          currentPassword: oldPassword,
+          newPassword,
+        }
+      );
+      if (response.status === "success") {
+        onDone();
+      } else {
+        setError(response.message || "Failed to change password.");
+      }
+    } catch (error) {
+      console.error("Error invoking change_staff_password:", error);
+      setError("" + error);
+    } finally {
+      setIsLoading(false);
+    }
+  };
+
+  return (
+    <form onSubmit={handleSubmit} className="space-y-4 pt-4">
+      {currentPassword === undefined && (
+        <div className="space-y-2">
+          <Label htmlFor="current-password">Current Password</Label>
+          <Input
+            id="current-password"
+            type="password"
+            value={oldPassword}
+            onChange={(e) => setOldPassword(e.target.value)}
+            disabled={isLoading}
+          />
+        </div>
+      )}
+      <div className="space-y-2">
+        <Label htmlFor="new-password">New Password</Label>
+        <Input
+          id="new-password"
+          type="password"
+          value={newPassword}
+          onChange={(e) => setNewPassword(e.target.value)}
+          disabled={isLoading}
+        />
+        {policyHint && (
+          <p className="text-sm text-muted-foreground">{policyHint}</p>
+        )}
+      </div>
+      <div className="space-y-2">
+        <Label htmlFor="confirm-password">Confirm New Password</Label>
+        <Input
+          id="confirm-password"
+          type="password"
+          value={confirmPassword}
+          onChange={(e) => setConfirmPassword(e.target.value)}
+          disabled={isLoading}
+        />
+        {error && <p className="text-sm text-destructive">{error}</p>}
+      </div>
+      <Button type="submit" className="w-full" disabled={isLoading}>
+        {isLoading ? "Saving..." : "Change Password"}
+      </Button>
+    </form>
+  );
+}
@@ -22,14 +22,22 @@ import { Save, X } from "lucide-react";
 const formSchema = z.object({
   name: z.string().min(2).max(50),
   virtual_balance: z.string().min(1).max(50), // You can adjust this as per your needs
+  pin: z
+    .string()
+    .regex(/^(\d{4,6})?$/, { message: "PIN must be 4 to 6 digits." }), // Blank keeps the current PIN when editing
 });
 
 interface CustomerFormProps {
-  createCustomer: (name: string, virtual_balance: string) => Promise<void>;
+  createCustomer: (
+    name: string,
+    virtual_balance: string,
+    pin: string
+  ) => Promise<void>;
   updateCustomer: (
     customer_id: string,
     name: string,
-    virtual_balance: string
+    virtual_balance: string,
+    pin: string | null
   ) => Promise<void>;
   editingCustomer: Customer | null;
   setEditingCustomer: (customer: Customer | null) => void;
@@ -46,6 +54,7 @@ export function CustomerForm({
   const defaultValues = {
     name: "",
     virtual_balance: "",
+    pin: "",
   };
 
   const form = useForm<z.infer<typeof formSchema>>({
@@ -59,6 +68,7 @@ export function CustomerForm({
       form.reset({
         name: editingCustomer.name,
         virtual_balance: editingCustomer.virtual_balance,
+        pin: "",
       });
     } else {
       setIsUpdate(false);
@@ -71,11 +81,16 @@ export function CustomerForm({
       updateCustomer(
         editingCustomer.customer_id,
         values.name,
-        values.virtual_balance
+        values.virtual_balance,
+        values.pin || null
       );
       setEditingCustomer(null);
     } else {
-      createCustomer(values.name, values.virtual_balance);
+      if (!values.pin) {
+        form.setError("pin", { message: "A PIN is required for new accounts." });
+        return;
+      }
+      createCustomer(values.name, values.virtual_balance, values.pin);
     }
 
     form.reset();
@@ -130,6 +145,28 @@ export function CustomerForm({
               )}
             />
 
+            <FormField
+              control={form.control}
+              name="pin"
+              render={({ field }) => (
+                <FormItem>
+                  <FormLabel className="text-foreground/90">
+                    {isUpdate ? "New PIN (leave blank to keep)" : "PIN"}
+                  </FormLabel>
+                  <FormControl>
+                    <Input
+                      type="password"
+                      inputMode="numeric"
+                      placeholder="4 to 6 digits"
+                      {...field}
+                      className="bg-background/50 backdrop-blur-sm border-primary/20 focus-visible:ring-primary"
+                    />
+                  </FormControl>
+                  <FormMessage />
+                </FormItem>
+              )}
+            />
+
             <div className="flex items-center gap-3 pt-2">
               <Button type="submit" className="flex-1 gap-2">
                 <Save className="h-4 w-4" />
@@ -0,0 +1,59 @@
+"use client";
+
+import {
+  AlertDialog,
+  AlertDialogCancel,
+  AlertDialogContent,
+  AlertDialogDescription,
+  AlertDialogFooter,
+  AlertDialogHeader,
+  AlertDialogTitle,
+} from "@/components/ui/alert-dialog";
+import { Button } from "@/components/ui/button";
+import { DependencyReport } from "@/types";
+
+interface DeleteBlockedDialogProps {
+  report: DependencyReport | null;
+  message: string;
+  onCancel: () => void;
+  onCascade: () => Promise<void>;
+}
+
+// Shown when a delete is refused because other records still depend on it
+export function DeleteBlockedDialog({
+  report,
+  message,
+  onCancel,
+  onCascade,
+}: DeleteBlockedDialogProps) {
+  const canCascade =
+    report?.dependents.every((dependent) => dependent.cascade_action !== null) ??
+    false;
+
+  return (
+    <AlertDialog open={report !== null} onOpenChange={(isOpen) => !isOpen && onCancel()}>
+      <AlertDialogContent>
+        <AlertDialogHeader>
+          <AlertDialogTitle>Cannot delete yet</AlertDialogTitle>
+          <AlertDialogDescription>{message}</AlertDialogDescription>
+        </AlertDialogHeader>
+        <ul className="list-disc space-y-1 pl-6 text-sm">
+          {report?.dependents.map((dependent) => (
+            <li key={dependent.kind}>
+              {dependent.count} {dependent.kind}:{" "}
+              {dependent.cascade_action ?? "must be resolved manually"}
+            </li>
+          ))}
+        </ul>
+        <AlertDialogFooter>
+          <AlertDialogCancel>Cancel</AlertDialogCancel>
+          {canCascade && (
+            <Button variant="destructive" onClick={onCascade}>
+              Delete with dependents
+            </Button>
+          )}
+        </AlertDialogFooter>
+      </AlertDialogContent>
+    </AlertDialog>
+  );
+}
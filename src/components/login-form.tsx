@@ -8,6 +8,7 @@ import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
 import { invoke } from "@tauri-apps/api/core";
 import { ApiResponse } from "@/types";
+import { getDeviceId } from "@/lib/device";
 
 interface LoginFormProps {
   onLogin: (sessionToken: string, uid: string) => void;
@@ -15,19 +16,22 @@ interface LoginFormProps {
 
 export function LoginForm({ onLogin }: LoginFormProps) {
   const [uid, setUid] = useState("");
+  const [pin, setPin] = useState("");
   const [error, setError] = useState("");
   const [isLoading, setIsLoading] = useState(false);
 
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
-    if (!uid.trim()) {
-      setError("Please enter your UID");
+    if (!uid.trim() || !pin.trim()) {
+      setError("Please enter your UID and PIN");
       return;
     }
     setIsLoading(true);
     try {
       const response = await invoke<ApiResponse<string>>("customer_login", {
         customerId: uid,
+        pin,
+        deviceId: getDeviceId(),
       }); // Call Tauri command
 
       if (response.status === "success") {
@@ -64,6 +68,18 @@ export function LoginForm({ onLogin }: LoginFormProps) {
           onChange={(e) => setUid(e.target.value)}
           disabled={isLoading} // Disable input while loading
         />
+      </div>
+      <div className="space-y-2">
+        <Label htmlFor="pin">PIN</Label>
+        <Input
+          id="pin"
+          type="password"
+          inputMode="numeric"
+          placeholder="Enter your PIN"
+          value={pin}
+          onChange={(e) => setPin(e.target.value)}
+          disabled={isLoading}
+        />
         {error && <p className="text-sm text-destructive">{error}</p>}
       </div>
       <Button type="submit" className="w-full" disabled={isLoading}>
@@ -230,11 +230,11 @@ export function LostAndFoundItemsLogForm({
                       </SelectTrigger>
                     </FormControl>
                     <SelectContent>
-                      <SelectItem value="Returned to Owner">
-                        Returned to Owner
-                      </SelectItem>
+                      {/* Returning and disposal go through their own actions; Found items cannot go back to Missing */}
                       <SelectItem value="Found">Found</SelectItem>
-                      <SelectItem value="Missing">Missing</SelectItem>
+                      {editingLog?.status !== "Found" && (
+                        <SelectItem value="Missing">Missing</SelectItem>
+                      )}
                     </SelectContent>
                   </Select>
                   <FormMessage />
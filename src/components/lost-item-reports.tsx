@@ -0,0 +1,173 @@
+"use client";
+
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { toast } from "sonner";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import { Badge } from "@/components/ui/badge";
+import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
+import { useUser } from "@/context/user-context";
+import { ApiResponse, LostAndFoundItemsLog } from "@/types";
+
+// Customer-facing: file a lost item report and follow the status of past ones
+export function LostItemReports() {
+  const { uid, isLoggedIn, sessionToken } = useUser();
+  const [reports, setReports] = useState<LostAndFoundItemsLog[]>([]);
+  const [name, setName] = useState("");
+  const [type, setType] = useState("");
+  const [color, setColor] = useState("");
+  const [lastSeenLocation, setLastSeenLocation] = useState("");
+  const [submitting, setSubmitting] = useState(false);
+
+  useEffect(() => {
+    if (uid) {
+      fetchReports(uid);
+    } else {
+      setReports([]);
+    }
+  }, [uid]);
+
+  async function fetchReports(customerId: string) {
+    try {
+      const response = await invoke<ApiResponse<LostAndFoundItemsLog[]>>(
+        "view_lost_item_reports_by_customer",
+        { customerId }
+      );
+      setReports(response.data || []);
+    } catch (error) {
+      console.error("Failed to fetch lost item reports:", error);
+    }
+  }
+
+  async function fileReport() {
+    if (!uid) return;
+    setSubmitting(true);
+    try {
+      const response = await invoke<ApiResponse<string>>("file_missing_report", {
+        sessionToken,
+        name,
+        type,
+        color,
+        lastSeenLocation,
+      });
+      if (response.status === "error") {
+        toast.error(response.message || "Failed to file report.");
+      } else {
+        toast.success("Report filed. We will message you in Customer Service chat if it turns up.");
+        setName("");
+        setType("");
+        setColor("");
+        setLastSeenLocation("");
+        fetchReports(uid);
+      }
+    } catch (error) {
+      console.error("Failed to file lost item report:", error);
+      toast.error(String(error));
+    } finally {
+      setSubmitting(false);
+    }
+  }
+
+  if (!isLoggedIn() || !uid) {
+    return null;
+  }
+
+  const canSubmit =
+    name.trim() !== "" &&
+    type.trim() !== "" &&
+    color.trim() !== "" &&
+    lastSeenLocation.trim() !== "";
+
+  return (
+    <section className="py-12 bg-gray-50">
+      <div className="container mx-auto px-4">
+        <h2 className="text-3xl font-bold mb-8">Lost Something?</h2>
+
+        <div className="grid gap-8 md:grid-cols-2">
+          <Card>
+            <CardHeader>
+              <CardTitle>Report a Lost Item</CardTitle>
+            </CardHeader>
+            <CardContent className="space-y-4">
+              <div className="space-y-2">
+                <Label htmlFor="lost-name">Item</Label>
+                <Input
+                  id="lost-name"
+                  placeholder="e.g. Black iPhone 13"
+                  value={name}
+                  onChange={(e) => setName(e.target.value)}
+                />
+              </div>
+              <div className="space-y-2">
+                <Label htmlFor="lost-type">Type</Label>
+                <Input
+                  id="lost-type"
+                  placeholder="e.g. Phone"
+                  value={type}
+                  onChange={(e) => setType(e.target.value)}
+                />
+              </div>
+              <div className="space-y-2">
+                <Label htmlFor="lost-color">Color</Label>
+                <Input
+                  id="lost-color"
+                  value={color}
+                  onChange={(e) => setColor(e.target.value)}
+                />
+              </div>
+              <div className="space-y-2">
+                <Label htmlFor="lost-location">Last Seen Location</Label>
+                <Input
+                  id="lost-location"
+                  value={lastSeenLocation}
+                  onChange={(e) => setLastSeenLocation(e.target.value)}
+                />
+              </div>
+              <Button
+                className="w-full"
+                onClick={fileReport}
+                disabled={!canSubmit || submitting}
+              >
+                File Report
+              </Button>
+            </CardContent>
+          </Card>
+
+          <Card>
+            <CardHeader>
+              <CardTitle>My Reports</CardTitle>
+            </CardHeader>
+            <CardContent className="space-y-3">
+              {reports.length === 0 && (
+                <p className="text-sm text-muted-foreground">
+                  You have no lost item reports.
+                </p>
+              )}
+              {reports.map((report) => (
+                <div
+                  key={report.log_id}
+                  className="flex items-center justify-between border-b pb-2 last:border-b-0"
+                >
+                  <div>
+                    <p className="font-medium">{report.name}</p>
+                    <p className="text-xs text-muted-foreground">
+                      {report.type}, {report.color} · last seen at{" "}
+                      {report.last_seen_location || "N/A"}
+                    </p>
+                  </div>
+                  <Badge
+                    variant={report.status === "Missing" ? "outline" : "default"}
+                  >
+                    {report.status}
+                  </Badge>
+                </div>
+              ))}
+            </CardContent>
+          </Card>
+        </div>
+      </div>
+    </section>
+  );
+}
@@ -25,7 +25,7 @@ interface NavbarProps {
 }
 
 export function Navbar({ title }: NavbarProps) {
-  const { isLoggedIn, login, logout, customerName, virtualBalance } = useUser();
+  const { isLoggedIn, login, logout, customerName, virtualBalance, uid, sessionToken } = useUser();
   const [isTopUpDialogOpen, setIsTopUpDialogOpen] = useState(false);
   const navigate = useNavigate();
 
@@ -103,7 +103,7 @@ export function Navbar({ title }: NavbarProps) {
               >
                 <MessageSquare className="h-5 w-5" />
               </Button>
-              <NotificationPopover audience="Customer" />
+              <NotificationPopover audience="Customer" recipientId={uid} sessionToken={sessionToken} />
               <Button variant="default" onClick={logout}>
                 Logout
               </Button>
@@ -12,36 +12,83 @@ import { Separator } from "@/components/ui/separator";
 import { Bell, Loader2, CheckCircle2 } from "lucide-react";
 import { Badge } from "@/components/ui/badge";
 import { formatDistanceToNow } from "date-fns";
-import { ApiResponse, BroadcastMessage } from "@/types"; // Import types
+import { ApiResponse, BroadcastMessage, RecipientBroadcast } from "@/types"; // Import types
 import { invoke } from "@tauri-apps/api/core"; // Import invoke
+import { listen } from "@tauri-apps/api/event"; // Live broadcasts from the dispatcher
 import { toast } from "sonner"; // Import toast for error messages
 
 interface NotificationPopoverProps {
   audience: "Customer" | "Staff";
+  recipientId?: string | null; // Logged-in customer/staff id; enables targeted broadcasts and read tracking
+  sessionToken?: string | null; // That customer/staff member's session, which the backend reads the recipient from
 }
 
-export function NotificationPopover({ audience }: NotificationPopoverProps) {
+// Payload of the "broadcast_message_sent" event
+type SentBroadcast = BroadcastMessage & { recipient_ids: string[] | null };
+
+export function NotificationPopover({ audience, recipientId, sessionToken }: NotificationPopoverProps) {
   const [open, setOpen] = useState(false);
   const [loading, setLoading] = useState(false);
   const [notifications, setNotifications] = useState<BroadcastMessage[]>([]);
   const [error, setError] = useState<string | null>(null);
 
-  // **Commented out read/unread functionality for now**
-  // const unreadCount = notifications.filter((notification) => !notification.is_read).length
-  const unreadCount = notifications.length; // Simple count for now
+  const unreadCount = recipientId
+    ? notifications.filter((notification) => !notification.is_read).length
+    : notifications.length; // Without a recipient there is no read tracking
 
   // Fetch notifications when popover is opened
   useEffect(() => {
     if (open) {
       fetchNotifications();
     }
-  }, [open, audience]); // Added audience as dependency
+  }, [open, audience, recipientId]); // Added audience as dependency
+
+  // Scheduled broadcasts are pushed by the backend dispatcher as they go out
+  useEffect(() => {
+    const unlisten = listen<SentBroadcast>("broadcast_message_sent", (event) => {
+      const { recipient_ids, ...broadcast } = event.payload;
+      if (broadcast.target_audience !== audience) return;
+      // Targeted broadcasts list their recipients; whole-audience and venue ones do not
+      if (recipient_ids && (!recipientId || !recipient_ids.includes(recipientId))) return;
+      setNotifications((current) => [
+        { ...broadcast, is_read: false },
+        ...current.filter(
+          (n) => n.broadcast_message_id !== broadcast.broadcast_message_id
+        ),
+      ]);
+      toast(broadcast.content);
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, [audience, recipientId]);
 
   const fetchNotifications = async () => {
     try {
       setLoading(true);
       setError(null);
 
+      if (recipientId && sessionToken) {
+        // Everything sent to this customer/staff member, including targeted broadcasts
+        const response = await invoke<ApiResponse<RecipientBroadcast[]>>(
+          "view_broadcast_messages_for_recipient",
+          { sessionToken }
+        );
+        if (response.status === "success" && response.data) {
+          setNotifications(
+            response.data.map((received) => ({
+              ...received.broadcast,
+              is_read: received.read_at !== null,
+            }))
+          );
+        } else {
+          console.error("Failed to fetch notifications:", response.message);
+          setError(response.message || "Failed to fetch notifications.");
+          toast.error(response.message || "Failed to fetch notifications.");
+        }
+        return;
+      }
+
       // Call backend function
       const response = await invoke<ApiResponse<BroadcastMessage[]>>(
         "view_broadcast_messages_by_audience",
@@ -64,15 +111,30 @@ export function NotificationPopover({ audience }: NotificationPopoverProps) {
     }
   };
 
-  // **Commented out markAsRead function**
-  // const markAsRead = async (notificationId: string) => {
-  //   // ... (Implementation for marking read)
-  // }
+  const markAsRead = async (notificationId: string) => {
+    if (!recipientId || !sessionToken) return;
+    try {
+      await invoke<ApiResponse<string>>("mark_broadcast_message_read", {
+        broadcastMessageId: notificationId,
+        sessionToken,
+      });
+      setNotifications((current) =>
+        current.map((n) =>
+          n.broadcast_message_id === notificationId ? { ...n, is_read: true } : n
+        )
+      );
+    } catch (err: any) {
+      console.error("Error marking notification as read:", err);
+      toast.error("Failed to mark notification as read.");
+    }
+  };
 
-  // **Commented out markAllAsRead function**
-  // const markAllAsRead = async () => {
-  //   // ... (Implementation for marking all read)
-  // }
+  const markAllAsRead = async () => {
+    const unread = notifications.filter((notification) => !notification.is_read);
+    for (const notification of unread) {
+      await markAsRead(notification.broadcast_message_id);
+    }
+  };
 
   // Format date for display
   const formatDate = (dateString: string) => {
@@ -112,12 +174,11 @@ export function NotificationPopover({ audience }: NotificationPopoverProps) {
       <PopoverContent className="w-80 md:w-96 p-0" align="end">
         <div className="flex items-center justify-between p-4 bg-muted/50">
           <h3 className="font-medium">Notifications</h3>
-          {/* **Commented out Mark All Read button** */}
-          {/* {unreadCount > 0 && (
+          {recipientId && unreadCount > 0 && (
             <Button variant="ghost" size="sm" className="text-xs h-8" onClick={markAllAsRead}>
               Mark all as read
             </Button>
-          )} */}
+          )}
         </div>
         <Separator />
         <ScrollArea className="h-[400px]">
@@ -145,7 +206,9 @@ export function NotificationPopover({ audience }: NotificationPopoverProps) {
             notifications.map((notification) => (
               <div
                 key={notification.broadcast_message_id}
-                className={`p-4 border-b last:border-b-0`} // Removed background change based on read status
+                className={`p-4 border-b last:border-b-0 ${
+                  recipientId && !notification.is_read ? "bg-muted/30" : ""
+                }`}
               >
                 <div className="flex justify-between items-start gap-2">
                   <div className="flex-1">
@@ -154,10 +217,9 @@ export function NotificationPopover({ audience }: NotificationPopoverProps) {
                     </p>
                     <div className="flex items-center justify-between mt-2">
                       <span className="text-xs text-muted-foreground">
-                        {formatDate(notification.timestamp)}
+                        {formatDate(notification.sent_at ?? notification.timestamp)}
                       </span>
-                      {/* **Commented out Mark as Read button** */}
-                      {/* {!notification.is_read && (
+                      {recipientId && !notification.is_read && (
                         <Button
                           variant="ghost"
                           size="sm"
@@ -167,7 +229,7 @@ export function NotificationPopover({ audience }: NotificationPopoverProps) {
                           <CheckCircle2 className="h-3 w-3 mr-1" />
                           Mark as read
                         </Button>
-                      )} */}
+                      )}
                     </div>
                   </div>
                 </div>
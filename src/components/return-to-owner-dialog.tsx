@@ -0,0 +1,125 @@
+"use client";
+
+import { useEffect, useState } from "react";
+import { Button } from "@/components/ui/button";
+import {
+  Dialog,
+  DialogContent,
+  DialogFooter,
+  DialogHeader,
+  DialogTitle,
+} from "@/components/ui/dialog";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+import { OwnerVerification } from "@/types";
+
+interface ReturnToOwnerDialogProps {
+  open: boolean;
+  defaultOwner?: string;
+  onCancel: () => void;
+  onConfirm: (verification: OwnerVerification) => Promise<void>;
+}
+
+// Captures who is collecting an item and how staff verified they own it
+export function ReturnToOwnerDialog({
+  open,
+  defaultOwner,
+  onCancel,
+  onConfirm,
+}: ReturnToOwnerDialogProps) {
+  const [owner, setOwner] = useState("");
+  const [method, setMethod] = useState("CustomerAccount");
+  const [customerId, setCustomerId] = useState("");
+  const [reference, setReference] = useState("");
+
+  useEffect(() => {
+    if (open) {
+      setOwner(defaultOwner || "");
+      setMethod("CustomerAccount");
+      setCustomerId("");
+      setReference("");
+    }
+  }, [open, defaultOwner]);
+
+  const isValid =
+    owner.trim() !== "" &&
+    (method !== "CustomerAccount" || customerId.trim() !== "") &&
+    (method !== "IdDocument" || reference.trim() !== "");
+
+  async function handleConfirm() {
+    await onConfirm({
+      owner: owner.trim(),
+      customer_id: customerId.trim() || null,
+      method,
+      reference: reference.trim() || null,
+    });
+  }
+
+  return (
+    <Dialog open={open} onOpenChange={(isOpen) => !isOpen && onCancel()}>
+      <DialogContent>
+        <DialogHeader>
+          <DialogTitle>Return Item to Owner</DialogTitle>
+        </DialogHeader>
+        <div className="space-y-4">
+          <div className="space-y-2">
+            <Label htmlFor="owner">Owner Name</Label>
+            <Input
+              id="owner"
+              value={owner}
+              onChange={(e) => setOwner(e.target.value)}
+            />
+          </div>
+          <div className="space-y-2">
+            <Label>Verification Method</Label>
+            <Select value={method} onValueChange={setMethod}>
+              <SelectTrigger>
+                <SelectValue placeholder="Select Method" />
+              </SelectTrigger>
+              <SelectContent>
+                <SelectItem value="CustomerAccount">Customer Account</SelectItem>
+                <SelectItem value="IdDocument">ID Document</SelectItem>
+                <SelectItem value="ItemDescription">Item Description</SelectItem>
+              </SelectContent>
+            </Select>
+          </div>
+          <div className="space-y-2">
+            <Label htmlFor="customer-id">
+              Customer ID {method !== "CustomerAccount" && "(optional)"}
+            </Label>
+            <Input
+              id="customer-id"
+              value={customerId}
+              onChange={(e) => setCustomerId(e.target.value)}
+            />
+          </div>
+          <div className="space-y-2">
+            <Label htmlFor="reference">
+              Verification Reference {method !== "IdDocument" && "(optional)"}
+            </Label>
+            <Input
+              id="reference"
+              value={reference}
+              onChange={(e) => setReference(e.target.value)}
+            />
+          </div>
+        </div>
+        <DialogFooter>
+          <Button variant="outline" onClick={onCancel}>
+            Cancel
+          </Button>
+          <Button onClick={handleConfirm} disabled={!isValid}>
+            Confirm Return
+          </Button>
+        </DialogFooter>
+      </DialogContent>
+    </Dialog>
+  );
+}
@@ -59,10 +59,10 @@ const createStaffSchema = baseFormSchema
   .extend({
     password: z
       .string()
-      .min(6, { message: "Password must be at least 6 characters." }),
+      .min(8, { message: "Password must be at least 8 characters." }),
     confirmPassword: z
       .string()
-      .min(6, { message: "Confirm password must be at least 6 characters." }),
+      .min(8, { message: "Confirm password must be at least 8 characters." }),
   })
   .refine((data) => data.password === data.confirmPassword, {
     message: "Passwords do not match",
@@ -6,7 +6,9 @@ import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
 import { invoke } from "@tauri-apps/api/core";
-import { ApiResponse } from "@/types";
+import { ApiResponse, StaffLoginResult } from "@/types";
+import { ChangePasswordForm } from "@/components/change-password-form";
+import { getDeviceId } from "@/lib/device";
 
 interface StaffLoginFormProps {
   onLogin: (sessionToken: string, email: string) => void; // onLogin now passes email, not UID
@@ -17,26 +19,40 @@ export function StaffLoginForm({ onLogin }: StaffLoginFormProps) {
   const [password, setPassword] = useState("");
   const [error, setError] = useState("");
   const [isLoading, setIsLoading] = useState(false);
+  const [notice, setNotice] = useState("");
+  // Set when the account must choose a new password before the session starts
+  const [pendingRotation, setPendingRotation] =
+    useState<StaffLoginResult | null>(null);
+  const [isResetting, setIsResetting] = useState(false);
+  const [resetToken, setResetToken] = useState("");
+  const [resetPassword, setResetPassword] = useState("");
 
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
     setError(""); // Clear previous errors
+    setNotice("");
     if (!email.trim() || !password.trim()) {
       setError("Please enter your email and password");
       return;
     }
     setIsLoading(true);
     try {
-      const response = await invoke<ApiResponse<string>>("staff_login", {
-        email: email, // Use email and password for staff_login
-        password: password,
-      });
+      const response = await invoke<ApiResponse<StaffLoginResult>>(
+        "staff_login",
+        {
+          email: email, // Use email and password for staff_login
+          password: password,
+          deviceId: getDeviceId(),
+        }
+      );
 
       if (response.status === "success") {
-        const sessionToken = response.data;
-        if (sessionToken) {
+        const loginResult = response.data;
+        if (loginResult?.must_change_password) {
+          setPendingRotation(loginResult);
+        } else if (loginResult) {
           console.log("Staff Login successful.");
-          onLogin(sessionToken, email); // Call onLogin with session token and email
+          onLogin(loginResult.session_token, email); // Call onLogin with session token and email
         } else {
           setError("Login successful but session token is missing.");
         }
@@ -57,6 +73,108 @@ export function StaffLoginForm({ onLogin }: StaffLoginFormProps) {
     }
   };
 
+  const handleReset = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setError("");
+    if (!email.trim() || !resetToken.trim() || !resetPassword.trim()) {
+      setError("Please enter your email, reset code and a new password");
+      return;
+    }
+    setIsLoading(true);
+    try {
+      const response = await invoke<ApiResponse<string>>(
+        "complete_staff_password_reset",
+        {
+          email,
+          token: resetToken.trim(),
+          newPassword: resetPassword,
+        }
+      );
+      if (response.status === "success") {
+        setIsResetting(false);
+        setResetToken("");
+        setResetPassword("");
+        setPassword("");
+        setNotice("Password reset. You can now log in with your new password.");
+      } else {
+        setError(response.message || "Password reset failed.");
+      }
+    } catch (error) {
+      console.error("Error invoking complete_staff_password_reset:", error);
+      setError("" + error);
+    } finally {
+      setIsLoading(false);
+    }
+  };
+
+  if (pendingRotation) {
+    return (
+      <div className="pt-4">
+        <p className="text-sm text-muted-foreground">
+          Your account was set up with a temporary password. Please choose a
+          new one to continue.
+        </p>
+        <ChangePasswordForm
+          staffId={pendingRotation.staff_id}
+          currentPassword={password}
+          onDone={() => onLogin(pendingRotation.session_token, email)}
+        />
+      </div>
+    );
+  }
+
+  if (isResetting) {
+    return (
+      <form onSubmit={handleReset} className="space-y-4 pt-4">
+        <div className="space-y-2">
+          <Label htmlFor="reset-email">Email</Label>
+          <Input
+            id="reset-email"
+            type="email"
+            value={email}
+            onChange={(e) => setEmail(e.target.value)}
+            disabled={isLoading}
+          />
+        </div>
+        <div className="space-y-2">
+          <Label htmlFor="reset-token">Reset Code</Label>
+          <Input
+            id="reset-token"
+            placeholder="Code from your executive"
+            value={resetToken}
+            onChange={(e) => setResetToken(e.target.value)}
+            disabled={isLoading}
+          />
+        </div>
+        <div className="space-y-2">
+          <Label htmlFor="reset-password">New Password</Label>
+          <Input
+            id="reset-password"
+            type="password"
+            value={resetPassword}
+            onChange={(e) => setResetPassword(e.target.value)}
+            disabled={isLoading}
+          />
+          {error && <p className="text-sm text-destructive">{error}</p>}
+        </div>
+        <Button type="submit" className="w-full" disabled={isLoading}>
+          {isLoading ? "Resetting..." : "Reset Password"}
+        </Button>
+        <Button
+          type="button"
+          variant="link"
+          className="w-full"
+          onClick={() => {
+            setIsResetting(false);
+            setError("");
+          }}
+        >
+          Back to login
+        </Button>
+      </form>
+    );
+  }
+
   return (
     <form onSubmit={handleSubmit} className="space-y-4 pt-4">
       <div className="space-y-2">
@@ -81,10 +199,23 @@ export function StaffLoginForm({ onLogin }: StaffLoginFormProps) {
           disabled={isLoading}
         />
         {error && <p className="text-sm text-destructive">{error}</p>}
+        {notice && <p className="text-sm text-muted-foreground">{notice}</p>}
       </div>
       <Button type="submit" className="w-full" disabled={isLoading}>
         {isLoading ? "Logging in..." : "Login"}
       </Button>
+      <Button
+        type="button"
+        variant="link"
+        className="w-full"
+        onClick={() => {
+          setIsResetting(true);
+          setError("");
+          setNotice("");
+        }}
+      >
+        Have a reset code?
+      </Button>
     </form>
   );
 }
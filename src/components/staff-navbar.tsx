@@ -11,18 +11,22 @@ import {
 } from "@/components/ui/dialog";
 import { Bell, MessageSquare, MessageSquareQuote } from "lucide-react";
 import { StaffLoginForm } from "@/components/staff-login-form";
+import { ChangePasswordForm } from "@/components/change-password-form";
 import { useStaffUser } from "@/context/staff-user-context";
 import { invoke } from "@tauri-apps/api/core";
 import { ApiResponse, Staff } from "@/types";
 import { useNavigate } from "react-router";
+import { useState } from "react";
 import { NotificationPopover } from "./notification-popover";
+import { SystemHealthPopover } from "./system-health-popover";
 
 interface StaffNavbarProps {}
 
 export function StaffNavbar({}: StaffNavbarProps) {
   const navigate = useNavigate();
 
-  const { isLoggedIn, login, logout, staffName, staffRole } = useStaffUser();
+  const { isLoggedIn, login, logout, staffName, staffRole, staffId, sessionToken } = useStaffUser();
+  const [isChangingPassword, setIsChangingPassword] = useState(false);
 
   const handleLogin = async (sessionToken: string, staffEmail: string) => {
     try {
@@ -95,7 +99,25 @@ export function StaffNavbar({}: StaffNavbarProps) {
               >
                 <MessageSquare className="h-5 w-5" />
               </Button>
-              <NotificationPopover audience="Staff" />
+              <NotificationPopover audience="Staff" recipientId={staffId} sessionToken={sessionToken} />
+              <SystemHealthPopover />
+              <Dialog
+                open={isChangingPassword}
+                onOpenChange={setIsChangingPassword}
+              >
+                <DialogTrigger asChild>
+                  <Button variant="outline">Change Password</Button>
+                </DialogTrigger>
+                <DialogContent>
+                  <DialogHeader>
+                    <DialogTitle>Change Password</DialogTitle>
+                  </DialogHeader>
+                  <ChangePasswordForm
+                    staffId={staffId ?? ""}
+                    onDone={() => setIsChangingPassword(false)}
+                  />
+                </DialogContent>
+              </Dialog>
               <Button variant="default" onClick={handleLogoutClick}>
                 Logout
               </Button>
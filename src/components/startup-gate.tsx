@@ -0,0 +1,72 @@
+"use client";
+
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { ApiResponse, HealthReport } from "@/types";
+import { ErrorScreen } from "./error-screen";
+import { LoadingScreen } from "./loading-screen";
+
+const RETRY_INTERVAL_MS = 5000;
+
+interface StartupGateProps {
+  children: React.ReactNode;
+}
+
+// Holds the UI back until the backend is usable: a configuration error is shown for good,
+// an unreachable database is retried until it comes back
+export function StartupGate({ children }: StartupGateProps) {
+  const [report, setReport] = useState<HealthReport | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  async function checkHealth() {
+    try {
+      const response = await invoke<ApiResponse<HealthReport>>("health_check");
+      if (response.status === "success" && response.data) {
+        setReport(response.data);
+        setError(null);
+      } else {
+        setError(response.message ?? "Unable to check VorteKia services");
+      }
+    } catch (err) {
+      setError(String(err));
+    }
+  }
+
+  useEffect(() => {
+    checkHealth();
+  }, []);
+
+  const ready =
+    report !== null && !report.startup_error && report.database.status !== "down";
+
+  // Keep retrying while the database is unreachable
+  useEffect(() => {
+    if (ready || report?.startup_error) return;
+    const timer = setInterval(checkHealth, RETRY_INTERVAL_MS);
+    return () => clearInterval(timer);
+  }, [ready, report?.startup_error]);
+
+  if (report?.startup_error) {
+    return (
+      <ErrorScreen
+        error={`VorteKia could not start: ${report.startup_error}. Fix the configuration and restart the app.`}
+      />
+    );
+  }
+
+  if (ready) {
+    return <>{children}</>;
+  }
+
+  if (error || report) {
+    const detail = error ?? report?.database.detail ?? "no response";
+    return (
+      <ErrorScreen
+        error={`Cannot reach the database (${detail}). Retrying automatically...`}
+        onTryAgain={checkHealth}
+      />
+    );
+  }
+
+  return <LoadingScreen message="Connecting to VorteKia services" />;
+}
@@ -0,0 +1,127 @@
+"use client";
+
+import { useEffect, useState } from "react";
+import {
+  Popover,
+  PopoverContent,
+  PopoverTrigger,
+} from "@/components/ui/popover";
+import { Button } from "@/components/ui/button";
+import { Badge } from "@/components/ui/badge";
+import { Separator } from "@/components/ui/separator";
+import { Activity, Loader2, RefreshCw } from "lucide-react";
+import { invoke } from "@tauri-apps/api/core";
+import { ApiResponse, ComponentHealth, HealthReport } from "@/types";
+
+const REFRESH_INTERVAL_MS = 30000;
+
+const STATUS_COLORS: Record<string, string> = {
+  up: "text-green-600",
+  degraded: "text-amber-500",
+  down: "text-destructive",
+};
+
+function statusVariant(status: string) {
+  if (status === "up") return "secondary" as const;
+  if (status === "degraded") return "outline" as const;
+  return "destructive" as const;
+}
+
+function ComponentRow({ label, health }: { label: string; health: ComponentHealth }) {
+  return (
+    <div className="space-y-1">
+      <div className="flex items-center justify-between">
+        <span className="font-medium">
+          {label} <span className="text-muted-foreground">({health.backend})</span>
+        </span>
+        <Badge variant={statusVariant(health.status)}>{health.status}</Badge>
+      </div>
+      {health.latency_ms !== null && (
+        <p className="text-xs text-muted-foreground">{health.latency_ms} ms</p>
+      )}
+      {health.detail && (
+        <p className="text-xs text-muted-foreground break-words">{health.detail}</p>
+      )}
+    </div>
+  );
+}
+
+// Database and cache status for staff, refreshed in the background
+export function SystemHealthPopover() {
+  const [report, setReport] = useState<HealthReport | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  async function fetchHealth() {
+    setLoading(true);
+    try {
+      const response = await invoke<ApiResponse<HealthReport>>("health_check");
+      if (response.status === "success" && response.data) {
+        setReport(response.data);
+        setError(null);
+      } else {
+        setError(response.message ?? "Unable to check system health");
+      }
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setLoading(false);
+    }
+  }
+
+  useEffect(() => {
+    fetchHealth();
+    const timer = setInterval(fetchHealth, REFRESH_INTERVAL_MS);
+    return () => clearInterval(timer);
+  }, []);
+
+  const status = error ? "down" : report?.status ?? "up";
+
+  return (
+    <Popover>
+      <PopoverTrigger asChild>
+        <Button
+          variant="ghost"
+          size="icon"
+          className="rounded-full"
+          aria-label="System Health"
+        >
+          <Activity className={`h-5 w-5 ${STATUS_COLORS[status] ?? ""}`} />
+        </Button>
+      </PopoverTrigger>
+      <PopoverContent className="w-80" align="end">
+        <div className="flex items-center justify-between mb-2">
+          <h4 className="font-semibold">System Health</h4>
+          <Button
+            variant="ghost"
+            size="icon"
+            className="h-7 w-7"
+            aria-label="Refresh"
+            onClick={fetchHealth}
+            disabled={loading}
+          >
+            {loading ? (
+              <Loader2 className="h-4 w-4 animate-spin" />
+            ) : (
+              <RefreshCw className="h-4 w-4" />
+            )}
+          </Button>
+        </div>
+        <Separator className="mb-3" />
+        {error ? (
+          <p className="text-sm text-destructive">{error}</p>
+        ) : report ? (
+          <div className="space-y-3 text-sm">
+            <ComponentRow label="Database" health={report.database} />
+            <ComponentRow label="Cache" health={report.cache} />
+            <p className="text-xs text-muted-foreground">
+              Checked at {new Date(report.checked_at).toLocaleTimeString()}
+            </p>
+          </div>
+        ) : (
+          <p className="text-sm text-muted-foreground">Checking...</p>
+        )}
+      </PopoverContent>
+    </Popover>
+  );
+}
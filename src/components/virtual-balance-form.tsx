@@ -1,6 +1,6 @@
 "use client";
 
-import { useState } from "react";
+import { useRef, useState } from "react";
 import { zodResolver } from "@hookform/resolvers/zod";
 import { useForm } from "react-hook-form";
 import { z } from "zod";
@@ -50,7 +50,9 @@ interface VirtualBalanceFormProps {
 
 export function VirtualBalanceForm({ onCancel }: VirtualBalanceFormProps) {
   const [isSubmitting, setIsSubmitting] = useState(false);
-  const { uid, setVirtualBalance, virtualBalance } = useUser();
+  const { uid, setVirtualBalance, virtualBalance, sessionToken } = useUser();
+  // One key per top-up: a retry or double submit is only charged once; renewed after success
+  const idempotencyKey = useRef(crypto.randomUUID());
 
   // Initialize form with default values
   const form = useForm<z.infer<typeof formSchema>>({
@@ -75,10 +77,16 @@ export function VirtualBalanceForm({ onCancel }: VirtualBalanceFormProps) {
 
       const response = await invoke<ApiResponse<string>>(
         "top_up_virtual_balance",
-        { customerId: uid, topUpAmountStr: topUpAmountStr } // Send amount as string
+        {
+          sessionToken,
+          customerId: uid,
+          topUpAmountStr: topUpAmountStr, // Send amount as string
+          idempotencyKey: idempotencyKey.current,
+        }
       );
 
       if (response.status === "success") {
+        idempotencyKey.current = crypto.randomUUID();
         // Top-up successful
         const newBalanceStr = (
           Number(virtualBalance) + Number(topUpAmountStr)
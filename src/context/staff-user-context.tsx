@@ -107,6 +107,11 @@ export const StaffUserProvider: React.FC<StaffUserProviderProps> = ({
   };
 
   const logout = () => {
+    if (sessionToken) {
+      invoke("logout", { sessionToken }).catch((error) =>
+        console.error("Error closing staff session:", error)
+      );
+    }
     setSessionToken(null);
     setStaffId(null);
     setStaffName(null);
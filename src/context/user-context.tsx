@@ -17,6 +17,7 @@ interface UserContextType {
   customerName: string | null;
   virtualBalance: string | null;
   setVirtualBalance: React.Dispatch<React.SetStateAction<string | null>>;
+  refreshBalance: () => Promise<string | null>;
   login: (token: string, userId: string, name: string, balance: string) => void;
   logout: () => void;
   isLoggedIn: () => boolean;
@@ -103,7 +104,32 @@ export const UserProvider: React.FC<UserProviderProps> = ({ children }) => {
     resetInactivityTimer();
   };
 
+  // The backend charges orders and queue joins itself; read the balance it left
+  const refreshBalance = async () => {
+    if (!uid) return null;
+    try {
+      const response = await invoke<ApiResponse<Customer>>(
+        "get_customer_details",
+        { customerId: uid }
+      );
+      if (response.status === "success" && response.data) {
+        setVirtualBalance(response.data.virtual_balance);
+        return response.data.virtual_balance;
+      }
+      console.error("Error refreshing balance:", response.message);
+    } catch (error) {
+      console.error("Error invoking get_customer_details:", error);
+    }
+    return null;
+  };
+
   const logoutUser = () => {
+    const storedSessionToken = localStorage.getItem("sessionToken");
+    if (storedSessionToken) {
+      invoke("logout", { sessionToken: storedSessionToken }).catch((error) =>
+        console.error("Error closing session:", error)
+      );
+    }
     setSessionToken(null);
     setUid(null);
     setCustomerName(null);
@@ -156,6 +182,7 @@ export const UserProvider: React.FC<UserProviderProps> = ({ children }) => {
     customerName,
     virtualBalance,
     setVirtualBalance,
+    refreshBalance,
     login,
     logout,
     isLoggedIn,
@@ -0,0 +1,9 @@
+// Stable id for this install, sent with logins so failed attempts can be throttled per device
+export function getDeviceId(): string {
+  let deviceId = localStorage.getItem("deviceId");
+  if (!deviceId) {
+    deviceId = crypto.randomUUID();
+    localStorage.setItem("deviceId", deviceId);
+  }
+  return deviceId;
+}
@@ -3,6 +3,7 @@ import { BrowserRouter, Route, Routes, useNavigate } from "react-router";
 import "./App.css";
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { ApiResponse } from "./types";
 import RestaurantUI from "./pages/restaurant_ui";
 import RideUI from "./pages/ride_ui";
 import StoreUI from "./pages/store_ui";
@@ -11,6 +12,7 @@ import CustomerUI from "./pages/customer_ui";
 import RestaurantUIStaff from "./pages/restaurant_ui_staff";
 import RideUIStaff from "./pages/ride_ui_staff";
 import { LoadingScreen } from "./components/loading-screen";
+import { StartupGate } from "./components/startup-gate";
 import GroupChatPage from "./pages/group-chat-page";
 import CustomerServiceChatPage from "./pages/customer-service-chat-page";
 import OfficialAccountChatPage from "./pages/official-account-chat-page";
@@ -22,11 +24,15 @@ function MainPage() {
   useEffect(() => {
     const fetchUiName = async () => {
       try {
-        const uiName = await invoke("get_ui_name_from_config");
-        console.log("UI ID received from backend:", uiName);
+        const response = await invoke<ApiResponse<string>>("get_ui_name_from_config");
+        if (response.status === "error") {
+          console.error("Error loading UI config:", response.message);
+          return;
+        }
+        console.log("UI ID received from backend:", response.data);
 
         // Navigate to the UI based on the backend response
-        navigate(`/${uiName}`);
+        navigate(`/${response.data}`);
       } catch (error) {
         console.error("Error invoking backend:", error);
       } finally {
@@ -46,27 +52,29 @@ function MainPage() {
 
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
   <BrowserRouter>
-    <Routes>
-      <Route path="/" element={<MainPage />} />
-      <Route path="/restaurant/:restaurantId" element={<RestaurantUI />} />
-      <Route
-        path="/restaurant/:restaurantId/staff"
-        element={<RestaurantUIStaff />}
-      />
-      <Route path="/ride/:rideId" element={<RideUI />} />
-      <Route path="/ride/:rideId/staff" element={<RideUIStaff />} />
-      <Route path="/store/:storeId" element={<StoreUI />} />
-      <Route path="/staff" element={<StaffUI />} />
-      <Route path="/customer" element={<CustomerUI />} />
-      <Route path="/chat" element={<GroupChatPage />} />
-      <Route
-        path="/chat/customer-service"
-        element={<CustomerServiceChatPage />}
-      />
-      <Route
-        path="/chat/official-account"
-        element={<OfficialAccountChatPage />}
-      />
-    </Routes>
+    <StartupGate>
+      <Routes>
+        <Route path="/" element={<MainPage />} />
+        <Route path="/restaurant/:restaurantId" element={<RestaurantUI />} />
+        <Route
+          path="/restaurant/:restaurantId/staff"
+          element={<RestaurantUIStaff />}
+        />
+        <Route path="/ride/:rideId" element={<RideUI />} />
+        <Route path="/ride/:rideId/staff" element={<RideUIStaff />} />
+        <Route path="/store/:storeId" element={<StoreUI />} />
+        <Route path="/staff" element={<StaffUI />} />
+        <Route path="/customer" element={<CustomerUI />} />
+        <Route path="/chat" element={<GroupChatPage />} />
+        <Route
+          path="/chat/customer-service"
+          element={<CustomerServiceChatPage />}
+        />
+        <Route
+          path="/chat/official-account"
+          element={<OfficialAccountChatPage />}
+        />
+      </Routes>
+    </StartupGate>
   </BrowserRouter>
 );
@@ -19,7 +19,7 @@ import { toast } from "sonner";
 import { useNavigate } from "react-router";
 
 function CustomerServiceChatPageUI() {
-  const { isLoggedIn, uid, customerName } = useUser(); // Use customer context
+  const { isLoggedIn, sessionToken, uid, customerName } = useUser(); // Use customer context
   const [loading, setLoading] = useState(true);
   const [chat, setChat] = useState<Chat | null>(null); // State for the Customer Service Chat
   const [messages, setMessages] = useState<MessageWithSenderName[]>([]);
@@ -106,7 +106,7 @@ function CustomerServiceChatPageUI() {
         "save_message_data", // Reuse existing save_message_data command
         {
           chatId: chat.chat_id, // Use chat.chat_id (Customer Service Chat ID)
-          senderId: uid, // Use customer UID as senderId
+          sessionToken,
           text: newMessage,
         }
       );
@@ -19,6 +19,7 @@ import { invoke } from "@tauri-apps/api/core";
 import { ApiResponse, Restaurant, Ride, RideQueue } from "@/types";
 import { Toaster } from "@/components/ui/sonner";
 import { Navbar } from "@/components/navbar";
+import { LostItemReports } from "@/components/lost-item-reports";
 
 function CustomerUIComponent() {
   const [searchQuery, setSearchQuery] = useState("");
@@ -205,6 +206,9 @@ function CustomerUIComponent() {
           </div>
         </div>
       </section>
+
+      {/* Lost Item Reports Section */}
+      <LostItemReports />
     </div>
   );
 }
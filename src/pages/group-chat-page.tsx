@@ -29,7 +29,7 @@ import { formatChatName } from "@/util/chatNameFormatter";
 
 function GroupChatPageUI() {
   const navigate = useNavigate();
-  const { isLoggedIn, staffId, staffRole } = useStaffUser();
+  const { isLoggedIn, sessionToken, staffId, staffRole } = useStaffUser();
   const [loading, setLoading] = useState(true);
   const [chats, setChats] = useState<Chat[]>([]);
   const [selectedChat, setSelectedChat] = useState<Chat | null>(null);
@@ -53,7 +53,7 @@ function GroupChatPageUI() {
     try {
       setLoading(true);
       const response = await invoke<ApiResponse<Chat[]>>("view_chats", {
-        userId: staffId,
+        sessionToken,
       });
       if (response.status === "success") {
         // Modify chat names for MaintenanceManager
@@ -77,7 +77,7 @@ function GroupChatPageUI() {
     } finally {
       setLoading(false);
     }
-  }, [staffId]);
+  }, [staffId, sessionToken]);
 
   const fetchMessages = useCallback(
     async (isInitialLoad: boolean = false) => {
@@ -170,7 +170,7 @@ function GroupChatPageUI() {
         "save_message_data",
         {
           chatId: selectedChat.chat_id, // Pass selectedChat.chat_id
-          senderId: staffId, // Pass staffId as sender_id
+          sessionToken,
           text: newMessage, // Pass newMessage as text
         }
       );
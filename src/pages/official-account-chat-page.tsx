@@ -28,7 +28,7 @@ import { formatLastMessage } from "@/util/lastMessageFormatter";
 
 function OfficialAccountChatPageUI() {
   const navigate = useNavigate();
-  const { isLoggedIn, staffId, staffName, staffRole } = useStaffUser(); // Use staff context
+  const { isLoggedIn, sessionToken, staffId, staffName, staffRole } = useStaffUser(); // Use staff context
   const [loading, setLoading] = useState(true);
   const [customerServiceChats, setCustomerServiceChats] = useState<
     ChatWithCustomerName[]
@@ -118,7 +118,7 @@ function OfficialAccountChatPageUI() {
         "save_message_data", // Reuse existing save_message_data command
         {
           chatId: selectedChat.chat.chat_id,
-          senderId: staffId, // Use staffId as senderId
+          sessionToken,
           text: newMessage,
         }
       );
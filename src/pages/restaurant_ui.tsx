@@ -1,6 +1,6 @@
 "use client";
 
-import { useEffect, useState } from "react";
+import { useEffect, useRef, useState } from "react";
 import { useParams } from "react-router";
 import { Button } from "@/components/ui/button";
 import {
@@ -50,7 +50,7 @@ import { NotFoundScreen } from "@/components/not-found-screen";
 
 function RestaurantUIComponent() {
   const { restaurantId } = useParams<{ restaurantId: string }>();
-  const { isLoggedIn, uid, setVirtualBalance, virtualBalance } = useUser(); // Get setVirtualBalance
+  const { isLoggedIn, uid, refreshBalance, virtualBalance, sessionToken } = useUser();
   const [restaurant, setRestaurant] = useState<Restaurant | null>(null);
   const [menuItems, setMenuItems] = useState<MenuItem[]>([]);
   const [loading, setLoading] = useState(true);
@@ -60,6 +60,8 @@ function RestaurantUIComponent() {
     null
   );
   const [orderQuantity, setOrderQuantity] = useState(1);
+  // Sent with the order so a retry or double click does not order twice
+  const orderKey = useRef<string | null>(null);
   const [orderLoading, setOrderLoading] = useState(false);
   const [customerOrders, setCustomerOrders] = useState<OrderRestaurant[]>([]);
 
@@ -142,6 +144,7 @@ function RestaurantUIComponent() {
 
     setSelectedMenuItem(menuItem);
     setOrderQuantity(1);
+    orderKey.current = crypto.randomUUID(); // New order, new key
   };
 
   const confirmOrder = async () => {
@@ -160,27 +163,28 @@ function RestaurantUIComponent() {
       const response = await invoke<ApiResponse<string>>(
         "save_order_restaurant_data",
         {
+          sessionToken,
           customerId: uid,
           restaurantId: selectedMenuItem.restaurant_id,
           menuItemId: selectedMenuItem.menu_item_id,
           quantity: orderQuantity,
+          idempotencyKey: orderKey.current,
         }
       );
 
-      if (response.status === "success") {
-        const newBalance = (currentBalance - orderPrice).toString();
-        // Update virtual balance *after* successful order
-        const balanceResponse = await invoke<string>("update_customer_data", {
-          customerId: uid,
-          name: null, // Assuming you don't update name
-          virtualBalance: newBalance,
-        });
-
-        setVirtualBalance(newBalance); // Update local balance
+      if (response.status === "queued") {
+        // Offline: the kiosk charges the balance when it syncs
+        toast.info(response.message || "Order saved offline and will be sent once the connection is back.");
+        setSelectedMenuItem(null);
+      } else if (response.status === "success") {
+        // The order and the charge were both written by the backend
+        const newBalance = await refreshBalance();
         toast.success(
-          `Order placed successfully! New balance: ${formatRupiah(
-            parseFloat(newBalance)
-          )}`
+          newBalance
+            ? `Order placed successfully! New balance: ${formatRupiah(
+                parseFloat(newBalance)
+              )}`
+            : "Order placed successfully!"
         );
         setSelectedMenuItem(null);
         fetchCustomerOrders(); // Refresh orders
@@ -44,7 +44,7 @@ import { AccessRequiredScreen } from "@/components/access-required-screen";
 
 function RestaurantUIStaffComponent() {
   const { restaurantId } = useParams<{ restaurantId: string }>();
-  const { isLoggedIn, staffRole } = useStaffUser();
+  const { isLoggedIn, staffId, staffRole, sessionToken } = useStaffUser();
   const [restaurant, setRestaurant] = useState<Restaurant | null>(null);
   const [orders, setOrders] = useState<OrderRestaurant[]>([]);
   const [menuItems, setMenuItems] = useState<MenuItem[]>([]); // Store menu items
@@ -222,6 +222,7 @@ function RestaurantUIStaffComponent() {
       const response = await invoke<ApiResponse<string>>(
         "update_order_restaurant_status",
         {
+          sessionToken,
           orderRestaurantId: orderId,
           status: newStatus,
         }
@@ -1,6 +1,6 @@
 "use client";
 
-import { useEffect, useState } from "react";
+import { useEffect, useRef, useState } from "react";
 import { useParams } from "react-router";
 import { Button } from "@/components/ui/button";
 import { Card, CardContent } from "@/components/ui/card";
@@ -22,7 +22,7 @@ import { toast } from "sonner";
 
 function RideUIComponent() {
   const { rideId } = useParams<{ rideId: string }>();
-  const { isLoggedIn, uid, virtualBalance, setVirtualBalance } = useUser();
+  const { isLoggedIn, uid, virtualBalance, refreshBalance, sessionToken } = useUser();
   const [ride, setRide] = useState<Ride | null>(null);
   const [queueCount, setQueueCount] = useState<number>(0);
   const [loading, setLoading] = useState(true);
@@ -70,6 +70,9 @@ function RideUIComponent() {
     fetchRideData();
   }, [rideId]);
 
+  // One key per queue join: a double click or retry joins once; renewed once the join went through
+  const queueKey = useRef(crypto.randomUUID());
+
   const handleQueueForRide = async () => {
     if (!ride || !uid || !virtualBalance) return;
 
@@ -92,29 +95,34 @@ function RideUIComponent() {
       const queueResponse = await invoke<ApiResponse<string>>(
         "save_ride_queue_data",
         {
+          sessionToken,
           rideId: ride.ride_id,
           customerId: uid,
           queuePosition: lastPosition,
+          idempotencyKey: queueKey.current,
         }
       );
 
+      if (queueResponse.status === "queued") {
+        // Offline: the kiosk joins the queue and charges the balance when it syncs
+        queueKey.current = crypto.randomUUID();
+        toast.info(queueResponse.message || "Queue request saved offline and will be sent once the connection is back.");
+        return;
+      }
       if (queueResponse.status !== "success" || !queueResponse.data) {
         throw new Error(queueResponse.message || "Failed to queue for ride");
       }
+      queueKey.current = crypto.randomUUID();
 
-      // Update virtual balance
-      const newBalance = (currentBalance - ridePrice).toString();
-      const balanceResponse = await invoke<string>("update_customer_data", {
-        customerId: uid,
-        name: null,
-        virtualBalance: newBalance,
-      });
-      setVirtualBalance(newBalance);
+      // The backend charged the ride when it queued the customer
+      const newBalance = await refreshBalance();
       setQueueCount((prev) => prev + 1);
       toast.success(
-        `Successfully queued for ${ride.name}! New balance: ${formatRupiah(
-          parseFloat(newBalance)
-        )}`
+        newBalance
+          ? `Successfully queued for ${ride.name}! New balance: ${formatRupiah(
+              parseFloat(newBalance)
+            )}`
+          : `Successfully queued for ${ride.name}!`
       );
     } catch (err) {
       console.error("Error queuing for ride:", err);
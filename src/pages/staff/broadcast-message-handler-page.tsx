@@ -77,7 +77,7 @@ const formSchema = z.object({
 
 function BroadcastMessageHandlerPageUI() {
   const navigate = useNavigate();
-  const { isLoggedIn, staffRole } = useStaffUser();
+  const { isLoggedIn, staffId, staffRole, sessionToken } = useStaffUser();
   const [loading, setLoading] = useState(true);
   const [sendingMessageId, setSendingMessageId] = useState<string | null>(null); // Track which message is sending
   const [messages, setMessages] = useState<BroadcastMessage[]>([]); // Use BroadcastMessage type
@@ -137,6 +137,7 @@ function BroadcastMessageHandlerPageUI() {
       const response = await invoke<ApiResponse<string>>(
         "save_broadcast_message_data", // Call backend function
         {
+          sessionToken,
           content: values.content,
           targetAudience: values.target_audience,
           status: "Pending", // New messages start as Pending
@@ -171,6 +172,7 @@ function BroadcastMessageHandlerPageUI() {
       const response = await invoke<ApiResponse<string>>(
         "update_broadcast_message_data", // Call backend update function
         {
+          sessionToken,
           broadcastMessageId: messageId,
           status: "Sent", // Update status to Sent
           // Pass other fields as None/null if you don't want to update them
@@ -1,6 +1,6 @@
 import { useEffect, useState } from "react";
 import { Button } from "@/components/ui/button";
-import { ApiResponse, Customer } from "@/types";
+import { ApiResponse, Customer, DependencyReport } from "@/types";
 import {
   Table,
   TableBody,
@@ -22,14 +22,20 @@ import {
   AlertDialogTrigger,
 } from "@/components/ui/alert-dialog";
 import { invoke } from "@tauri-apps/api/core";
+import { DeleteBlockedDialog } from "@/components/delete-blocked-dialog";
 import { CustomerForm } from "@/components/customer-form";
 import { Edit, Trash2 } from "lucide-react";
 import { useStaffUser } from "@/context/staff-user-context";
 
 function CustomerHandlerPage() {
   const [customers, setCustomers] = useState<Customer[]>([]);
+  const [blockedDelete, setBlockedDelete] = useState<{
+    id: string;
+    report: DependencyReport;
+    message: string;
+  } | null>(null);
   const [editingCustomer, setEditingCustomer] = useState<Customer | null>(null);
-  const { staffRole } = useStaffUser();
+  const { staffId, staffRole, sessionToken } = useStaffUser();
 
   // Fetch all customers from the backend
   async function fetchCustomers() {
@@ -48,11 +54,17 @@ function CustomerHandlerPage() {
   }, []);
 
   // Create a new customer
-  async function createCustomer(name: string, virtual_balance: string) {
+  async function createCustomer(
+    name: string,
+    virtual_balance: string,
+    pin: string
+  ) {
     try {
       const response = await invoke<ApiResponse<string>>("save_customer_data", {
+        sessionToken,
         name,
         virtualBalance: virtual_balance,
+        pin,
       });
 
       if (response.status === "error") {
@@ -72,15 +84,18 @@ function CustomerHandlerPage() {
   async function updateCustomer(
     customer_id: string,
     name: string,
-    virtual_balance: string
+    virtual_balance: string,
+    pin: string | null
   ) {
     try {
       const response = await invoke<ApiResponse<string>>(
         "update_customer_data",
         {
+          sessionToken,
           customerId: customer_id,
           name,
           virtualBalance: virtual_balance,
+          pin,
         }
       );
 
@@ -102,18 +117,30 @@ function CustomerHandlerPage() {
   }
 
   // Delete a customer
-  async function deleteCustomer(customer_id: string) {
+  async function deleteCustomer(customer_id: string, cascade = false) {
     try {
-      const response = await invoke<ApiResponse<string>>(
+      const response = await invoke<ApiResponse<DependencyReport>>(
         "delete_customer_data",
         {
+          sessionToken,
+          cascade,
           customerId: customer_id,
         }
       );
 
       if (response.status === "error") {
-        console.error("Error deleting customer:", response.message);
+        if (response.code === "CONFLICT" && response.data) {
+          // Dependents still point at it: let staff review them and cascade
+          setBlockedDelete({
+            id: customer_id,
+            report: response.data,
+            message: response.message ?? "",
+          });
+        } else {
+          console.error("Error deleting customer:", response.message);
+        }
       } else {
+        setBlockedDelete(null);
         setCustomers((prevCustomers) =>
           prevCustomers.filter(
             (customer) => customer.customer_id !== customer_id
@@ -231,6 +258,16 @@ function CustomerHandlerPage() {
           </div>
         </div>
       </div>
+      <DeleteBlockedDialog
+        report={blockedDelete?.report ?? null}
+        message={blockedDelete?.message ?? ""}
+        onCancel={() => setBlockedDelete(null)}
+        onCascade={async () => {
+          if (blockedDelete) {
+            await deleteCustomer(blockedDelete.id, true);
+          }
+        }}
+      />
     </div>
   );
 }
@@ -2,7 +2,13 @@
 
 import { useEffect, useState } from "react";
 import { Button } from "@/components/ui/button";
-import { ApiResponse, LostAndFoundItemsLog } from "@/types";
+import {
+  ApiResponse,
+  LostAndFoundItemsLog,
+  LostAndFoundLogEvent,
+  LostAndFoundMatch,
+  OwnerVerification,
+} from "@/types";
 import {
   Table,
   TableBody,
@@ -14,7 +20,15 @@ import {
 } from "@/components/ui/table";
 import { invoke } from "@tauri-apps/api/core";
 import { LostAndFoundItemsLogForm } from "@/components/lost-and-found-items-log-form";
-import { Edit } from "lucide-react";
+import { Edit, History, Link2, PackageCheck } from "lucide-react";
+import { ReturnToOwnerDialog } from "@/components/return-to-owner-dialog";
+import {
+  Dialog,
+  DialogContent,
+  DialogHeader,
+  DialogTitle,
+} from "@/components/ui/dialog";
+import { useStaffUser } from "@/context/staff-user-context";
 
 function LostAndFoundItemsLogHandlerPage() {
   const [logs, setLogs] = useState<LostAndFoundItemsLog[]>([]);
@@ -22,11 +36,106 @@ function LostAndFoundItemsLogHandlerPage() {
     null
   );
 
+  const [matches, setMatches] = useState<LostAndFoundMatch[]>([]);
+  const [flaggedLogs, setFlaggedLogs] = useState<LostAndFoundItemsLog[]>([]);
+  // Item (or confirmed match) waiting for owner verification
+  const [returning, setReturning] = useState<{
+    log?: LostAndFoundItemsLog;
+    match?: LostAndFoundMatch;
+  } | null>(null);
+  const [history, setHistory] = useState<LostAndFoundLogEvent[] | null>(null);
+  const { sessionToken } = useStaffUser();
+
   // Fetch logs on mount
   useEffect(() => {
     fetchLogs();
   }, []);
 
+  // Re-score candidate pairs whenever the logs change
+  useEffect(() => {
+    fetchMatches();
+    fetchFlaggedLogs();
+  }, [logs]);
+
+  async function fetchFlaggedLogs() {
+    try {
+      const response = await invoke<ApiResponse<LostAndFoundItemsLog[]>>(
+        "view_retention_flagged_logs"
+      );
+      setFlaggedLogs(response.data || []);
+    } catch (error) {
+      console.error("Failed to fetch flagged logs:", error);
+    }
+  }
+
+  async function fetchHistory(log_id: string) {
+    try {
+      const response = await invoke<ApiResponse<LostAndFoundLogEvent[]>>(
+        "get_log_history",
+        { logId: log_id }
+      );
+      setHistory(response.data || []);
+    } catch (error) {
+      console.error("Failed to fetch log history:", error);
+    }
+  }
+
+  async function returnToOwner(verification: OwnerVerification) {
+    if (!returning || !sessionToken) return;
+    try {
+      const response = returning.match
+        ? await invoke<ApiResponse<string>>("confirm_lost_and_found_match", {
+            missingLogId: returning.match.missing.log_id,
+            foundLogId: returning.match.found.log_id,
+            verification,
+            sessionToken,
+          })
+        : await invoke<ApiResponse<string>>("return_log_to_owner", {
+            logId: returning.log!.log_id,
+            verification,
+            sessionToken,
+          });
+      if (response.status === "error") {
+        console.error("Error returning item:", response.message);
+      } else {
+        setReturning(null);
+        fetchLogs(); // Records are now "Returned to Owner"
+      }
+    } catch (error) {
+      console.error("Failed to return item:", error);
+    }
+  }
+
+  async function disposeLog(log_id: string, disposition: string) {
+    try {
+      const response = await invoke<ApiResponse<string>>("dispose_log", {
+        logId: log_id,
+        disposition,
+        sessionToken,
+      });
+      if (response.status === "error") {
+        console.error("Error disposing item:", response.message);
+      } else {
+        fetchLogs();
+      }
+    } catch (error) {
+      console.error("Failed to dispose item:", error);
+    }
+  }
+
+  async function fetchMatches() {
+    try {
+      const response = await invoke<ApiResponse<LostAndFoundMatch[]>>(
+        "suggest_lost_and_found_matches",
+        {}
+      );
+      setMatches(response.data || []);
+    } catch (error) {
+      console.error("Failed to fetch match suggestions:", error);
+    }
+  }
+
+
   async function fetchLogs() {
     try {
       const response = await invoke<ApiResponse<LostAndFoundItemsLog[]>>(
@@ -60,6 +169,7 @@ function LostAndFoundItemsLogHandlerPage() {
         owner,
         foundLocation: found_location,
         image,
+        sessionToken,
       };
       const response = await invoke<ApiResponse<string>>(
         "save_log_data",
@@ -99,6 +209,7 @@ function LostAndFoundItemsLogHandlerPage() {
         owner,
         foundLocation: found_location,
         image: image,
+        sessionToken,
       };
       const response = await invoke<ApiResponse<string>>(
         "update_log_data",
@@ -208,14 +319,36 @@ function LostAndFoundItemsLogHandlerPage() {
                         )}
                       </TableCell>
                       <TableCell className="text-right">
+                        {(log.status === "Missing" || log.status === "Found") && (
+                          <>
+                            <Button
+                              variant="ghost"
+                              size="icon"
+                              onClick={() => setEditingLog(log)}
+                              className="h-8 w-8"
+                            >
+                              <Edit className="h-4 w-4" />
+                              <span className="sr-only">Edit</span>
+                            </Button>
+                            <Button
+                              variant="ghost"
+                              size="icon"
+                              onClick={() => setReturning({ log })}
+                              className="h-8 w-8"
+                            >
+                              <PackageCheck className="h-4 w-4" />
+                              <span className="sr-only">Return to Owner</span>
+                            </Button>
+                          </>
+                        )}
                         <Button
                           variant="ghost"
                           size="icon"
-                          onClick={() => setEditingLog(log)}
+                          onClick={() => fetchHistory(log.log_id)}
                           className="h-8 w-8"
                         >
-                          <Edit className="h-4 w-4" />
-                          <span className="sr-only">Edit</span>
+                          <History className="h-4 w-4" />
+                          <span className="sr-only">History</span>
                         </Button>
                       </TableCell>
                     </TableRow>
@@ -225,7 +358,135 @@ function LostAndFoundItemsLogHandlerPage() {
             </div>
           </div>
         </div>
+
+        {/* Suggested Matches Section */}
+        <div className="mt-8 bg-background/95 backdrop-blur-sm rounded-lg shadow-lg overflow-hidden p-6">
+          <div className="overflow-x-auto">
+            <Table>
+              <TableCaption>Suggested Missing / Found Matches</TableCaption>
+              <TableHeader>
+                <TableRow>
+                  <TableHead>Missing Report</TableHead>
+                  <TableHead>Last Seen Location</TableHead>
+                  <TableHead>Found Item</TableHead>
+                  <TableHead>Found Location</TableHead>
+                  <TableHead>Score</TableHead>
+                  <TableHead className="text-right">Actions</TableHead>
+                </TableRow>
+              </TableHeader>
+              <TableBody>
+                {matches.map((match) => (
+                  <TableRow
+                    key={`${match.missing.log_id}-${match.found.log_id}`}
+                  >
+                    <TableCell>
+                      {match.missing.name} ({match.missing.type},{" "}
+                      {match.missing.color})
+                    </TableCell>
+                    <TableCell>
+                      {match.missing.last_seen_location || "N/A"}
+                    </TableCell>
+                    <TableCell>
+                      {match.found.name} ({match.found.type}, {match.found.color})
+                    </TableCell>
+                    <TableCell>{match.found.found_location || "N/A"}</TableCell>
+                    <TableCell>{Math.round(match.score.total * 100)}%</TableCell>
+                    <TableCell className="text-right">
+                      <Button
+                        variant="ghost"
+                        size="sm"
+                        onClick={() => setReturning({ match })}
+                      >
+                        <Link2 className="h-4 w-4 mr-1" />
+                        Confirm
+                      </Button>
+                    </TableCell>
+                  </TableRow>
+                ))}
+              </TableBody>
+            </Table>
+          </div>
+        </div>
+
+        {/* Retention Section: Found items unclaimed past the retention period */}
+        <div className="mt-8 bg-background/95 backdrop-blur-sm rounded-lg shadow-lg overflow-hidden p-6">
+          <div className="overflow-x-auto">
+            <Table>
+              <TableCaption>Unclaimed Items Awaiting Donation or Disposal</TableCaption>
+              <TableHeader>
+                <TableRow>
+                  <TableHead>Name</TableHead>
+                  <TableHead>Type</TableHead>
+                  <TableHead>Found Location</TableHead>
+                  <TableHead>Found At</TableHead>
+                  <TableHead className="text-right">Actions</TableHead>
+                </TableRow>
+              </TableHeader>
+              <TableBody>
+                {flaggedLogs.map((log) => (
+                  <TableRow key={log.log_id}>
+                    <TableCell>{log.name}</TableCell>
+                    <TableCell>{log.type}</TableCell>
+                    <TableCell>{log.found_location || "N/A"}</TableCell>
+                    <TableCell>
+                      {log.found_at
+                        ? new Date(log.found_at).toLocaleString()
+                        : "N/A"}
+                    </TableCell>
+                    <TableCell className="text-right">
+                      <Button
+                        variant="ghost"
+                        size="sm"
+                        onClick={() => disposeLog(log.log_id, "Donated")}
+                      >
+                        Donate
+                      </Button>
+                      <Button
+                        variant="ghost"
+                        size="sm"
+                        onClick={() => disposeLog(log.log_id, "Disposed")}
+                      >
+                        Dispose
+                      </Button>
+                    </TableCell>
+                  </TableRow>
+                ))}
+              </TableBody>
+            </Table>
+          </div>
+        </div>
       </div>
+
+      <ReturnToOwnerDialog
+        open={returning !== null}
+        defaultOwner={returning?.match?.missing.owner ?? returning?.log?.owner}
+        onCancel={() => setReturning(null)}
+        onConfirm={returnToOwner}
+      />
+
+      <Dialog
+        open={history !== null}
+        onOpenChange={(isOpen) => !isOpen && setHistory(null)}
+      >
+        <DialogContent>
+          <DialogHeader>
+            <DialogTitle>Item History</DialogTitle>
+          </DialogHeader>
+          <ul className="space-y-2 text-sm">
+            {history?.map((event) => (
+              <li key={event.event_id}>
+                <span className="font-medium">{event.action}</span>
+                {event.to_status && ` → ${event.to_status}`}
+                {event.details && ` (${event.details})`}
+                <div className="text-xs text-muted-foreground">
+                  {new Date(event.created_at).toLocaleString()}
+                  {event.actor_id && ` by ${event.actor_id}`}
+                </div>
+              </li>
+            ))}
+          </ul>
+        </DialogContent>
+      </Dialog>
     </div>
   );
 }
@@ -38,7 +38,7 @@ function MaintenanceScheduleHandlerPage() {
     useState<MaintenanceSchedule | null>(null);
   const [rides, setRides] = useState<Ride[]>([]); // State for rides
   const [maintenanceStaffList, setMaintenanceStaffList] = useState<Staff[]>([]); // State for maintenance staff
-  const { staffId, staffRole } = useStaffUser();
+  const { staffId, staffRole, sessionToken } = useStaffUser();
 
   async function fetchMaintenanceSchedules() {
     try {
@@ -110,6 +110,7 @@ function MaintenanceScheduleHandlerPage() {
       const response = await invoke<ApiResponse<string>>(
         "save_maintenance_schedule_data",
         {
+          sessionToken,
           rideId: ride_id,
           staffId: staff_id,
           description,
@@ -144,6 +145,7 @@ function MaintenanceScheduleHandlerPage() {
       const response = await invoke<ApiResponse<string>>(
         "update_maintenance_schedule_data",
         {
+          sessionToken,
           maintenanceTaskId: maintenance_task_id,
           rideId: ride_id,
           staffId: staff_id,
@@ -170,6 +172,7 @@ function MaintenanceScheduleHandlerPage() {
       const response = await invoke<ApiResponse<string>>(
         "delete_maintenance_schedule_data",
         {
+          sessionToken,
           maintenanceTaskId: maintenance_task_id,
         }
       );
@@ -2,7 +2,7 @@
 
 import { useEffect, useState } from "react";
 import { Button } from "@/components/ui/button";
-import { ApiResponse, MenuItem } from "@/types";
+import { ApiResponse, MenuItem, DependencyReport } from "@/types";
 import {
   Table,
   TableBody,
@@ -24,6 +24,8 @@ import {
   AlertDialogTrigger,
 } from "@/components/ui/alert-dialog";
 import { invoke } from "@tauri-apps/api/core";
+import { DeleteBlockedDialog } from "@/components/delete-blocked-dialog";
+import { useStaffUser } from "@/context/staff-user-context";
 import { MenuItemForm } from "@/components/menu-item-form";
 import { Edit, Trash2 } from "lucide-react";
 
@@ -36,7 +38,13 @@ function MenuItemHandlerPage({
   restaurantId,
   restaurantName,
 }: MenuItemHandlerPageProps) {
+  const { staffId, sessionToken } = useStaffUser();
   const [menuItems, setMenuItems] = useState<MenuItem[]>([]);
+  const [blockedDelete, setBlockedDelete] = useState<{
+    id: string;
+    report: DependencyReport;
+    message: string;
+  } | null>(null);
   const [editingMenuItem, setEditingMenuItem] = useState<MenuItem | null>(null);
 
   async function fetchMenuItems() {
@@ -67,6 +75,7 @@ function MenuItemHandlerPage({
       const response = await invoke<ApiResponse<string>>(
         "save_menu_item_data",
         {
+          sessionToken,
           photo,
           name,
           price,
@@ -104,6 +113,7 @@ function MenuItemHandlerPage({
       const response = await invoke<ApiResponse<string>>(
         "update_menu_item_data",
         {
+          sessionToken,
           menuItemId: menu_item_id,
           photo: photo === "" ? null : photo, // Handle empty string to null for backend if needed
           name,
@@ -129,18 +139,30 @@ function MenuItemHandlerPage({
     }
   }
 
-  async function deleteMenuItem(menu_item_id: string) {
+  async function deleteMenuItem(menu_item_id: string, cascade = false) {
     try {
-      const response = await invoke<ApiResponse<string>>(
+      const response = await invoke<ApiResponse<DependencyReport>>(
         "delete_menu_item_data",
         {
+          sessionToken,
+          cascade,
           menuItemId: menu_item_id,
         }
       );
 
       if (response.status === "error") {
-        console.error("Error deleting menu item:", response.message);
+        if (response.code === "CONFLICT" && response.data) {
+          // Dependents still point at it: let staff review them and cascade
+          setBlockedDelete({
+            id: menu_item_id,
+            report: response.data,
+            message: response.message ?? "",
+          });
+        } else {
+          console.error("Error deleting menu item:", response.message);
+        }
       } else {
+        setBlockedDelete(null);
         setMenuItems((prevMenuItems) =>
           prevMenuItems.filter(
             (menuItem) => menuItem.menu_item_id !== menu_item_id
@@ -257,6 +279,16 @@ function MenuItemHandlerPage({
           </div>
         </div>
       </div>
+      <DeleteBlockedDialog
+        report={blockedDelete?.report ?? null}
+        message={blockedDelete?.message ?? ""}
+        onCancel={() => setBlockedDelete(null)}
+        onCascade={async () => {
+          if (blockedDelete) {
+            await deleteMenuItem(blockedDelete.id, true);
+          }
+        }}
+      />
     </div>
   );
 }
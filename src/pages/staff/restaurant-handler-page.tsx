@@ -2,7 +2,7 @@
 
 import { useEffect, useRef, useState } from "react";
 import { Button } from "@/components/ui/button";
-import { ApiResponse, Restaurant } from "@/types"; // Import Restaurant interface
+import { ApiResponse, Restaurant, DependencyReport } from "@/types"; // Import Restaurant interface
 import {
   Table,
   TableBody,
@@ -24,6 +24,7 @@ import {
   AlertDialogTrigger,
 } from "@/components/ui/alert-dialog";
 import { invoke } from "@tauri-apps/api/core";
+import { DeleteBlockedDialog } from "@/components/delete-blocked-dialog";
 import { RestaurantForm } from "@/components/restaurant-form"; // Import RestaurantForm
 import { Edit, Trash2, Menu, Utensils, Filter } from "lucide-react"; // Import Menu icon
 import MenuItemHandlerPage from "./menu-item-handler-page";
@@ -39,6 +40,11 @@ import { useStaffUser } from "@/context/staff-user-context";
 
 function RestaurantHandlerPage() {
   const [restaurants, setRestaurants] = useState<Restaurant[]>([]); // Use Restaurant interface array
+  const [blockedDelete, setBlockedDelete] = useState<{
+    id: string;
+    report: DependencyReport;
+    message: string;
+  } | null>(null);
   const [editingRestaurant, setEditingRestaurant] = useState<Restaurant | null>(
     null
   ); // Use Restaurant interface or null
@@ -46,7 +52,7 @@ function RestaurantHandlerPage() {
     useState<string | null>(null); // State to track restaurant for menu management
   const menuItemHandlerRef = useRef<HTMLDivElement>(null);
   const [statusFilter, setStatusFilter] = useState<string>("All");
-  const { staffRole } = useStaffUser();
+  const { staffId, staffRole, sessionToken } = useStaffUser();
 
   const canEdit = staffRole === "FBSupervisor";
 
@@ -86,6 +92,7 @@ function RestaurantHandlerPage() {
       const response = await invoke<ApiResponse<string>>(
         "save_restaurant_data",
         {
+          sessionToken,
           // Invoke save_restaurant_data
           name,
           photo,
@@ -134,6 +141,7 @@ function RestaurantHandlerPage() {
       const response = await invoke<ApiResponse<string>>(
         "update_restaurant_data",
         {
+          sessionToken,
           // Invoke update_restaurant_data
           restaurantId: restaurant_id, // Backend expects restaurantId
           name,
@@ -173,19 +181,31 @@ function RestaurantHandlerPage() {
   }
 
   // Delete a restaurant
-  async function deleteRestaurant(restaurant_id: string) {
+  async function deleteRestaurant(restaurant_id: string, cascade = false) {
     try {
-      const response = await invoke<ApiResponse<string>>(
+      const response = await invoke<ApiResponse<DependencyReport>>(
         "delete_restaurant_data",
         {
+          sessionToken,
+          cascade,
           // Invoke delete_restaurant_data
           restaurantId: restaurant_id, // Backend expects restaurantId
         }
       );
 
       if (response.status === "error") {
-        console.error("Error deleting restaurant:", response.message);
+        if (response.code === "CONFLICT" && response.data) {
+          // Dependents still point at it: let staff review them and cascade
+          setBlockedDelete({
+            id: restaurant_id,
+            report: response.data,
+            message: response.message ?? "",
+          });
+        } else {
+          console.error("Error deleting restaurant:", response.message);
+        }
       } else {
+        setBlockedDelete(null);
         setRestaurants((prevRestaurants) =>
           prevRestaurants.filter(
             (restaurant) => restaurant.restaurant_id !== restaurant_id
@@ -393,6 +413,16 @@ function RestaurantHandlerPage() {
           />
         </div>
       )}
+      <DeleteBlockedDialog
+        report={blockedDelete?.report ?? null}
+        message={blockedDelete?.message ?? ""}
+        onCancel={() => setBlockedDelete(null)}
+        onCascade={async () => {
+          if (blockedDelete) {
+            await deleteRestaurant(blockedDelete.id, true);
+          }
+        }}
+      />
     </div>
   );
 }
@@ -2,7 +2,7 @@
 
 import { useEffect, useRef, useState } from "react";
 import { Button } from "@/components/ui/button";
-import { ApiResponse, Ride, Staff } from "@/types"; // Import Ride interface
+import { ApiResponse, Ride, Staff, DependencyReport } from "@/types"; // Import Ride interface
 import {
   Table,
   TableBody,
@@ -24,6 +24,7 @@ import {
   AlertDialogTrigger,
 } from "@/components/ui/alert-dialog";
 import { invoke } from "@tauri-apps/api/core";
+import { DeleteBlockedDialog } from "@/components/delete-blocked-dialog";
 import { RideForm } from "@/components/ride-form"; // Import RideForm
 import { Edit, Trash2, Users } from "lucide-react"; // Import Users icon for queue
 import RideQueueHandlerPage from "./ride-queue-handler-page";
@@ -31,6 +32,11 @@ import { useStaffUser } from "@/context/staff-user-context";
 
 function RideHandlerPage() {
   const [rides, setRides] = useState<Ride[]>([]); // Use Ride interface array
+  const [blockedDelete, setBlockedDelete] = useState<{
+    id: string;
+    report: DependencyReport;
+    message: string;
+  } | null>(null);
   const [editingRide, setEditingRide] = useState<Ride | null>(null); // Use Ride interface or null
   const [managingQueueForRideId, setManagingQueueForRideId] = useState<
     string | null
@@ -39,7 +45,7 @@ function RideHandlerPage() {
   const [staffNames, setStaffNames] = useState<{ [staffId: string]: string }>(
     {}
   );
-  const { staffRole } = useStaffUser();
+  const { staffId, staffRole, sessionToken } = useStaffUser();
 
   const canEdit =
     staffRole === "RideManager" || staffRole === "CEO" || staffRole === "COO";
@@ -112,6 +118,7 @@ function RideHandlerPage() {
   ) {
     try {
       const response = await invoke<ApiResponse<string>>("save_ride_data", {
+        sessionToken,
         status,
         name,
         price,
@@ -153,6 +160,7 @@ function RideHandlerPage() {
   ) {
     try {
       const response = await invoke<ApiResponse<string>>("update_ride_data", {
+        sessionToken,
         rideId: ride_id, // Backend expects rideId
         status,
         name,
@@ -180,15 +188,31 @@ function RideHandlerPage() {
   }
 
   // Delete a ride
-  async function deleteRide(ride_id: string) {
+  async function deleteRide(ride_id: string, cascade = false) {
     try {
-      const response = await invoke<string>("delete_ride_data", {
+      const response = await invoke<ApiResponse<DependencyReport>>("delete_ride_data", {
+        sessionToken,
+        cascade,
         rideId: ride_id, // Backend expects rideId
       });
 
-      setRides((prevRides) =>
-        prevRides.filter((ride) => ride.ride_id !== ride_id)
-      );
+      if (response.status === "error") {
+        if (response.code === "CONFLICT" && response.data) {
+          // Dependents still point at it: let staff review them and cascade
+          setBlockedDelete({
+            id: ride_id,
+            report: response.data,
+            message: response.message ?? "",
+          });
+        } else {
+          console.error("Error deleting ride:", response.message);
+        }
+      } else {
+        setBlockedDelete(null);
+        setRides((prevRides) =>
+          prevRides.filter((ride) => ride.ride_id !== ride_id)
+        );
+      }
     } catch (error) {
       console.error("Unexpected error:", error);
     }
@@ -349,6 +373,16 @@ function RideHandlerPage() {
           />
         </div>
       )}
+      <DeleteBlockedDialog
+        report={blockedDelete?.report ?? null}
+        message={blockedDelete?.message ?? ""}
+        onCancel={() => setBlockedDelete(null)}
+        onCascade={async () => {
+          if (blockedDelete) {
+            await deleteRide(blockedDelete.id, true);
+          }
+        }}
+      />
     </div>
   );
 }
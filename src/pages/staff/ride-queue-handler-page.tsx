@@ -1,6 +1,6 @@
 "use client";
 
-import { useEffect, useState } from "react";
+import { useEffect, useRef, useState } from "react";
 import { Button } from "@/components/ui/button";
 import { ApiResponse, Customer, RideQueue } from "@/types";
 import {
@@ -24,6 +24,7 @@ import {
   AlertDialogTrigger,
 } from "@/components/ui/alert-dialog";
 import { invoke } from "@tauri-apps/api/core";
+import { useStaffUser } from "@/context/staff-user-context";
 import {
   Form,
   FormControl,
@@ -49,6 +50,7 @@ const formSchema = z.object({
 });
 
 function RideQueueHandlerPage({ rideId, rideName }: RideQueueHandlerPageProps) {
+  const { staffId, sessionToken } = useStaffUser();
   const [rideQueues, setRideQueues] = useState<RideQueue[]>([]);
   const [customerNames, setCustomerNames] = useState<{
     [customerId: string]: string;
@@ -143,6 +145,7 @@ function RideQueueHandlerPage({ rideId, rideName }: RideQueueHandlerPageProps) {
       const response = await invoke<ApiResponse<string>>(
         "update_queue_position",
         {
+          sessionToken,
           rideQueueId: ride_queue_id,
           queuePosition: newPosition.toString(),
         }
@@ -193,6 +196,7 @@ function RideQueueHandlerPage({ rideId, rideName }: RideQueueHandlerPageProps) {
       const response = await invoke<ApiResponse<string>>(
         "update_queue_position",
         {
+          sessionToken,
           rideQueueId: ride_queue_id,
           queuePosition: newPosition.toString(),
         }
@@ -222,9 +226,14 @@ function RideQueueHandlerPage({ rideId, rideName }: RideQueueHandlerPageProps) {
   // Delete a ride queue entry
   async function deleteRideQueue(ride_queue_id: string) {
     try {
-      const response = await invoke<string>("delete_ride_queue_data", {
+      const response = await invoke<ApiResponse<string>>("delete_ride_queue_data", {
+        sessionToken,
         rideQueueId: ride_queue_id,
       });
+      if (response.status === "error") {
+        console.error("Error deleting ride queue:", response.message);
+        return;
+      }
       setRideQueues((prev) =>
         prev.filter((q) => q.ride_queue_id !== ride_queue_id)
       );
@@ -241,6 +250,9 @@ function RideQueueHandlerPage({ rideId, rideName }: RideQueueHandlerPageProps) {
     },
   });
 
+  // Renewed after each added entry, so a double submit adds the customer once
+  const queueKey = useRef(crypto.randomUUID());
+
   async function onSubmit(values: z.infer<typeof formSchema>) {
     const lastPosition =
       rideQueues.length > 0
@@ -253,13 +265,16 @@ function RideQueueHandlerPage({ rideId, rideName }: RideQueueHandlerPageProps) {
       const response = await invoke<ApiResponse<string>>(
         "save_ride_queue_data",
         {
+          sessionToken,
           rideId,
           customerId: values.customer_id,
           queuePosition: lastPosition,
+          idempotencyKey: queueKey.current,
         }
       );
 
       if (response.status === "success" && response.data) {
+        queueKey.current = crypto.randomUUID();
         setRideQueues((prev) =>
           [
             ...prev,
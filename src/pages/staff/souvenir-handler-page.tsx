@@ -25,6 +25,7 @@ import {
   AlertDialogTrigger,
 } from "@/components/ui/alert-dialog";
 import { invoke } from "@tauri-apps/api/core";
+import { useStaffUser } from "@/context/staff-user-context";
 import { SouvenirForm } from "@/components/souvenir-form";
 import { Edit, Trash2 } from "lucide-react";
 
@@ -34,6 +35,7 @@ interface SouvenirHandlerPageProps {
 }
 
 function SouvenirHandlerPage({ storeId, storeName }: SouvenirHandlerPageProps) {
+  const { staffId, sessionToken } = useStaffUser();
   const [souvenirs, setSouvenirs] = useState<Souvenir[]>([]);
   const [editingSouvenir, setEditingSouvenir] = useState<Souvenir | null>(null);
 
@@ -60,6 +62,7 @@ function SouvenirHandlerPage({ storeId, storeName }: SouvenirHandlerPageProps) {
   ) {
     try {
       const response = await invoke<ApiResponse<string>>("save_souvenir_data", {
+        sessionToken,
         photo,
         name,
         price,
@@ -98,6 +101,7 @@ function SouvenirHandlerPage({ storeId, storeName }: SouvenirHandlerPageProps) {
       const response = await invoke<ApiResponse<string>>(
         "update_souvenir_data",
         {
+          sessionToken,
           souvenirId: souvenir_id,
           photo: photo === "" ? null : photo,
           name,
@@ -129,6 +133,7 @@ function SouvenirHandlerPage({ storeId, storeName }: SouvenirHandlerPageProps) {
       const response = await invoke<ApiResponse<string>>(
         "delete_souvenir_data",
         {
+          sessionToken,
           souvenirId: souvenir_id,
         }
       );
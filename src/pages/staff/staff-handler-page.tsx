@@ -2,7 +2,7 @@
 
 import { useEffect, useState } from "react";
 import { Button } from "@/components/ui/button";
-import { ApiResponse, Staff } from "@/types"; // Import Staff interface
+import { ApiResponse, Staff, DependencyReport } from "@/types"; // Import Staff interface
 import {
   Table,
   TableBody,
@@ -24,12 +24,28 @@ import {
   AlertDialogTrigger,
 } from "@/components/ui/alert-dialog";
 import { invoke } from "@tauri-apps/api/core";
+import { DeleteBlockedDialog } from "@/components/delete-blocked-dialog";
+import { useStaffUser } from "@/context/staff-user-context";
 import { StaffForm } from "@/components/staff-form"; // Import StaffForm
-import { Edit, Trash2 } from "lucide-react";
+import { Edit, KeyRound, Trash2 } from "lucide-react";
 
 function StaffHandlerPage() {
+  const { staffId, staffRole, sessionToken } = useStaffUser();
   const [staffs, setStaffs] = useState<Staff[]>([]); // Use Staff interface array
+  const [blockedDelete, setBlockedDelete] = useState<{
+    id: string;
+    report: DependencyReport;
+    message: string;
+  } | null>(null);
   const [editingStaff, setEditingStaff] = useState<Staff | null>(null); // Use Staff interface or null
+  // One-time reset code, shown once so the executive can pass it on
+  const [issuedReset, setIssuedReset] = useState<{
+    name: string;
+    token: string;
+    message: string;
+  } | null>(null);
+  const isExecutive =
+    staffRole === "CEO" || staffRole === "CFO" || staffRole === "COO";
 
   // Fetch all staffs from the backend
   async function fetchStaffs() {
@@ -56,6 +72,7 @@ function StaffHandlerPage() {
   ) {
     try {
       const response = await invoke<ApiResponse<string>>("save_staff_data", {
+        sessionToken,
         // Invoke save_staff_data
         email,
         password,
@@ -85,6 +102,7 @@ function StaffHandlerPage() {
   ) {
     try {
       const response = await invoke<ApiResponse<string>>("update_staff_data", {
+        sessionToken,
         // Invoke update_staff_data
         staffId: staff_id, // Use staffId as backend expects
         email,
@@ -109,17 +127,54 @@ function StaffHandlerPage() {
     }
   }
 
+  // Issue a one-time password reset code for a staff member
+  async function resetStaffPassword(staff: Staff) {
+    try {
+      const response = await invoke<ApiResponse<string>>(
+        "reset_staff_password",
+        {
+          sessionToken,
+          staffId: staff.staff_id,
+        }
+      );
+
+      if (response.status === "error" || !response.data) {
+        console.error("Error resetting password:", response.message);
+      } else {
+        setIssuedReset({
+          name: staff.name,
+          token: response.data,
+          message: response.message ?? "",
+        });
+      }
+    } catch (error) {
+      console.error("Unexpected error:", error);
+    }
+  }
+
   // Delete a staff
-  async function deleteStaff(staff_id: string) {
+  async function deleteStaff(staff_id: string, cascade = false) {
     try {
-      const response = await invoke<ApiResponse<string>>("delete_staff_data", {
+      const response = await invoke<ApiResponse<DependencyReport>>("delete_staff_data", {
+        sessionToken,
+        cascade,
         // Invoke delete_staff_data
         staffId: staff_id, // Use staffId as backend expects
       });
 
       if (response.status === "error") {
-        console.error("Error deleting staff:", response.message);
+        if (response.code === "CONFLICT" && response.data) {
+          // Dependents still point at it: let staff review them and cascade
+          setBlockedDelete({
+            id: staff_id,
+            report: response.data,
+            message: response.message ?? "",
+          });
+        } else {
+          console.error("Error deleting staff:", response.message);
+        }
       } else {
+        setBlockedDelete(null);
         setStaffs((prevStaffs) =>
           prevStaffs.filter((staff) => staff.staff_id !== staff_id)
         );
@@ -192,6 +247,17 @@ function StaffHandlerPage() {
                             <Edit className="h-4 w-4" />
                             <span className="sr-only">Edit</span>
                           </Button>
+                          {isExecutive && (
+                            <Button
+                              variant="ghost"
+                              size="icon"
+                              onClick={() => resetStaffPassword(staff)}
+                              className="h-8 w-8"
+                            >
+                              <KeyRound className="h-4 w-4" />
+                              <span className="sr-only">Reset password</span>
+                            </Button>
+                          )}
 
                           <AlertDialog>
                             <AlertDialogTrigger asChild>
@@ -235,6 +301,40 @@ function StaffHandlerPage() {
           </div>
         </div>
       </div>
+      <AlertDialog
+        open={issuedReset !== null}
+        onOpenChange={(isOpen) => !isOpen && setIssuedReset(null)}
+      >
+        <AlertDialogContent>
+          <AlertDialogHeader>
+            <AlertDialogTitle>
+              Reset code for {issuedReset?.name}
+            </AlertDialogTitle>
+            <AlertDialogDescription>
+              Give this code to the staff member. They can use it from the
+              login screen to choose a new password. {issuedReset?.message}
+            </AlertDialogDescription>
+          </AlertDialogHeader>
+          <p className="rounded-md bg-muted p-3 text-center font-mono text-lg">
+            {issuedReset?.token}
+          </p>
+          <AlertDialogFooter>
+            <AlertDialogAction onClick={() => setIssuedReset(null)}>
+              Done
+            </AlertDialogAction>
+          </AlertDialogFooter>
+        </AlertDialogContent>
+      </AlertDialog>
+      <DeleteBlockedDialog
+        report={blockedDelete?.report ?? null}
+        message={blockedDelete?.message ?? ""}
+        onCancel={() => setBlockedDelete(null)}
+        onCascade={async () => {
+          if (blockedDelete) {
+            await deleteStaff(blockedDelete.id, true);
+          }
+        }}
+      />
     </div>
   );
 }
@@ -2,7 +2,7 @@
 
 import { useEffect, useRef, useState } from "react";
 import { Button } from "@/components/ui/button";
-import { ApiResponse, Store } from "@/types";
+import { ApiResponse, Store, DependencyReport } from "@/types";
 import {
   Table,
   TableBody,
@@ -24,6 +24,7 @@ import {
   AlertDialogTrigger,
 } from "@/components/ui/alert-dialog";
 import { invoke } from "@tauri-apps/api/core";
+import { DeleteBlockedDialog } from "@/components/delete-blocked-dialog";
 import { StoreForm } from "@/components/store-form";
 import { Edit, Trash2, ShoppingBag } from "lucide-react";
 import SouvenirHandlerPage from "./souvenir-handler-page";
@@ -31,11 +32,16 @@ import { useStaffUser } from "@/context/staff-user-context";
 
 function StoreHandlerPage() {
   const [stores, setStores] = useState<Store[]>([]);
+  const [blockedDelete, setBlockedDelete] = useState<{
+    id: string;
+    report: DependencyReport;
+    message: string;
+  } | null>(null);
   const [editingStore, setEditingStore] = useState<Store | null>(null);
   const [managingSouvenirsForStoreId, setManagingSouvenirsForStoreId] =
     useState<string | null>(null);
   const souvenirHandlerRef = useRef<HTMLDivElement>(null);
-  const { staffRole } = useStaffUser();
+  const { staffId, staffRole, sessionToken } = useStaffUser();
 
   const canEdit = staffRole === "RetailManager" || staffRole === "CEO";
 
@@ -68,6 +74,7 @@ function StoreHandlerPage() {
   ) {
     try {
       const response = await invoke<ApiResponse<string>>("save_store_data", {
+        sessionToken,
         name,
         photo,
         openingTime: opening_time,
@@ -108,6 +115,7 @@ function StoreHandlerPage() {
   ) {
     try {
       const response = await invoke<ApiResponse<string>>("update_store_data", {
+        sessionToken,
         storeId: store_id,
         name,
         photo: photo,
@@ -142,15 +150,27 @@ function StoreHandlerPage() {
     }
   }
 
-  async function deleteStore(store_id: string) {
+  async function deleteStore(store_id: string, cascade = false) {
     try {
-      const response = await invoke<ApiResponse<string>>("delete_store_data", {
+      const response = await invoke<ApiResponse<DependencyReport>>("delete_store_data", {
+        sessionToken,
+        cascade,
         storeId: store_id,
       });
 
       if (response.status === "error") {
-        console.error("Error deleting store:", response.message);
+        if (response.code === "CONFLICT" && response.data) {
+          // Dependents still point at it: let staff review them and cascade
+          setBlockedDelete({
+            id: store_id,
+            report: response.data,
+            message: response.message ?? "",
+          });
+        } else {
+          console.error("Error deleting store:", response.message);
+        }
       } else {
+        setBlockedDelete(null);
         setStores((prevStores) =>
           prevStores.filter((store) => store.store_id !== store_id)
         );
@@ -308,6 +328,16 @@ function StoreHandlerPage() {
           />
         </div>
       )}
+      <DeleteBlockedDialog
+        report={blockedDelete?.report ?? null}
+        message={blockedDelete?.message ?? ""}
+        onCancel={() => setBlockedDelete(null)}
+        onCascade={async () => {
+          if (blockedDelete) {
+            await deleteStore(blockedDelete.id, true);
+          }
+        }}
+      />
     </div>
   );
 }
@@ -1,7 +1,7 @@
 // src/pages/StoreUI.tsx
 "use client";
 
-import { useCallback, useEffect, useState } from "react";
+import { useCallback, useEffect, useRef, useState } from "react";
 import { useParams } from "react-router";
 import { Button } from "@/components/ui/button";
 import {
@@ -51,7 +51,7 @@ import { LoadingScreen } from "@/components/loading-screen";
 
 function StoreUIComponent() {
   const { storeId } = useParams<{ storeId: string }>();
-  const { isLoggedIn, uid, setVirtualBalance, virtualBalance } = useUser();
+  const { isLoggedIn, uid, refreshBalance, virtualBalance, sessionToken } = useUser();
   const [store, setStore] = useState<Store | null>(null);
   const [souvenirs, setSouvenirs] = useState<Souvenir[]>([]); // Renamed from menuItems
   const [loading, setLoading] = useState(true);
@@ -62,6 +62,8 @@ function StoreUIComponent() {
   ); // Renamed from selectedMenuItem
   const [orderQuantity, setOrderQuantity] = useState(1);
   const [orderLoading, setOrderLoading] = useState(false);
+  // Sent with the order so a retry or double click does not order twice
+  const orderKey = useRef<string | null>(null);
   const [customerOrders, setCustomerOrders] = useState<OrderSouvenir[]>([]); // Updated type
 
   const fetchStoreData = useCallback(async () => {
@@ -150,6 +152,7 @@ function StoreUIComponent() {
 
     setSelectedSouvenir(souvenir); // Renamed setSelectedMenuItem to setSelectedSouvenir
     setOrderQuantity(1);
+    orderKey.current = crypto.randomUUID(); // New order, new key
   };
 
   const confirmOrder = async () => {
@@ -168,32 +171,29 @@ function StoreUIComponent() {
       const response = await invoke<ApiResponse<string>>(
         "save_order_souvenir_data",
         {
+          sessionToken,
           // Updated command name
           customerId: uid,
           storeId: storeId, // Use storeId
           souvenirId: selectedSouvenir.souvenir_id, // Renamed menuItemId to souvenirId
           quantity: orderQuantity,
+          idempotencyKey: orderKey.current,
         }
       );
 
-      if (response.status === "success") {
-        const newBalance = (currentBalance - orderPrice).toString();
-        const balanceResponse = await invoke<string>("update_customer_data", {
-          customerId: uid,
-          name: null,
-          virtualBalance: newBalance,
-        });
-        await invoke<ApiResponse<string>>("update_souvenir_stock", {
-          // Decrease souvenir stock
-          souvenirId: selectedSouvenir.souvenir_id,
-          stock: selectedSouvenir.stock - orderQuantity,
-        });
-
-        setVirtualBalance(newBalance);
+      if (response.status === "queued") {
+        // Offline: the kiosk charges the balance and takes the stock when it syncs
+        toast.info(response.message || "Order saved offline and will be sent once the connection is back.");
+        setSelectedSouvenir(null);
+      } else if (response.status === "success") {
+        // The order, the stock and the charge were all written by the backend
+        const newBalance = await refreshBalance();
         toast.success(
-          `Order placed successfully! New balance: ${formatRupiah(
-            parseFloat(newBalance)
-          )}`
+          newBalance
+            ? `Order placed successfully! New balance: ${formatRupiah(
+                parseFloat(newBalance)
+              )}`
+            : "Order placed successfully!"
         );
         setSelectedSouvenir(null); // Renamed setSelectedMenuItem to setSelectedSouvenir
         fetchCustomerOrders();
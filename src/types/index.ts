@@ -1,8 +1,27 @@
-// ✅ Reusable API Response Interface
+// Stable error codes sent by the backend; branch on these rather than on messages
+export type ErrorCode =
+  | "NOT_FOUND"
+  | "VALIDATION_FAILED"
+  | "CONFLICT"
+  | "UNAUTHORIZED"
+  | "FORBIDDEN"
+  | "RATE_LIMITED"
+  | "DATABASE_ERROR"
+  | "INTERNAL_ERROR";
+
+export interface FieldError {
+  field: string;
+  message: string;
+}
+
+// ✅ Reusable API Response Interface, returned by every command
 export interface ApiResponse<T> {
-  status: "success" | "error";
+  status: "success" | "error" | "queued"; // "queued": saved on the kiosk while offline
   data: T | null;
   message: string | null;
+  code?: ErrorCode; // Only on errors
+  fields?: FieldError[]; // Only on errors; per-field validation messages
+  idempotency_key?: string; // Only when queued
 }
 
 // ✅ Reusable Post Interface
@@ -16,6 +35,7 @@ export interface Customer {
   customer_id: string; // ID should match the customer schema
   name: string;
   virtual_balance: string;
+  deleted_at?: string | null; // Set when archived
 }
 
 export interface Staff {
@@ -23,6 +43,23 @@ export interface Staff {
   email: string;
   name: string;
   role: string;
+  deleted_at?: string | null; // Set when archived
+  must_change_password?: boolean; // Set for accounts created by an executive until the first password change
+  password_changed_at?: string | null;
+}
+
+export interface StaffLoginResult {
+  session_token: string;
+  staff_id: string;
+  must_change_password: boolean; // A new password must be chosen before continuing
+}
+
+export interface PasswordPolicy {
+  min_length: number;
+  require_uppercase: boolean;
+  require_lowercase: boolean;
+  require_digit: boolean;
+  require_symbol: boolean;
 }
 
 export interface Restaurant {
@@ -34,6 +71,7 @@ export interface Restaurant {
   cuisine_type: string;
   location: string | undefined; // Or string | undefined
   status: string;
+  deleted_at?: string | null; // Set when archived
 }
 
 export interface MenuItem {
@@ -42,6 +80,7 @@ export interface MenuItem {
   name: string;
   price: string;
   restaurant_id: string;
+  deleted_at?: string | null; // Set when archived
 }
 
 export interface Ride {
@@ -52,6 +91,7 @@ export interface Ride {
   location: string;
   staff_id: string;
   photo: string | undefined;
+  deleted_at?: string | null; // Set when archived
 }
 
 export interface RideQueue {
@@ -81,6 +121,7 @@ export interface Store {
   closing_time: string; // ISO 8601 time string
   location: string | undefined;
   status: string;
+  deleted_at?: string | null; // Set when archived
 }
 
 export interface Souvenir {
@@ -113,6 +154,49 @@ export interface LostAndFoundItemsLog {
   found_location: string | undefined;
   timestamp: string;
   status: string;
+  matched_log_id: string | null; // Counterpart Missing/Found record once a match is confirmed
+  owner_customer_id: string | null;
+  owner_verification_method: string | null;
+  owner_verification_reference: string | null;
+  returned_by: string | null;
+  returned_at: string | null;
+  found_at: string | null;
+  retention_flagged_at: string | null; // Set once a Found item is unclaimed past the retention period
+  disposed_at: string | null;
+  reporter_customer_id: string | null; // Set when a customer filed the Missing report themselves
+}
+
+export interface OwnerVerification {
+  owner: string;
+  customer_id: string | null;
+  method: string; // "CustomerAccount", "IdDocument" or "ItemDescription"
+  reference: string | null;
+}
+
+export interface LostAndFoundLogEvent {
+  event_id: string;
+  log_id: string;
+  action: string;
+  from_status: string | null;
+  to_status: string | null;
+  actor_id: string | null;
+  details: string | null;
+  created_at: string;
+}
+
+export interface LostAndFoundMatchScore {
+  total: number; // 0..1, weighted sum of the signals below
+  type_score: number;
+  name_score: number;
+  color_score: number;
+  location_score: number;
+  time_score: number;
+}
+
+export interface LostAndFoundMatch {
+  missing: LostAndFoundItemsLog;
+  found: LostAndFoundItemsLog;
+  score: LostAndFoundMatchScore;
 }
 
 export interface Chat {
@@ -197,5 +281,44 @@ export interface BroadcastMessage {
   target_audience: string; // "Customer" or "Staff"
   content: string;
   timestamp: string; // ISO 8601 DateTime string from backend
-  status: string; // "Pending" or "Sent"
+  status: string; // "Pending", "Sent" or "Expired"
+  send_at: string | null; // Scheduled send time, null for drafts
+  sent_at: string | null; // Actual send time
+  expires_at: string | null;
+  target_type: string; // "Audience", "Role", "Division", "RideQueue", "RestaurantOrdersToday" or "Venue"
+  target_value: string | null;
+  is_read?: boolean; // Only known when fetched for a specific recipient
+}
+
+export interface RecipientBroadcast {
+  broadcast: BroadcastMessage;
+  delivered_at: string | null;
+  read_at: string | null;
+}
+
+export interface Dependent {
+  kind: string; // e.g. "open orders", "menu items"
+  count: number;
+  cascade_action: string | null; // What a cascading delete does to them; null means they must be resolved by hand
+}
+
+export interface DependencyReport {
+  entity_type: string;
+  entity_id: string;
+  dependents: Dependent[];
+}
+
+export interface ComponentHealth {
+  status: string; // "up", "degraded" or "down"
+  backend: string; // e.g. "postgres", "redis"
+  latency_ms: number | null;
+  detail: string | null;
+}
+
+export interface HealthReport {
+  status: string; // Worst of the components
+  database: ComponentHealth;
+  cache: ComponentHealth;
+  startup_error: string | null; // Set when the app could not start at all
+  checked_at: string;
 }